@@ -1,7 +1,10 @@
+use chrono::{DateTime, Utc};
 use claw_core::{Tool, ToolCall};
 use tracing::{info, warn};
 
 use crate::level::AutonomyLevel;
+use crate::policy::PolicyGuardrail;
+use crate::shell::{self, ShellCommandGuardrail};
 
 /// A guardrail rule that can approve, deny, or escalate a tool call.
 #[derive(Debug, Clone)]
@@ -14,15 +17,75 @@ pub enum GuardrailVerdict {
     Escalate(String),
 }
 
+/// Where a tool call comes from: the autonomy level it runs at, who asked
+/// for it and when.
+#[derive(Debug, Clone)]
+pub struct GuardrailContext {
+    pub level: AutonomyLevel,
+    /// Channel the conversation came in on, e.g. `telegram`.
+    pub channel: Option<String>,
+    /// Sender id on that channel.
+    pub sender: Option<String>,
+    pub now: DateTime<Utc>,
+}
+
+impl GuardrailContext {
+    /// A call made now, on nobody's behalf.
+    pub fn new(level: AutonomyLevel) -> Self {
+        Self {
+            level,
+            channel: None,
+            sender: None,
+            now: Utc::now(),
+        }
+    }
+
+    /// The same call, asked for by `sender` on `channel`.
+    pub fn from_sender(mut self, channel: &str, sender: &str) -> Self {
+        self.channel = Some(channel.to_string());
+        self.sender = Some(sender.to_string());
+        self
+    }
+}
+
 /// A single guardrail rule.
 pub trait Guardrail: Send + Sync {
     fn name(&self) -> &str;
     fn evaluate(&self, tool: &Tool, call: &ToolCall, level: AutonomyLevel) -> GuardrailVerdict;
+
+    /// Evaluate with the full context of the call. `None` means the rule has
+    /// no objection; `Some(Approve)` is an explicit approval that ends
+    /// evaluation. Rules that only look at the autonomy level needn't
+    /// implement this.
+    fn decide(
+        &self,
+        tool: &Tool,
+        call: &ToolCall,
+        ctx: &GuardrailContext,
+    ) -> Option<GuardrailVerdict> {
+        match self.evaluate(tool, call, ctx.level) {
+            GuardrailVerdict::Approve => None,
+            verdict => Some(verdict),
+        }
+    }
+}
+
+/// A verdict and what reached it: `denylist`, `allowlist` or the name of
+/// the guardrail. `None` if nothing objected.
+#[derive(Debug, Clone)]
+pub struct GuardrailDecision {
+    pub verdict: GuardrailVerdict,
+    pub decided_by: Option<String>,
 }
 
 /// The guardrail engine applies all registered rules to a tool call.
+///
+/// The denylist is checked first, then blocked shell commands, then the
+/// policy file (if any), then the allowlist, then the built-in rules. The
+/// first verdict other than "no objection" wins.
 pub struct GuardrailEngine {
     rules: Vec<Box<dyn Guardrail>>,
+    policy: Option<PolicyGuardrail>,
     allowlist: Vec<String>,
     denylist: Vec<String>,
 }
@@ -37,13 +100,15 @@ impl GuardrailEngine {
     pub fn new() -> Self {
         let mut engine = Self {
             rules: Vec::new(),
+            policy: None,
             allowlist: Vec::new(),
             denylist: Vec::new(),
         };
-        // Register built-in guardrails
+        // Register built-in guardrails. Shell analysis goes first so a
+        // blocked command is denied rather than merely escalated.
+        engine.add_rule(Box::new(ShellCommandGuardrail));
         engine.add_rule(Box::new(RiskLevelGuardrail));
         engine.add_rule(Box::new(DestructiveActionGuardrail { max_deletes: 5 }));
-        engine.add_rule(Box::new(NetworkExfiltrationGuardrail));
         engine
     }
 
@@ -59,43 +124,101 @@ impl GuardrailEngine {
         self.denylist = list;
     }
 
+    /// Apply a policy file's rules ahead of the allowlist and built-in rules.
+    pub fn set_policy(&mut self, policy: PolicyGuardrail) {
+        self.policy = Some(policy);
+    }
+
+    pub fn policy(&self) -> Option<&PolicyGuardrail> {
+        self.policy.as_ref()
+    }
+
     /// Evaluate a tool call against all guardrails.
     pub fn evaluate(&self, tool: &Tool, call: &ToolCall, level: AutonomyLevel) -> GuardrailVerdict {
+        self.evaluate_in(tool, call, &GuardrailContext::new(level))
+    }
+
+    /// Evaluate a tool call made in a given context.
+    pub fn evaluate_in(
+        &self,
+        tool: &Tool,
+        call: &ToolCall,
+        ctx: &GuardrailContext,
+    ) -> GuardrailVerdict {
+        self.decide(tool, call, ctx).verdict
+    }
+
+    /// Evaluate a tool call and report which guardrail decided.
+    pub fn decide(
+        &self,
+        tool: &Tool,
+        call: &ToolCall,
+        ctx: &GuardrailContext,
+    ) -> GuardrailDecision {
         // Check denylist first
         if self.denylist.iter().any(|d| d == &tool.name) {
             warn!(tool = %tool.name, "tool is on denylist");
-            return GuardrailVerdict::Deny(format!("tool '{}' is on the denylist", tool.name));
+            return GuardrailDecision {
+                verdict: GuardrailVerdict::Deny(format!("tool '{}' is on the denylist", tool.name)),
+                decided_by: Some("denylist".into()),
+            };
+        }
+
+        // A blocked shell command never runs — no policy or allowlist
+        // entry can approve it
+        if let Some(reason) = shell::blocked(tool, call) {
+            let verdict = GuardrailVerdict::Deny(reason);
+            log_verdict(ShellCommandGuardrail.name(), &tool.name, &verdict);
+            return GuardrailDecision {
+                verdict,
+                decided_by: Some(ShellCommandGuardrail.name().to_string()),
+            };
+        }
+
+        // The security policy outranks the allowlist
+        if let Some(policy) = &self.policy
+            && let Some(verdict) = policy.decide(tool, call, ctx)
+        {
+            log_verdict(policy.name(), &tool.name, &verdict);
+            return GuardrailDecision {
+                verdict,
+                decided_by: Some(policy.name().to_string()),
+            };
         }
 
         // Check allowlist — always approve
         if self.allowlist.iter().any(|a| a == &tool.name) {
-            return GuardrailVerdict::Approve;
+            return GuardrailDecision {
+                verdict: GuardrailVerdict::Approve,
+                decided_by: Some("allowlist".into()),
+            };
         }
 
         // Run all guardrail rules
         for rule in &self.rules {
-            match rule.evaluate(tool, call, level) {
-                GuardrailVerdict::Approve => continue,
-                verdict @ GuardrailVerdict::Deny(_) => {
-                    info!(
-                        rule = rule.name(),
-                        tool = %tool.name,
-                        "guardrail denied action"
-                    );
-                    return verdict;
-                }
-                verdict @ GuardrailVerdict::Escalate(_) => {
-                    info!(
-                        rule = rule.name(),
-                        tool = %tool.name,
-                        "guardrail escalated action for approval"
-                    );
-                    return verdict;
-                }
+            if let Some(verdict) = rule.decide(tool, call, ctx) {
+                log_verdict(rule.name(), &tool.name, &verdict);
+                return GuardrailDecision {
+                    verdict,
+                    decided_by: Some(rule.name().to_string()),
+                };
             }
         }
 
-        GuardrailVerdict::Approve
+        GuardrailDecision {
+            verdict: GuardrailVerdict::Approve,
+            decided_by: None,
+        }
+    }
+}
+
+fn log_verdict(rule: &str, tool: &str, verdict: &GuardrailVerdict) {
+    match verdict {
+        GuardrailVerdict::Approve => {}
+        GuardrailVerdict::Deny(_) => info!(rule, tool, "guardrail denied action"),
+        GuardrailVerdict::Escalate(_) => {
+            info!(rule, tool, "guardrail escalated action for approval")
+        }
     }
 }
 
@@ -155,28 +278,3 @@ impl Guardrail for DestructiveActionGuardrail {
         GuardrailVerdict::Approve
     }
 }
-
-/// Detects potential data exfiltration via network requests.
-struct NetworkExfiltrationGuardrail;
-
-impl Guardrail for NetworkExfiltrationGuardrail {
-    fn name(&self) -> &str {
-        "network_exfiltration"
-    }
-
-    fn evaluate(&self, tool: &Tool, call: &ToolCall, _level: AutonomyLevel) -> GuardrailVerdict {
-        // If a shell command is piping file content to curl/wget, that's suspicious
-        if (tool.name == "shell_exec" || tool.name == "system_run")
-            && let Some(cmd) = call.arguments.get("command").and_then(|v| v.as_str())
-        {
-            let suspicious = cmd.contains("curl") && (cmd.contains("cat ") || cmd.contains("< /"))
-                || cmd.contains("wget") && cmd.contains("--post-file");
-            if suspicious {
-                return GuardrailVerdict::Escalate(
-                    "command may be exfiltrating data via network".into(),
-                );
-            }
-        }
-        GuardrailVerdict::Approve
-    }
-}
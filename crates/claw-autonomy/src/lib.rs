@@ -2,16 +2,25 @@
 //!
 //! The autonomy and guardrail system. Implements five autonomy levels (L0-L4),
 //! budget tracking, risk assessment, human-in-the-loop approval flows,
-//! and a goal planning engine.
+//! declarative guardrail policies, shell command analysis, filesystem
+//! scoping for path-taking tools, and a goal planning engine.
 
 pub mod approval;
 pub mod budget;
 pub mod guardrail;
 pub mod level;
 pub mod planner;
+pub mod policy;
+pub mod scope;
+pub mod shell;
 
 pub use approval::{ApprovalGate, ApprovalRequest, ApprovalResponse};
 pub use budget::BudgetTracker;
-pub use guardrail::{Guardrail, GuardrailEngine, GuardrailVerdict};
+pub use guardrail::{
+    Guardrail, GuardrailContext, GuardrailDecision, GuardrailEngine, GuardrailVerdict,
+};
 pub use level::AutonomyLevel;
 pub use planner::{Goal, GoalPlanner, GoalStatus, Step, StepStatus};
+pub use policy::{Policy, PolicyAction, PolicyGuardrail, PolicyRule, RuleTrace};
+pub use scope::{PathAccess, PathScope};
+pub use shell::{ShellAnalysis, ShellCommand, ShellCommandGuardrail, ShellFinding, ShellRisk};
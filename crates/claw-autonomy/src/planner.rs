@@ -101,7 +101,7 @@ impl GoalPlanner {
             retrospective: None,
         };
         self.goals.push(goal);
-        self.goals.sort_by(|a, b| b.priority.cmp(&a.priority));
+        self.goals.sort_by_key(|g| std::cmp::Reverse(g.priority));
         self.goals.last().unwrap()
     }
 
@@ -413,7 +413,7 @@ impl GoalPlanner {
         };
 
         self.goals.push(goal);
-        self.goals.sort_by(|a, b| b.priority.cmp(&a.priority));
+        self.goals.sort_by_key(|g| std::cmp::Reverse(g.priority));
     }
 
     /// Get the highest-priority active goal.
@@ -0,0 +1,764 @@
+//! Guardrail policies — rules kept in a TOML file, so the people who decide
+//! what the agent may do can change it without a rebuild.
+//!
+//! ```toml
+//! [[rule]]
+//! name = "restart-after-hours"
+//! tool = ["shell_exec", "system_run"]
+//! action = "escalate"
+//! reason = "restarting services outside business hours needs approval"
+//!
+//! [[rule.args]]
+//! path = "command"
+//! regex = '\bsystemctl\s+restart\b'
+//!
+//! [rule.time]
+//! days = ["mon", "tue", "wed", "thu", "fri"]
+//! hours = "09:00-18:00"
+//! outside = true
+//! ```
+//!
+//! Rules are checked top to bottom; the first whose conditions all hold
+//! decides, with `action` = `approve`, `deny` or `escalate`. A rule may
+//! require:
+//!
+//! - `tool` — the tool name matches one of these globs,
+//! - `args` — argument conditions, each naming a JSON `path` into the
+//!   arguments (`command`, `options.force`, `paths[*]`) and testing it with
+//!   `equals`, `regex`, `glob` (a path glob: `*` stays within a directory,
+//!   `**` crosses them) or `exists`. A path that reaches several values holds
+//!   if any of them passes,
+//! - `channel` / `sender` — where the request came from, as globs,
+//! - `min_level` / `max_level` — the autonomy level, 0–4,
+//! - `time` — `days` and an `hours` range in local time (or `utc_offset`),
+//!   or with `outside = true`, any time but that.
+//!
+//! The policy outranks the tool allowlist and the built-in guardrails; only
+//! the denylist and blocked shell commands (risk 10) come before it.
+//! [`PolicyGuardrail`] reloads the file when it changes, keeping the previous
+//! rules if the new file doesn't parse.
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Datelike, FixedOffset, Local, Timelike, Utc, Weekday};
+use claw_core::{ClawError, Result, Tool, ToolCall};
+use parking_lot::RwLock;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::guardrail::{Guardrail, GuardrailContext, GuardrailVerdict};
+use crate::level::AutonomyLevel;
+
+/// What a matching rule does with the call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyAction {
+    Approve,
+    Deny,
+    Escalate,
+}
+
+impl std::fmt::Display for PolicyAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PolicyAction::Approve => "approve",
+            PolicyAction::Deny => "deny",
+            PolicyAction::Escalate => "escalate",
+        })
+    }
+}
+
+// ── File format ────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    #[serde(default)]
+    rule: Vec<RuleSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    name: String,
+    #[serde(default, deserialize_with = "one_or_many")]
+    tool: Vec<String>,
+    #[serde(default)]
+    args: Vec<ArgSpec>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    channel: Vec<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    sender: Vec<String>,
+    min_level: Option<u8>,
+    max_level: Option<u8>,
+    time: Option<TimeSpec>,
+    action: PolicyAction,
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ArgSpec {
+    path: String,
+    equals: Option<Value>,
+    regex: Option<String>,
+    glob: Option<String>,
+    exists: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TimeSpec {
+    #[serde(default)]
+    days: Vec<String>,
+    hours: Option<String>,
+    #[serde(default)]
+    outside: bool,
+    utc_offset: Option<String>,
+}
+
+/// A string or a list of strings.
+fn one_or_many<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(d)? {
+        OneOrMany::One(s) => vec![s],
+        OneOrMany::Many(v) => v,
+    })
+}
+
+// ── Compiled rules ─────────────────────────────────────────────
+
+/// A set of parsed, checked rules.
+#[derive(Debug, Default)]
+pub struct Policy {
+    rules: Vec<PolicyRule>,
+}
+
+/// One rule of a policy.
+#[derive(Debug)]
+pub struct PolicyRule {
+    pub name: String,
+    pub action: PolicyAction,
+    reason: Option<String>,
+    tools: Vec<Glob>,
+    args: Vec<ArgCondition>,
+    channels: Vec<Glob>,
+    senders: Vec<Glob>,
+    min_level: Option<u8>,
+    max_level: Option<u8>,
+    time: Option<TimeWindow>,
+}
+
+/// How one rule fared against a call, for explaining a verdict.
+#[derive(Debug, Clone)]
+pub struct RuleTrace {
+    pub rule: String,
+    pub action: PolicyAction,
+    /// The first condition that didn't hold; `None` if the rule matched.
+    pub mismatch: Option<String>,
+}
+
+impl Policy {
+    /// Parse a policy from TOML, rejecting unknown keys, bad patterns and
+    /// duplicate rule names.
+    pub fn parse(raw: &str) -> Result<Self> {
+        Self::compile(raw).map_err(ClawError::Config)
+    }
+
+    /// Read and parse a policy file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path).map_err(|e| {
+            ClawError::Config(format!("failed to read policy {}: {e}", path.display()))
+        })?;
+        Self::compile(&raw)
+            .map_err(|e| ClawError::Config(format!("failed to parse {}: {e}", path.display())))
+    }
+
+    fn compile(raw: &str) -> std::result::Result<Self, String> {
+        let file: PolicyFile = toml::from_str(raw).map_err(|e| e.to_string())?;
+        let mut rules: Vec<PolicyRule> = Vec::with_capacity(file.rule.len());
+        for spec in file.rule {
+            if rules.iter().any(|r| r.name == spec.name) {
+                return Err(format!("duplicate policy rule name '{}'", spec.name));
+            }
+            let name = spec.name.clone();
+            rules
+                .push(PolicyRule::compile(spec).map_err(|e| format!("policy rule '{name}': {e}"))?);
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn rules(&self) -> &[PolicyRule] {
+        &self.rules
+    }
+
+    /// The first rule matching a call, if any.
+    pub fn matching(
+        &self,
+        tool: &Tool,
+        call: &ToolCall,
+        ctx: &GuardrailContext,
+    ) -> Option<&PolicyRule> {
+        self.rules.iter().find(|r| r.check(tool, call, ctx).is_ok())
+    }
+
+    /// Every rule up to and including the one that matches, with why each
+    /// earlier rule didn't.
+    pub fn explain(&self, tool: &Tool, call: &ToolCall, ctx: &GuardrailContext) -> Vec<RuleTrace> {
+        let mut traces = Vec::new();
+        for rule in &self.rules {
+            let mismatch = rule.check(tool, call, ctx).err();
+            let matched = mismatch.is_none();
+            traces.push(RuleTrace {
+                rule: rule.name.clone(),
+                action: rule.action,
+                mismatch,
+            });
+            if matched {
+                break;
+            }
+        }
+        traces
+    }
+}
+
+impl PolicyRule {
+    fn compile(spec: RuleSpec) -> std::result::Result<Self, String> {
+        let globs = |patterns: &[String]| -> std::result::Result<Vec<Glob>, String> {
+            patterns.iter().map(|p| Glob::new(p)).collect()
+        };
+        for level in [spec.min_level, spec.max_level].into_iter().flatten() {
+            if level > AutonomyLevel::FullAuto as u8 {
+                return Err(format!("autonomy level {level} is out of range 0-4"));
+            }
+        }
+        Ok(Self {
+            tools: globs(&spec.tool)?,
+            args: spec
+                .args
+                .into_iter()
+                .map(ArgCondition::compile)
+                .collect::<std::result::Result<_, _>>()?,
+            channels: globs(&spec.channel)?,
+            senders: globs(&spec.sender)?,
+            min_level: spec.min_level,
+            max_level: spec.max_level,
+            time: spec.time.map(TimeWindow::compile).transpose()?,
+            name: spec.name,
+            action: spec.action,
+            reason: spec.reason,
+        })
+    }
+
+    /// `Ok` if every condition holds, else the first that doesn't.
+    pub fn check(
+        &self,
+        tool: &Tool,
+        call: &ToolCall,
+        ctx: &GuardrailContext,
+    ) -> std::result::Result<(), String> {
+        if !self.tools.is_empty() && !self.tools.iter().any(|g| g.matches(&tool.name)) {
+            return Err(format!("tool '{}' is not {}", tool.name, list(&self.tools)));
+        }
+        for arg in &self.args {
+            arg.check(&call.arguments)?;
+        }
+        let level = ctx.level as u8;
+        if let Some(min) = self.min_level
+            && level < min
+        {
+            return Err(format!("autonomy level L{level} is below L{min}"));
+        }
+        if let Some(max) = self.max_level
+            && level > max
+        {
+            return Err(format!("autonomy level L{level} is above L{max}"));
+        }
+        check_origin("channel", &self.channels, ctx.channel.as_deref())?;
+        check_origin("sender", &self.senders, ctx.sender.as_deref())?;
+        if let Some(time) = &self.time
+            && !time.contains(ctx.now)
+        {
+            return Err(format!("the time is not {time}"));
+        }
+        Ok(())
+    }
+
+    /// The verdict this rule gives.
+    pub fn verdict(&self) -> GuardrailVerdict {
+        let reason = match &self.reason {
+            Some(reason) => format!("{reason} (policy rule '{}')", self.name),
+            None => format!("policy rule '{}'", self.name),
+        };
+        match self.action {
+            PolicyAction::Approve => GuardrailVerdict::Approve,
+            PolicyAction::Deny => GuardrailVerdict::Deny(reason),
+            PolicyAction::Escalate => GuardrailVerdict::Escalate(reason),
+        }
+    }
+}
+
+fn check_origin(
+    what: &str,
+    globs: &[Glob],
+    value: Option<&str>,
+) -> std::result::Result<(), String> {
+    if globs.is_empty() {
+        return Ok(());
+    }
+    match value {
+        Some(v) if globs.iter().any(|g| g.matches(v)) => Ok(()),
+        Some(v) => Err(format!("{what} '{v}' is not {}", list(globs))),
+        None => Err(format!("the call has no {what}")),
+    }
+}
+
+fn list(globs: &[Glob]) -> String {
+    let quoted: Vec<String> = globs.iter().map(|g| format!("'{}'", g.pattern)).collect();
+    match quoted.as_slice() {
+        [one] => one.clone(),
+        _ => format!("one of {}", quoted.join(", ")),
+    }
+}
+
+// ── Argument conditions ────────────────────────────────────────
+
+#[derive(Debug)]
+struct ArgCondition {
+    path: String,
+    steps: Vec<Step>,
+    equals: Option<Value>,
+    regex: Option<Regex>,
+    glob: Option<Glob>,
+    exists: Option<bool>,
+}
+
+#[derive(Debug)]
+enum Step {
+    Key(String),
+    Index(usize),
+    Any,
+}
+
+impl ArgCondition {
+    fn compile(spec: ArgSpec) -> std::result::Result<Self, String> {
+        if spec.equals.is_none()
+            && spec.regex.is_none()
+            && spec.glob.is_none()
+            && spec.exists.is_none()
+        {
+            return Err(format!(
+                "args '{}' needs one of equals, regex, glob or exists",
+                spec.path
+            ));
+        }
+        let regex = spec
+            .regex
+            .map(|r| Regex::new(&r).map_err(|e| format!("args '{}': {e}", spec.path)))
+            .transpose()?;
+        Ok(Self {
+            steps: parse_path(&spec.path)?,
+            equals: spec.equals,
+            regex,
+            glob: spec.glob.as_deref().map(Glob::new).transpose()?,
+            exists: spec.exists,
+            path: spec.path,
+        })
+    }
+
+    fn check(&self, args: &Value) -> std::result::Result<(), String> {
+        let values = resolve(args, &self.steps);
+        if let Some(exists) = self.exists
+            && exists == values.is_empty()
+        {
+            return Err(if exists {
+                format!("argument '{}' is missing", self.path)
+            } else {
+                format!("argument '{}' is present", self.path)
+            });
+        }
+        if self.equals.is_none() && self.regex.is_none() && self.glob.is_none() {
+            return Ok(());
+        }
+        if values.is_empty() {
+            return Err(format!("argument '{}' is missing", self.path));
+        }
+        if values.iter().any(|v| self.test(v)) {
+            return Ok(());
+        }
+        let mut wanted = Vec::new();
+        if let Some(equals) = &self.equals {
+            wanted.push(format!("equal {equals}"));
+        }
+        if let Some(regex) = &self.regex {
+            wanted.push(format!("match /{regex}/"));
+        }
+        if let Some(glob) = &self.glob {
+            wanted.push(format!("match '{}'", glob.pattern));
+        }
+        Err(format!(
+            "argument '{}' does not {}",
+            self.path,
+            wanted.join(" and ")
+        ))
+    }
+
+    fn test(&self, value: &Value) -> bool {
+        let text = match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        self.equals.as_ref().is_none_or(|e| e == value)
+            && self.regex.as_ref().is_none_or(|r| r.is_match(&text))
+            && self
+                .glob
+                .as_ref()
+                .is_none_or(|g| g.matches(&normalize_path(&text)))
+    }
+}
+
+/// Parse `a.b[0].c`, `items[*].path` or `$.command` into steps. `*` as a
+/// key or index matches every member.
+fn parse_path(path: &str) -> std::result::Result<Vec<Step>, String> {
+    let trimmed = path.strip_prefix('$').unwrap_or(path);
+    let trimmed = trimmed.strip_prefix('.').unwrap_or(trimmed);
+    let mut steps = Vec::new();
+    for segment in trimmed.split('.') {
+        let (key, mut rest) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+        match key {
+            "" if rest.is_empty() => return Err(format!("bad argument path '{path}'")),
+            "" => {}
+            "*" => steps.push(Step::Any),
+            key => steps.push(Step::Key(key.to_string())),
+        }
+        while !rest.is_empty() {
+            let Some((index, tail)) = rest.strip_prefix('[').and_then(|r| r.split_once(']')) else {
+                return Err(format!("bad argument path '{path}'"));
+            };
+            steps.push(match index {
+                "*" => Step::Any,
+                i => Step::Index(
+                    i.parse()
+                        .map_err(|_| format!("bad index '{i}' in argument path '{path}'"))?,
+                ),
+            });
+            rest = tail;
+        }
+    }
+    Ok(steps)
+}
+
+fn resolve<'a>(value: &'a Value, steps: &[Step]) -> Vec<&'a Value> {
+    let Some((step, rest)) = steps.split_first() else {
+        return vec![value];
+    };
+    let next: Vec<&Value> = match (step, value) {
+        (Step::Key(k), Value::Object(map)) => map.get(k).into_iter().collect(),
+        (Step::Index(i), Value::Array(items)) => items.get(*i).into_iter().collect(),
+        (Step::Any, Value::Array(items)) => items.iter().collect(),
+        (Step::Any, Value::Object(map)) => map.values().collect(),
+        _ => vec![],
+    };
+    next.into_iter().flat_map(|v| resolve(v, rest)).collect()
+}
+
+/// Resolve `.` and `..` so `/tmp/../etc/passwd` can't slip past `/etc/**`.
+pub(crate) fn normalize_path(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    let absolute = path.starts_with('/');
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str().unwrap_or_default()),
+            Component::ParentDir => {
+                if parts.last().is_some_and(|p| *p != "..") {
+                    parts.pop();
+                } else if !absolute {
+                    parts.push("..");
+                }
+            }
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    let joined = parts.join("/");
+    if absolute {
+        format!("/{joined}")
+    } else {
+        joined
+    }
+}
+
+// ── Globs ──────────────────────────────────────────────────────
+
+/// A glob: `*` matches within a path segment, `**` across segments, `?` one
+/// character.
+#[derive(Debug)]
+struct Glob {
+    pattern: String,
+    regex: Regex,
+}
+
+impl Glob {
+    fn new(pattern: &str) -> std::result::Result<Self, String> {
+        let mut re = String::from("^");
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        re.push_str("(?:.*/)?");
+                    } else {
+                        re.push_str(".*");
+                    }
+                }
+                '*' => re.push_str("[^/]*"),
+                '?' => re.push_str("[^/]"),
+                c => re.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        re.push('$');
+        Ok(Self {
+            pattern: pattern.to_string(),
+            regex: Regex::new(&re).map_err(|e| format!("bad glob '{pattern}': {e}"))?,
+        })
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+}
+
+// ── Time windows ───────────────────────────────────────────────
+
+#[derive(Debug)]
+struct TimeWindow {
+    days: Vec<Weekday>,
+    /// Minutes after midnight, start inclusive, end exclusive. A start after
+    /// the end wraps past midnight.
+    hours: Option<(u32, u32)>,
+    outside: bool,
+    offset: Option<FixedOffset>,
+    spec: String,
+}
+
+impl TimeWindow {
+    fn compile(spec: TimeSpec) -> std::result::Result<Self, String> {
+        let days = spec
+            .days
+            .iter()
+            .map(|d| {
+                d.parse::<Weekday>()
+                    .map_err(|_| format!("unknown day '{d}'"))
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let hours = spec.hours.as_deref().map(parse_hours).transpose()?;
+        let offset = spec
+            .utc_offset
+            .as_deref()
+            .map(|o| {
+                o.parse::<FixedOffset>()
+                    .map_err(|_| format!("bad utc_offset '{o}' (expected e.g. +02:00)"))
+            })
+            .transpose()?;
+        let mut description = Vec::new();
+        if !spec.days.is_empty() {
+            description.push(spec.days.join("/"));
+        }
+        if let Some(h) = &spec.hours {
+            description.push(h.clone());
+        }
+        if let Some(o) = &spec.utc_offset {
+            description.push(format!("UTC{o}"));
+        }
+        let description = if description.is_empty() {
+            "any time".to_string()
+        } else {
+            description.join(" ")
+        };
+        Ok(Self {
+            days,
+            hours,
+            outside: spec.outside,
+            offset,
+            spec: if spec.outside {
+                format!("outside {description}")
+            } else {
+                format!("within {description}")
+            },
+        })
+    }
+
+    fn contains(&self, now: DateTime<Utc>) -> bool {
+        let (weekday, minute) = match self.offset {
+            Some(offset) => {
+                let t = now.with_timezone(&offset);
+                (t.weekday(), t.hour() * 60 + t.minute())
+            }
+            None => {
+                let t = now.with_timezone(&Local);
+                (t.weekday(), t.hour() * 60 + t.minute())
+            }
+        };
+        let on_day = self.days.is_empty() || self.days.contains(&weekday);
+        let in_hours = match self.hours {
+            None => true,
+            Some((start, end)) if start <= end => (start..end).contains(&minute),
+            Some((start, end)) => minute >= start || minute < end,
+        };
+        (on_day && in_hours) != self.outside
+    }
+}
+
+impl std::fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.spec)
+    }
+}
+
+/// `09:00-18:00` → minutes after midnight.
+fn parse_hours(hours: &str) -> std::result::Result<(u32, u32), String> {
+    let bad = || format!("bad hours '{hours}' (expected e.g. 09:00-18:00)");
+    let minutes = |t: &str| -> Option<u32> {
+        let (h, m) = t.trim().split_once(':')?;
+        let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+        (h <= 24 && m < 60 && h * 60 + m <= 24 * 60).then_some(h * 60 + m)
+    };
+    let (start, end) = hours.split_once('-').ok_or_else(bad)?;
+    Ok((
+        minutes(start).ok_or_else(bad)?,
+        minutes(end).ok_or_else(bad)?,
+    ))
+}
+
+// ── Guardrail ──────────────────────────────────────────────────
+
+/// A policy file as a guardrail. Clones share the same rules; when the file
+/// changes, the next call reloads it.
+#[derive(Clone)]
+pub struct PolicyGuardrail {
+    inner: Arc<PolicyInner>,
+}
+
+struct PolicyInner {
+    path: Option<PathBuf>,
+    state: RwLock<Loaded>,
+}
+
+struct Loaded {
+    policy: Arc<Policy>,
+    modified: Option<SystemTime>,
+}
+
+impl PolicyGuardrail {
+    /// Load a policy file, watching it for changes.
+    pub fn load(path: &Path) -> Result<Self> {
+        let modified = modified(path);
+        let policy = Policy::load(path)?;
+        info!(path = %path.display(), rules = policy.rules.len(), "loaded guardrail policy");
+        Ok(Self {
+            inner: Arc::new(PolicyInner {
+                path: Some(path.to_path_buf()),
+                state: RwLock::new(Loaded {
+                    policy: Arc::new(policy),
+                    modified,
+                }),
+            }),
+        })
+    }
+
+    /// A fixed policy that isn't backed by a file.
+    pub fn from_policy(policy: Policy) -> Self {
+        Self {
+            inner: Arc::new(PolicyInner {
+                path: None,
+                state: RwLock::new(Loaded {
+                    policy: Arc::new(policy),
+                    modified: None,
+                }),
+            }),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.inner.path.as_deref()
+    }
+
+    /// The current rules, reloading the file first if it changed since it
+    /// was last read.
+    pub fn policy(&self) -> Arc<Policy> {
+        if let Some(path) = &self.inner.path {
+            let modified = modified(path);
+            if modified.is_some() && modified != self.inner.state.read().modified {
+                let mut state = self.inner.state.write();
+                if modified != state.modified {
+                    // Recorded even on failure, so a broken file warns once
+                    state.modified = modified;
+                    match Policy::load(path) {
+                        Ok(policy) => {
+                            info!(
+                                path = %path.display(),
+                                rules = policy.rules.len(),
+                                "guardrail policy reloaded"
+                            );
+                            state.policy = Arc::new(policy);
+                        }
+                        Err(e) => {
+                            warn!(error = %e, "policy file has errors, keeping current rules");
+                        }
+                    }
+                }
+            }
+        }
+        Arc::clone(&self.inner.state.read().policy)
+    }
+
+    /// Re-read the policy file now.
+    pub fn reload(&self) -> Result<()> {
+        let Some(path) = &self.inner.path else {
+            return Ok(());
+        };
+        let modified = modified(path);
+        let policy = Policy::load(path)?;
+        let mut state = self.inner.state.write();
+        state.policy = Arc::new(policy);
+        state.modified = modified;
+        Ok(())
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl Guardrail for PolicyGuardrail {
+    fn name(&self) -> &str {
+        "policy"
+    }
+
+    fn evaluate(&self, tool: &Tool, call: &ToolCall, level: AutonomyLevel) -> GuardrailVerdict {
+        self.decide(tool, call, &GuardrailContext::new(level))
+            .unwrap_or(GuardrailVerdict::Approve)
+    }
+
+    fn decide(
+        &self,
+        tool: &Tool,
+        call: &ToolCall,
+        ctx: &GuardrailContext,
+    ) -> Option<GuardrailVerdict> {
+        let policy = self.policy();
+        let rule = policy.matching(tool, call, ctx)?;
+        info!(rule = %rule.name, tool = %tool.name, action = %rule.action, "policy rule matched");
+        Some(rule.verdict())
+    }
+}
@@ -0,0 +1,310 @@
+//! Filesystem scoping for tools that take paths — `file_*`, `apply_patch`,
+//! `kb_ingest`, `channel_send_file`, `browser_upload_file` and
+//! `android_install`.
+//!
+//! Every path argument is resolved the way the OS will resolve it: `~` is
+//! expanded, relative paths are joined onto the workspace, and symlinks and
+//! `..` are resolved through the longest prefix that exists. The tool then
+//! gets the resolved path, so what was checked is what gets opened — a
+//! `../../` walk or a symlink pointing out of the workspace lands outside
+//! the scope and is refused.
+//!
+//! Forbidden paths (`~/.ssh`, the Claw config, `/etc/shadow`, ...) are off
+//! limits everywhere. With `workspace_only`, or when `allowed_paths` is set,
+//! paths must also lie under the workspace or one of the allowed paths.
+
+use std::path::{Component, Path, PathBuf};
+
+use claw_config::schema::AutonomyConfig;
+
+/// How a tool uses a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathAccess {
+    /// Reads or writes the file, or lists the directory.
+    File,
+    /// Reads the contents of everything under the directory.
+    Search,
+}
+
+/// Where a tool keeps its path arguments.
+enum PathArg {
+    /// A string argument.
+    Field(&'static str),
+    /// An array of path strings.
+    List(&'static str),
+    /// A field of each object in an array.
+    Each(&'static str, &'static str),
+}
+
+fn path_args(tool: &str) -> &'static [(PathArg, PathAccess)] {
+    use PathAccess::*;
+    use PathArg::*;
+    match tool {
+        "file_read" | "file_write" | "file_edit" | "file_list" => &[(Field("path"), File)],
+        // `find` doesn't read contents, `grep -r` does
+        "file_find" => &[(Field("directory"), File)],
+        "file_grep" => &[(Field("directory"), Search)],
+        "kb_ingest" => &[(Field("path"), Search)],
+        "apply_patch" => &[(Each("edits", "path"), File)],
+        "channel_send_file" => &[(Field("file_path"), File)],
+        "browser_upload_file" => &[(List("files"), File)],
+        "android_install" => &[(Field("apk_path"), File)],
+        _ => &[],
+    }
+}
+
+/// `~` and `~/...` under the home directory.
+fn expand(path: &Path) -> PathBuf {
+    let home = || dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+    match path.to_str() {
+        Some("~") => home(),
+        Some(s) if s.starts_with("~/") => home().join(&s[2..]),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// The filesystem scope path-taking tools run in.
+#[derive(Debug, Clone)]
+pub struct PathScope {
+    workspace: PathBuf,
+    workspace_only: bool,
+    allowed: Vec<PathBuf>,
+    forbidden: Vec<PathBuf>,
+    /// Forbidden files whose siblings sharing their name are forbidden too.
+    forbidden_stems: Vec<PathBuf>,
+}
+
+impl PathScope {
+    /// An unrestricted scope rooted at `workspace`, which relative paths
+    /// resolve against.
+    pub fn new(workspace: impl AsRef<Path>) -> Self {
+        Self {
+            workspace: root(workspace.as_ref()),
+            workspace_only: false,
+            allowed: vec![],
+            forbidden: vec![],
+            forbidden_stems: vec![],
+        }
+    }
+
+    /// The scope `[autonomy]` configures, rooted at `workspace`.
+    pub fn from_config(config: &AutonomyConfig, workspace: impl AsRef<Path>) -> Self {
+        Self::new(workspace)
+            .workspace_only(config.workspace_only)
+            .allow(&config.allowed_paths)
+            .forbid(&config.forbidden_paths)
+    }
+
+    /// Limit paths to the workspace (and any allowed paths).
+    pub fn workspace_only(mut self, on: bool) -> Self {
+        self.workspace_only = on;
+        self
+    }
+
+    /// Limit paths to these directories (and the workspace, with
+    /// `workspace_only`).
+    pub fn allow<P: AsRef<Path>>(mut self, paths: impl IntoIterator<Item = P>) -> Self {
+        self.allowed
+            .extend(paths.into_iter().map(|p| root(p.as_ref())));
+        self
+    }
+
+    /// Never allow paths under these.
+    pub fn forbid<P: AsRef<Path>>(mut self, paths: impl IntoIterator<Item = P>) -> Self {
+        for path in paths {
+            let lexical = normalize(&expand(path.as_ref()));
+            let resolved = root(&lexical);
+            if resolved != lexical {
+                self.forbidden.push(lexical);
+            }
+            self.forbidden.push(resolved);
+        }
+        self
+    }
+
+    /// Never allow these files, nor siblings whose names start with theirs —
+    /// a database's `-wal` journal and its backups.
+    pub fn forbid_with_siblings<P: AsRef<Path>>(
+        mut self,
+        files: impl IntoIterator<Item = P>,
+    ) -> Self {
+        for file in files {
+            let lexical = normalize(&expand(file.as_ref()));
+            let resolved = root(&lexical);
+            if resolved != lexical {
+                self.forbidden_stems.push(lexical);
+            }
+            self.forbidden_stems.push(resolved);
+        }
+        self
+    }
+
+    pub fn workspace(&self) -> &Path {
+        &self.workspace
+    }
+
+    /// Whether paths are limited to the workspace and allowed paths.
+    pub fn is_restricted(&self) -> bool {
+        self.workspace_only || !self.allowed.is_empty()
+    }
+
+    /// The forbidden path a resolved, absolute path lies under, if any. For
+    /// checking each file a tool finds while walking a directory it was
+    /// given.
+    pub fn forbidden_by(&self, path: &Path) -> Option<&Path> {
+        let sibling = |stem: &PathBuf| {
+            path.ancestors().any(|a| {
+                a.parent() == stem.parent()
+                    && a.file_name()
+                        .zip(stem.file_name())
+                        .is_some_and(|(a, stem)| {
+                            a.as_encoded_bytes().starts_with(stem.as_encoded_bytes())
+                        })
+            })
+        };
+        self.forbidden
+            .iter()
+            .find(|f| path.starts_with(f))
+            .or_else(|| self.forbidden_stems.iter().find(|stem| sibling(stem)))
+            .map(PathBuf::as_path)
+    }
+
+    /// Resolve a path argument, or say why it's out of scope.
+    pub fn resolve(&self, path: &str, access: PathAccess) -> Result<PathBuf, String> {
+        let expanded = expand(Path::new(path));
+        let absolute = if expanded.is_absolute() {
+            expanded
+        } else {
+            self.workspace.join(expanded)
+        };
+        let resolved = resolve_existing(&absolute).map_err(|why| format!("{path}: {why}"))?;
+
+        if let Some(forbidden) = self.forbidden_by(&resolved) {
+            return Err(format!(
+                "{path} is inside forbidden path {} (autonomy.forbidden_paths)",
+                forbidden.display()
+            ));
+        }
+        if access == PathAccess::Search
+            && let Some(forbidden) = self
+                .forbidden
+                .iter()
+                .chain(&self.forbidden_stems)
+                .find(|f| f.starts_with(&resolved))
+        {
+            return Err(format!(
+                "searching {path} would read forbidden path {} (autonomy.forbidden_paths) — search a narrower directory",
+                forbidden.display()
+            ));
+        }
+        if self.is_restricted() {
+            let inside_workspace = self.workspace_only && resolved.starts_with(&self.workspace);
+            if !inside_workspace && !self.allowed.iter().any(|a| resolved.starts_with(a)) {
+                return Err(format!(
+                    "{path} is outside the {} (autonomy.workspace_only / allowed_paths)",
+                    self.describe()
+                ));
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Check every path argument of a tool call. Returns the arguments with
+    /// each path replaced by its resolved form, or `None` if the tool takes
+    /// no paths.
+    pub fn check_call(
+        &self,
+        tool: &str,
+        args: &serde_json::Value,
+    ) -> Result<Option<serde_json::Value>, String> {
+        let specs = path_args(tool);
+        if specs.is_empty() {
+            return Ok(None);
+        }
+        let mut args = args.clone();
+        for (arg, access) in specs {
+            let slots: Vec<&mut serde_json::Value> = match arg {
+                PathArg::Field(key) => args.get_mut(*key).into_iter().collect(),
+                PathArg::List(key) => match args.get_mut(*key) {
+                    Some(serde_json::Value::Array(items)) => items.iter_mut().collect(),
+                    _ => vec![],
+                },
+                PathArg::Each(key, field) => match args.get_mut(*key) {
+                    Some(serde_json::Value::Array(items)) => items
+                        .iter_mut()
+                        .filter_map(|item| item.get_mut(*field))
+                        .collect(),
+                    _ => vec![],
+                },
+            };
+            for slot in slots {
+                // Missing or malformed arguments are the tool's to report
+                if let serde_json::Value::String(path) = slot {
+                    let resolved = self.resolve(path, *access)?;
+                    *slot = serde_json::Value::String(resolved.to_string_lossy().into_owned());
+                }
+            }
+        }
+        Ok(Some(args))
+    }
+
+    fn describe(&self) -> String {
+        let mut roots = vec![];
+        if self.workspace_only {
+            roots.push(format!("workspace {}", self.workspace.display()));
+        }
+        roots.extend(self.allowed.iter().map(|a| a.display().to_string()));
+        format!("allowed paths ({})", roots.join(", "))
+    }
+}
+
+/// A configured directory, absolute and with symlinks resolved where it
+/// exists.
+fn root(path: &Path) -> PathBuf {
+    let path = expand(path);
+    let absolute = std::path::absolute(&path).unwrap_or(path);
+    resolve_existing(&absolute).unwrap_or_else(|_| normalize(&absolute))
+}
+
+/// Resolve symlinks and `..` through the longest prefix of an absolute path
+/// that exists, then append the rest. The rest doesn't exist yet, so it
+/// can't be a symlink — unless it's a dangling one, which is refused, as is
+/// `..` past a directory that isn't there.
+fn resolve_existing(path: &Path) -> Result<PathBuf, String> {
+    let components: Vec<Component> = path.components().collect();
+    for split in (1..=components.len()).rev() {
+        let prefix: PathBuf = components[..split].iter().collect();
+        let Ok(mut resolved) = std::fs::canonicalize(&prefix) else {
+            continue;
+        };
+        for (i, component) in components[split..].iter().enumerate() {
+            match component {
+                Component::CurDir => {}
+                Component::Normal(name) => {
+                    resolved.push(name);
+                    if i == 0 && std::fs::symlink_metadata(&resolved).is_ok() {
+                        return Err("is a dangling symlink".into());
+                    }
+                }
+                _ => return Err("walks `..` out of a directory that doesn't exist".into()),
+            }
+        }
+        return Ok(resolved);
+    }
+    Ok(normalize(path))
+}
+
+/// Lexically resolve `.` and `..`.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
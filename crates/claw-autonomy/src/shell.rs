@@ -0,0 +1,1632 @@
+//! Shell command analysis — parses the command lines agents hand to
+//! `shell_exec`, `process_start`, `terminal_run` and `terminal_input` into
+//! the programs they actually run, and scores what those programs do.
+//!
+//! The parser understands quoting, pipes, `&&` / `||` / `;`, subshells,
+//! command and process substitution, redirections and heredocs, and looks
+//! through wrappers — `sudo`, `env`, `nohup`, `timeout`, `xargs`,
+//! `find -exec`, `watch`, `eval` and `sh -c` / `bash -c` at any depth — to
+//! the command underneath. It doesn't expand variables or globs: it sees
+//! what the agent wrote, which is what the agent meant.
+//!
+//! Each command is classified as destructive, privileged, network egress,
+//! data exfiltration, a package install or opaque — code whose text isn't
+//! there to inspect, like a script piped into `sh` or a program name built
+//! at run time — with a 0–10 risk score. The
+//! command line scores as its riskiest finding. [`ShellCommandGuardrail`]
+//! turns that score into a verdict: 10 is denied outright, anything above
+//! the autonomy level's auto-approve threshold needs approval. The outright
+//! denial holds even for allowlisted tools and policy approvals.
+
+use claw_core::{Tool, ToolCall};
+use regex::Regex;
+use serde::Serialize;
+use std::sync::LazyLock;
+
+use crate::guardrail::{Guardrail, GuardrailVerdict};
+use crate::level::AutonomyLevel;
+use crate::policy::normalize_path;
+
+/// Scores at or above this are never run.
+pub const BLOCK_SCORE: u8 = 10;
+
+/// How deep `bash -c "sh -c '...'"` and `$(...)` nesting is followed.
+const MAX_DEPTH: usize = 8;
+
+/// The shell text a tool call will run, if the tool runs shell text.
+/// Terminal tools type into a line that may already hold input, so the
+/// runtime judges them with that pending input folded into the call.
+pub fn shell_text(tool: &str, args: &serde_json::Value) -> Option<String> {
+    let key = match tool {
+        "shell_exec" | "system_run" | "process_start" | "terminal_run" => "command",
+        "terminal_input" => "text",
+        _ => return None,
+    };
+    match args.get(key)? {
+        serde_json::Value::String(s) => Some(s.clone()),
+        // An argv array
+        serde_json::Value::Array(items) => Some(
+            items
+                .iter()
+                .map(|v| match v {
+                    serde_json::Value::String(s) => quote(s),
+                    other => other.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        _ => None,
+    }
+}
+
+fn quote(word: &str) -> String {
+    if !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@%+,".contains(c))
+    {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+// ── Parsed commands ────────────────────────────────────────────
+
+/// One program a command line runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ShellCommand {
+    /// Executable name, without its directory.
+    pub program: String,
+    pub args: Vec<String>,
+    /// Redirections as (operator, target), e.g. `(">", "/dev/sda")`.
+    pub redirects: Vec<(String, String)>,
+    /// Wrappers it runs under, outermost first, e.g. `["sudo", "xargs"]`.
+    pub via: Vec<String>,
+}
+
+impl ShellCommand {
+    fn is_privileged(&self) -> bool {
+        self.via
+            .iter()
+            .any(|w| matches!(w.as_str(), "sudo" | "doas" | "su" | "pkexec"))
+    }
+
+    /// Operands: arguments that aren't options.
+    fn operands(&self) -> impl Iterator<Item = &str> {
+        self.args
+            .iter()
+            .map(String::as_str)
+            .filter(|a| !a.starts_with('-') || *a == "-")
+    }
+
+    /// Whether a short flag (in any cluster, like `-rf`) or a long flag is set.
+    fn has_flag(&self, short: char, long: &str) -> bool {
+        self.args.iter().any(|a| {
+            a == long
+                || (a.starts_with('-')
+                    && !a.starts_with("--")
+                    && a[1..].chars().all(|c| c.is_ascii_alphabetic())
+                    && a[1..].contains(short))
+        })
+    }
+
+    fn subcommand(&self) -> Option<&str> {
+        self.operands().next()
+    }
+
+    /// `rm -rf /tmp/x`, shortened for messages.
+    fn display(&self) -> String {
+        let mut words = vec![self.program.clone()];
+        words.extend(self.args.iter().take(4).cloned());
+        if self.args.len() > 4 {
+            words.push("…".into());
+        }
+        let text = words.join(" ");
+        if text.chars().count() > 60 {
+            format!("{}…", text.chars().take(60).collect::<String>())
+        } else {
+            text
+        }
+    }
+}
+
+/// What a command does that makes it risky.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShellRisk {
+    /// Deletes or overwrites data, or takes the machine down.
+    Destructive,
+    /// Runs as root or widens permissions.
+    Privilege,
+    /// Talks to another host.
+    Network,
+    /// Sends local data to another host.
+    Exfiltration,
+    /// Installs or removes software.
+    PackageInstall,
+    /// Runs code that can't be inspected ahead of time.
+    Opaque,
+}
+
+impl std::fmt::Display for ShellRisk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ShellRisk::Destructive => "destructive",
+            ShellRisk::Privilege => "privilege",
+            ShellRisk::Network => "network",
+            ShellRisk::Exfiltration => "exfiltration",
+            ShellRisk::PackageInstall => "package install",
+            ShellRisk::Opaque => "opaque",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShellFinding {
+    pub risk: ShellRisk,
+    /// 0–10.
+    pub score: u8,
+    /// The command it's about, shortened.
+    pub command: String,
+    pub description: String,
+}
+
+/// A parsed and classified command line.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ShellAnalysis {
+    /// Pipelines, each a list of commands joined by `|`. Commands nested in
+    /// `bash -c`, `$(...)` and the like get pipelines of their own.
+    pub pipelines: Vec<Vec<ShellCommand>>,
+    /// Riskiest first.
+    pub findings: Vec<ShellFinding>,
+    /// The highest finding score, 0 if none.
+    pub score: u8,
+}
+
+impl ShellAnalysis {
+    pub fn commands(&self) -> impl Iterator<Item = &ShellCommand> {
+        self.pipelines.iter().flatten()
+    }
+
+    /// The riskiest findings as one line, for verdict reasons.
+    pub fn summary(&self) -> String {
+        let top: Vec<String> = self
+            .findings
+            .iter()
+            .take(3)
+            .map(|f| format!("{} ({})", f.description, f.risk))
+            .collect();
+        let more = self.findings.len().saturating_sub(3);
+        if more > 0 {
+            format!("{}; and {more} more", top.join("; "))
+        } else {
+            top.join("; ")
+        }
+    }
+}
+
+/// Parse and classify a command line.
+pub fn analyze(command_line: &str) -> ShellAnalysis {
+    let mut parser = Parser::default();
+    parser.script(command_line, &[], 0);
+    let mut findings = Vec::new();
+    fork_bombs(command_line, &mut findings);
+    for pipeline in &parser.pipelines {
+        for cmd in pipeline {
+            classify(cmd, &mut findings);
+        }
+        classify_pipeline(pipeline, &mut findings);
+    }
+    findings.sort_by_key(|f| std::cmp::Reverse(f.score));
+    let mut seen = std::collections::HashSet::new();
+    findings.retain(|f| seen.insert(f.description.clone()));
+    ShellAnalysis {
+        score: findings.first().map_or(0, |f| f.score),
+        pipelines: parser.pipelines,
+        findings,
+    }
+}
+
+// ── Lexer ──────────────────────────────────────────────────────
+
+#[derive(Debug)]
+enum Token {
+    Word(Word),
+    /// `|`, `|&`, `&&`, `||`, `;`, `&`, `(`, `)`, or `;` for a newline.
+    Op(String),
+    /// A redirection operator; its target is the next word.
+    Redirect(String),
+}
+
+#[derive(Debug, Default)]
+struct Word {
+    text: String,
+    /// Command and process substitutions inside the word.
+    subs: Vec<String>,
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    tokens: Vec<Token>,
+    /// Heredoc delimiters whose bodies start at the next newline.
+    heredocs: Vec<(String, bool)>,
+}
+
+fn lex(text: &str) -> Vec<Token> {
+    let mut lexer = Lexer {
+        chars: text.chars().collect(),
+        pos: 0,
+        tokens: Vec::new(),
+        heredocs: Vec::new(),
+    };
+    lexer.run();
+    lexer.tokens
+}
+
+impl Lexer {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn run(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' => self.pos += 1,
+                '\\' if self.peek_at(1) == Some('\n') => self.pos += 2,
+                '\n' => {
+                    self.pos += 1;
+                    self.tokens.push(Token::Op(";".into()));
+                    self.skip_heredocs();
+                }
+                '#' => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.pos += 1;
+                    }
+                }
+                '<' | '>' if self.peek_at(1) == Some('(') => self.word(),
+                '<' | '>' => self.redirect(String::new()),
+                '&' if self.peek_at(1) == Some('>') => self.redirect(String::new()),
+                ';' | '&' | '|' | '(' | ')' => {
+                    let next = self.peek_at(1);
+                    let op = match (c, next) {
+                        ('&', Some('&'))
+                        | ('|', Some('|'))
+                        | ('|', Some('&'))
+                        | (';', Some(';')) => {
+                            self.pos += 2;
+                            format!("{c}{}", next.unwrap_or_default())
+                        }
+                        _ => {
+                            self.pos += 1;
+                            c.to_string()
+                        }
+                    };
+                    self.tokens.push(Token::Op(op));
+                }
+                _ => self.word(),
+            }
+        }
+    }
+
+    /// A redirection operator, e.g. `>`, `>>`, `2>&1`, `<<-`, `&>`.
+    fn redirect(&mut self, fd: String) {
+        let mut op = fd;
+        while let Some(c) = self.peek()
+            && "<>&|-".contains(c)
+            && op.len() < 4
+        {
+            // `>&` / `<&` take an fd, `<<-` a delimiter; stop before a word
+            if c == '-' && !op.ends_with("<<") {
+                break;
+            }
+            op.push(c);
+            self.pos += 1;
+        }
+        let heredoc = (op.ends_with("<<") && !op.ends_with("<<<")) || op.ends_with("<<-");
+        self.tokens.push(Token::Redirect(op.clone()));
+        if heredoc {
+            while self.peek().is_some_and(|c| c == ' ' || c == '\t') {
+                self.pos += 1;
+            }
+            let start = self.tokens.len();
+            self.word();
+            if let Some(Token::Word(w)) = self.tokens.get(start) {
+                self.heredocs.push((w.text.clone(), op.ends_with('-')));
+            }
+        }
+    }
+
+    /// Skip heredoc bodies — they're data, not commands.
+    fn skip_heredocs(&mut self) {
+        for (delimiter, strip_tabs) in std::mem::take(&mut self.heredocs) {
+            loop {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.pos += 1;
+                }
+                let line: String = self.chars[start..self.pos].iter().collect();
+                if self.peek().is_some() {
+                    self.pos += 1;
+                }
+                let line = if strip_tabs {
+                    line.trim_start_matches('\t')
+                } else {
+                    line.as_str()
+                };
+                if line.trim_end_matches('\r') == delimiter || self.peek().is_none() {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn word(&mut self) {
+        let mut word = Word::default();
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' | '\n' | ';' | '&' | '|' | '(' | ')' => break,
+                '<' | '>' if self.peek_at(1) == Some('(') => {
+                    self.pos += 2;
+                    let inner = self.balanced('(', ')');
+                    word.text.push_str(&format!("{c}({inner})"));
+                    word.subs.push(inner);
+                }
+                '<' | '>' => {
+                    // `2>` — the word was a file descriptor
+                    if !word.text.is_empty()
+                        && word.subs.is_empty()
+                        && word.text.chars().all(|c| c.is_ascii_digit())
+                    {
+                        let fd = std::mem::take(&mut word.text);
+                        self.redirect(fd);
+                        return;
+                    }
+                    break;
+                }
+                '\\' => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('\n') => self.pos += 1,
+                        Some(c) => {
+                            word.text.push(c);
+                            self.pos += 1;
+                        }
+                        None => {}
+                    }
+                }
+                '\'' => {
+                    self.pos += 1;
+                    while let Some(c) = self.peek() {
+                        self.pos += 1;
+                        if c == '\'' {
+                            break;
+                        }
+                        word.text.push(c);
+                    }
+                }
+                '"' => {
+                    self.pos += 1;
+                    self.double_quoted(&mut word);
+                }
+                '$' => self.dollar(&mut word),
+                '`' => {
+                    self.pos += 1;
+                    let inner = self.backticks();
+                    word.text.push_str(&format!("`{inner}`"));
+                    word.subs.push(inner);
+                }
+                c => {
+                    word.text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        self.tokens.push(Token::Word(word));
+    }
+
+    fn double_quoted(&mut self, word: &mut Word) {
+        while let Some(c) = self.peek() {
+            match c {
+                '"' => {
+                    self.pos += 1;
+                    return;
+                }
+                '\\' => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(n @ ('$' | '`' | '"' | '\\')) => {
+                            word.text.push(n);
+                            self.pos += 1;
+                        }
+                        Some('\n') => self.pos += 1,
+                        _ => word.text.push('\\'),
+                    }
+                }
+                '$' => self.dollar(word),
+                '`' => {
+                    self.pos += 1;
+                    let inner = self.backticks();
+                    word.text.push_str(&format!("`{inner}`"));
+                    word.subs.push(inner);
+                }
+                c => {
+                    word.text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// `$(...)`, `$((...))`, `${...}`, `$'...'` or a plain `$`.
+    fn dollar(&mut self, word: &mut Word) {
+        match (self.peek_at(1), self.peek_at(2)) {
+            (Some('('), Some('(')) => {
+                self.pos += 3;
+                let inner = self.balanced('(', ')');
+                // Arithmetic: eat the second closing paren
+                if self.peek() == Some(')') {
+                    self.pos += 1;
+                }
+                word.text.push_str(&format!("$(({inner}))"));
+            }
+            (Some('('), _) => {
+                self.pos += 2;
+                let inner = self.balanced('(', ')');
+                word.text.push_str(&format!("$({inner})"));
+                word.subs.push(inner);
+            }
+            (Some('{'), _) => {
+                self.pos += 2;
+                let inner = self.balanced('{', '}');
+                word.text.push_str(&format!("${{{inner}}}"));
+            }
+            (Some('\''), _) => {
+                self.pos += 2;
+                while let Some(c) = self.peek() {
+                    self.pos += 1;
+                    match c {
+                        '\'' => break,
+                        '\\' => {
+                            if let Some(n) = self.peek() {
+                                self.pos += 1;
+                                word.text.push(match n {
+                                    'n' => '\n',
+                                    't' => '\t',
+                                    n => n,
+                                });
+                            }
+                        }
+                        c => word.text.push(c),
+                    }
+                }
+            }
+            _ => {
+                word.text.push('$');
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Text up to the matching `close`, which is consumed. Quotes inside
+    /// are skipped over.
+    fn balanced(&mut self, open: char, close: char) -> String {
+        let start = self.pos;
+        let mut depth = 1;
+        while let Some(c) = self.peek() {
+            match c {
+                '\\' => self.pos += 1,
+                '\'' => {
+                    self.pos += 1;
+                    while self.peek().is_some_and(|c| c != '\'') {
+                        self.pos += 1;
+                    }
+                }
+                '"' => {
+                    self.pos += 1;
+                    while let Some(c) = self.peek()
+                        && c != '"'
+                    {
+                        if c == '\\' {
+                            self.pos += 1;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                c if c == open => depth += 1,
+                c if c == close => {
+                    depth -= 1;
+                    if depth == 0 {
+                        let inner = self.chars[start..self.pos].iter().collect();
+                        self.pos += 1;
+                        return inner;
+                    }
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        self.pos = self.pos.min(self.chars.len());
+        self.chars[start.min(self.chars.len())..].iter().collect()
+    }
+
+    fn backticks(&mut self) -> String {
+        let mut inner = String::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '`' => break,
+                '\\' => {
+                    if let Some(n) = self.peek() {
+                        self.pos += 1;
+                        inner.push(n);
+                    }
+                }
+                c => inner.push(c),
+            }
+        }
+        inner
+    }
+}
+
+// ── Parser ─────────────────────────────────────────────────────
+
+/// Words that start or end a compound command rather than name a program.
+const RESERVED: &[&str] = &[
+    "if", "then", "else", "elif", "fi", "do", "done", "while", "until", "for", "in", "case",
+    "esac", "select", "function", "{", "}", "!", "[[", "]]",
+];
+
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "ash", "fish"];
+
+const INTERPRETERS: &[&str] = &[
+    "sh", "bash", "zsh", "dash", "ksh", "ash", "fish", "python", "python3", "python2", "perl",
+    "ruby", "node", "php", "lua",
+];
+
+#[derive(Default)]
+struct Parser {
+    pipelines: Vec<Vec<ShellCommand>>,
+}
+
+impl Parser {
+    fn script(&mut self, text: &str, via: &[String], depth: usize) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+        let mut pipeline: Vec<ShellCommand> = Vec::new();
+        let mut words: Vec<String> = Vec::new();
+        let mut redirects: Vec<(String, String)> = Vec::new();
+        let mut tokens = lex(text).into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            match token {
+                Token::Word(word) => {
+                    for sub in &word.subs {
+                        self.script(sub, via, depth + 1);
+                    }
+                    words.push(word.text);
+                }
+                Token::Redirect(op) => {
+                    let target = match tokens.peek() {
+                        Some(Token::Word(_)) => match tokens.next() {
+                            Some(Token::Word(w)) => {
+                                for sub in &w.subs {
+                                    self.script(sub, via, depth + 1);
+                                }
+                                w.text
+                            }
+                            _ => String::new(),
+                        },
+                        _ => String::new(),
+                    };
+                    redirects.push((op, target));
+                }
+                Token::Op(op) => {
+                    let words = std::mem::take(&mut words);
+                    let redirects = std::mem::take(&mut redirects);
+                    if let Some(cmd) = self.command(words, redirects, via.to_vec(), depth) {
+                        pipeline.push(cmd);
+                    }
+                    if op != "|" && op != "|&" && !pipeline.is_empty() {
+                        self.piped_scripts(&pipeline, depth);
+                        self.pipelines.push(std::mem::take(&mut pipeline));
+                    }
+                }
+            }
+        }
+        if let Some(cmd) = self.command(words, redirects, via.to_vec(), depth) {
+            pipeline.push(cmd);
+        }
+        if !pipeline.is_empty() {
+            self.piped_scripts(&pipeline, depth);
+            self.pipelines.push(pipeline);
+        }
+    }
+
+    /// `echo 'rm -rf /' | sh`: a shell reading literal text from `echo` or
+    /// `printf` runs that text, so parse it too.
+    fn piped_scripts(&mut self, pipeline: &[ShellCommand], depth: usize) {
+        for pair in pipeline.windows(2) {
+            let (source, shell) = (&pair[0], &pair[1]);
+            if matches!(source.program.as_str(), "echo" | "printf")
+                && SHELLS.contains(&shell.program.as_str())
+                && reads_stdin(shell)
+            {
+                let text: Vec<&str> = source
+                    .args
+                    .iter()
+                    .map(String::as_str)
+                    .skip_while(|a| matches!(*a, "-n" | "-e" | "-E" | "--"))
+                    .collect();
+                let mut inner = shell.via.clone();
+                inner.push(shell.program.clone());
+                self.script(&text.join(" "), &inner, depth + 1);
+            }
+        }
+    }
+
+    /// Resolve a simple command's words to the program that runs, looking
+    /// through wrappers. Scripts it runs (`bash -c`, `find -exec`, ...) are
+    /// parsed as pipelines of their own.
+    fn command(
+        &mut self,
+        words: Vec<String>,
+        redirects: Vec<(String, String)>,
+        mut via: Vec<String>,
+        depth: usize,
+    ) -> Option<ShellCommand> {
+        let mut words: &[String] = &words;
+        loop {
+            while let Some(first) = words.first()
+                && (RESERVED.contains(&first.as_str()) || is_assignment(first))
+            {
+                words = &words[1..];
+            }
+            let first = words.first()?;
+            let rest = &words[1..];
+            // `$x -rf /`, `$(echo rm)`: the program is only known at run time
+            if is_dynamic(first) {
+                return Some(shell_command(first, rest, redirects, via));
+            }
+            let program = basename(first);
+            let next = match program.as_str() {
+                "sudo" => skip_options(
+                    rest,
+                    &["-u", "-g", "-C", "-D", "-h", "-p", "-r", "-t", "-U", "-T"],
+                ),
+                "doas" => skip_options(rest, &["-u", "-C"]),
+                // `busybox rm`, `busybox sh -c`: the applet is the program
+                "busybox" => skip_options(rest, &[]),
+                "pkexec" => skip_options(rest, &["--user"]),
+                "env" => skip_options(rest, &["-u", "-C", "-S", "--unset", "--chdir"]),
+                "nohup" | "exec" | "builtin" | "time" | "unbuffer" | "chronic" | "caffeinate" => {
+                    skip_options(rest, &["-a"])
+                }
+                "command" if !rest.iter().any(|a| a == "-v" || a == "-V") => rest,
+                "nice" => skip_options(rest, &["-n"]),
+                "ionice" => skip_options(rest, &["-c", "-n", "-p"]),
+                "stdbuf" => skip_options(rest, &["-i", "-o", "-e"]),
+                "timeout" => {
+                    let rest = skip_options(rest, &["-s", "-k", "--signal", "--kill-after"]);
+                    rest.get(1..).unwrap_or_default()
+                }
+                "chroot" => {
+                    let rest = skip_options(rest, &["--userspec", "--groups"]);
+                    rest.get(1..).unwrap_or_default()
+                }
+                "xargs" => skip_options(
+                    rest,
+                    &[
+                        "-I",
+                        "-i",
+                        "-n",
+                        "-P",
+                        "-L",
+                        "-l",
+                        "-d",
+                        "-E",
+                        "-e",
+                        "-s",
+                        "-a",
+                        "--delimiter",
+                        "--max-args",
+                        "--max-procs",
+                        "--arg-file",
+                        "--replace",
+                    ],
+                ),
+                "su" => {
+                    if let Some(script) = option_value(rest, &["-c", "--command"]) {
+                        let mut inner = via.clone();
+                        inner.push(program.clone());
+                        self.script(script, &inner, depth + 1);
+                    }
+                    return Some(shell_command(&program, rest, redirects, via));
+                }
+                "eval" => {
+                    via.push(program.clone());
+                    self.script(&rest.join(" "), &via, depth + 1);
+                    return None;
+                }
+                "watch" => {
+                    let rest = skip_options(rest, &["-n", "-d", "--interval"]);
+                    via.push(program.clone());
+                    self.script(&rest.join(" "), &via, depth + 1);
+                    return None;
+                }
+                "find" => {
+                    self.find_exec(rest, &via, depth);
+                    return Some(shell_command(&program, rest, redirects, via));
+                }
+                p if SHELLS.contains(&p) => {
+                    // `bash -c SCRIPT` and `bash <<< SCRIPT`
+                    let here_string = redirects
+                        .iter()
+                        .find(|(op, _)| op.ends_with("<<<"))
+                        .map(|(_, text)| text.as_str());
+                    if let Some(script) = shell_script(rest).or(here_string) {
+                        let mut inner = via.clone();
+                        inner.push(program.clone());
+                        self.script(script, &inner, depth + 1);
+                    }
+                    return Some(shell_command(&program, rest, redirects, via));
+                }
+                _ => return Some(shell_command(&program, rest, redirects, via)),
+            };
+            via.push(program);
+            if next.is_empty() {
+                // `sudo` alone, `sudo -i`: a root shell
+                let wrapper = via.pop().unwrap_or_default();
+                return Some(shell_command(&wrapper, rest, redirects, via));
+            }
+            words = next;
+        }
+    }
+
+    /// `find ... -exec cmd {} ;` runs `cmd`.
+    fn find_exec(&mut self, args: &[String], via: &[String], depth: usize) {
+        let mut i = 0;
+        while i < args.len() {
+            if matches!(args[i].as_str(), "-exec" | "-execdir" | "-ok" | "-okdir") {
+                let end = args[i + 1..]
+                    .iter()
+                    .position(|a| a == ";" || a == "+")
+                    .map_or(args.len(), |p| i + 1 + p);
+                let script: Vec<String> = args[i + 1..end].iter().map(|a| quote(a)).collect();
+                let mut inner = via.to_vec();
+                inner.push("find".into());
+                self.script(&script.join(" "), &inner, depth + 1);
+                i = end;
+            }
+            i += 1;
+        }
+    }
+}
+
+fn shell_command(
+    program: &str,
+    args: &[String],
+    redirects: Vec<(String, String)>,
+    via: Vec<String>,
+) -> ShellCommand {
+    ShellCommand {
+        program: program.to_string(),
+        args: args.to_vec(),
+        redirects,
+        via,
+    }
+}
+
+/// A word whose value comes from a variable or a command substitution.
+fn is_dynamic(word: &str) -> bool {
+    word.contains('$') || word.contains('`')
+}
+
+/// Whether an interpreter takes its program on stdin — no script argument,
+/// or `-` / `-s` — rather than from `-c` or a file.
+fn reads_stdin(cmd: &ShellCommand) -> bool {
+    if SHELLS.contains(&cmd.program.as_str()) && shell_script(&cmd.args).is_some() {
+        return false;
+    }
+    cmd.args.iter().any(|a| a == "-s") || cmd.operands().next().is_none_or(|first| first == "-")
+}
+
+fn basename(word: &str) -> String {
+    word.rsplit('/').next().unwrap_or(word).to_string()
+}
+
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+    })
+}
+
+/// Skip leading options (and assignments, for `env`); `with_arg` options
+/// take the next word as their value.
+fn skip_options<'a>(words: &'a [String], with_arg: &[&str]) -> &'a [String] {
+    let mut i = 0;
+    while let Some(w) = words.get(i) {
+        if w == "--" {
+            return &words[i + 1..];
+        }
+        if with_arg.contains(&w.as_str()) {
+            i += 2;
+        } else if (w.starts_with('-') && w.len() > 1) || is_assignment(w) {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    words.get(i..).unwrap_or_default()
+}
+
+/// The value of `-c VALUE` / `--command=VALUE`.
+fn option_value<'a>(words: &'a [String], names: &[&str]) -> Option<&'a str> {
+    for (i, w) in words.iter().enumerate() {
+        if names.contains(&w.as_str()) {
+            return words.get(i + 1).map(String::as_str);
+        }
+        for name in names.iter().filter(|n| n.starts_with("--")) {
+            if let Some(value) = w.strip_prefix(&format!("{name}=")) {
+                return Some(value);
+            }
+        }
+    }
+    None
+}
+
+/// The script of `sh -c SCRIPT`, `bash -lc SCRIPT` and the like.
+fn shell_script(args: &[String]) -> Option<&str> {
+    let mut takes_script = false;
+    for arg in args {
+        if takes_script {
+            return Some(arg);
+        }
+        if arg.starts_with('-') && !arg.starts_with("--") && arg[1..].contains('c') {
+            takes_script = true;
+        } else if !arg.starts_with('-') && !arg.starts_with('+') {
+            // `sh script.sh`: not inline
+            return None;
+        }
+    }
+    None
+}
+
+// ── Classification ─────────────────────────────────────────────
+
+/// Top-level paths whose recursive deletion wrecks the system or a home.
+const CRITICAL_PATHS: &[&str] = &[
+    "/",
+    "/*",
+    "~",
+    "~/*",
+    "$HOME",
+    "$HOME/*",
+    "${HOME}",
+    "/home",
+    "/root",
+    "/etc",
+    "/usr",
+    "/var",
+    "/boot",
+    "/bin",
+    "/sbin",
+    "/lib",
+    "/lib64",
+    "/opt",
+    "/sys",
+    "/dev",
+    "/proc",
+    "/Users",
+    "/System",
+    "/Library",
+    "/Applications",
+];
+
+const NETWORK_TOOLS: &[&str] = &[
+    "curl",
+    "wget",
+    "nc",
+    "ncat",
+    "netcat",
+    "socat",
+    "telnet",
+    "ssh",
+    "scp",
+    "sftp",
+    "rsync",
+    "ftp",
+    "tftp",
+    "http",
+    "https",
+    "aria2c",
+    "lftp",
+    "smbclient",
+];
+
+/// Device paths that are whole disks or partitions.
+fn is_block_device(path: &str) -> bool {
+    [
+        "/dev/sd",
+        "/dev/hd",
+        "/dev/vd",
+        "/dev/xvd",
+        "/dev/nvme",
+        "/dev/mmcblk",
+        "/dev/disk",
+        "/dev/mapper/",
+        "/dev/md",
+        "/dev/dm-",
+        "/dev/loop",
+    ]
+    .iter()
+    .any(|p| path.starts_with(p))
+}
+
+/// Whether a path is, or resolves to, one of [`CRITICAL_PATHS`] —
+/// `/tmp/../home` and `/./etc` included.
+fn critical_path(path: &str) -> bool {
+    let home = ["${HOME}", "$HOME", "~"].into_iter().find_map(|home| {
+        path.strip_prefix(home)
+            .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+            .map(|rest| (home, rest))
+    });
+    let normalized = match home {
+        Some((home, rest)) => format!("{home}{}", normalize_path(&format!("/{rest}"))),
+        None if path.starts_with('/') => normalize_path(path),
+        None => path.to_string(),
+    };
+    let trimmed = normalized.trim_end_matches('/');
+    CRITICAL_PATHS.contains(&normalized.as_str())
+        || CRITICAL_PATHS.contains(&trimmed)
+        || trimmed.is_empty() && normalized.starts_with('/')
+}
+
+fn push(
+    findings: &mut Vec<ShellFinding>,
+    cmd: &ShellCommand,
+    risk: ShellRisk,
+    score: u8,
+    description: String,
+) {
+    // Root makes a damaging command worse, but never pushes it into
+    // outright blocking on its own
+    let score = if cmd.is_privileged() && risk == ShellRisk::Destructive && score < 9 {
+        score + 1
+    } else {
+        score
+    };
+    findings.push(ShellFinding {
+        risk,
+        score,
+        command: cmd.display(),
+        description,
+    });
+}
+
+fn classify(cmd: &ShellCommand, findings: &mut Vec<ShellFinding>) {
+    use ShellRisk::*;
+    let p = cmd.program.as_str();
+
+    if is_dynamic(p) {
+        push(
+            findings,
+            cmd,
+            Opaque,
+            8,
+            format!("`{}` runs a program named at run time", cmd.display()),
+        );
+    }
+
+    if matches!(p, "sudo" | "doas" | "su" | "pkexec") {
+        push(
+            findings,
+            cmd,
+            Privilege,
+            7,
+            format!("`{p}` opens a root shell"),
+        );
+    } else if cmd.is_privileged() {
+        let wrapper = cmd
+            .via
+            .iter()
+            .find(|w| matches!(w.as_str(), "sudo" | "doas" | "su" | "pkexec"))
+            .cloned()
+            .unwrap_or_default();
+        push(
+            findings,
+            cmd,
+            Privilege,
+            7,
+            format!("runs commands as root via {wrapper}"),
+        );
+    }
+
+    for (op, target) in &cmd.redirects {
+        if !op.contains('>') {
+            continue;
+        }
+        if is_block_device(target) {
+            push(
+                findings,
+                cmd,
+                Destructive,
+                10,
+                format!("writes straight to disk device {target}"),
+            );
+        } else if target.starts_with("/etc/sudoers")
+            || matches!(
+                target.as_str(),
+                "/etc/passwd" | "/etc/shadow" | "/etc/group"
+            )
+        {
+            push(findings, cmd, Privilege, 9, format!("overwrites {target}"));
+        } else if target.starts_with("/dev/tcp/") || target.starts_with("/dev/udp/") {
+            push(
+                findings,
+                cmd,
+                Exfiltration,
+                8,
+                format!("sends output to {target} — may be exfiltrating data"),
+            );
+        } else if target.starts_with("/boot/") || target.starts_with("/etc/") {
+            push(
+                findings,
+                cmd,
+                Destructive,
+                6,
+                format!("overwrites system file {target}"),
+            );
+        }
+    }
+
+    match p {
+        "rm" | "unlink" | "rmdir" => {
+            let recursive = cmd.has_flag('r', "--recursive") || cmd.has_flag('R', "--recursive");
+            let force = cmd.has_flag('f', "--force");
+            let critical: Vec<&str> = cmd.operands().filter(|t| critical_path(t)).collect();
+            if (recursive && !critical.is_empty())
+                || cmd.args.iter().any(|a| a == "--no-preserve-root")
+            {
+                let target = critical.first().copied().unwrap_or("/");
+                push(
+                    findings,
+                    cmd,
+                    Destructive,
+                    10,
+                    format!("`{}` deletes everything under {target}", cmd.display()),
+                );
+            } else if recursive && force {
+                push(
+                    findings,
+                    cmd,
+                    Destructive,
+                    7,
+                    format!("`{}` deletes recursively without asking", cmd.display()),
+                );
+            } else if recursive {
+                push(
+                    findings,
+                    cmd,
+                    Destructive,
+                    6,
+                    format!("`{}` deletes recursively", cmd.display()),
+                );
+            } else {
+                push(
+                    findings,
+                    cmd,
+                    Destructive,
+                    3,
+                    format!("`{}` deletes files", cmd.display()),
+                );
+            }
+        }
+        "dd" => {
+            let target = cmd.args.iter().find_map(|a| a.strip_prefix("of="));
+            match target {
+                Some(t) if is_block_device(t) => push(
+                    findings,
+                    cmd,
+                    Destructive,
+                    10,
+                    format!("`dd` overwrites disk device {t}"),
+                ),
+                Some(t) => push(findings, cmd, Destructive, 4, format!("`dd` writes {t}")),
+                None => {}
+            }
+        }
+        _ if p.starts_with("mkfs") || matches!(p, "mke2fs" | "mkswap" | "wipefs") => {
+            push(
+                findings,
+                cmd,
+                Destructive,
+                10,
+                format!("`{p}` erases a filesystem"),
+            );
+        }
+        "fdisk" | "sfdisk" | "gdisk" | "sgdisk" | "parted" | "cfdisk" => {
+            push(
+                findings,
+                cmd,
+                Destructive,
+                9,
+                format!("`{p}` edits disk partitions"),
+            );
+        }
+        "shred" => push(
+            findings,
+            cmd,
+            Destructive,
+            8,
+            "`shred` irrecoverably destroys files".into(),
+        ),
+        "shutdown" | "reboot" | "halt" | "poweroff" => {
+            push(
+                findings,
+                cmd,
+                Destructive,
+                8,
+                format!("`{p}` takes the machine down"),
+            );
+        }
+        "init" | "telinit" if cmd.operands().any(|a| a == "0" || a == "6") => {
+            push(
+                findings,
+                cmd,
+                Destructive,
+                8,
+                format!("`{}` takes the machine down", cmd.display()),
+            );
+        }
+        "systemctl" => match cmd.subcommand() {
+            Some(s @ ("poweroff" | "reboot" | "halt" | "kexec" | "rescue" | "emergency")) => {
+                push(
+                    findings,
+                    cmd,
+                    Destructive,
+                    8,
+                    format!("`systemctl {s}` takes the machine down"),
+                );
+            }
+            Some(s @ ("stop" | "disable" | "mask" | "kill" | "restart")) => {
+                push(
+                    findings,
+                    cmd,
+                    Destructive,
+                    5,
+                    format!("`systemctl {s}` interrupts a service"),
+                );
+            }
+            _ => {}
+        },
+        "kill" if cmd.operands().any(|a| a == "1" || a == "-1") => {
+            push(
+                findings,
+                cmd,
+                Destructive,
+                8,
+                "`kill` targets init or every process".into(),
+            );
+        }
+        "killall" | "pkill" => push(
+            findings,
+            cmd,
+            Destructive,
+            5,
+            format!("`{p}` kills processes by name"),
+        ),
+        "chmod" => {
+            let recursive = cmd.has_flag('R', "--recursive");
+            let mode = cmd.operands().next().unwrap_or_default();
+            let open = mode.ends_with("777")
+                || mode.ends_with("666")
+                || mode.contains("o+w")
+                || mode.contains("a+w")
+                || mode == "a+rwx"
+                || mode == "ugo+rwx";
+            let setuid =
+                mode.contains("+s") || (mode.len() == 4 && mode.starts_with(['4', '2', '6']));
+            let critical = cmd.operands().skip(1).any(critical_path);
+            if recursive && critical {
+                push(
+                    findings,
+                    cmd,
+                    Privilege,
+                    9,
+                    format!("`{}` changes permissions across the system", cmd.display()),
+                );
+            } else if setuid {
+                push(
+                    findings,
+                    cmd,
+                    Privilege,
+                    7,
+                    format!(
+                        "`{}` makes a program run with its owner's rights",
+                        cmd.display()
+                    ),
+                );
+            } else if open {
+                push(
+                    findings,
+                    cmd,
+                    Privilege,
+                    if recursive { 7 } else { 6 },
+                    format!("`{}` makes files writable by everyone", cmd.display()),
+                );
+            }
+        }
+        "chown" | "chgrp"
+            if cmd.has_flag('R', "--recursive") && cmd.operands().skip(1).any(critical_path) =>
+        {
+            push(
+                findings,
+                cmd,
+                Privilege,
+                9,
+                format!("`{}` changes ownership across the system", cmd.display()),
+            );
+        }
+        "visudo" | "usermod" | "useradd" | "adduser" | "passwd" | "chpasswd" | "groupadd"
+        | "setcap" => {
+            push(
+                findings,
+                cmd,
+                Privilege,
+                7,
+                format!("`{p}` changes users or privileges"),
+            );
+        }
+        "crontab" if cmd.has_flag('r', "--remove") => {
+            push(
+                findings,
+                cmd,
+                Destructive,
+                6,
+                "`crontab -r` deletes every scheduled job".into(),
+            );
+        }
+        "git" => {
+            let sub = cmd.subcommand().unwrap_or_default();
+            let force = cmd.has_flag('f', "--force")
+                || cmd
+                    .args
+                    .iter()
+                    .any(|a| a.starts_with("--force") || a.starts_with('+'));
+            if sub == "push" && force {
+                push(
+                    findings,
+                    cmd,
+                    Destructive,
+                    6,
+                    "`git push --force` rewrites remote history".into(),
+                );
+            } else if sub == "reset" && cmd.args.iter().any(|a| a == "--hard") {
+                push(
+                    findings,
+                    cmd,
+                    Destructive,
+                    5,
+                    "`git reset --hard` discards local changes".into(),
+                );
+            } else if sub == "clean" && cmd.has_flag('f', "--force") {
+                push(
+                    findings,
+                    cmd,
+                    Destructive,
+                    5,
+                    "`git clean -f` deletes untracked files".into(),
+                );
+            }
+        }
+        "mv" if cmd.operands().last() == Some("/dev/null") => {
+            push(
+                findings,
+                cmd,
+                Destructive,
+                7,
+                "`mv` into /dev/null destroys the file".into(),
+            );
+        }
+        "find" if cmd.args.iter().any(|a| a == "-delete") => {
+            let score = if cmd.operands().next().is_some_and(critical_path) {
+                9
+            } else {
+                6
+            };
+            push(
+                findings,
+                cmd,
+                Destructive,
+                score,
+                format!("`{}` deletes every match", cmd.display()),
+            );
+        }
+        _ => {}
+    }
+
+    classify_network(cmd, findings);
+    classify_packages(cmd, findings);
+}
+
+fn classify_network(cmd: &ShellCommand, findings: &mut Vec<ShellFinding>) {
+    use ShellRisk::*;
+    let p = cmd.program.as_str();
+    if !NETWORK_TOOLS.contains(&p) {
+        return;
+    }
+    let host = cmd
+        .operands()
+        .find(|a| a.contains("://"))
+        .or_else(|| cmd.operands().find(|a| a.contains('@') || a.contains(':')))
+        .or_else(|| {
+            cmd.operands()
+                .find(|a| a.contains('.') && !a.starts_with(['.', '/', '~']))
+        })
+        .map(|a| {
+            let a = a.split("://").nth(1).unwrap_or(a);
+            let host = a.split(['/', '?']).next().unwrap_or(a);
+            host.trim_end_matches(':').to_string()
+        });
+    let to = host.map(|h| format!(" to {h}")).unwrap_or_default();
+
+    let uploads = match p {
+        "curl" => {
+            cmd.args.windows(2).any(|w| {
+                let (flag, value) = (w[0].as_str(), w[1].as_str());
+                (matches!(
+                    flag,
+                    "-d" | "--data"
+                        | "--data-binary"
+                        | "--data-raw"
+                        | "--data-urlencode"
+                        | "-F"
+                        | "--form"
+                ) && (value.starts_with('@')
+                    || value.contains("=@")
+                    || value.contains("$(")
+                    || value.contains('`')))
+                    || matches!(flag, "-T" | "--upload-file")
+            }) || cmd
+                .args
+                .iter()
+                .any(|a| a.starts_with("--data") && a.contains("=@") || a.starts_with("-d@"))
+        }
+        "wget" => cmd.args.iter().any(|a| {
+            a.starts_with("--post-file")
+                || a.starts_with("--body-file")
+                || a.starts_with("--post-data")
+        }),
+        "scp" | "rsync" | "sftp" => {
+            // Remote destination: the last operand names a host
+            cmd.operands()
+                .last()
+                .is_some_and(|d| d.contains(':') && !d.starts_with('/'))
+        }
+        "nc" | "ncat" | "netcat" | "socat" | "telnet" => {
+            cmd.redirects.iter().any(|(op, _)| op.starts_with('<'))
+        }
+        "ssh" => cmd.redirects.iter().any(|(op, _)| op.starts_with('<')),
+        _ => false,
+    };
+    if uploads {
+        push(
+            findings,
+            cmd,
+            Exfiltration,
+            8,
+            format!("`{p}` sends local data{to} — may be exfiltrating data"),
+        );
+    } else {
+        push(findings, cmd, Network, 4, format!("`{p}` connects{to}"));
+    }
+}
+
+fn classify_packages(cmd: &ShellCommand, findings: &mut Vec<ShellFinding>) {
+    use ShellRisk::*;
+    let p = cmd.program.as_str();
+    let sub = cmd.subcommand().unwrap_or_default();
+    let global = cmd.has_flag('g', "--global");
+    let (score, what) = match (p, sub) {
+        (
+            "apt" | "apt-get" | "aptitude" | "dnf" | "yum" | "zypper" | "snap" | "port",
+            "install" | "reinstall",
+        ) => (6, "installs system packages"),
+        (
+            "apt" | "apt-get" | "aptitude" | "dnf" | "yum" | "zypper" | "snap",
+            "remove" | "purge" | "erase" | "autoremove",
+        ) => {
+            push(
+                findings,
+                cmd,
+                Destructive,
+                5,
+                format!("`{p} {sub}` removes system packages"),
+            );
+            return;
+        }
+        ("apk", "add") => (6, "installs system packages"),
+        ("pacman", _)
+            if cmd
+                .args
+                .iter()
+                .any(|a| a.starts_with("-S") || a.starts_with("-U")) =>
+        {
+            (6, "installs system packages")
+        }
+        ("rpm" | "dpkg", _) if cmd.has_flag('i', "--install") => (6, "installs system packages"),
+        ("brew", "install" | "reinstall") => (5, "installs packages"),
+        ("pip" | "pip3" | "pipx" | "uv" | "poetry", "install" | "add") => {
+            (4, "installs Python packages")
+        }
+        ("python" | "python3", _)
+            if cmd.args.windows(2).any(|w| w[0] == "-m" && w[1] == "pip")
+                && cmd.args.iter().any(|a| a == "install") =>
+        {
+            (4, "installs Python packages")
+        }
+        ("npm" | "pnpm" | "yarn" | "bun", "install" | "i" | "add" | "global")
+            if global || sub == "global" =>
+        {
+            (5, "installs packages globally")
+        }
+        ("npm" | "pnpm" | "yarn" | "bun", "install" | "i" | "add") => (3, "installs packages"),
+        ("cargo" | "go" | "gem" | "composer", "install" | "get" | "require") => {
+            (4, "installs packages")
+        }
+        _ => return,
+    };
+    push(
+        findings,
+        cmd,
+        PackageInstall,
+        score,
+        format!("`{}` {what}", cmd.display()),
+    );
+}
+
+/// Data flowing into or out of the network within one pipeline, and code
+/// fed to an interpreter on stdin.
+fn classify_pipeline(pipeline: &[ShellCommand], findings: &mut Vec<ShellFinding>) {
+    use ShellRisk::*;
+    // `… | sh`, `bash <<< …`, `python <<EOF`: whatever arrives on stdin
+    // runs, and it can be decoded or generated on the way
+    for (i, cmd) in pipeline.iter().enumerate() {
+        if !INTERPRETERS.contains(&cmd.program.as_str()) || !reads_stdin(cmd) {
+            continue;
+        }
+        let feeder = if i > 0 {
+            // A download piped in is reported as such below
+            let source = pipeline[i - 1].program.as_str();
+            (!NETWORK_TOOLS.contains(&source)).then(|| format!("`{source}` output"))
+        } else if cmd.redirects.iter().any(|(op, _)| op.contains("<<")) {
+            Some("inline text".to_string())
+        } else {
+            None
+        };
+        if let Some(feeder) = feeder {
+            push(
+                findings,
+                cmd,
+                Opaque,
+                9,
+                format!("`{}` runs {feeder} as code", cmd.program),
+            );
+        }
+    }
+    for (i, cmd) in pipeline.iter().enumerate() {
+        if !NETWORK_TOOLS.contains(&cmd.program.as_str()) {
+            continue;
+        }
+        if let Some(runner) = pipeline[i + 1..]
+            .iter()
+            .find(|c| INTERPRETERS.contains(&c.program.as_str()))
+        {
+            push(
+                findings,
+                runner,
+                Network,
+                9,
+                format!(
+                    "pipes a download from `{}` straight into `{}`",
+                    cmd.program, runner.program
+                ),
+            );
+        }
+        if i > 0 {
+            push(
+                findings,
+                cmd,
+                Exfiltration,
+                8,
+                format!(
+                    "pipes `{}` output to `{}` — may be exfiltrating data",
+                    pipeline[i - 1].program,
+                    cmd.program
+                ),
+            );
+        }
+    }
+    // `bash <(curl ...)`: a download run through process substitution
+    for cmd in pipeline {
+        if INTERPRETERS.contains(&cmd.program.as_str())
+            && cmd.args.iter().any(|a| {
+                (a.starts_with("<(") || a.starts_with("$("))
+                    && a.split(|c: char| !c.is_ascii_alphanumeric())
+                        .any(|w| NETWORK_TOOLS.contains(&w))
+            })
+        {
+            push(
+                findings,
+                cmd,
+                Network,
+                9,
+                format!("`{}` runs a script straight from the network", cmd.program),
+            );
+        }
+    }
+}
+
+/// `:(){ :|:& };:` and named variants.
+fn fork_bombs(text: &str, findings: &mut Vec<ShellFinding>) {
+    static FUNCTION: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"([A-Za-z_:.][\w:.]*)\s*\(\s*\)\s*\{([^}]*)\}").unwrap());
+    for caps in FUNCTION.captures_iter(text) {
+        let name = &caps[1];
+        let body: String = caps[2].chars().filter(|c| !c.is_whitespace()).collect();
+        if body.contains(&format!("{name}|{name}")) && body.contains('&') {
+            findings.push(ShellFinding {
+                risk: ShellRisk::Destructive,
+                score: BLOCK_SCORE,
+                command: format!("{name}()"),
+                description: "fork bomb — spawns processes until the machine locks up".into(),
+            });
+        }
+    }
+}
+
+// ── Guardrail ──────────────────────────────────────────────────
+
+/// Why a tool call's shell text is never run, if its score reaches
+/// [`BLOCK_SCORE`].
+pub fn blocked(tool: &Tool, call: &ToolCall) -> Option<String> {
+    let analysis = analyze(&shell_text(&tool.name, &call.arguments)?);
+    (analysis.score >= BLOCK_SCORE)
+        .then(|| format!("blocked shell command: {}", analysis.summary()))
+}
+
+/// Scores every command a shell-running tool call would execute.
+pub struct ShellCommandGuardrail;
+
+impl Guardrail for ShellCommandGuardrail {
+    fn name(&self) -> &str {
+        "shell_command"
+    }
+
+    fn evaluate(&self, tool: &Tool, call: &ToolCall, level: AutonomyLevel) -> GuardrailVerdict {
+        let Some(text) = shell_text(&tool.name, &call.arguments) else {
+            return GuardrailVerdict::Approve;
+        };
+        let analysis = analyze(&text);
+        if analysis.score >= BLOCK_SCORE {
+            GuardrailVerdict::Deny(format!("blocked shell command: {}", analysis.summary()))
+        } else if analysis.score > level.auto_approve_threshold() {
+            GuardrailVerdict::Escalate(format!(
+                "shell command risk {}/10 exceeds threshold {} for {level}: {}",
+                analysis.score,
+                level.auto_approve_threshold(),
+                analysis.summary()
+            ))
+        } else {
+            GuardrailVerdict::Approve
+        }
+    }
+}
@@ -48,7 +48,7 @@ pub struct OutgoingMessage {
 pub struct Attachment {
     pub filename: String,
     pub media_type: String,
-    /// Base64 data or URL.
+    /// Base64 data, a downloaded file's path or a URL.
     pub data: String,
 }
 
@@ -16,7 +16,8 @@ const SLACK_API_BASE: &str = "https://slack.com/api";
 ///
 /// 1. Create a Slack App at <https://api.slack.com/apps>
 /// 2. Enable **Socket Mode** and generate an App-Level Token (`xapp-...`) with `connections:write`
-/// 3. Add Bot Token Scopes: `chat:write`, `channels:history`, `groups:history`, `im:history`
+/// 3. Add Bot Token Scopes: `chat:write`, `channels:history`, `groups:history`, `im:history`,
+///    `files:read` (to download files users share)
 /// 4. Subscribe to events: `message.channels`, `message.groups`, `message.im`, `app_mention`
 /// 5. Install to workspace and copy the Bot Token (`xoxb-...`)
 /// 6. Configure in claw.toml:
@@ -235,7 +236,7 @@ async fn fetch_bot_user_id(client: &reqwest::Client, bot_token: &str) -> Option<
 #[allow(clippy::too_many_arguments)]
 async fn slack_socket_mode_loop(
     app_token: String,
-    _bot_token: String,
+    bot_token: String,
     channel_id: String,
     event_tx: mpsc::Sender<ChannelEvent>,
     mut shutdown_rx: tokio::sync::watch::Receiver<bool>,
@@ -361,6 +362,9 @@ async fn slack_socket_mode_loop(
                                                     .unwrap_or(false)
                                             };
 
+                                            let files = parse_slack_files(event);
+                                            let attachments = download_slack_files(&client, &bot_token, files).await;
+
                                             let incoming = IncomingMessage {
                                                 id: ts.to_string(),
                                                 channel: channel_id.clone(),
@@ -368,7 +372,7 @@ async fn slack_socket_mode_loop(
                                                 sender_name: None, // Slack doesn't include display name in events
                                                 group: if is_dm { None } else { Some(channel_ref.to_string()) },
                                                 text: Some(text.to_string()),
-                                                attachments: parse_slack_files(event),
+                                                attachments,
                                                 is_mention,
                                                 is_reply_to_bot: false,
                                                 metadata: event.clone(),
@@ -481,6 +485,87 @@ async fn request_socket_mode_url(client: &reqwest::Client, app_token: &str) -> O
     data["url"].as_str().map(|s| s.to_string())
 }
 
+/// Download attached files with the bot token, which their `url_private`
+/// needs, into `~/.claw/downloads/slack/`. A file that can't be downloaded
+/// keeps its URL.
+async fn download_slack_files(
+    client: &reqwest::Client,
+    bot_token: &str,
+    attachments: Vec<Attachment>,
+) -> Vec<Attachment> {
+    let mut result = Vec::with_capacity(attachments.len());
+    for mut att in attachments {
+        match download_slack_file(client, bot_token, &att).await {
+            Ok(path) => att.data = path.to_string_lossy().to_string(),
+            Err(e) => warn!(error = %e, file = %att.filename, "failed to download Slack file"),
+        }
+        result.push(att);
+    }
+    result
+}
+
+async fn download_slack_file(
+    client: &reqwest::Client,
+    bot_token: &str,
+    att: &Attachment,
+) -> claw_core::Result<std::path::PathBuf> {
+    let err = |reason: String| claw_core::ClawError::Channel {
+        channel: "slack".into(),
+        reason,
+    };
+    let resp = client
+        .get(&att.data)
+        .header("Authorization", format!("Bearer {bot_token}"))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| err(format!("file download failed: {e}")))?;
+    // Without `files:read` Slack answers with its sign-in page
+    let is_html = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/html"));
+    if is_html && !att.media_type.starts_with("text/html") {
+        return Err(err(
+            "got a sign-in page — does the bot token have files:read?".into(),
+        ));
+    }
+    let bytes = resp
+        .bytes()
+        .await
+        .map_err(|e| err(format!("file download bytes failed: {e}")))?;
+
+    let dir = slack_downloads_dir();
+    tokio::fs::create_dir_all(&dir).await.map_err(|e| {
+        err(format!(
+            "failed to create downloads dir {}: {e}",
+            dir.display()
+        ))
+    })?;
+    // url_private is .../files-pri/<team>-<file id>/<name>
+    let file_ref = att.data.rsplit('/').nth(1).unwrap_or("file");
+    let name = std::path::Path::new(&att.filename)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".into());
+    let out_path = dir.join(format!("{file_ref}-{name}"));
+    tokio::fs::write(&out_path, &bytes)
+        .await
+        .map_err(|e| err(format!("failed to write {}: {e}", out_path.display())))?;
+
+    debug!(path = %out_path.display(), "downloaded Slack file");
+    Ok(out_path)
+}
+
+fn slack_downloads_dir() -> std::path::PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| std::path::PathBuf::from("/tmp"))
+        .join(".claw")
+        .join("downloads")
+        .join("slack")
+}
+
 /// Extract file attachments from a Slack message event.
 fn parse_slack_files(event: &Value) -> Vec<Attachment> {
     let mut result = Vec::new();
@@ -491,7 +576,7 @@ fn parse_slack_files(event: &Value) -> Vec<Attachment> {
                 .as_str()
                 .unwrap_or("application/octet-stream")
                 .to_string();
-            // url_private requires auth, but we store it for later download
+            // url_private requires auth; download_slack_files fetches it
             let url = file["url_private"]
                 .as_str()
                 .or_else(|| file["url_private_download"].as_str())
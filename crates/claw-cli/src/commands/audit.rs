@@ -0,0 +1,84 @@
+use claw_memory::{AuditLog, AuditReport};
+
+use super::AuditAction;
+
+/// Reads the database directly (read-only), so it works whether or not the
+/// agent is running — and without trusting it.
+pub(super) fn cmd_audit(
+    config: claw_config::ClawConfig,
+    action: AuditAction,
+) -> claw_core::Result<()> {
+    let db_path = config.memory.resolved_db_path();
+    let log = AuditLog::open(&db_path)?;
+
+    match action {
+        AuditAction::Verify { segment, json } => {
+            let (report, what) = match segment {
+                Some(path) => {
+                    let file = std::fs::File::open(&path)?;
+                    let report = claw_memory::audit::verify_segment(
+                        &mut std::io::BufReader::new(file),
+                        log.key(),
+                    )?;
+                    (report, format!("segment {}", path.display()))
+                }
+                None => (log.verify()?, format!("audit log {}", db_path.display())),
+            };
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            } else {
+                print_report(&report, &what);
+            }
+            if !report.is_intact() {
+                return Err(claw_core::ClawError::Agent(format!(
+                    "{what} failed verification ({} problems)",
+                    report.issues.len()
+                )));
+            }
+        }
+        AuditAction::Export { from, to, output } => match output {
+            Some(path) => {
+                let mut file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+                let header = log.export_segment(from, to, &mut file)?;
+                println!(
+                    "🔏 Exported audit entries #{}–#{} ({}) to {}",
+                    header.first_id,
+                    header.last_id,
+                    header.count,
+                    path.display()
+                );
+                println!("   signed with key {}", header.key_id);
+            }
+            None => {
+                log.export_segment(from, to, &mut std::io::stdout().lock())?;
+            }
+        },
+    }
+    Ok(())
+}
+
+fn print_report(report: &AuditReport, what: &str) {
+    let range = match (report.first_id, report.last_id) {
+        (Some(first), Some(last)) => format!(" #{first}–#{last}"),
+        _ => String::new(),
+    };
+    println!("🔏 Verifying {what}");
+    println!(
+        "   {} entries{range}, key {}",
+        report.entries, report.key_id
+    );
+    if report.legacy > 0 {
+        println!(
+            "   {} entries predate the hash chain (pinned when it started)",
+            report.legacy
+        );
+    }
+    if report.is_intact() {
+        println!("\n   ✅ Chain intact — no edits, deletions or reordering");
+        return;
+    }
+    println!("\n   ❌ {} problems:", report.issues.len());
+    for issue in &report.issues {
+        println!("      {issue}");
+    }
+}
@@ -1,5 +1,3 @@
-use std::sync::Arc;
-
 use tracing::error;
 
 use claw_runtime::AgentRuntime;
@@ -17,17 +15,7 @@ pub(super) async fn cmd_chat(
     let mut runtime = AgentRuntime::new(config.clone())?;
 
     // Register LLM providers — config file keys take priority, env vars are fallback
-    let mut providers_registered = 0u32;
-    if let Some(ref key) = config.services.anthropic_api_key {
-        runtime.add_provider(Arc::new(claw_llm::anthropic::AnthropicProvider::new(
-            key.clone(),
-        )));
-        providers_registered += 1;
-    }
-    if let Some(ref key) = config.services.openai_api_key {
-        runtime.add_provider(Arc::new(claw_llm::openai::OpenAiProvider::new(key.clone())));
-        providers_registered += 1;
-    }
+    let providers_registered = super::start::register_providers(&mut runtime, &config);
 
     if providers_registered == 0 {
         let model = &config.agent.model;
@@ -40,6 +28,9 @@ pub(super) async fn cmd_chat(
             } else if model.starts_with("openai/") {
                 eprintln!("   Add to [services] in claw.toml:  openai_api_key = \"sk-...\"");
                 eprintln!("   Or set env var: export OPENAI_API_KEY=sk-...");
+            } else if model.starts_with("google/") {
+                eprintln!("   Add to [services] in claw.toml:  gemini_api_key = \"AIza...\"");
+                eprintln!("   Or set env var: export GEMINI_API_KEY=AIza...");
             } else {
                 eprintln!(
                     "   Add API keys to [services] in claw.toml or set ANTHROPIC_API_KEY / OPENAI_API_KEY."
@@ -196,9 +187,11 @@ pub(super) async fn cmd_chat(
                             input_tokens,
                             output_tokens,
                             cost_usd,
+                            model,
                         } => {
+                            let route = model.map(|m| format!("{m} · ")).unwrap_or_default();
                             eprintln!(
-                                "\n\x1b[90m   [{input_tokens} in / {output_tokens} out, ${cost_usd:.4}]\x1b[0m"
+                                "\n\x1b[90m   [{route}{input_tokens} in / {output_tokens} out, ${cost_usd:.4}]\x1b[0m"
                             );
                         }
                         StreamEvent::Error { message } => {
@@ -0,0 +1,115 @@
+use super::KbAction;
+
+pub(super) async fn cmd_kb(
+    config: claw_config::ClawConfig,
+    action: KbAction,
+) -> claw_core::Result<()> {
+    let listen = &config.server.listen;
+    let client = reqwest::Client::builder()
+        .tcp_keepalive(None)
+        .build()
+        .unwrap_or_default();
+    let unreachable = |e: reqwest::Error| {
+        claw_core::ClawError::Agent(format!(
+            "Cannot reach agent at {listen} — is it running? ({e})"
+        ))
+    };
+
+    let request = match action {
+        KbAction::Add { ref path } => {
+            // Resolve here: the agent may run with a different working directory
+            let path = std::fs::canonicalize(path)
+                .map_err(|e| claw_core::ClawError::Agent(format!("{}: {e}", path.display())))?;
+            println!("📚 Ingesting {} ...", path.display());
+            client
+                .post(format!("http://{listen}/api/v1/kb/ingest"))
+                .json(&serde_json::json!({ "path": path }))
+        }
+        KbAction::List { .. } => client.get(format!("http://{listen}/api/v1/kb/sources")),
+        KbAction::Search { ref query, .. } => client
+            .get(format!("http://{listen}/api/v1/kb/search"))
+            .query(&[("q", query.as_str())]),
+    };
+    let request = match config.server.api_key {
+        Some(ref key) => request.header("Authorization", format!("Bearer {key}")),
+        None => request,
+    };
+    let resp = request.send().await.map_err(unreachable)?;
+    let status = resp.status();
+    let data: serde_json::Value = resp.json().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(claw_core::ClawError::Agent(match data["error"].as_str() {
+            Some(e) => e.to_string(),
+            None => format!("Server returned {status}"),
+        }));
+    }
+
+    match action {
+        KbAction::Add { .. } => {
+            println!(
+                "   {} file(s) scanned: {} ingested ({} passages), {} unchanged, {} removed",
+                data["files"], data["ingested"], data["chunks"], data["unchanged"], data["removed"]
+            );
+            for e in data["errors"].as_array().into_iter().flatten() {
+                println!("   ⚠️  {}", e.as_str().unwrap_or_default());
+            }
+        }
+        KbAction::List { json } => {
+            if json {
+                println!("{}", serde_json::to_string_pretty(&data).unwrap());
+                return Ok(());
+            }
+            let sources = data["sources"].as_array().cloned().unwrap_or_default();
+            if sources.is_empty() {
+                println!("The knowledge base is empty. Add documents with `claw kb add <path>`.");
+                return Ok(());
+            }
+            println!("📚 Knowledge base ({} files)\n", sources.len());
+            for s in &sources {
+                println!(
+                    "   {}  ({} passages, ingested {})",
+                    s["path"].as_str().unwrap_or_default(),
+                    s["chunks"],
+                    s["ingested_at"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .get(..16)
+                        .unwrap_or("")
+                );
+            }
+        }
+        KbAction::Search { query, json } => {
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&data["results"]).unwrap()
+                );
+                return Ok(());
+            }
+            let results = data["results"].as_array().cloned().unwrap_or_default();
+            if results.is_empty() {
+                println!("No passages match \"{query}\".");
+                return Ok(());
+            }
+            println!("📚 Knowledge base search: \"{query}\"\n");
+            for (i, r) in results.iter().enumerate() {
+                let text = r["text"].as_str().unwrap_or_default().replace('\n', " ");
+                let preview: String = text.chars().take(240).collect();
+                println!(
+                    "   [{}] {}",
+                    i + 1,
+                    r["citation"].as_str().unwrap_or_default()
+                );
+                println!(
+                    "       {preview}{}\n",
+                    if text.len() > preview.len() {
+                        "…"
+                    } else {
+                        ""
+                    }
+                );
+            }
+        }
+    }
+    Ok(())
+}
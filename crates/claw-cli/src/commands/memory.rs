@@ -0,0 +1,170 @@
+use super::MemoryAction;
+
+pub(super) async fn cmd_memory(
+    config: claw_config::ClawConfig,
+    action: MemoryAction,
+) -> claw_core::Result<()> {
+    let listen = &config.server.listen;
+    let client = reqwest::Client::builder()
+        .tcp_keepalive(None)
+        .build()
+        .unwrap_or_default();
+
+    let authorize = |req: reqwest::RequestBuilder| match config.server.api_key {
+        Some(ref key) => req.header("Authorization", format!("Bearer {key}")),
+        None => req,
+    };
+    let unreachable = |e: reqwest::Error| {
+        claw_core::ClawError::Agent(format!(
+            "Cannot reach agent at {listen} — is it running? ({e})"
+        ))
+    };
+
+    match action {
+        MemoryAction::Search { query, kind, json } => {
+            let url = format!("http://{listen}/api/v1/memory/search");
+            let req = authorize(client.get(&url).query(&[("q", query.as_str())]));
+            let resp = req.send().await.map_err(unreachable)?;
+
+            if !resp.status().is_success() {
+                return Err(claw_core::ClawError::Agent(format!(
+                    "Server returned {}",
+                    resp.status()
+                )));
+            }
+
+            let data: serde_json::Value = resp
+                .json()
+                .await
+                .map_err(|e| claw_core::ClawError::Agent(e.to_string()))?;
+
+            let results: Vec<&serde_json::Value> = data["results"]
+                .as_array()
+                .map(|r| {
+                    r.iter()
+                        .filter(|v| kind.as_deref().is_none_or(|k| v["type"] == k))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&results).unwrap());
+                return Ok(());
+            }
+            if results.is_empty() {
+                println!("No memories match \"{query}\".");
+                return Ok(());
+            }
+
+            println!("🧠 Memory search: \"{query}\"\n");
+            for r in results {
+                let text = |field: &str| r[field].as_str().unwrap_or("").to_string();
+                match r["type"].as_str() {
+                    Some("fact") => println!(
+                        "   📌 [{}] {}: {}",
+                        text("category"),
+                        text("key"),
+                        text("value")
+                    ),
+                    Some("episode") => println!(
+                        "   📖 {} {}",
+                        text("created_at").get(..10).unwrap_or(""),
+                        text("summary")
+                    ),
+                    Some("message") => println!(
+                        "   💬 {} {} ({}): {}",
+                        text("timestamp").get(..10).unwrap_or(""),
+                        text("role"),
+                        text("session_id").get(..8).unwrap_or(""),
+                        text("snippet").replace('\n', " ")
+                    ),
+                    _ => {}
+                }
+            }
+        }
+        MemoryAction::Export { output } => {
+            let url = format!("http://{listen}/api/v1/memory/export");
+            let resp = authorize(client.get(&url))
+                .send()
+                .await
+                .map_err(unreachable)?;
+            if !resp.status().is_success() {
+                return Err(claw_core::ClawError::Agent(format!(
+                    "Server returned {}",
+                    resp.status()
+                )));
+            }
+            let archive = resp
+                .bytes()
+                .await
+                .map_err(|e| claw_core::ClawError::Agent(e.to_string()))?;
+
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, &archive)?;
+                    // The manifest line carries per-table row counts
+                    let manifest: serde_json::Value = archive
+                        .split(|b| *b == b'\n')
+                        .next()
+                        .and_then(|line| serde_json::from_slice(line).ok())
+                        .unwrap_or_default();
+                    println!("💾 Memory exported to {}", path.display());
+                    for table in manifest["tables"].as_array().into_iter().flatten() {
+                        println!(
+                            "   {:<18} {}",
+                            table["name"].as_str().unwrap_or_default(),
+                            table["rows"]
+                        );
+                    }
+                }
+                None => {
+                    use std::io::Write;
+                    std::io::stdout().write_all(&archive)?;
+                }
+            }
+        }
+        MemoryAction::Import { file, strategy } => {
+            let archive = std::fs::read(&file)?;
+            let url = format!("http://{listen}/api/v1/memory/import");
+            let resp = authorize(
+                client
+                    .post(&url)
+                    .query(&[("strategy", strategy.as_str())])
+                    .header("Content-Type", "application/x-ndjson")
+                    .body(archive),
+            )
+            .send()
+            .await
+            .map_err(unreachable)?;
+            let status = resp.status();
+            let data: serde_json::Value = resp.json().await.unwrap_or_default();
+            if !status.is_success() {
+                return Err(claw_core::ClawError::Agent(match data["error"].as_str() {
+                    Some(e) => e.to_string(),
+                    None => format!("Server returned {status}"),
+                }));
+            }
+
+            println!("📥 Imported {} (strategy: {strategy})\n", file.display());
+            println!(
+                "   {:<18} {:>8} {:>8} {:>8}",
+                "table", "inserted", "updated", "skipped"
+            );
+            if let Some(tables) = data["tables"].as_object() {
+                for (name, t) in tables {
+                    println!(
+                        "   {name:<18} {:>8} {:>8} {:>8}",
+                        t["inserted"], t["updated"], t["skipped"]
+                    );
+                }
+            }
+            if data["unknown_rows"].as_u64().unwrap_or(0) > 0 {
+                println!(
+                    "\n   ⚠️  {} rows for unknown tables were ignored",
+                    data["unknown_rows"]
+                );
+            }
+        }
+    }
+    Ok(())
+}
@@ -4,10 +4,15 @@ use std::path::PathBuf;
 
 use claw_config::ConfigLoader;
 
+mod audit;
 mod channels;
 mod chat;
+mod kb;
+mod memory;
 mod mesh;
+mod models;
 mod plugins;
+mod policy;
 mod setup;
 mod skills;
 mod start;
@@ -62,11 +67,36 @@ enum Commands {
         #[arg(long)]
         json: bool,
     },
+    /// Inspect the model catalog (context windows, pricing, capabilities)
+    Models {
+        #[command(subcommand)]
+        action: ModelsAction,
+    },
     /// Manage plugins
     Plugin {
         #[command(subcommand)]
         action: PluginAction,
     },
+    /// Search, export and import long-term memory
+    Memory {
+        #[command(subcommand)]
+        action: MemoryAction,
+    },
+    /// Manage the document knowledge base
+    Kb {
+        #[command(subcommand)]
+        action: KbAction,
+    },
+    /// Verify the audit log's hash chain and export signed segments
+    Audit {
+        #[command(subcommand)]
+        action: AuditAction,
+    },
+    /// Check tool calls against the guardrail policy
+    Policy {
+        #[command(subcommand)]
+        action: PolicyAction,
+    },
     /// Show recent audit log entries
     Logs {
         /// Number of entries to show (default 50)
@@ -176,6 +206,123 @@ enum PluginAction {
     },
 }
 
+#[derive(Subcommand)]
+enum MemoryAction {
+    /// Full-text + semantic search across facts, episodes and session messages
+    Search {
+        /// Search query
+        query: String,
+        /// Only show one result type: fact, episode, message
+        #[arg(short = 't', long = "type")]
+        kind: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Export all memory to a JSONL archive (for backups and migration)
+    Export {
+        /// Archive file to write (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Merge a JSONL archive into the running agent's memory
+    Import {
+        /// Archive file written by `claw memory export`
+        file: PathBuf,
+        /// What to do with rows that already exist
+        #[arg(short, long, default_value = "skip", value_parser = ["skip", "overwrite", "newest-wins"])]
+        strategy: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditAction {
+    /// Check the audit log (or an exported segment) for edited, deleted or
+    /// reordered entries
+    Verify {
+        /// Verify an exported segment instead of the database
+        #[arg(short, long)]
+        segment: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Export audit entries as a signed JSONL segment for off-box archival
+    Export {
+        /// First entry id to include (default: the start of the chain)
+        #[arg(long)]
+        from: Option<i64>,
+        /// Last entry id to include (default: the newest)
+        #[arg(long)]
+        to: Option<i64>,
+        /// Segment file to write (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum PolicyAction {
+    /// Show the verdict a tool call would get, and which rule gave it
+    Test {
+        /// Tool name, e.g. shell_exec
+        tool: String,
+        /// Arguments as JSON, a JSON file, or - for stdin (default: {})
+        args: Option<String>,
+        /// Policy file to test instead of autonomy.policy_file
+        #[arg(short, long)]
+        policy: Option<PathBuf>,
+        /// Channel the call comes from, e.g. telegram
+        #[arg(long)]
+        channel: Option<String>,
+        /// Sender id on that channel
+        #[arg(long)]
+        sender: Option<String>,
+        /// Autonomy level 0-4 (default: autonomy.level)
+        #[arg(long)]
+        level: Option<u8>,
+        /// When the call is made, e.g. "2026-03-02 22:30" (default: now)
+        #[arg(long)]
+        at: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum KbAction {
+    /// Ingest a file or directory (re-run to pick up changes)
+    Add {
+        /// File or directory path
+        path: PathBuf,
+    },
+    /// List ingested files
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Search ingested documents
+    Search {
+        /// Search query
+        query: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ModelsAction {
+    /// List known models
+    List {
+        /// Only show models from this provider (e.g. anthropic, openai, google)
+        #[arg(short, long)]
+        provider: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
 #[derive(Subcommand)]
 enum SkillAction {
     /// List available skills
@@ -330,7 +477,12 @@ impl Cli {
             Commands::Status => Self::cmd_status(config).await,
             Commands::Version => Self::cmd_version(),
             Commands::Config { json } => Self::cmd_config(config, json),
+            Commands::Models { action } => models::cmd_models(config, action),
             Commands::Plugin { action } => plugins::cmd_plugin(config, action).await,
+            Commands::Memory { action } => memory::cmd_memory(config, action).await,
+            Commands::Kb { action } => kb::cmd_kb(config, action).await,
+            Commands::Audit { action } => audit::cmd_audit(config, action),
+            Commands::Policy { action } => policy::cmd_policy(config, action),
             Commands::Logs {
                 limit,
                 event_type,
@@ -649,17 +801,114 @@ impl Cli {
             extra_ok += 1;
         }
 
+        // Check the guardrail policy file parses
+        if let Some(path) = config.autonomy.resolved_policy_file() {
+            match claw_autonomy::Policy::load(&path) {
+                Ok(policy) => {
+                    println!(
+                        "  ✅ autonomy.policy_file: {} rules in {}",
+                        policy.rules().len(),
+                        path.display()
+                    );
+                    extra_ok += 1;
+                }
+                Err(e) => {
+                    println!("  ❌ autonomy.policy_file: {e} — the agent won't start");
+                    warn_count += 1;
+                }
+            }
+        }
+
+        // Check file tools can't read secrets
+        if config.autonomy.forbidden_paths.is_empty() {
+            println!(
+                "  ⚠️  autonomy.forbidden_paths: empty — file tools can read ~/.ssh and this config's API keys"
+            );
+            warn_count += 1;
+        } else {
+            extra_ok += 1;
+        }
+
+        // Check the process sandbox works on this kernel
+        if config.runtime.sandbox != claw_config::schema::SandboxMode::None {
+            match claw_runtime::sandbox::Sandbox::from_config(&config.runtime) {
+                Ok(_) => {
+                    println!(
+                        "  ✅ runtime.sandbox: {} — commands run in {}",
+                        config.runtime.sandbox,
+                        config.runtime.resolved_sandbox_dir().display()
+                    );
+                    extra_ok += 1;
+                }
+                Err(e) => {
+                    println!("  ❌ {e} — the agent won't start");
+                    warn_count += 1;
+                }
+            }
+        }
+
         // Check if API key is set (for any bind address)
         if config.server.api_key.is_some() {
             extra_ok += 1;
         }
 
+        // Check the memory database schema
+        let db_path = config.memory.resolved_db_path();
+        let latest = claw_memory::migrations::latest_version();
+        match claw_memory::migrations::inspect(&db_path) {
+            Ok(None) => {
+                println!(
+                    "  💡 memory: no database at {} yet — it is created on first start (schema v{latest})",
+                    db_path.display()
+                );
+                info_count += 1;
+            }
+            Ok(Some(v)) if v == latest => {
+                println!("  ✅ memory: schema v{v} (current) — {}", db_path.display());
+                extra_ok += 1;
+                match claw_memory::AuditLog::open(&db_path).and_then(|log| log.verify()) {
+                    Ok(report) if report.is_intact() => {
+                        println!("  ✅ audit: hash chain intact ({} entries)", report.entries);
+                        extra_ok += 1;
+                    }
+                    Ok(report) => {
+                        println!(
+                            "  ⚠️  audit: hash chain broken ({} problems) — run `claw audit verify`",
+                            report.issues.len()
+                        );
+                        warn_count += 1;
+                    }
+                    Err(e) => {
+                        println!("  ⚠️  audit: cannot verify the log: {e}");
+                        warn_count += 1;
+                    }
+                }
+            }
+            Ok(Some(v)) if v < latest => {
+                println!(
+                    "  💡 memory: schema v{v} will be migrated to v{latest} on next start (a backup is taken first) — {}",
+                    db_path.display()
+                );
+                info_count += 1;
+            }
+            Ok(Some(v)) => {
+                println!(
+                    "  ⚠️  memory: schema v{v} is newer than this claw supports (v{latest}) — upgrade claw"
+                );
+                warn_count += 1;
+            }
+            Err(e) => {
+                println!("  ⚠️  memory: cannot read {}: {e}", db_path.display());
+                warn_count += 1;
+            }
+        }
+
         println!();
         let ok_total = extra_ok
             + (if warnings.is_empty() {
                 5
             } else {
-                5 - warn_count - info_count
+                5usize.saturating_sub(warn_count + info_count)
             });
         println!(
             "  ✅ {ok_total} checks passed, ⚠️  {warn_count} warnings, 💡 {info_count} suggestions"
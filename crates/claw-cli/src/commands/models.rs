@@ -0,0 +1,97 @@
+use super::ModelsAction;
+
+pub(super) fn cmd_models(
+    config: claw_config::ClawConfig,
+    action: ModelsAction,
+) -> claw_core::Result<()> {
+    match action {
+        ModelsAction::List { provider, json } => {
+            let catalog = claw_config::catalog();
+            let models: Vec<&claw_config::ModelInfo> = catalog
+                .iter()
+                .filter(|m| provider.as_deref().is_none_or(|p| m.provider == p))
+                .collect();
+
+            if json {
+                let list: Vec<serde_json::Value> = models
+                    .iter()
+                    .map(|m| {
+                        let mut value = serde_json::to_value(m).unwrap_or_default();
+                        value["id"] = serde_json::json!(m.id);
+                        value
+                    })
+                    .collect();
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&list)
+                        .map_err(|e| claw_core::ClawError::Agent(e.to_string()))?
+                );
+                return Ok(());
+            }
+
+            if models.is_empty() {
+                println!("No models found.");
+            } else {
+                println!(
+                    "\x1b[1m{:<24} {:<10} {:>9} {:>8} {:>9} {:>9}  FEATURES\x1b[0m",
+                    "MODEL", "PROVIDER", "CONTEXT", "OUTPUT", "$IN/M", "$OUT/M"
+                );
+                for m in &models {
+                    let mut features = Vec::new();
+                    if m.vision {
+                        features.push("vision");
+                    }
+                    if m.tools {
+                        features.push("tools");
+                    }
+                    if m.thinking {
+                        features.push("thinking");
+                    }
+                    println!(
+                        "{:<24} {:<10} {:>9} {:>8} {:>9.3} {:>9.3}  {}",
+                        m.id,
+                        m.provider,
+                        format_tokens(m.context_window),
+                        format_tokens(m.max_output as usize),
+                        m.input_cost_per_m,
+                        m.output_cost_per_m,
+                        features.join(", ")
+                    );
+                }
+            }
+
+            // Models served by [providers.*] endpoints
+            let mut custom: Vec<_> = config
+                .providers
+                .iter()
+                .filter(|(name, p)| {
+                    !p.models.is_empty() && provider.as_deref().is_none_or(|f| f == name.as_str())
+                })
+                .collect();
+            custom.sort_by(|a, b| a.0.cmp(b.0));
+            for (name, p) in custom {
+                println!("\n\x1b[1m[providers.{name}]\x1b[0m ({})", p.base_url);
+                for model in &p.models {
+                    let window = config.context_window_for(&format!("{name}/{model}"));
+                    println!("  {model:<22} context {}", format_tokens(window));
+                }
+            }
+
+            println!(
+                "\nOverride or add models in {}",
+                claw_config::ModelCatalog::overrides_path().display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Render a token count compactly, e.g. `200k` or `1.0M`.
+fn format_tokens(n: usize) -> String {
+    match n {
+        0 => "—".into(),
+        n if n >= 1_000_000 => format!("{:.1}M", n as f64 / 1_000_000.0),
+        n if n >= 1_000 => format!("{}k", n / 1_000),
+        n => n.to_string(),
+    }
+}
@@ -0,0 +1,181 @@
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use claw_autonomy::{AutonomyLevel, GuardrailContext, GuardrailVerdict};
+use claw_core::{ClawError, Tool, ToolCall};
+use std::io::Read;
+
+use super::PolicyAction;
+
+pub(super) fn cmd_policy(
+    config: claw_config::ClawConfig,
+    action: PolicyAction,
+) -> claw_core::Result<()> {
+    match action {
+        PolicyAction::Test {
+            tool,
+            args,
+            policy,
+            channel,
+            sender,
+            level,
+            at,
+        } => {
+            let mut autonomy = config.autonomy.clone();
+            if let Some(path) = policy {
+                // Relative to here, not ~/.claw
+                autonomy.policy_file = Some(std::path::absolute(path)?);
+            }
+            let engine = claw_runtime::agent::guardrails(&autonomy)?;
+
+            let arguments = read_args(args.as_deref())?;
+            let call = ToolCall {
+                id: "policy-test".into(),
+                tool_name: tool.clone(),
+                arguments,
+            };
+            // Unknown tools get the same defaults the agent loop gives them
+            let tool_def = claw_runtime::tools::BuiltinTools::new()
+                .tools()
+                .into_iter()
+                .find(|t| t.name == tool)
+                .unwrap_or_else(|| Tool {
+                    name: tool.clone(),
+                    description: String::new(),
+                    parameters: serde_json::Value::Null,
+                    capabilities: vec![],
+                    is_mutating: true,
+                    risk_level: 5,
+                    provider: None,
+                });
+
+            let mut ctx = GuardrailContext::new(AutonomyLevel::from_u8(
+                level.unwrap_or(config.autonomy.level),
+            ));
+            ctx.channel = channel;
+            ctx.sender = sender;
+            if let Some(at) = at {
+                ctx.now = parse_time(&at)?;
+            }
+
+            println!("🛡️  {tool} {}", call.arguments);
+            let mut origin = vec![ctx.level.to_string()];
+            if let Some(c) = &ctx.channel {
+                origin.push(format!("channel {c}"));
+            }
+            if let Some(s) = &ctx.sender {
+                origin.push(format!("sender {s}"));
+            }
+            origin.push(format!(
+                "at {}",
+                ctx.now
+                    .with_timezone(&Local)
+                    .format("%a %Y-%m-%d %H:%M %:z")
+            ));
+            println!("   {}", origin.join(", "));
+            println!("   risk level {}", tool_def.risk_level);
+
+            if let Some(text) = claw_autonomy::shell::shell_text(&tool, &call.arguments) {
+                let analysis = claw_autonomy::shell::analyze(&text);
+                println!("\n   Shell analysis: risk {}/10", analysis.score);
+                for pipeline in &analysis.pipelines {
+                    let stages: Vec<String> = pipeline
+                        .iter()
+                        .map(|c| {
+                            let via: String = c.via.iter().map(|w| format!("{w} → ")).collect();
+                            format!("{via}{} {}", c.program, c.args.join(" "))
+                                .trim_end()
+                                .to_string()
+                        })
+                        .collect();
+                    println!("   $  {}", stages.join(" | "));
+                }
+                for finding in &analysis.findings {
+                    println!(
+                        "   {:>2} {} ({})",
+                        finding.score, finding.description, finding.risk
+                    );
+                }
+            }
+
+            match engine.policy() {
+                Some(policy) => {
+                    let rules = policy.policy();
+                    let path = policy
+                        .path()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_default();
+                    println!("\n   Policy {path} ({} rules)", rules.rules().len());
+                    let traces = rules.explain(&tool_def, &call, &ctx);
+                    for trace in &traces {
+                        match &trace.mismatch {
+                            Some(why) => println!("   ·  {} — {why}", trace.rule),
+                            None => println!("   ✓  {} → {}", trace.rule, trace.action),
+                        }
+                    }
+                    if traces.last().is_none_or(|t| t.mismatch.is_some()) {
+                        println!("   no rule matched");
+                    }
+                }
+                None => println!("\n   No policy file (autonomy.policy_file)"),
+            }
+
+            // The filesystem scope is checked before any guardrail runs
+            let scope = claw_runtime::agent::path_scope(&config);
+            if let Err(reason) = scope.check_call(&tool, &call.arguments) {
+                println!("\n   ⛔ Deny (by filesystem scope): {reason}");
+                return Ok(());
+            }
+
+            let decision = engine.decide(&tool_def, &call, &ctx);
+            let by = decision
+                .decided_by
+                .map(|by| format!(" (by {by})"))
+                .unwrap_or_default();
+            match decision.verdict {
+                GuardrailVerdict::Approve => println!("\n   ✅ Approve{by}"),
+                GuardrailVerdict::Deny(reason) => println!("\n   ⛔ Deny{by}: {reason}"),
+                GuardrailVerdict::Escalate(reason) => {
+                    println!("\n   ⚠️  Needs approval{by}: {reason}")
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Arguments given inline as JSON, as a file, or `-` for stdin.
+fn read_args(args: Option<&str>) -> claw_core::Result<serde_json::Value> {
+    let raw = match args {
+        None => return Ok(serde_json::json!({})),
+        Some("-") => {
+            let mut raw = String::new();
+            std::io::stdin().read_to_string(&mut raw)?;
+            raw
+        }
+        Some(arg) if arg.trim_start().starts_with('{') => arg.to_string(),
+        Some(path) => std::fs::read_to_string(path)?,
+    };
+    let value: serde_json::Value = serde_json::from_str(&raw)?;
+    if !value.is_object() {
+        return Err(ClawError::Agent(
+            "tool arguments must be a JSON object".into(),
+        ));
+    }
+    Ok(value)
+}
+
+/// RFC 3339, or a local `YYYY-MM-DD HH:MM`.
+fn parse_time(at: &str) -> claw_core::Result<DateTime<Utc>> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(at) {
+        return Ok(t.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S"]
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(at, f).ok())
+        .and_then(|t| Local.from_local_datetime(&t).earliest())
+        .map(|t| t.with_timezone(&Utc))
+        .ok_or_else(|| {
+            ClawError::Agent(format!(
+                "can't read time '{at}' (use e.g. \"2026-03-02 22:30\" or RFC 3339)"
+            ))
+        })
+}
@@ -39,6 +39,7 @@ daily_budget_usd = 10.0
 [memory]
 # db_path = "memory.db"
 # vector_search = true
+# embedding_provider = "builtin"   # builtin (offline), openai, ollama, none
 
 [server]
 listen = "127.0.0.1:3700"
@@ -48,6 +49,7 @@ listen = "127.0.0.1:3700"
 [services]
 # anthropic_api_key = "sk-ant-..."   # or env: ANTHROPIC_API_KEY
 # openai_api_key = "sk-..."          # or env: OPENAI_API_KEY
+# gemini_api_key = "AIza..."         # or env: GEMINI_API_KEY
 # brave_api_key = "..."              # or env: BRAVE_API_KEY
 # hub_url = "http://your-hub-server:3800"     # Skills Hub — run 'claw hub serve' to host
 
@@ -592,7 +594,10 @@ pub(super) fn cmd_setup(
     // Memory section
     config.push_str("[memory]\n");
     config.push_str("# db_path = \"memory.db\"\n");
-    config.push_str("# vector_search = true\n\n");
+    config.push_str("# vector_search = true\n");
+    config.push_str(
+        "# embedding_provider = \"builtin\"   # builtin (offline), openai, ollama, none\n\n",
+    );
 
     // Server section
     config.push_str("[server]\n");
@@ -4,6 +4,73 @@ use tracing::error;
 use claw_config::ConfigLoader;
 use claw_runtime::AgentRuntime;
 
+/// Register the built-in LLM providers whose API keys are configured, plus every
+/// `[providers.*]` endpoint. Returns the number of providers registered.
+pub(super) fn register_providers(
+    runtime: &mut AgentRuntime,
+    config: &claw_config::ClawConfig,
+) -> u32 {
+    let mut registered = 0u32;
+    if let Some(ref key) = config.services.anthropic_api_key {
+        let provider = claw_llm::anthropic::AnthropicProvider::new(key.clone())
+            .with_prompt_caching(config.agent.prompt_caching);
+        runtime.add_provider(Arc::new(provider));
+        registered += 1;
+    }
+    if let Some(ref key) = config.services.openai_api_key {
+        let provider = claw_llm::openai::OpenAiProvider::new(key.clone());
+        runtime.add_provider(Arc::new(provider));
+        registered += 1;
+    }
+    if let Some(ref key) = config.services.gemini_api_key {
+        let provider = claw_llm::gemini::GeminiProvider::new(key.clone());
+        runtime.add_provider(Arc::new(provider));
+        registered += 1;
+    }
+
+    // Custom endpoints from [providers.*] — sorted so registration order is stable
+    let mut custom: Vec<_> = config.providers.iter().collect();
+    custom.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, p) in custom {
+        if p.is_limits_only(name) {
+            continue;
+        }
+        if p.base_url.is_empty() {
+            tracing::warn!(provider = %name, "skipping provider with empty base_url");
+            continue;
+        }
+        let key = p.resolve_api_key().unwrap_or_default();
+        match p.api.as_str() {
+            "anthropic" => {
+                let mut provider = claw_llm::anthropic::AnthropicProvider::new(key)
+                    .with_base_url(p.base_url.clone())
+                    .with_name(name.clone())
+                    .with_models(p.models.clone())
+                    .with_prompt_caching(config.agent.prompt_caching);
+                if let Some((input, output)) = p.pricing() {
+                    provider = provider.with_pricing(input, output);
+                }
+                runtime.add_provider(Arc::new(provider));
+            }
+            "openai" => {
+                let mut provider = claw_llm::openai::OpenAiProvider::new(key)
+                    .with_base_url(p.base_url.clone(), name.clone())
+                    .with_models(p.models.clone());
+                if let Some((input, output)) = p.pricing() {
+                    provider = provider.with_pricing(input, output);
+                }
+                runtime.add_provider(Arc::new(provider));
+            }
+            other => {
+                tracing::warn!(provider = %name, api = %other, "skipping provider with unknown api");
+                continue;
+            }
+        }
+        registered += 1;
+    }
+    registered
+}
+
 pub(super) async fn cmd_start(
     config: claw_config::ClawConfig,
     no_server: bool,
@@ -16,11 +83,9 @@ pub(super) async fn cmd_start(
 
     // Background update check — non-blocking, best-effort
     tokio::spawn(async {
-        match super::update::check_for_update().await {
-            Some((current, latest)) => {
-                println!("   📦 Update available: v{current} → v{latest}  (run `claw update`)");
-            }
-            None => {} // up to date or network error
+        // None = up to date or network error
+        if let Some((current, latest)) = super::update::check_for_update().await {
+            println!("   📦 Update available: v{current} → v{latest}  (run `claw update`)");
         }
     });
 
@@ -39,17 +104,7 @@ pub(super) async fn cmd_start(
     let mut runtime = AgentRuntime::new(config.clone())?;
 
     // Register LLM providers — config file keys take priority, env vars are fallback
-    let mut providers_registered = 0u32;
-    if let Some(ref key) = config.services.anthropic_api_key {
-        let provider = claw_llm::anthropic::AnthropicProvider::new(key.clone());
-        runtime.add_provider(Arc::new(provider));
-        providers_registered += 1;
-    }
-    if let Some(ref key) = config.services.openai_api_key {
-        let provider = claw_llm::openai::OpenAiProvider::new(key.clone());
-        runtime.add_provider(Arc::new(provider));
-        providers_registered += 1;
-    }
+    let providers_registered = register_providers(&mut runtime, &config);
 
     if providers_registered == 0 {
         let model = &config.agent.model;
@@ -67,6 +122,11 @@ pub(super) async fn cmd_start(
                 eprintln!("   In claw.toml:  [services]");
                 eprintln!("                  openai_api_key = \"sk-...\"");
                 eprintln!("   Or env var:    export OPENAI_API_KEY=sk-...");
+            } else if model.starts_with("google/") {
+                eprintln!("   Your model is '{model}'. Set your key:");
+                eprintln!("   In claw.toml:  [services]");
+                eprintln!("                  gemini_api_key = \"AIza...\"");
+                eprintln!("   Or env var:    export GEMINI_API_KEY=AIza...");
             } else {
                 eprintln!("   Add your API keys to [services] in claw.toml:");
                 eprintln!("   anthropic_api_key = \"sk-ant-...\"");
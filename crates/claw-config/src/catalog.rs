@@ -0,0 +1,233 @@
+//! Model catalog — context windows, output limits, pricing and capabilities.
+//!
+//! The built-in catalog is embedded from `models.toml`. Users can override or
+//! extend it with `~/.claw/models.toml`; entries are merged field by field, so
+//! an override only needs the values that differ.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+/// Context window assumed for models missing from the catalog.
+pub const DEFAULT_CONTEXT_WINDOW: usize = 128_000;
+
+const BUILTIN_CATALOG: &str = include_str!("../models.toml");
+
+/// Everything we know about a single model.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelInfo {
+    /// Model id (the catalog key), e.g. `claude-sonnet-4`.
+    #[serde(skip)]
+    pub id: String,
+    /// Provider that serves the model, e.g. `anthropic`.
+    pub provider: String,
+    /// Context window in tokens.
+    pub context_window: usize,
+    /// Maximum output tokens per response (0 = unknown).
+    pub max_output: u32,
+    /// USD per million uncached input tokens.
+    pub input_cost_per_m: f64,
+    /// USD per million output tokens.
+    pub output_cost_per_m: f64,
+    /// USD per million cache-read tokens (defaults to the input rate).
+    pub cache_read_cost_per_m: Option<f64>,
+    /// USD per million cache-write tokens (defaults to the input rate).
+    pub cache_write_cost_per_m: Option<f64>,
+    /// Accepts image input.
+    pub vision: bool,
+    /// Supports tool / function calling.
+    pub tools: bool,
+    /// Supports extended thinking / reasoning.
+    pub thinking: bool,
+}
+
+impl ModelInfo {
+    pub fn pricing(&self) -> ModelPricing {
+        ModelPricing {
+            input: self.input_cost_per_m,
+            output: self.output_cost_per_m,
+            cache_read: self.cache_read_cost_per_m.unwrap_or(self.input_cost_per_m),
+            cache_write: self.cache_write_cost_per_m.unwrap_or(self.input_cost_per_m),
+        }
+    }
+}
+
+/// Per-million-token prices used for cost estimation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    pub cache_read: f64,
+    pub cache_write: f64,
+}
+
+impl ModelPricing {
+    /// Input/output pricing with cached tokens billed at the input rate.
+    pub fn flat(input: f64, output: f64) -> Self {
+        Self {
+            input,
+            output,
+            cache_read: input,
+            cache_write: input,
+        }
+    }
+
+    /// Estimated cost in USD. `input_tokens` must exclude cached tokens.
+    pub fn cost(
+        &self,
+        input_tokens: u32,
+        output_tokens: u32,
+        cache_read_tokens: u32,
+        cache_write_tokens: u32,
+    ) -> f64 {
+        (input_tokens as f64 * self.input
+            + output_tokens as f64 * self.output
+            + cache_read_tokens as f64 * self.cache_read
+            + cache_write_tokens as f64 * self.cache_write)
+            / 1_000_000.0
+    }
+}
+
+#[derive(Deserialize)]
+struct CatalogFile {
+    #[serde(default)]
+    models: BTreeMap<String, ModelInfo>,
+}
+
+/// A set of known models, keyed by id.
+#[derive(Debug, Clone, Default)]
+pub struct ModelCatalog {
+    models: BTreeMap<String, ModelInfo>,
+}
+
+impl ModelCatalog {
+    /// The embedded catalog with no user overrides.
+    pub fn builtin() -> Self {
+        Self::parse(&[BUILTIN_CATALOG]).expect("embedded models.toml is valid")
+    }
+
+    /// The embedded catalog merged with `~/.claw/models.toml` (if present).
+    /// A malformed override file is logged and ignored.
+    pub fn load() -> Self {
+        Self::load_with_overrides(&Self::overrides_path())
+    }
+
+    /// The embedded catalog merged with the overrides file at `path`.
+    pub fn load_with_overrides(path: &Path) -> Self {
+        let Ok(overrides) = std::fs::read_to_string(path) else {
+            return Self::builtin();
+        };
+        match Self::parse(&[BUILTIN_CATALOG, &overrides]) {
+            Ok(catalog) => catalog,
+            Err(e) => {
+                tracing::warn!(path = %path.display(), error = %e, "ignoring invalid model catalog overrides");
+                Self::builtin()
+            }
+        }
+    }
+
+    /// Location of the user override file.
+    pub fn overrides_path() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".claw")
+            .join("models.toml")
+    }
+
+    /// Parse and merge catalog sources; later sources override earlier ones.
+    pub fn parse(sources: &[&str]) -> Result<Self, String> {
+        let mut merged = toml::Table::new();
+        for source in sources {
+            let table: toml::Table = toml::from_str(source).map_err(|e| e.to_string())?;
+            merge_tables(&mut merged, table);
+        }
+        let file: CatalogFile = toml::Value::Table(merged)
+            .try_into()
+            .map_err(|e: toml::de::Error| e.to_string())?;
+        let models = file
+            .models
+            .into_iter()
+            .map(|(id, mut info)| {
+                let id = id.to_lowercase();
+                info.id = id.clone();
+                (id, info)
+            })
+            .collect();
+        Ok(Self { models })
+    }
+
+    /// Look up a model. Accepts `provider/model` ids, matches exact ids first
+    /// and then the longest catalog id that prefixes the name at a `-`, `.`,
+    /// `:` or `@` boundary (so dated snapshots resolve to their family).
+    pub fn get(&self, model: &str) -> Option<&ModelInfo> {
+        let model = model.to_lowercase();
+        let mut candidates = vec![model.as_str()];
+        if let Some((_, rest)) = model.split_once('/') {
+            candidates.push(rest);
+        }
+        if let Some((_, last)) = model.rsplit_once('/') {
+            candidates.push(last);
+        }
+        candidates.into_iter().find_map(|name| self.lookup(name))
+    }
+
+    fn lookup(&self, name: &str) -> Option<&ModelInfo> {
+        if let Some(info) = self.models.get(name) {
+            return Some(info);
+        }
+        self.models
+            .iter()
+            .filter(|(id, _)| {
+                name.strip_prefix(id.as_str())
+                    .and_then(|rest| rest.chars().next())
+                    .is_some_and(|c| matches!(c, '-' | '.' | ':' | '@'))
+            })
+            .max_by_key(|(id, _)| id.len())
+            .map(|(_, info)| info)
+    }
+
+    /// All models, sorted by id.
+    pub fn iter(&self) -> impl Iterator<Item = &ModelInfo> {
+        self.models.values()
+    }
+
+    /// Model ids served by `provider`, sorted.
+    pub fn models_for(&self, provider: &str) -> Vec<String> {
+        self.models
+            .values()
+            .filter(|m| m.provider == provider)
+            .map(|m| m.id.clone())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.models.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+}
+
+/// Recursively merge `overlay` into `base`, replacing non-table values.
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(incoming)) => {
+                merge_tables(existing, incoming);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// The process-wide catalog (built-in + user overrides), loaded on first use.
+pub fn catalog() -> &'static ModelCatalog {
+    static CATALOG: OnceLock<ModelCatalog> = OnceLock::new();
+    CATALOG.get_or_init(ModelCatalog::load)
+}
@@ -5,11 +5,14 @@
 //!
 //! Supports hot-reload via filesystem watcher.
 
+pub mod catalog;
 pub mod loader;
 pub mod schema;
 
+pub use catalog::{DEFAULT_CONTEXT_WINDOW, ModelCatalog, ModelInfo, ModelPricing, catalog};
 pub use loader::ConfigLoader;
 pub use schema::ClawConfig;
 pub use schema::{
-    ConfigWarning, CredentialsConfig, ServicesConfig, WarningSeverity, resolve_context_window,
+    BUILTIN_PROVIDERS, ConfigWarning, CredentialsConfig, ProviderConfig, RoutingConfig,
+    ServicesConfig, TaskClass, WarningSeverity, resolve_context_window,
 };
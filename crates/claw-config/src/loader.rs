@@ -46,7 +46,8 @@ impl ConfigLoader {
         };
 
         // Apply environment variable overrides
-        let config = Self::apply_env_overrides(config);
+        let mut config = Self::apply_env_overrides(config);
+        config.source = Some(config_path.clone());
 
         // Validate config — log warnings, fail on errors
         match config.validate() {
@@ -114,6 +115,12 @@ impl ConfigLoader {
         {
             config.services.openai_api_key = Some(v);
         }
+        if config.services.gemini_api_key.is_none()
+            && let Ok(v) =
+                std::env::var("GEMINI_API_KEY").or_else(|_| std::env::var("GOOGLE_API_KEY"))
+        {
+            config.services.gemini_api_key = Some(v);
+        }
         if config.services.brave_api_key.is_none()
             && let Ok(v) = std::env::var("BRAVE_API_KEY")
         {
@@ -144,7 +151,8 @@ impl ConfigLoader {
                 e
             ))
         })?;
-        let new_config = Self::apply_env_overrides(new_config);
+        let mut new_config = Self::apply_env_overrides(new_config);
+        new_config.source = Some(self.config_path.clone());
         *self.config.write() = new_config;
         info!("configuration reloaded");
         Ok(())
@@ -179,7 +187,8 @@ impl ConfigLoader {
                                     Ok(raw) => {
                                         match toml::from_str::<ClawConfig>(&raw) {
                                             Ok(new_config) => {
-                                                let new_config = ConfigLoader::apply_env_overrides(new_config);
+                                                let mut new_config = ConfigLoader::apply_env_overrides(new_config);
+                                                new_config.source = Some(path_for_event.clone());
                                                 *config.write() = new_config;
                                                 info!("configuration hot-reloaded successfully");
                                             }
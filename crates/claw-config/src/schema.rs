@@ -9,6 +9,7 @@ use std::path::PathBuf;
 pub struct ClawConfig {
     pub agent: AgentConfig,
     pub autonomy: AutonomyConfig,
+    pub runtime: RuntimeConfig,
     pub memory: MemoryConfig,
     pub channels: HashMap<String, ChannelConfig>,
     pub mesh: MeshConfig,
@@ -17,6 +18,13 @@ pub struct ClawConfig {
     pub logging: LoggingConfig,
     pub credentials: CredentialsConfig,
     pub services: ServicesConfig,
+    /// Custom LLM endpoints, keyed by routing prefix (`[providers.groq]` → `groq/<model>`).
+    pub providers: HashMap<String, ProviderConfig>,
+    pub routing: RoutingConfig,
+    /// The file this config was loaded from — `--config`, `CLAW_CONFIG` or
+    /// `~/.claw/claw.toml`. Set by the loader, never read from the file.
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
 }
 
 // ── Agent ──────────────────────────────────────────────────────
@@ -46,7 +54,7 @@ pub struct AgentConfig {
     pub max_parallel_tools: u32,
     /// Thinking / reasoning budget ("off", "low", "medium", "high", "xhigh").
     pub thinking_level: String,
-    /// Context window size in tokens. If 0, auto-detected from model name.
+    /// Context window size in tokens. If 0, looked up in the model catalog.
     pub context_window: usize,
     /// Maximum tokens per tool result. Longer results are truncated with a note.
     /// Default: 12000 (~48KB of text). Set to 0 to disable truncation.
@@ -61,6 +69,14 @@ pub struct AgentConfig {
     pub parallel_tool_calls: bool,
     /// Automatically resume interrupted tasks via the scheduler. Default: true.
     pub auto_resume: bool,
+    /// Attach screenshots taken by browser/android/ios tools to the next LLM turn
+    /// as images (needs a vision-capable model). Only the latest screenshot is kept
+    /// in context. Default: false.
+    pub attach_screenshots: bool,
+    /// Use Anthropic prompt caching for the system prompt, tool schemas and
+    /// conversation prefix. Cache reads bill at ~10% of the input rate.
+    /// Default: true.
+    pub prompt_caching: bool,
 }
 
 impl Default for AgentConfig {
@@ -83,6 +99,8 @@ impl Default for AgentConfig {
             request_timeout_secs: 0,
             parallel_tool_calls: true,
             auto_resume: true,
+            attach_screenshots: false,
+            prompt_caching: true,
         }
     }
 }
@@ -104,6 +122,19 @@ pub struct AutonomyConfig {
     pub tool_allowlist: Vec<String>,
     /// Tools that are always blocked.
     pub tool_denylist: Vec<String>,
+    /// TOML file of guardrail policy rules, reloaded when it changes.
+    /// Relative paths are under `~/.claw`.
+    pub policy_file: Option<PathBuf>,
+    /// Limit file tools to the workspace — the directory Claw runs in —
+    /// and `allowed_paths`.
+    pub workspace_only: bool,
+    /// Directories file tools may use. When set, paths outside them (and
+    /// outside the workspace, with `workspace_only`) are refused.
+    pub allowed_paths: Vec<PathBuf>,
+    /// Paths file tools may never read or write, even inside allowed ones.
+    /// Claw's loaded config, memory database, audit key and policy file are
+    /// always added.
+    pub forbidden_paths: Vec<PathBuf>,
     /// Actions above this risk level require human approval (0-10).
     pub approval_threshold: u8,
     /// Enable proactive heartbeat / background tasks.
@@ -123,6 +154,30 @@ impl Default for AutonomyConfig {
             max_delete_files: 5,
             tool_allowlist: vec![],
             tool_denylist: vec![],
+            policy_file: None,
+            workspace_only: false,
+            allowed_paths: vec![],
+            forbidden_paths: [
+                "~/.ssh",
+                "~/.gnupg",
+                "~/.aws",
+                "~/.azure",
+                "~/.config/gcloud",
+                "~/.kube/config",
+                "~/.docker/config.json",
+                "~/.netrc",
+                "~/.git-credentials",
+                "~/.claw/claw.toml",
+                "~/.claw/audit.key",
+                "~/.claw/credentials",
+                "/etc/shadow",
+                "/etc/gshadow",
+                "/etc/sudoers",
+                "/etc/sudoers.d",
+            ]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect(),
             approval_threshold: 7,
             proactive: false,
             heartbeat_cron: None,
@@ -131,6 +186,23 @@ impl Default for AutonomyConfig {
     }
 }
 
+impl AutonomyConfig {
+    /// The policy file path, with relative paths resolved under `~/.claw`.
+    pub fn resolved_policy_file(&self) -> Option<PathBuf> {
+        let path = self.policy_file.as_ref()?;
+        if path.is_absolute() {
+            Some(path.clone())
+        } else {
+            Some(
+                dirs::home_dir()
+                    .unwrap_or_else(|| PathBuf::from("."))
+                    .join(".claw")
+                    .join(path),
+            )
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GoalConfig {
     pub description: String,
@@ -141,6 +213,90 @@ pub struct GoalConfig {
     pub enabled: bool,
 }
 
+// ── Runtime ────────────────────────────────────────────────────
+
+/// How `shell_exec`, `process_start` and terminals are confined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SandboxMode {
+    /// Commands run as Claw does.
+    #[default]
+    None,
+    /// Landlock filesystem rules, a seccomp filter and resource limits.
+    Landlock,
+    /// Everything `landlock` does, inside user, mount, IPC and UTS
+    /// namespaces with read-only system dirs and a private `/tmp`.
+    Namespaces,
+}
+
+impl std::fmt::Display for SandboxMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::None => "none",
+            Self::Landlock => "landlock",
+            Self::Namespaces => "namespaces",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuntimeConfig {
+    /// Sandbox for shell and process tools (Linux only): "none", "landlock"
+    /// or "namespaces".
+    pub sandbox: SandboxMode,
+    /// Where each session's writable workspace is created. Relative paths
+    /// are under `~/.claw`.
+    pub sandbox_dir: PathBuf,
+    /// Extra paths sandboxed commands may read (system dirs always are).
+    pub sandbox_read_paths: Vec<PathBuf>,
+    /// Let sandboxed commands use the network. Off also refuses Unix
+    /// sockets, which reach host daemons.
+    pub sandbox_network: bool,
+    /// CPU seconds per sandboxed process (0 = unlimited).
+    pub sandbox_cpu_secs: u64,
+    /// Address space per sandboxed process, in MB (0 = unlimited).
+    pub sandbox_memory_mb: u64,
+    /// Largest file a sandboxed process may write, in MB (0 = unlimited).
+    pub sandbox_file_size_mb: u64,
+    /// Processes the sandbox user may run at once (0 = unlimited).
+    pub sandbox_max_processes: u64,
+    /// Longest `shell_exec` timeout a sandboxed command may ask for, in
+    /// seconds (0 = whatever the call asks). At the timeout the command's
+    /// whole process group is killed.
+    pub sandbox_max_command_secs: u64,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            sandbox: SandboxMode::None,
+            sandbox_dir: PathBuf::from("sandbox"),
+            sandbox_read_paths: vec![],
+            sandbox_network: true,
+            sandbox_cpu_secs: 600,
+            sandbox_memory_mb: 8192,
+            sandbox_file_size_mb: 1024,
+            sandbox_max_processes: 1024,
+            sandbox_max_command_secs: 1800,
+        }
+    }
+}
+
+impl RuntimeConfig {
+    /// The sandbox workspace root, with relative paths resolved under `~/.claw`.
+    pub fn resolved_sandbox_dir(&self) -> PathBuf {
+        if self.sandbox_dir.is_absolute() {
+            self.sandbox_dir.clone()
+        } else {
+            dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".claw")
+                .join(&self.sandbox_dir)
+        }
+    }
+}
+
 // ── Memory ─────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -154,8 +310,44 @@ pub struct MemoryConfig {
     pub vector_search: bool,
     /// Embedding dimensions (384 for MiniLM, 1536 for OpenAI, etc.)
     pub embedding_dims: usize,
+    /// Embedder for vector search: "builtin" (offline, no model download),
+    /// "openai", "ollama", or "none". Changing it re-embeds stored facts.
+    pub embedding_provider: String,
+    /// Model for the openai / ollama embedders. None = text-embedding-3-small
+    /// (openai) or nomic-embed-text (ollama).
+    pub embedding_model: Option<String>,
     /// Auto-summarize conversations after this many messages.
     pub auto_summarize_after: usize,
+    /// Knowledge base passage size in characters.
+    pub kb_chunk_chars: usize,
+    /// Characters of context repeated between consecutive passages.
+    pub kb_chunk_overlap: usize,
+    /// Re-scan ingested paths for changes this often (seconds). 0 = never.
+    pub kb_refresh_secs: u64,
+    /// Days for an unused fact's confidence to halve; each recall slows it
+    /// further. 0 = facts never decay.
+    pub fact_half_life_days: f64,
+    /// Share of a fact's lost confidence restored when it's recalled (0.0-1.0).
+    pub fact_reinforcement: f64,
+    /// Facts whose decayed confidence drops below this are archived.
+    pub fact_archive_below: f64,
+    /// Delete archived facts after this many days. 0 = keep them.
+    pub fact_archive_retention_days: u64,
+    /// Archive faded facts this often (seconds). 0 = never.
+    pub fact_consolidation_secs: u64,
+    /// Fact categories that never decay.
+    pub fact_decay_exempt: Vec<String>,
+    /// Ask the `fact_conflict` task model (fast_model by default) whether a
+    /// fact's changed value contradicts the old one. Off = flag changes that
+    /// come from a different source or flip back to an earlier value.
+    pub judge_fact_conflicts: bool,
+    /// After each turn, have the `graph_extraction` task model (fast_model by
+    /// default) pull entities and relations into the knowledge graph. The
+    /// graph is shared, so only conversations whose default write scope is
+    /// "global" feed it.
+    pub graph_extraction: bool,
+    /// Which memory scopes conversations read and write.
+    pub scopes: MemoryScopesConfig,
 }
 
 impl Default for MemoryConfig {
@@ -165,11 +357,107 @@ impl Default for MemoryConfig {
             max_episodes: 10_000,
             vector_search: true,
             embedding_dims: 384,
+            embedding_provider: "builtin".into(),
+            embedding_model: None,
             auto_summarize_after: 50,
+            kb_chunk_chars: 1200,
+            kb_chunk_overlap: 200,
+            kb_refresh_secs: 300,
+            fact_half_life_days: 90.0,
+            fact_reinforcement: 0.2,
+            fact_archive_below: 0.1,
+            fact_archive_retention_days: 180,
+            fact_consolidation_secs: 3600,
+            fact_decay_exempt: vec!["learned_lessons".into()],
+            judge_fact_conflicts: false,
+            graph_extraction: true,
+            scopes: MemoryScopesConfig::default(),
+        }
+    }
+}
+
+impl MemoryConfig {
+    /// The database file: `db_path` if absolute, otherwise under `~/.claw/`.
+    pub fn resolved_db_path(&self) -> PathBuf {
+        if self.db_path.is_absolute() {
+            self.db_path.clone()
+        } else {
+            dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".claw")
+                .join(&self.db_path)
         }
     }
 }
 
+/// Embedders accepted in `memory.embedding_provider`.
+pub const EMBEDDING_PROVIDERS: &[&str] = &["builtin", "openai", "ollama", "none"];
+
+/// Memory scopes, narrowest last: shared by everyone, by one channel, one
+/// person, or one conversation.
+pub const MEMORY_SCOPES: &[&str] = &["global", "channel", "user", "session"];
+
+/// `[memory.scopes]` — which scopes facts and episodes are recalled from and
+/// stored in, with per-channel overrides.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MemoryScopesConfig {
+    /// Scopes recalled and searched.
+    pub read: Vec<String>,
+    /// Scopes `memory_store` may write to.
+    pub write: Vec<String>,
+    /// Where facts and episodes go unless another scope is asked for.
+    pub default_write: String,
+    /// Overrides by channel id ("telegram", "api", "sub-agent", ...).
+    pub channels: HashMap<String, ScopeRules>,
+    /// Sender ids of one person on different channels, "channel:sender" →
+    /// name, so they share one "user" scope.
+    pub identities: HashMap<String, String>,
+}
+
+impl Default for MemoryScopesConfig {
+    fn default() -> Self {
+        Self {
+            read: MEMORY_SCOPES.iter().map(|s| s.to_string()).collect(),
+            write: MEMORY_SCOPES.iter().map(|s| s.to_string()).collect(),
+            default_write: "user".into(),
+            channels: HashMap::new(),
+            identities: HashMap::new(),
+        }
+    }
+}
+
+/// A channel's overrides of `[memory.scopes]`; unset fields inherit.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScopeRules {
+    pub read: Option<Vec<String>>,
+    pub write: Option<Vec<String>>,
+    pub default_write: Option<String>,
+}
+
+impl MemoryScopesConfig {
+    /// The (read, write, default_write) scopes for a channel.
+    pub fn rules_for(&self, channel: &str) -> (&[String], &[String], &str) {
+        let rules = self.channels.get(channel);
+        (
+            rules.and_then(|r| r.read.as_deref()).unwrap_or(&self.read),
+            rules
+                .and_then(|r| r.write.as_deref())
+                .unwrap_or(&self.write),
+            rules
+                .and_then(|r| r.default_write.as_deref())
+                .unwrap_or(&self.default_write),
+        )
+    }
+
+    /// The identity behind a sender: its linked name, or `channel:sender`.
+    pub fn identity(&self, channel: &str, sender: &str) -> String {
+        let id = format!("{channel}:{sender}");
+        self.identities.get(&id).cloned().unwrap_or(id)
+    }
+}
+
 // ── Channels ───────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -371,6 +659,10 @@ pub struct ServicesConfig {
     /// Can also be set via OPENAI_API_KEY environment variable.
     /// Config file takes priority over environment variable.
     pub openai_api_key: Option<String>,
+    /// Google Gemini API key — used for Gemini models (`google/gemini-2.5-pro`).
+    /// Can also be set via GEMINI_API_KEY (or GOOGLE_API_KEY) environment variable.
+    /// Config file takes priority over environment variable.
+    pub gemini_api_key: Option<String>,
     /// Brave Search API key — get one free at https://api.search.brave.com/
     pub brave_api_key: Option<String>,
     /// URL of the central Skills Hub (e.g. "https://hub.claw.dev" or "http://192.168.1.50:3800").
@@ -379,6 +671,154 @@ pub struct ServicesConfig {
     pub hub_url: Option<String>,
 }
 
+// ── Providers ──────────────────────────────────────────────────
+
+/// Provider prefixes that are built into the runtime and need no `[providers.*]` entry.
+pub const BUILTIN_PROVIDERS: &[&str] = &["anthropic", "openai", "google", "ollama", "local"];
+
+/// A custom LLM endpoint declared under `[providers.<name>]`.
+///
+/// The table key is the provider name and routing prefix — `[providers.groq]`
+/// makes `groq/llama-3.3-70b` resolve to this endpoint. Works for any
+/// OpenAI-compatible server (OpenRouter, Groq, DeepSeek, vLLM, LM Studio, …)
+/// or an Anthropic-compatible proxy.
+///
+/// A built-in provider name with no `base_url` (e.g. `[providers.anthropic]`
+/// with only `requests_per_minute`) just sets that provider's rate limits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderConfig {
+    /// Wire protocol: "openai" (chat completions) or "anthropic" (messages API).
+    pub api: String,
+    /// Base URL of the API, e.g. "https://api.groq.com/openai/v1".
+    pub base_url: String,
+    /// API key. Takes priority over `api_key_env`.
+    pub api_key: Option<String>,
+    /// Name of an environment variable holding the API key, e.g. "GROQ_API_KEY".
+    pub api_key_env: Option<String>,
+    /// Models served by this endpoint (used for prefix-less model lookup).
+    pub models: Vec<String>,
+    /// Context window in tokens for this provider's models. 0 = infer from model name.
+    pub context_window: usize,
+    /// Price per million input tokens (USD). None = model catalog price.
+    pub input_cost_per_m: Option<f64>,
+    /// Price per million output tokens (USD). None = model catalog price.
+    pub output_cost_per_m: Option<f64>,
+    /// Client-side cap on requests per minute to this provider. 0 = unlimited.
+    pub requests_per_minute: u32,
+    /// Client-side cap on tokens (prompt + max output) per minute. 0 = unlimited.
+    pub tokens_per_minute: u32,
+}
+
+impl Default for ProviderConfig {
+    fn default() -> Self {
+        Self {
+            api: "openai".into(),
+            base_url: String::new(),
+            api_key: None,
+            api_key_env: None,
+            models: vec![],
+            context_window: 0,
+            input_cost_per_m: None,
+            output_cost_per_m: None,
+            requests_per_minute: 0,
+            tokens_per_minute: 0,
+        }
+    }
+}
+
+impl ProviderConfig {
+    /// Resolve the API key — inline value first, then the named env var.
+    pub fn resolve_api_key(&self) -> Option<String> {
+        self.api_key.clone().or_else(|| {
+            self.api_key_env
+                .as_ref()
+                .and_then(|var| std::env::var(var).ok())
+        })
+    }
+
+    /// Per-million (input, output) pricing, if both are configured.
+    pub fn pricing(&self) -> Option<(f64, f64)> {
+        Some((self.input_cost_per_m?, self.output_cost_per_m?))
+    }
+
+    /// Whether this entry only sets rate limits for the built-in provider `name`.
+    pub fn is_limits_only(&self, name: &str) -> bool {
+        self.base_url.is_empty() && BUILTIN_PROVIDERS.contains(&name)
+    }
+}
+
+// ── Routing ────────────────────────────────────────────────────
+
+/// Routing policy names accepted in `routing.policy`.
+pub const ROUTING_POLICIES: &[&str] = &["primary", "cascade", "latency"];
+
+/// Task classes accepted as keys of `[routing.tasks]` (plus `sub_agent.<role>`).
+pub const ROUTING_TASKS: &[&str] = &[
+    "compaction",
+    "lesson_extraction",
+    "title",
+    "fact_conflict",
+    "graph_extraction",
+    "sub_agent",
+];
+
+/// How the model router picks a model for each request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoutingConfig {
+    /// Model selection for agent turns:
+    /// - "primary": always `agent.model` (failover to `agent.fallback_model`).
+    /// - "cascade": try `agent.fast_model` first and escalate to `agent.model`
+    ///   on a low-confidence reply or a tool-heavy turn. The fast model's
+    ///   reply is streamed once it's been judged.
+    /// - "latency": the model in `candidates` with the lowest observed p50.
+    pub policy: String,
+    /// Models the latency policy chooses between. Empty = `agent.model`,
+    /// `agent.fast_model` and `agent.fallback_model`.
+    pub candidates: Vec<String>,
+    /// Cascade: turns with at least this many tool calls since the last user
+    /// message go straight to `agent.model`.
+    pub cascade_tool_threshold: usize,
+    /// Maximum worst-case cost of a single request in USD (prompt + max_tokens
+    /// of output at catalog prices). Over-cap requests are downgraded to
+    /// `agent.fast_model`, or refused if that is over the cap too. 0 = no cap.
+    pub max_cost_per_request_usd: f64,
+    /// Task class → model, e.g. `compaction = "openai/gpt-4o-mini"`. Keys:
+    /// `compaction`, `lesson_extraction`, `title`, `fact_conflict`,
+    /// `graph_extraction`, `sub_agent` and `sub_agent.<role>`. Setting `title` enables LLM-generated session titles.
+    pub tasks: HashMap<String, String>,
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            policy: "primary".into(),
+            candidates: vec![],
+            cascade_tool_threshold: 3,
+            max_cost_per_request_usd: 0.0,
+            tasks: HashMap::new(),
+        }
+    }
+}
+
+/// Background and delegated work that can be routed to its own model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskClass<'a> {
+    /// LLM-powered context compaction.
+    Compaction,
+    /// Self-learning lesson extraction.
+    LessonExtraction,
+    /// Session title generation.
+    Title,
+    /// Judging whether a fact's new value contradicts its old one.
+    FactConflict,
+    /// Knowledge graph extraction after a turn.
+    GraphExtraction,
+    /// A sub-agent with the given role.
+    SubAgent(&'a str),
+}
+
 // ── Default for root ───────────────────────────────────────────
 
 fn default_true() -> bool {
@@ -390,54 +830,16 @@ fn default_dm_policy() -> String {
 }
 
 /// Resolve context window size for a model. If the user configured a specific
-/// value, use that. Otherwise, infer from the model name.
+/// value, use that. Otherwise, look the model up in the model catalog.
 pub fn resolve_context_window(config_value: usize, model: &str) -> usize {
     if config_value > 0 {
         return config_value;
     }
-    let m = model.to_lowercase();
-    // Claude models
-    if m.contains("claude") {
-        return 200_000;
-    }
-    // GPT-5 and variants
-    if m.contains("gpt-5") || m.contains("gpt5") {
-        return 1_000_000;
-    }
-    // GPT-4o and variants
-    if m.contains("gpt-4o") || m.contains("gpt-4-turbo") {
-        return 128_000;
-    }
-    // GPT-4 (original)
-    if m.contains("gpt-4") {
-        return 8_192;
-    }
-    // o1 / o3 reasoning models
-    if m.contains("o1") || m.contains("o3") {
-        return 200_000;
-    }
-    // GPT-3.5
-    if m.contains("gpt-3.5") {
-        return 16_385;
-    }
-    // Llama 3 models
-    if m.contains("llama3") || m.contains("llama-3") {
-        return 128_000;
-    }
-    // Mistral / Mixtral
-    if m.contains("mistral") || m.contains("mixtral") {
-        return 32_768;
-    }
-    // Gemini
-    if m.contains("gemini") {
-        return 1_000_000;
-    }
-    // DeepSeek
-    if m.contains("deepseek") {
-        return 128_000;
-    }
-    // Default fallback
-    128_000
+    crate::catalog::catalog()
+        .get(model)
+        .map(|m| m.context_window)
+        .filter(|&w| w > 0)
+        .unwrap_or(crate::catalog::DEFAULT_CONTEXT_WINDOW)
 }
 
 // ── Validation ─────────────────────────────────────────────────
@@ -474,6 +876,54 @@ impl std::fmt::Display for ConfigWarning {
 }
 
 impl ClawConfig {
+    /// Whether `prefix` names a built-in provider or a `[providers.*]` entry.
+    pub fn is_known_provider(&self, prefix: &str) -> bool {
+        BUILTIN_PROVIDERS.contains(&prefix) || self.providers.contains_key(prefix)
+    }
+
+    /// Context window for `model`: explicit `agent.context_window`, then the
+    /// matching `[providers.*]` entry, then the model catalog.
+    pub fn context_window_for(&self, model: &str) -> usize {
+        if self.agent.context_window == 0
+            && let Some((prefix, _)) = model.split_once('/')
+            && let Some(p) = self.providers.get(prefix)
+            && p.context_window > 0
+        {
+            return p.context_window;
+        }
+        resolve_context_window(self.agent.context_window, model)
+    }
+
+    /// Response token limit for `model`: `agent.max_tokens`, capped at the
+    /// model's catalog output limit so small-output models aren't over-asked.
+    pub fn max_tokens_for(&self, model: &str) -> u32 {
+        match crate::catalog::catalog().get(model) {
+            Some(info) if info.max_output > 0 => self.agent.max_tokens.min(info.max_output),
+            _ => self.agent.max_tokens,
+        }
+    }
+
+    /// Model for a task class: the `[routing.tasks]` entry, else
+    /// `agent.fast_model` for background tasks and `agent.model` for sub-agents.
+    pub fn model_for_task(&self, task: TaskClass<'_>) -> String {
+        let tasks = &self.routing.tasks;
+        let configured = match task {
+            TaskClass::Compaction => tasks.get("compaction"),
+            TaskClass::LessonExtraction => tasks.get("lesson_extraction"),
+            TaskClass::Title => tasks.get("title"),
+            TaskClass::FactConflict => tasks.get("fact_conflict"),
+            TaskClass::GraphExtraction => tasks.get("graph_extraction"),
+            TaskClass::SubAgent(role) => tasks
+                .get(&format!("sub_agent.{role}"))
+                .or_else(|| tasks.get("sub_agent")),
+        };
+        let default = match task {
+            TaskClass::SubAgent(_) => None,
+            _ => self.agent.fast_model.as_ref(),
+        };
+        configured.or(default).unwrap_or(&self.agent.model).clone()
+    }
+
     /// Validate the config and return a list of warnings/errors.
     /// Returns `Err` with all messages joined if any severity is Error.
     pub fn validate(&self) -> Result<Vec<ConfigWarning>, String> {
@@ -502,6 +952,289 @@ impl ClawConfig {
             });
         }
 
+        // ── Model provider prefixes ───
+        let model_fields = [
+            ("agent.model", Some(&self.agent.model)),
+            ("agent.fallback_model", self.agent.fallback_model.as_ref()),
+            ("agent.fast_model", self.agent.fast_model.as_ref()),
+            ("agent.local_model", self.agent.local_model.as_ref()),
+        ];
+        for (field, value) in model_fields {
+            if let Some(m) = value
+                && let Some((prefix, _)) = m.split_once('/')
+                && !self.is_known_provider(prefix)
+            {
+                let mut known: Vec<&str> = BUILTIN_PROVIDERS.to_vec();
+                known.extend(self.providers.keys().map(|k| k.as_str()));
+                warnings.push(ConfigWarning {
+                    field: field.into(),
+                    message: format!("unknown provider prefix '{prefix}' in '{m}'"),
+                    severity: WarningSeverity::Warning,
+                    hint: Some(format!(
+                        "Known providers: {}. Add a [providers.{prefix}] section for custom endpoints.",
+                        known.join(", ")
+                    )),
+                });
+            }
+        }
+
+        // ── Model catalog ───
+        for (field, value) in model_fields {
+            let Some(m) = value.filter(|m| !m.is_empty()) else {
+                continue;
+            };
+            let window_configured = (field == "agent.model" && self.agent.context_window > 0)
+                || m.split_once('/')
+                    .and_then(|(prefix, _)| self.providers.get(prefix))
+                    .is_some_and(|p| p.context_window > 0);
+            if !window_configured && crate::catalog::catalog().get(m).is_none() {
+                warnings.push(ConfigWarning {
+                    field: field.into(),
+                    message: format!(
+                        "model '{m}' is not in the model catalog — assuming a {}-token context window",
+                        crate::catalog::DEFAULT_CONTEXT_WINDOW
+                    ),
+                    severity: WarningSeverity::Warning,
+                    hint: Some(format!(
+                        "Add it to {} or set agent.context_window",
+                        crate::catalog::ModelCatalog::overrides_path().display()
+                    )),
+                });
+            }
+        }
+
+        // ── Custom providers ───
+        for (name, p) in &self.providers {
+            if p.is_limits_only(name) {
+                continue;
+            }
+            if p.base_url.is_empty() {
+                warnings.push(ConfigWarning {
+                    field: format!("providers.{name}.base_url"),
+                    message: "base_url is empty".into(),
+                    severity: WarningSeverity::Error,
+                    hint: Some("Set to e.g. 'https://api.groq.com/openai/v1'".into()),
+                });
+            }
+            if p.api != "openai" && p.api != "anthropic" {
+                warnings.push(ConfigWarning {
+                    field: format!("providers.{name}.api"),
+                    message: format!("unknown provider API '{}'", p.api),
+                    severity: WarningSeverity::Error,
+                    hint: Some("Valid values: openai, anthropic".into()),
+                });
+            }
+            if BUILTIN_PROVIDERS.contains(&name.as_str()) {
+                warnings.push(ConfigWarning {
+                    field: format!("providers.{name}"),
+                    message: format!("'{name}' is a built-in provider name"),
+                    severity: WarningSeverity::Warning,
+                    hint: Some(
+                        "The built-in provider takes precedence when its API key is set — pick another name"
+                            .into(),
+                    ),
+                });
+            }
+            if p.input_cost_per_m.is_some() != p.output_cost_per_m.is_some() {
+                warnings.push(ConfigWarning {
+                    field: format!("providers.{name}"),
+                    message: "only one of input_cost_per_m / output_cost_per_m is set".into(),
+                    severity: WarningSeverity::Warning,
+                    hint: Some("Set both to use custom pricing".into()),
+                });
+            }
+            if p.api_key.is_none() && p.api_key_env.is_none() {
+                warnings.push(ConfigWarning {
+                    field: format!("providers.{name}.api_key"),
+                    message: "no API key configured — requests will be unauthenticated".into(),
+                    severity: WarningSeverity::Info,
+                    hint: Some(
+                        "Fine for local servers (vLLM, LM Studio); otherwise set api_key_env"
+                            .into(),
+                    ),
+                });
+            }
+        }
+
+        // ── Memory ───
+        if !EMBEDDING_PROVIDERS.contains(&self.memory.embedding_provider.as_str()) {
+            warnings.push(ConfigWarning {
+                field: "memory.embedding_provider".into(),
+                message: format!(
+                    "unknown embedding provider '{}'",
+                    self.memory.embedding_provider
+                ),
+                severity: WarningSeverity::Error,
+                hint: Some(format!(
+                    "Valid providers: {}",
+                    EMBEDDING_PROVIDERS.join(", ")
+                )),
+            });
+        }
+        if self.memory.embedding_provider != "none" && self.memory.embedding_dims == 0 {
+            warnings.push(ConfigWarning {
+                field: "memory.embedding_dims".into(),
+                message: "embedding_dims must be greater than 0".into(),
+                severity: WarningSeverity::Error,
+                hint: Some(
+                    "384 suits the builtin embedder; match your model's output size otherwise"
+                        .into(),
+                ),
+            });
+        }
+        if self.memory.embedding_provider == "openai" && self.services.openai_api_key.is_none() {
+            warnings.push(ConfigWarning {
+                field: "memory.embedding_provider".into(),
+                message: "openai embeddings need an OpenAI API key — vector search is off".into(),
+                severity: WarningSeverity::Warning,
+                hint: Some(
+                    "Set services.openai_api_key or use embedding_provider = \"builtin\"".into(),
+                ),
+            });
+        }
+        if self.memory.kb_chunk_overlap >= self.memory.kb_chunk_chars {
+            warnings.push(ConfigWarning {
+                field: "memory.kb_chunk_overlap".into(),
+                message: "kb_chunk_overlap must be smaller than kb_chunk_chars".into(),
+                severity: WarningSeverity::Error,
+                hint: Some("e.g. kb_chunk_chars = 1200, kb_chunk_overlap = 200".into()),
+            });
+        }
+        if self.memory.fact_half_life_days.is_nan() || self.memory.fact_half_life_days < 0.0 {
+            warnings.push(ConfigWarning {
+                field: "memory.fact_half_life_days".into(),
+                message: "fact_half_life_days must be 0 or more".into(),
+                severity: WarningSeverity::Error,
+                hint: Some("Use 0 to turn fact decay off".into()),
+            });
+        }
+        for (field, value) in [
+            ("fact_reinforcement", self.memory.fact_reinforcement),
+            ("fact_archive_below", self.memory.fact_archive_below),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                warnings.push(ConfigWarning {
+                    field: format!("memory.{field}"),
+                    message: format!("{field} must be between 0.0 and 1.0"),
+                    severity: WarningSeverity::Error,
+                    hint: None,
+                });
+            }
+        }
+        let scopes = &self.memory.scopes;
+        let mut channels: Vec<&String> = scopes.channels.keys().collect();
+        channels.sort();
+        let top = (
+            scopes.read.as_slice(),
+            scopes.write.as_slice(),
+            scopes.default_write.as_str(),
+        );
+        let rule_sets = std::iter::once(("memory.scopes".to_string(), top)).chain(
+            channels
+                .into_iter()
+                .map(|c| (format!("memory.scopes.channels.{c}"), scopes.rules_for(c))),
+        );
+        for (field, (read, write, default_write)) in rule_sets {
+            let lists = [("read", read), ("write", write)];
+            for (name, list) in lists {
+                for scope in list.iter().filter(|s| !MEMORY_SCOPES.contains(&s.as_str())) {
+                    warnings.push(ConfigWarning {
+                        field: format!("{field}.{name}"),
+                        message: format!("unknown memory scope '{scope}'"),
+                        severity: WarningSeverity::Error,
+                        hint: Some(format!("Valid scopes: {}", MEMORY_SCOPES.join(", "))),
+                    });
+                }
+            }
+            if !write.is_empty() && !write.iter().any(|s| s == default_write) {
+                warnings.push(ConfigWarning {
+                    field: format!("{field}.default_write"),
+                    message: format!("default_write '{default_write}' is not in write"),
+                    severity: WarningSeverity::Error,
+                    hint: Some(format!("Add it to write, which is [{}]", write.join(", "))),
+                });
+            }
+            if write.iter().any(|w| !read.contains(w)) {
+                warnings.push(ConfigWarning {
+                    field: format!("{field}.read"),
+                    message: "some writable scopes can't be read back".into(),
+                    severity: WarningSeverity::Warning,
+                    hint: Some("Facts stored there won't be recalled".into()),
+                });
+            }
+        }
+        for id in scopes.identities.keys().filter(|id| !id.contains(':')) {
+            warnings.push(ConfigWarning {
+                field: "memory.scopes.identities".into(),
+                message: format!("'{id}' is not a 'channel:sender' id"),
+                severity: WarningSeverity::Warning,
+                hint: Some("e.g. \"telegram:123456789\" = \"alice\"".into()),
+            });
+        }
+
+        // ── Routing ───
+        if !ROUTING_POLICIES.contains(&self.routing.policy.as_str()) {
+            warnings.push(ConfigWarning {
+                field: "routing.policy".into(),
+                message: format!("unknown routing policy '{}'", self.routing.policy),
+                severity: WarningSeverity::Error,
+                hint: Some(format!("Valid policies: {}", ROUTING_POLICIES.join(", "))),
+            });
+        } else if self.routing.policy == "cascade" && self.agent.fast_model.is_none() {
+            warnings.push(ConfigWarning {
+                field: "routing.policy".into(),
+                message: "cascade routing needs agent.fast_model — using agent.model only".into(),
+                severity: WarningSeverity::Warning,
+                hint: Some("Set agent.fast_model, e.g. 'anthropic/claude-haiku-4-5'".into()),
+            });
+        }
+        if self.routing.max_cost_per_request_usd < 0.0 {
+            warnings.push(ConfigWarning {
+                field: "routing.max_cost_per_request_usd".into(),
+                message: "per-request cost cap is negative".into(),
+                severity: WarningSeverity::Error,
+                hint: Some("Use 0 to disable the cap".into()),
+            });
+        }
+        let routed_models = self
+            .routing
+            .tasks
+            .iter()
+            .map(|(task, m)| (format!("routing.tasks.{task}"), m))
+            .chain(
+                self.routing
+                    .candidates
+                    .iter()
+                    .map(|m| ("routing.candidates".to_string(), m)),
+            );
+        for (field, m) in routed_models {
+            if let Some((prefix, _)) = m.split_once('/')
+                && !self.is_known_provider(prefix)
+            {
+                warnings.push(ConfigWarning {
+                    field,
+                    message: format!("unknown provider prefix '{prefix}' in '{m}'"),
+                    severity: WarningSeverity::Warning,
+                    hint: Some(format!(
+                        "Add a [providers.{prefix}] section for custom endpoints"
+                    )),
+                });
+            }
+        }
+        for task in self.routing.tasks.keys() {
+            if !ROUTING_TASKS.contains(&task.as_str()) && !task.starts_with("sub_agent.") {
+                warnings.push(ConfigWarning {
+                    field: format!("routing.tasks.{task}"),
+                    message: format!("unknown task class '{task}'"),
+                    severity: WarningSeverity::Warning,
+                    hint: Some(format!(
+                        "Valid task classes: {}, sub_agent.<role>",
+                        ROUTING_TASKS.join(", ")
+                    )),
+                });
+            }
+        }
+
         // ── Temperature ───
         if self.agent.temperature < 0.0 || self.agent.temperature > 2.0 {
             warnings.push(ConfigWarning {
@@ -583,6 +1316,34 @@ impl ClawConfig {
             });
         }
 
+        // ── Sandbox ───
+        if self.runtime.sandbox != SandboxMode::None && !cfg!(target_os = "linux") {
+            warnings.push(ConfigWarning {
+                field: "runtime.sandbox".into(),
+                message: format!(
+                    "sandbox \"{}\" needs Linux — the agent won't start",
+                    self.runtime.sandbox
+                ),
+                severity: WarningSeverity::Error,
+                hint: Some("Set runtime.sandbox = \"none\" on this platform".into()),
+            });
+        } else if self.runtime.sandbox == SandboxMode::None
+            && (3..=4).contains(&self.autonomy.level)
+            && cfg!(target_os = "linux")
+        {
+            warnings.push(ConfigWarning {
+                field: "runtime.sandbox".into(),
+                message: format!(
+                    "shell commands run unsandboxed at autonomy level {}",
+                    self.autonomy.level
+                ),
+                severity: WarningSeverity::Info,
+                hint: Some(
+                    "Set runtime.sandbox = \"landlock\" or \"namespaces\" to confine them".into(),
+                ),
+            });
+        }
+
         // ── Server listen address ───
         if self.server.listen.is_empty() {
             warnings.push(ConfigWarning {
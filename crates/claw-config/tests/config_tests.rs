@@ -202,4 +202,353 @@ model = "anthropic/claude-opus-4-20250514"
         let restored: ClawConfig = serde_json::from_str(&json).unwrap();
         assert_eq!(restored.agent.model, config.agent.model);
     }
+
+    // ── Custom providers ───────────────────────────────────────
+
+    #[test]
+    fn test_providers_deserialize() {
+        let toml_str = r#"
+[agent]
+model = "groq/llama-3.3-70b"
+
+[providers.groq]
+base_url = "https://api.groq.com/openai/v1"
+api_key_env = "CLAW_TEST_GROQ_KEY_UNSET"
+models = ["llama-3.3-70b"]
+context_window = 131072
+input_cost_per_m = 0.59
+output_cost_per_m = 0.79
+
+[providers.proxy]
+api = "anthropic"
+base_url = "http://localhost:8080/v1"
+api_key = "inline-key"
+"#;
+        let config: ClawConfig = toml::from_str(toml_str).unwrap();
+        let groq = &config.providers["groq"];
+        assert_eq!(groq.api, "openai");
+        assert_eq!(groq.models, vec!["llama-3.3-70b"]);
+        assert_eq!(groq.pricing(), Some((0.59, 0.79)));
+        assert_eq!(groq.resolve_api_key(), None);
+
+        let proxy = &config.providers["proxy"];
+        assert_eq!(proxy.api, "anthropic");
+        assert_eq!(proxy.resolve_api_key().as_deref(), Some("inline-key"));
+        assert_eq!(proxy.pricing(), None);
+
+        assert!(config.is_known_provider("groq"));
+        assert!(config.is_known_provider("anthropic"));
+        assert!(!config.is_known_provider("deepseek"));
+    }
+
+    #[test]
+    fn test_context_window_for_custom_provider() {
+        let mut config = ClawConfig::default();
+        config.providers.insert(
+            "groq".into(),
+            ProviderConfig {
+                base_url: "https://api.groq.com/openai/v1".into(),
+                context_window: 131_072,
+                ..Default::default()
+            },
+        );
+        assert_eq!(config.context_window_for("groq/llama-3.3-70b"), 131_072);
+        // Unknown to [providers] — inferred from the model name
+        assert_eq!(
+            config.context_window_for("anthropic/claude-sonnet-4"),
+            200_000
+        );
+        // Explicit agent.context_window always wins
+        config.agent.context_window = 32_000;
+        assert_eq!(config.context_window_for("groq/llama-3.3-70b"), 32_000);
+    }
+
+    #[test]
+    fn test_validate_flags_unknown_provider_prefix() {
+        let mut config = ClawConfig::default();
+        config.agent.model = "deepseek/deepseek-chat".into();
+        let warnings = config.validate().unwrap();
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.field == "agent.model" && w.message.contains("deepseek"))
+        );
+
+        config.providers.insert(
+            "deepseek".into(),
+            ProviderConfig {
+                base_url: "https://api.deepseek.com/v1".into(),
+                api_key_env: Some("DEEPSEEK_API_KEY".into()),
+                ..Default::default()
+            },
+        );
+        let warnings = config.validate().unwrap();
+        assert!(!warnings.iter().any(|w| w.field == "agent.model"));
+    }
+
+    #[test]
+    fn test_validate_provider_without_base_url_is_error() {
+        let mut config = ClawConfig::default();
+        config
+            .providers
+            .insert("broken".into(), ProviderConfig::default());
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("providers.broken.base_url"));
+    }
+
+    #[test]
+    fn test_validate_embedding_provider() {
+        let mut config = ClawConfig::default();
+        assert_eq!(config.memory.embedding_provider, "builtin");
+        assert!(config.validate().is_ok());
+
+        config.memory.embedding_provider = "word2vec".into();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("memory.embedding_provider"));
+
+        config.memory.embedding_provider = "builtin".into();
+        config.memory.embedding_dims = 0;
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("memory.embedding_dims"));
+    }
+
+    #[test]
+    fn test_builtin_provider_entry_sets_rate_limits_only() {
+        let config: ClawConfig = toml::from_str(
+            r#"
+            [providers.anthropic]
+            requests_per_minute = 50
+            tokens_per_minute = 40000
+            "#,
+        )
+        .unwrap();
+        let anthropic = &config.providers["anthropic"];
+        assert!(anthropic.is_limits_only("anthropic"));
+        assert_eq!(anthropic.requests_per_minute, 50);
+        assert_eq!(anthropic.tokens_per_minute, 40_000);
+        let warnings = config.validate().unwrap();
+        assert!(!warnings.iter().any(|w| w.field.starts_with("providers.")));
+    }
+
+    // ── Model catalog ──────────────────────────────────────────
+
+    #[test]
+    fn test_catalog_lookup_matches_snapshots_and_prefixes() {
+        use claw_config::ModelCatalog;
+        let catalog = ModelCatalog::builtin();
+
+        let sonnet = catalog.get("anthropic/claude-sonnet-4-20250514").unwrap();
+        assert_eq!(sonnet.id, "claude-sonnet-4");
+        assert_eq!(sonnet.context_window, 200_000);
+        assert!(sonnet.vision && sonnet.tools);
+
+        // Longest prefix wins at a boundary; no partial-word matches
+        assert_eq!(
+            catalog.get("gpt-4o-mini-2024-07-18").unwrap().id,
+            "gpt-4o-mini"
+        );
+        assert_eq!(catalog.get("openai/gpt-4o").unwrap().id, "gpt-4o");
+        assert_eq!(catalog.get("ollama/llama3.1:8b").unwrap().id, "llama3.1");
+        assert!(catalog.get("gpt-4oz").is_none());
+        assert!(catalog.get("totally-unknown-model").is_none());
+
+        assert!(
+            catalog
+                .models_for("google")
+                .contains(&"gemini-2.5-pro".to_string())
+        );
+    }
+
+    #[test]
+    fn test_catalog_overrides_merge_field_by_field() {
+        use claw_config::ModelCatalog;
+        let overrides = r#"
+[models."gpt-4o"]
+context_window = 64000
+
+[models."my-local-model"]
+provider = "ollama"
+context_window = 16384
+max_output = 4096
+"#;
+        let builtin = include_str!("../models.toml");
+        let catalog = ModelCatalog::parse(&[builtin, overrides]).unwrap();
+
+        let gpt = catalog.get("gpt-4o").unwrap();
+        assert_eq!(gpt.context_window, 64_000);
+        // Untouched fields keep their built-in values
+        assert_eq!(gpt.input_cost_per_m, 2.50);
+        assert!(gpt.vision);
+
+        let local = catalog.get("my-local-model").unwrap();
+        assert_eq!(local.provider, "ollama");
+        assert_eq!(local.max_output, 4096);
+
+        assert!(ModelCatalog::parse(&[builtin, "models = 3"]).is_err());
+    }
+
+    #[test]
+    fn test_catalog_pricing_defaults_cache_rates_to_input() {
+        let catalog = claw_config::ModelCatalog::builtin();
+        let pricing = catalog.get("gpt-4-turbo").unwrap().pricing();
+        assert_eq!(pricing.cache_read, pricing.input);
+        let pricing = catalog.get("claude-haiku-3-5").unwrap().pricing();
+        assert_eq!(pricing.cache_read, 0.08);
+        // 1M input + 1M output + 1M cache reads
+        assert!((pricing.cost(1_000_000, 1_000_000, 1_000_000, 0) - 4.88).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_context_window_and_max_tokens_from_catalog() {
+        let mut config = ClawConfig::default();
+        assert_eq!(config.context_window_for("openai/gpt-4o"), 128_000);
+        assert_eq!(
+            config.context_window_for("google/gemini-2.5-pro"),
+            1_048_576
+        );
+        assert_eq!(
+            config.context_window_for("mystery/unknown-model"),
+            claw_config::DEFAULT_CONTEXT_WINDOW
+        );
+
+        // agent.max_tokens is capped at the model's output limit
+        config.agent.max_tokens = 16_384;
+        assert_eq!(config.max_tokens_for("anthropic/claude-haiku-3-5"), 8_192);
+        assert_eq!(config.max_tokens_for("anthropic/claude-sonnet-4"), 16_384);
+        assert_eq!(config.max_tokens_for("mystery/unknown-model"), 16_384);
+    }
+
+    #[test]
+    fn test_validate_warns_on_unknown_model() {
+        let mut config = ClawConfig::default();
+        config.agent.model = "openai/gpt-9-ultra".into();
+        let warnings = config.validate().unwrap();
+        assert!(warnings.iter().any(|w| w.field == "agent.model"
+            && w.severity == WarningSeverity::Warning
+            && w.message.contains("not in the model catalog")));
+
+        // An explicit context window silences it
+        config.agent.context_window = 64_000;
+        let warnings = config.validate().unwrap();
+        assert!(!warnings.iter().any(|w| w.field == "agent.model"));
+    }
+
+    // ── Routing ────────────────────────────────────────────────
+
+    #[test]
+    fn test_model_for_task_uses_routing_tasks_then_defaults() {
+        let mut config = ClawConfig::default();
+        config.agent.model = "anthropic/claude-sonnet-4".into();
+        config.agent.fast_model = Some("anthropic/claude-haiku-4-5".into());
+        assert_eq!(
+            config.model_for_task(TaskClass::Compaction),
+            "anthropic/claude-haiku-4-5"
+        );
+        assert_eq!(
+            config.model_for_task(TaskClass::SubAgent("researcher")),
+            "anthropic/claude-sonnet-4"
+        );
+
+        let toml_str = r#"
+[routing.tasks]
+lesson_extraction = "openai/gpt-4o-mini"
+sub_agent = "openai/gpt-4.1"
+"sub_agent.coder" = "anthropic/claude-opus-4-1"
+"#;
+        config.routing = toml::from_str::<ClawConfig>(toml_str).unwrap().routing;
+        assert_eq!(config.routing.policy, "primary");
+        assert_eq!(
+            config.model_for_task(TaskClass::LessonExtraction),
+            "openai/gpt-4o-mini"
+        );
+        assert_eq!(
+            config.model_for_task(TaskClass::SubAgent("coder")),
+            "anthropic/claude-opus-4-1"
+        );
+        assert_eq!(
+            config.model_for_task(TaskClass::SubAgent("researcher")),
+            "openai/gpt-4.1"
+        );
+
+        config.agent.fast_model = None;
+        assert_eq!(
+            config.model_for_task(TaskClass::Title),
+            "anthropic/claude-sonnet-4"
+        );
+    }
+
+    #[test]
+    fn test_validate_routing() {
+        let mut config = ClawConfig::default();
+        config.routing.policy = "fastest".into();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("routing.policy"));
+
+        config.routing.policy = "cascade".into();
+        config.agent.fast_model = None;
+        config
+            .routing
+            .tasks
+            .insert("summaries".into(), "openai/gpt-4o-mini".into());
+        config
+            .routing
+            .tasks
+            .insert("sub_agent.coder".into(), "nowhere/model".into());
+        let warnings = config.validate().unwrap();
+        assert!(warnings.iter().any(|w| w.field == "routing.policy"));
+        assert!(
+            warnings.iter().any(|w| w.field == "routing.tasks.summaries"
+                && w.message.contains("unknown task class"))
+        );
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.field == "routing.tasks.sub_agent.coder"
+                    && w.message.contains("unknown provider prefix"))
+        );
+    }
+
+    #[test]
+    fn test_memory_scopes_channel_overrides_and_validation() {
+        let toml_str = r#"
+[memory.scopes]
+default_write = "channel"
+
+[memory.scopes.channels.telegram]
+read = ["global", "user"]
+write = ["user"]
+default_write = "user"
+
+[memory.scopes.identities]
+"telegram:42" = "alice"
+"#;
+        let config: ClawConfig = toml::from_str(toml_str).unwrap();
+        let scopes = &config.memory.scopes;
+        let (read, write, default_write) = scopes.rules_for("telegram");
+        assert_eq!(read, ["global", "user"]);
+        assert_eq!(write, ["user"]);
+        assert_eq!(default_write, "user");
+        let (read, _, default_write) = scopes.rules_for("slack");
+        assert_eq!(read.len(), 4);
+        assert_eq!(default_write, "channel");
+        assert_eq!(scopes.identity("telegram", "42"), "alice");
+        assert_eq!(scopes.identity("slack", "U1"), "slack:U1");
+        assert!(config.validate().is_ok());
+
+        let mut config = config;
+        config
+            .memory
+            .scopes
+            .channels
+            .get_mut("telegram")
+            .unwrap()
+            .default_write = Some("global".into());
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("memory.scopes.channels.telegram.default_write"));
+
+        let mut config = ClawConfig::default();
+        config.memory.scopes.read.push("tenant".into());
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("unknown memory scope 'tenant'"));
+    }
 }
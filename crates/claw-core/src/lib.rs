@@ -11,6 +11,6 @@ pub mod types;
 
 pub use error::{ClawError, Result};
 pub use event::{Event, EventBus};
-pub use message::{Message, MessageContent, Role};
+pub use message::{IMAGE_TOKEN_ESTIMATE, Message, MessageContent, Role};
 pub use tool::{Tool, ToolCall, ToolExecutor, ToolResult};
 pub use types::*;
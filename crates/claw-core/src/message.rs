@@ -36,7 +36,7 @@ pub enum MessageContent {
         text: String,
     },
     Image {
-        /// Base64‐encoded image data or a URL.
+        /// Base64‐encoded image data, a `data:`/http(s) URL, or a local file path.
         data: String,
         media_type: String,
     },
@@ -84,8 +84,10 @@ impl Message {
     /// Estimate token count for this message.
     /// Uses a simple heuristic: ~4 chars per token for English text.
     /// Includes tool call arguments and tool result content.
+    /// Images count as a flat [`IMAGE_TOKEN_ESTIMATE`] regardless of encoding.
     pub fn estimate_tokens(&self) -> usize {
         let mut chars = 0usize;
+        let mut image_tokens = 0usize;
 
         // Role overhead (~4 tokens for role markers)
         chars += 16;
@@ -102,9 +104,15 @@ impl Message {
                     chars += content.len();
                     chars += tool_call_id.len();
                 }
-                MessageContent::Image { data, .. } => chars += data.len().min(1000),
+                MessageContent::Image { .. } => image_tokens += IMAGE_TOKEN_ESTIMATE,
                 MessageContent::Audio { data, .. } => chars += data.len().min(1000),
-                MessageContent::File { path, .. } => chars += path.len(),
+                MessageContent::File { path, media_type } => {
+                    if is_image_file(path, media_type.as_deref()) {
+                        image_tokens += IMAGE_TOKEN_ESTIMATE;
+                    } else {
+                        chars += path.len();
+                    }
+                }
             }
         }
 
@@ -116,6 +124,25 @@ impl Message {
         }
 
         // ~4 chars per token, minimum 1
-        (chars / 4).max(1)
+        (chars / 4).max(1) + image_tokens
+    }
+}
+
+/// Token cost charged per attached image.
+///
+/// Vision APIs bill images by pixel area, not by payload size: Anthropic
+/// charges ~(width × height) / 750 and downscales to ~1.15 MP (≈1,600 tokens),
+/// OpenAI high-detail is 85 + 170 per 512px tile (≈765–1,105 for typical
+/// screenshots). We use the upper bound so compaction triggers early enough.
+pub const IMAGE_TOKEN_ESTIMATE: usize = 1_600;
+
+/// Whether a file attachment will be sent to the model as an image.
+pub fn is_image_file(path: &str, media_type: Option<&str>) -> bool {
+    if media_type.is_some_and(|mt| mt.starts_with("image/")) {
+        return true;
     }
+    let lower = path.to_lowercase();
+    [".png", ".jpg", ".jpeg", ".gif", ".webp"]
+        .iter()
+        .any(|ext| lower.ends_with(ext))
 }
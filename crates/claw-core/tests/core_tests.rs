@@ -234,4 +234,38 @@ mod tests {
             let _restored: MessageContent = serde_json::from_str(&json).unwrap();
         }
     }
+
+    #[test]
+    fn test_estimate_tokens_images_flat_cost() {
+        let text_only = Message::text(Uuid::nil(), Role::User, "look");
+        let base = text_only.estimate_tokens();
+
+        // A huge base64 payload costs the same as a short path — billing is by pixels
+        let mut big = text_only.clone();
+        big.content.push(MessageContent::Image {
+            data: "A".repeat(2_000_000),
+            media_type: "image/png".into(),
+        });
+        let mut path = text_only.clone();
+        path.content.push(MessageContent::Image {
+            data: "/api/v1/screenshots/browser_1.png".into(),
+            media_type: "image/png".into(),
+        });
+        assert_eq!(big.estimate_tokens(), base + IMAGE_TOKEN_ESTIMATE);
+        assert_eq!(path.estimate_tokens(), base + IMAGE_TOKEN_ESTIMATE);
+
+        // Image files count as images; other files only by path length
+        let mut file_img = text_only.clone();
+        file_img.content.push(MessageContent::File {
+            path: "/home/u/.claw/downloads/telegram/photo.JPG".into(),
+            media_type: None,
+        });
+        assert_eq!(file_img.estimate_tokens(), base + IMAGE_TOKEN_ESTIMATE);
+        let mut file_doc = text_only.clone();
+        file_doc.content.push(MessageContent::File {
+            path: "/tmp/report.pdf".into(),
+            media_type: Some("application/pdf".into()),
+        });
+        assert!(file_doc.estimate_tokens() < base + 10);
+    }
 }
@@ -1,8 +1,10 @@
 use async_trait::async_trait;
+use claw_config::ModelPricing;
 use claw_core::Result;
 use reqwest::Client;
 use tracing::{debug, info};
 
+use crate::media::{self, ImageSource};
 use crate::provider::*;
 
 /// Anthropic Claude API provider.
@@ -10,6 +12,13 @@ pub struct AnthropicProvider {
     client: Client,
     api_key: String,
     base_url: String,
+    provider_name: String,
+    /// Model list override for custom endpoints (empty = built-in Claude list).
+    custom_models: Vec<String>,
+    /// Per-million (input, output) pricing override.
+    pricing: Option<(f64, f64)>,
+    /// Mark system, tools and the conversation prefix as cacheable.
+    prompt_caching: bool,
 }
 
 impl AnthropicProvider {
@@ -18,6 +27,10 @@ impl AnthropicProvider {
             client: Client::new(),
             api_key,
             base_url: "https://api.anthropic.com/v1".into(),
+            provider_name: "anthropic".into(),
+            custom_models: vec![],
+            pricing: None,
+            prompt_caching: true,
         }
     }
 
@@ -26,11 +39,52 @@ impl AnthropicProvider {
         self
     }
 
+    /// Register under a different provider name (routing prefix), e.g. for a proxy.
+    pub fn with_name(mut self, name: String) -> Self {
+        self.provider_name = name;
+        self
+    }
+
+    /// Advertise the models served by a custom endpoint.
+    pub fn with_models(mut self, models: Vec<String>) -> Self {
+        self.custom_models = models;
+        self
+    }
+
+    /// Override cost estimation with per-million-token (input, output) pricing.
+    pub fn with_pricing(mut self, input_per_m: f64, output_per_m: f64) -> Self {
+        self.pricing = Some((input_per_m, output_per_m));
+        self
+    }
+
+    /// Enable or disable prompt caching (`cache_control` breakpoints).
+    pub fn with_prompt_caching(mut self, enabled: bool) -> Self {
+        self.prompt_caching = enabled;
+        self
+    }
+
     fn build_request_body(&self, request: &LlmRequest) -> serde_json::Value {
         let mut messages = Vec::new();
         for msg in &request.messages {
             match msg.role {
                 claw_core::Role::System => continue, // handled via top-level "system" field
+                claw_core::Role::User if media::has_media(msg) => {
+                    let (images, notes) = media::message_media(msg);
+                    let mut content_blocks: Vec<serde_json::Value> =
+                        images.iter().map(image_block).collect();
+                    let mut text = msg.text_content();
+                    for note in notes {
+                        text.push_str(&format!("\n{note}"));
+                    }
+                    let text = text.trim();
+                    if !text.is_empty() {
+                        content_blocks.push(serde_json::json!({ "type": "text", "text": text }));
+                    }
+                    messages.push(serde_json::json!({
+                        "role": "user",
+                        "content": content_blocks,
+                    }));
+                }
                 claw_core::Role::User => {
                     messages.push(serde_json::json!({
                         "role": "user",
@@ -85,6 +139,20 @@ impl AnthropicProvider {
                             }));
                         }
                     }
+                    // Attached images (e.g. screenshots) ride inside the tool_result
+                    if media::has_media(msg)
+                        && let Some(last) = content_blocks.last_mut()
+                    {
+                        let (images, _) = media::message_media(msg);
+                        if !images.is_empty() {
+                            let mut inner = vec![serde_json::json!({
+                                "type": "text",
+                                "text": last["content"].as_str().unwrap_or_default(),
+                            })];
+                            inner.extend(images.iter().map(image_block));
+                            last["content"] = serde_json::json!(inner);
+                        }
+                    }
                     if content_blocks.is_empty() {
                         // Fallback: send as plain user message
                         messages.push(serde_json::json!({
@@ -101,6 +169,14 @@ impl AnthropicProvider {
             }
         }
 
+        // Conversation prefix: everything up to the newest message is stable
+        // across agent-loop iterations, so cache through its last block.
+        if self.prompt_caching
+            && let Some(last) = messages.last_mut()
+        {
+            add_cache_breakpoint(last);
+        }
+
         let mut body = serde_json::json!({
             "model": &request.model,
             "max_tokens": request.max_tokens,
@@ -109,28 +185,51 @@ impl AnthropicProvider {
         });
 
         if let Some(ref system) = request.system {
-            body["system"] = serde_json::json!(system);
+            body["system"] = if self.prompt_caching {
+                serde_json::json!([{
+                    "type": "text",
+                    "text": system,
+                    "cache_control": { "type": "ephemeral" },
+                }])
+            } else {
+                serde_json::json!(system)
+            };
         }
 
         // Tool definitions
-        if !request.tools.is_empty() {
-            let tools: Vec<serde_json::Value> = request
-                .tools
-                .iter()
-                .map(|t| {
-                    serde_json::json!({
-                        "name": t.name,
-                        "description": t.description,
-                        "input_schema": t.parameters,
-                    })
+        let mut tools: Vec<serde_json::Value> = request
+            .tools
+            .iter()
+            .map(|t| {
+                serde_json::json!({
+                    "name": t.name,
+                    "description": t.description,
+                    "input_schema": t.parameters,
                 })
-                .collect();
+            })
+            .collect();
+        // Structured output: force a call to a tool whose input is the schema
+        if let Some(ref format) = request.response_format {
+            tools.push(serde_json::json!({
+                "name": format.name,
+                "description": "Return the final answer in this exact structure.",
+                "input_schema": format.schema,
+            }));
+            body["tool_choice"] = serde_json::json!({ "type": "tool", "name": format.name });
+        }
+        if !tools.is_empty() {
             body["tools"] = serde_json::json!(tools);
+            if self.prompt_caching
+                && let Some(last) = body["tools"].as_array_mut().and_then(|t| t.last_mut())
+            {
+                last["cache_control"] = serde_json::json!({ "type": "ephemeral" });
+            }
         }
 
-        // Extended thinking
+        // Extended thinking (not allowed together with a forced tool_choice)
         if let Some(ref level) = request.thinking_level
             && level != "off"
+            && request.response_format.is_none()
         {
             let budget = match level.as_str() {
                 "low" => 2048,
@@ -152,16 +251,14 @@ impl AnthropicProvider {
 #[async_trait]
 impl LlmProvider for AnthropicProvider {
     fn name(&self) -> &str {
-        "anthropic"
+        &self.provider_name
     }
 
     fn models(&self) -> Vec<String> {
-        vec![
-            "claude-opus-4-6".into(),
-            "claude-opus-4-20250514".into(),
-            "claude-sonnet-4-20250514".into(),
-            "claude-haiku-3-5".into(),
-        ]
+        if !self.custom_models.is_empty() {
+            return self.custom_models.clone();
+        }
+        claw_config::catalog().models_for("anthropic")
     }
 
     async fn complete(&self, request: &LlmRequest) -> Result<LlmResponse> {
@@ -180,16 +277,7 @@ impl LlmProvider for AnthropicProvider {
             .map_err(|e| claw_core::ClawError::LlmProvider(e.to_string()))?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            if status.as_u16() == 429 {
-                return Err(claw_core::ClawError::RateLimited {
-                    retry_after_secs: 30,
-                });
-            }
-            return Err(claw_core::ClawError::LlmProvider(format!(
-                "HTTP {status}: {text}"
-            )));
+            return Err(crate::ratelimit::error_from_response(resp).await);
         }
 
         let data: serde_json::Value = resp
@@ -197,8 +285,10 @@ impl LlmProvider for AnthropicProvider {
             .await
             .map_err(|e| claw_core::ClawError::LlmProvider(e.to_string()))?;
 
+        let structured_tool = request.response_format.as_ref().map(|f| f.name.as_str());
+
         // Parse the response into our standard format
-        let content_text = data["content"]
+        let mut content_text = data["content"]
             .as_array()
             .map(|blocks| {
                 blocks
@@ -222,7 +312,7 @@ impl LlmProvider for AnthropicProvider {
                 blocks
                     .iter()
                     .filter_map(|b| {
-                        if b["type"] == "tool_use" {
+                        if b["type"] == "tool_use" && b["name"].as_str() != structured_tool {
                             Some(claw_core::ToolCall {
                                 id: b["id"].as_str().unwrap_or("").to_string(),
                                 tool_name: b["name"].as_str().unwrap_or("").to_string(),
@@ -236,18 +326,38 @@ impl LlmProvider for AnthropicProvider {
             })
             .unwrap_or_default();
 
+        // Structured output arrives as the forced tool's input
+        if let Some(name) = structured_tool
+            && let Some(block) = data["content"].as_array().and_then(|blocks| {
+                blocks
+                    .iter()
+                    .find(|b| b["type"] == "tool_use" && b["name"] == name)
+            })
+        {
+            content_text = block["input"].to_string();
+        }
+
         let has_tool_calls = !tool_calls.is_empty();
 
         let stop_reason = match data["stop_reason"].as_str() {
-            Some("tool_use") => StopReason::ToolUse,
+            Some("tool_use") if has_tool_calls => StopReason::ToolUse,
+            Some("tool_use") => StopReason::EndTurn,
             Some("max_tokens") => StopReason::MaxTokens,
             Some("stop_sequence") => StopReason::StopSequence,
             _ => StopReason::EndTurn,
         };
 
         let usage_data = &data["usage"];
-        let input_tokens = usage_data["input_tokens"].as_u64().unwrap_or(0) as u32;
-        let output_tokens = usage_data["output_tokens"].as_u64().unwrap_or(0) as u32;
+        let mut usage = Usage {
+            input_tokens: usage_data["input_tokens"].as_u64().unwrap_or(0) as u32,
+            output_tokens: usage_data["output_tokens"].as_u64().unwrap_or(0) as u32,
+            cache_read_tokens: usage_data["cache_read_input_tokens"].as_u64().unwrap_or(0) as u32,
+            cache_write_tokens: usage_data["cache_creation_input_tokens"]
+                .as_u64()
+                .unwrap_or(0) as u32,
+            ..Default::default()
+        };
+        usage.estimated_cost_usd = estimate_anthropic_cost(&request.model, self.pricing, &usage);
 
         let mut message =
             claw_core::Message::text(uuid::Uuid::nil(), claw_core::Role::Assistant, content_text);
@@ -255,21 +365,7 @@ impl LlmProvider for AnthropicProvider {
 
         Ok(LlmResponse {
             message,
-            usage: Usage {
-                input_tokens,
-                output_tokens,
-                thinking_tokens: 0,
-                cache_read_tokens: usage_data["cache_read_input_tokens"].as_u64().unwrap_or(0)
-                    as u32,
-                cache_write_tokens: usage_data["cache_creation_input_tokens"]
-                    .as_u64()
-                    .unwrap_or(0) as u32,
-                estimated_cost_usd: estimate_anthropic_cost(
-                    &request.model,
-                    input_tokens,
-                    output_tokens,
-                ),
-            },
+            usage,
             has_tool_calls,
             stop_reason,
         })
@@ -286,6 +382,8 @@ impl LlmProvider for AnthropicProvider {
         let base_url = self.base_url.clone();
         let api_key = self.api_key.clone();
         let model = request.model.clone();
+        let pricing = self.pricing;
+        let structured_tool = request.response_format.as_ref().map(|f| f.name.clone());
 
         tokio::spawn(async move {
             let resp = client
@@ -307,8 +405,7 @@ impl LlmProvider for AnthropicProvider {
                     let mut current_tool_name = String::new();
                     let mut current_tool_input = String::new();
                     let mut in_tool_input = false;
-                    let mut input_tokens = 0u32;
-                    let mut output_tokens = 0u32;
+                    let mut usage = Usage::default();
                     let mut stop_reason = StopReason::EndTurn;
                     let mut has_tool_calls = false;
 
@@ -335,15 +432,18 @@ impl LlmProvider for AnthropicProvider {
 
                                     match event["type"].as_str() {
                                         Some("message_start") => {
-                                            // Extract usage from message_start
-                                            if let Some(usage) =
-                                                event["message"]["usage"].as_object()
-                                                && let Some(it) = usage
-                                                    .get("input_tokens")
-                                                    .and_then(|v| v.as_u64())
-                                            {
-                                                input_tokens = it as u32;
-                                            }
+                                            // Extract usage (incl. cache hits) from message_start
+                                            let u = &event["message"]["usage"];
+                                            usage.input_tokens =
+                                                u["input_tokens"].as_u64().unwrap_or(0) as u32;
+                                            usage.cache_read_tokens =
+                                                u["cache_read_input_tokens"].as_u64().unwrap_or(0)
+                                                    as u32;
+                                            usage.cache_write_tokens =
+                                                u["cache_creation_input_tokens"]
+                                                    .as_u64()
+                                                    .unwrap_or(0)
+                                                    as u32;
                                         }
                                         Some("content_block_start") => {
                                             let cb = &event["content_block"];
@@ -387,23 +487,32 @@ impl LlmProvider for AnthropicProvider {
                                                 _ => {}
                                             }
                                         }
-                                        Some("content_block_stop") => {
-                                            if in_tool_input {
-                                                let arguments: serde_json::Value =
-                                                    serde_json::from_str(&current_tool_input)
-                                                        .unwrap_or_default();
-                                                let _ = tx
-                                                    .send(StreamChunk::ToolCall(
-                                                        claw_core::ToolCall {
-                                                            id: current_tool_id.clone(),
-                                                            tool_name: current_tool_name.clone(),
-                                                            arguments,
-                                                        },
-                                                    ))
-                                                    .await;
-                                                has_tool_calls = true;
-                                                in_tool_input = false;
-                                            }
+                                        Some("content_block_stop")
+                                            if in_tool_input
+                                                && structured_tool.as_deref()
+                                                    == Some(current_tool_name.as_str()) =>
+                                        {
+                                            // Structured output — surface the JSON as text
+                                            let _ = tx
+                                                .send(StreamChunk::TextDelta(
+                                                    current_tool_input.clone(),
+                                                ))
+                                                .await;
+                                            in_tool_input = false;
+                                        }
+                                        Some("content_block_stop") if in_tool_input => {
+                                            let arguments: serde_json::Value =
+                                                serde_json::from_str(&current_tool_input)
+                                                    .unwrap_or_default();
+                                            let _ = tx
+                                                .send(StreamChunk::ToolCall(claw_core::ToolCall {
+                                                    id: current_tool_id.clone(),
+                                                    tool_name: current_tool_name.clone(),
+                                                    arguments,
+                                                }))
+                                                .await;
+                                            has_tool_calls = true;
+                                            in_tool_input = false;
                                         }
                                         Some("message_delta") => {
                                             if let Some(sr) = event["delta"]["stop_reason"].as_str()
@@ -415,30 +524,22 @@ impl LlmProvider for AnthropicProvider {
                                                     _ => StopReason::EndTurn,
                                                 };
                                             }
-                                            if let Some(usage) = event["usage"].as_object()
-                                                && let Some(ot) = usage
-                                                    .get("output_tokens")
-                                                    .and_then(|v| v.as_u64())
+                                            if let Some(ot) =
+                                                event["usage"]["output_tokens"].as_u64()
                                             {
-                                                output_tokens = ot as u32;
+                                                usage.output_tokens = ot as u32;
                                             }
                                         }
                                         Some("message_stop") => {
-                                            let cost = estimate_anthropic_cost(
-                                                &model,
-                                                input_tokens,
-                                                output_tokens,
-                                            );
-                                            let _ = tx
-                                                .send(StreamChunk::Usage(Usage {
-                                                    input_tokens,
-                                                    output_tokens,
-                                                    estimated_cost_usd: cost,
-                                                    ..Default::default()
-                                                }))
-                                                .await;
+                                            usage.estimated_cost_usd =
+                                                estimate_anthropic_cost(&model, pricing, &usage);
+                                            let _ =
+                                                tx.send(StreamChunk::Usage(usage.clone())).await;
                                             let final_stop = if has_tool_calls {
                                                 StopReason::ToolUse
+                                            } else if stop_reason == StopReason::ToolUse {
+                                                // Only the structured-output tool was called
+                                                StopReason::EndTurn
                                             } else {
                                                 stop_reason
                                             };
@@ -467,8 +568,7 @@ impl LlmProvider for AnthropicProvider {
                     let _ = tx.send(StreamChunk::Done(stop_reason)).await;
                 }
                 Ok(resp) => {
-                    let text = resp.text().await.unwrap_or_default();
-                    let _ = tx.send(StreamChunk::Error(text)).await;
+                    let _ = tx.send(crate::ratelimit::stream_error(resp).await).await;
                 }
                 Err(e) => {
                     let _ = tx.send(StreamChunk::Error(e.to_string())).await;
@@ -490,13 +590,62 @@ impl LlmProvider for AnthropicProvider {
     }
 }
 
-/// Estimate cost for Anthropic models (USD per 1M tokens).
-fn estimate_anthropic_cost(model: &str, input_tokens: u32, output_tokens: u32) -> f64 {
-    let (input_per_m, output_per_m) = match model {
-        m if m.contains("opus") => (15.00, 75.00),
-        m if m.contains("sonnet") => (3.00, 15.00),
-        m if m.contains("haiku") => (0.80, 4.00),
-        _ => (3.00, 15.00), // default to sonnet pricing
+/// Serialize an image as an Anthropic `image` content block.
+fn image_block(image: &ImageSource) -> serde_json::Value {
+    match image {
+        ImageSource::Base64 { media_type, data } => serde_json::json!({
+            "type": "image",
+            "source": { "type": "base64", "media_type": media_type, "data": data },
+        }),
+        ImageSource::Url(url) => serde_json::json!({
+            "type": "image",
+            "source": { "type": "url", "url": url },
+        }),
+    }
+}
+
+/// Attach a cache breakpoint to the last content block of a message,
+/// converting plain string content into a text block first.
+fn add_cache_breakpoint(message: &mut serde_json::Value) {
+    let cache_control = serde_json::json!({ "type": "ephemeral" });
+    if let Some(text) = message["content"].as_str() {
+        if text.is_empty() {
+            return;
+        }
+        message["content"] = serde_json::json!([{
+            "type": "text",
+            "text": text,
+            "cache_control": cache_control,
+        }]);
+    } else if let Some(last) = message["content"]
+        .as_array_mut()
+        .and_then(|blocks| blocks.last_mut())
+    {
+        last["cache_control"] = cache_control;
+    }
+}
+
+/// Estimate cost for Anthropic models from the model catalog.
+/// Configured `pricing` takes precedence; cache writes then bill at 1.25x the
+/// input rate and cache reads at 0.1x. `input_tokens` excludes cached tokens.
+fn estimate_anthropic_cost(model: &str, pricing: Option<(f64, f64)>, usage: &Usage) -> f64 {
+    let pricing = match pricing {
+        Some((input, output)) => ModelPricing {
+            input,
+            output,
+            cache_read: input * 0.1,
+            cache_write: input * 1.25,
+        },
+        None => claw_config::catalog()
+            .get(model)
+            .or_else(|| claw_config::catalog().get("claude-sonnet-4")) // default to sonnet pricing
+            .map(|m| m.pricing())
+            .unwrap_or(ModelPricing::flat(0.0, 0.0)),
     };
-    (input_tokens as f64 * input_per_m + output_tokens as f64 * output_per_m) / 1_000_000.0
+    pricing.cost(
+        usage.input_tokens,
+        usage.output_tokens,
+        usage.cache_read_tokens,
+        usage.cache_write_tokens,
+    )
 }
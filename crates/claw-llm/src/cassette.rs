@@ -0,0 +1,462 @@
+//! Record/replay LLM providers for deterministic integration tests.
+//!
+//! `RecordingProvider` wraps any real provider and tees every request and
+//! its response (or stream chunks) into a JSON cassette file.
+//! `ReplayProvider` serves a cassette back offline, matching requests by a
+//! hash of their normalized form — message/session ids, tool-call ids and any
+//! UUIDs inside text are ignored, so a re-run of the same conversation hits the
+//! same entries.
+//!
+//! # Example
+//! ```no_run
+//! use claw_llm::cassette::{RecordingProvider, ReplayProvider};
+//! # fn real_provider() -> std::sync::Arc<dyn claw_llm::LlmProvider> { unimplemented!() }
+//! // Once, against a real model:
+//! let recorder = RecordingProvider::new(real_provider(), "tests/cassettes/greet.json");
+//! // In CI:
+//! let replay = ReplayProvider::from_file("tests/cassettes/greet.json").unwrap();
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use claw_core::{ClawError, MessageContent, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::provider::*;
+
+/// A recorded set of provider interactions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    /// Name of the provider the cassette was recorded against (routing prefix).
+    pub provider: String,
+    pub interactions: Vec<Interaction>,
+}
+
+/// One request and what the provider returned for it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    /// Hash of the normalized request.
+    pub key: String,
+    /// The normalized request the key was derived from (handy when diffing a miss).
+    pub request: serde_json::Value,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+/// What the provider produced for a request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Outcome {
+    Complete { response: LlmResponse },
+    Stream { chunks: Vec<StreamChunk> },
+    Error { message: String },
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read_to_string(path)?;
+        serde_json::from_str(&data).map_err(|e| {
+            ClawError::LlmProvider(format!("invalid cassette {}: {e}", path.display()))
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+// ── Request normalization ──────────────────────────────────────
+
+/// Normalize a request into the JSON form used for matching.
+///
+/// Drops message ids, session ids, timestamps and the `stream` flag, renumbers
+/// tool-call ids in order of appearance and replaces UUIDs in text with
+/// `<uuid>`.
+pub fn normalize_request(request: &LlmRequest) -> serde_json::Value {
+    let mut call_ids: HashMap<String, String> = HashMap::new();
+    let mut placeholder = |id: &str| {
+        let next = format!("call_{}", call_ids.len());
+        call_ids.entry(id.to_string()).or_insert(next).clone()
+    };
+
+    let messages: Vec<serde_json::Value> = request
+        .messages
+        .iter()
+        .map(|msg| {
+            let content: Vec<serde_json::Value> = msg
+                .content
+                .iter()
+                .map(|block| match block {
+                    MessageContent::ToolResult {
+                        tool_call_id,
+                        content,
+                        is_error,
+                    } => serde_json::json!({
+                        "type": "tool_result",
+                        "tool_call_id": placeholder(tool_call_id),
+                        "content": content,
+                        "is_error": is_error,
+                    }),
+                    other => serde_json::to_value(other).unwrap_or_default(),
+                })
+                .collect();
+            let tool_calls: Vec<serde_json::Value> = msg
+                .tool_calls
+                .iter()
+                .map(|tc| {
+                    serde_json::json!({
+                        "id": placeholder(&tc.id),
+                        "name": tc.tool_name,
+                        "arguments": tc.arguments,
+                    })
+                })
+                .collect();
+            serde_json::json!({
+                "role": msg.role,
+                "content": content,
+                "tool_calls": tool_calls,
+            })
+        })
+        .collect();
+
+    let tools: Vec<serde_json::Value> = request
+        .tools
+        .iter()
+        .map(|t| {
+            serde_json::json!({
+                "name": t.name,
+                "description": t.description,
+                "parameters": t.parameters,
+            })
+        })
+        .collect();
+
+    let mut normalized = serde_json::json!({
+        "model": request.model,
+        "system": request.system,
+        "messages": messages,
+        "tools": tools,
+        "max_tokens": request.max_tokens,
+        "temperature": request.temperature,
+        "thinking_level": request.thinking_level,
+        "response_format": request.response_format,
+    });
+    scrub_uuids(&mut normalized);
+    normalized
+}
+
+/// Stable key for a request: a short BLAKE3 hash of its normalized form.
+pub fn request_key(request: &LlmRequest) -> String {
+    let normalized = normalize_request(request);
+    let hash = blake3::hash(normalized.to_string().as_bytes());
+    hash.to_hex()[..16].to_string()
+}
+
+fn scrub_uuids(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::String(s) if s.len() >= 36 => *s = replace_uuids(s),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(scrub_uuids),
+        serde_json::Value::Object(map) => map.values_mut().for_each(scrub_uuids),
+        _ => {}
+    }
+}
+
+/// Replace every 8-4-4-4-12 hex UUID in `text` with `<uuid>`.
+fn replace_uuids(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let is_uuid_at = |i: usize| {
+        i + 36 <= bytes.len()
+            && bytes[i..i + 36].iter().enumerate().all(|(j, b)| match j {
+                8 | 13 | 18 | 23 => *b == b'-',
+                _ => b.is_ascii_hexdigit(),
+            })
+    };
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if is_uuid_at(i) {
+            out.push_str(&text[last..i]);
+            out.push_str("<uuid>");
+            i += 36;
+            last = i;
+        } else {
+            i += 1;
+        }
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+// ── Recording ──────────────────────────────────────────────────
+
+/// Wraps a real provider and records every interaction to a cassette file.
+/// The file is rewritten after each interaction, so a crashed run still
+/// leaves a usable cassette behind.
+pub struct RecordingProvider {
+    inner: Arc<dyn LlmProvider>,
+    path: PathBuf,
+    cassette: Arc<Mutex<Cassette>>,
+}
+
+impl RecordingProvider {
+    /// Record into `path`, starting a fresh cassette.
+    pub fn new(inner: Arc<dyn LlmProvider>, path: impl Into<PathBuf>) -> Self {
+        let cassette = Cassette {
+            provider: inner.name().to_string(),
+            interactions: vec![],
+        };
+        Self {
+            inner,
+            path: path.into(),
+            cassette: Arc::new(Mutex::new(cassette)),
+        }
+    }
+
+    /// A snapshot of everything recorded so far.
+    pub fn cassette(&self) -> Cassette {
+        self.cassette.lock().unwrap().clone()
+    }
+
+    fn record(&self, request: &LlmRequest, outcome: Outcome) {
+        record_into(&self.cassette, &self.path, request, outcome);
+    }
+}
+
+fn record_into(cassette: &Mutex<Cassette>, path: &Path, request: &LlmRequest, outcome: Outcome) {
+    let mut cassette = cassette.lock().unwrap();
+    cassette.interactions.push(Interaction {
+        key: request_key(request),
+        request: normalize_request(request),
+        outcome,
+    });
+    if let Err(e) = cassette.save(path) {
+        warn!(path = %path.display(), error = %e, "failed to write cassette");
+    }
+}
+
+#[async_trait]
+impl LlmProvider for RecordingProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn models(&self) -> Vec<String> {
+        self.inner.models()
+    }
+
+    async fn complete(&self, request: &LlmRequest) -> Result<LlmResponse> {
+        match self.inner.complete(request).await {
+            Ok(response) => {
+                self.record(
+                    request,
+                    Outcome::Complete {
+                        response: response.clone(),
+                    },
+                );
+                Ok(response)
+            }
+            Err(e) => {
+                self.record(
+                    request,
+                    Outcome::Error {
+                        message: e.to_string(),
+                    },
+                );
+                Err(e)
+            }
+        }
+    }
+
+    async fn stream(&self, request: &LlmRequest) -> Result<mpsc::Receiver<StreamChunk>> {
+        let mut inner_rx = match self.inner.stream(request).await {
+            Ok(rx) => rx,
+            Err(e) => {
+                self.record(
+                    request,
+                    Outcome::Error {
+                        message: e.to_string(),
+                    },
+                );
+                return Err(e);
+            }
+        };
+
+        let (tx, rx) = mpsc::channel(256);
+        let cassette = self.cassette.clone();
+        let path = self.path.clone();
+        let request = request.clone();
+        tokio::spawn(async move {
+            let mut chunks = Vec::new();
+            while let Some(chunk) = inner_rx.recv().await {
+                chunks.push(chunk.clone());
+                let _ = tx.send(chunk).await;
+            }
+            // Saved before `tx` drops, so the consumer sees the end of the
+            // stream only once the cassette is on disk.
+            record_into(&cassette, &path, &request, Outcome::Stream { chunks });
+        });
+        Ok(rx)
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        self.inner.health_check().await
+    }
+}
+
+// ── Replay ─────────────────────────────────────────────────────
+
+/// Serves a recorded cassette without touching the network.
+///
+/// Identical requests are answered in recording order; once a key's entries
+/// run out the last one is repeated. A request with no recorded entry fails
+/// with `ClawError::LlmProvider` naming the missing key. Complete and stream
+/// entries are interchangeable — each is converted to the other on demand.
+pub struct ReplayProvider {
+    name: String,
+    models: Vec<String>,
+    entries: HashMap<String, Vec<Outcome>>,
+    cursors: Mutex<HashMap<String, usize>>,
+}
+
+impl ReplayProvider {
+    pub fn new(cassette: Cassette) -> Self {
+        let mut models: Vec<String> = Vec::new();
+        let mut entries: HashMap<String, Vec<Outcome>> = HashMap::new();
+        for interaction in cassette.interactions {
+            if let Some(model) = interaction.request["model"].as_str()
+                && !models.iter().any(|m| m == model)
+            {
+                models.push(model.to_string());
+            }
+            entries
+                .entry(interaction.key)
+                .or_default()
+                .push(interaction.outcome);
+        }
+        Self {
+            name: cassette.provider,
+            models,
+            entries,
+            cursors: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(Cassette::load(path.as_ref())?))
+    }
+
+    /// Register under a different provider name (routing prefix).
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    fn next_outcome(&self, request: &LlmRequest) -> Result<Outcome> {
+        let key = request_key(request);
+        let Some(outcomes) = self.entries.get(&key) else {
+            return Err(ClawError::LlmProvider(format!(
+                "cassette miss: no recorded interaction for request {key} (model {})",
+                request.model
+            )));
+        };
+        let mut cursors = self.cursors.lock().unwrap();
+        let cursor = cursors.entry(key).or_insert(0);
+        let outcome = outcomes[(*cursor).min(outcomes.len() - 1)].clone();
+        *cursor += 1;
+        Ok(outcome)
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ReplayProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn models(&self) -> Vec<String> {
+        self.models.clone()
+    }
+
+    async fn complete(&self, request: &LlmRequest) -> Result<LlmResponse> {
+        match self.next_outcome(request)? {
+            Outcome::Complete { response } => Ok(response),
+            Outcome::Stream { chunks } => chunks_to_response(chunks),
+            Outcome::Error { message } => Err(ClawError::LlmProvider(message)),
+        }
+    }
+
+    async fn stream(&self, request: &LlmRequest) -> Result<mpsc::Receiver<StreamChunk>> {
+        let chunks = match self.next_outcome(request)? {
+            Outcome::Complete { response } => response_to_chunks(response),
+            Outcome::Stream { chunks } => chunks,
+            Outcome::Error { message } => return Err(ClawError::LlmProvider(message)),
+        };
+        let (tx, rx) = mpsc::channel(chunks.len().max(1));
+        for chunk in chunks {
+            let _ = tx.send(chunk).await;
+        }
+        Ok(rx)
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Fold recorded stream chunks into a complete response.
+fn chunks_to_response(chunks: Vec<StreamChunk>) -> Result<LlmResponse> {
+    let mut text = String::new();
+    let mut tool_calls = Vec::new();
+    let mut usage = Usage::default();
+    let mut stop_reason = StopReason::EndTurn;
+    for chunk in chunks {
+        match chunk {
+            StreamChunk::TextDelta(t) => text.push_str(&t),
+            StreamChunk::ToolCall(tc) => tool_calls.push(tc),
+            StreamChunk::Usage(u) => usage = u,
+            StreamChunk::Done(reason) => stop_reason = reason,
+            StreamChunk::Error(e) => return Err(ClawError::LlmProvider(e)),
+            StreamChunk::RateLimited { retry_after_secs } => {
+                return Err(ClawError::RateLimited { retry_after_secs });
+            }
+            StreamChunk::Thinking(_) => {}
+        }
+    }
+    let mut message = claw_core::Message::text(uuid::Uuid::nil(), claw_core::Role::Assistant, text);
+    message.tool_calls = tool_calls;
+    Ok(LlmResponse {
+        has_tool_calls: !message.tool_calls.is_empty(),
+        message,
+        usage,
+        stop_reason,
+    })
+}
+
+/// Expand a recorded complete response into stream chunks.
+fn response_to_chunks(response: LlmResponse) -> Vec<StreamChunk> {
+    let mut chunks = Vec::new();
+    let text = response.message.text_content();
+    if !text.is_empty() {
+        chunks.push(StreamChunk::TextDelta(text));
+    }
+    chunks.extend(
+        response
+            .message
+            .tool_calls
+            .into_iter()
+            .map(StreamChunk::ToolCall),
+    );
+    chunks.push(StreamChunk::Usage(response.usage));
+    chunks.push(StreamChunk::Done(response.stop_reason));
+    chunks
+}
@@ -1,6 +1,9 @@
 use async_trait::async_trait;
+use claw_config::ClawConfig;
 use claw_core::Result;
-use tracing::debug;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::{debug, warn};
 
 /// Trait for generating text embeddings.
 #[async_trait]
@@ -13,6 +16,12 @@ pub trait EmbeddingProvider: Send + Sync {
 
     /// Provider name.
     fn name(&self) -> &str;
+
+    /// Identity of the vector space (provider, model and dimensions). Stored
+    /// vectors from a different space must be re-embedded before comparing.
+    fn space(&self) -> String {
+        format!("{}:{}", self.name(), self.dimensions())
+    }
 }
 
 /// OpenAI embeddings provider (text-embedding-3-small, text-embedding-3-large, etc.)
@@ -59,10 +68,14 @@ impl EmbeddingProvider for OpenAiEmbedding {
 
         debug!(model = %self.model, count = texts.len(), "generating embeddings");
 
-        let body = serde_json::json!({
+        let mut body = serde_json::json!({
             "model": &self.model,
             "input": texts,
         });
+        // text-embedding-3 models can be shortened to the configured size
+        if self.model.starts_with("text-embedding-3") {
+            body["dimensions"] = serde_json::json!(self.dims);
+        }
 
         let resp = self
             .client
@@ -113,6 +126,10 @@ impl EmbeddingProvider for OpenAiEmbedding {
     fn name(&self) -> &str {
         "openai"
     }
+
+    fn space(&self) -> String {
+        format!("openai/{}:{}", self.model, self.dims)
+    }
 }
 
 /// Ollama embeddings provider (uses /api/embeddings endpoint).
@@ -137,6 +154,12 @@ impl OllamaEmbedding {
         self.base_url = url;
         self
     }
+
+    /// Output dimensions of the model (768 for nomic-embed-text, 1024 for mxbai-embed-large).
+    pub fn with_dims(mut self, dims: usize) -> Self {
+        self.dims = dims;
+        self
+    }
 }
 
 #[async_trait]
@@ -194,4 +217,147 @@ impl EmbeddingProvider for OllamaEmbedding {
     fn name(&self) -> &str {
         "ollama"
     }
+
+    fn space(&self) -> String {
+        format!("ollama/{}:{}", self.model, self.dims)
+    }
+}
+
+/// Offline embedder — signed feature hashing of words, word bigrams and
+/// character trigrams, with sublinear term weighting and L2 normalisation.
+///
+/// Needs no model download or network, runs anywhere (air-gapped boxes,
+/// Raspberry Pis). It captures lexical overlap and morphology (`deploy` ~
+/// `deployment`), not paraphrase — a good floor when no embedding API is
+/// available.
+pub struct HashEmbedding {
+    dims: usize,
+}
+
+impl HashEmbedding {
+    pub fn new(dims: usize) -> Self {
+        Self { dims: dims.max(1) }
+    }
+
+    fn embed_one(&self, text: &str) -> Vec<f32> {
+        let lower = text.to_lowercase();
+        let words: Vec<&str> = lower
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect();
+
+        let mut counts: BTreeMap<String, f32> = BTreeMap::new();
+        for word in &words {
+            *counts.entry(format!("w:{word}")).or_default() += 1.0;
+            // Character trigrams of the padded word, for morphology and typos
+            let padded: Vec<char> = format!("<{word}>").chars().collect();
+            for gram in padded.windows(3) {
+                *counts
+                    .entry(format!("c:{}", gram.iter().collect::<String>()))
+                    .or_default() += 0.25;
+            }
+        }
+        for pair in words.windows(2) {
+            *counts
+                .entry(format!("b:{} {}", pair[0], pair[1]))
+                .or_default() += 0.5;
+        }
+
+        let mut vector = vec![0.0f32; self.dims];
+        for (feature, weight) in counts {
+            let hash = blake3::hash(feature.as_bytes());
+            let bytes = hash.as_bytes();
+            let index =
+                u64::from_le_bytes(bytes[..8].try_into().unwrap_or_default()) as usize % self.dims;
+            let sign = if bytes[8] & 1 == 0 { 1.0 } else { -1.0 };
+            vector[index] += sign * (1.0 + weight.ln_1p());
+        }
+        let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|v| *v /= norm);
+        }
+        vector
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for HashEmbedding {
+    async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|t| self.embed_one(t)).collect())
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dims
+    }
+
+    fn name(&self) -> &str {
+        "builtin"
+    }
+}
+
+/// Rejects vectors whose size doesn't match `memory.embedding_dims`, so a
+/// misconfigured model can't silently mix vector spaces.
+struct DimensionChecked {
+    inner: Arc<dyn EmbeddingProvider>,
+}
+
+#[async_trait]
+impl EmbeddingProvider for DimensionChecked {
+    async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let vectors = self.inner.embed(texts).await?;
+        let expected = self.inner.dimensions();
+        if let Some(bad) = vectors.iter().find(|v| v.len() != expected) {
+            return Err(claw_core::ClawError::Config(format!(
+                "{} returned {}-dimensional embeddings but memory.embedding_dims is {expected}",
+                self.inner.name(),
+                bad.len()
+            )));
+        }
+        Ok(vectors)
+    }
+
+    fn dimensions(&self) -> usize {
+        self.inner.dimensions()
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn space(&self) -> String {
+        self.inner.space()
+    }
+}
+
+/// Build the embedder selected by `memory.embedding_provider`. `None` when
+/// vector search is off, the provider is `none`, or its API key is missing.
+pub fn embedder_from_config(config: &ClawConfig) -> Option<Arc<dyn EmbeddingProvider>> {
+    let memory = &config.memory;
+    if !memory.vector_search {
+        return None;
+    }
+    let dims = memory.embedding_dims;
+    let inner: Arc<dyn EmbeddingProvider> = match memory.embedding_provider.as_str() {
+        "builtin" => Arc::new(HashEmbedding::new(dims)),
+        "openai" => {
+            let Some(ref key) = config.services.openai_api_key else {
+                warn!("memory.embedding_provider = \"openai\" but no OpenAI API key is set");
+                return None;
+            };
+            let model = memory
+                .embedding_model
+                .clone()
+                .unwrap_or_else(|| "text-embedding-3-small".into());
+            Arc::new(OpenAiEmbedding::new(key.clone()).with_model(model, dims))
+        }
+        "ollama" => {
+            let model = memory
+                .embedding_model
+                .as_deref()
+                .unwrap_or("nomic-embed-text");
+            Arc::new(OllamaEmbedding::new(model).with_dims(dims))
+        }
+        _ => return None,
+    };
+    Some(Arc::new(DimensionChecked { inner }))
 }
@@ -0,0 +1,471 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use claw_config::ModelPricing;
+use claw_core::Result;
+use reqwest::Client;
+use tracing::{debug, info};
+
+use crate::media::{self, ImageSource};
+use crate::provider::*;
+
+/// Google Gemini API provider (generativelanguage.googleapis.com).
+pub struct GeminiProvider {
+    client: Client,
+    api_key: String,
+    base_url: String,
+}
+
+impl GeminiProvider {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            client: Client::new(),
+            api_key,
+            base_url: "https://generativelanguage.googleapis.com/v1beta".into(),
+        }
+    }
+
+    pub fn with_base_url(mut self, url: String) -> Self {
+        self.base_url = url;
+        self
+    }
+
+    fn build_request_body(&self, request: &LlmRequest) -> serde_json::Value {
+        // Gemini identifies function responses by name, not by call id — so map
+        // every tool call id we've seen back to the function that produced it.
+        let call_names: HashMap<&str, &str> = request
+            .messages
+            .iter()
+            .flat_map(|m| m.tool_calls.iter())
+            .map(|tc| (tc.id.as_str(), tc.tool_name.as_str()))
+            .collect();
+
+        let mut system_parts: Vec<serde_json::Value> = Vec::new();
+        if let Some(ref system) = request.system {
+            system_parts.push(serde_json::json!({ "text": system }));
+        }
+
+        let mut contents = Vec::new();
+        for msg in &request.messages {
+            match msg.role {
+                claw_core::Role::System => {
+                    system_parts.push(serde_json::json!({ "text": msg.text_content() }));
+                }
+                claw_core::Role::User => {
+                    let (images, notes) = media::message_media(msg);
+                    let mut text = msg.text_content();
+                    for note in notes {
+                        text.push_str(&format!("\n{note}"));
+                    }
+                    let mut parts: Vec<serde_json::Value> = images.iter().map(image_part).collect();
+                    let text = text.trim();
+                    if !text.is_empty() || parts.is_empty() {
+                        parts.push(serde_json::json!({ "text": text }));
+                    }
+                    contents.push(serde_json::json!({ "role": "user", "parts": parts }));
+                }
+                claw_core::Role::Assistant => {
+                    let mut parts: Vec<serde_json::Value> = Vec::new();
+                    let text = msg.text_content();
+                    if !text.is_empty() {
+                        parts.push(serde_json::json!({ "text": text }));
+                    }
+                    for tc in &msg.tool_calls {
+                        parts.push(serde_json::json!({
+                            "functionCall": { "name": tc.tool_name, "args": tc.arguments }
+                        }));
+                    }
+                    if parts.is_empty() {
+                        parts.push(serde_json::json!({ "text": "" }));
+                    }
+                    contents.push(serde_json::json!({ "role": "model", "parts": parts }));
+                }
+                claw_core::Role::Tool => {
+                    let mut parts: Vec<serde_json::Value> = Vec::new();
+                    for block in &msg.content {
+                        if let claw_core::MessageContent::ToolResult {
+                            tool_call_id,
+                            content,
+                            is_error,
+                        } = block
+                        {
+                            let name = call_names
+                                .get(tool_call_id.as_str())
+                                .copied()
+                                .unwrap_or(tool_call_id.as_str());
+                            // `response` must be a JSON object
+                            let response = if *is_error {
+                                serde_json::json!({ "error": content })
+                            } else {
+                                serde_json::json!({ "content": content })
+                            };
+                            parts.push(serde_json::json!({
+                                "functionResponse": { "name": name, "response": response }
+                            }));
+                        }
+                    }
+                    // Fallback: if no ToolResult blocks, send as plain user text
+                    if parts.is_empty() {
+                        parts.push(serde_json::json!({ "text": msg.text_content() }));
+                    }
+                    parts.extend(media::message_media(msg).0.iter().map(image_part));
+                    contents.push(serde_json::json!({ "role": "user", "parts": parts }));
+                }
+            }
+        }
+
+        let mut generation_config = serde_json::json!({
+            "maxOutputTokens": request.max_tokens,
+            "temperature": request.temperature,
+        });
+
+        // Thinking budget (Gemini 2.5+)
+        if let Some(ref level) = request.thinking_level
+            && level != "off"
+        {
+            let budget = match level.as_str() {
+                "low" => 2048,
+                "medium" => 8192,
+                "high" => 16384,
+                "xhigh" => 32768,
+                _ => 8192,
+            };
+            generation_config["thinkingConfig"] = serde_json::json!({
+                "thinkingBudget": budget,
+                "includeThoughts": true,
+            });
+        }
+
+        if let Some(ref format) = request.response_format {
+            let mut schema = format.schema.clone();
+            sanitize_schema(&mut schema);
+            generation_config["responseMimeType"] = serde_json::json!("application/json");
+            generation_config["responseSchema"] = schema;
+        }
+
+        let mut body = serde_json::json!({
+            "contents": contents,
+            "generationConfig": generation_config,
+        });
+
+        if !system_parts.is_empty() {
+            body["systemInstruction"] = serde_json::json!({ "parts": system_parts });
+        }
+
+        if !request.tools.is_empty() {
+            body["tools"] = serde_json::json!([{
+                "functionDeclarations": tools_to_json(&request.tools),
+            }]);
+        }
+
+        body
+    }
+}
+
+/// Serialize an image as a Gemini `inlineData` / `fileData` part.
+fn image_part(image: &ImageSource) -> serde_json::Value {
+    match image {
+        ImageSource::Base64 { media_type, data } => serde_json::json!({
+            "inlineData": { "mimeType": media_type, "data": data }
+        }),
+        ImageSource::Url(url) => serde_json::json!({
+            "fileData": { "mimeType": media::image_media_type(std::path::Path::new(url)).unwrap_or("image/jpeg"), "fileUri": url }
+        }),
+    }
+}
+
+/// Serialize tools as Gemini `functionDeclarations`.
+fn tools_to_json(tools: &[claw_core::Tool]) -> Vec<serde_json::Value> {
+    tools
+        .iter()
+        .map(|t| {
+            let mut params = t.parameters.clone();
+            sanitize_schema(&mut params);
+            serde_json::json!({
+                "name": t.name,
+                "description": t.description,
+                "parameters": params,
+            })
+        })
+        .collect()
+}
+
+/// Gemini accepts an OpenAPI subset of JSON Schema and rejects keywords like
+/// `additionalProperties` or `$schema` — strip them recursively.
+fn sanitize_schema(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.remove("additionalProperties");
+            map.remove("$schema");
+            for v in map.values_mut() {
+                sanitize_schema(v);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for v in items {
+                sanitize_schema(v);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Text, thinking and function calls extracted from one candidate's parts.
+#[derive(Default)]
+struct ParsedParts {
+    text: String,
+    thinking: String,
+    tool_calls: Vec<claw_core::ToolCall>,
+}
+
+fn parse_parts(candidate: &serde_json::Value) -> ParsedParts {
+    let mut parsed = ParsedParts::default();
+    let Some(parts) = candidate["content"]["parts"].as_array() else {
+        return parsed;
+    };
+    for part in parts {
+        if let Some(call) = part.get("functionCall") {
+            let id = call["id"]
+                .as_str()
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple()));
+            parsed.tool_calls.push(claw_core::ToolCall {
+                id,
+                tool_name: call["name"].as_str().unwrap_or_default().to_string(),
+                arguments: call
+                    .get("args")
+                    .cloned()
+                    .unwrap_or_else(|| serde_json::json!({})),
+            });
+        } else if let Some(text) = part["text"].as_str() {
+            if part["thought"].as_bool() == Some(true) {
+                parsed.thinking.push_str(text);
+            } else {
+                parsed.text.push_str(text);
+            }
+        }
+    }
+    parsed
+}
+
+fn parse_usage(model: &str, usage: &serde_json::Value) -> Usage {
+    // promptTokenCount includes the cached tokens; report them apart, as
+    // Anthropic does, so they aren't counted twice
+    let prompt = usage["promptTokenCount"].as_u64().unwrap_or(0) as u32;
+    let cached = usage["cachedContentTokenCount"].as_u64().unwrap_or(0) as u32;
+    let mut parsed = Usage {
+        input_tokens: prompt.saturating_sub(cached),
+        output_tokens: usage["candidatesTokenCount"].as_u64().unwrap_or(0) as u32,
+        thinking_tokens: usage["thoughtsTokenCount"].as_u64().unwrap_or(0) as u32,
+        cache_read_tokens: cached,
+        ..Default::default()
+    };
+    parsed.estimated_cost_usd = estimate_gemini_cost(model, &parsed);
+    parsed
+}
+
+fn map_finish_reason(reason: Option<&str>, has_tool_calls: bool) -> StopReason {
+    match reason {
+        Some("MAX_TOKENS") => StopReason::MaxTokens,
+        Some("SAFETY")
+        | Some("RECITATION")
+        | Some("BLOCKLIST")
+        | Some("PROHIBITED_CONTENT")
+        | Some("SPII") => StopReason::ContentFilter,
+        _ if has_tool_calls => StopReason::ToolUse,
+        _ => StopReason::EndTurn,
+    }
+}
+
+#[async_trait]
+impl LlmProvider for GeminiProvider {
+    fn name(&self) -> &str {
+        "google"
+    }
+
+    fn models(&self) -> Vec<String> {
+        claw_config::catalog().models_for("google")
+    }
+
+    async fn complete(&self, request: &LlmRequest) -> Result<LlmResponse> {
+        let body = self.build_request_body(request);
+
+        debug!(model = %request.model, "sending Gemini request");
+
+        let resp = self
+            .client
+            .post(format!(
+                "{}/models/{}:generateContent",
+                self.base_url, request.model
+            ))
+            .header("x-goog-api-key", &self.api_key)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| claw_core::ClawError::LlmProvider(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            return Err(crate::ratelimit::error_from_response(resp).await);
+        }
+
+        let data: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| claw_core::ClawError::LlmProvider(e.to_string()))?;
+
+        let candidate = &data["candidates"][0];
+        let parsed = parse_parts(candidate);
+        let has_tool_calls = !parsed.tool_calls.is_empty();
+
+        let mut message =
+            claw_core::Message::text(uuid::Uuid::nil(), claw_core::Role::Assistant, parsed.text);
+        message.tool_calls = parsed.tool_calls;
+
+        Ok(LlmResponse {
+            message,
+            usage: parse_usage(&request.model, &data["usageMetadata"]),
+            has_tool_calls,
+            stop_reason: map_finish_reason(candidate["finishReason"].as_str(), has_tool_calls),
+        })
+    }
+
+    async fn stream(
+        &self,
+        request: &LlmRequest,
+    ) -> Result<tokio::sync::mpsc::Receiver<StreamChunk>> {
+        let (tx, rx) = tokio::sync::mpsc::channel(256);
+        let body = self.build_request_body(request);
+
+        let client = self.client.clone();
+        let url = format!(
+            "{}/models/{}:streamGenerateContent?alt=sse",
+            self.base_url, request.model
+        );
+        let api_key = self.api_key.clone();
+        let model = request.model.clone();
+
+        tokio::spawn(async move {
+            let resp = client
+                .post(url)
+                .header("x-goog-api-key", api_key)
+                .json(&body)
+                .send()
+                .await;
+
+            match resp {
+                Ok(resp) if resp.status().is_success() => {
+                    use futures::StreamExt;
+                    let mut stream = resp.bytes_stream();
+                    let mut buffer = String::new();
+                    let mut usage = Usage::default();
+                    let mut finish_reason: Option<String> = None;
+                    let mut saw_tool_calls = false;
+
+                    while let Some(chunk_result) = stream.next().await {
+                        let bytes = match chunk_result {
+                            Ok(bytes) => bytes,
+                            Err(e) => {
+                                let _ = tx.send(StreamChunk::Error(e.to_string())).await;
+                                return;
+                            }
+                        };
+                        buffer.push_str(&String::from_utf8_lossy(&bytes));
+
+                        // Each SSE event is a full GenerateContentResponse
+                        while let Some(newline_pos) = buffer.find('\n') {
+                            let line = buffer[..newline_pos].trim().to_string();
+                            buffer.drain(..newline_pos + 1);
+
+                            let Some(data) = line.strip_prefix("data:") else {
+                                continue;
+                            };
+                            let Ok(event) = serde_json::from_str::<serde_json::Value>(data.trim())
+                            else {
+                                continue;
+                            };
+
+                            let candidate = &event["candidates"][0];
+                            let parsed = parse_parts(candidate);
+                            if !parsed.thinking.is_empty() {
+                                let _ = tx.send(StreamChunk::Thinking(parsed.thinking)).await;
+                            }
+                            if !parsed.text.is_empty() {
+                                let _ = tx.send(StreamChunk::TextDelta(parsed.text)).await;
+                            }
+                            for tc in parsed.tool_calls {
+                                saw_tool_calls = true;
+                                let _ = tx.send(StreamChunk::ToolCall(tc)).await;
+                            }
+                            if let Some(reason) = candidate["finishReason"].as_str() {
+                                finish_reason = Some(reason.to_string());
+                            }
+                            // Usage is cumulative — the last event carries the totals
+                            if event.get("usageMetadata").is_some() {
+                                usage = parse_usage(&model, &event["usageMetadata"]);
+                            }
+                        }
+                    }
+
+                    let _ = tx.send(StreamChunk::Usage(usage)).await;
+                    let _ = tx
+                        .send(StreamChunk::Done(map_finish_reason(
+                            finish_reason.as_deref(),
+                            saw_tool_calls,
+                        )))
+                        .await;
+                }
+                Ok(resp) => {
+                    let _ = tx.send(crate::ratelimit::stream_error(resp).await).await;
+                }
+                Err(e) => {
+                    let _ = tx.send(StreamChunk::Error(e.to_string())).await;
+                }
+            }
+        });
+
+        Ok(rx)
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        info!("checking Gemini API health");
+        if self.api_key.is_empty() {
+            return Err(claw_core::ClawError::LlmProvider(
+                "GEMINI_API_KEY not set".into(),
+            ));
+        }
+        let resp = self
+            .client
+            .get(format!("{}/models", self.base_url))
+            .header("x-goog-api-key", &self.api_key)
+            .send()
+            .await
+            .map_err(|e| claw_core::ClawError::LlmProvider(format!("gemini unreachable: {e}")))?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            Err(claw_core::ClawError::LlmProvider(format!(
+                "HTTP {status}: {text}"
+            )))
+        }
+    }
+}
+
+/// Estimate cost for Gemini models from the model catalog.
+/// `promptTokenCount` includes cached tokens, which bill at the cache-read rate;
+/// thinking tokens bill at the output rate.
+fn estimate_gemini_cost(model: &str, usage: &Usage) -> f64 {
+    let pricing = claw_config::catalog()
+        .get(model)
+        .or_else(|| claw_config::catalog().get("gemini-2.5-flash")) // default to 2.5 flash pricing
+        .map(|m| m.pricing())
+        .unwrap_or(ModelPricing::flat(0.0, 0.0));
+    pricing.cost(
+        usage.input_tokens,
+        usage.output_tokens + usage.thinking_tokens,
+        usage.cache_read_tokens,
+        0,
+    )
+}
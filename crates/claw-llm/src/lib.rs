@@ -4,14 +4,25 @@
 //! thinking/reasoning, and automatic failover between providers.
 
 pub mod anthropic;
+pub mod cassette;
 pub mod embedding;
+pub mod gemini;
 pub mod local;
+pub mod media;
 pub mod mock;
 pub mod openai;
 pub mod provider;
+pub mod ratelimit;
 pub mod router;
+pub mod routing;
+pub mod structured;
+pub mod tokenizer;
 
 pub use embedding::EmbeddingProvider;
 pub use mock::MockProvider;
-pub use provider::{LlmProvider, LlmRequest, LlmResponse, StopReason, StreamChunk, Usage};
+pub use provider::{
+    LlmProvider, LlmRequest, LlmResponse, ResponseFormat, StopReason, StreamChunk, Usage,
+};
 pub use router::ModelRouter;
+pub use routing::{LatencyTracker, RoutingPolicy};
+pub use tokenizer::Tokenizer;
@@ -61,7 +61,7 @@ impl LlmProvider for LocalProvider {
             }));
         }
 
-        let body = serde_json::json!({
+        let mut body = serde_json::json!({
             "model": &request.model,
             "messages": messages,
             "stream": false,
@@ -70,6 +70,10 @@ impl LlmProvider for LocalProvider {
                 "num_predict": request.max_tokens,
             }
         });
+        // Ollama constrains output to a JSON schema via `format`
+        if let Some(ref format) = request.response_format {
+            body["format"] = format.schema.clone();
+        }
 
         let resp = self
             .client
@@ -138,7 +142,7 @@ impl LlmProvider for LocalProvider {
             }));
         }
 
-        let body = serde_json::json!({
+        let mut body = serde_json::json!({
             "model": &request.model,
             "messages": messages,
             "stream": true,
@@ -147,6 +151,10 @@ impl LlmProvider for LocalProvider {
                 "num_predict": request.max_tokens,
             }
         });
+        // Ollama constrains output to a JSON schema via `format`
+        if let Some(ref format) = request.response_format {
+            body["format"] = format.schema.clone();
+        }
 
         let client = self.client.clone();
         let base_url = self.base_url.clone();
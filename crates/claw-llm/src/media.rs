@@ -0,0 +1,174 @@
+//! Resolve image / file content blocks into something a vision API can consume.
+//!
+//! `MessageContent::Image::data` may hold base64, a `data:` URL, an http(s)
+//! URL, a `/api/v1/screenshots/<name>` path, or a local file path. Local files
+//! are only read from inside `~/.claw/` (screenshots, channel downloads) so a
+//! crafted message can't exfiltrate arbitrary files to a provider.
+
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use tracing::debug;
+
+/// Largest image we will inline (Anthropic's per-image limit).
+pub const MAX_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// An image ready to be serialized into a provider request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageSource {
+    /// Inline base64 data with its media type.
+    Base64 { media_type: String, data: String },
+    /// A remote URL the provider fetches itself.
+    Url(String),
+}
+
+impl ImageSource {
+    /// Render as a `data:` URL (OpenAI style) or pass a remote URL through.
+    pub fn to_url(&self) -> String {
+        match self {
+            ImageSource::Base64 { media_type, data } => format!("data:{media_type};base64,{data}"),
+            ImageSource::Url(url) => url.clone(),
+        }
+    }
+}
+
+/// Resolve the `data` of a `MessageContent::Image` block.
+pub fn resolve_image(data: &str, media_type: &str) -> Option<ImageSource> {
+    let data = data.trim();
+    if data.is_empty() {
+        return None;
+    }
+    if data.starts_with("http://") || data.starts_with("https://") {
+        return Some(ImageSource::Url(data.to_string()));
+    }
+    if let Some(rest) = data.strip_prefix("data:") {
+        let (meta, payload) = rest.split_once(',')?;
+        let media_type = meta.strip_suffix(";base64")?;
+        return Some(ImageSource::Base64 {
+            media_type: media_type.to_string(),
+            data: payload.to_string(),
+        });
+    }
+    if let Some(name) = data.strip_prefix("/api/v1/screenshots/") {
+        if name.contains('/') || name.contains("..") {
+            return None;
+        }
+        return load_local_image(&claw_dir().join("screenshots").join(name), Some(media_type));
+    }
+    if data.starts_with('/') || data.starts_with("~/") {
+        return load_local_image(&expand_home(data), Some(media_type));
+    }
+    // Anything else is assumed to already be base64
+    Some(ImageSource::Base64 {
+        media_type: media_type.to_string(),
+        data: data.to_string(),
+    })
+}
+
+/// Image bytes as a `data:` URL, for `MessageContent::Image::data`.
+pub fn data_url(media_type: &str, bytes: &[u8]) -> String {
+    format!(
+        "data:{media_type};base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    )
+}
+
+/// Resolve a `MessageContent::File` block. Returns `None` for non-image files.
+pub fn resolve_file(path: &str, media_type: Option<&str>) -> Option<ImageSource> {
+    let media_type = media_type
+        .filter(|m| m.starts_with("image/"))
+        .or_else(|| image_media_type(Path::new(path)))?;
+    resolve_image(path, media_type)
+}
+
+/// Guess an image media type from a file extension.
+pub fn image_media_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    match ext.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+fn claw_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join(".claw")
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Read and base64-encode an image, refusing anything outside `~/.claw/`.
+fn load_local_image(path: &Path, media_type: Option<&str>) -> Option<ImageSource> {
+    let canonical = path.canonicalize().ok()?;
+    let root = claw_dir().canonicalize().ok()?;
+    if !canonical.starts_with(&root) {
+        debug!(path = %path.display(), "refusing to attach image outside ~/.claw");
+        return None;
+    }
+    let meta = std::fs::metadata(&canonical).ok()?;
+    if !meta.is_file() || meta.len() > MAX_IMAGE_BYTES {
+        debug!(path = %path.display(), size = meta.len(), "image missing or too large to attach");
+        return None;
+    }
+    let media_type = media_type
+        .filter(|m| m.starts_with("image/"))
+        .or_else(|| image_media_type(&canonical))?
+        .to_string();
+    let bytes = std::fs::read(&canonical).ok()?;
+    Some(ImageSource::Base64 {
+        media_type,
+        data: base64::engine::general_purpose::STANDARD.encode(bytes),
+    })
+}
+
+/// Images and file notes carried by a message, in block order.
+///
+/// Images that resolve are returned as `ImageSource`s; non-image files (and
+/// images that can't be loaded) become short text notes so the model still
+/// knows something was attached.
+pub fn message_media(msg: &claw_core::Message) -> (Vec<ImageSource>, Vec<String>) {
+    let mut images = Vec::new();
+    let mut notes = Vec::new();
+    for block in &msg.content {
+        match block {
+            claw_core::MessageContent::Image { data, media_type } => {
+                match resolve_image(data, media_type) {
+                    Some(img) => images.push(img),
+                    None => notes.push(format!("[Image unavailable ({media_type})]")),
+                }
+            }
+            claw_core::MessageContent::File { path, media_type } => {
+                match resolve_file(path, media_type.as_deref()) {
+                    Some(img) => images.push(img),
+                    None => notes.push(match media_type {
+                        Some(mt) => format!("[Attached file: {path} ({mt})]"),
+                        None => format!("[Attached file: {path}]"),
+                    }),
+                }
+            }
+            _ => {}
+        }
+    }
+    (images, notes)
+}
+
+/// Whether a message carries any image or file blocks.
+pub fn has_media(msg: &claw_core::Message) -> bool {
+    msg.content.iter().any(|c| {
+        matches!(
+            c,
+            claw_core::MessageContent::Image { .. } | claw_core::MessageContent::File { .. }
+        )
+    })
+}
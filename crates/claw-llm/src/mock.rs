@@ -48,6 +48,7 @@ impl Default for MockResponse {
                 cache_read_tokens: 0,
                 cache_write_tokens: 0,
                 estimated_cost_usd: 0.001,
+                model: None,
             },
             error: None,
         }
@@ -236,6 +237,7 @@ mod tests {
             max_tokens: 100,
             temperature: 0.7,
             thinking_level: None,
+            response_format: None,
             stream: false,
         };
 
@@ -257,6 +259,7 @@ mod tests {
             max_tokens: 100,
             temperature: 0.7,
             thinking_level: None,
+            response_format: None,
             stream: false,
         };
 
@@ -277,6 +280,7 @@ mod tests {
             max_tokens: 100,
             temperature: 0.7,
             thinking_level: None,
+            response_format: None,
             stream: false,
         };
 
@@ -295,6 +299,7 @@ mod tests {
             max_tokens: 100,
             temperature: 0.7,
             thinking_level: None,
+            response_format: None,
             stream: false,
         };
 
@@ -316,6 +321,7 @@ mod tests {
             max_tokens: 100,
             temperature: 0.7,
             thinking_level: None,
+            response_format: None,
             stream: true,
         };
 
@@ -343,6 +349,7 @@ mod tests {
             max_tokens: 100,
             temperature: 0.7,
             thinking_level: None,
+            response_format: None,
             stream: false,
         };
 
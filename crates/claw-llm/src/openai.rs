@@ -1,7 +1,9 @@
 use async_trait::async_trait;
+use claw_config::ModelPricing;
 use claw_core::Result;
 use tracing::info;
 
+use crate::media::{self, ImageSource};
 use crate::provider::*;
 
 /// Serialize tools for the OpenAI API.
@@ -29,12 +31,158 @@ fn tools_to_json(tools: &[claw_core::Tool]) -> Vec<serde_json::Value> {
         .collect()
 }
 
+/// Convert the conversation into OpenAI chat `messages`, including the system prompt.
+fn build_messages(request: &LlmRequest) -> Vec<serde_json::Value> {
+    let mut messages = Vec::new();
+
+    if let Some(ref system) = request.system {
+        messages.push(serde_json::json!({
+            "role": "system",
+            "content": system,
+        }));
+    }
+
+    // Tool messages can't carry images, so images attached to tool results are
+    // held back and sent as a user message once the run of tool messages ends.
+    let mut pending_images: Vec<ImageSource> = Vec::new();
+
+    for msg in &request.messages {
+        if msg.role != claw_core::Role::Tool && !pending_images.is_empty() {
+            messages.push(images_message(std::mem::take(&mut pending_images)));
+        }
+        match msg.role {
+            claw_core::Role::System => {
+                messages.push(serde_json::json!({
+                    "role": "system",
+                    "content": msg.text_content(),
+                }));
+            }
+            claw_core::Role::User if media::has_media(msg) => {
+                let (images, notes) = media::message_media(msg);
+                let mut text = msg.text_content();
+                for note in notes {
+                    text.push_str(&format!("\n{note}"));
+                }
+                let mut parts = Vec::new();
+                let text = text.trim();
+                if !text.is_empty() {
+                    parts.push(serde_json::json!({ "type": "text", "text": text }));
+                }
+                parts.extend(images.iter().map(image_part));
+                messages.push(serde_json::json!({
+                    "role": "user",
+                    "content": parts,
+                }));
+            }
+            claw_core::Role::User => {
+                messages.push(serde_json::json!({
+                    "role": "user",
+                    "content": msg.text_content(),
+                }));
+            }
+            claw_core::Role::Assistant => {
+                if msg.tool_calls.is_empty() {
+                    messages.push(serde_json::json!({
+                        "role": "assistant",
+                        "content": msg.text_content(),
+                    }));
+                } else {
+                    // Assistant message with tool calls — must include tool_calls array
+                    let tc: Vec<serde_json::Value> = msg
+                        .tool_calls
+                        .iter()
+                        .map(|tc| {
+                            serde_json::json!({
+                                "id": tc.id,
+                                "type": "function",
+                                "function": {
+                                    "name": tc.tool_name,
+                                    "arguments": serde_json::to_string(&tc.arguments).unwrap_or_default(),
+                                }
+                            })
+                        })
+                        .collect();
+                    let text = msg.text_content();
+                    let content = if text.is_empty() {
+                        serde_json::Value::Null
+                    } else {
+                        serde_json::json!(text)
+                    };
+                    messages.push(serde_json::json!({
+                        "role": "assistant",
+                        "content": content,
+                        "tool_calls": tc,
+                    }));
+                }
+            }
+            claw_core::Role::Tool => {
+                // Tool result messages — extract tool_call_id from ToolResult content blocks
+                for block in &msg.content {
+                    if let claw_core::MessageContent::ToolResult {
+                        tool_call_id,
+                        content,
+                        ..
+                    } = block
+                    {
+                        messages.push(serde_json::json!({
+                            "role": "tool",
+                            "tool_call_id": tool_call_id,
+                            "content": content,
+                        }));
+                    }
+                }
+                // Fallback: if no ToolResult blocks, send as user message to avoid API errors
+                if !msg
+                    .content
+                    .iter()
+                    .any(|c| matches!(c, claw_core::MessageContent::ToolResult { .. }))
+                {
+                    messages.push(serde_json::json!({
+                        "role": "user",
+                        "content": msg.text_content(),
+                    }));
+                }
+                if media::has_media(msg) {
+                    pending_images.extend(media::message_media(msg).0);
+                }
+            }
+        }
+    }
+    if !pending_images.is_empty() {
+        messages.push(images_message(pending_images));
+    }
+
+    messages
+}
+
+/// Serialize an image as an OpenAI `image_url` content part.
+fn image_part(image: &ImageSource) -> serde_json::Value {
+    serde_json::json!({
+        "type": "image_url",
+        "image_url": { "url": image.to_url() },
+    })
+}
+
+/// A user message carrying images produced by tool calls.
+fn images_message(images: Vec<ImageSource>) -> serde_json::Value {
+    let mut parts = vec![serde_json::json!({
+        "type": "text",
+        "text": "Images attached by the preceding tool results:",
+    })];
+    parts.extend(images.iter().map(image_part));
+    serde_json::json!({ "role": "user", "content": parts })
+}
+
 /// OpenAI-compatible API provider (works with OpenAI, Azure, Together, etc.)
 pub struct OpenAiProvider {
     client: reqwest::Client,
     api_key: String,
     base_url: String,
     provider_name: String,
+    /// Model list override for custom endpoints (empty = built-in OpenAI list).
+    custom_models: Vec<String>,
+    /// Per-million (input, output) pricing override.
+    pricing: Option<(f64, f64)>,
 }
 
 impl OpenAiProvider {
@@ -44,6 +192,8 @@ impl OpenAiProvider {
             api_key,
             base_url: "https://api.openai.com/v1".into(),
             provider_name: "openai".into(),
+            custom_models: vec![],
+            pricing: None,
         }
     }
 
@@ -53,6 +203,18 @@ impl OpenAiProvider {
         self.provider_name = name;
         self
     }
+
+    /// Advertise the models served by a custom endpoint.
+    pub fn with_models(mut self, models: Vec<String>) -> Self {
+        self.custom_models = models;
+        self
+    }
+
+    /// Override cost estimation with per-million-token (input, output) pricing.
+    pub fn with_pricing(mut self, input_per_m: f64, output_per_m: f64) -> Self {
+        self.pricing = Some((input_per_m, output_per_m));
+        self
+    }
 }
 
 #[async_trait]
@@ -62,101 +224,14 @@ impl LlmProvider for OpenAiProvider {
     }
 
     fn models(&self) -> Vec<String> {
-        vec![
-            "gpt-4o".into(),
-            "gpt-4o-mini".into(),
-            "o1".into(),
-            "o1-mini".into(),
-            "o3".into(),
-            "o3-mini".into(),
-        ]
+        if !self.custom_models.is_empty() {
+            return self.custom_models.clone();
+        }
+        claw_config::catalog().models_for("openai")
     }
 
     async fn complete(&self, request: &LlmRequest) -> Result<LlmResponse> {
-        let mut messages = Vec::new();
-
-        if let Some(ref system) = request.system {
-            messages.push(serde_json::json!({
-                "role": "system",
-                "content": system,
-            }));
-        }
-
-        for msg in &request.messages {
-            match msg.role {
-                claw_core::Role::System => {
-                    messages.push(serde_json::json!({
-                        "role": "system",
-                        "content": msg.text_content(),
-                    }));
-                }
-                claw_core::Role::User => {
-                    messages.push(serde_json::json!({
-                        "role": "user",
-                        "content": msg.text_content(),
-                    }));
-                }
-                claw_core::Role::Assistant => {
-                    if msg.tool_calls.is_empty() {
-                        messages.push(serde_json::json!({
-                            "role": "assistant",
-                            "content": msg.text_content(),
-                        }));
-                    } else {
-                        // Assistant message with tool calls — must include tool_calls array
-                        let tc: Vec<serde_json::Value> = msg.tool_calls.iter().map(|tc| {
-                            serde_json::json!({
-                                "id": tc.id,
-                                "type": "function",
-                                "function": {
-                                    "name": tc.tool_name,
-                                    "arguments": serde_json::to_string(&tc.arguments).unwrap_or_default(),
-                                }
-                            })
-                        }).collect();
-                        let text = msg.text_content();
-                        let content = if text.is_empty() {
-                            serde_json::Value::Null
-                        } else {
-                            serde_json::json!(text)
-                        };
-                        messages.push(serde_json::json!({
-                            "role": "assistant",
-                            "content": content,
-                            "tool_calls": tc,
-                        }));
-                    }
-                }
-                claw_core::Role::Tool => {
-                    // Tool result messages — extract tool_call_id from ToolResult content blocks
-                    for block in &msg.content {
-                        if let claw_core::MessageContent::ToolResult {
-                            tool_call_id,
-                            content,
-                            ..
-                        } = block
-                        {
-                            messages.push(serde_json::json!({
-                                "role": "tool",
-                                "tool_call_id": tool_call_id,
-                                "content": content,
-                            }));
-                        }
-                    }
-                    // Fallback: if no ToolResult blocks, send as user message to avoid API errors
-                    if !msg
-                        .content
-                        .iter()
-                        .any(|c| matches!(c, claw_core::MessageContent::ToolResult { .. }))
-                    {
-                        messages.push(serde_json::json!({
-                            "role": "user",
-                            "content": msg.text_content(),
-                        }));
-                    }
-                }
-            }
-        }
+        let messages = build_messages(request);
 
         let mut body = serde_json::json!({
             "model": &request.model,
@@ -174,6 +249,9 @@ impl LlmProvider for OpenAiProvider {
         if !request.tools.is_empty() {
             body["tools"] = serde_json::json!(tools_to_json(&request.tools));
         }
+        if let Some(ref format) = request.response_format {
+            body["response_format"] = response_format_json(format);
+        }
 
         let resp = self
             .client
@@ -185,11 +263,7 @@ impl LlmProvider for OpenAiProvider {
             .map_err(|e| claw_core::ClawError::LlmProvider(e.to_string()))?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(claw_core::ClawError::LlmProvider(format!(
-                "HTTP {status}: {text}"
-            )));
+            return Err(crate::ratelimit::error_from_response(resp).await);
         }
 
         let data: serde_json::Value = resp
@@ -233,7 +307,8 @@ impl LlmProvider for OpenAiProvider {
         let usage_data = &data["usage"];
         let input_tokens = usage_data["prompt_tokens"].as_u64().unwrap_or(0) as u32;
         let output_tokens = usage_data["completion_tokens"].as_u64().unwrap_or(0) as u32;
-        let estimated_cost_usd = estimate_openai_cost(&request.model, input_tokens, output_tokens);
+        let estimated_cost_usd =
+            estimate_openai_cost(&request.model, self.pricing, input_tokens, output_tokens);
 
         Ok(LlmResponse {
             message,
@@ -244,6 +319,7 @@ impl LlmProvider for OpenAiProvider {
                 cache_read_tokens: 0,
                 cache_write_tokens: 0,
                 estimated_cost_usd,
+                model: None,
             },
             has_tool_calls,
             stop_reason: match finish_reason {
@@ -262,87 +338,7 @@ impl LlmProvider for OpenAiProvider {
         let (tx, rx) = tokio::sync::mpsc::channel(256);
 
         // Build the same body as complete() but add stream: true
-        let mut messages = Vec::new();
-
-        if let Some(ref system) = request.system {
-            messages.push(serde_json::json!({
-                "role": "system",
-                "content": system,
-            }));
-        }
-
-        for msg in &request.messages {
-            match msg.role {
-                claw_core::Role::System => {
-                    messages.push(serde_json::json!({
-                        "role": "system",
-                        "content": msg.text_content(),
-                    }));
-                }
-                claw_core::Role::User => {
-                    messages.push(serde_json::json!({
-                        "role": "user",
-                        "content": msg.text_content(),
-                    }));
-                }
-                claw_core::Role::Assistant => {
-                    if msg.tool_calls.is_empty() {
-                        messages.push(serde_json::json!({
-                            "role": "assistant",
-                            "content": msg.text_content(),
-                        }));
-                    } else {
-                        let tc: Vec<serde_json::Value> = msg.tool_calls.iter().map(|tc| {
-                            serde_json::json!({
-                                "id": tc.id,
-                                "type": "function",
-                                "function": {
-                                    "name": tc.tool_name,
-                                    "arguments": serde_json::to_string(&tc.arguments).unwrap_or_default(),
-                                }
-                            })
-                        }).collect();
-                        let text = msg.text_content();
-                        let content = if text.is_empty() {
-                            serde_json::Value::Null
-                        } else {
-                            serde_json::json!(text)
-                        };
-                        messages.push(serde_json::json!({
-                            "role": "assistant",
-                            "content": content,
-                            "tool_calls": tc,
-                        }));
-                    }
-                }
-                claw_core::Role::Tool => {
-                    for block in &msg.content {
-                        if let claw_core::MessageContent::ToolResult {
-                            tool_call_id,
-                            content,
-                            ..
-                        } = block
-                        {
-                            messages.push(serde_json::json!({
-                                "role": "tool",
-                                "tool_call_id": tool_call_id,
-                                "content": content,
-                            }));
-                        }
-                    }
-                    if !msg
-                        .content
-                        .iter()
-                        .any(|c| matches!(c, claw_core::MessageContent::ToolResult { .. }))
-                    {
-                        messages.push(serde_json::json!({
-                            "role": "user",
-                            "content": msg.text_content(),
-                        }));
-                    }
-                }
-            }
-        }
+        let messages = build_messages(request);
 
         let mut body = serde_json::json!({
             "model": &request.model,
@@ -362,11 +358,15 @@ impl LlmProvider for OpenAiProvider {
         if !request.tools.is_empty() {
             body["tools"] = serde_json::json!(tools_to_json(&request.tools));
         }
+        if let Some(ref format) = request.response_format {
+            body["response_format"] = response_format_json(format);
+        }
 
         let client = self.client.clone();
         let base_url = self.base_url.clone();
         let api_key = self.api_key.clone();
         let model = request.model.clone();
+        let pricing = self.pricing;
 
         tokio::spawn(async move {
             let resp = client
@@ -424,6 +424,7 @@ impl LlmProvider for OpenAiProvider {
                                             };
                                             let cost = estimate_openai_cost(
                                                 &model,
+                                                pricing,
                                                 input_tokens,
                                                 output_tokens,
                                             );
@@ -509,8 +510,7 @@ impl LlmProvider for OpenAiProvider {
                     let _ = tx.send(StreamChunk::Done(StopReason::EndTurn)).await;
                 }
                 Ok(resp) => {
-                    let text = resp.text().await.unwrap_or_default();
-                    let _ = tx.send(StreamChunk::Error(text)).await;
+                    let _ = tx.send(crate::ratelimit::stream_error(resp).await).await;
                 }
                 Err(e) => {
                     let _ = tx.send(StreamChunk::Error(e.to_string())).await;
@@ -533,6 +533,18 @@ impl LlmProvider for OpenAiProvider {
     }
 }
 
+/// Serialize a structured-output request as an OpenAI `json_schema` response format.
+fn response_format_json(format: &ResponseFormat) -> serde_json::Value {
+    serde_json::json!({
+        "type": "json_schema",
+        "json_schema": {
+            "name": format.name,
+            "schema": format.schema,
+            "strict": format.strict,
+        },
+    })
+}
+
 /// Returns true for models that require `max_completion_tokens` instead of `max_tokens`.
 fn uses_max_completion_tokens(model: &str) -> bool {
     let m = model.to_lowercase();
@@ -543,20 +555,21 @@ fn uses_max_completion_tokens(model: &str) -> bool {
         || m.contains("gpt5")
 }
 
-/// Estimate cost for OpenAI models (USD per 1M tokens).
-fn estimate_openai_cost(model: &str, input_tokens: u32, output_tokens: u32) -> f64 {
-    let (input_per_m, output_per_m) = match model {
-        m if m.starts_with("gpt-4o-mini") => (0.15, 0.60),
-        m if m.starts_with("gpt-4o") => (2.50, 10.00),
-        m if m.starts_with("gpt-4-turbo") => (10.00, 30.00),
-        m if m.starts_with("gpt-4") => (30.00, 60.00),
-        m if m.contains("gpt-5") || m.contains("gpt5") => (2.50, 10.00),
-        m if m.starts_with("o3-mini") => (1.10, 4.40),
-        m if m.starts_with("o3") => (10.00, 40.00),
-        m if m.starts_with("o4-mini") => (1.10, 4.40),
-        m if m.starts_with("o1-mini") => (3.00, 12.00),
-        m if m.starts_with("o1") => (15.00, 60.00),
-        _ => (2.50, 10.00), // default to gpt-4o pricing
+/// Estimate cost for OpenAI models from the model catalog.
+/// Configured `pricing` takes precedence over the catalog.
+fn estimate_openai_cost(
+    model: &str,
+    pricing: Option<(f64, f64)>,
+    input_tokens: u32,
+    output_tokens: u32,
+) -> f64 {
+    let pricing = match pricing {
+        Some((input, output)) => ModelPricing::flat(input, output),
+        None => claw_config::catalog()
+            .get(model)
+            .or_else(|| claw_config::catalog().get("gpt-4o")) // default to gpt-4o pricing
+            .map(|m| m.pricing())
+            .unwrap_or(ModelPricing::flat(0.0, 0.0)),
     };
-    (input_tokens as f64 * input_per_m + output_tokens as f64 * output_per_m) / 1_000_000.0
+    pricing.cost(input_tokens, output_tokens, 0, 0)
 }
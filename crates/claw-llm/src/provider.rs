@@ -22,10 +22,43 @@ pub struct LlmRequest {
     pub thinking_level: Option<String>,
     /// Whether to stream the response.
     pub stream: bool,
+    /// Constrain the reply to JSON matching a schema. The response text is
+    /// then the JSON document; `ModelRouter::complete` validates it.
+    pub response_format: Option<ResponseFormat>,
+}
+
+/// A JSON Schema the model's reply must conform to (structured output).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponseFormat {
+    /// Schema name (letters, digits, `_` and `-`). Anthropic uses it as the
+    /// name of the forced tool.
+    pub name: String,
+    /// JSON Schema for the reply. The top level should be an object.
+    pub schema: serde_json::Value,
+    /// Ask providers that support it (OpenAI) to enforce the schema strictly.
+    /// Strict schemas must list every property in `required` and set
+    /// `additionalProperties: false`.
+    pub strict: bool,
+}
+
+impl ResponseFormat {
+    pub fn new(name: impl Into<String>, schema: serde_json::Value) -> Self {
+        Self {
+            name: name.into(),
+            schema,
+            strict: false,
+        }
+    }
+
+    /// Request strict schema enforcement.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
 }
 
 /// A complete (non-streaming) response from an LLM.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmResponse {
     pub message: Message,
     pub usage: Usage,
@@ -46,7 +79,8 @@ pub enum StopReason {
 }
 
 /// A chunk of a streaming response.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum StreamChunk {
     /// Thinking / reasoning text (shown to user as "thinking...").
     Thinking(String),
@@ -60,6 +94,9 @@ pub enum StreamChunk {
     Done(StopReason),
     /// An error occurred mid-stream.
     Error(String),
+    /// The provider refused the request with a 429. Ends the stream like
+    /// `Error`.
+    RateLimited { retry_after_secs: u64 },
 }
 
 /// Token usage statistics.
@@ -72,6 +109,10 @@ pub struct Usage {
     pub cache_write_tokens: u32,
     /// Estimated cost in USD (computed by the provider adapter).
     pub estimated_cost_usd: f64,
+    /// Model that answered, as routed (e.g. `anthropic/claude-haiku-4-5`).
+    /// Set by `ModelRouter`; `None` straight from a provider.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
 }
 
 impl Usage {
@@ -86,6 +127,9 @@ impl Usage {
         self.cache_read_tokens += other.cache_read_tokens;
         self.cache_write_tokens += other.cache_write_tokens;
         self.estimated_cost_usd += other.estimated_cost_usd;
+        if other.model.is_some() {
+            self.model = other.model.clone();
+        }
     }
 }
 
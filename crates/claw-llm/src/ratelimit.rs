@@ -0,0 +1,275 @@
+//! Client-side rate limiting — per-provider request / token budgets.
+//!
+//! Every call through `ModelRouter` waits its turn in a FIFO queue per
+//! provider until the provider's requests-per-minute and tokens-per-minute
+//! windows have room. A `429` from the provider blocks the whole queue until
+//! its `retry-after` passes, so concurrent sessions, sub-agents and cron tasks
+//! back off together instead of each hammering the API on its own schedule.
+
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::provider::StreamChunk;
+use claw_core::ClawError;
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+
+/// Length of the sliding rate-limit window.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Back-off when a 429 carries no usable retry hint.
+const DEFAULT_RETRY_AFTER_SECS: u64 = 30;
+
+/// Longest back-off a provider's retry hint can impose. A hostile or
+/// broken header shouldn't stall every caller indefinitely.
+pub const MAX_RETRY_AFTER_SECS: u64 = 600;
+
+/// Per-minute budgets for one provider. 0 = unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProviderLimits {
+    pub requests_per_minute: u32,
+    pub tokens_per_minute: u32,
+}
+
+impl ProviderLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.requests_per_minute == 0 && self.tokens_per_minute == 0
+    }
+}
+
+/// Queue and usage window for one provider.
+#[derive(Default)]
+struct Lane {
+    /// Held while a caller waits for budget — tokio's mutex is FIFO-fair.
+    gate: tokio::sync::Mutex<()>,
+    /// Callers queued or waiting for budget.
+    waiting: AtomicUsize,
+    /// Admitted calls in the current window: (when, reserved tokens).
+    window: Mutex<VecDeque<(Instant, u32)>>,
+    /// Provider-imposed pause from a 429's retry-after.
+    blocked_until: Mutex<Option<Instant>>,
+}
+
+impl Lane {
+    /// How long until a call of `tokens` fits in the window (zero = now).
+    fn wait_for(&self, limits: ProviderLimits, tokens: u32, now: Instant) -> Duration {
+        let mut window = self.window.lock();
+        while window.front().is_some_and(|(t, _)| now - *t >= WINDOW) {
+            window.pop_front();
+        }
+        let mut ready_at = now;
+
+        let rpm = limits.requests_per_minute as usize;
+        if rpm > 0 && window.len() >= rpm {
+            ready_at = ready_at.max(window[window.len() - rpm].0 + WINDOW);
+        }
+
+        let tpm = limits.tokens_per_minute as u64;
+        let used: u64 = window.iter().map(|(_, n)| *n as u64).sum();
+        if tpm > 0 && used + tokens as u64 > tpm {
+            // Oldest entries that must expire; a call larger than the whole
+            // budget runs once the window is empty
+            let mut remaining = used;
+            for (t, n) in window.iter() {
+                remaining -= *n as u64;
+                if remaining + tokens as u64 <= tpm || remaining == 0 {
+                    ready_at = ready_at.max(*t + WINDOW);
+                    break;
+                }
+            }
+        }
+
+        if let Some(until) = *self.blocked_until.lock() {
+            ready_at = ready_at.max(until);
+        }
+        ready_at.saturating_duration_since(now)
+    }
+}
+
+/// Decrements a lane's queue depth when the caller is admitted (or gives up).
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Per-provider admission control shared by all callers of a router.
+#[derive(Default)]
+pub struct RateLimiter {
+    limits: Mutex<HashMap<String, ProviderLimits>>,
+    lanes: Mutex<HashMap<String, Arc<Lane>>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the budgets for a provider (by provider name).
+    pub fn set_limits(&self, provider: &str, limits: ProviderLimits) {
+        self.limits.lock().insert(provider.to_string(), limits);
+    }
+
+    fn lane(&self, provider: &str) -> Arc<Lane> {
+        Arc::clone(self.lanes.lock().entry(provider.to_string()).or_default())
+    }
+
+    /// Wait until a call of about `tokens` tokens may be sent to `provider`,
+    /// then reserve it. Callers are admitted in arrival order.
+    pub async fn acquire(&self, provider: &str, tokens: u32) {
+        let limits = self
+            .limits
+            .lock()
+            .get(provider)
+            .copied()
+            .unwrap_or_default();
+        let lane = self.lane(provider);
+        lane.waiting.fetch_add(1, Ordering::Relaxed);
+        let _waiting = Waiting(&lane.waiting);
+        let _turn = lane.gate.lock().await;
+        loop {
+            let wait = lane.wait_for(limits, tokens, Instant::now());
+            if wait.is_zero() {
+                break;
+            }
+            tracing::debug!(
+                provider,
+                wait_ms = wait.as_millis() as u64,
+                "rate limit budget exhausted, queueing request"
+            );
+            tokio::time::sleep(wait).await;
+        }
+        lane.window.lock().push_back((Instant::now(), tokens));
+    }
+
+    /// Pause every caller of `provider` for `retry_after` (a 429 response),
+    /// at most [`MAX_RETRY_AFTER_SECS`].
+    pub fn block(&self, provider: &str, retry_after: Duration) {
+        let lane = self.lane(provider);
+        let retry_after = retry_after.min(Duration::from_secs(MAX_RETRY_AFTER_SECS));
+        let now = Instant::now();
+        let until = now.checked_add(retry_after).unwrap_or(now);
+        let mut blocked = lane.blocked_until.lock();
+        if blocked.is_none_or(|b| b < until) {
+            tracing::warn!(
+                provider,
+                retry_after_secs = retry_after.as_secs(),
+                "provider rate limited, pausing all requests"
+            );
+            *blocked = Some(until);
+        }
+    }
+
+    /// Callers currently queued per provider, sorted by provider name.
+    pub fn queue_depths(&self) -> Vec<(String, usize)> {
+        let mut depths: Vec<(String, usize)> = self
+            .lanes
+            .lock()
+            .iter()
+            .map(|(name, lane)| (name.clone(), lane.waiting.load(Ordering::Relaxed)))
+            .collect();
+        depths.sort();
+        depths
+    }
+}
+
+// ── HTTP helpers ───────────────────────────────────────────────
+
+/// Seconds to wait from a 429's headers: `retry-after-ms`, `retry-after`
+/// (delta-seconds or HTTP date), then OpenAI's `x-ratelimit-reset-*`.
+/// Capped at [`MAX_RETRY_AFTER_SECS`]; negative and non-finite values are
+/// ignored.
+pub fn retry_after_secs(headers: &HeaderMap) -> Option<u64> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.trim().parse::<f64>().ok())
+        && let Some(secs) = clamp_secs(ms / 1000.0)
+    {
+        return Some(secs);
+    }
+    if let Some(value) = header("retry-after") {
+        let value = value.trim();
+        if let Ok(secs) = value.parse::<f64>()
+            && let Some(secs) = clamp_secs(secs)
+        {
+            return Some(secs);
+        }
+        if let Ok(date) = chrono::DateTime::parse_from_rfc2822(value) {
+            let secs = (date.with_timezone(&chrono::Utc) - chrono::Utc::now()).num_seconds();
+            return Some(secs.clamp(0, MAX_RETRY_AFTER_SECS as i64) as u64);
+        }
+    }
+    ["x-ratelimit-reset-requests", "x-ratelimit-reset-tokens"]
+        .iter()
+        .filter_map(|name| header(name).and_then(parse_reset_duration))
+        .max()
+}
+
+/// Parse Go-style durations like `1s`, `6m0s`, `250ms`, `1.5s` into whole seconds.
+fn parse_reset_duration(value: &str) -> Option<u64> {
+    let mut total = 0.0;
+    let mut rest = value.trim();
+    while !rest.is_empty() {
+        let split = rest.find(|c: char| c.is_ascii_alphabetic())?;
+        let number: f64 = rest[..split].parse().ok()?;
+        rest = &rest[split..];
+        let unit_len = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        total += match &rest[..unit_len] {
+            "ms" => number / 1000.0,
+            "s" => number,
+            "m" => number * 60.0,
+            "h" => number * 3600.0,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+    }
+    clamp_secs(total)
+}
+
+/// Whole seconds from a parsed hint, or `None` for `NaN`, infinities and
+/// negative values.
+fn clamp_secs(secs: f64) -> Option<u64> {
+    if !secs.is_finite() || secs < 0.0 {
+        return None;
+    }
+    Some(secs.ceil().min(MAX_RETRY_AFTER_SECS as f64) as u64)
+}
+
+/// The error for a non-success provider response: `RateLimited` for 429
+/// (with the parsed retry-after), otherwise `HTTP {status}: {body}`.
+pub fn http_error(status: StatusCode, headers: &HeaderMap, body: &str) -> ClawError {
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return ClawError::RateLimited {
+            retry_after_secs: retry_after_secs(headers).unwrap_or(DEFAULT_RETRY_AFTER_SECS),
+        };
+    }
+    ClawError::LlmProvider(format!("HTTP {status}: {body}"))
+}
+
+/// Read a failed response into [`http_error`].
+pub async fn error_from_response(resp: reqwest::Response) -> ClawError {
+    let status = resp.status();
+    let headers = resp.headers().clone();
+    let body = resp.text().await.unwrap_or_default();
+    http_error(status, &headers, &body)
+}
+
+/// The chunk that ends a stream whose response failed: `RateLimited` for a
+/// 429, so the router can back off, otherwise `Error`.
+pub async fn stream_error(resp: reqwest::Response) -> StreamChunk {
+    match error_from_response(resp).await {
+        ClawError::RateLimited { retry_after_secs } => {
+            StreamChunk::RateLimited { retry_after_secs }
+        }
+        ClawError::LlmProvider(message) => StreamChunk::Error(message),
+        other => StreamChunk::Error(other.to_string()),
+    }
+}
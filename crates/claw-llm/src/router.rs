@@ -5,7 +5,11 @@ use std::time::{Duration, Instant};
 use tracing::{info, warn};
 
 use crate::provider::{LlmProvider, LlmRequest, LlmResponse, StreamChunk};
-use claw_core::Result;
+use crate::ratelimit::{ProviderLimits, RateLimiter};
+use crate::routing::{self, LatencyTracker, PrimaryPolicy, RouteContext, RoutingPolicy};
+use crate::structured;
+use claw_config::ClawConfig;
+use claw_core::{Message, Result, Role};
 
 /// Maximum retry attempts for transient errors (429, 500, 502, 503).
 const MAX_RETRIES: u32 = 3;
@@ -94,12 +98,21 @@ impl CircuitBreaker {
     }
 }
 
-/// Routes model requests to the correct provider, with automatic failover.
+/// Routes model requests to the correct provider, with policy-based model
+/// selection and automatic failover.
 #[derive(Clone)]
 pub struct ModelRouter {
     providers: Vec<Arc<dyn LlmProvider>>,
     /// Circuit breakers keyed by provider name.
     breakers: Arc<Mutex<HashMap<String, CircuitBreaker>>>,
+    /// Chooses the model for each request.
+    policy: Arc<dyn RoutingPolicy>,
+    /// Observed latency per route, for latency-aware policies.
+    latency: Arc<LatencyTracker>,
+    /// Per-request cost cap in USD, and the cheaper model to downgrade to.
+    cost_cap: Option<(f64, Option<String>)>,
+    /// Per-provider request / token budgets and 429 back-off, shared by all callers.
+    limiter: Arc<RateLimiter>,
 }
 
 /// Check if an error is transient and worth retrying.
@@ -131,6 +144,14 @@ fn retry_after_hint(err: &claw_core::ClawError) -> Option<u64> {
     }
 }
 
+/// Replace a response's message text with canonical JSON.
+fn with_json_text(mut response: LlmResponse, value: &serde_json::Value) -> LlmResponse {
+    response.message.content = vec![claw_core::MessageContent::Text {
+        text: value.to_string(),
+    }];
+    response
+}
+
 impl Default for ModelRouter {
     fn default() -> Self {
         Self::new()
@@ -142,6 +163,89 @@ impl ModelRouter {
         Self {
             providers: vec![],
             breakers: Arc::new(Mutex::new(HashMap::new())),
+            policy: Arc::new(PrimaryPolicy),
+            latency: Arc::new(LatencyTracker::new()),
+            cost_cap: None,
+            limiter: Arc::new(RateLimiter::new()),
+        }
+    }
+
+    /// A router with the policy and cost cap from `[routing]` and the rate
+    /// limits from `[providers.*]`.
+    pub fn from_config(config: &ClawConfig) -> Self {
+        let mut router = Self::new().with_policy(routing::policy_from_config(config));
+        if config.routing.max_cost_per_request_usd > 0.0 {
+            router = router.with_cost_cap(
+                config.routing.max_cost_per_request_usd,
+                config.agent.fast_model.clone(),
+            );
+        }
+        for (name, p) in &config.providers {
+            router = router.with_rate_limit(
+                name,
+                ProviderLimits {
+                    requests_per_minute: p.requests_per_minute,
+                    tokens_per_minute: p.tokens_per_minute,
+                },
+            );
+        }
+        router
+    }
+
+    /// Use a routing policy (default: always the requested model).
+    pub fn with_policy(mut self, policy: Arc<dyn RoutingPolicy>) -> Self {
+        info!(policy = policy.name(), "model routing policy");
+        self.policy = policy;
+        self
+    }
+
+    /// Cap the worst-case cost of each request. Over-cap requests are sent to
+    /// `downgrade_to` if that fits, otherwise refused with `BudgetExceeded`.
+    pub fn with_cost_cap(mut self, max_usd: f64, downgrade_to: Option<String>) -> Self {
+        self.cost_cap = Some((max_usd, downgrade_to));
+        self
+    }
+
+    /// Limit requests / tokens per minute sent to a provider (by provider
+    /// name). Calls over budget wait in a FIFO queue.
+    pub fn with_rate_limit(self, provider: &str, limits: ProviderLimits) -> Self {
+        if !limits.is_unlimited() {
+            info!(
+                provider,
+                rpm = limits.requests_per_minute,
+                tpm = limits.tokens_per_minute,
+                "provider rate limit"
+            );
+            self.limiter.set_limits(provider, limits);
+        }
+        self
+    }
+
+    /// Observed latency per route.
+    pub fn latency(&self) -> &LatencyTracker {
+        &self.latency
+    }
+
+    /// Calls waiting for rate-limit budget, per provider.
+    pub fn queue_depths(&self) -> Vec<(String, usize)> {
+        self.limiter.queue_depths()
+    }
+
+    /// Wait for rate-limit budget for one call to `provider`.
+    async fn admit(&self, provider: &dyn LlmProvider, request: &LlmRequest) {
+        let tokens = routing::estimate_prompt_tokens(request) as u32 + request.max_tokens;
+        self.limiter.acquire(provider.name(), tokens).await;
+    }
+
+    /// Pause all callers of a provider that answered 429, and report whether
+    /// the error was one.
+    fn note_rate_limit(&self, provider: &str, err: &claw_core::ClawError) -> bool {
+        match retry_after_hint(err) {
+            Some(secs) => {
+                self.limiter.block(provider, Duration::from_secs(secs));
+                true
+            }
+            None => false,
         }
     }
 
@@ -174,8 +278,12 @@ impl ModelRouter {
         }
     }
 
-    /// Record a failure for a provider.
-    fn record_failure(&self, provider_name: &str) {
+    /// Record a failure for a provider. Rate limiting doesn't count — the
+    /// provider is healthy, and the limiter already pauses its queue.
+    fn record_failure(&self, provider_name: &str, err: &claw_core::ClawError) {
+        if matches!(err, claw_core::ClawError::RateLimited { .. }) {
+            return;
+        }
         let mut breakers = self.breakers.lock();
         if let Some(cb) = breakers.get_mut(provider_name) {
             let was_open = cb.is_open();
@@ -191,6 +299,84 @@ impl ModelRouter {
         }
     }
 
+    /// Pick the model for a request: the routing policy's choice, downgraded
+    /// (or refused) if it would break the per-request cost cap.
+    fn select_model(&self, request: &LlmRequest) -> Result<String> {
+        let mut selected = self.policy.select(&RouteContext {
+            request,
+            latency: &self.latency,
+        });
+        if selected != request.model && self.resolve(&selected).is_none() {
+            warn!(model = %selected, "routed model has no provider, using the requested model");
+            selected = request.model.clone();
+        }
+        let Some((cap, ref downgrade)) = self.cost_cap else {
+            return Ok(selected);
+        };
+        let Some(cost) = routing::estimate_request_cost(&selected, request) else {
+            return Ok(selected);
+        };
+        if cost <= cap {
+            return Ok(selected);
+        }
+        if let Some(cheaper) = downgrade
+            && routing::estimate_request_cost(cheaper, request).is_some_and(|c| c <= cap)
+        {
+            warn!(
+                model = %selected,
+                downgrade = %cheaper,
+                estimate_usd = cost,
+                cap_usd = cap,
+                "request over cost cap, downgrading model"
+            );
+            return Ok(cheaper.clone());
+        }
+        Err(claw_core::ClawError::BudgetExceeded {
+            resource: format!("request cost ({selected})"),
+            used: cost,
+            limit: cap,
+        })
+    }
+
+    /// Tag a response with its route and record the call's latency.
+    fn finish(&self, route: &str, started: Instant, mut response: LlmResponse) -> LlmResponse {
+        self.latency.record(route, started.elapsed());
+        response.usage.model = Some(route.to_string());
+        response
+    }
+
+    /// Forward a stream, tagging its usage with the route and recording the
+    /// latency once it completes.
+    fn track_stream(
+        &self,
+        provider: &str,
+        route: &str,
+        started: Instant,
+        mut rx: tokio::sync::mpsc::Receiver<StreamChunk>,
+    ) -> tokio::sync::mpsc::Receiver<StreamChunk> {
+        let (tx, out) = tokio::sync::mpsc::channel(256);
+        let provider = provider.to_string();
+        let route = route.to_string();
+        let latency = Arc::clone(&self.latency);
+        let limiter = Arc::clone(&self.limiter);
+        tokio::spawn(async move {
+            while let Some(mut chunk) = rx.recv().await {
+                match chunk {
+                    StreamChunk::Usage(ref mut usage) => usage.model = Some(route.clone()),
+                    StreamChunk::Done(_) => latency.record(&route, started.elapsed()),
+                    StreamChunk::RateLimited { retry_after_secs } => {
+                        limiter.block(&provider, Duration::from_secs(retry_after_secs));
+                    }
+                    _ => {}
+                }
+                if tx.send(chunk).await.is_err() {
+                    break;
+                }
+            }
+        });
+        out
+    }
+
     /// Find the right provider for a model string like "anthropic/claude-opus-4-6".
     fn resolve(&self, model: &str) -> Option<(Arc<dyn LlmProvider>, String)> {
         // Format: "provider/model-name" or just "model-name" (try all providers)
@@ -211,11 +397,82 @@ impl ModelRouter {
     }
 
     /// Complete a request, with retry on transient errors and failover to alternative providers.
+    ///
+    /// When the request carries a `response_format`, the reply is validated
+    /// against its schema. An invalid reply gets one repair attempt (the model
+    /// is shown its output and the validation error); if that also fails the
+    /// call errors. On success the message text is the canonical JSON.
     pub async fn complete(
         &self,
         request: &LlmRequest,
         fallback_model: Option<&str>,
     ) -> Result<LlmResponse> {
+        let Some(ref format) = request.response_format else {
+            return self.complete_routed(request, fallback_model).await;
+        };
+
+        let response = self.complete_routed(request, fallback_model).await?;
+        let text = response.message.text_content();
+        let error = match structured::parse_and_validate(&text, &format.schema) {
+            Ok(value) => return Ok(with_json_text(response, &value)),
+            Err(e) => e,
+        };
+        warn!(schema = %format.name, error = %error, "structured output invalid, asking for a repair");
+
+        let mut repair = request.clone();
+        repair
+            .messages
+            .push(Message::text(uuid::Uuid::nil(), Role::Assistant, text));
+        repair.messages.push(Message::text(
+            uuid::Uuid::nil(),
+            Role::User,
+            format!(
+                "Your reply did not match the required JSON schema: {error}. \
+                 Reply again with only the corrected JSON."
+            ),
+        ));
+        let mut retry = self.complete_routed(&repair, fallback_model).await?;
+        retry.usage.merge(&response.usage);
+        match structured::parse_and_validate(&retry.message.text_content(), &format.schema) {
+            Ok(value) => Ok(with_json_text(retry, &value)),
+            Err(e) => Err(claw_core::ClawError::LlmProvider(format!(
+                "structured output did not match schema '{}': {e}",
+                format.name
+            ))),
+        }
+    }
+
+    /// Route a complete() call through the policy, escalating a weak reply
+    /// if the policy asks for it.
+    async fn complete_routed(
+        &self,
+        request: &LlmRequest,
+        fallback_model: Option<&str>,
+    ) -> Result<LlmResponse> {
+        let mut routed = request.clone();
+        routed.model = self.select_model(request)?;
+        let response = self.complete_with_failover(&routed, fallback_model).await?;
+
+        let answered_by = response.usage.model.as_deref().unwrap_or(&routed.model);
+        let Some(escalated) = self.policy.escalate(request, answered_by, &response) else {
+            return Ok(response);
+        };
+        info!(from = %answered_by, to = %escalated, "escalating low-confidence reply");
+        routed.model = escalated;
+        let mut retry = self.complete_with_failover(&routed, fallback_model).await?;
+        let mut usage = response.usage;
+        usage.merge(&retry.usage);
+        retry.usage = usage;
+        Ok(retry)
+    }
+
+    /// Send a complete() call to the given model's provider, then the fallback.
+    async fn complete_with_failover(
+        &self,
+        request: &LlmRequest,
+        fallback_model: Option<&str>,
+    ) -> Result<LlmResponse> {
+        let started = Instant::now();
         // Try primary with retries (if circuit is closed)
         if let Some((provider, model_name)) = self.resolve(&request.model) {
             if self.is_available(provider.name()) {
@@ -225,10 +482,10 @@ impl ModelRouter {
                 match self.complete_with_retry(&*provider, &req).await {
                     Ok(resp) => {
                         self.record_success(provider.name());
-                        return Ok(resp);
+                        return Ok(self.finish(&request.model, started, resp));
                     }
                     Err(e) => {
-                        self.record_failure(provider.name());
+                        self.record_failure(provider.name(), &e);
                         warn!(
                             provider = provider.name(),
                             error = %e,
@@ -254,10 +511,10 @@ impl ModelRouter {
             match self.complete_with_retry(&*provider, &req).await {
                 Ok(resp) => {
                     self.record_success(provider.name());
-                    return Ok(resp);
+                    return Ok(self.finish(fallback, started, resp));
                 }
                 Err(e) => {
-                    self.record_failure(provider.name());
+                    self.record_failure(provider.name(), &e);
                     return Err(e);
                 }
             }
@@ -267,11 +524,79 @@ impl ModelRouter {
     }
 
     /// Stream a request with retry on transient errors and failover.
+    ///
+    /// The routing policy picks the model up front. A reply from a model the
+    /// policy may escalate is buffered and only passed on once it's judged
+    /// good enough; otherwise the escalated model's stream is returned, its
+    /// usage including the discarded reply's. The final `StreamChunk::Usage`
+    /// names the model that answered.
     pub async fn stream(
         &self,
         request: &LlmRequest,
         fallback_model: Option<&str>,
     ) -> Result<tokio::sync::mpsc::Receiver<StreamChunk>> {
+        let mut routed = request.clone();
+        routed.model = self.select_model(request)?;
+        let mut rx = self.stream_with_failover(&routed, fallback_model).await?;
+        if !self.policy.escalates(&routed.model) {
+            return Ok(rx);
+        }
+
+        let mut chunks = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            chunks.push(chunk);
+        }
+        let buffered = buffered_reply(&chunks);
+        let answered_by = buffered.usage.model.as_deref().unwrap_or(&routed.model);
+        let escalated = match buffered.stop_reason {
+            Some(stop_reason) => self.policy.escalate(
+                request,
+                answered_by,
+                &LlmResponse {
+                    message: Message::text(uuid::Uuid::nil(), Role::Assistant, buffered.text),
+                    usage: buffered.usage.clone(),
+                    has_tool_calls: buffered.has_tool_calls,
+                    stop_reason,
+                },
+            ),
+            // Errors are passed on as they are
+            None => None,
+        };
+        let (tx, out) = tokio::sync::mpsc::channel(chunks.len().max(1));
+        let Some(escalated) = escalated else {
+            for chunk in chunks {
+                let _ = tx.try_send(chunk);
+            }
+            return Ok(out);
+        };
+        info!(from = %answered_by, to = %escalated, "escalating low-confidence streamed reply");
+        routed.model = escalated;
+        let mut rx = self.stream_with_failover(&routed, fallback_model).await?;
+        let mut discarded = Some(buffered.usage);
+        tokio::spawn(async move {
+            while let Some(mut chunk) = rx.recv().await {
+                if let StreamChunk::Usage(ref mut usage) = chunk
+                    && let Some(mut total) = discarded.take()
+                {
+                    total.merge(usage);
+                    *usage = total;
+                }
+                if tx.send(chunk).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(out)
+    }
+
+    /// Open a stream to the request's model's provider, then the fallback.
+    async fn stream_with_failover(
+        &self,
+        request: &LlmRequest,
+        fallback_model: Option<&str>,
+    ) -> Result<tokio::sync::mpsc::Receiver<StreamChunk>> {
+        let started = Instant::now();
+
         // Try primary with retries (if circuit is closed)
         if let Some((provider, model_name)) = self.resolve(&request.model) {
             if self.is_available(provider.name()) {
@@ -281,10 +606,10 @@ impl ModelRouter {
                 match self.stream_with_retry(&*provider, &req).await {
                     Ok(rx) => {
                         self.record_success(provider.name());
-                        return Ok(rx);
+                        return Ok(self.track_stream(provider.name(), &request.model, started, rx));
                     }
                     Err(e) => {
-                        self.record_failure(provider.name());
+                        self.record_failure(provider.name(), &e);
                         warn!(
                             provider = provider.name(),
                             error = %e,
@@ -310,10 +635,10 @@ impl ModelRouter {
             match self.stream_with_retry(&*provider, &req).await {
                 Ok(rx) => {
                     self.record_success(provider.name());
-                    return Ok(rx);
+                    return Ok(self.track_stream(provider.name(), fallback, started, rx));
                 }
                 Err(e) => {
-                    self.record_failure(provider.name());
+                    self.record_failure(provider.name(), &e);
                     return Err(e);
                 }
             }
@@ -331,9 +656,11 @@ impl ModelRouter {
         let mut last_err = None;
 
         for attempt in 0..=MAX_RETRIES {
+            self.admit(provider, request).await;
             match provider.complete(request).await {
                 Ok(resp) => return Ok(resp),
                 Err(e) if is_retryable(&e) && attempt < MAX_RETRIES => {
+                    let rate_limited = self.note_rate_limit(provider.name(), &e);
                     let delay = retry_after_hint(&e)
                         .map(|s| s * 1000)
                         .unwrap_or(BASE_DELAY_MS * 2u64.pow(attempt));
@@ -345,10 +672,16 @@ impl ModelRouter {
                         error = %e,
                         "retrying after transient error"
                     );
-                    tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+                    // A 429 pauses the provider's whole queue — admit() waits it out
+                    if !rate_limited {
+                        tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+                    }
                     last_err = Some(e);
                 }
-                Err(e) => return Err(e),
+                Err(e) => {
+                    self.note_rate_limit(provider.name(), &e);
+                    return Err(e);
+                }
             }
         }
 
@@ -364,9 +697,11 @@ impl ModelRouter {
         let mut last_err = None;
 
         for attempt in 0..=MAX_RETRIES {
+            self.admit(provider, request).await;
             match provider.stream(request).await {
                 Ok(rx) => return Ok(rx),
                 Err(e) if is_retryable(&e) && attempt < MAX_RETRIES => {
+                    let rate_limited = self.note_rate_limit(provider.name(), &e);
                     let delay = retry_after_hint(&e)
                         .map(|s| s * 1000)
                         .unwrap_or(BASE_DELAY_MS * 2u64.pow(attempt));
@@ -378,13 +713,48 @@ impl ModelRouter {
                         error = %e,
                         "retrying stream after transient error"
                     );
-                    tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+                    // A 429 pauses the provider's whole queue — admit() waits it out
+                    if !rate_limited {
+                        tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+                    }
                     last_err = Some(e);
                 }
-                Err(e) => return Err(e),
+                Err(e) => {
+                    self.note_rate_limit(provider.name(), &e);
+                    return Err(e);
+                }
             }
         }
 
         Err(last_err.unwrap())
     }
 }
+
+/// What a buffered stream said, for judging it like a complete() reply.
+/// `stop_reason` is `None` if the stream ended without finishing.
+struct BufferedReply {
+    text: String,
+    has_tool_calls: bool,
+    usage: crate::provider::Usage,
+    stop_reason: Option<crate::provider::StopReason>,
+}
+
+fn buffered_reply(chunks: &[StreamChunk]) -> BufferedReply {
+    let mut reply = BufferedReply {
+        text: String::new(),
+        has_tool_calls: false,
+        usage: Default::default(),
+        stop_reason: None,
+    };
+    for chunk in chunks {
+        match chunk {
+            StreamChunk::TextDelta(text) => reply.text.push_str(text),
+            StreamChunk::ToolCall(_) => reply.has_tool_calls = true,
+            StreamChunk::Usage(usage) => reply.usage.merge(usage),
+            StreamChunk::Done(stop_reason) => reply.stop_reason = Some(*stop_reason),
+            StreamChunk::Error(_) | StreamChunk::RateLimited { .. } => return reply,
+            StreamChunk::Thinking(_) => {}
+        }
+    }
+    reply
+}
@@ -0,0 +1,282 @@
+//! Routing policies — decide which model answers a request.
+//!
+//! `ModelRouter` asks its policy for a model before every call, then applies
+//! the per-request cost cap and the usual retry / failover. Policies see the
+//! request and the observed latency of every route, and may escalate a weak
+//! reply to a stronger model — streamed replies from a model that may be
+//! escalated are buffered until they've been judged.
+
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::provider::{LlmRequest, LlmResponse, StopReason};
+use claw_config::ClawConfig;
+use claw_core::Role;
+
+/// Latency samples kept per route for percentile estimates.
+const LATENCY_WINDOW: usize = 50;
+
+/// Phrases that mark a reply as low-confidence for cascade escalation.
+const HEDGES: &[&str] = &[
+    "i'm not sure",
+    "i am not sure",
+    "i don't know",
+    "i do not know",
+    "i'm unable to",
+    "i am unable to",
+    "i can't help",
+    "i cannot help",
+];
+
+// ── Latency tracking ───────────────────────────────────────────
+
+/// Rolling window of observed response latencies, keyed by route
+/// (the model string a request was sent to, e.g. `openai/gpt-4o-mini`).
+#[derive(Debug, Default)]
+pub struct LatencyTracker {
+    samples: Mutex<HashMap<String, VecDeque<Duration>>>,
+}
+
+impl LatencyTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one completed call.
+    pub fn record(&self, route: &str, latency: Duration) {
+        let mut samples = self.samples.lock();
+        let window = samples.entry(route.to_string()).or_default();
+        if window.len() == LATENCY_WINDOW {
+            window.pop_front();
+        }
+        window.push_back(latency);
+    }
+
+    /// Median latency over the recent window, if the route has been used.
+    pub fn p50(&self, route: &str) -> Option<Duration> {
+        let samples = self.samples.lock();
+        let mut sorted: Vec<Duration> = samples.get(route)?.iter().copied().collect();
+        sorted.sort();
+        sorted.get(sorted.len() / 2).copied()
+    }
+
+    /// Number of recorded samples for a route.
+    pub fn sample_count(&self, route: &str) -> usize {
+        self.samples.lock().get(route).map_or(0, |w| w.len())
+    }
+}
+
+// ── Policies ───────────────────────────────────────────────────
+
+/// Inputs available to a policy when choosing a model.
+pub struct RouteContext<'a> {
+    pub request: &'a LlmRequest,
+    pub latency: &'a LatencyTracker,
+}
+
+/// Chooses the model for each request.
+pub trait RoutingPolicy: Send + Sync {
+    /// Policy name, for logs.
+    fn name(&self) -> &str;
+
+    /// The model to send the request to. `ctx.request.model` is the model the
+    /// caller asked for.
+    fn select(&self, ctx: &RouteContext<'_>) -> String;
+
+    /// Whether replies from `routed` may be escalated. The router buffers
+    /// their streams so it can.
+    fn escalates(&self, _routed: &str) -> bool {
+        false
+    }
+
+    /// After a reply from `routed`, optionally name a model to retry the
+    /// original request with.
+    fn escalate(
+        &self,
+        _request: &LlmRequest,
+        _routed: &str,
+        _response: &LlmResponse,
+    ) -> Option<String> {
+        None
+    }
+}
+
+/// Always use the requested model.
+pub struct PrimaryPolicy;
+
+impl RoutingPolicy for PrimaryPolicy {
+    fn name(&self) -> &str {
+        "primary"
+    }
+
+    fn select(&self, ctx: &RouteContext<'_>) -> String {
+        ctx.request.model.clone()
+    }
+}
+
+/// Try the fast model first; use the primary for tool-heavy turns and
+/// escalate to it when the fast model's reply looks unreliable.
+///
+/// Only requests for the primary model are cascaded — background tasks that
+/// already ask for a specific model (compaction, lessons, titles) are left
+/// alone. Streamed fast replies reach the caller only once they've passed.
+pub struct CascadePolicy {
+    primary: String,
+    fast: String,
+    tool_threshold: usize,
+}
+
+impl CascadePolicy {
+    pub fn new(primary: impl Into<String>, fast: impl Into<String>, tool_threshold: usize) -> Self {
+        Self {
+            primary: primary.into(),
+            fast: fast.into(),
+            tool_threshold,
+        }
+    }
+}
+
+impl RoutingPolicy for CascadePolicy {
+    fn name(&self) -> &str {
+        "cascade"
+    }
+
+    fn select(&self, ctx: &RouteContext<'_>) -> String {
+        let request = ctx.request;
+        if request.model != self.primary
+            || tool_calls_this_turn(request) >= self.tool_threshold.max(1)
+        {
+            return request.model.clone();
+        }
+        self.fast.clone()
+    }
+
+    fn escalates(&self, routed: &str) -> bool {
+        routed == self.fast
+    }
+
+    fn escalate(
+        &self,
+        _request: &LlmRequest,
+        routed: &str,
+        response: &LlmResponse,
+    ) -> Option<String> {
+        (routed == self.fast && is_low_confidence(response)).then(|| self.primary.clone())
+    }
+}
+
+/// Pick the candidate with the lowest observed p50 latency. Candidates with
+/// no samples yet are tried first so every route gets measured.
+pub struct LatencyPolicy {
+    candidates: Vec<String>,
+}
+
+impl LatencyPolicy {
+    pub fn new(candidates: Vec<String>) -> Self {
+        Self { candidates }
+    }
+}
+
+impl RoutingPolicy for LatencyPolicy {
+    fn name(&self) -> &str {
+        "latency"
+    }
+
+    fn select(&self, ctx: &RouteContext<'_>) -> String {
+        if !self.candidates.contains(&ctx.request.model) {
+            return ctx.request.model.clone();
+        }
+        if let Some(unmeasured) = self
+            .candidates
+            .iter()
+            .find(|c| ctx.latency.sample_count(c) == 0)
+        {
+            return unmeasured.clone();
+        }
+        self.candidates
+            .iter()
+            .min_by_key(|c| ctx.latency.p50(c))
+            .cloned()
+            .unwrap_or_else(|| ctx.request.model.clone())
+    }
+}
+
+/// Build the policy selected by `[routing]`.
+pub fn policy_from_config(config: &ClawConfig) -> Arc<dyn RoutingPolicy> {
+    let agent = &config.agent;
+    match config.routing.policy.as_str() {
+        "cascade" => match agent.fast_model {
+            Some(ref fast) => Arc::new(CascadePolicy::new(
+                agent.model.clone(),
+                fast.clone(),
+                config.routing.cascade_tool_threshold,
+            )),
+            None => Arc::new(PrimaryPolicy),
+        },
+        "latency" => {
+            let candidates = if config.routing.candidates.is_empty() {
+                std::iter::once(&agent.model)
+                    .chain(agent.fast_model.iter())
+                    .chain(agent.fallback_model.iter())
+                    .cloned()
+                    .collect()
+            } else {
+                config.routing.candidates.clone()
+            };
+            Arc::new(LatencyPolicy::new(candidates))
+        }
+        _ => Arc::new(PrimaryPolicy),
+    }
+}
+
+// ── Heuristics ─────────────────────────────────────────────────
+
+/// Tool calls the assistant has made since the last user message.
+fn tool_calls_this_turn(request: &LlmRequest) -> usize {
+    request
+        .messages
+        .iter()
+        .rev()
+        .take_while(|m| m.role != Role::User)
+        .map(|m| m.tool_calls.len())
+        .sum()
+}
+
+/// A reply that was cut off, came back empty, or hedges.
+fn is_low_confidence(response: &LlmResponse) -> bool {
+    if response.stop_reason == StopReason::MaxTokens {
+        return true;
+    }
+    if response.has_tool_calls {
+        return false;
+    }
+    let text = response.message.text_content().to_lowercase();
+    text.trim().is_empty() || HEDGES.iter().any(|h| text.contains(h))
+}
+
+/// Rough prompt size of a request in tokens (≈4 chars per token): system
+/// prompt, tool schemas and messages.
+pub fn estimate_prompt_tokens(request: &LlmRequest) -> usize {
+    let tool_chars: usize = request
+        .tools
+        .iter()
+        .map(|t| t.name.len() + t.description.len() + t.parameters.to_string().len())
+        .sum();
+    request.system.as_ref().map_or(0, |s| s.len() / 4)
+        + tool_chars / 4
+        + request
+            .messages
+            .iter()
+            .map(|m| m.estimate_tokens())
+            .sum::<usize>()
+}
+
+/// Worst-case cost of a request in USD: the prompt plus `max_tokens` of
+/// output at catalog prices. `None` for models missing from the catalog.
+pub fn estimate_request_cost(model: &str, request: &LlmRequest) -> Option<f64> {
+    let pricing = claw_config::catalog().get(model)?.pricing();
+    let prompt_tokens = estimate_prompt_tokens(request);
+    Some(pricing.cost(prompt_tokens as u32, request.max_tokens, 0, 0))
+}
@@ -0,0 +1,207 @@
+//! Structured output helpers — pull JSON out of a model reply and check it
+//! against the request's `ResponseFormat` schema.
+//!
+//! The validator covers the JSON Schema subset models are asked to produce:
+//! `type`, `enum`, `const`, `properties`, `required`, `additionalProperties`,
+//! `items`, `minItems`/`maxItems`, `minLength`/`maxLength`,
+//! `minimum`/`maximum` and `anyOf`/`oneOf`/`allOf`. Unknown keywords (and
+//! `$ref`) are ignored rather than rejected.
+
+use serde_json::Value;
+
+/// Parse the JSON document in a model reply, tolerating markdown code fences
+/// and prose around a single top-level object or array.
+pub fn extract_json(text: &str) -> Option<Value> {
+    let trimmed = text.trim();
+    let unfenced = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .map(|rest| rest.trim_end().trim_end_matches("```"))
+        .unwrap_or(trimmed)
+        .trim();
+    if let Ok(value) = serde_json::from_str(unfenced) {
+        return Some(value);
+    }
+    // Fall back to the outermost {...} or [...] span
+    let start = unfenced.find(['{', '['])?;
+    let close = if unfenced[start..].starts_with('{') {
+        '}'
+    } else {
+        ']'
+    };
+    let end = unfenced.rfind(close)?;
+    (end > start)
+        .then(|| serde_json::from_str(&unfenced[start..=end]).ok())
+        .flatten()
+}
+
+/// Parse `text` as JSON and validate it against `schema`.
+pub fn parse_and_validate(text: &str, schema: &Value) -> Result<Value, String> {
+    let value = extract_json(text).ok_or_else(|| "reply is not valid JSON".to_string())?;
+    validate(&value, schema)?;
+    Ok(value)
+}
+
+/// Validate `value` against `schema`. The error names the first offending path.
+pub fn validate(value: &Value, schema: &Value) -> Result<(), String> {
+    validate_at(value, schema, "$")
+}
+
+fn validate_at(value: &Value, schema: &Value, path: &str) -> Result<(), String> {
+    let Some(schema) = schema.as_object() else {
+        // `true` / `{}` accept anything; `false` accepts nothing
+        return match schema {
+            Value::Bool(false) => Err(format!("{path}: no value allowed")),
+            _ => Ok(()),
+        };
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+            _ => vec![],
+        };
+        if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+            return Err(format!(
+                "{path}: expected {}, got {}",
+                types.join(" or "),
+                type_name(value)
+            ));
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array())
+        && !allowed.contains(value)
+    {
+        return Err(format!(
+            "{path}: {value} is not one of {}",
+            Value::from(allowed.clone())
+        ));
+    }
+    if let Some(constant) = schema.get("const")
+        && constant != value
+    {
+        return Err(format!("{path}: expected {constant}"));
+    }
+
+    if let Some(all) = schema.get("allOf").and_then(|a| a.as_array()) {
+        for sub in all {
+            validate_at(value, sub, path)?;
+        }
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(options) = schema.get(key).and_then(|a| a.as_array())
+            && !options.is_empty()
+        {
+            let mut first_err = None;
+            if !options
+                .iter()
+                .any(|sub| match validate_at(value, sub, path) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        first_err.get_or_insert(e);
+                        false
+                    }
+                })
+            {
+                return Err(first_err.unwrap_or_else(|| format!("{path}: no {key} branch matched")));
+            }
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+                for key in required.iter().filter_map(|k| k.as_str()) {
+                    if !map.contains_key(key) {
+                        return Err(format!("{path}: missing required property '{key}'"));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(|p| p.as_object());
+            for (key, child) in map {
+                let child_path = format!("{path}.{key}");
+                match properties.and_then(|p| p.get(key)) {
+                    Some(sub) => validate_at(child, sub, &child_path)?,
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            return Err(format!("{path}: unexpected property '{key}'"));
+                        }
+                        Some(sub @ Value::Object(_)) => validate_at(child, sub, &child_path)?,
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(|m| m.as_u64())
+                && (items.len() as u64) < min
+            {
+                return Err(format!("{path}: expected at least {min} items"));
+            }
+            if let Some(max) = schema.get("maxItems").and_then(|m| m.as_u64())
+                && (items.len() as u64) > max
+            {
+                return Err(format!("{path}: expected at most {max} items"));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_at(item, item_schema, &format!("{path}[{i}]"))?;
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(|m| m.as_u64())
+                && len < min
+            {
+                return Err(format!("{path}: shorter than {min} characters"));
+            }
+            if let Some(max) = schema.get("maxLength").and_then(|m| m.as_u64())
+                && len > max
+            {
+                return Err(format!("{path}: longer than {max} characters"));
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(|m| m.as_f64())
+                && n < min
+            {
+                return Err(format!("{path}: {n} is below the minimum {min}"));
+            }
+            if let Some(max) = schema.get("maximum").and_then(|m| m.as_f64())
+                && n > max
+            {
+                return Err(format!("{path}: {n} is above the maximum {max}"));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Bool(_) => "boolean",
+        Value::Null => "null",
+        Value::Number(_) => "number",
+    }
+}
@@ -0,0 +1,185 @@
+//! Token counting — BPE tokenizers with per-provider calibration.
+//!
+//! OpenAI models are counted exactly with their own encodings (`o200k_base`
+//! for GPT-4o / 4.1 / 5 and the o-series, `cl100k_base` for older models).
+//! Other providers don't publish their vocabularies, so their counts use the
+//! closest public encoding scaled by a per-provider calibration factor.
+
+use std::sync::Arc;
+
+use claw_core::message::is_image_file;
+use claw_core::{IMAGE_TOKEN_ESTIMATE, Message, MessageContent};
+use tiktoken_rs::CoreBPE;
+
+/// Tokens of role / framing overhead per message (ChatML-style).
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Counts tokens the way a model family does.
+pub trait Tokenizer: Send + Sync {
+    /// Tokenizer name, e.g. `o200k_base`.
+    fn name(&self) -> &str;
+
+    /// Number of tokens in `text`.
+    fn count(&self, text: &str) -> usize;
+
+    /// Number of tokens a message occupies in the prompt: text, tool results
+    /// and tool-call arguments, plus per-message overhead. Images count as a
+    /// flat [`IMAGE_TOKEN_ESTIMATE`].
+    fn count_message(&self, message: &Message) -> usize {
+        let mut tokens = MESSAGE_OVERHEAD_TOKENS;
+        for block in &message.content {
+            tokens += match block {
+                MessageContent::Text { text } => self.count(text),
+                MessageContent::ToolResult {
+                    content,
+                    tool_call_id,
+                    ..
+                } => self.count(content) + self.count(tool_call_id),
+                MessageContent::Image { .. } => IMAGE_TOKEN_ESTIMATE,
+                // Audio payloads are base64 — don't tokenize megabytes of it
+                MessageContent::Audio { data, .. } => data.len().min(1000) / 4,
+                MessageContent::File { path, media_type } => {
+                    if is_image_file(path, media_type.as_deref()) {
+                        IMAGE_TOKEN_ESTIMATE
+                    } else {
+                        self.count(path)
+                    }
+                }
+            };
+        }
+        for tc in &message.tool_calls {
+            tokens += self.count(&tc.tool_name)
+                + self.count(&tc.id)
+                + self.count(&tc.arguments.to_string());
+        }
+        tokens
+    }
+}
+
+/// The legacy ~4 characters per token estimate.
+pub struct HeuristicTokenizer;
+
+impl Tokenizer for HeuristicTokenizer {
+    fn name(&self) -> &str {
+        "heuristic"
+    }
+
+    fn count(&self, text: &str) -> usize {
+        text.len().div_ceil(4)
+    }
+
+    fn count_message(&self, message: &Message) -> usize {
+        message.estimate_tokens()
+    }
+}
+
+/// A public BPE vocabulary (embedded in the binary).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// GPT-4 / GPT-3.5 era, 100k vocabulary.
+    Cl100k,
+    /// GPT-4o and later, 200k vocabulary.
+    O200k,
+}
+
+impl Encoding {
+    fn bpe(self) -> &'static CoreBPE {
+        match self {
+            Encoding::Cl100k => tiktoken_rs::cl100k_base_singleton(),
+            Encoding::O200k => tiktoken_rs::o200k_base_singleton(),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Encoding::Cl100k => "cl100k_base",
+            Encoding::O200k => "o200k_base",
+        }
+    }
+}
+
+/// Byte-pair-encoding tokenizer, optionally scaled to approximate another
+/// provider's tokenizer.
+pub struct BpeTokenizer {
+    encoding: Encoding,
+    calibration: f64,
+    name: String,
+}
+
+impl BpeTokenizer {
+    /// Exact counts for `encoding`.
+    pub fn new(encoding: Encoding) -> Self {
+        Self::calibrated(encoding, 1.0)
+    }
+
+    /// Counts for `encoding` multiplied by `calibration`.
+    pub fn calibrated(encoding: Encoding, calibration: f64) -> Self {
+        let name = if calibration == 1.0 {
+            encoding.name().to_string()
+        } else {
+            format!("{}×{calibration}", encoding.name())
+        };
+        Self {
+            encoding,
+            calibration,
+            name,
+        }
+    }
+}
+
+impl Tokenizer for BpeTokenizer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count(&self, text: &str) -> usize {
+        if text.is_empty() {
+            return 0;
+        }
+        let raw = self.encoding.bpe().encode_ordinary(text).len();
+        (raw as f64 * self.calibration).ceil() as usize
+    }
+}
+
+/// Closest public encoding and an approximate scale factor for providers
+/// with private vocabularies. Claude's tokenizer runs ~15% above
+/// `cl100k_base`; open-weight families (Llama 3, Qwen, Mistral) are close to
+/// it. `WorkingMemory` corrects the remaining drift from reported counts.
+fn provider_encoding(provider: &str) -> (Encoding, f64) {
+    match provider {
+        "anthropic" => (Encoding::Cl100k, 1.15),
+        "google" => (Encoding::O200k, 1.05),
+        _ => (Encoding::Cl100k, 1.0),
+    }
+}
+
+/// The tokenizer for a model id (`provider/model` or bare). The provider comes
+/// from the prefix or, failing that, the model catalog.
+pub fn for_model(model: &str) -> Arc<dyn Tokenizer> {
+    let (prefix, name) = model.split_once('/').unwrap_or(("", model));
+    let name = name.to_lowercase();
+    let provider = match prefix {
+        "" => claw_config::catalog()
+            .get(&name)
+            .map(|m| m.provider.clone())
+            .unwrap_or_default(),
+        p => p.to_lowercase(),
+    };
+
+    let is_openai_family = name.starts_with("gpt-")
+        || name
+            .strip_prefix('o')
+            .and_then(|rest| rest.chars().next())
+            .is_some_and(|c| c.is_ascii_digit());
+    if provider == "openai" || (provider.is_empty() && is_openai_family) {
+        let legacy = name.starts_with("gpt-4-") || name == "gpt-4" || name.starts_with("gpt-3.5");
+        let encoding = if legacy {
+            Encoding::Cl100k
+        } else {
+            Encoding::O200k
+        };
+        return Arc::new(BpeTokenizer::new(encoding));
+    }
+    let (encoding, calibration) = provider_encoding(&provider);
+    Arc::new(BpeTokenizer::calibrated(encoding, calibration))
+}
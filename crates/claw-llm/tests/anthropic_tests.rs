@@ -0,0 +1,155 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::mock_server;
+    use claw_core::{Message, Role, Tool};
+    use claw_llm::anthropic::AnthropicProvider;
+    use claw_llm::provider::{LlmProvider, LlmRequest, StreamChunk};
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    fn tool(name: &str) -> Tool {
+        Tool {
+            name: name.into(),
+            description: format!("The {name} tool"),
+            parameters: serde_json::json!({ "type": "object", "properties": {} }),
+            capabilities: vec![],
+            is_mutating: false,
+            risk_level: 0,
+            provider: None,
+        }
+    }
+
+    fn make_request() -> LlmRequest {
+        LlmRequest {
+            model: "claude-sonnet-4".to_string(),
+            messages: vec![
+                Message::text(Uuid::nil(), Role::User, "Hi"),
+                Message::text(Uuid::nil(), Role::Assistant, "Hello!"),
+                Message::text(Uuid::nil(), Role::User, "List files"),
+            ],
+            max_tokens: 256,
+            temperature: 0.5,
+            tools: Arc::new(vec![tool("file_list"), tool("file_read")]),
+            system: Some("You are helpful.".to_string()),
+            stream: false,
+            thinking_level: None,
+            response_format: None,
+        }
+    }
+
+    fn cached_response() -> String {
+        serde_json::json!({
+            "content": [{ "type": "text", "text": "Done." }],
+            "stop_reason": "end_turn",
+            "usage": {
+                "input_tokens": 100_000,
+                "output_tokens": 10_000,
+                "cache_creation_input_tokens": 200_000,
+                "cache_read_input_tokens": 1_000_000
+            }
+        })
+        .to_string()
+    }
+
+    // ── Prompt caching ─────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_cache_breakpoints_on_system_tools_and_prefix() {
+        let (url, captured) = mock_server(200, "application/json", cached_response()).await;
+        let provider = AnthropicProvider::new("k".into()).with_base_url(url);
+        provider.complete(&make_request()).await.unwrap();
+
+        let reqs = captured.lock().unwrap();
+        let sent: serde_json::Value = serde_json::from_str(&reqs[0].1).unwrap();
+        assert_eq!(sent["system"][0]["text"], "You are helpful.");
+        assert_eq!(sent["system"][0]["cache_control"]["type"], "ephemeral");
+
+        let tools = sent["tools"].as_array().unwrap();
+        assert!(tools[0].get("cache_control").is_none());
+        assert_eq!(tools[1]["cache_control"]["type"], "ephemeral");
+
+        let messages = sent["messages"].as_array().unwrap();
+        assert_eq!(messages[0]["content"], "Hi");
+        assert_eq!(messages[2]["content"][0]["text"], "List files");
+        assert_eq!(
+            messages[2]["content"][0]["cache_control"]["type"],
+            "ephemeral"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prompt_caching_disabled() {
+        let (url, captured) = mock_server(200, "application/json", cached_response()).await;
+        let provider = AnthropicProvider::new("k".into())
+            .with_base_url(url)
+            .with_prompt_caching(false);
+        provider.complete(&make_request()).await.unwrap();
+
+        let reqs = captured.lock().unwrap();
+        assert!(!reqs[0].1.contains("cache_control"));
+        let sent: serde_json::Value = serde_json::from_str(&reqs[0].1).unwrap();
+        assert_eq!(sent["system"], "You are helpful.");
+        assert_eq!(sent["messages"][2]["content"], "List files");
+    }
+
+    #[tokio::test]
+    async fn test_complete_cache_usage_and_cost() {
+        let (url, _) = mock_server(200, "application/json", cached_response()).await;
+        let provider = AnthropicProvider::new("k".into()).with_base_url(url);
+        let resp = provider.complete(&make_request()).await.unwrap();
+
+        assert_eq!(resp.usage.input_tokens, 100_000);
+        assert_eq!(resp.usage.cache_write_tokens, 200_000);
+        assert_eq!(resp.usage.cache_read_tokens, 1_000_000);
+        // Sonnet at $3/$15: 0.1M input + 0.2M * 1.25 write + 1M * 0.1 read
+        // = 0.45M input-equivalent ($1.35) + 0.01M output ($0.15)
+        assert!((resp.usage.estimated_cost_usd - 1.50).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_stream_cache_usage() {
+        let events = [
+            serde_json::json!({
+                "type": "message_start",
+                "message": { "usage": {
+                    "input_tokens": 50,
+                    "cache_creation_input_tokens": 0,
+                    "cache_read_input_tokens": 4000
+                } }
+            }),
+            serde_json::json!({
+                "type": "content_block_delta",
+                "index": 0,
+                "delta": { "type": "text_delta", "text": "ok" }
+            }),
+            serde_json::json!({
+                "type": "message_delta",
+                "delta": { "stop_reason": "end_turn" },
+                "usage": { "output_tokens": 5 }
+            }),
+            serde_json::json!({ "type": "message_stop" }),
+        ];
+        let sse: String = events.iter().map(|e| format!("data: {e}\n\n")).collect();
+        let (url, _) = mock_server(200, "text/event-stream", sse).await;
+        let provider = AnthropicProvider::new("k".into())
+            .with_base_url(url)
+            .with_pricing(1.0, 2.0);
+
+        let mut rx = provider.stream(&make_request()).await.unwrap();
+        let mut usage = None;
+        while let Some(chunk) = rx.recv().await {
+            if let StreamChunk::Usage(u) = chunk {
+                usage = Some(u);
+            }
+        }
+        let usage = usage.unwrap();
+        assert_eq!(usage.input_tokens, 50);
+        assert_eq!(usage.output_tokens, 5);
+        assert_eq!(usage.cache_read_tokens, 4000);
+        assert_eq!(usage.cache_write_tokens, 0);
+        // (50 + 4000 * 0.1) * $1/M + 5 * $2/M
+        assert!((usage.estimated_cost_usd - 0.00046).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,195 @@
+#[cfg(test)]
+mod tests {
+    use claw_core::{Message, MessageContent, Role, ToolCall};
+    use claw_llm::cassette::{Cassette, Outcome, RecordingProvider, ReplayProvider, request_key};
+    use claw_llm::mock::MockProvider;
+    use claw_llm::provider::{LlmProvider, LlmRequest, ResponseFormat, StopReason, StreamChunk};
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    fn make_request(session_id: Uuid, text: &str) -> LlmRequest {
+        LlmRequest {
+            model: "test-model".to_string(),
+            messages: vec![Message::text(session_id, Role::User, text)],
+            max_tokens: 256,
+            temperature: 0.5,
+            tools: Arc::new(vec![]),
+            system: Some("You are helpful.".to_string()),
+            stream: false,
+            thinking_level: None,
+            response_format: None,
+        }
+    }
+
+    /// A user turn, an assistant tool call and its result, with the given call id.
+    fn tool_turn(session_id: Uuid, call_id: &str) -> LlmRequest {
+        let mut assistant = Message::text(session_id, Role::Assistant, "");
+        assistant.tool_calls = vec![ToolCall {
+            id: call_id.into(),
+            tool_name: "file_read".into(),
+            arguments: serde_json::json!({ "path": "notes.txt" }),
+        }];
+        let mut result = Message::text(session_id, Role::Tool, "");
+        result.content = vec![MessageContent::ToolResult {
+            tool_call_id: call_id.into(),
+            content: format!("saved by session {session_id}"),
+            is_error: false,
+        }];
+        let mut req = make_request(session_id, "Read my notes");
+        req.messages.extend([assistant, result]);
+        req
+    }
+
+    async fn collect(provider: &dyn LlmProvider, req: &LlmRequest) -> Vec<StreamChunk> {
+        let mut rx = provider.stream(req).await.unwrap();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            chunks.push(chunk);
+        }
+        chunks
+    }
+
+    // ── Request keys ───────────────────────────────────────────
+
+    #[test]
+    fn test_request_key_ignores_ids() {
+        let a = tool_turn(Uuid::new_v4(), "toolu_01");
+        let b = tool_turn(Uuid::new_v4(), "call_abc");
+        assert_eq!(request_key(&a), request_key(&b));
+
+        let mut c = tool_turn(Uuid::new_v4(), "toolu_01");
+        c.messages[0] = Message::text(Uuid::nil(), Role::User, "Read my diary");
+        assert_ne!(request_key(&a), request_key(&c));
+
+        let mut d = a.clone();
+        d.model = "other-model".into();
+        assert_ne!(request_key(&a), request_key(&d));
+
+        // Asking for structured output is a different request
+        let mut e = a.clone();
+        e.response_format = Some(ResponseFormat {
+            name: "notes".into(),
+            schema: serde_json::json!({ "type": "object" }),
+            strict: false,
+        });
+        assert_ne!(request_key(&a), request_key(&e));
+        let mut f = e.clone();
+        f.response_format.as_mut().unwrap().schema = serde_json::json!({ "type": "array" });
+        assert_ne!(request_key(&e), request_key(&f));
+    }
+
+    // ── Record → replay ────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_record_and_replay_complete() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassettes/complete.json");
+        let mock = MockProvider::new("mock")
+            .with_tool_call("file_read", serde_json::json!({ "path": "notes.txt" }))
+            .with_response("Your notes say hi.");
+        let recorder = RecordingProvider::new(Arc::new(mock), &path);
+        assert_eq!(recorder.name(), "mock");
+
+        let first = recorder
+            .complete(&make_request(Uuid::new_v4(), "Read my notes"))
+            .await
+            .unwrap();
+        let second = recorder
+            .complete(&tool_turn(Uuid::new_v4(), &first.message.tool_calls[0].id))
+            .await
+            .unwrap();
+        assert_eq!(recorder.cassette().interactions.len(), 2);
+
+        // A fresh session replays offline with different ids
+        let replay = ReplayProvider::from_file(&path).unwrap();
+        assert_eq!(replay.name(), "mock");
+        assert_eq!(replay.models(), vec!["test-model".to_string()]);
+        let resp = replay
+            .complete(&make_request(Uuid::new_v4(), "Read my notes"))
+            .await
+            .unwrap();
+        assert!(resp.has_tool_calls);
+        assert_eq!(resp.message.tool_calls[0].tool_name, "file_read");
+        let resp = replay
+            .complete(&tool_turn(Uuid::new_v4(), &resp.message.tool_calls[0].id))
+            .await
+            .unwrap();
+        assert_eq!(resp.message.text_content(), second.message.text_content());
+        assert_eq!(resp.usage.input_tokens, second.usage.input_tokens);
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay_stream() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stream.json");
+        let recorder = RecordingProvider::new(
+            Arc::new(MockProvider::new("mock").with_response("Hello there friend")),
+            &path,
+        );
+        let req = make_request(Uuid::new_v4(), "Hi");
+        let recorded = collect(&recorder, &req).await;
+        assert_eq!(recorded.len(), 5);
+
+        let cassette = Cassette::load(&path).unwrap();
+        assert!(matches!(
+            &cassette.interactions[0].outcome,
+            Outcome::Stream { chunks } if chunks.len() == 5
+        ));
+
+        let replay = ReplayProvider::new(cassette);
+        let replayed = collect(&replay, &req).await;
+        let text: String = replayed
+            .iter()
+            .filter_map(|c| match c {
+                StreamChunk::TextDelta(t) => Some(t.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, "Hello there friend ");
+        assert!(matches!(
+            replayed.last(),
+            Some(StreamChunk::Done(StopReason::EndTurn))
+        ));
+
+        // Stream entries can also answer a non-streaming call
+        let resp = replay.complete(&req).await.unwrap();
+        assert_eq!(resp.message.text_content(), "Hello there friend ");
+    }
+
+    #[tokio::test]
+    async fn test_replay_repeats_in_order_and_reports_misses() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("repeat.json");
+        let recorder = RecordingProvider::new(
+            Arc::new(
+                MockProvider::new("mock")
+                    .with_error("overloaded")
+                    .with_response("second try"),
+            ),
+            &path,
+        );
+        let req = make_request(Uuid::new_v4(), "Hi");
+        assert!(recorder.complete(&req).await.is_err());
+        recorder.complete(&req).await.unwrap();
+
+        let replay = ReplayProvider::from_file(&path)
+            .unwrap()
+            .with_name("anthropic");
+        assert_eq!(replay.name(), "anthropic");
+        let err = replay.complete(&req).await.unwrap_err();
+        assert!(err.to_string().contains("overloaded"));
+        assert_eq!(
+            replay.complete(&req).await.unwrap().message.text_content(),
+            "second try"
+        );
+        // Exhausted keys keep serving the last entry
+        let chunks = collect(&replay, &req).await;
+        assert!(matches!(&chunks[0], StreamChunk::TextDelta(t) if t == "second try"));
+
+        let err = replay
+            .complete(&make_request(Uuid::new_v4(), "Something new"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("cassette miss"));
+    }
+}
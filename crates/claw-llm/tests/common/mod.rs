@@ -0,0 +1,65 @@
+//! Shared helpers for provider tests that talk to a local mock HTTP server.
+
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// A captured HTTP request: (request line + headers, body).
+pub type Captured = Arc<Mutex<Vec<(String, String)>>>;
+
+/// Spawn a one-shot-per-connection HTTP server that answers every request
+/// with the given status, content type and body.
+pub async fn mock_server(status: u16, content_type: &str, body: String) -> (String, Captured) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let captured: Captured = Arc::new(Mutex::new(Vec::new()));
+    let cap = Arc::clone(&captured);
+    let content_type = content_type.to_string();
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut socket, _)) = listener.accept().await else {
+                return;
+            };
+            let mut buf = Vec::new();
+            let mut tmp = [0u8; 4096];
+            // Read headers, then as much body as Content-Length says
+            let (head, body_start) = loop {
+                let n = socket.read(&mut tmp).await.unwrap();
+                if n == 0 {
+                    break (String::from_utf8_lossy(&buf).to_string(), buf.len());
+                }
+                buf.extend_from_slice(&tmp[..n]);
+                if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break (String::from_utf8_lossy(&buf[..pos]).to_string(), pos + 4);
+                }
+            };
+            let content_length = head
+                .lines()
+                .find_map(|l| {
+                    let (k, v) = l.split_once(':')?;
+                    k.eq_ignore_ascii_case("content-length")
+                        .then(|| v.trim().parse::<usize>().ok())?
+                })
+                .unwrap_or(0);
+            while buf.len() < body_start + content_length {
+                let n = socket.read(&mut tmp).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                buf.extend_from_slice(&tmp[..n]);
+            }
+            let req_body = String::from_utf8_lossy(&buf[body_start..]).to_string();
+            cap.lock().unwrap().push((head, req_body));
+
+            let response = format!(
+                "HTTP/1.1 {status} X\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+            let _ = socket.shutdown().await;
+        }
+    });
+
+    (format!("http://{addr}"), captured)
+}
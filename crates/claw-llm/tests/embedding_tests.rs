@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use claw_config::ClawConfig;
+    use claw_llm::EmbeddingProvider;
+    use claw_llm::embedding::{HashEmbedding, embedder_from_config};
+
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    #[tokio::test]
+    async fn test_builtin_embeddings_are_normalized_and_deterministic() {
+        let embedder = HashEmbedding::new(384);
+        let vecs = embedder
+            .embed(&[
+                "Deploy the service to staging",
+                "Deploy the service to staging",
+            ])
+            .await
+            .unwrap();
+        assert_eq!(vecs.len(), 2);
+        assert_eq!(vecs[0].len(), 384);
+        assert_eq!(vecs[0], vecs[1]);
+        let norm: f32 = vecs[0].iter().map(|v| v * v).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+
+        let empty = embedder.embed(&[""]).await.unwrap();
+        assert!(empty[0].iter().all(|v| *v == 0.0));
+    }
+
+    #[tokio::test]
+    async fn test_builtin_embeddings_rank_related_text_higher() {
+        let embedder = HashEmbedding::new(384);
+        let vecs = embedder
+            .embed(&[
+                "user prefers dark mode in the editor",
+                "the editor should use a dark theme",
+                "quarterly revenue grew by twelve percent",
+            ])
+            .await
+            .unwrap();
+        let related = cosine(&vecs[0], &vecs[1]);
+        let unrelated = cosine(&vecs[0], &vecs[2]);
+        assert!(related > unrelated + 0.1, "{related} vs {unrelated}");
+    }
+
+    #[test]
+    fn test_embedder_from_config() {
+        let mut config = ClawConfig::default();
+        let embedder = embedder_from_config(&config).expect("builtin by default");
+        assert_eq!(embedder.name(), "builtin");
+        assert_eq!(embedder.space(), "builtin:384");
+
+        config.memory.embedding_dims = 256;
+        assert_eq!(
+            embedder_from_config(&config).unwrap().space(),
+            "builtin:256"
+        );
+
+        config.memory.embedding_provider = "openai".into();
+        config.services.openai_api_key = None;
+        assert!(embedder_from_config(&config).is_none());
+        config.services.openai_api_key = Some("sk-test".into());
+        assert_eq!(
+            embedder_from_config(&config).unwrap().space(),
+            "openai/text-embedding-3-small:256"
+        );
+
+        config.memory.embedding_provider = "none".into();
+        assert!(embedder_from_config(&config).is_none());
+        config.memory.embedding_provider = "builtin".into();
+        config.memory.vector_search = false;
+        assert!(embedder_from_config(&config).is_none());
+    }
+}
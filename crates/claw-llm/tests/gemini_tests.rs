@@ -0,0 +1,288 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::mock_server;
+    use claw_core::{Message, MessageContent, Role, Tool, ToolCall};
+    use claw_llm::gemini::GeminiProvider;
+    use claw_llm::provider::{LlmProvider, LlmRequest, StopReason, StreamChunk};
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    fn make_request(messages: Vec<Message>, tools: Vec<Tool>) -> LlmRequest {
+        LlmRequest {
+            model: "gemini-2.5-flash".to_string(),
+            messages,
+            max_tokens: 256,
+            temperature: 0.5,
+            tools: Arc::new(tools),
+            system: Some("You are helpful.".to_string()),
+            stream: false,
+            thinking_level: None,
+            response_format: None,
+        }
+    }
+
+    fn weather_tool() -> Tool {
+        Tool {
+            name: "get_weather".into(),
+            description: "Get the weather".into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": { "city": { "type": "string" } },
+                "required": ["city"],
+                "additionalProperties": false,
+            }),
+            capabilities: vec![],
+            is_mutating: false,
+            risk_level: 0,
+            provider: None,
+        }
+    }
+
+    // ── complete ───────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_complete_text_and_usage() {
+        let body = serde_json::json!({
+            "candidates": [{
+                "content": { "role": "model", "parts": [{ "text": "Hello " }, { "text": "there" }] },
+                "finishReason": "STOP"
+            }],
+            "usageMetadata": {
+                "promptTokenCount": 1000,
+                "candidatesTokenCount": 500,
+                "thoughtsTokenCount": 100,
+                "cachedContentTokenCount": 200
+            }
+        });
+        let (url, captured) = mock_server(200, "application/json", body.to_string()).await;
+        let provider =
+            GeminiProvider::new("test-key".into()).with_base_url(format!("{url}/v1beta"));
+
+        let req = make_request(vec![Message::text(Uuid::nil(), Role::User, "Hi")], vec![]);
+        let resp = provider.complete(&req).await.unwrap();
+
+        assert_eq!(resp.message.text_content(), "Hello there");
+        assert_eq!(resp.stop_reason, StopReason::EndTurn);
+        assert!(!resp.has_tool_calls);
+        // Cached prompt tokens are reported apart from full-price input
+        assert_eq!(resp.usage.input_tokens, 800);
+        assert_eq!(resp.usage.output_tokens, 500);
+        assert_eq!(resp.usage.thinking_tokens, 100);
+        assert_eq!(resp.usage.cache_read_tokens, 200);
+        // 2.5 flash: 800 uncached * 0.30 + 200 cached * 0.075 + 600 * 2.50 per million
+        assert!((resp.usage.estimated_cost_usd - 0.001755).abs() < 1e-9);
+
+        let reqs = captured.lock().unwrap();
+        let (head, sent) = &reqs[0];
+        assert!(head.starts_with("POST /v1beta/models/gemini-2.5-flash:generateContent"));
+        assert!(head.to_lowercase().contains("x-goog-api-key: test-key"));
+        let sent: serde_json::Value = serde_json::from_str(sent).unwrap();
+        assert_eq!(
+            sent["systemInstruction"]["parts"][0]["text"],
+            "You are helpful."
+        );
+        assert_eq!(sent["contents"][0]["role"], "user");
+        assert_eq!(sent["contents"][0]["parts"][0]["text"], "Hi");
+        assert_eq!(sent["generationConfig"]["maxOutputTokens"], 256);
+    }
+
+    #[tokio::test]
+    async fn test_complete_function_call() {
+        let body = serde_json::json!({
+            "candidates": [{
+                "content": { "role": "model", "parts": [
+                    { "functionCall": { "name": "get_weather", "args": { "city": "Paris" } } }
+                ] },
+                "finishReason": "STOP"
+            }],
+            "usageMetadata": { "promptTokenCount": 10, "candidatesTokenCount": 5 }
+        });
+        let (url, captured) = mock_server(200, "application/json", body.to_string()).await;
+        let provider = GeminiProvider::new("k".into()).with_base_url(format!("{url}/v1beta"));
+
+        let req = make_request(
+            vec![Message::text(Uuid::nil(), Role::User, "Weather?")],
+            vec![weather_tool()],
+        );
+        let resp = provider.complete(&req).await.unwrap();
+
+        assert!(resp.has_tool_calls);
+        assert_eq!(resp.stop_reason, StopReason::ToolUse);
+        assert_eq!(resp.message.tool_calls.len(), 1);
+        assert_eq!(resp.message.tool_calls[0].tool_name, "get_weather");
+        assert_eq!(resp.message.tool_calls[0].arguments["city"], "Paris");
+        assert!(!resp.message.tool_calls[0].id.is_empty());
+
+        let reqs = captured.lock().unwrap();
+        let sent: serde_json::Value = serde_json::from_str(&reqs[0].1).unwrap();
+        let decl = &sent["tools"][0]["functionDeclarations"][0];
+        assert_eq!(decl["name"], "get_weather");
+        // Unsupported JSON Schema keywords are stripped
+        assert!(decl["parameters"].get("additionalProperties").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tool_results_sent_as_function_response() {
+        let body = serde_json::json!({
+            "candidates": [{
+                "content": { "role": "model", "parts": [{ "text": "It's sunny." }] },
+                "finishReason": "STOP"
+            }]
+        });
+        let (url, captured) = mock_server(200, "application/json", body.to_string()).await;
+        let provider = GeminiProvider::new("k".into()).with_base_url(format!("{url}/v1beta"));
+
+        let mut assistant = Message::text(Uuid::nil(), Role::Assistant, "");
+        assistant.tool_calls = vec![ToolCall {
+            id: "call_1".into(),
+            tool_name: "get_weather".into(),
+            arguments: serde_json::json!({ "city": "Paris" }),
+        }];
+        let mut tool_msg = Message::text(Uuid::nil(), Role::Tool, "");
+        tool_msg.content = vec![MessageContent::ToolResult {
+            tool_call_id: "call_1".into(),
+            content: "sunny, 22C".into(),
+            is_error: false,
+        }];
+        let req = make_request(
+            vec![
+                Message::text(Uuid::nil(), Role::User, "Weather?"),
+                assistant,
+                tool_msg,
+            ],
+            vec![weather_tool()],
+        );
+        provider.complete(&req).await.unwrap();
+
+        let reqs = captured.lock().unwrap();
+        let sent: serde_json::Value = serde_json::from_str(&reqs[0].1).unwrap();
+        let contents = sent["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 3);
+        assert_eq!(contents[1]["role"], "model");
+        assert_eq!(
+            contents[1]["parts"][0]["functionCall"]["name"],
+            "get_weather"
+        );
+        assert_eq!(contents[2]["role"], "user");
+        let fr = &contents[2]["parts"][0]["functionResponse"];
+        assert_eq!(fr["name"], "get_weather");
+        assert_eq!(fr["response"]["content"], "sunny, 22C");
+    }
+
+    #[tokio::test]
+    async fn test_complete_http_error() {
+        let (url, _) = mock_server(429, "application/json", "{\"error\":{}}".into()).await;
+        let provider = GeminiProvider::new("k".into()).with_base_url(format!("{url}/v1beta"));
+        let req = make_request(vec![Message::text(Uuid::nil(), Role::User, "Hi")], vec![]);
+        let err = provider.complete(&req).await.unwrap_err();
+        assert!(
+            matches!(err, claw_core::ClawError::RateLimited { .. }),
+            "{err}"
+        );
+    }
+
+    // ── stream ─────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_stream_text_tool_call_and_usage() {
+        let events = [
+            serde_json::json!({
+                "candidates": [{ "content": { "role": "model", "parts": [
+                    { "text": "pondering", "thought": true }
+                ] } }]
+            }),
+            serde_json::json!({
+                "candidates": [{ "content": { "role": "model", "parts": [{ "text": "Let me check" }] } }]
+            }),
+            serde_json::json!({
+                "candidates": [{
+                    "content": { "role": "model", "parts": [
+                        { "functionCall": { "id": "fc-9", "name": "get_weather", "args": { "city": "Oslo" } } }
+                    ] },
+                    "finishReason": "STOP"
+                }],
+                "usageMetadata": { "promptTokenCount": 40, "candidatesTokenCount": 12 }
+            }),
+        ];
+        let sse: String = events
+            .iter()
+            .map(|e| format!("data: {e}\r\n\r\n"))
+            .collect();
+        let (url, captured) = mock_server(200, "text/event-stream", sse).await;
+        let provider = GeminiProvider::new("k".into()).with_base_url(format!("{url}/v1beta"));
+
+        let req = make_request(
+            vec![Message::text(Uuid::nil(), Role::User, "Weather in Oslo?")],
+            vec![weather_tool()],
+        );
+        let mut rx = provider.stream(&req).await.unwrap();
+
+        let mut thinking = String::new();
+        let mut text = String::new();
+        let mut calls = Vec::new();
+        let mut usage = None;
+        let mut done = None;
+        while let Some(chunk) = rx.recv().await {
+            match chunk {
+                StreamChunk::Thinking(t) => thinking.push_str(&t),
+                StreamChunk::TextDelta(t) => text.push_str(&t),
+                StreamChunk::ToolCall(tc) => calls.push(tc),
+                StreamChunk::Usage(u) => usage = Some(u),
+                StreamChunk::Done(r) => done = Some(r),
+                other => panic!("unexpected chunk: {other:?}"),
+            }
+        }
+
+        assert_eq!(thinking, "pondering");
+        assert_eq!(text, "Let me check");
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id, "fc-9");
+        assert_eq!(calls[0].arguments["city"], "Oslo");
+        let usage = usage.unwrap();
+        assert_eq!(usage.input_tokens, 40);
+        assert_eq!(usage.output_tokens, 12);
+        assert_eq!(done, Some(StopReason::ToolUse));
+
+        let reqs = captured.lock().unwrap();
+        assert!(
+            reqs[0]
+                .0
+                .starts_with("POST /v1beta/models/gemini-2.5-flash:streamGenerateContent?alt=sse")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_http_error() {
+        let (url, _) = mock_server(500, "application/json", "boom".into()).await;
+        let provider = GeminiProvider::new("k".into()).with_base_url(format!("{url}/v1beta"));
+        let req = make_request(vec![Message::text(Uuid::nil(), Role::User, "Hi")], vec![]);
+        let mut rx = provider.stream(&req).await.unwrap();
+        match rx.recv().await {
+            Some(StreamChunk::Error(e)) => assert!(e.contains("HTTP 500")),
+            other => panic!("expected error chunk, got {other:?}"),
+        }
+    }
+
+    // ── health_check ───────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_health_check() {
+        let (url, captured) = mock_server(200, "application/json", "{\"models\":[]}".into()).await;
+        let provider = GeminiProvider::new("k".into()).with_base_url(format!("{url}/v1beta"));
+        provider.health_check().await.unwrap();
+        assert!(
+            captured.lock().unwrap()[0]
+                .0
+                .starts_with("GET /v1beta/models")
+        );
+
+        let (url, _) = mock_server(403, "application/json", "denied".into()).await;
+        let provider = GeminiProvider::new("k".into()).with_base_url(format!("{url}/v1beta"));
+        assert!(provider.health_check().await.is_err());
+
+        let provider = GeminiProvider::new(String::new());
+        assert!(provider.health_check().await.is_err());
+    }
+}
@@ -0,0 +1,231 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::mock_server;
+    use claw_core::{Message, MessageContent, Role, ToolCall};
+    use claw_llm::anthropic::AnthropicProvider;
+    use claw_llm::media::{self, ImageSource};
+    use claw_llm::openai::OpenAiProvider;
+    use claw_llm::provider::{LlmProvider, LlmRequest};
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    fn make_request(model: &str, messages: Vec<Message>) -> LlmRequest {
+        LlmRequest {
+            model: model.to_string(),
+            messages,
+            max_tokens: 256,
+            temperature: 0.5,
+            tools: Arc::new(vec![]),
+            system: None,
+            stream: false,
+            thinking_level: None,
+            response_format: None,
+        }
+    }
+
+    fn user_with_image(text: &str, data: &str) -> Message {
+        let mut msg = Message::text(Uuid::nil(), Role::User, text);
+        msg.content.push(MessageContent::Image {
+            data: data.into(),
+            media_type: "image/png".into(),
+        });
+        msg
+    }
+
+    /// An assistant tool call followed by a tool result carrying a screenshot.
+    fn screenshot_turn() -> Vec<Message> {
+        let mut assistant = Message::text(Uuid::nil(), Role::Assistant, "");
+        assistant.tool_calls = vec![ToolCall {
+            id: "call_1".into(),
+            tool_name: "browser_screenshot".into(),
+            arguments: serde_json::json!({}),
+        }];
+        let mut tool_msg = Message::text(Uuid::nil(), Role::Tool, "");
+        tool_msg.content = vec![
+            MessageContent::ToolResult {
+                tool_call_id: "call_1".into(),
+                content: "Screenshot saved".into(),
+                is_error: false,
+            },
+            MessageContent::Image {
+                data: "iVBORw0KGgo=".into(),
+                media_type: "image/png".into(),
+            },
+        ];
+        vec![
+            Message::text(Uuid::nil(), Role::User, "Take a screenshot"),
+            assistant,
+            tool_msg,
+        ]
+    }
+
+    // ── Image resolution ───────────────────────────────────────
+
+    #[test]
+    fn test_resolve_image_sources() {
+        assert_eq!(
+            media::resolve_image("https://example.com/cat.png", "image/png"),
+            Some(ImageSource::Url("https://example.com/cat.png".into()))
+        );
+        assert_eq!(
+            media::resolve_image("data:image/jpeg;base64,AAAA", "image/png"),
+            Some(ImageSource::Base64 {
+                media_type: "image/jpeg".into(),
+                data: "AAAA".into(),
+            })
+        );
+        assert_eq!(
+            media::resolve_image("iVBORw0KGgo=", "image/png"),
+            Some(ImageSource::Base64 {
+                media_type: "image/png".into(),
+                data: "iVBORw0KGgo=".into(),
+            })
+        );
+        // Traversal out of the screenshots dir is refused
+        assert_eq!(
+            media::resolve_image("/api/v1/screenshots/../claw.toml", "image/png"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_local_files_outside_claw_dir_are_not_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secret.png");
+        std::fs::write(&path, b"not really a png").unwrap();
+        assert_eq!(
+            media::resolve_image(path.to_str().unwrap(), "image/png"),
+            None
+        );
+
+        let mut msg = Message::text(Uuid::nil(), Role::User, "see attached");
+        msg.content.push(MessageContent::File {
+            path: "/tmp/report.pdf".into(),
+            media_type: Some("application/pdf".into()),
+        });
+        let (images, notes) = media::message_media(&msg);
+        assert!(images.is_empty());
+        assert_eq!(
+            notes,
+            vec!["[Attached file: /tmp/report.pdf (application/pdf)]"]
+        );
+    }
+
+    // ── Anthropic ──────────────────────────────────────────────
+
+    fn anthropic_ok() -> String {
+        serde_json::json!({
+            "content": [{ "type": "text", "text": "A cat." }],
+            "stop_reason": "end_turn",
+            "usage": { "input_tokens": 10, "output_tokens": 3 }
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_user_image_blocks() {
+        let (url, captured) = mock_server(200, "application/json", anthropic_ok()).await;
+        let provider = AnthropicProvider::new("k".into()).with_base_url(url);
+        let mut msg = user_with_image("What is this?", "iVBORw0KGgo=");
+        msg.content.push(MessageContent::Image {
+            data: "https://example.com/cat.jpg".into(),
+            media_type: "image/jpeg".into(),
+        });
+        let req = make_request("claude-sonnet-4", vec![msg]);
+        provider.complete(&req).await.unwrap();
+
+        let reqs = captured.lock().unwrap();
+        let sent: serde_json::Value = serde_json::from_str(&reqs[0].1).unwrap();
+        let content = sent["messages"][0]["content"].as_array().unwrap();
+        assert_eq!(content[0]["type"], "image");
+        assert_eq!(content[0]["source"]["type"], "base64");
+        assert_eq!(content[0]["source"]["media_type"], "image/png");
+        assert_eq!(content[0]["source"]["data"], "iVBORw0KGgo=");
+        assert_eq!(content[1]["source"]["type"], "url");
+        assert_eq!(content[1]["source"]["url"], "https://example.com/cat.jpg");
+        assert_eq!(content[2]["type"], "text");
+        assert_eq!(content[2]["text"], "What is this?");
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_tool_result_with_screenshot() {
+        let (url, captured) = mock_server(200, "application/json", anthropic_ok()).await;
+        let provider = AnthropicProvider::new("k".into()).with_base_url(url);
+        let req = make_request("claude-sonnet-4", screenshot_turn());
+        provider.complete(&req).await.unwrap();
+
+        let reqs = captured.lock().unwrap();
+        let sent: serde_json::Value = serde_json::from_str(&reqs[0].1).unwrap();
+        let result = &sent["messages"][2]["content"][0];
+        assert_eq!(result["type"], "tool_result");
+        assert_eq!(result["content"][0]["text"], "Screenshot saved");
+        assert_eq!(result["content"][1]["type"], "image");
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_text_only_unchanged() {
+        let (url, captured) = mock_server(200, "application/json", anthropic_ok()).await;
+        let provider = AnthropicProvider::new("k".into())
+            .with_base_url(url)
+            .with_prompt_caching(false);
+        let req = make_request(
+            "claude-sonnet-4",
+            vec![Message::text(Uuid::nil(), Role::User, "Hi")],
+        );
+        provider.complete(&req).await.unwrap();
+
+        let reqs = captured.lock().unwrap();
+        let sent: serde_json::Value = serde_json::from_str(&reqs[0].1).unwrap();
+        assert_eq!(sent["messages"][0]["content"], "Hi");
+    }
+
+    // ── OpenAI ─────────────────────────────────────────────────
+
+    fn openai_ok() -> String {
+        serde_json::json!({
+            "choices": [{ "message": { "content": "A cat." }, "finish_reason": "stop" }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 3 }
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_openai_user_image_parts() {
+        let (url, captured) = mock_server(200, "application/json", openai_ok()).await;
+        let provider = OpenAiProvider::new("k".into()).with_base_url(url, "openai".into());
+        let req = make_request(
+            "gpt-4o",
+            vec![user_with_image("What is this?", "iVBORw0KGgo=")],
+        );
+        provider.complete(&req).await.unwrap();
+
+        let reqs = captured.lock().unwrap();
+        let sent: serde_json::Value = serde_json::from_str(&reqs[0].1).unwrap();
+        let parts = sent["messages"][0]["content"].as_array().unwrap();
+        assert_eq!(parts[0]["type"], "text");
+        assert_eq!(parts[1]["type"], "image_url");
+        assert_eq!(
+            parts[1]["image_url"]["url"],
+            "data:image/png;base64,iVBORw0KGgo="
+        );
+    }
+
+    #[tokio::test]
+    async fn test_openai_tool_images_follow_tool_messages() {
+        let (url, captured) = mock_server(200, "application/json", openai_ok()).await;
+        let provider = OpenAiProvider::new("k".into()).with_base_url(url, "openai".into());
+        let req = make_request("gpt-4o", screenshot_turn());
+        provider.complete(&req).await.unwrap();
+
+        let reqs = captured.lock().unwrap();
+        let sent: serde_json::Value = serde_json::from_str(&reqs[0].1).unwrap();
+        let messages = sent["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[2]["role"], "tool");
+        assert_eq!(messages[2]["content"], "Screenshot saved");
+        assert_eq!(messages[3]["role"], "user");
+        assert_eq!(messages[3]["content"][1]["type"], "image_url");
+    }
+}
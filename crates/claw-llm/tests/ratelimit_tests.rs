@@ -0,0 +1,203 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::mock_server;
+    use claw_core::{ClawError, Message, Role};
+    use claw_llm::anthropic::AnthropicProvider;
+    use claw_llm::provider::{LlmProvider, LlmRequest, StreamChunk};
+    use claw_llm::ratelimit::{self, ProviderLimits, RateLimiter};
+    use reqwest::StatusCode;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::time::Instant;
+    use uuid::Uuid;
+
+    fn limiter(rpm: u32, tpm: u32) -> Arc<RateLimiter> {
+        let limiter = RateLimiter::new();
+        limiter.set_limits(
+            "p",
+            ProviderLimits {
+                requests_per_minute: rpm,
+                tokens_per_minute: tpm,
+            },
+        );
+        Arc::new(limiter)
+    }
+
+    // ── Budgets and queueing ───────────────────────────────────
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_per_minute_queues_excess_calls() {
+        let limiter = limiter(2, 0);
+        let start = Instant::now();
+        limiter.acquire("p", 10).await;
+        limiter.acquire("p", 10).await;
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        limiter.acquire("p", 10).await;
+        assert!(start.elapsed() >= Duration::from_secs(60));
+        // Other providers are unaffected
+        let other = Instant::now();
+        limiter.acquire("q", 10).await;
+        assert!(other.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_tokens_per_minute_and_oversized_calls() {
+        let limiter = limiter(0, 1000);
+        let start = Instant::now();
+        limiter.acquire("p", 600).await;
+        limiter.acquire("p", 600).await;
+        assert!(start.elapsed() >= Duration::from_secs(60));
+
+        // Larger than the whole budget: runs once the window is empty
+        let start = Instant::now();
+        limiter.acquire("p", 5000).await;
+        assert!(start.elapsed() >= Duration::from_secs(60));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_block_pauses_queue_and_reports_depth() {
+        let limiter = limiter(0, 0);
+        limiter.block("p", Duration::from_secs(10));
+
+        let start = Instant::now();
+        let waiters: Vec<_> = (0..3)
+            .map(|_| {
+                let limiter = Arc::clone(&limiter);
+                tokio::spawn(async move { limiter.acquire("p", 1).await })
+            })
+            .collect();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(limiter.queue_depths(), vec![("p".to_string(), 3)]);
+
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_secs(10));
+        assert_eq!(limiter.queue_depths(), vec![("p".to_string(), 0)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_block_is_capped() {
+        let limiter = limiter(0, 0);
+        limiter.block("p", Duration::MAX);
+
+        let start = Instant::now();
+        limiter.acquire("p", 1).await;
+        assert_eq!(start.elapsed().as_secs(), ratelimit::MAX_RETRY_AFTER_SECS);
+    }
+
+    // ── 429 parsing ────────────────────────────────────────────
+
+    #[test]
+    fn test_retry_after_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(ratelimit::retry_after_secs(&headers), None);
+
+        headers.insert(
+            "x-ratelimit-reset-requests",
+            HeaderValue::from_static("6m0s"),
+        );
+        headers.insert(
+            "x-ratelimit-reset-tokens",
+            HeaderValue::from_static("250ms"),
+        );
+        assert_eq!(ratelimit::retry_after_secs(&headers), Some(360));
+
+        headers.insert("retry-after", HeaderValue::from_static("7"));
+        assert_eq!(ratelimit::retry_after_secs(&headers), Some(7));
+
+        headers.insert("retry-after-ms", HeaderValue::from_static("1500"));
+        assert_eq!(ratelimit::retry_after_secs(&headers), Some(2));
+    }
+
+    #[test]
+    fn test_hostile_retry_after_headers() {
+        let max = Some(ratelimit::MAX_RETRY_AFTER_SECS);
+        for (value, expected) in [
+            ("inf", None),
+            ("-inf", None),
+            ("NaN", None),
+            ("-5", None),
+            ("1e30", max),
+            ("99999999", max),
+        ] {
+            let mut headers = HeaderMap::new();
+            headers.insert("retry-after", HeaderValue::from_static(value));
+            assert_eq!(ratelimit::retry_after_secs(&headers), expected, "{value}");
+
+            let mut headers = HeaderMap::new();
+            headers.insert("retry-after-ms", HeaderValue::from_static(value));
+            assert_eq!(ratelimit::retry_after_secs(&headers), expected, "{value}");
+        }
+
+        // A bad hint falls through to the next header
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after-ms", HeaderValue::from_static("NaN"));
+        headers.insert("retry-after", HeaderValue::from_static("7"));
+        assert_eq!(ratelimit::retry_after_secs(&headers), Some(7));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-ratelimit-reset-requests",
+            HeaderValue::from_static("99999999h"),
+        );
+        assert_eq!(ratelimit::retry_after_secs(&headers), max);
+
+        headers.insert("retry-after", HeaderValue::from_static("inf"));
+        let err = ratelimit::http_error(StatusCode::TOO_MANY_REQUESTS, &headers, "");
+        assert!(matches!(
+            err,
+            ClawError::RateLimited { retry_after_secs } if retry_after_secs == ratelimit::MAX_RETRY_AFTER_SECS
+        ));
+    }
+
+    #[test]
+    fn test_http_error_maps_429() {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("12"));
+        let err = ratelimit::http_error(StatusCode::TOO_MANY_REQUESTS, &headers, "slow down");
+        assert!(matches!(
+            err,
+            ClawError::RateLimited {
+                retry_after_secs: 12
+            }
+        ));
+
+        let err = ratelimit::http_error(StatusCode::BAD_GATEWAY, &headers, "oops");
+        assert_eq!(
+            err.to_string(),
+            "llm provider error: HTTP 502 Bad Gateway: oops"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_429_is_rate_limited() {
+        let (url, _) = mock_server(429, "application/json", "{}".into()).await;
+        let provider = AnthropicProvider::new("k".into()).with_base_url(url);
+        let req = LlmRequest {
+            model: "claude-sonnet-4".into(),
+            messages: vec![Message::text(Uuid::nil(), Role::User, "Hi")],
+            max_tokens: 64,
+            temperature: 0.0,
+            tools: Arc::new(vec![]),
+            system: None,
+            stream: false,
+            thinking_level: None,
+            response_format: None,
+        };
+        let err = provider.complete(&req).await.unwrap_err();
+        assert!(matches!(err, ClawError::RateLimited { .. }), "{err}");
+
+        let mut rx = provider.stream(&req).await.unwrap();
+        match rx.recv().await {
+            Some(StreamChunk::RateLimited { retry_after_secs }) => {
+                assert_eq!(retry_after_secs, 30)
+            }
+            other => panic!("expected rate-limited chunk, got {other:?}"),
+        }
+    }
+}
@@ -2,8 +2,9 @@
 mod tests {
     use claw_core::{Message, Role};
     use claw_llm::mock::{MockProvider, MockResponse};
-    use claw_llm::provider::LlmRequest;
+    use claw_llm::provider::{LlmRequest, StreamChunk};
     use claw_llm::router::ModelRouter;
+    use claw_llm::routing::{CascadePolicy, LatencyPolicy};
     use std::sync::Arc;
     use uuid::Uuid;
 
@@ -17,6 +18,7 @@ mod tests {
             system: None,
             stream: false,
             thinking_level: None,
+            response_format: None,
         }
     }
 
@@ -131,4 +133,209 @@ mod tests {
         assert_eq!(recorded.len(), 1);
         assert_eq!(recorded[0].messages[0].text_content(), "Hello");
     }
+
+    // ── Routing policies ───────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_usage_names_the_route() {
+        let mock = MockProvider::new("testprovider").with_response("hi");
+        let mut router = ModelRouter::new();
+        router.add_provider(Arc::new(mock));
+        let resp = router
+            .complete(&make_request("testprovider/gpt-4o"), None)
+            .await
+            .unwrap();
+        assert_eq!(resp.usage.model.as_deref(), Some("testprovider/gpt-4o"));
+
+        let mock = MockProvider::new("testprovider").with_response("hi there");
+        let mut router = ModelRouter::new();
+        router.add_provider(Arc::new(mock));
+        let mut rx = router
+            .stream(&make_request("testprovider/gpt-4o"), None)
+            .await
+            .unwrap();
+        let mut route = None;
+        while let Some(chunk) = rx.recv().await {
+            if let claw_llm::provider::StreamChunk::Usage(u) = chunk {
+                route = u.model;
+            }
+        }
+        assert_eq!(route.as_deref(), Some("testprovider/gpt-4o"));
+        assert_eq!(router.latency().sample_count("testprovider/gpt-4o"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cascade_escalates_low_confidence_reply() {
+        let fast = MockProvider::new("fast").with_response("I'm not sure, sorry.");
+        let primary = MockProvider::new("primary").with_response("The answer is 42.");
+        let fast_requests = fast.recorded_requests();
+
+        let mut router = ModelRouter::new().with_policy(Arc::new(CascadePolicy::new(
+            "primary/big",
+            "fast/small",
+            3,
+        )));
+        router.add_provider(Arc::new(fast));
+        router.add_provider(Arc::new(primary));
+
+        let resp = router
+            .complete(&make_request("primary/big"), None)
+            .await
+            .unwrap();
+        assert_eq!(resp.message.text_content(), "The answer is 42.");
+        assert_eq!(resp.usage.model.as_deref(), Some("primary/big"));
+        // Both calls are billed
+        assert_eq!(resp.usage.input_tokens, 200);
+        assert_eq!(fast_requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cascade_keeps_confident_fast_reply_and_skips_tool_heavy_turns() {
+        let fast = MockProvider::new("fast")
+            .with_response("Paris.")
+            .with_response("unused");
+        let primary = MockProvider::new("primary").with_response("Done with tools.");
+        let mut router = ModelRouter::new().with_policy(Arc::new(CascadePolicy::new(
+            "primary/big",
+            "fast/small",
+            2,
+        )));
+        router.add_provider(Arc::new(fast));
+        router.add_provider(Arc::new(primary));
+
+        let resp = router
+            .complete(&make_request("primary/big"), None)
+            .await
+            .unwrap();
+        assert_eq!(resp.usage.model.as_deref(), Some("fast/small"));
+
+        // Two tool calls since the last user message → straight to primary
+        let mut req = make_request("primary/big");
+        let mut assistant = Message::text(Uuid::nil(), Role::Assistant, "");
+        assistant.tool_calls = (0..2)
+            .map(|i| claw_core::ToolCall {
+                id: format!("call_{i}"),
+                tool_name: "file_read".into(),
+                arguments: serde_json::json!({}),
+            })
+            .collect();
+        req.messages.push(assistant);
+        let resp = router.complete(&req, None).await.unwrap();
+        assert_eq!(resp.usage.model.as_deref(), Some("primary/big"));
+
+        // Requests for other models are not cascaded
+        let resp = router
+            .complete(&make_request("fast/other"), None)
+            .await
+            .unwrap();
+        assert_eq!(resp.usage.model.as_deref(), Some("fast/other"));
+    }
+
+    #[tokio::test]
+    async fn test_cascade_escalates_streamed_replies() {
+        let fast = MockProvider::new("fast")
+            .with_response("I'm not sure, sorry.")
+            .with_response("Paris.");
+        let primary = MockProvider::new("primary").with_response("The answer is 42.");
+        let mut router = ModelRouter::new().with_policy(Arc::new(CascadePolicy::new(
+            "primary/big",
+            "fast/small",
+            3,
+        )));
+        router.add_provider(Arc::new(fast));
+        router.add_provider(Arc::new(primary));
+
+        let collect = |mut rx: tokio::sync::mpsc::Receiver<StreamChunk>| async move {
+            let (mut text, mut usage) = (String::new(), None);
+            while let Some(chunk) = rx.recv().await {
+                match chunk {
+                    StreamChunk::TextDelta(t) => text.push_str(&t),
+                    StreamChunk::Usage(u) => usage = Some(u),
+                    _ => {}
+                }
+            }
+            (text, usage.unwrap())
+        };
+
+        // The hedging fast reply never reaches the caller
+        let rx = router
+            .stream(&make_request("primary/big"), None)
+            .await
+            .unwrap();
+        let (text, usage) = collect(rx).await;
+        assert_eq!(text.trim(), "The answer is 42.");
+        assert_eq!(usage.model.as_deref(), Some("primary/big"));
+        assert_eq!(usage.input_tokens, 200);
+
+        let rx = router
+            .stream(&make_request("primary/big"), None)
+            .await
+            .unwrap();
+        let (text, usage) = collect(rx).await;
+        assert_eq!(text.trim(), "Paris.");
+        assert_eq!(usage.model.as_deref(), Some("fast/small"));
+    }
+
+    #[tokio::test]
+    async fn test_latency_policy_prefers_lowest_p50() {
+        let candidates = vec!["a/model".to_string(), "b/model".to_string()];
+        let mut router = ModelRouter::new().with_policy(Arc::new(LatencyPolicy::new(candidates)));
+        router.add_provider(Arc::new(MockProvider::new("a")));
+        router.add_provider(Arc::new(MockProvider::new("b")));
+
+        // Unmeasured candidates are tried first
+        router
+            .latency()
+            .record("a/model", std::time::Duration::from_millis(900));
+        let resp = router
+            .complete(&make_request("a/model"), None)
+            .await
+            .unwrap();
+        assert_eq!(resp.usage.model.as_deref(), Some("b/model"));
+
+        router
+            .latency()
+            .record("a/model", std::time::Duration::from_millis(800));
+        router
+            .latency()
+            .record("b/model", std::time::Duration::from_secs(5));
+        router
+            .latency()
+            .record("b/model", std::time::Duration::from_secs(5));
+        let resp = router
+            .complete(&make_request("b/model"), None)
+            .await
+            .unwrap();
+        assert_eq!(resp.usage.model.as_deref(), Some("a/model"));
+    }
+
+    #[tokio::test]
+    async fn test_cost_cap_downgrades_then_refuses() {
+        let anthropic = MockProvider::new("anthropic")
+            .with_response("cheap")
+            .with_response("unused");
+        let requests = anthropic.recorded_requests();
+        let mut router =
+            ModelRouter::new().with_cost_cap(0.001, Some("anthropic/claude-haiku-3-5".into()));
+        router.add_provider(Arc::new(anthropic));
+
+        // Opus: 100 output tokens at $75/M ≈ $0.0075 > cap → haiku
+        let resp = router
+            .complete(&make_request("anthropic/claude-opus-4-1"), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            resp.usage.model.as_deref(),
+            Some("anthropic/claude-haiku-3-5")
+        );
+        assert_eq!(requests.lock().unwrap()[0].model, "claude-haiku-3-5");
+
+        let mut router = ModelRouter::new().with_cost_cap(0.000_01, None);
+        router.add_provider(Arc::new(MockProvider::new("anthropic")));
+        let err = router
+            .complete(&make_request("anthropic/claude-opus-4-1"), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, claw_core::ClawError::BudgetExceeded { .. }));
+    }
 }
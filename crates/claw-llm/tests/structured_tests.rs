@@ -0,0 +1,193 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::mock_server;
+    use claw_core::{Message, Role};
+    use claw_llm::anthropic::AnthropicProvider;
+    use claw_llm::mock::{MockProvider, MockResponse};
+    use claw_llm::openai::OpenAiProvider;
+    use claw_llm::provider::{LlmProvider, LlmRequest, ResponseFormat, StopReason};
+    use claw_llm::router::ModelRouter;
+    use claw_llm::structured;
+    use serde_json::json;
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    fn person_schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "minLength": 1 },
+                "age": { "type": "integer", "minimum": 0 },
+                "tags": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["name", "age"],
+            "additionalProperties": false
+        })
+    }
+
+    fn make_request(model: &str) -> LlmRequest {
+        LlmRequest {
+            model: model.to_string(),
+            messages: vec![Message::text(Uuid::nil(), Role::User, "Describe Ada")],
+            max_tokens: 256,
+            temperature: 0.2,
+            tools: Arc::new(vec![]),
+            system: None,
+            stream: false,
+            thinking_level: None,
+            response_format: Some(ResponseFormat::new("person", person_schema())),
+        }
+    }
+
+    // ── Validation ─────────────────────────────────────────────
+
+    #[test]
+    fn test_validate_accepts_and_reports_paths() {
+        let schema = person_schema();
+        assert!(structured::validate(&json!({"name": "Ada", "age": 36}), &schema).is_ok());
+
+        let err = structured::validate(&json!({"name": "Ada"}), &schema).unwrap_err();
+        assert!(err.contains("missing required property 'age'"), "{err}");
+
+        let err = structured::validate(
+            &json!({"name": "Ada", "age": 36, "tags": ["a", 1]}),
+            &schema,
+        )
+        .unwrap_err();
+        assert_eq!(err, "$.tags[1]: expected string, got number");
+
+        let err =
+            structured::validate(&json!({"name": "Ada", "age": 36, "x": 1}), &schema).unwrap_err();
+        assert!(err.contains("unexpected property 'x'"), "{err}");
+
+        let err = structured::validate(&json!({"name": "Ada", "age": -1}), &schema).unwrap_err();
+        assert!(err.contains("below the minimum"), "{err}");
+    }
+
+    #[test]
+    fn test_extract_json_from_fenced_and_wrapped_text() {
+        assert_eq!(
+            structured::extract_json("```json\n{\"a\": 1}\n```"),
+            Some(json!({"a": 1}))
+        );
+        assert_eq!(
+            structured::extract_json("Here you go: {\"a\": [1, 2]} — done."),
+            Some(json!({"a": [1, 2]}))
+        );
+        assert_eq!(structured::extract_json("no json here"), None);
+    }
+
+    // ── Router validation and repair ───────────────────────────
+
+    #[tokio::test]
+    async fn test_router_repairs_invalid_reply_once() {
+        let mut mock = MockProvider::new("mock");
+        mock.queue_response(MockResponse::text("Sure! {\"name\": \"Ada\"}"));
+        mock.queue_response(MockResponse::text("{\"name\": \"Ada\", \"age\": 36}"));
+        let requests = mock.recorded_requests();
+
+        let mut router = ModelRouter::new();
+        router.add_provider(Arc::new(mock));
+        let resp = router
+            .complete(&make_request("mock/model"), None)
+            .await
+            .unwrap();
+
+        let value: serde_json::Value = serde_json::from_str(&resp.message.text_content()).unwrap();
+        assert_eq!(value, json!({"name": "Ada", "age": 36}));
+
+        let recorded = requests.lock().unwrap();
+        assert_eq!(recorded.len(), 2);
+        let repair = recorded[1].messages.last().unwrap();
+        assert_eq!(repair.role, Role::User);
+        assert!(
+            repair
+                .text_content()
+                .contains("missing required property 'age'")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_router_errors_when_repair_fails() {
+        let mut mock = MockProvider::new("mock");
+        mock.queue_response(MockResponse::text("not json"));
+        mock.queue_response(MockResponse::text("still not json"));
+
+        let mut router = ModelRouter::new();
+        router.add_provider(Arc::new(mock));
+        let err = router
+            .complete(&make_request("mock/model"), None)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("did not match schema 'person'"),
+            "{err}"
+        );
+    }
+
+    // ── Provider wiring ────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_anthropic_forces_structured_tool() {
+        let body = json!({
+            "content": [{
+                "type": "tool_use",
+                "id": "toolu_1",
+                "name": "person",
+                "input": { "name": "Ada", "age": 36 }
+            }],
+            "stop_reason": "tool_use",
+            "usage": { "input_tokens": 10, "output_tokens": 5 }
+        })
+        .to_string();
+        let (url, captured) = mock_server(200, "application/json", body).await;
+        let provider = AnthropicProvider::new("k".into()).with_base_url(url);
+        let resp = provider
+            .complete(&make_request("claude-sonnet-4"))
+            .await
+            .unwrap();
+
+        assert!(resp.message.tool_calls.is_empty());
+        assert_eq!(resp.stop_reason, StopReason::EndTurn);
+        let value: serde_json::Value = serde_json::from_str(&resp.message.text_content()).unwrap();
+        assert_eq!(value["age"], 36);
+
+        let reqs = captured.lock().unwrap();
+        let sent: serde_json::Value = serde_json::from_str(&reqs[0].1).unwrap();
+        assert_eq!(
+            sent["tool_choice"],
+            json!({"type": "tool", "name": "person"})
+        );
+        let tools = sent["tools"].as_array().unwrap();
+        assert_eq!(tools.last().unwrap()["input_schema"], person_schema());
+    }
+
+    #[tokio::test]
+    async fn test_openai_sends_json_schema_response_format() {
+        let body = json!({
+            "choices": [{
+                "message": { "content": "{\"name\": \"Ada\", \"age\": 36}" },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5 }
+        })
+        .to_string();
+        let (url, captured) = mock_server(200, "application/json", body).await;
+        let provider = OpenAiProvider::new("k".into()).with_base_url(url, "openai".into());
+        let mut req = make_request("gpt-4o");
+        req.response_format = req.response_format.map(ResponseFormat::strict);
+        provider.complete(&req).await.unwrap();
+
+        let reqs = captured.lock().unwrap();
+        let sent: serde_json::Value = serde_json::from_str(&reqs[0].1).unwrap();
+        assert_eq!(sent["response_format"]["type"], "json_schema");
+        assert_eq!(sent["response_format"]["json_schema"]["name"], "person");
+        assert_eq!(sent["response_format"]["json_schema"]["strict"], true);
+        assert_eq!(
+            sent["response_format"]["json_schema"]["schema"],
+            person_schema()
+        );
+    }
+}
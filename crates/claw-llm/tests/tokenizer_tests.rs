@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod tests {
+    use claw_core::{Message, MessageContent, Role};
+    use claw_llm::Tokenizer;
+    use claw_llm::tokenizer::{self, BpeTokenizer, Encoding};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_bpe_counts_match_reference_encodings() {
+        let o200k = BpeTokenizer::new(Encoding::O200k);
+        let cl100k = BpeTokenizer::new(Encoding::Cl100k);
+        assert_eq!(o200k.count("hello world"), 2);
+        assert_eq!(cl100k.count("hello world"), 2);
+        assert_eq!(o200k.count(""), 0);
+    }
+
+    #[test]
+    fn test_calibration_scales_counts() {
+        let exact = BpeTokenizer::new(Encoding::Cl100k);
+        let scaled = BpeTokenizer::calibrated(Encoding::Cl100k, 1.5);
+        let text = "The quick brown fox jumps over the lazy dog.";
+        let raw = exact.count(text);
+        assert_eq!(scaled.count(text), (raw as f64 * 1.5).ceil() as usize);
+        assert_eq!(scaled.name(), "cl100k_base×1.5");
+    }
+
+    #[test]
+    fn test_for_model_picks_encoding() {
+        assert_eq!(tokenizer::for_model("openai/gpt-4o").name(), "o200k_base");
+        assert_eq!(tokenizer::for_model("gpt-4.1-mini").name(), "o200k_base");
+        assert_eq!(tokenizer::for_model("o3-mini").name(), "o200k_base");
+        assert_eq!(tokenizer::for_model("openai/gpt-4").name(), "cl100k_base");
+        assert_eq!(
+            tokenizer::for_model("openai/gpt-3.5-turbo").name(),
+            "cl100k_base"
+        );
+        assert_eq!(
+            tokenizer::for_model("anthropic/claude-sonnet-4").name(),
+            "cl100k_base×1.15"
+        );
+        assert_eq!(
+            tokenizer::for_model("google/gemini-2.5-pro").name(),
+            "o200k_base×1.05"
+        );
+        assert_eq!(tokenizer::for_model("ollama/llama3").name(), "cl100k_base");
+    }
+
+    #[test]
+    fn test_count_message_includes_tool_results_and_overhead() {
+        let tok = BpeTokenizer::new(Encoding::O200k);
+        let mut msg = Message::text(Uuid::nil(), Role::User, "hello world");
+        assert_eq!(tok.count_message(&msg), 2 + 4);
+
+        msg.content.push(MessageContent::ToolResult {
+            tool_call_id: "call_1".into(),
+            content: "hello world".into(),
+            is_error: false,
+        });
+        assert_eq!(tok.count_message(&msg), 2 + 4 + 2 + tok.count("call_1"));
+    }
+}
@@ -0,0 +1,868 @@
+//! Tamper-evident audit log.
+//!
+//! Every `audit_log` entry is sealed with an HMAC-SHA256 keyed by a secret
+//! that never leaves the device (`audit.key` next to the database). The MAC
+//! covers the entry's id and contents and the MAC of the entry before it
+//! (`prev_mac`), so the entries form a chain: editing, deleting or
+//! reordering rows breaks it where it happened, and without the key nobody
+//! can re-seal the rows after the change. The newest link and the number of
+//! chained entries are also recorded in `memory_meta`, signed with the same
+//! key, so deleting entries from the end shows up too — and new entries
+//! link to that recorded head, so appending afterwards doesn't hide it.
+//!
+//! Entries written before the chain existed ("legacy" entries, with no
+//! `prev_mac`) can't be proven unaltered up to that point, but the first
+//! chained entry records their count and digest, which pins them from then
+//! on.
+//!
+//! Segments of the chain can be exported to JSONL for archiving off the
+//! box; the header is signed with the same key, and [`verify_segment`]
+//! checks a segment on its own.
+
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use claw_core::{ClawError, Result};
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Format name in the first line of an exported segment.
+const SEGMENT_FORMAT: &str = "claw-audit-segment";
+const SEGMENT_VERSION: u32 = 1;
+
+/// `memory_meta` key holding the newest link: `<id> <mac> <count> <signature>`.
+const HEAD_KEY: &str = "audit_head";
+/// `memory_meta` key holding the id of the key that started the chain.
+const KEY_ID_KEY: &str = "audit_key_id";
+
+/// The first chained entry after legacy entries.
+const CHAIN_STARTED: (&str, &str) = ("audit", "chain_started");
+
+fn db_err(e: rusqlite::Error) -> ClawError {
+    ClawError::Memory(e.to_string())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len() / 2)
+        .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok())
+        .collect()
+}
+
+/// Where the audit key of a database lives: `audit.key` in its directory.
+pub fn key_path(db_path: &Path) -> PathBuf {
+    db_path.with_file_name("audit.key")
+}
+
+// ── Key ─────────────────────────────────────────────────────────
+
+/// The device-local secret audit entries are sealed with.
+pub struct AuditKey {
+    key: hmac::Key,
+    id: String,
+}
+
+impl AuditKey {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self {
+            key: hmac::Key::new(hmac::HMAC_SHA256, bytes),
+            id: blake3::hash(bytes).to_hex()[..16].to_string(),
+        }
+    }
+
+    fn random() -> Result<[u8; 32]> {
+        let mut bytes = [0u8; 32];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|_| ClawError::Memory("no system randomness for the audit key".into()))?;
+        Ok(bytes)
+    }
+
+    /// A fresh key that isn't stored anywhere (in-memory databases).
+    pub fn ephemeral() -> Result<Self> {
+        Ok(Self::from_bytes(&Self::random()?))
+    }
+
+    /// Read the key at `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            ClawError::Memory(format!("cannot read audit key {}: {e}", path.display()))
+        })?;
+        let bytes = from_hex(text.trim())
+            .filter(|b| b.len() == 32)
+            .ok_or_else(|| {
+                ClawError::Memory(format!("audit key {} is malformed", path.display()))
+            })?;
+        Ok(Self::from_bytes(&bytes))
+    }
+
+    /// Read the key at `path`, generating it (readable by the owner only)
+    /// if there is none yet.
+    pub fn load_or_create(path: &Path) -> Result<Self> {
+        if path.exists() {
+            return Self::load(path);
+        }
+        let bytes = Self::random()?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let write = options
+            .open(path)
+            .and_then(|mut file| file.write_all(format!("{}\n", to_hex(&bytes)).as_bytes()));
+        write.map_err(|e| {
+            ClawError::Memory(format!("cannot create audit key {}: {e}", path.display()))
+        })?;
+        tracing::info!(path = %path.display(), "generated audit log key");
+        Ok(Self::from_bytes(&bytes))
+    }
+
+    /// Short, public fingerprint of the key.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn sign(&self, data: &[u8]) -> String {
+        to_hex(hmac::sign(&self.key, data).as_ref())
+    }
+
+    fn verify(&self, data: &[u8], mac: &str) -> bool {
+        from_hex(mac).is_some_and(|tag| hmac::verify(&self.key, data, &tag).is_ok())
+    }
+}
+
+// ── Entries ─────────────────────────────────────────────────────
+
+/// One audit log row.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub timestamp: String,
+    pub event_type: String,
+    pub action: String,
+    pub details: Option<String>,
+    /// MAC of the entry before; `None` for legacy entries, empty for the
+    /// first link of the chain.
+    pub prev_mac: Option<String>,
+    /// This entry's MAC (a plain checksum for legacy entries).
+    pub mac: String,
+}
+
+impl AuditEntry {
+    /// The bytes the MAC covers: every field, length-prefixed so no two
+    /// entries encode the same.
+    fn signed_bytes(&self) -> Vec<u8> {
+        let id = self.id.to_string();
+        let fields = [
+            Some(self.prev_mac.as_deref().unwrap_or("")),
+            Some(id.as_str()),
+            Some(self.timestamp.as_str()),
+            Some(self.event_type.as_str()),
+            Some(self.action.as_str()),
+            self.details.as_deref(),
+        ];
+        encode(&fields)
+    }
+}
+
+fn encode(fields: &[Option<&str>]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for field in fields {
+        match field {
+            Some(text) => {
+                out.push(1);
+                out.extend_from_slice(&(text.len() as u64).to_le_bytes());
+                out.extend_from_slice(text.as_bytes());
+            }
+            None => out.push(0),
+        }
+    }
+    out
+}
+
+const ENTRY_COLUMNS: &str = "id, timestamp, event_type, action, details, prev_mac, checksum";
+
+fn entry_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<AuditEntry> {
+    Ok(AuditEntry {
+        id: row.get(0)?,
+        timestamp: row.get(1)?,
+        event_type: row.get(2)?,
+        action: row.get(3)?,
+        details: row.get(4)?,
+        prev_mac: row.get(5)?,
+        mac: row.get(6)?,
+    })
+}
+
+fn entries(
+    conn: &Connection,
+    filter: &str,
+    params: &[&dyn rusqlite::ToSql],
+) -> Result<Vec<AuditEntry>> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {ENTRY_COLUMNS} FROM audit_log {filter} ORDER BY id"
+        ))
+        .map_err(db_err)?;
+    let rows = stmt
+        .query_map(params, entry_from_row)
+        .map_err(db_err)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(db_err)?;
+    Ok(rows)
+}
+
+fn meta(conn: &Connection, key: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT value FROM memory_meta WHERE key = ?1",
+        [key],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(db_err)
+}
+
+fn set_meta(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO memory_meta (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        [key, value],
+    )
+    .map_err(db_err)?;
+    Ok(())
+}
+
+/// Digest of the legacy entries, as recorded when the chain started.
+fn legacy_digest(legacy: &[AuditEntry]) -> String {
+    let mut hasher = blake3::Hasher::new();
+    for entry in legacy {
+        let id = entry.id.to_string();
+        hasher.update(&encode(&[
+            Some(&id),
+            Some(&entry.timestamp),
+            Some(&entry.event_type),
+            Some(&entry.action),
+            entry.details.as_deref(),
+            Some(&entry.mac),
+        ]));
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+/// The newest link of the chain, as recorded in `memory_meta`.
+struct ChainHead {
+    id: i64,
+    mac: String,
+    /// Chained entries up to and including this one.
+    count: u64,
+}
+
+/// A recorded head, and whether its signature holds.
+enum Head {
+    Valid(ChainHead),
+    /// Edited, forged or signed with another key.
+    Invalid,
+}
+
+impl ChainHead {
+    fn signed_bytes(&self) -> Vec<u8> {
+        let (id, count) = (self.id.to_string(), self.count.to_string());
+        encode(&[Some(HEAD_KEY), Some(&id), Some(&self.mac), Some(&count)])
+    }
+
+    fn store(&self, conn: &Connection, key: &AuditKey) -> Result<()> {
+        let signature = key.sign(&self.signed_bytes());
+        let value = format!("{} {} {} {signature}", self.id, self.mac, self.count);
+        set_meta(conn, HEAD_KEY, &value)
+    }
+
+    fn load(conn: &Connection, key: &AuditKey) -> Result<Option<Head>> {
+        let Some(value) = meta(conn, HEAD_KEY)? else {
+            return Ok(None);
+        };
+        let parsed = match value.split(' ').collect::<Vec<_>>()[..] {
+            [id, mac, count, signature] => {
+                id.parse().ok().zip(count.parse().ok()).map(|(id, count)| {
+                    let head = ChainHead {
+                        id,
+                        mac: mac.to_string(),
+                        count,
+                    };
+                    (head, signature)
+                })
+            }
+            _ => None,
+        };
+        Ok(Some(match parsed {
+            Some((head, signature)) if key.verify(&head.signed_bytes(), signature) => {
+                Head::Valid(head)
+            }
+            _ => Head::Invalid,
+        }))
+    }
+}
+
+fn chained_count(conn: &Connection) -> Result<u64> {
+    conn.query_row(
+        "SELECT count(*) FROM audit_log WHERE prev_mac IS NOT NULL",
+        [],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|n| n as u64)
+    .map_err(db_err)
+}
+
+/// Append an entry to the chain. It links to the recorded head, not the
+/// newest row, so entries deleted from the end stay missing from the chain.
+pub(crate) fn append(
+    conn: &Connection,
+    key: &AuditKey,
+    timestamp: &str,
+    event_type: &str,
+    action: &str,
+    details: Option<&str>,
+) -> Result<AuditEntry> {
+    // A savepoint, so appending works inside an import's transaction too
+    conn.execute_batch("SAVEPOINT audit_append")
+        .map_err(db_err)?;
+    let result = (|| {
+        // Without a head that verifies there is nothing trustworthy to
+        // link to: start a new link, which shows as a break unless this is
+        // the first entry of the chain
+        let (prev_mac, count) = match ChainHead::load(conn, key)? {
+            Some(Head::Valid(head)) => (head.mac, Some(head.count + 1)),
+            Some(Head::Invalid) | None => (String::new(), None),
+        };
+        conn.execute(
+            "INSERT INTO audit_log (timestamp, event_type, action, details, prev_mac, checksum)
+             VALUES (?1, ?2, ?3, ?4, ?5, '')",
+            rusqlite::params![timestamp, event_type, action, details, prev_mac],
+        )
+        .map_err(db_err)?;
+        let mut entry = AuditEntry {
+            id: conn.last_insert_rowid(),
+            timestamp: timestamp.to_string(),
+            event_type: event_type.to_string(),
+            action: action.to_string(),
+            details: details.map(str::to_string),
+            prev_mac: Some(prev_mac),
+            mac: String::new(),
+        };
+        entry.mac = key.sign(&entry.signed_bytes());
+        conn.execute(
+            "UPDATE audit_log SET checksum = ?1 WHERE id = ?2",
+            rusqlite::params![entry.mac, entry.id],
+        )
+        .map_err(db_err)?;
+        let count = match count {
+            Some(count) => count,
+            None => chained_count(conn)?,
+        };
+        ChainHead {
+            id: entry.id,
+            mac: entry.mac.clone(),
+            count,
+        }
+        .store(conn, key)?;
+        if meta(conn, KEY_ID_KEY)?.is_none() {
+            set_meta(conn, KEY_ID_KEY, key.id())?;
+        }
+        Ok(entry)
+    })();
+    match result {
+        Ok(entry) => {
+            conn.execute_batch("RELEASE audit_append").map_err(db_err)?;
+            Ok(entry)
+        }
+        Err(e) => {
+            let _ = conn.execute_batch("ROLLBACK TO audit_append; RELEASE audit_append");
+            Err(e)
+        }
+    }
+}
+
+/// Start the chain over entries written before it existed, pinning them
+/// with a `chain_started` entry. Does nothing once the chain has started.
+pub(crate) fn seal_legacy(conn: &Connection, key: &AuditKey) -> Result<()> {
+    if meta(conn, HEAD_KEY)?.is_some() {
+        if let Some(started_with) = meta(conn, KEY_ID_KEY)?
+            && started_with != key.id()
+        {
+            tracing::warn!(
+                expected = %started_with,
+                found = %key.id(),
+                "audit key differs from the one the audit chain was started with — verification will fail"
+            );
+        }
+        return Ok(());
+    }
+    let legacy = entries(conn, "WHERE prev_mac IS NULL", &[])?;
+    if legacy.is_empty() {
+        return Ok(());
+    }
+    let details = serde_json::json!({
+        "legacy_entries": legacy.len(),
+        "legacy_digest": legacy_digest(&legacy),
+    });
+    append(
+        conn,
+        key,
+        &Utc::now().to_rfc3339(),
+        CHAIN_STARTED.0,
+        CHAIN_STARTED.1,
+        Some(&details.to_string()),
+    )?;
+    tracing::info!(legacy = legacy.len(), "started audit log hash chain");
+    Ok(())
+}
+
+/// Add an entry from a memory archive: its contents are kept and it is
+/// sealed into the local chain, unless an identical entry is already there.
+/// Returns whether it was added.
+pub(crate) fn import(
+    conn: &Connection,
+    key: &AuditKey,
+    timestamp: &str,
+    event_type: &str,
+    action: &str,
+    details: Option<&str>,
+) -> Result<bool> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT count(*) > 0 FROM audit_log
+             WHERE timestamp = ?1 AND event_type = ?2 AND action = ?3 AND details IS ?4",
+            rusqlite::params![timestamp, event_type, action, details],
+            |row| row.get(0),
+        )
+        .map_err(db_err)?;
+    if !exists {
+        append(conn, key, timestamp, event_type, action, details)?;
+    }
+    Ok(!exists)
+}
+
+// ── Verification ────────────────────────────────────────────────
+
+/// Something wrong with the chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditIssue {
+    /// The entry where it shows, if any.
+    pub id: Option<i64>,
+    pub problem: String,
+}
+
+impl std::fmt::Display for AuditIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.id {
+            Some(id) => write!(f, "#{id}: {}", self.problem),
+            None => f.write_str(&self.problem),
+        }
+    }
+}
+
+/// Outcome of verifying the audit log or a segment of it.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AuditReport {
+    /// Entries checked.
+    pub entries: usize,
+    /// Entries from before the chain, pinned by its first entry.
+    pub legacy: usize,
+    pub first_id: Option<i64>,
+    pub last_id: Option<i64>,
+    /// Fingerprint of the key used.
+    pub key_id: String,
+    pub issues: Vec<AuditIssue>,
+}
+
+impl AuditReport {
+    /// No deletions, edits or reordering found.
+    pub fn is_intact(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn issue(&mut self, id: Option<i64>, problem: impl Into<String>) {
+        self.issues.push(AuditIssue {
+            id,
+            problem: problem.into(),
+        });
+    }
+}
+
+/// Walks chained entries in id order, checking each MAC and link.
+struct ChainCheck<'a> {
+    key: &'a AuditKey,
+    /// Id and MAC of the last entry seen.
+    prev: Option<(i64, String)>,
+}
+
+impl ChainCheck<'_> {
+    fn check(&mut self, entry: &AuditEntry, report: &mut AuditReport) {
+        let prev_mac = entry.prev_mac.as_deref().unwrap_or("");
+        match &self.prev {
+            Some((prev_id, mac)) if prev_mac != mac => {
+                let missing = entry.id - prev_id - 1;
+                if missing > 0 {
+                    report.issue(
+                        Some(entry.id),
+                        format!("{missing} entries deleted after #{prev_id}"),
+                    );
+                } else {
+                    report.issue(
+                        Some(entry.id),
+                        format!("not linked to #{prev_id} — entries were reordered or replaced"),
+                    );
+                }
+            }
+            _ => {}
+        }
+        if !self.key.verify(&entry.signed_bytes(), &entry.mac) {
+            report.issue(
+                Some(entry.id),
+                "contents don't match the MAC — the entry was edited or moved",
+            );
+        }
+        self.prev = Some((entry.id, entry.mac.clone()));
+    }
+}
+
+/// Verify the whole audit log of a database.
+pub(crate) fn verify(conn: &Connection, key: &AuditKey) -> Result<AuditReport> {
+    let rows = entries(conn, "", &[])?;
+    let mut report = AuditReport {
+        entries: rows.len(),
+        first_id: rows.first().map(|e| e.id),
+        last_id: rows.last().map(|e| e.id),
+        key_id: key.id().to_string(),
+        ..Default::default()
+    };
+    let started_with = meta(conn, KEY_ID_KEY)?;
+    let key_replaced = started_with.as_deref().is_some_and(|k| k != key.id());
+    if let Some(started_with) = started_with.filter(|_| key_replaced) {
+        report.issue(
+            None,
+            format!(
+                "the chain was started with key {started_with}, not {} — the audit key was replaced",
+                key.id()
+            ),
+        );
+    }
+
+    let mut legacy = Vec::new();
+    let mut chained = 0;
+    let mut chain = ChainCheck { key, prev: None };
+    for entry in &rows {
+        if entry.prev_mac.is_none() {
+            if chain.prev.is_some() {
+                report.issue(
+                    Some(entry.id),
+                    "not sealed into the chain — inserted directly",
+                );
+            } else {
+                legacy.push(entry.clone());
+            }
+            continue;
+        }
+        if chain.prev.is_none() {
+            if entry.prev_mac.as_deref() != Some("") {
+                report.issue(
+                    Some(entry.id),
+                    "the start of the chain is missing — entries were deleted",
+                );
+            }
+            if !legacy.is_empty() {
+                check_legacy(entry, &legacy, &mut report);
+            }
+        }
+        chain.check(entry, &mut report);
+        chained += 1;
+    }
+    report.legacy = legacy.len();
+
+    // Deleting the newest entries leaves a valid chain; the signed head
+    // doesn't match it
+    match (ChainHead::load(conn, key)?, &chain.prev) {
+        (None, None) => {}
+        (None, Some((last_id, _))) => report.issue(
+            None,
+            format!(
+                "the chain head record is missing — entries after #{last_id} may have been deleted"
+            ),
+        ),
+        // Every signature fails with another key; that's already reported
+        (Some(Head::Invalid), _) if key_replaced => {}
+        (Some(Head::Invalid), _) => report.issue(
+            None,
+            "the chain head record doesn't match its signature — it was edited",
+        ),
+        (Some(Head::Valid(head)), None) => report.issue(
+            None,
+            format!("every chained entry up to #{} was deleted", head.id),
+        ),
+        (Some(Head::Valid(head)), Some((last_id, last_mac))) if head.mac != *last_mac => {
+            if head.id > *last_id {
+                report.issue(
+                    None,
+                    format!(
+                        "entries after #{last_id} were deleted (the chain ended at #{})",
+                        head.id
+                    ),
+                );
+            } else {
+                report.issue(
+                    Some(*last_id),
+                    format!("the chain should end at #{}", head.id),
+                );
+            }
+        }
+        (Some(Head::Valid(head)), Some(_)) if report.is_intact() && head.count != chained => {
+            report.issue(
+                None,
+                format!(
+                    "the chain should hold {} entries, found {chained}",
+                    head.count
+                ),
+            );
+        }
+        _ => {}
+    }
+    Ok(report)
+}
+
+/// The first chained entry must pin the legacy entries before it.
+fn check_legacy(first: &AuditEntry, legacy: &[AuditEntry], report: &mut AuditReport) {
+    let pinned = (first.event_type.as_str(), first.action.as_str()) == CHAIN_STARTED;
+    let details: serde_json::Value = first
+        .details
+        .as_deref()
+        .and_then(|d| serde_json::from_str(d).ok())
+        .unwrap_or_default();
+    if !pinned {
+        report.issue(
+            Some(first.id),
+            format!("{} unchained entries before the chain", legacy.len()),
+        );
+    } else if details["legacy_entries"].as_u64() != Some(legacy.len() as u64)
+        || details["legacy_digest"].as_str() != Some(legacy_digest(legacy).as_str())
+    {
+        report.issue(
+            Some(first.id),
+            "entries from before the chain were changed since it started",
+        );
+    }
+}
+
+// ── Segments ────────────────────────────────────────────────────
+
+/// First line of an exported segment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentHeader {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: String,
+    pub key_id: String,
+    pub first_id: i64,
+    pub last_id: i64,
+    pub count: usize,
+    /// Link to the entry before the segment.
+    pub prev_mac: String,
+    /// MAC of the segment's last entry.
+    pub last_mac: String,
+    /// MAC over the fields above.
+    pub signature: String,
+}
+
+impl SegmentHeader {
+    fn signed_bytes(&self) -> Vec<u8> {
+        let (version, first, last, count) = (
+            self.version.to_string(),
+            self.first_id.to_string(),
+            self.last_id.to_string(),
+            self.count.to_string(),
+        );
+        encode(&[
+            Some(&self.format),
+            Some(&version),
+            Some(&self.exported_at),
+            Some(&self.key_id),
+            Some(&first),
+            Some(&last),
+            Some(&count),
+            Some(&self.prev_mac),
+            Some(&self.last_mac),
+        ])
+    }
+}
+
+/// Write the chained entries with ids in `from..=to` as a signed segment:
+/// a header line, then one entry per line.
+pub(crate) fn export_segment(
+    conn: &Connection,
+    key: &AuditKey,
+    from: Option<i64>,
+    to: Option<i64>,
+    out: &mut dyn Write,
+) -> Result<SegmentHeader> {
+    let rows = entries(
+        conn,
+        "WHERE prev_mac IS NOT NULL AND id >= ?1 AND id <= ?2",
+        &[&from.unwrap_or(i64::MIN), &to.unwrap_or(i64::MAX)],
+    )?;
+    let (Some(first), Some(last)) = (rows.first(), rows.last()) else {
+        return Err(ClawError::Memory(
+            "no chained audit entries in that range".into(),
+        ));
+    };
+    let mut header = SegmentHeader {
+        format: SEGMENT_FORMAT.to_string(),
+        version: SEGMENT_VERSION,
+        exported_at: Utc::now().to_rfc3339(),
+        key_id: key.id().to_string(),
+        first_id: first.id,
+        last_id: last.id,
+        count: rows.len(),
+        prev_mac: first.prev_mac.clone().unwrap_or_default(),
+        last_mac: last.mac.clone(),
+        signature: String::new(),
+    };
+    header.signature = key.sign(&header.signed_bytes());
+
+    let io_err = |e: std::io::Error| ClawError::Memory(format!("audit segment: {e}"));
+    serde_json::to_writer(&mut *out, &header)?;
+    out.write_all(b"\n").map_err(io_err)?;
+    for entry in &rows {
+        serde_json::to_writer(&mut *out, entry)?;
+        out.write_all(b"\n").map_err(io_err)?;
+    }
+    out.flush().map_err(io_err)?;
+    Ok(header)
+}
+
+/// Check an exported segment against `key`: the header signature, every
+/// entry's MAC and link, and that the entries are the ones the header
+/// names.
+pub fn verify_segment(input: &mut dyn BufRead, key: &AuditKey) -> Result<AuditReport> {
+    let mut lines = input.lines();
+    let header = lines
+        .next()
+        .ok_or_else(|| ClawError::Memory("audit segment is empty".into()))?
+        .map_err(|e| ClawError::Memory(format!("audit segment: {e}")))?;
+    let header: SegmentHeader = serde_json::from_str(&header)
+        .map_err(|e| ClawError::Memory(format!("audit segment: bad header: {e}")))?;
+    if header.format != SEGMENT_FORMAT {
+        return Err(ClawError::Memory(format!(
+            "not an audit segment (format '{}')",
+            header.format
+        )));
+    }
+
+    let mut report = AuditReport {
+        key_id: key.id().to_string(),
+        ..Default::default()
+    };
+    if header.key_id != key.id() {
+        report.issue(
+            None,
+            format!(
+                "segment was signed with key {}, not {}",
+                header.key_id,
+                key.id()
+            ),
+        );
+    } else if !key.verify(&header.signed_bytes(), &header.signature) {
+        report.issue(
+            None,
+            "header signature doesn't match — the header was edited",
+        );
+    }
+
+    let mut chain = ChainCheck {
+        key,
+        prev: Some((header.first_id - 1, header.prev_mac.clone())),
+    };
+    for (n, line) in lines.enumerate() {
+        let line = line.map_err(|e| ClawError::Memory(format!("audit segment: {e}")))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: AuditEntry = serde_json::from_str(&line)
+            .map_err(|e| ClawError::Memory(format!("audit segment line {}: {e}", n + 2)))?;
+        report.entries += 1;
+        report.first_id.get_or_insert(entry.id);
+        report.last_id = Some(entry.id);
+        chain.check(&entry, &mut report);
+    }
+
+    if report.entries != header.count || report.last_id != Some(header.last_id) {
+        report.issue(
+            None,
+            format!(
+                "segment should hold {} entries #{}–#{}, found {}",
+                header.count, header.first_id, header.last_id, report.entries
+            ),
+        );
+    } else if chain.prev.map(|(_, mac)| mac) != Some(header.last_mac) {
+        report.issue(Some(header.last_id), "last entry doesn't match the header");
+    }
+    Ok(report)
+}
+
+// ── Offline access ──────────────────────────────────────────────
+
+/// A database's audit log opened read-only with its key — for verifying
+/// and exporting from the CLI, whether or not the agent is running.
+pub struct AuditLog {
+    conn: Connection,
+    key: AuditKey,
+}
+
+impl AuditLog {
+    pub fn open(db_path: &Path) -> Result<Self> {
+        if !db_path.exists() {
+            return Err(ClawError::Memory(format!(
+                "no memory database at {}",
+                db_path.display()
+            )));
+        }
+        let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(db_err)?;
+        let chained = conn
+            .prepare("SELECT prev_mac FROM audit_log LIMIT 0")
+            .is_ok();
+        if !chained {
+            return Err(ClawError::Memory(
+                "the audit log predates hash chaining — start the agent once to migrate it".into(),
+            ));
+        }
+        let key = AuditKey::load(&key_path(db_path))?;
+        Ok(Self { conn, key })
+    }
+
+    pub fn key(&self) -> &AuditKey {
+        &self.key
+    }
+
+    pub fn verify(&self) -> Result<AuditReport> {
+        verify(&self.conn, &self.key)
+    }
+
+    pub fn export_segment(
+        &self,
+        from: Option<i64>,
+        to: Option<i64>,
+        out: &mut dyn Write,
+    ) -> Result<SegmentHeader> {
+        export_segment(&self.conn, &self.key, from, to, out)
+    }
+}
@@ -0,0 +1,576 @@
+//! Export / import — a portable JSONL archive of the memory database.
+//!
+//! The first line is a manifest naming the format, its version and, per
+//! table, the key columns and row count. Every further line is one row:
+//! `{"table": "facts", "row": {...}}`, grouped by table. Fact embeddings are
+//! written as arrays of floats; other blobs as `{"hex": "..."}`.
+//!
+//! Import matches rows on each table's natural key and resolves clashes with
+//! a [`MergeStrategy`]. Columns the local schema doesn't have are ignored, so
+//! archives move between versions. Derived data (full-text and vector
+//! indexes) is rebuilt rather than archived.
+
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::io::{BufRead, Write};
+
+use claw_core::{ClawError, Result};
+
+use crate::audit::AuditKey;
+
+/// Identifies an archive in its manifest.
+pub const FORMAT: &str = "claw-memory-export";
+/// Current archive version. Import accepts this version and older.
+pub const VERSION: u32 = 1;
+
+/// An archived table: its natural key, and the column deciding which of two
+/// copies of a row is newer.
+struct TableSpec {
+    name: &'static str,
+    key: &'static [&'static str],
+    updated: Option<&'static str>,
+    /// Rows are never overwritten, and their `id` is a local rowid that
+    /// isn't archived.
+    append_only: bool,
+}
+
+/// Tables in export order (parents before children). Fact history comes
+/// before facts so importing a fact doesn't record its value a second time.
+/// The audit log and fact history are append-only: their rows are matched
+/// on content and never overwritten. Imported audit entries are re-sealed
+/// into the local hash chain.
+const TABLES: &[TableSpec] = &[
+    TableSpec {
+        name: "fact_history",
+        key: &["scope", "category", "key", "version"],
+        updated: None,
+        append_only: true,
+    },
+    TableSpec {
+        name: "facts",
+        key: &["scope", "category", "key"],
+        updated: Some("updated_at"),
+        append_only: false,
+    },
+    TableSpec {
+        name: "facts_archive",
+        key: &["scope", "category", "key"],
+        updated: Some("archived_at"),
+        append_only: false,
+    },
+    TableSpec {
+        name: "fact_conflicts",
+        key: &["id"],
+        updated: Some("resolved_at"),
+        append_only: false,
+    },
+    TableSpec {
+        name: "graph_entities",
+        key: &["id"],
+        updated: Some("updated_at"),
+        append_only: false,
+    },
+    TableSpec {
+        name: "graph_relations",
+        key: &["subject", "predicate", "object"],
+        updated: Some("updated_at"),
+        append_only: false,
+    },
+    TableSpec {
+        name: "episodes",
+        key: &["id"],
+        updated: Some("updated_at"),
+        append_only: false,
+    },
+    TableSpec {
+        name: "episode_messages",
+        key: &["id"],
+        updated: None,
+        append_only: false,
+    },
+    TableSpec {
+        name: "goals",
+        key: &["id"],
+        updated: Some("updated_at"),
+        append_only: false,
+    },
+    TableSpec {
+        name: "goal_steps",
+        key: &["id"],
+        updated: None,
+        append_only: false,
+    },
+    TableSpec {
+        name: "sessions",
+        key: &["id"],
+        updated: Some("updated_at"),
+        append_only: false,
+    },
+    TableSpec {
+        name: "session_messages",
+        key: &["session_id"],
+        updated: Some("updated_at"),
+        append_only: false,
+    },
+    TableSpec {
+        name: "scheduled_tasks",
+        key: &["id"],
+        updated: Some("last_fired"),
+        append_only: false,
+    },
+    TableSpec {
+        name: "audit_log",
+        key: &["timestamp", "event_type", "action", "details"],
+        updated: None,
+        append_only: true,
+    },
+];
+
+/// How import treats a row that already exists locally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeStrategy {
+    /// Keep the local row.
+    #[default]
+    Skip,
+    /// Replace the local row with the archived one.
+    Overwrite,
+    /// Keep whichever row was updated last; ties keep the local row.
+    NewestWins,
+}
+
+impl std::str::FromStr for MergeStrategy {
+    type Err = ClawError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            "newest-wins" | "newest" => Ok(Self::NewestWins),
+            other => Err(ClawError::Memory(format!(
+                "unknown merge strategy '{other}' (expected skip, overwrite or newest-wins)"
+            ))),
+        }
+    }
+}
+
+/// Per-table entry in the archive manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableManifest {
+    pub name: String,
+    pub key: Vec<String>,
+    pub rows: usize,
+}
+
+/// First line of an archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: String,
+    pub claw_version: String,
+    pub tables: Vec<TableManifest>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RowLine {
+    table: String,
+    row: BTreeMap<String, serde_json::Value>,
+}
+
+/// Row counts for one table after an import.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TableImport {
+    pub inserted: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+/// Outcome of [`crate::MemoryStore::import`], keyed by table name.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport {
+    pub strategy: MergeStrategy,
+    pub tables: BTreeMap<String, TableImport>,
+    /// Rows for tables this version doesn't know, which were ignored.
+    pub unknown_rows: usize,
+}
+
+impl ImportReport {
+    pub fn total(&self) -> TableImport {
+        self.tables
+            .values()
+            .fold(TableImport::default(), |a, t| TableImport {
+                inserted: a.inserted + t.inserted,
+                updated: a.updated + t.updated,
+                skipped: a.skipped + t.skipped,
+            })
+    }
+}
+
+fn db_err(e: rusqlite::Error) -> ClawError {
+    ClawError::Memory(e.to_string())
+}
+
+fn io_err(e: std::io::Error) -> ClawError {
+    ClawError::Memory(format!("memory archive: {e}"))
+}
+
+fn columns(conn: &Connection, table: &str) -> Result<Vec<String>> {
+    let mut stmt = conn
+        .prepare(&format!("PRAGMA table_info({table})"))
+        .map_err(db_err)?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(db_err)?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(columns)
+}
+
+fn to_json(column: &str, value: Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Integer(i) => i.into(),
+        Value::Real(f) => f.into(),
+        Value::Text(s) => s.into(),
+        Value::Blob(b) if column == "embedding" && b.len().is_multiple_of(4) => b
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect::<Vec<f32>>()
+            .into(),
+        Value::Blob(b) => {
+            let hex: String = b.iter().map(|byte| format!("{byte:02x}")).collect();
+            serde_json::json!({ "hex": hex })
+        }
+    }
+}
+
+fn from_json(value: &serde_json::Value) -> Result<Value> {
+    Ok(match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        serde_json::Value::Array(items) => Value::Blob(
+            items
+                .iter()
+                .flat_map(|v| (v.as_f64().unwrap_or_default() as f32).to_le_bytes())
+                .collect(),
+        ),
+        serde_json::Value::Object(obj) => {
+            let hex = obj
+                .get("hex")
+                .and_then(|h| h.as_str())
+                .filter(|h| h.is_ascii());
+            let hex = hex.ok_or_else(|| {
+                ClawError::Memory("memory archive: unsupported value".to_string())
+            })?;
+            let bytes = (0..hex.len() / 2)
+                .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16))
+                .collect::<std::result::Result<Vec<u8>, _>>()
+                .map_err(|e| ClawError::Memory(format!("memory archive: bad hex blob: {e}")))?;
+            Value::Blob(bytes)
+        }
+    })
+}
+
+/// Write every archived table to `out`. Returns the manifest written.
+pub(crate) fn export(conn: &Connection, out: &mut dyn Write) -> Result<Manifest> {
+    // One read transaction, so the manifest counts match the rows written
+    conn.execute_batch("BEGIN").map_err(db_err)?;
+    let result = export_tables(conn, out);
+    let _ = conn.execute_batch("COMMIT");
+    result
+}
+
+fn export_tables(conn: &Connection, out: &mut dyn Write) -> Result<Manifest> {
+    let mut manifest = Manifest {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        exported_at: Utc::now().to_rfc3339(),
+        claw_version: env!("CARGO_PKG_VERSION").to_string(),
+        tables: Vec::new(),
+    };
+    for spec in TABLES {
+        let rows: i64 = conn
+            .query_row(&format!("SELECT count(*) FROM {}", spec.name), [], |r| {
+                r.get(0)
+            })
+            .map_err(db_err)?;
+        manifest.tables.push(TableManifest {
+            name: spec.name.to_string(),
+            key: spec.key.iter().map(|k| k.to_string()).collect(),
+            rows: rows as usize,
+        });
+    }
+    serde_json::to_writer(&mut *out, &manifest)?;
+    out.write_all(b"\n").map_err(io_err)?;
+
+    for spec in TABLES {
+        // Append-only tables' rowids are local; everything else is archived as-is
+        let columns: Vec<String> = columns(conn, spec.name)?
+            .into_iter()
+            .filter(|c| !(spec.append_only && c == "id"))
+            .collect();
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM {} ORDER BY rowid",
+                columns.join(", "),
+                spec.name
+            ))
+            .map_err(db_err)?;
+        let mut rows = stmt.query([]).map_err(db_err)?;
+        while let Some(row) = rows.next().map_err(db_err)? {
+            let mut line = RowLine {
+                table: spec.name.to_string(),
+                row: BTreeMap::new(),
+            };
+            for (i, column) in columns.iter().enumerate() {
+                let value: Value = row.get(i).map_err(db_err)?;
+                line.row.insert(column.clone(), to_json(column, value));
+            }
+            serde_json::to_writer(&mut *out, &line)?;
+            out.write_all(b"\n").map_err(io_err)?;
+        }
+    }
+    out.flush().map_err(io_err)?;
+    Ok(manifest)
+}
+
+/// Whether `incoming` is strictly newer than `existing`, comparing RFC 3339
+/// timestamps (`NULL` is older than anything).
+fn is_newer(incoming: &Value, existing: &Value) -> bool {
+    let parse = |v: &Value| match v {
+        Value::Text(s) => DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|d| d.with_timezone(&Utc)),
+        _ => None,
+    };
+    match (parse(incoming), parse(existing)) {
+        (Some(a), Some(b)) => a > b,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+/// Read an archive from `input` into the database in one transaction.
+/// Returns the report and the `messages_json` of imported session rows,
+/// which the caller adds to the message index.
+pub(crate) fn import(
+    conn: &Connection,
+    input: &mut dyn BufRead,
+    strategy: MergeStrategy,
+    audit_key: &AuditKey,
+) -> Result<(ImportReport, Vec<String>)> {
+    let mut lines = input.lines();
+    let header = lines
+        .next()
+        .ok_or_else(|| ClawError::Memory("memory archive is empty".to_string()))?
+        .map_err(io_err)?;
+    let manifest: Manifest = serde_json::from_str(&header)
+        .map_err(|e| ClawError::Memory(format!("memory archive: bad manifest: {e}")))?;
+    if manifest.format != FORMAT {
+        return Err(ClawError::Memory(format!(
+            "not a memory archive (format '{}')",
+            manifest.format
+        )));
+    }
+    if manifest.version > VERSION {
+        return Err(ClawError::Memory(format!(
+            "memory archive version {} is newer than supported ({VERSION}) — upgrade claw",
+            manifest.version
+        )));
+    }
+
+    let local_columns: BTreeMap<&str, HashSet<String>> = TABLES
+        .iter()
+        .map(|spec| Ok((spec.name, columns(conn, spec.name)?.into_iter().collect())))
+        .collect::<Result<_>>()?;
+
+    let mut report = ImportReport {
+        strategy,
+        ..Default::default()
+    };
+    let mut imported_messages = Vec::new();
+
+    conn.execute_batch("BEGIN IMMEDIATE").map_err(db_err)?;
+    let result = (|| -> Result<()> {
+        for (n, line) in lines.enumerate() {
+            let line = line.map_err(io_err)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let row: RowLine = serde_json::from_str(&line)
+                .map_err(|e| ClawError::Memory(format!("memory archive line {}: {e}", n + 2)))?;
+            let Some(spec) = TABLES.iter().find(|s| s.name == row.table) else {
+                report.unknown_rows += 1;
+                continue;
+            };
+            let local = &local_columns[spec.name];
+            let mut values: Vec<(&str, Value)> = Vec::new();
+            for (column, value) in &row.row {
+                if local.contains(column) && !(spec.append_only && column == "id") {
+                    values.push((column.as_str(), from_json(value)?));
+                }
+            }
+            // Archives from before memory scopes: everything was global
+            if local.contains("scope") && !row.row.contains_key("scope") {
+                values.push(("scope", Value::Text(crate::scope::global())));
+            }
+            let counts = report.tables.entry(spec.name.to_string()).or_default();
+            let outcome = if spec.name == "audit_log" {
+                import_audit_entry(conn, audit_key, &row.row)?
+            } else {
+                import_row(conn, spec, &values, strategy)?
+            };
+            match outcome {
+                RowOutcome::Inserted => counts.inserted += 1,
+                RowOutcome::Updated => counts.updated += 1,
+                RowOutcome::Skipped => counts.skipped += 1,
+            }
+            if spec.name == "session_messages"
+                && outcome != RowOutcome::Skipped
+                && let Some((_, Value::Text(json))) =
+                    values.iter().find(|(c, _)| *c == "messages_json")
+            {
+                imported_messages.push(json.clone());
+            }
+        }
+        Ok(())
+    })();
+    match result {
+        Ok(()) => conn.execute_batch("COMMIT").map_err(db_err)?,
+        Err(e) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            return Err(e);
+        }
+    }
+    Ok((report, imported_messages))
+}
+
+/// Audit entries are sealed into the local chain rather than copied, so
+/// their MACs verify with this device's key.
+fn import_audit_entry(
+    conn: &Connection,
+    key: &AuditKey,
+    row: &BTreeMap<String, serde_json::Value>,
+) -> Result<RowOutcome> {
+    let text = |column: &str| row.get(column).and_then(|v| v.as_str());
+    let (Some(timestamp), Some(event_type), Some(action)) =
+        (text("timestamp"), text("event_type"), text("action"))
+    else {
+        return Err(ClawError::Memory(
+            "memory archive: audit_log row without timestamp, event_type or action".to_string(),
+        ));
+    };
+    let added = crate::audit::import(conn, key, timestamp, event_type, action, text("details"))?;
+    Ok(if added {
+        RowOutcome::Inserted
+    } else {
+        RowOutcome::Skipped
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowOutcome {
+    Inserted,
+    Updated,
+    Skipped,
+}
+
+fn import_row(
+    conn: &Connection,
+    spec: &TableSpec,
+    values: &[(&str, Value)],
+    strategy: MergeStrategy,
+) -> Result<RowOutcome> {
+    let value_of = |column: &str| values.iter().find(|(c, _)| *c == column).map(|(_, v)| v);
+    let mut key_values = Vec::with_capacity(spec.key.len());
+    for column in spec.key {
+        let value = value_of(column).ok_or_else(|| {
+            ClawError::Memory(format!(
+                "memory archive: {} row without key column '{column}'",
+                spec.name
+            ))
+        })?;
+        key_values.push(value.clone());
+    }
+    let key_clause = spec
+        .key
+        .iter()
+        .enumerate()
+        .map(|(i, c)| format!("{c} IS ?{}", i + 1))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+
+    let existing: Option<Value> = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM {} WHERE {key_clause}",
+                spec.updated.unwrap_or("NULL"),
+                spec.name
+            ),
+            rusqlite::params_from_iter(&key_values),
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(db_err)?;
+
+    let Some(existing) = existing else {
+        let columns: Vec<&str> = values.iter().map(|(c, _)| *c).collect();
+        let placeholders: Vec<String> = (1..=values.len()).map(|i| format!("?{i}")).collect();
+        conn.execute(
+            &format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                spec.name,
+                columns.join(", "),
+                placeholders.join(", ")
+            ),
+            rusqlite::params_from_iter(values.iter().map(|(_, v)| v)),
+        )
+        .map_err(db_err)?;
+        return Ok(RowOutcome::Inserted);
+    };
+
+    let replace = !spec.append_only
+        && match strategy {
+            MergeStrategy::Skip => false,
+            MergeStrategy::Overwrite => true,
+            MergeStrategy::NewestWins => spec
+                .updated
+                .and_then(value_of)
+                .is_some_and(|incoming| is_newer(incoming, &existing)),
+        };
+    if !replace {
+        return Ok(RowOutcome::Skipped);
+    }
+
+    // Key columns stay as they are, and so does a fact's local id
+    let updates: Vec<&(&str, Value)> = values
+        .iter()
+        .filter(|(c, _)| !spec.key.contains(c))
+        .filter(|(c, _)| !(matches!(spec.name, "facts" | "facts_archive") && *c == "id"))
+        .collect();
+    if updates.is_empty() {
+        return Ok(RowOutcome::Skipped);
+    }
+    let set_clause = updates
+        .iter()
+        .enumerate()
+        .map(|(i, (c, _))| format!("{c} = ?{}", key_values.len() + i + 1))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let params = key_values.iter().chain(updates.iter().map(|(_, v)| v));
+    conn.execute(
+        &format!("UPDATE {} SET {set_clause} WHERE {key_clause}", spec.name),
+        rusqlite::params_from_iter(params),
+    )
+    .map_err(db_err)?;
+    Ok(RowOutcome::Updated)
+}
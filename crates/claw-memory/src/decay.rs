@@ -0,0 +1,102 @@
+//! Forgetting curve for facts — confidence fades while a fact goes unused
+//! and recovers each time it's recalled.
+//!
+//! A fact's stored `confidence` is its strength as of its last update or
+//! recall. From then on it halves every [`DecayPolicy::half_life`] days, a
+//! half-life that grows with every recall, so facts that keep proving useful
+//! fade more slowly. Recalling a fact settles the decay so far and adds a
+//! share of what was lost back. Facts that fade below
+//! [`DecayPolicy::archive_below`] are moved out of the live set by
+//! [`MemoryStore::consolidate_facts`](crate::MemoryStore::consolidate_facts).
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::semantic::Fact;
+
+/// Share of a recall's ranking weight that doesn't depend on confidence, so
+/// a faded but highly relevant fact can still be recalled.
+const RELEVANCE_FLOOR: f64 = 0.25;
+
+/// Ranking boost per e-fold of recalls.
+const FREQUENCY_WEIGHT: f64 = 0.1;
+
+/// How facts decay, are reinforced and get archived.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecayPolicy {
+    /// Days for an unused, never-recalled fact's confidence to halve.
+    /// 0 disables decay.
+    pub half_life_days: f64,
+    /// Share of the missing confidence restored on each recall (0.0-1.0).
+    pub reinforcement: f64,
+    /// Decayed confidence below which a fact is archived.
+    pub archive_below: f64,
+    /// Days archived facts are kept before being deleted. 0 = forever.
+    pub archive_retention_days: u64,
+    /// Categories that never decay.
+    pub exempt_categories: Vec<String>,
+}
+
+impl Default for DecayPolicy {
+    fn default() -> Self {
+        Self {
+            half_life_days: 90.0,
+            reinforcement: 0.2,
+            archive_below: 0.1,
+            archive_retention_days: 180,
+            exempt_categories: vec!["learned_lessons".into()],
+        }
+    }
+}
+
+impl DecayPolicy {
+    /// Whether facts in `category` decay at all.
+    pub fn decays(&self, category: &str) -> bool {
+        self.half_life_days > 0.0 && !self.exempt_categories.iter().any(|c| c == category)
+    }
+
+    /// Half-life in days of a fact recalled `access_count` times.
+    pub fn half_life(&self, access_count: u32) -> f64 {
+        self.half_life_days * (1.0 + f64::from(access_count).ln_1p())
+    }
+
+    /// A fact's confidence after decaying until `now`.
+    pub fn effective_confidence(&self, fact: &Fact, now: DateTime<Utc>) -> f64 {
+        if !self.decays(&fact.category) {
+            return fact.confidence;
+        }
+        let since = fact
+            .last_accessed
+            .map_or(fact.updated_at, |at| at.max(fact.updated_at));
+        let days = (now - since).num_seconds().max(0) as f64 / 86_400.0;
+        fact.confidence * 0.5_f64.powf(days / self.half_life(fact.access_count))
+    }
+
+    /// The confidence to store when a fact is recalled at `now`.
+    pub fn reinforced(&self, fact: &Fact, now: DateTime<Utc>) -> f64 {
+        let current = self.effective_confidence(fact, now);
+        (current + self.reinforcement * (1.0 - current)).clamp(0.0, 1.0)
+    }
+
+    /// Multiplier applied to a fact's relevance score when ranking recall
+    /// results: confident and frequently recalled facts rank higher.
+    pub fn recall_weight(&self, fact: &Fact, now: DateTime<Utc>) -> f64 {
+        let confidence = self.effective_confidence(fact, now);
+        (RELEVANCE_FLOOR + (1.0 - RELEVANCE_FLOOR) * confidence)
+            * (1.0 + FREQUENCY_WEIGHT * f64::from(fact.access_count).ln_1p())
+    }
+
+    /// Whether a fact has faded enough to be archived.
+    pub fn is_stale(&self, fact: &Fact, now: DateTime<Utc>) -> bool {
+        self.decays(&fact.category) && self.effective_confidence(fact, now) < self.archive_below
+    }
+}
+
+/// What [`MemoryStore::consolidate_facts`](crate::MemoryStore::consolidate_facts) did.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Consolidation {
+    /// Facts moved from the live set to the archive.
+    pub archived: usize,
+    /// Archived facts deleted for being older than the retention period.
+    pub purged: usize,
+}
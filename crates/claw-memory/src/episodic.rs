@@ -11,6 +11,9 @@ use uuid::Uuid;
 pub struct Episode {
     pub id: Uuid,
     pub session_id: Uuid,
+    /// Who can recall it (see [`crate::scope`]).
+    #[serde(default = "crate::scope::global")]
+    pub scope: String,
     pub summary: String,
     pub outcome: Option<String>,
     pub tags: Vec<String>,
@@ -53,11 +56,12 @@ impl EpisodicMemory {
             let tags_json =
                 serde_json::to_string(&episode.tags).unwrap_or_else(|_| "[]".to_string());
             let _ = db.execute(
-                "INSERT OR REPLACE INTO episodes (id, session_id, summary, outcome, tags, created_at, updated_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                "INSERT OR REPLACE INTO episodes (id, session_id, scope, summary, outcome, tags, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
                 rusqlite::params![
                     episode.id.to_string(),
                     episode.session_id.to_string(),
+                    &episode.scope,
                     &episode.summary,
                     &episode.outcome,
                     &tags_json,
@@ -81,22 +85,24 @@ impl EpisodicMemory {
         };
         let db = db.lock();
         let mut stmt = db
-            .prepare_cached("SELECT id, session_id, summary, outcome, tags, created_at, updated_at FROM episodes ORDER BY created_at DESC LIMIT 100")
+            .prepare_cached("SELECT id, session_id, scope, summary, outcome, tags, created_at, updated_at FROM episodes ORDER BY created_at DESC LIMIT 100")
             .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
 
         let rows = stmt
             .query_map([], |row| {
                 let id_str: String = row.get(0)?;
                 let session_str: String = row.get(1)?;
-                let summary: String = row.get(2)?;
-                let outcome: Option<String> = row.get(3)?;
-                let tags_str: String = row.get(4)?;
-                let created_str: String = row.get(5)?;
-                let updated_str: String = row.get(6)?;
+                let scope: String = row.get(2)?;
+                let summary: String = row.get(3)?;
+                let outcome: Option<String> = row.get(4)?;
+                let tags_str: String = row.get(5)?;
+                let created_str: String = row.get(6)?;
+                let updated_str: String = row.get(7)?;
 
                 Ok((
                     id_str,
                     session_str,
+                    scope,
                     summary,
                     outcome,
                     tags_str,
@@ -107,7 +113,7 @@ impl EpisodicMemory {
             .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
 
         let mut count = 0;
-        for (id_str, session_str, summary, outcome, tags_str, created_str, updated_str) in
+        for (id_str, session_str, scope, summary, outcome, tags_str, created_str, updated_str) in
             rows.flatten()
         {
             let id = id_str.parse::<Uuid>().unwrap_or_else(|_| Uuid::new_v4());
@@ -123,6 +129,7 @@ impl EpisodicMemory {
             self.recent.push_back(Episode {
                 id,
                 session_id,
+                scope,
                 summary,
                 outcome,
                 tags,
@@ -0,0 +1,360 @@
+//! Full-text search — SQLite FTS5 indexes over facts, episodes and session
+//! messages, ranked with BM25.
+//!
+//! `facts_fts` and `episodes_fts` are external-content tables kept in sync by
+//! triggers on their source tables. Session messages are persisted as one JSON
+//! blob per session, so their text is also written one row per message to
+//! `message_text`, which `messages_fts` indexes. Rows there are keyed by
+//! message id and never rewritten, so text dropped from working memory by
+//! compaction stays searchable.
+
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::hash::Hash;
+use uuid::Uuid;
+
+use crate::episodic::Episode;
+
+/// Rank offset for reciprocal rank fusion (the usual k = 60).
+const RRF_K: f64 = 60.0;
+
+/// A fact matched by full-text search. Higher `score` is better.
+#[derive(Debug, Clone)]
+pub struct FactHit {
+    pub scope: String,
+    pub category: String,
+    pub key: String,
+    pub value: String,
+    pub score: f64,
+}
+
+/// An episode matched by full-text search.
+#[derive(Debug, Clone)]
+pub struct EpisodeHit {
+    pub episode: Episode,
+    pub score: f64,
+}
+
+/// A session message matched by full-text search.
+#[derive(Debug, Clone)]
+pub struct MessageHit {
+    pub message_id: String,
+    pub session_id: String,
+    pub role: String,
+    /// Matching excerpt with hits in `[brackets]`.
+    pub snippet: String,
+    pub timestamp: String,
+    pub score: f64,
+}
+
+fn table_exists(conn: &Connection, name: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE name = ?1",
+        [name],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|r| r.is_some())
+}
+
+/// Create the FTS tables and triggers, backfilling them from existing rows.
+/// Their delete triggers rely on `PRAGMA recursive_triggers`, which the
+/// store turns on for its connection: without it a row replaced by `INSERT
+/// OR REPLACE` would linger in the index.
+pub(crate) fn init(conn: &Connection) -> rusqlite::Result<()> {
+    let facts_new = !table_exists(conn, "facts_fts")?;
+    let episodes_new = !table_exists(conn, "episodes_fts")?;
+    let messages_new = !table_exists(conn, "messages_fts")?;
+
+    conn.execute_batch(
+        "
+        CREATE VIRTUAL TABLE IF NOT EXISTS facts_fts USING fts5(
+            category, key, value,
+            content='facts', content_rowid='rowid', tokenize='porter unicode61'
+        );
+        CREATE TRIGGER IF NOT EXISTS facts_fts_insert AFTER INSERT ON facts BEGIN
+            INSERT INTO facts_fts(rowid, category, key, value)
+            VALUES (new.rowid, new.category, new.key, new.value);
+        END;
+        CREATE TRIGGER IF NOT EXISTS facts_fts_delete AFTER DELETE ON facts BEGIN
+            INSERT INTO facts_fts(facts_fts, rowid, category, key, value)
+            VALUES ('delete', old.rowid, old.category, old.key, old.value);
+        END;
+        CREATE TRIGGER IF NOT EXISTS facts_fts_update AFTER UPDATE OF category, key, value ON facts BEGIN
+            INSERT INTO facts_fts(facts_fts, rowid, category, key, value)
+            VALUES ('delete', old.rowid, old.category, old.key, old.value);
+            INSERT INTO facts_fts(rowid, category, key, value)
+            VALUES (new.rowid, new.category, new.key, new.value);
+        END;
+
+        CREATE VIRTUAL TABLE IF NOT EXISTS episodes_fts USING fts5(
+            summary, outcome, tags,
+            content='episodes', content_rowid='rowid', tokenize='porter unicode61'
+        );
+        CREATE TRIGGER IF NOT EXISTS episodes_fts_insert AFTER INSERT ON episodes BEGIN
+            INSERT INTO episodes_fts(rowid, summary, outcome, tags)
+            VALUES (new.rowid, new.summary, new.outcome, new.tags);
+        END;
+        CREATE TRIGGER IF NOT EXISTS episodes_fts_delete AFTER DELETE ON episodes BEGIN
+            INSERT INTO episodes_fts(episodes_fts, rowid, summary, outcome, tags)
+            VALUES ('delete', old.rowid, old.summary, old.outcome, old.tags);
+        END;
+        CREATE TRIGGER IF NOT EXISTS episodes_fts_update AFTER UPDATE ON episodes BEGIN
+            INSERT INTO episodes_fts(episodes_fts, rowid, summary, outcome, tags)
+            VALUES ('delete', old.rowid, old.summary, old.outcome, old.tags);
+            INSERT INTO episodes_fts(rowid, summary, outcome, tags)
+            VALUES (new.rowid, new.summary, new.outcome, new.tags);
+        END;
+
+        CREATE TABLE IF NOT EXISTS message_text (
+            id TEXT PRIMARY KEY,
+            session_id TEXT NOT NULL,
+            role TEXT NOT NULL,
+            text TEXT NOT NULL,
+            timestamp TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_message_text_session ON message_text(session_id);
+        CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+            text,
+            content='message_text', content_rowid='rowid', tokenize='porter unicode61'
+        );
+        CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON message_text BEGIN
+            INSERT INTO messages_fts(rowid, text) VALUES (new.rowid, new.text);
+        END;
+        CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON message_text BEGIN
+            INSERT INTO messages_fts(messages_fts, rowid, text) VALUES ('delete', old.rowid, old.text);
+        END;
+        ",
+    )?;
+
+    if facts_new {
+        conn.execute("INSERT INTO facts_fts(facts_fts) VALUES ('rebuild')", [])?;
+    }
+    if episodes_new {
+        conn.execute(
+            "INSERT INTO episodes_fts(episodes_fts) VALUES ('rebuild')",
+            [],
+        )?;
+    }
+    if messages_new {
+        backfill_messages(conn)?;
+    }
+    Ok(())
+}
+
+/// Index the text of sessions persisted before `message_text` existed.
+fn backfill_messages(conn: &Connection) -> rusqlite::Result<()> {
+    let blobs: Vec<String> = conn
+        .prepare("SELECT messages_json FROM session_messages")?
+        .query_map([], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+    for json in blobs {
+        if let Ok(messages) = serde_json::from_str::<Vec<claw_core::Message>>(&json) {
+            index_messages(conn, &messages)?;
+        }
+    }
+    Ok(())
+}
+
+/// Add user and assistant text from `messages` to the message index. Messages
+/// already indexed (by id) are skipped.
+pub(crate) fn index_messages(
+    conn: &Connection,
+    messages: &[claw_core::Message],
+) -> rusqlite::Result<usize> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT OR IGNORE INTO message_text (id, session_id, role, text, timestamp)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    let mut added = 0;
+    for message in messages {
+        let role = match message.role {
+            claw_core::Role::User => "user",
+            claw_core::Role::Assistant => "assistant",
+            _ => continue,
+        };
+        let text = message.text_content();
+        if text.trim().is_empty() {
+            continue;
+        }
+        added += stmt.execute(rusqlite::params![
+            message.id.to_string(),
+            message.session_id.to_string(),
+            role,
+            text,
+            message.timestamp.to_rfc3339(),
+        ])?;
+    }
+    Ok(added)
+}
+
+/// Turn free text into an FTS5 query: each word is quoted (so punctuation and
+/// operators in user input can't break the syntax) and the words are OR-ed,
+/// leaving relevance to BM25. `None` if there are no searchable words.
+pub fn match_query(text: &str) -> Option<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 2)
+    {
+        let term = format!("\"{}\"", word.to_lowercase());
+        if !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+    (!terms.is_empty()).then(|| terms.join(" OR "))
+}
+
+/// `scopes` as a JSON array for `json_each` — `None` (every scope) stays
+/// NULL.
+fn scope_list(scopes: Option<&[String]>) -> Option<String> {
+    scopes.map(|s| serde_json::to_string(s).unwrap_or_else(|_| "[]".into()))
+}
+
+/// BM25-ranked facts; key matches weigh most, category least. `scopes`
+/// limits the search to facts in those scopes.
+pub(crate) fn search_facts(
+    conn: &Connection,
+    query: &str,
+    scopes: Option<&[String]>,
+    limit: usize,
+) -> rusqlite::Result<Vec<FactHit>> {
+    let Some(query) = match_query(query) else {
+        return Ok(vec![]);
+    };
+    let mut stmt = conn.prepare_cached(
+        "SELECT f.scope, f.category, f.key, f.value, bm25(facts_fts, 0.5, 2.0, 1.0) AS rank
+         FROM facts_fts JOIN facts f ON f.rowid = facts_fts.rowid
+         WHERE facts_fts MATCH ?1
+           AND (?2 IS NULL OR f.scope IN (SELECT value FROM json_each(?2)))
+         ORDER BY rank LIMIT ?3",
+    )?;
+    stmt.query_map(
+        rusqlite::params![query, scope_list(scopes), limit as i64],
+        |row| {
+            Ok(FactHit {
+                scope: row.get(0)?,
+                category: row.get(1)?,
+                key: row.get(2)?,
+                value: row.get(3)?,
+                score: -row.get::<_, f64>(4)?,
+            })
+        },
+    )?
+    .collect()
+}
+
+/// BM25-ranked episodes across the whole history (not just the recent
+/// cache), optionally only those in `scopes`.
+pub(crate) fn search_episodes(
+    conn: &Connection,
+    query: &str,
+    scopes: Option<&[String]>,
+    limit: usize,
+) -> rusqlite::Result<Vec<EpisodeHit>> {
+    let Some(query) = match_query(query) else {
+        return Ok(vec![]);
+    };
+    let mut stmt = conn.prepare_cached(
+        "SELECT e.id, e.session_id, e.summary, e.outcome, e.tags, e.created_at, e.updated_at,
+                bm25(episodes_fts, 2.0, 1.0, 1.0) AS rank, e.scope
+         FROM episodes_fts JOIN episodes e ON e.rowid = episodes_fts.rowid
+         WHERE episodes_fts MATCH ?1
+           AND (?2 IS NULL OR e.scope IN (SELECT value FROM json_each(?2)))
+         ORDER BY rank LIMIT ?3",
+    )?;
+    stmt.query_map(
+        rusqlite::params![query, scope_list(scopes), limit as i64],
+        |row| {
+            let parse_time = |s: String| {
+                chrono::DateTime::parse_from_rfc3339(&s)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now())
+            };
+            let episode = Episode {
+                id: row
+                    .get::<_, String>(0)?
+                    .parse()
+                    .unwrap_or_else(|_| Uuid::nil()),
+                session_id: row
+                    .get::<_, String>(1)?
+                    .parse()
+                    .unwrap_or_else(|_| Uuid::nil()),
+                scope: row.get(8)?,
+                summary: row.get(2)?,
+                outcome: row.get(3)?,
+                tags: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or_default(),
+                created_at: parse_time(row.get(5)?),
+                updated_at: parse_time(row.get(6)?),
+            };
+            Ok(EpisodeHit {
+                episode,
+                score: -row.get::<_, f64>(7)?,
+            })
+        },
+    )?
+    .collect()
+}
+
+/// BM25-ranked session messages, optionally limited to some sessions.
+pub(crate) fn search_messages(
+    conn: &Connection,
+    query: &str,
+    sessions: Option<&[String]>,
+    limit: usize,
+) -> rusqlite::Result<Vec<MessageHit>> {
+    let Some(query) = match_query(query) else {
+        return Ok(vec![]);
+    };
+    if sessions.is_some_and(|s| s.is_empty()) {
+        return Ok(vec![]);
+    }
+    let sessions = sessions.map(|s| serde_json::Value::from(s).to_string());
+    let mut stmt = conn.prepare_cached(
+        "SELECT m.id, m.session_id, m.role, snippet(messages_fts, 0, '[', ']', '…', 24),
+                m.timestamp, bm25(messages_fts) AS rank
+         FROM messages_fts JOIN message_text m ON m.rowid = messages_fts.rowid
+         WHERE messages_fts MATCH ?1
+           AND (?2 IS NULL OR m.session_id IN (SELECT value FROM json_each(?2)))
+         ORDER BY rank LIMIT ?3",
+    )?;
+    stmt.query_map(rusqlite::params![query, sessions, limit as i64], |row| {
+        Ok(MessageHit {
+            message_id: row.get(0)?,
+            session_id: row.get(1)?,
+            role: row.get(2)?,
+            snippet: row.get(3)?,
+            timestamp: row.get(4)?,
+            score: -row.get::<_, f64>(5)?,
+        })
+    })?
+    .collect()
+}
+
+/// Merge several best-first rankings with reciprocal rank fusion: each item
+/// scores Σ 1/(k + rank) over the lists it appears in. Scores from different
+/// retrievers (BM25, cosine) aren't comparable, ranks are.
+pub fn reciprocal_rank_fusion<K: Eq + Hash + Clone>(rankings: &[Vec<K>]) -> Vec<(K, f64)> {
+    let mut order: Vec<K> = Vec::new();
+    let mut scores: HashMap<K, f64> = HashMap::new();
+    for ranking in rankings {
+        for (rank, item) in ranking.iter().enumerate() {
+            let score = scores.entry(item.clone()).or_insert_with(|| {
+                order.push(item.clone());
+                0.0
+            });
+            *score += 1.0 / (RRF_K + rank as f64 + 1.0);
+        }
+    }
+    let mut fused: Vec<(K, f64)> = order
+        .into_iter()
+        .map(|k| {
+            let score = scores[&k];
+            (k, score)
+        })
+        .collect();
+    fused.sort_by(|a, b| b.1.total_cmp(&a.1));
+    fused
+}
@@ -0,0 +1,569 @@
+//! Knowledge graph — entities (people, servers, services, projects, repos …)
+//! and typed relations between them.
+//!
+//! An entity is identified by `kind:name`, both normalised (lowercase,
+//! single spaces), e.g. `server:web-01` or `person:alice smith`. The id
+//! depends only on what the entity is, so the same entity extracted in two
+//! sessions — or on two mesh peers — is one node. Relations are directed
+//! `subject —predicate→ object` triples with a confidence and the source they
+//! came from (`session:<id>`, `mesh:<peer>`, …); restating a relation
+//! refreshes it rather than adding a duplicate.
+//!
+//! Extraction is up to the caller (this crate has no LLM access): it hands
+//! entities to [`KnowledgeGraph::upsert_entity`] and triples to
+//! [`KnowledgeGraph::relate`].
+
+use chrono::Utc;
+use parking_lot::Mutex;
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+use claw_core::{ClawError, Result};
+
+/// Deepest traversal [`KnowledgeGraph::neighbors`] allows.
+pub const MAX_DEPTH: usize = 3;
+
+/// A node in the graph.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Entity {
+    /// `kind:name`, normalised.
+    pub id: String,
+    pub kind: String,
+    /// Display name, as last written.
+    pub name: String,
+    pub attributes: BTreeMap<String, String>,
+    pub source: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A directed, typed edge: `subject —predicate→ object`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Relation {
+    pub subject: String,
+    pub predicate: String,
+    pub object: String,
+    pub confidence: f64,
+    /// Who stated it last, e.g. `session:<id>` or `mesh:<peer>`.
+    pub source: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl std::fmt::Display for Relation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} —{}→ {}", self.subject, self.predicate, self.object)
+    }
+}
+
+/// Which edges of a node to follow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    /// Edges where the node is the subject.
+    Outgoing,
+    /// Edges where the node is the object.
+    Incoming,
+    #[default]
+    Both,
+}
+
+impl std::str::FromStr for Direction {
+    type Err = ClawError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "out" | "outgoing" => Ok(Self::Outgoing),
+            "in" | "incoming" => Ok(Self::Incoming),
+            "both" | "any" => Ok(Self::Both),
+            other => Err(ClawError::Memory(format!(
+                "unknown direction '{other}' (expected out, in or both)"
+            ))),
+        }
+    }
+}
+
+/// Filter for [`KnowledgeGraph::relations`]. Entities are given by id or by
+/// name (matching every kind with that name); `None` matches anything.
+#[derive(Debug, Clone, Default)]
+pub struct RelationPattern {
+    pub subject: Option<String>,
+    pub predicate: Option<String>,
+    pub object: Option<String>,
+    pub subject_kind: Option<String>,
+    pub object_kind: Option<String>,
+}
+
+/// What [`KnowledgeGraph::neighbors`] reached from an entity.
+#[derive(Debug, Clone, Serialize)]
+pub struct Neighborhood {
+    pub root: Entity,
+    /// Every other entity reached, nearest first.
+    pub entities: Vec<Entity>,
+    /// Edges traversed, with the hop (1-based) they were found at.
+    pub relations: Vec<(usize, Relation)>,
+}
+
+/// Lowercase, trim and collapse whitespace — the form names and kinds take
+/// in entity ids.
+pub fn normalize(s: &str) -> String {
+    s.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// A predicate or kind as an identifier: `Runs On` → `runs_on`.
+pub fn normalize_label(s: &str) -> String {
+    normalize(s)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// The id of the entity of `kind` called `name`.
+pub fn entity_id(kind: &str, name: &str) -> String {
+    format!("{}:{}", normalize_label(kind), normalize(name))
+}
+
+fn db_err(e: rusqlite::Error) -> ClawError {
+    ClawError::Memory(e.to_string())
+}
+
+/// Create the entity and relation tables.
+pub(crate) fn create_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS graph_entities (
+            id TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            name TEXT NOT NULL,
+            name_key TEXT NOT NULL,
+            attributes TEXT NOT NULL DEFAULT '{}',
+            source TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_graph_entities_name ON graph_entities(name_key);
+        CREATE INDEX IF NOT EXISTS idx_graph_entities_kind ON graph_entities(kind);
+
+        CREATE TABLE IF NOT EXISTS graph_relations (
+            subject TEXT NOT NULL,
+            predicate TEXT NOT NULL,
+            object TEXT NOT NULL,
+            confidence REAL NOT NULL DEFAULT 1.0,
+            source TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE(subject, predicate, object)
+        );
+        CREATE INDEX IF NOT EXISTS idx_graph_relations_object ON graph_relations(object);
+        CREATE INDEX IF NOT EXISTS idx_graph_relations_predicate ON graph_relations(predicate);
+        ",
+    )
+}
+
+const ENTITY_COLUMNS: &str = "id, kind, name, attributes, source, created_at, updated_at";
+
+const RELATION_COLUMNS: &str =
+    "r.subject, r.predicate, r.object, r.confidence, r.source, r.created_at, r.updated_at";
+
+fn entity_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Entity> {
+    let attributes: String = row.get(3)?;
+    Ok(Entity {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        name: row.get(2)?,
+        attributes: serde_json::from_str(&attributes).unwrap_or_default(),
+        source: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+fn relation_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Relation> {
+    Ok(Relation {
+        subject: row.get(0)?,
+        predicate: row.get(1)?,
+        object: row.get(2)?,
+        confidence: row.get(3)?,
+        source: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+/// Entities and the relations between them.
+pub struct KnowledgeGraph {
+    db: Arc<Mutex<Connection>>,
+}
+
+impl KnowledgeGraph {
+    /// The tables are created by the schema migrations.
+    pub(crate) fn open(db: Arc<Mutex<Connection>>) -> Self {
+        Self { db }
+    }
+
+    /// Add an entity, or update it: the display name and source are
+    /// replaced and `attributes` merged over the stored ones.
+    pub fn upsert_entity(
+        &self,
+        kind: &str,
+        name: &str,
+        attributes: &BTreeMap<String, String>,
+        source: Option<&str>,
+    ) -> Result<Entity> {
+        let kind = normalize_label(kind);
+        let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        if kind.is_empty() || name.is_empty() {
+            return Err(ClawError::Memory(
+                "an entity needs a kind and a name".into(),
+            ));
+        }
+        let id = entity_id(&kind, &name);
+        let conn = self.db.lock();
+        let mut merged: BTreeMap<String, String> = conn
+            .query_row(
+                "SELECT attributes FROM graph_entities WHERE id = ?1",
+                [&id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(db_err)?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        merged.extend(
+            attributes
+                .iter()
+                .filter(|(k, _)| !k.trim().is_empty())
+                .map(|(k, v)| (normalize_label(k), v.trim().to_string())),
+        );
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO graph_entities
+                (id, kind, name, name_key, attributes, source, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)
+             ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                attributes = excluded.attributes,
+                source = COALESCE(excluded.source, graph_entities.source),
+                updated_at = excluded.updated_at",
+            rusqlite::params![
+                id,
+                kind,
+                name,
+                normalize(&name),
+                serde_json::to_string(&merged).unwrap_or_else(|_| "{}".into()),
+                source,
+                now
+            ],
+        )
+        .map_err(db_err)?;
+        conn.query_row(
+            &format!("SELECT {ENTITY_COLUMNS} FROM graph_entities WHERE id = ?1"),
+            [&id],
+            entity_from_row,
+        )
+        .map_err(db_err)
+    }
+
+    /// Record `subject —predicate→ object` between two stored entities
+    /// (given by id). Restating a relation updates its confidence, source and
+    /// timestamp.
+    pub fn relate(
+        &self,
+        subject: &str,
+        predicate: &str,
+        object: &str,
+        confidence: f64,
+        source: Option<&str>,
+    ) -> Result<Relation> {
+        let predicate = normalize_label(predicate);
+        if predicate.is_empty() {
+            return Err(ClawError::Memory("a relation needs a predicate".into()));
+        }
+        let conn = self.db.lock();
+        for id in [subject, object] {
+            let exists: bool = conn
+                .query_row(
+                    "SELECT EXISTS(SELECT 1 FROM graph_entities WHERE id = ?1)",
+                    [id],
+                    |row| row.get(0),
+                )
+                .map_err(db_err)?;
+            if !exists {
+                return Err(ClawError::Memory(format!("unknown entity '{id}'")));
+            }
+        }
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO graph_relations
+                (subject, predicate, object, confidence, source, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+             ON CONFLICT(subject, predicate, object) DO UPDATE SET
+                confidence = excluded.confidence,
+                source = COALESCE(excluded.source, graph_relations.source),
+                updated_at = excluded.updated_at",
+            rusqlite::params![
+                subject,
+                predicate,
+                object,
+                confidence.clamp(0.0, 1.0),
+                source,
+                now
+            ],
+        )
+        .map_err(db_err)?;
+        conn.query_row(
+            &format!(
+                "SELECT {RELATION_COLUMNS} FROM graph_relations r
+                 WHERE subject = ?1 AND predicate = ?2 AND object = ?3"
+            ),
+            [subject, &predicate, object],
+            relation_from_row,
+        )
+        .map_err(db_err)
+    }
+
+    /// The entity with this id.
+    pub fn entity(&self, id: &str) -> Result<Option<Entity>> {
+        self.db
+            .lock()
+            .query_row(
+                &format!("SELECT {ENTITY_COLUMNS} FROM graph_entities WHERE id = ?1"),
+                [normalize(id)],
+                entity_from_row,
+            )
+            .optional()
+            .map_err(db_err)
+    }
+
+    /// Entities a reference names: the one with that id, else every entity
+    /// with that name.
+    pub fn resolve(&self, reference: &str) -> Result<Vec<Entity>> {
+        if let Some(entity) = self.entity(reference)? {
+            return Ok(vec![entity]);
+        }
+        let conn = self.db.lock();
+        let mut stmt = conn
+            .prepare_cached(&format!(
+                "SELECT {ENTITY_COLUMNS} FROM graph_entities WHERE name_key = ?1 ORDER BY id"
+            ))
+            .map_err(db_err)?;
+        stmt.query_map([normalize(reference)], entity_from_row)
+            .map_err(db_err)?
+            .collect::<rusqlite::Result<_>>()
+            .map_err(db_err)
+    }
+
+    /// Entities whose id or name contains `query`, optionally of one kind,
+    /// most recently updated first.
+    pub fn find_entities(
+        &self,
+        query: &str,
+        kind: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Entity>> {
+        let conn = self.db.lock();
+        let mut stmt = conn
+            .prepare_cached(&format!(
+                "SELECT {ENTITY_COLUMNS} FROM graph_entities
+                 WHERE instr(id, ?1) > 0 AND (?2 IS NULL OR kind = ?2)
+                 ORDER BY updated_at DESC LIMIT ?3"
+            ))
+            .map_err(db_err)?;
+        stmt.query_map(
+            rusqlite::params![normalize(query), kind.map(normalize_label), limit as i64],
+            entity_from_row,
+        )
+        .map_err(db_err)?
+        .collect::<rusqlite::Result<_>>()
+        .map_err(db_err)
+    }
+
+    /// Entities whose name appears in `text` (names of 3+ characters),
+    /// longest name first.
+    pub fn mentioned_in(&self, text: &str, limit: usize) -> Result<Vec<Entity>> {
+        let conn = self.db.lock();
+        let mut stmt = conn
+            .prepare_cached(&format!(
+                "SELECT {ENTITY_COLUMNS} FROM graph_entities
+                 WHERE length(name_key) >= 3 AND instr(?1, name_key) > 0
+                 ORDER BY length(name_key) DESC, updated_at DESC LIMIT ?2"
+            ))
+            .map_err(db_err)?;
+        stmt.query_map(
+            rusqlite::params![normalize(text), limit as i64],
+            entity_from_row,
+        )
+        .map_err(db_err)?
+        .collect::<rusqlite::Result<_>>()
+        .map_err(db_err)
+    }
+
+    /// Relations matching a pattern, most recently updated first.
+    pub fn relations(&self, pattern: &RelationPattern, limit: usize) -> Result<Vec<Relation>> {
+        let conn = self.db.lock();
+        let mut stmt = conn
+            .prepare_cached(&format!(
+                "SELECT {RELATION_COLUMNS} FROM graph_relations r
+                 JOIN graph_entities s ON s.id = r.subject
+                 JOIN graph_entities o ON o.id = r.object
+                 WHERE (?1 IS NULL OR r.subject = ?1 OR s.name_key = ?1)
+                   AND (?2 IS NULL OR r.predicate = ?2)
+                   AND (?3 IS NULL OR r.object = ?3 OR o.name_key = ?3)
+                   AND (?4 IS NULL OR s.kind = ?4)
+                   AND (?5 IS NULL OR o.kind = ?5)
+                 ORDER BY r.updated_at DESC LIMIT ?6"
+            ))
+            .map_err(db_err)?;
+        stmt.query_map(
+            rusqlite::params![
+                pattern.subject.as_deref().map(normalize),
+                pattern.predicate.as_deref().map(normalize_label),
+                pattern.object.as_deref().map(normalize),
+                pattern.subject_kind.as_deref().map(normalize_label),
+                pattern.object_kind.as_deref().map(normalize_label),
+                limit as i64
+            ],
+            relation_from_row,
+        )
+        .map_err(db_err)?
+        .collect::<rusqlite::Result<_>>()
+        .map_err(db_err)
+    }
+
+    /// Edges touching one entity.
+    fn edges(
+        conn: &Connection,
+        id: &str,
+        direction: Direction,
+        predicate: Option<&str>,
+    ) -> rusqlite::Result<Vec<Relation>> {
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {RELATION_COLUMNS} FROM graph_relations r
+             WHERE ((?2 AND r.subject = ?1) OR (?3 AND r.object = ?1))
+               AND (?4 IS NULL OR r.predicate = ?4)
+             ORDER BY r.predicate, r.subject, r.object"
+        ))?;
+        stmt.query_map(
+            rusqlite::params![
+                id,
+                direction != Direction::Incoming,
+                direction != Direction::Outgoing,
+                predicate
+            ],
+            relation_from_row,
+        )?
+        .collect()
+    }
+
+    /// Everything within `depth` hops (1..=[`MAX_DEPTH`]) of an entity,
+    /// following edges in `direction`, optionally only one predicate. Stops
+    /// adding edges after `limit`.
+    pub fn neighbors(
+        &self,
+        id: &str,
+        depth: usize,
+        direction: Direction,
+        predicate: Option<&str>,
+        limit: usize,
+    ) -> Result<Option<Neighborhood>> {
+        let Some(root) = self.entity(id)? else {
+            return Ok(None);
+        };
+        let predicate = predicate.map(normalize_label);
+        let conn = self.db.lock();
+        let mut seen: HashSet<String> = HashSet::from([root.id.clone()]);
+        let mut frontier = vec![root.id.clone()];
+        let mut reached = Vec::new();
+        let mut relations = Vec::new();
+        let mut edges_seen = HashSet::new();
+
+        'hops: for hop in 1..=depth.clamp(1, MAX_DEPTH) {
+            let mut next = Vec::new();
+            for id in &frontier {
+                for relation in
+                    Self::edges(&conn, id, direction, predicate.as_deref()).map_err(db_err)?
+                {
+                    let key = (
+                        relation.subject.clone(),
+                        relation.predicate.clone(),
+                        relation.object.clone(),
+                    );
+                    if !edges_seen.insert(key) {
+                        continue;
+                    }
+                    if relations.len() >= limit {
+                        break 'hops;
+                    }
+                    let other = if relation.subject == *id {
+                        &relation.object
+                    } else {
+                        &relation.subject
+                    };
+                    if seen.insert(other.clone()) {
+                        next.push(other.clone());
+                    }
+                    relations.push((hop, relation));
+                }
+            }
+            reached.extend(next.iter().cloned());
+            frontier = next;
+            if frontier.is_empty() {
+                break;
+            }
+        }
+
+        let mut stmt = conn
+            .prepare_cached(&format!(
+                "SELECT {ENTITY_COLUMNS} FROM graph_entities WHERE id = ?1"
+            ))
+            .map_err(db_err)?;
+        let mut entities = Vec::with_capacity(reached.len());
+        for id in &reached {
+            if let Some(entity) = stmt
+                .query_row([id], entity_from_row)
+                .optional()
+                .map_err(db_err)?
+            {
+                entities.push(entity);
+            }
+        }
+        Ok(Some(Neighborhood {
+            root,
+            entities,
+            relations,
+        }))
+    }
+
+    /// Delete an entity and every relation touching it.
+    pub fn delete_entity(&self, id: &str) -> Result<bool> {
+        let id = normalize(id);
+        let conn = self.db.lock();
+        conn.execute(
+            "DELETE FROM graph_relations WHERE subject = ?1 OR object = ?1",
+            [&id],
+        )
+        .map_err(db_err)?;
+        let deleted = conn
+            .execute("DELETE FROM graph_entities WHERE id = ?1", [&id])
+            .map_err(db_err)?;
+        Ok(deleted > 0)
+    }
+
+    /// Number of entities and relations.
+    pub fn counts(&self) -> Result<(usize, usize)> {
+        let conn = self.db.lock();
+        let count = |table: &str| -> Result<usize> {
+            conn.query_row(&format!("SELECT count(*) FROM {table}"), [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .map(|n| n as usize)
+            .map_err(db_err)
+        };
+        Ok((count("graph_entities")?, count("graph_relations")?))
+    }
+}
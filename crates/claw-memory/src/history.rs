@@ -0,0 +1,335 @@
+//! Fact history and contradictions.
+//!
+//! Every value a fact takes is kept in `fact_history`, numbered per
+//! scope/category/key. Triggers on `facts` add a version whenever a fact is
+//! inserted or its value changes, so every write path — including import —
+//! is covered. When a new value conflicts with the one it replaces, a
+//! [`FactConflict`] is opened for the agent or user to resolve. Further flips
+//! of the same fact update that open conflict rather than piling up new ones.
+
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::semantic::Fact;
+
+/// One stored value of a fact.
+#[derive(Debug, Clone, Serialize)]
+pub struct FactVersion {
+    pub version: u32,
+    pub value: String,
+    pub confidence: Option<f64>,
+    /// Who wrote it, e.g. `tool:memory_store`, `session:<id>`, `mesh:<peer>`.
+    pub source: Option<String>,
+    pub recorded_at: String,
+}
+
+/// Two values of a fact that can't both be true.
+#[derive(Debug, Clone, Serialize)]
+pub struct FactConflict {
+    pub id: String,
+    pub scope: String,
+    pub category: String,
+    pub key: String,
+    /// The value that was replaced.
+    pub previous_value: String,
+    pub previous_source: Option<String>,
+    /// The value that replaced it (and is currently stored).
+    pub value: String,
+    pub source: Option<String>,
+    /// Why the values were judged to conflict.
+    pub reason: Option<String>,
+    /// How many times the fact has flipped while the conflict was open.
+    pub occurrences: u32,
+    /// `open` or `resolved`.
+    pub status: String,
+    /// The value kept on resolution.
+    pub resolution: Option<String>,
+    pub detected_at: String,
+    pub resolved_at: Option<String>,
+}
+
+impl FactConflict {
+    pub fn is_open(&self) -> bool {
+        self.status == "open"
+    }
+}
+
+/// How a new value for a fact relates to the stored one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueChange {
+    /// Same value, up to case, whitespace and trailing punctuation.
+    Same,
+    /// One value contains the other — more or less detail, not a conflict.
+    Refined,
+    /// A different value: possibly a contradiction.
+    Changed,
+}
+
+/// Compare a fact's stored value with a new one.
+pub fn classify(previous: &str, new: &str) -> ValueChange {
+    let normalize = |s: &str| {
+        s.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+            .trim_end_matches(['.', '!', ';', ','])
+            .to_string()
+    };
+    let (previous, new) = (normalize(previous), normalize(new));
+    if previous == new {
+        ValueChange::Same
+    } else if previous.is_empty() || new.is_empty() {
+        ValueChange::Changed
+    } else if previous.contains(&new) || new.contains(&previous) {
+        ValueChange::Refined
+    } else {
+        ValueChange::Changed
+    }
+}
+
+/// Decide, without a model, whether `new` replacing `previous` is a
+/// contradiction rather than an update: a changed value from a different
+/// source, or a flip back to a value the fact held before the current one.
+/// `history` is the fact's history before `new` is stored. Returns why.
+pub fn detect_conflict(previous: &Fact, new: &Fact, history: &[FactVersion]) -> Option<String> {
+    if classify(&previous.value, &new.value) != ValueChange::Changed {
+        return None;
+    }
+    let reverted = history
+        .iter()
+        .rev()
+        .skip(1)
+        .any(|v| classify(&v.value, &new.value) == ValueChange::Same);
+    if reverted {
+        return Some("flips back to an earlier value".into());
+    }
+    match (&previous.source, &new.source) {
+        (Some(before), Some(now)) if before != now => Some(format!("{before} and {now} disagree")),
+        _ => None,
+    }
+}
+
+/// Create the history and conflict tables and the triggers recording
+/// versions, seeding version 1 from the facts already stored.
+pub(crate) fn create_tables(conn: &Connection) -> rusqlite::Result<()> {
+    // Skips the write when the value is already the latest version, so
+    // rewriting a fact unchanged (or importing a fact whose history came
+    // with it) doesn't add duplicates.
+    let record = "
+        INSERT INTO fact_history (category, key, version, value, confidence, source, recorded_at)
+        SELECT new.category, new.key,
+               (SELECT COALESCE(MAX(version), 0) + 1 FROM fact_history
+                WHERE category = new.category AND key = new.key),
+               new.value, new.confidence, new.source, new.updated_at
+        WHERE (SELECT value FROM fact_history
+               WHERE category = new.category AND key = new.key
+               ORDER BY version DESC LIMIT 1) IS NOT new.value;";
+    conn.execute_batch(&format!(
+        "
+        CREATE TABLE IF NOT EXISTS fact_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            category TEXT NOT NULL,
+            key TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            value TEXT NOT NULL,
+            confidence REAL,
+            source TEXT,
+            recorded_at TEXT NOT NULL,
+            UNIQUE(category, key, version)
+        );
+
+        CREATE TABLE IF NOT EXISTS fact_conflicts (
+            id TEXT PRIMARY KEY,
+            category TEXT NOT NULL,
+            key TEXT NOT NULL,
+            previous_value TEXT NOT NULL,
+            previous_source TEXT,
+            value TEXT NOT NULL,
+            source TEXT,
+            reason TEXT,
+            occurrences INTEGER NOT NULL DEFAULT 1,
+            status TEXT NOT NULL DEFAULT 'open',
+            resolution TEXT,
+            detected_at TEXT NOT NULL,
+            resolved_at TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_fact_conflicts_status ON fact_conflicts(status);
+
+        CREATE TRIGGER IF NOT EXISTS fact_history_insert AFTER INSERT ON facts BEGIN
+            {record}
+        END;
+        CREATE TRIGGER IF NOT EXISTS fact_history_update AFTER UPDATE OF value ON facts BEGIN
+            {record}
+        END;
+
+        INSERT OR IGNORE INTO fact_history (category, key, version, value, confidence, source, recorded_at)
+        SELECT category, key, 1, value, confidence, source, updated_at FROM facts;
+        "
+    ))
+}
+
+/// Replace the version-recording triggers with ones that number versions
+/// per scope, for the scoped `facts` table.
+pub(crate) fn create_scoped_triggers(conn: &Connection) -> rusqlite::Result<()> {
+    let record = "
+        INSERT INTO fact_history (scope, category, key, version, value, confidence, source, recorded_at)
+        SELECT new.scope, new.category, new.key,
+               (SELECT COALESCE(MAX(version), 0) + 1 FROM fact_history
+                WHERE scope = new.scope AND category = new.category AND key = new.key),
+               new.value, new.confidence, new.source, new.updated_at
+        WHERE (SELECT value FROM fact_history
+               WHERE scope = new.scope AND category = new.category AND key = new.key
+               ORDER BY version DESC LIMIT 1) IS NOT new.value;";
+    conn.execute_batch(&format!(
+        "
+        DROP TRIGGER IF EXISTS fact_history_insert;
+        DROP TRIGGER IF EXISTS fact_history_update;
+        CREATE TRIGGER fact_history_insert AFTER INSERT ON facts BEGIN
+            {record}
+        END;
+        CREATE TRIGGER fact_history_update AFTER UPDATE OF value ON facts BEGIN
+            {record}
+        END;
+        "
+    ))
+}
+
+/// Every recorded value of a fact, oldest first.
+pub fn history(
+    conn: &Connection,
+    scope: &str,
+    category: &str,
+    key: &str,
+) -> rusqlite::Result<Vec<FactVersion>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT version, value, confidence, source, recorded_at FROM fact_history
+         WHERE scope = ?1 AND category = ?2 AND key = ?3 ORDER BY version",
+    )?;
+    stmt.query_map([scope, category, key], |row| {
+        Ok(FactVersion {
+            version: row.get(0)?,
+            value: row.get(1)?,
+            confidence: row.get(2)?,
+            source: row.get(3)?,
+            recorded_at: row.get(4)?,
+        })
+    })?
+    .collect()
+}
+
+const CONFLICT_COLUMNS: &str = "id, scope, category, key, previous_value, previous_source, value,
+     source, reason, occurrences, status, resolution, detected_at, resolved_at";
+
+fn conflict_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<FactConflict> {
+    Ok(FactConflict {
+        id: row.get(0)?,
+        scope: row.get(1)?,
+        category: row.get(2)?,
+        key: row.get(3)?,
+        previous_value: row.get(4)?,
+        previous_source: row.get(5)?,
+        value: row.get(6)?,
+        source: row.get(7)?,
+        reason: row.get(8)?,
+        occurrences: row.get(9)?,
+        status: row.get(10)?,
+        resolution: row.get(11)?,
+        detected_at: row.get(12)?,
+        resolved_at: row.get(13)?,
+    })
+}
+
+/// Record that `value` replaced a conflicting `previous_value`. If the fact
+/// already has an open conflict, that one is updated instead.
+#[allow(clippy::too_many_arguments)]
+pub fn open_conflict(
+    conn: &Connection,
+    scope: &str,
+    category: &str,
+    key: &str,
+    previous_value: &str,
+    previous_source: Option<&str>,
+    value: &str,
+    source: Option<&str>,
+    reason: Option<&str>,
+) -> rusqlite::Result<FactConflict> {
+    let open: Option<String> = conn
+        .query_row(
+            "SELECT id FROM fact_conflicts
+             WHERE scope = ?1 AND category = ?2 AND key = ?3 AND status = 'open'",
+            [scope, category, key],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let id = match open {
+        Some(id) => {
+            conn.execute(
+                "UPDATE fact_conflicts SET previous_value = ?2, previous_source = ?3, value = ?4,
+                    source = ?5, reason = COALESCE(?6, reason), occurrences = occurrences + 1
+                 WHERE id = ?1",
+                rusqlite::params![id, previous_value, previous_source, value, source, reason],
+            )?;
+            id
+        }
+        None => {
+            let id = Uuid::new_v4().to_string();
+            conn.execute(
+                "INSERT INTO fact_conflicts
+                    (id, scope, category, key, previous_value, previous_source, value, source, reason,
+                     detected_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                rusqlite::params![
+                    id,
+                    scope,
+                    category,
+                    key,
+                    previous_value,
+                    previous_source,
+                    value,
+                    source,
+                    reason,
+                    Utc::now().to_rfc3339()
+                ],
+            )?;
+            id
+        }
+    };
+    conflict(conn, &id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+}
+
+/// Look up a conflict by id or by an unambiguous id prefix.
+pub fn conflict(conn: &Connection, id: &str) -> rusqlite::Result<Option<FactConflict>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {CONFLICT_COLUMNS} FROM fact_conflicts
+         WHERE id = ?1 OR (length(?1) >= 4 AND substr(id, 1, length(?1)) = ?1) LIMIT 2"
+    ))?;
+    let mut matches: Vec<FactConflict> = stmt
+        .query_map([id], conflict_from_row)?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(match matches.len() {
+        1 => matches.pop(),
+        _ => matches.into_iter().find(|c| c.id == id),
+    })
+}
+
+/// Conflicts, newest first; resolved ones only if `include_resolved`.
+pub fn conflicts(conn: &Connection, include_resolved: bool) -> rusqlite::Result<Vec<FactConflict>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {CONFLICT_COLUMNS} FROM fact_conflicts
+         WHERE ?1 OR status = 'open' ORDER BY detected_at DESC"
+    ))?;
+    stmt.query_map([include_resolved], conflict_from_row)?
+        .collect()
+}
+
+/// Close a conflict, recording the value that was kept.
+pub fn mark_resolved(conn: &Connection, id: &str, resolution: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE fact_conflicts SET status = 'resolved', resolution = ?2, resolved_at = ?3
+         WHERE id = ?1",
+        rusqlite::params![id, resolution, Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,496 @@
+//! Hierarchical Navigable Small World graph — approximate nearest-neighbour
+//! search over fact embeddings (Malkov & Yashunin, 2016).
+//!
+//! Vectors are L2-normalised on insert so similarity is a dot product.
+//! Removal tombstones a node (it still routes searches but is never
+//! returned); the graph is rebuilt once tombstones outnumber live nodes.
+//! Level assignment uses a seeded PRNG, so the same insert sequence always
+//! builds the same graph.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Neighbours kept per node on upper layers (layer 0 keeps twice as many).
+const M: usize = 16;
+/// Candidate list size while inserting.
+const EF_CONSTRUCTION: usize = 100;
+/// Rebuild once at least this many tombstones outnumber live nodes.
+const MIN_TOMBSTONES_FOR_REBUILD: usize = 64;
+/// Serialization format tag and version.
+const MAGIC: &[u8; 4] = b"HNSW";
+const VERSION: u8 = 1;
+
+struct Node {
+    id: String,
+    vector: Vec<f32>,
+    /// Neighbour lists, one per layer the node lives on.
+    links: Vec<Vec<u32>>,
+    deleted: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Candidate {
+    sim: f32,
+    node: u32,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sim
+            .total_cmp(&other.sim)
+            .then(self.node.cmp(&other.node))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// An HNSW index over fixed-size vectors, keyed by string id.
+pub struct HnswIndex {
+    dims: usize,
+    nodes: Vec<Node>,
+    ids: HashMap<String, u32>,
+    entry: Option<u32>,
+    max_level: usize,
+    rng: u64,
+}
+
+impl HnswIndex {
+    pub fn new(dims: usize) -> Self {
+        Self {
+            dims,
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+            entry: None,
+            max_level: 0,
+            rng: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    /// Vector size this index accepts.
+    pub fn dims(&self) -> usize {
+        self.dims
+    }
+
+    /// Number of live (searchable) vectors.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Whether `id` is indexed with exactly this vector (after normalisation).
+    pub fn contains(&self, id: &str, vector: &[f32]) -> bool {
+        self.ids
+            .get(id)
+            .is_some_and(|&n| self.nodes[n as usize].vector == normalized(vector))
+    }
+
+    /// Ids of all live vectors.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.ids.keys().map(|s| s.as_str())
+    }
+
+    /// Insert or replace the vector for `id`. Returns false (and does
+    /// nothing) if the vector has the wrong size.
+    pub fn insert(&mut self, id: &str, vector: &[f32]) -> bool {
+        if vector.len() != self.dims {
+            return false;
+        }
+        let vector = normalized(vector);
+        if let Some(&existing) = self.ids.get(id) {
+            if self.nodes[existing as usize].vector == vector {
+                return true;
+            }
+            self.remove(id);
+        }
+
+        let level = self.random_level();
+        let idx = self.nodes.len() as u32;
+        self.nodes.push(Node {
+            id: id.to_string(),
+            vector,
+            links: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.ids.insert(id.to_string(), idx);
+
+        let Some(mut entry) = self.entry else {
+            self.entry = Some(idx);
+            self.max_level = level;
+            return true;
+        };
+
+        let query = self.nodes[idx as usize].vector.clone();
+        for layer in (level + 1..=self.max_level).rev() {
+            entry = self.greedy_closest(&query, entry, layer);
+        }
+        let mut entries = vec![entry];
+        for layer in (0..=level.min(self.max_level)).rev() {
+            let candidates = self.search_layer(&query, &entries, EF_CONSTRUCTION, layer);
+            let neighbours = self.select_neighbours(&candidates, M);
+            self.nodes[idx as usize].links[layer] = neighbours.clone();
+            for &n in &neighbours {
+                self.link(n, idx, layer);
+            }
+            entries = candidates.iter().map(|c| c.node).collect();
+        }
+
+        if level > self.max_level {
+            self.max_level = level;
+            self.entry = Some(idx);
+        }
+        true
+    }
+
+    /// Remove `id`. Returns true if it was indexed.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(idx) = self.ids.remove(id) else {
+            return false;
+        };
+        self.nodes[idx as usize].deleted = true;
+        let tombstones = self.nodes.len() - self.ids.len();
+        if tombstones >= MIN_TOMBSTONES_FOR_REBUILD && tombstones > self.ids.len() {
+            self.rebuild();
+        }
+        true
+    }
+
+    /// The `k` most similar live vectors to `query`, best first, as
+    /// (id, cosine similarity). `ef` trades recall for speed (≥ k).
+    pub fn search(&self, query: &[f32], k: usize, ef: usize) -> Vec<(&str, f32)> {
+        let Some(mut entry) = self.entry else {
+            return vec![];
+        };
+        if query.len() != self.dims || k == 0 {
+            return vec![];
+        }
+        let query = normalized(query);
+        for layer in (1..=self.max_level).rev() {
+            entry = self.greedy_closest(&query, entry, layer);
+        }
+        self.search_layer(&query, &[entry], ef.max(k), 0)
+            .into_iter()
+            .filter(|c| !self.nodes[c.node as usize].deleted)
+            .take(k)
+            .map(|c| (self.nodes[c.node as usize].id.as_str(), c.sim))
+            .collect()
+    }
+
+    // ── Graph internals ────────────────────────────────────────
+
+    fn sim(&self, query: &[f32], node: u32) -> f32 {
+        dot(query, &self.nodes[node as usize].vector)
+    }
+
+    /// Geometric level distribution with mean 1/ln(M) (splitmix64 PRNG).
+    fn random_level(&mut self) -> usize {
+        self.rng = self.rng.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        let uniform = ((z >> 11) as f64 + 0.5) / (1u64 << 53) as f64;
+        let level = (-uniform.ln() / (M as f64).ln()).floor() as usize;
+        level.min(16)
+    }
+
+    fn greedy_closest(&self, query: &[f32], mut current: u32, layer: usize) -> u32 {
+        let mut best = self.sim(query, current);
+        loop {
+            let mut improved = false;
+            for &n in self.neighbours(current, layer) {
+                let s = self.sim(query, n);
+                if s > best {
+                    best = s;
+                    current = n;
+                    improved = true;
+                }
+            }
+            if !improved {
+                return current;
+            }
+        }
+    }
+
+    fn neighbours(&self, node: u32, layer: usize) -> &[u32] {
+        self.nodes[node as usize]
+            .links
+            .get(layer)
+            .map_or(&[], |l| l.as_slice())
+    }
+
+    /// Best-first search of one layer; returns up to `ef` candidates, best first.
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entries: &[u32],
+        ef: usize,
+        layer: usize,
+    ) -> Vec<Candidate> {
+        let mut visited: HashSet<u32> = entries.iter().copied().collect();
+        let mut frontier: BinaryHeap<Candidate> = BinaryHeap::new();
+        let mut found: BinaryHeap<std::cmp::Reverse<Candidate>> = BinaryHeap::new();
+        for &e in entries {
+            let c = Candidate {
+                sim: self.sim(query, e),
+                node: e,
+            };
+            frontier.push(c);
+            found.push(std::cmp::Reverse(c));
+        }
+        while found.len() > ef {
+            found.pop();
+        }
+
+        while let Some(current) = frontier.pop() {
+            let worst = found.peek().map_or(f32::NEG_INFINITY, |w| w.0.sim);
+            if current.sim < worst && found.len() >= ef {
+                break;
+            }
+            for &n in self.neighbours(current.node, layer) {
+                if !visited.insert(n) {
+                    continue;
+                }
+                let c = Candidate {
+                    sim: self.sim(query, n),
+                    node: n,
+                };
+                let worst = found.peek().map_or(f32::NEG_INFINITY, |w| w.0.sim);
+                if found.len() < ef || c.sim > worst {
+                    frontier.push(c);
+                    found.push(std::cmp::Reverse(c));
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        let mut result: Vec<Candidate> = found.into_iter().map(|r| r.0).collect();
+        result.sort_by(|a, b| b.cmp(a));
+        result
+    }
+
+    /// Neighbour selection heuristic: prefer candidates closer to the query
+    /// than to any already-selected neighbour, which keeps links spread out.
+    fn select_neighbours(&self, candidates: &[Candidate], m: usize) -> Vec<u32> {
+        let mut selected: Vec<u32> = Vec::with_capacity(m);
+        let mut skipped: Vec<u32> = Vec::new();
+        for c in candidates {
+            if selected.len() == m {
+                break;
+            }
+            let vector = &self.nodes[c.node as usize].vector;
+            if selected.iter().all(|&s| self.sim(vector, s) < c.sim) {
+                selected.push(c.node);
+            } else {
+                skipped.push(c.node);
+            }
+        }
+        for s in skipped {
+            if selected.len() == m {
+                break;
+            }
+            selected.push(s);
+        }
+        selected
+    }
+
+    /// Add a link `from → to` on `layer`, pruning `from`'s list if it overflows.
+    fn link(&mut self, from: u32, to: u32, layer: usize) {
+        let max = if layer == 0 { 2 * M } else { M };
+        let links = &mut self.nodes[from as usize].links[layer];
+        links.push(to);
+        if links.len() <= max {
+            return;
+        }
+        let base = self.nodes[from as usize].vector.clone();
+        let mut candidates: Vec<Candidate> = self.nodes[from as usize].links[layer]
+            .iter()
+            .map(|&n| Candidate {
+                sim: self.sim(&base, n),
+                node: n,
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.cmp(a));
+        let pruned = self.select_neighbours(&candidates, max);
+        self.nodes[from as usize].links[layer] = pruned;
+    }
+
+    /// Rebuild the graph from live nodes, dropping tombstones.
+    fn rebuild(&mut self) {
+        let live: Vec<(String, Vec<f32>)> = self
+            .nodes
+            .drain(..)
+            .filter(|n| !n.deleted)
+            .map(|n| (n.id, n.vector))
+            .collect();
+        let rng = self.rng;
+        *self = Self::new(self.dims);
+        self.rng = rng;
+        for (id, vector) in live {
+            self.insert(&id, &vector);
+        }
+    }
+
+    // ── Persistence ────────────────────────────────────────────
+
+    /// Serialize the index (little-endian binary).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.nodes.len() * (self.dims * 4 + 64));
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        put_u32(&mut out, self.dims as u32);
+        put_u32(&mut out, self.entry.unwrap_or(u32::MAX));
+        put_u32(&mut out, self.max_level as u32);
+        out.extend_from_slice(&self.rng.to_le_bytes());
+        put_u32(&mut out, self.nodes.len() as u32);
+        for node in &self.nodes {
+            out.push(node.deleted as u8);
+            put_u32(&mut out, node.id.len() as u32);
+            out.extend_from_slice(node.id.as_bytes());
+            for v in &node.vector {
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+            put_u32(&mut out, node.links.len() as u32);
+            for layer in &node.links {
+                put_u32(&mut out, layer.len() as u32);
+                for &n in layer {
+                    put_u32(&mut out, n);
+                }
+            }
+        }
+        out
+    }
+
+    /// Deserialize an index written by [`to_bytes`](Self::to_bytes). `None`
+    /// if the data is corrupt or from another format version. Every count
+    /// is checked against the bytes left and every link against the node it
+    /// points at, so a damaged file is rebuilt rather than trusted.
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        let mut r = Reader { data, pos: 0 };
+        if r.take(4)? != MAGIC || r.take(1)?[0] != VERSION {
+            return None;
+        }
+        let dims = r.u32()? as usize;
+        let entry = r.u32()?;
+        let max_level = r.u32()? as usize;
+        let rng = u64::from_le_bytes(r.take(8)?.try_into().ok()?);
+        let count = r.u32()? as usize;
+        // Deleted flag, id length, vector and level count
+        let min_node = dims.checked_mul(4)?.checked_add(9)?;
+        if count > r.remaining() / min_node {
+            return None;
+        }
+
+        let mut index = Self::new(dims);
+        index.rng = rng;
+        index.max_level = max_level;
+        index.entry = (entry != u32::MAX).then_some(entry);
+        for i in 0..count {
+            let deleted = r.take(1)?[0] != 0;
+            let id_len = r.u32()? as usize;
+            let id = String::from_utf8(r.take(id_len)?.to_vec()).ok()?;
+            let vector = r
+                .take(dims * 4)?
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+            let levels = r.u32()? as usize;
+            if levels == 0 || levels > r.remaining() / 4 {
+                return None;
+            }
+            let mut links = Vec::with_capacity(levels);
+            for _ in 0..levels {
+                let n = r.u32()? as usize;
+                let layer: Vec<u32> = r
+                    .take(n.checked_mul(4)?)?
+                    .chunks_exact(4)
+                    .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect();
+                if layer.iter().any(|&l| l as usize >= count) {
+                    return None;
+                }
+                links.push(layer);
+            }
+            if !deleted && index.ids.insert(id.clone(), i as u32).is_some() {
+                return None;
+            }
+            index.nodes.push(Node {
+                id,
+                vector,
+                links,
+                deleted,
+            });
+        }
+        if r.pos != data.len() {
+            return None;
+        }
+
+        // Neighbours must live on the layer they're linked on, and the
+        // entry point must span every layer searches start from
+        let nodes = &index.nodes;
+        let linked_ok = nodes.iter().all(|node| {
+            node.links.iter().enumerate().all(|(layer, neighbours)| {
+                neighbours
+                    .iter()
+                    .all(|&n| nodes[n as usize].links.len() > layer)
+            })
+        });
+        let entry_ok = match index.entry {
+            Some(e) => nodes
+                .get(e as usize)
+                .is_some_and(|n| n.links.len() == max_level + 1),
+            None => count == 0 && max_level == 0,
+        };
+        if !linked_ok || !entry_ok {
+            return None;
+        }
+        Some(index)
+    }
+}
+
+fn put_u32(out: &mut Vec<u8>, v: u32) {
+    out.extend_from_slice(&v.to_le_bytes());
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let slice = self.data.get(self.pos..self.pos.checked_add(n)?)?;
+        self.pos += n;
+        Some(slice)
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalized(v: &[f32]) -> Vec<f32> {
+    let norm = dot(v, v).sqrt();
+    if norm == 0.0 {
+        return v.to_vec();
+    }
+    v.iter().map(|x| x / norm).collect()
+}
@@ -0,0 +1,899 @@
+//! Knowledge base — documents ingested from disk as chunked passages.
+//!
+//! Files (Markdown, plain text, source code, HTML, PDF) are converted to text
+//! and split into overlapping chunks along paragraph boundaries. Each chunk
+//! keeps its source path, byte offsets, line range (page for PDFs) and nearest
+//! heading, so answers can cite where they came from. Chunks are stored in
+//! SQLite with an FTS5 index and optional embeddings; search fuses BM25 and
+//! vector rankings like fact recall does.
+//!
+//! Embedding is up to the caller (this crate has no LLM access): ingest code
+//! chunks a file with [`chunk_text`], embeds the passages, then hands both to
+//! [`KnowledgeBase::replace_source`].
+
+use chrono::Utc;
+use parking_lot::Mutex;
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use claw_core::{ClawError, Result};
+
+/// Directories never descended into when ingesting a directory.
+const SKIP_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    "__pycache__",
+    "vendor",
+];
+
+/// Files larger than this are skipped.
+pub const MAX_FILE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// What a file is, which decides how its text is extracted and split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Markdown,
+    Text,
+    Code,
+    Html,
+    Pdf,
+}
+
+impl DocumentKind {
+    /// Kind from a file extension; `None` for unsupported files.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        Some(match ext.as_str() {
+            "md" | "markdown" | "mdx" => Self::Markdown,
+            "txt" | "text" | "rst" | "adoc" | "org" | "log" | "csv" => Self::Text,
+            "html" | "htm" | "xhtml" => Self::Html,
+            "pdf" => Self::Pdf,
+            "rs" | "py" | "js" | "ts" | "tsx" | "jsx" | "go" | "java" | "kt" | "c" | "h" | "cc"
+            | "cpp" | "hpp" | "cs" | "rb" | "php" | "swift" | "scala" | "sh" | "bash" | "zsh"
+            | "sql" | "toml" | "yaml" | "yml" | "json" | "ini" | "cfg" | "conf" | "tf"
+            | "proto" | "lua" | "ex" | "exs" | "hs" | "ml" | "vue" | "svelte" | "css" | "scss"
+            | "dockerfile" | "mk" => Self::Code,
+            _ => return None,
+        })
+    }
+}
+
+/// Chunk size limits, in bytes of UTF-8 text (≈ characters for prose).
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkOptions {
+    pub max_chars: usize,
+    pub overlap: usize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            max_chars: 1200,
+            overlap: 200,
+        }
+    }
+}
+
+/// One passage of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub index: usize,
+    /// Byte range in the extracted text.
+    pub start: usize,
+    pub end: usize,
+    /// 1-based line range in the extracted text.
+    pub start_line: usize,
+    pub end_line: usize,
+    /// 1-based page, for PDFs.
+    pub page: Option<usize>,
+    /// Nearest Markdown / HTML heading above the chunk.
+    pub heading: Option<String>,
+    pub text: String,
+}
+
+impl Chunk {
+    /// Where the chunk is in its source: `p. 3` or `L12-40`.
+    pub fn location(&self) -> String {
+        match self.page {
+            Some(page) => format!("p. {page}"),
+            None if self.start_line == self.end_line => format!("L{}", self.start_line),
+            None => format!("L{}-{}", self.start_line, self.end_line),
+        }
+    }
+}
+
+/// An ingested file.
+#[derive(Debug, Clone)]
+pub struct KbSource {
+    pub path: String,
+    /// The file or directory it was ingested through.
+    pub root: String,
+    pub hash: String,
+    pub size: u64,
+    /// Modification time (unix seconds) when ingested.
+    pub modified: i64,
+    pub chunk_count: usize,
+    pub ingested_at: String,
+}
+
+/// A passage matched by [`KnowledgeBase::search`]. Higher `score` is better.
+#[derive(Debug, Clone)]
+pub struct KbHit {
+    pub source: String,
+    pub chunk: Chunk,
+    pub score: f64,
+}
+
+impl KbHit {
+    /// Citation for the passage: `path (L12-40, § Heading)`.
+    pub fn citation(&self) -> String {
+        match self.chunk.heading {
+            Some(ref heading) => {
+                format!("{} ({}, § {heading})", self.source, self.chunk.location())
+            }
+            None => format!("{} ({})", self.source, self.chunk.location()),
+        }
+    }
+}
+
+// ── Text extraction ─────────────────────────────────────────────
+
+/// Read a file and convert it to plain text for chunking.
+pub fn extract_text(path: &Path, kind: DocumentKind) -> Result<String> {
+    let bytes = std::fs::read(path)?;
+    match kind {
+        DocumentKind::Pdf => pdf_text(path, &bytes),
+        DocumentKind::Html => Ok(html_to_text(&String::from_utf8_lossy(&bytes))),
+        _ => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+    }
+}
+
+/// PDF text via `pdftotext` (poppler) when installed, which keeps page
+/// breaks as form feeds. Without it only uncompressed text streams can be read.
+fn pdf_text(path: &Path, bytes: &[u8]) -> Result<String> {
+    if let Ok(out) = std::process::Command::new("pdftotext")
+        .args(["-layout", "-enc", "UTF-8"])
+        .arg(path)
+        .arg("-")
+        .output()
+        && out.status.success()
+    {
+        return Ok(String::from_utf8_lossy(&out.stdout).into_owned());
+    }
+    let text = pdf_text_fallback(bytes);
+    if text.trim().is_empty() {
+        return Err(ClawError::Memory(format!(
+            "no extractable text in {} (install poppler-utils for pdftotext to read compressed PDFs)",
+            path.display()
+        )));
+    }
+    Ok(text)
+}
+
+/// Pull string operands of `Tj` / `TJ` out of uncompressed content streams.
+/// Pages are separated by form feeds like `pdftotext` output.
+fn pdf_text_fallback(bytes: &[u8]) -> String {
+    let data = String::from_utf8_lossy(bytes);
+    let mut pages: Vec<String> = Vec::new();
+    let mut rest: &str = &data;
+    while let Some(start) = rest.find("stream") {
+        let after = &rest[start + "stream".len()..];
+        let Some(end) = after.find("endstream") else {
+            break;
+        };
+        let text = content_stream_text(&after[..end]);
+        if !text.trim().is_empty() {
+            pages.push(text);
+        }
+        rest = &after[end + "endstream".len()..];
+    }
+    pages.join("\x0c")
+}
+
+/// Text shown by one content stream: string literals, with a line break for
+/// each text-positioning or end-of-text operator.
+fn content_stream_text(body: &str) -> String {
+    let mut text = String::new();
+    let mut token = String::new();
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c == '(' {
+            let mut depth = 1;
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => match chars.next() {
+                        Some('n') => text.push('\n'),
+                        Some(other) => text.push(other),
+                        None => {}
+                    },
+                    '(' => {
+                        depth += 1;
+                        text.push(c);
+                    }
+                    ')' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                        text.push(c);
+                    }
+                    _ => text.push(c),
+                }
+            }
+            continue;
+        }
+        if c.is_ascii_alphabetic() || c == '*' || c == '\'' || c == '"' {
+            token.push(c);
+            continue;
+        }
+        if matches!(token.as_str(), "Td" | "TD" | "T*" | "'" | "\"" | "ET") {
+            text.push('\n');
+        }
+        token.clear();
+    }
+    text
+}
+
+/// Strip HTML to text. Headings become Markdown `#` lines so chunks can be
+/// attributed to sections; block elements become paragraph breaks.
+pub fn html_to_text(html: &str) -> String {
+    let mut out = String::with_capacity(html.len() / 2);
+    // ASCII lowercasing keeps byte offsets aligned with `html`
+    let lower = html.to_ascii_lowercase();
+    let mut i = 0;
+    while i < html.len() {
+        let rest = &html[i..];
+        if rest.starts_with("<!--") {
+            i += rest.find("-->").map_or(rest.len(), |e| e + 3);
+            continue;
+        }
+        if !rest.starts_with('<') {
+            let next = rest.find('<').unwrap_or(rest.len());
+            out.push_str(&decode_entities(&rest[..next]));
+            i += next;
+            continue;
+        }
+        let close = rest.find('>');
+        let end = close.map_or(rest.len(), |e| e + 1);
+        let tag = lower[i + 1..i + close.unwrap_or(rest.len())]
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("")
+            .to_string();
+        let closing = rest.starts_with("</");
+        i += end;
+        match tag.as_str() {
+            "script" | "style" | "noscript" | "template" if !closing => {
+                let close = format!("</{tag}");
+                i += lower[i..].find(&close).unwrap_or(html.len() - i);
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                out.push_str("\n\n");
+                if !closing {
+                    let level = tag[1..].parse::<usize>().unwrap_or(1);
+                    out.push_str(&"#".repeat(level));
+                    out.push(' ');
+                }
+            }
+            "p" | "div" | "section" | "article" | "table" | "ul" | "ol" | "pre" | "blockquote"
+            | "header" | "footer" | "main" | "nav" => out.push_str("\n\n"),
+            "br" | "tr" | "li" | "dt" | "dd" => out.push('\n'),
+            "td" | "th" => out.push('\t'),
+            _ => {}
+        }
+    }
+    // Collapse runs of blank lines and trailing spaces
+    let mut text = String::with_capacity(out.len());
+    let mut blank = 0;
+    for line in out.lines() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            blank += 1;
+            if blank == 1 && !text.is_empty() {
+                text.push('\n');
+            }
+            continue;
+        }
+        blank = 0;
+        text.push_str(line.trim_start_matches([' ', '\t']));
+        text.push('\n');
+    }
+    while text.ends_with("\n\n") {
+        text.pop();
+    }
+    text
+}
+
+fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest[..rest.len().min(12)].find(';') else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            e if e.starts_with("#x") || e.starts_with("#X") => u32::from_str_radix(&e[2..], 16)
+                .ok()
+                .and_then(char::from_u32),
+            e if e.starts_with('#') => e[1..].parse().ok().and_then(char::from_u32),
+            _ => None,
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// ── Chunking ────────────────────────────────────────────────────
+
+/// Split `text` into passages of at most `max_chars` (longer paragraphs are
+/// cut at line, then character, boundaries) with about `overlap` characters
+/// of trailing context repeated at the start of the next chunk.
+pub fn chunk_text(text: &str, kind: DocumentKind, options: ChunkOptions) -> Vec<Chunk> {
+    let max = options.max_chars.max(100);
+    let blocks = split_blocks(text, max);
+    let headings = if matches!(kind, DocumentKind::Markdown | DocumentKind::Html) {
+        markdown_headings(text)
+    } else {
+        vec![]
+    };
+
+    // PDF chunks stay within a page so their page citation is exact
+    let page_break = |a: usize, b: usize| {
+        kind == DocumentKind::Pdf && text[blocks[a].1..blocks[b].0].contains('\x0c')
+    };
+
+    let mut chunks = Vec::new();
+    let mut first = 0;
+    while first < blocks.len() {
+        let start = blocks[first].0;
+        let mut last = first;
+        while last + 1 < blocks.len()
+            && blocks[last + 1].1 - start <= max
+            && !page_break(last, last + 1)
+        {
+            last += 1;
+        }
+        let end = blocks[last].1;
+        let heading = headings
+            .iter()
+            .rev()
+            .find(|(pos, _)| *pos <= start)
+            .map(|(_, h)| h.clone());
+        let page = (kind == DocumentKind::Pdf).then(|| text[..start].matches('\x0c').count() + 1);
+        chunks.push(Chunk {
+            index: chunks.len(),
+            start,
+            end,
+            start_line: text[..start].matches('\n').count() + 1,
+            end_line: text[..end].trim_end().matches('\n').count() + 1,
+            page,
+            heading,
+            text: text[start..end].trim().to_string(),
+        });
+        if last + 1 >= blocks.len() {
+            break;
+        }
+        // Step back over trailing blocks that fit in the overlap
+        let mut next = last + 1;
+        while next - 1 > first
+            && end - blocks[next - 1].0 <= options.overlap
+            && !page_break(next - 1, next)
+        {
+            next -= 1;
+        }
+        first = next;
+    }
+    chunks
+}
+
+/// Non-blank spans of `text` separated by blank lines (or page breaks), each
+/// at most `max` bytes long.
+fn split_blocks(text: &str, max: usize) -> Vec<(usize, usize)> {
+    let mut blocks = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut pos = 0;
+    for line in text.split_inclusive('\n') {
+        let blank = line.trim().is_empty() || line.starts_with('\x0c');
+        if blank {
+            if let Some(s) = start.take() {
+                push_block(text, s, pos, max, &mut blocks);
+            }
+            if !line.trim_start_matches('\x0c').trim().is_empty() {
+                start = Some(pos + line.find(|c| c != '\x0c').unwrap_or(0));
+            }
+        } else if start.is_none() {
+            start = Some(pos);
+        }
+        pos += line.len();
+    }
+    if let Some(s) = start {
+        push_block(text, s, pos, max, &mut blocks);
+    }
+    blocks
+}
+
+/// Add the block `start..end`, cut into pieces of at most `max` bytes at line
+/// boundaries where possible.
+fn push_block(text: &str, start: usize, end: usize, max: usize, blocks: &mut Vec<(usize, usize)>) {
+    let mut s = start;
+    while end - s > max {
+        let mut cut = s + max;
+        while !text.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        if let Some(nl) = text[s..cut].rfind('\n')
+            && nl > 0
+        {
+            cut = s + nl + 1;
+        }
+        blocks.push((s, cut));
+        s = cut;
+    }
+    if end > s {
+        blocks.push((s, end));
+    }
+}
+
+/// Positions and titles of Markdown ATX headings (outside code fences).
+fn markdown_headings(text: &str) -> Vec<(usize, String)> {
+    let mut headings = Vec::new();
+    let mut in_fence = false;
+    let mut pos = 0;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        } else if !in_fence && trimmed.starts_with('#') {
+            let title = trimmed.trim_start_matches('#');
+            if title.starts_with(' ') && !title.trim().is_empty() {
+                headings.push((pos, title.trim().to_string()));
+            }
+        }
+        pos += line.len();
+    }
+    headings
+}
+
+// ── Files ───────────────────────────────────────────────────────
+
+/// Supported files at `path` (the file itself, or every supported file under
+/// a directory, skipping hidden and build directories), sorted.
+pub fn collect_files(path: &Path) -> Result<Vec<PathBuf>> {
+    let meta = std::fs::metadata(path)?;
+    if meta.is_file() {
+        return Ok(DocumentKind::from_path(path)
+            .map(|_| vec![path.to_path_buf()])
+            .unwrap_or_default());
+    }
+    let mut files = Vec::new();
+    let mut stack = vec![path.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        for entry in std::fs::read_dir(&dir)?.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with('.') {
+                continue;
+            }
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let entry_path = entry.path();
+            if file_type.is_dir() {
+                if !SKIP_DIRS.contains(&name.as_ref()) {
+                    stack.push(entry_path);
+                }
+            } else if file_type.is_file() && DocumentKind::from_path(&entry_path).is_some() {
+                files.push(entry_path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Hex BLAKE3 digest of file contents, for change detection.
+pub fn content_hash(bytes: &[u8]) -> String {
+    blake3::hash(bytes).to_hex().to_string()
+}
+
+// ── Storage ─────────────────────────────────────────────────────
+
+fn db_err(e: rusqlite::Error) -> ClawError {
+    ClawError::Memory(e.to_string())
+}
+
+fn embedding_blob(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|f| f.to_le_bytes()).collect()
+}
+
+fn blob_embedding(blob: &[u8]) -> Option<Vec<f32>> {
+    blob.len().is_multiple_of(4).then(|| {
+        blob.chunks_exact(4)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect()
+    })
+}
+
+/// Create the knowledge-base tables and their full-text index.
+pub(crate) fn create_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS kb_roots (
+            path TEXT PRIMARY KEY,
+            added_at TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS kb_sources (
+            path TEXT PRIMARY KEY,
+            root TEXT NOT NULL,
+            hash TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            modified INTEGER NOT NULL,
+            chunk_count INTEGER NOT NULL,
+            ingested_at TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS kb_chunks (
+            source TEXT NOT NULL REFERENCES kb_sources(path),
+            idx INTEGER NOT NULL,
+            start_offset INTEGER NOT NULL,
+            end_offset INTEGER NOT NULL,
+            start_line INTEGER NOT NULL,
+            end_line INTEGER NOT NULL,
+            page INTEGER,
+            heading TEXT,
+            text TEXT NOT NULL,
+            embedding BLOB,
+            UNIQUE(source, idx)
+        );
+        CREATE VIRTUAL TABLE IF NOT EXISTS kb_chunks_fts USING fts5(
+            heading, text,
+            content='kb_chunks', content_rowid='rowid', tokenize='porter unicode61'
+        );
+        CREATE TRIGGER IF NOT EXISTS kb_chunks_fts_insert AFTER INSERT ON kb_chunks BEGIN
+            INSERT INTO kb_chunks_fts(rowid, heading, text)
+            VALUES (new.rowid, new.heading, new.text);
+        END;
+        CREATE TRIGGER IF NOT EXISTS kb_chunks_fts_delete AFTER DELETE ON kb_chunks BEGIN
+            INSERT INTO kb_chunks_fts(kb_chunks_fts, rowid, heading, text)
+            VALUES ('delete', old.rowid, old.heading, old.text);
+        END;
+        ",
+    )
+}
+
+/// Ingested documents and their passages.
+pub struct KnowledgeBase {
+    db: Arc<Mutex<Connection>>,
+    /// Chunk embeddings by chunk rowid, for vector search.
+    vectors: HashMap<i64, Vec<f32>>,
+}
+
+impl KnowledgeBase {
+    /// Load chunk embeddings for vector search. The tables are created by
+    /// the schema migrations.
+    pub(crate) fn open(db: Arc<Mutex<Connection>>) -> Result<Self> {
+        let vectors = {
+            let conn = db.lock();
+            let mut stmt = conn
+                .prepare("SELECT rowid, embedding FROM kb_chunks WHERE embedding IS NOT NULL")
+                .map_err(db_err)?;
+            stmt.query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
+            })
+            .map_err(db_err)?
+            .filter_map(|r| r.ok())
+            .filter_map(|(id, blob)| blob_embedding(&blob).map(|v| (id, v)))
+            .collect()
+        };
+        Ok(Self { db, vectors })
+    }
+
+    /// Remember `path` (a file or directory) for periodic refresh.
+    pub fn add_root(&self, path: &str) -> Result<()> {
+        self.db
+            .lock()
+            .execute(
+                "INSERT OR IGNORE INTO kb_roots (path, added_at) VALUES (?1, ?2)",
+                rusqlite::params![path, Utc::now().to_rfc3339()],
+            )
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    /// Paths passed to ingest, in the order they were added.
+    pub fn roots(&self) -> Result<Vec<String>> {
+        let conn = self.db.lock();
+        let mut stmt = conn
+            .prepare("SELECT path FROM kb_roots ORDER BY added_at")
+            .map_err(db_err)?;
+        let roots = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(db_err)?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(roots)
+    }
+
+    /// The stored record for a source file.
+    pub fn source(&self, path: &str) -> Option<KbSource> {
+        self.db
+            .lock()
+            .query_row(
+                "SELECT path, root, hash, size, modified, chunk_count, ingested_at
+                 FROM kb_sources WHERE path = ?1",
+                [path],
+                source_from_row,
+            )
+            .optional()
+            .ok()
+            .flatten()
+    }
+
+    /// All ingested files, sorted by path.
+    pub fn sources(&self) -> Result<Vec<KbSource>> {
+        let conn = self.db.lock();
+        let mut stmt = conn
+            .prepare(
+                "SELECT path, root, hash, size, modified, chunk_count, ingested_at
+                 FROM kb_sources ORDER BY path",
+            )
+            .map_err(db_err)?;
+        let sources = stmt
+            .query_map([], source_from_row)
+            .map_err(db_err)?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(sources)
+    }
+
+    /// Record a new modification time for a source whose content is unchanged.
+    pub fn touch_source(&self, path: &str, size: u64, modified: i64) -> Result<()> {
+        self.db
+            .lock()
+            .execute(
+                "UPDATE kb_sources SET size = ?2, modified = ?3 WHERE path = ?1",
+                rusqlite::params![path, size as i64, modified],
+            )
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    /// Store a (re-)ingested file, replacing its previous chunks.
+    /// `embeddings`, when given, must have one vector per chunk.
+    pub fn replace_source(
+        &mut self,
+        source: &KbSource,
+        chunks: &[Chunk],
+        embeddings: Option<&[Vec<f32>]>,
+    ) -> Result<()> {
+        if embeddings.is_some_and(|e| e.len() != chunks.len()) {
+            return Err(ClawError::Memory(
+                "knowledge base: embedding count doesn't match chunk count".into(),
+            ));
+        }
+        let mut conn = self.db.lock();
+        let tx = conn.transaction().map_err(db_err)?;
+        let old: Vec<i64> = {
+            let mut stmt = tx
+                .prepare("SELECT rowid FROM kb_chunks WHERE source = ?1")
+                .map_err(db_err)?;
+            stmt.query_map([&source.path], |row| row.get(0))
+                .map_err(db_err)?
+                .filter_map(|r| r.ok())
+                .collect()
+        };
+        tx.execute("DELETE FROM kb_chunks WHERE source = ?1", [&source.path])
+            .map_err(db_err)?;
+        tx.execute(
+            "INSERT INTO kb_sources (path, root, hash, size, modified, chunk_count, ingested_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(path) DO UPDATE SET root = excluded.root, hash = excluded.hash,
+                size = excluded.size, modified = excluded.modified,
+                chunk_count = excluded.chunk_count, ingested_at = excluded.ingested_at",
+            rusqlite::params![
+                source.path,
+                source.root,
+                source.hash,
+                source.size as i64,
+                source.modified,
+                chunks.len() as i64,
+                Utc::now().to_rfc3339(),
+            ],
+        )
+        .map_err(db_err)?;
+        let mut added = Vec::with_capacity(chunks.len());
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO kb_chunks (source, idx, start_offset, end_offset, start_line,
+                        end_line, page, heading, text, embedding)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                )
+                .map_err(db_err)?;
+            for (i, chunk) in chunks.iter().enumerate() {
+                let embedding = embeddings.map(|e| &e[i]);
+                stmt.execute(rusqlite::params![
+                    source.path,
+                    chunk.index as i64,
+                    chunk.start as i64,
+                    chunk.end as i64,
+                    chunk.start_line as i64,
+                    chunk.end_line as i64,
+                    chunk.page.map(|p| p as i64),
+                    chunk.heading,
+                    chunk.text,
+                    embedding.map(|e| embedding_blob(e)),
+                ])
+                .map_err(db_err)?;
+                if let Some(e) = embedding {
+                    added.push((tx.last_insert_rowid(), e.clone()));
+                }
+            }
+        }
+        tx.commit().map_err(db_err)?;
+        drop(conn);
+
+        for id in old {
+            self.vectors.remove(&id);
+        }
+        self.vectors.extend(added);
+        Ok(())
+    }
+
+    /// Forget a source file and its chunks. Returns false if it wasn't stored.
+    pub fn remove_source(&mut self, path: &str) -> Result<bool> {
+        let conn = self.db.lock();
+        let old: Vec<i64> = {
+            let mut stmt = conn
+                .prepare("SELECT rowid FROM kb_chunks WHERE source = ?1")
+                .map_err(db_err)?;
+            stmt.query_map([path], |row| row.get(0))
+                .map_err(db_err)?
+                .filter_map(|r| r.ok())
+                .collect()
+        };
+        conn.execute("DELETE FROM kb_chunks WHERE source = ?1", [path])
+            .map_err(db_err)?;
+        let removed = conn
+            .execute("DELETE FROM kb_sources WHERE path = ?1", [path])
+            .map_err(db_err)?;
+        drop(conn);
+        for id in old {
+            self.vectors.remove(&id);
+        }
+        Ok(removed > 0)
+    }
+
+    /// Number of stored chunks.
+    pub fn chunk_count(&self) -> usize {
+        self.db
+            .lock()
+            .query_row("SELECT count(*) FROM kb_chunks", [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .map_or(0, |n| n as usize)
+    }
+
+    /// Best passages for a query: BM25 over chunk text and, given a query
+    /// embedding, cosine similarity over chunk embeddings, fused by rank.
+    pub fn search(
+        &self,
+        query: &str,
+        query_embedding: Option<&[f32]>,
+        limit: usize,
+    ) -> Result<Vec<KbHit>> {
+        let depth = (limit * 3).max(10);
+        let mut keyword: Vec<i64> = Vec::new();
+        if let Some(fts_query) = crate::fts::match_query(query) {
+            let conn = self.db.lock();
+            let mut stmt = conn
+                .prepare_cached(
+                    "SELECT rowid FROM kb_chunks_fts WHERE kb_chunks_fts MATCH ?1
+                     ORDER BY bm25(kb_chunks_fts, 2.0, 1.0) LIMIT ?2",
+                )
+                .map_err(db_err)?;
+            keyword = stmt
+                .query_map(rusqlite::params![fts_query, depth as i64], |row| row.get(0))
+                .map_err(db_err)?
+                .filter_map(|r| r.ok())
+                .collect();
+        }
+
+        let mut vector: Vec<(i64, f32)> = match query_embedding {
+            Some(q) => self
+                .vectors
+                .iter()
+                .filter(|(_, v)| v.len() == q.len())
+                .map(|(id, v)| (*id, cosine(q, v)))
+                .collect(),
+            None => vec![],
+        };
+        vector.sort_by(|a, b| b.1.total_cmp(&a.1));
+        vector.truncate(depth);
+        let vector: Vec<i64> = vector.into_iter().map(|(id, _)| id).collect();
+
+        let fused = crate::fts::reciprocal_rank_fusion(&[keyword, vector]);
+        let conn = self.db.lock();
+        let mut stmt = conn
+            .prepare_cached(
+                "SELECT source, idx, start_offset, end_offset, start_line, end_line, page,
+                        heading, text
+                 FROM kb_chunks WHERE rowid = ?1",
+            )
+            .map_err(db_err)?;
+        let mut hits = Vec::new();
+        for (id, score) in fused.into_iter().take(limit) {
+            let hit = stmt
+                .query_row([id], |row| {
+                    Ok(KbHit {
+                        source: row.get(0)?,
+                        chunk: Chunk {
+                            index: row.get::<_, i64>(1)? as usize,
+                            start: row.get::<_, i64>(2)? as usize,
+                            end: row.get::<_, i64>(3)? as usize,
+                            start_line: row.get::<_, i64>(4)? as usize,
+                            end_line: row.get::<_, i64>(5)? as usize,
+                            page: row.get::<_, Option<i64>>(6)?.map(|p| p as usize),
+                            heading: row.get(7)?,
+                            text: row.get(8)?,
+                        },
+                        score,
+                    })
+                })
+                .optional()
+                .map_err(db_err)?;
+            hits.extend(hit);
+        }
+        Ok(hits)
+    }
+}
+
+fn source_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<KbSource> {
+    Ok(KbSource {
+        path: row.get(0)?,
+        root: row.get(1)?,
+        hash: row.get(2)?,
+        size: row.get::<_, i64>(3)? as u64,
+        modified: row.get(4)?,
+        chunk_count: row.get::<_, i64>(5)? as usize,
+        ingested_at: row.get(6)?,
+    })
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let na: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let nb: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if na == 0.0 || nb == 0.0 {
+        return 0.0;
+    }
+    dot / (na * nb)
+}
@@ -6,16 +6,57 @@
 //! - **Episodic memory**: Past conversations, events, outcomes (SQLite, persistent).
 //! - **Semantic memory**: Facts, knowledge, embeddings (SQLite + vector index, persistent).
 //!
+//! Facts, episodes and session messages are also full-text indexed (FTS5)
+//! for BM25-ranked and hybrid keyword + vector search. A knowledge base holds
+//! ingested documents as chunked, citable passages. The database can be
+//! exported to and merged from a portable JSONL archive for backups and
+//! migration.
+//!
+//! Fact confidence decays along a forgetting curve while a fact goes unused
+//! and is reinforced whenever it's recalled; faded facts are archived. Every
+//! value a fact has held is versioned, and contradicting values are flagged
+//! as conflicts to resolve.
+//!
+//! A knowledge graph links entities — people, servers, services, projects,
+//! repos — through typed relations, for questions flat facts can't answer.
+//!
+//! Facts and episodes belong to a scope — global, one channel, one person or
+//! one session — so what the agent learns from one person isn't recalled for
+//! another.
+//!
+//! The audit log is a hash chain sealed with a device-local key, so edits,
+//! deletions and reordering of its entries can be detected.
+//!
 //! The memory system enables the agent to learn from past interactions,
 //! recall relevant context, and build long-term knowledge.
 
+pub mod audit;
+pub mod backup;
+pub mod decay;
 pub mod episodic;
+pub mod fts;
+pub mod graph;
+pub mod history;
+pub mod hnsw;
+pub mod knowledge;
+pub mod migrations;
+pub mod scope;
 pub mod semantic;
 pub mod store;
 pub mod working;
 
+pub use audit::{AuditEntry, AuditIssue, AuditKey, AuditLog, AuditReport, SegmentHeader};
+pub use backup::{ImportReport, Manifest, MergeStrategy, TableImport};
+pub use decay::{Consolidation, DecayPolicy};
 pub use episodic::{Episode, EpisodicMemory};
+pub use fts::{EpisodeHit, FactHit, MessageHit};
+pub use graph::{Direction, Entity, KnowledgeGraph, Neighborhood, Relation, RelationPattern};
+pub use history::{FactConflict, FactVersion, ValueChange};
+pub use hnsw::HnswIndex;
+pub use knowledge::{Chunk, ChunkOptions, DocumentKind, KbHit, KbSource, KnowledgeBase};
+pub use migrations::MigrationReport;
+pub use scope::{MemoryScope, ScopeKind};
 pub use semantic::{Fact, SemanticMemory};
 pub use store::MemoryStore;
 pub use store::{GoalRow, GoalStepRow, SessionRow};
-pub use working::WorkingMemory;
+pub use working::{TokenCounter, WorkingMemory};
@@ -0,0 +1,457 @@
+//! Schema migrations — ordered, versioned changes to the memory database.
+//!
+//! The schema version lives in `PRAGMA user_version`. Opening a store runs
+//! every migration newer than the file's version, each in its own
+//! transaction together with the version bump, so a failure leaves the
+//! database at the last version that applied cleanly. Before migrating an
+//! existing file a copy is written next to it with `VACUUM INTO`.
+//!
+//! Databases created before versioning report version 0; the first
+//! migrations only use `IF NOT EXISTS`, so they adopt those files as-is.
+//! New schema changes go at the end of [`MIGRATIONS`] — never edit one that
+//! has shipped.
+
+use chrono::Utc;
+use rusqlite::{Connection, OpenFlags};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use claw_core::{ClawError, Result};
+
+/// Pre-migration backups kept per database; older ones are deleted.
+const KEEP_BACKUPS: usize = 3;
+
+/// One schema change.
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&Connection) -> rusqlite::Result<()>,
+}
+
+/// All migrations, in order. `version` must count up from 1 without gaps.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "core tables",
+        apply: core_tables,
+    },
+    Migration {
+        version: 2,
+        description: "full-text search indexes",
+        apply: crate::fts::init,
+    },
+    Migration {
+        version: 3,
+        description: "knowledge base",
+        apply: crate::knowledge::create_tables,
+    },
+    Migration {
+        version: 4,
+        description: "fact access tracking and archive",
+        apply: fact_access,
+    },
+    Migration {
+        version: 5,
+        description: "fact history and conflicts",
+        apply: crate::history::create_tables,
+    },
+    Migration {
+        version: 6,
+        description: "knowledge graph",
+        apply: crate::graph::create_tables,
+    },
+    Migration {
+        version: 7,
+        description: "memory scopes",
+        apply: memory_scopes,
+    },
+    Migration {
+        version: 8,
+        description: "audit log hash chain",
+        apply: audit_chain,
+    },
+];
+
+/// The schema version this build creates and expects.
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+/// Schema version recorded in a database.
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| ClawError::Memory(e.to_string()))
+}
+
+/// Schema version of the database file at `path`, read without modifying
+/// it. `None` if the file doesn't exist yet.
+pub fn inspect(path: &Path) -> Result<Option<u32>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| ClawError::Memory(e.to_string()))?;
+    schema_version(&conn).map(Some)
+}
+
+/// What [`migrate`] did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from: u32,
+    pub to: u32,
+    /// Copy of the database taken before migrating, if one was needed.
+    pub backup: Option<PathBuf>,
+}
+
+/// Apply pending migrations. `path` is the database file, used for the
+/// pre-migration backup (`None` for in-memory databases). Refuses to open a
+/// database written by a newer version.
+pub fn migrate(conn: &mut Connection, path: Option<&Path>) -> Result<MigrationReport> {
+    let from = schema_version(conn)?;
+    let latest = latest_version();
+    if from > latest {
+        return Err(ClawError::Memory(format!(
+            "memory database schema is v{from}, newer than this version of claw supports (v{latest}) — upgrade claw"
+        )));
+    }
+    let mut report = MigrationReport {
+        from,
+        to: from,
+        backup: None,
+    };
+    if from == latest {
+        return Ok(report);
+    }
+
+    if let Some(path) = path
+        && has_tables(conn)?
+    {
+        report.backup = Some(backup(conn, path, from)?);
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > from) {
+        let tx = conn
+            .transaction()
+            .map_err(|e| ClawError::Memory(e.to_string()))?;
+        (migration.apply)(&tx)
+            .and_then(|()| tx.pragma_update(None, "user_version", migration.version))
+            .and_then(|()| tx.commit())
+            .map_err(|e| {
+                ClawError::Memory(format!(
+                    "memory schema migration v{} ({}) failed: {e}",
+                    migration.version, migration.description
+                ))
+            })?;
+        report.to = migration.version;
+    }
+    if from > 0 || report.backup.is_some() {
+        info!(from, to = report.to, backup = ?report.backup, "migrated memory database schema");
+    }
+    Ok(report)
+}
+
+fn has_tables(conn: &Connection) -> Result<bool> {
+    conn.query_row(
+        "SELECT count(*) FROM sqlite_master WHERE type = 'table'",
+        [],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|n| n > 0)
+    .map_err(|e| ClawError::Memory(e.to_string()))
+}
+
+/// Write a consistent copy of the database to
+/// `<file>.v<version>-<timestamp>.bak` and prune old copies.
+fn backup(conn: &Connection, path: &Path, version: u32) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "memory.db".to_string());
+    let prefix = format!("{file_name}.v");
+    let target = path.with_file_name(format!(
+        "{prefix}{version}-{}.bak",
+        Utc::now().format("%Y%m%d%H%M%S%3f")
+    ));
+    conn.execute("VACUUM INTO ?1", [target.to_string_lossy()])
+        .map_err(|e| ClawError::Memory(format!("pre-migration backup failed: {e}")))?;
+
+    // Keep only the newest few
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty());
+    if let Ok(entries) = std::fs::read_dir(dir.unwrap_or(Path::new("."))) {
+        let mut backups: Vec<(std::time::SystemTime, PathBuf)> = entries
+            .flatten()
+            .filter(|e| {
+                let name = e.file_name().to_string_lossy().to_string();
+                name.starts_with(&prefix) && name.ends_with(".bak")
+            })
+            .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+            .collect();
+        backups.sort();
+        let excess = backups.len().saturating_sub(KEEP_BACKUPS);
+        for (_, old) in &backups[..excess] {
+            if let Err(e) = std::fs::remove_file(old) {
+                warn!(path = %old.display(), error = %e, "failed to remove old memory backup");
+            }
+        }
+    }
+    Ok(target)
+}
+
+// ── Migrations ──────────────────────────────────────────────────
+
+fn core_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS episodes (
+            id TEXT PRIMARY KEY,
+            session_id TEXT NOT NULL,
+            summary TEXT NOT NULL,
+            outcome TEXT,
+            tags TEXT DEFAULT '[]',
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS episode_messages (
+            id TEXT PRIMARY KEY,
+            episode_id TEXT NOT NULL REFERENCES episodes(id),
+            role TEXT NOT NULL,
+            content TEXT NOT NULL,
+            timestamp TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS facts (
+            id TEXT PRIMARY KEY,
+            category TEXT NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT NOT NULL,
+            confidence REAL DEFAULT 1.0,
+            source TEXT,
+            embedding BLOB,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE(category, key)
+        );
+
+        CREATE TABLE IF NOT EXISTS goals (
+            id TEXT PRIMARY KEY,
+            description TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'active',
+            priority INTEGER DEFAULT 5,
+            progress REAL DEFAULT 0.0,
+            parent_id TEXT REFERENCES goals(id),
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS goal_steps (
+            id TEXT PRIMARY KEY,
+            goal_id TEXT NOT NULL REFERENCES goals(id),
+            description TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            result TEXT,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp TEXT NOT NULL,
+            event_type TEXT NOT NULL,
+            action TEXT NOT NULL,
+            details TEXT,
+            checksum TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_episodes_session ON episodes(session_id);
+        CREATE INDEX IF NOT EXISTS idx_facts_category ON facts(category);
+        CREATE INDEX IF NOT EXISTS idx_goals_status ON goals(status);
+        CREATE INDEX IF NOT EXISTS idx_audit_timestamp ON audit_log(timestamp);
+
+        CREATE TABLE IF NOT EXISTS sessions (
+            id TEXT PRIMARY KEY,
+            name TEXT,
+            channel TEXT,
+            target TEXT,
+            active INTEGER DEFAULT 1,
+            message_count INTEGER DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_sessions_active ON sessions(active);
+
+        CREATE TABLE IF NOT EXISTS session_messages (
+            session_id TEXT PRIMARY KEY,
+            messages_json TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS scheduled_tasks (
+            id TEXT PRIMARY KEY,
+            label TEXT,
+            description TEXT NOT NULL,
+            kind_json TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            session_id TEXT,
+            active INTEGER DEFAULT 1,
+            fire_count INTEGER DEFAULT 0,
+            last_fired TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_scheduled_tasks_active ON scheduled_tasks(active);
+
+        CREATE TABLE IF NOT EXISTS memory_meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS vector_index (
+            name TEXT PRIMARY KEY,
+            data BLOB NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        ",
+    )
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> rusqlite::Result<bool> {
+    Ok(conn
+        .prepare(&format!("PRAGMA table_info({table})"))?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .iter()
+        .any(|c| c == column))
+}
+
+/// `ALTER TABLE ... ADD COLUMN` unless the column is already there.
+fn add_column(conn: &Connection, table: &str, column: &str, decl: &str) -> rusqlite::Result<()> {
+    if !has_column(conn, table, column)? {
+        conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl}"))?;
+    }
+    Ok(())
+}
+
+/// Rebuild `table` with a `scope` column (existing rows become global)
+/// whose uniqueness constraint includes it. `columns` lists the columns
+/// kept, and `schema` declares everything after `scope`. Rowids are kept,
+/// so external-content FTS indexes stay valid. No-op once scoped.
+fn add_scope(conn: &Connection, table: &str, columns: &str, schema: &str) -> rusqlite::Result<()> {
+    if has_column(conn, table, "scope")? {
+        return Ok(());
+    }
+    conn.execute_batch(&format!(
+        "
+        CREATE TABLE {table}_scoped (
+            scope TEXT NOT NULL DEFAULT 'global',
+            {schema}
+        );
+        INSERT INTO {table}_scoped (rowid, {columns}) SELECT rowid, {columns} FROM {table};
+        DROP TABLE {table};
+        ALTER TABLE {table}_scoped RENAME TO {table};
+        "
+    ))
+}
+
+fn fact_access(conn: &Connection) -> rusqlite::Result<()> {
+    add_column(conn, "facts", "access_count", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column(conn, "facts", "last_accessed", "TEXT")?;
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS facts_archive (
+            id TEXT PRIMARY KEY,
+            category TEXT NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT NOT NULL,
+            confidence REAL,
+            source TEXT,
+            embedding BLOB,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            access_count INTEGER NOT NULL DEFAULT 0,
+            last_accessed TEXT,
+            archived_at TEXT NOT NULL,
+            UNIQUE(category, key)
+        );
+        CREATE INDEX IF NOT EXISTS idx_facts_archive_archived ON facts_archive(archived_at);
+        ",
+    )
+}
+
+/// Facts, their history and archive are keyed by scope as well as
+/// category/key; conflicts and episodes record a scope.
+fn memory_scopes(conn: &Connection) -> rusqlite::Result<()> {
+    add_scope(
+        conn,
+        "facts",
+        "id, category, key, value, confidence, source, embedding, created_at, updated_at,
+         access_count, last_accessed",
+        "id TEXT PRIMARY KEY,
+         category TEXT NOT NULL,
+         key TEXT NOT NULL,
+         value TEXT NOT NULL,
+         confidence REAL DEFAULT 1.0,
+         source TEXT,
+         embedding BLOB,
+         created_at TEXT NOT NULL,
+         updated_at TEXT NOT NULL,
+         access_count INTEGER NOT NULL DEFAULT 0,
+         last_accessed TEXT,
+         UNIQUE(scope, category, key)",
+    )?;
+    add_scope(
+        conn,
+        "facts_archive",
+        "id, category, key, value, confidence, source, embedding, created_at, updated_at,
+         access_count, last_accessed, archived_at",
+        "id TEXT PRIMARY KEY,
+         category TEXT NOT NULL,
+         key TEXT NOT NULL,
+         value TEXT NOT NULL,
+         confidence REAL,
+         source TEXT,
+         embedding BLOB,
+         created_at TEXT NOT NULL,
+         updated_at TEXT NOT NULL,
+         access_count INTEGER NOT NULL DEFAULT 0,
+         last_accessed TEXT,
+         archived_at TEXT NOT NULL,
+         UNIQUE(scope, category, key)",
+    )?;
+    add_scope(
+        conn,
+        "fact_history",
+        "id, category, key, version, value, confidence, source, recorded_at",
+        "id INTEGER PRIMARY KEY AUTOINCREMENT,
+         category TEXT NOT NULL,
+         key TEXT NOT NULL,
+         version INTEGER NOT NULL,
+         value TEXT NOT NULL,
+         confidence REAL,
+         source TEXT,
+         recorded_at TEXT NOT NULL,
+         UNIQUE(scope, category, key, version)",
+    )?;
+    add_column(
+        conn,
+        "fact_conflicts",
+        "scope",
+        "TEXT NOT NULL DEFAULT 'global'",
+    )?;
+    add_column(conn, "episodes", "scope", "TEXT NOT NULL DEFAULT 'global'")?;
+    conn.execute_batch(
+        "
+        CREATE INDEX IF NOT EXISTS idx_facts_category ON facts(category);
+        CREATE INDEX IF NOT EXISTS idx_facts_scope ON facts(scope);
+        CREATE INDEX IF NOT EXISTS idx_facts_archive_archived ON facts_archive(archived_at);
+        CREATE INDEX IF NOT EXISTS idx_episodes_scope ON episodes(scope);
+        ",
+    )?;
+    // Rebuilding `facts` dropped the triggers on it
+    crate::fts::init(conn)?;
+    crate::history::create_scoped_triggers(conn)
+}
+
+/// Audit entries link to the one before: `prev_mac` holds its MAC and
+/// `checksum` the entry's own (see [`crate::audit`]). Existing entries keep
+/// a `NULL` link; the store pins them when it first opens with a key.
+fn audit_chain(conn: &Connection) -> rusqlite::Result<()> {
+    add_column(conn, "audit_log", "prev_mac", "TEXT")
+}
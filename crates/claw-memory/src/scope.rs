@@ -0,0 +1,158 @@
+//! Memory scopes — who a fact or episode belongs to.
+//!
+//! Every fact and episode carries a scope string:
+//!
+//! - `global` — shared by everyone the agent talks to (and its mesh peers),
+//! - `channel:<channel>` — everyone on one channel, e.g. `channel:telegram`,
+//! - `user:<identity>` — one person; `<identity>` is `<channel>:<sender>`
+//!   unless the config links their sender ids across channels to one name,
+//! - `session:<id>` — one conversation.
+//!
+//! A [`MemoryScope`] describes a caller: which of those scopes it may read,
+//! which it may write, and where writes go by default. Memory written before
+//! scopes existed is global.
+
+use claw_core::{ClawError, Result};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The scope everyone can read.
+pub const GLOBAL: &str = "global";
+
+pub(crate) fn global() -> String {
+    GLOBAL.to_string()
+}
+
+/// A kind of scope, narrowest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScopeKind {
+    Session,
+    User,
+    Channel,
+    Global,
+}
+
+impl ScopeKind {
+    pub const ALL: [ScopeKind; 4] = [
+        ScopeKind::Session,
+        ScopeKind::User,
+        ScopeKind::Channel,
+        ScopeKind::Global,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScopeKind::Session => "session",
+            ScopeKind::User => "user",
+            ScopeKind::Channel => "channel",
+            ScopeKind::Global => "global",
+        }
+    }
+
+    /// The kind of a scope string, e.g. `user:telegram:42` → `User`.
+    pub fn of(scope: &str) -> Option<ScopeKind> {
+        scope.split(':').next()?.parse().ok()
+    }
+}
+
+impl std::fmt::Display for ScopeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ScopeKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        ScopeKind::ALL
+            .into_iter()
+            .find(|k| k.as_str() == s)
+            .ok_or_else(|| format!("unknown memory scope '{s}' (global, channel, user, session)"))
+    }
+}
+
+/// A caller's view of memory: who they are, and which scopes they may read
+/// and write.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryScope {
+    /// Channel the conversation came in on.
+    pub channel: Option<String>,
+    /// Identity of the person talking.
+    pub user: Option<String>,
+    pub session: Option<Uuid>,
+    pub read: Vec<ScopeKind>,
+    pub write: Vec<ScopeKind>,
+    /// Where writes go when no scope is asked for.
+    pub default_write: ScopeKind,
+}
+
+impl MemoryScope {
+    /// Reads and writes global memory only — for work done on nobody's
+    /// behalf, such as mesh sync.
+    pub fn global() -> Self {
+        Self {
+            channel: None,
+            user: None,
+            session: None,
+            read: vec![ScopeKind::Global],
+            write: vec![ScopeKind::Global],
+            default_write: ScopeKind::Global,
+        }
+    }
+
+    /// This caller's scope string of a kind; `None` if the caller has no
+    /// such identity (e.g. no session).
+    pub fn id(&self, kind: ScopeKind) -> Option<String> {
+        match kind {
+            ScopeKind::Global => Some(global()),
+            ScopeKind::Channel => self.channel.as_ref().map(|c| format!("channel:{c}")),
+            ScopeKind::User => self.user.as_ref().map(|u| format!("user:{u}")),
+            ScopeKind::Session => self.session.map(|s| format!("session:{s}")),
+        }
+    }
+
+    /// Every scope string the caller may read.
+    pub fn readable(&self) -> Vec<String> {
+        self.read.iter().filter_map(|k| self.id(*k)).collect()
+    }
+
+    /// Every scope string the caller may write.
+    pub fn writable(&self) -> Vec<String> {
+        self.write.iter().filter_map(|k| self.id(*k)).collect()
+    }
+
+    pub fn can_read(&self, scope: &str) -> bool {
+        self.read
+            .iter()
+            .any(|k| self.id(*k).as_deref() == Some(scope))
+    }
+
+    pub fn can_write(&self, scope: &str) -> bool {
+        self.write
+            .iter()
+            .any(|k| self.id(*k).as_deref() == Some(scope))
+    }
+
+    /// Where a write of `kind` goes. With no kind, the default — or, if the
+    /// caller has no identity of that kind, the next broader scope it may
+    /// write.
+    pub fn write_scope(&self, kind: Option<ScopeKind>) -> Result<String> {
+        if let Some(kind) = kind {
+            if !self.write.contains(&kind) {
+                return Err(ClawError::Memory(format!(
+                    "this conversation may not write {kind} memory"
+                )));
+            }
+            return self.id(kind).ok_or_else(|| {
+                ClawError::Memory(format!("this conversation has no {kind} to write to"))
+            });
+        }
+        ScopeKind::ALL
+            .into_iter()
+            .filter(|k| *k >= self.default_write && self.write.contains(k))
+            .find_map(|k| self.id(k))
+            .ok_or_else(|| ClawError::Memory("this conversation may not write memory".into()))
+    }
+}
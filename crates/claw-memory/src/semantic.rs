@@ -1,12 +1,27 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+use crate::hnsw::HnswIndex;
+
+/// Embedded facts at which `vector_search` switches from an exact scan to the
+/// HNSW index. Below this a linear scan is both exact and fast enough.
+pub const ANN_THRESHOLD: usize = 1_000;
+
+/// Minimum HNSW candidate list size for searches.
+const ANN_EF_SEARCH: usize = 64;
+
+/// Separates scope, category and key in index ids.
+const ID_SEPARATOR: char = '\u{1f}';
+
 /// A fact is a piece of knowledge the agent has learned.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fact {
     pub id: Uuid,
+    /// Who can recall it (see [`crate::scope`]).
+    #[serde(default = "crate::scope::global")]
+    pub scope: String,
     pub category: String,
     pub key: String,
     pub value: String,
@@ -18,12 +33,26 @@ pub struct Fact {
     pub embedding: Option<Vec<f32>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Times the fact has been recalled.
+    #[serde(default)]
+    pub access_count: u32,
+    /// When the fact was last recalled.
+    #[serde(default)]
+    pub last_accessed: Option<DateTime<Utc>>,
 }
 
 /// Manages semantic memory — structured knowledge and facts.
 pub struct SemanticMemory {
     /// In-memory fact index by category.
     facts: HashMap<String, Vec<Fact>>,
+    /// Approximate nearest-neighbour index over embeddings. Built once
+    /// `embedded` reaches [`ANN_THRESHOLD`] (or restored from disk), then
+    /// maintained incrementally.
+    index: Option<HnswIndex>,
+    /// Number of facts that have an embedding.
+    embedded: usize,
+    /// Index changed since it was last persisted.
+    index_dirty: bool,
 }
 
 impl Default for SemanticMemory {
@@ -36,54 +65,148 @@ impl SemanticMemory {
     pub fn new() -> Self {
         Self {
             facts: HashMap::new(),
+            index: None,
+            embedded: 0,
+            index_dirty: false,
         }
     }
 
-    /// Store or update a fact.
+    /// Store or update a fact. Updating keeps the existing fact's recall
+    /// history.
     pub fn upsert(&mut self, fact: Fact) {
+        let scope = fact.scope.clone();
+        let category = fact.category.clone();
+        let key = fact.key.clone();
+        let embedding = fact.embedding.clone();
         let entry = self.facts.entry(fact.category.clone()).or_default();
         // Update existing or insert new
-        if let Some(existing) = entry.iter_mut().find(|f| f.key == fact.key) {
+        if let Some(existing) = entry
+            .iter_mut()
+            .find(|f| f.scope == fact.scope && f.key == fact.key)
+        {
             existing.value = fact.value;
             existing.confidence = fact.confidence;
-            existing.updated_at = Utc::now();
+            if fact.source.is_some() {
+                existing.source = fact.source;
+            }
+            existing.updated_at = fact.updated_at;
+            existing.access_count = existing.access_count.max(fact.access_count);
+            existing.last_accessed = existing.last_accessed.max(fact.last_accessed);
             if fact.embedding.is_some() {
+                if existing.embedding.is_none() {
+                    self.embedded += 1;
+                }
                 existing.embedding = fact.embedding;
             }
         } else {
+            if fact.embedding.is_some() {
+                self.embedded += 1;
+            }
             entry.push(fact);
         }
+        if let Some(embedding) = embedding {
+            self.index_embedding(&scope, &category, &key, &embedding);
+        }
     }
 
     /// Look up a specific fact.
-    pub fn get(&self, category: &str, key: &str) -> Option<&Fact> {
+    pub fn get(&self, scope: &str, category: &str, key: &str) -> Option<&Fact> {
         self.facts
             .get(category)
-            .and_then(|facts| facts.iter().find(|f| f.key == key))
+            .and_then(|facts| facts.iter().find(|f| f.scope == scope && f.key == key))
+    }
+
+    /// Replace a fact's embedding. Returns false if the fact doesn't exist.
+    pub fn set_embedding(
+        &mut self,
+        scope: &str,
+        category: &str,
+        key: &str,
+        embedding: Option<Vec<f32>>,
+    ) -> bool {
+        match self
+            .facts
+            .get_mut(category)
+            .and_then(|facts| facts.iter_mut().find(|f| f.scope == scope && f.key == key))
+        {
+            Some(fact) => {
+                let had = fact.embedding.is_some();
+                fact.embedding = embedding.clone();
+                self.embedded = self.embedded + embedding.is_some() as usize - had as usize;
+                match embedding {
+                    Some(embedding) => self.index_embedding(scope, category, key, &embedding),
+                    None => self.unindex(scope, category, key),
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Record a recall of a fact at `now`, storing its reinforced
+    /// `confidence`. Returns the updated fact, or `None` if it doesn't exist.
+    pub fn record_access(
+        &mut self,
+        scope: &str,
+        category: &str,
+        key: &str,
+        confidence: f64,
+        now: DateTime<Utc>,
+    ) -> Option<&Fact> {
+        let fact = self
+            .facts
+            .get_mut(category)?
+            .iter_mut()
+            .find(|f| f.scope == scope && f.key == key)?;
+        fact.access_count = fact.access_count.saturating_add(1);
+        fact.last_accessed = Some(now);
+        fact.confidence = confidence;
+        Some(fact)
     }
 
-    /// Remove a specific fact by category and key. Returns true if found and removed.
-    pub fn remove(&mut self, category: &str, key: &str) -> bool {
+    /// Remove a specific fact by scope, category and key. Returns true if found and removed.
+    pub fn remove(&mut self, scope: &str, category: &str, key: &str) -> bool {
         if let Some(facts) = self.facts.get_mut(category) {
-            let before = facts.len();
-            facts.retain(|f| f.key != key);
-            let removed = facts.len() < before;
+            let Some(pos) = facts.iter().position(|f| f.scope == scope && f.key == key) else {
+                return false;
+            };
+            let fact = facts.remove(pos);
             // Clean up empty category
             if facts.is_empty() {
                 self.facts.remove(category);
             }
-            removed
+            if fact.embedding.is_some() {
+                self.embedded -= 1;
+                self.unindex(scope, category, key);
+            }
+            true
         } else {
             false
         }
     }
 
-    /// Remove all facts in a category. Returns the number of facts removed.
-    pub fn remove_category(&mut self, category: &str) -> usize {
-        self.facts.remove(category).map(|v| v.len()).unwrap_or(0)
+    /// Remove all of a scope's facts in a category. Returns the number of
+    /// facts removed.
+    pub fn remove_category(&mut self, scope: &str, category: &str) -> usize {
+        let Some(facts) = self.facts.get_mut(category) else {
+            return 0;
+        };
+        let (removed, kept): (Vec<Fact>, Vec<Fact>) = std::mem::take(facts)
+            .into_iter()
+            .partition(|f| f.scope == scope);
+        if kept.is_empty() {
+            self.facts.remove(category);
+        } else {
+            *facts = kept;
+        }
+        for fact in removed.iter().filter(|f| f.embedding.is_some()) {
+            self.embedded -= 1;
+            self.unindex(scope, category, &fact.key);
+        }
+        removed.len()
     }
 
-    /// Get all facts in a category.
+    /// Get all facts in a category, in every scope.
     pub fn category(&self, category: &str) -> &[Fact] {
         self.facts
             .get(category)
@@ -142,12 +265,33 @@ impl SemanticMemory {
             .collect();
 
         // Sort by score descending
-        scored.sort_by(|a, b| b.1.cmp(&a.1));
+        scored.sort_by_key(|s| std::cmp::Reverse(s.1));
         scored.into_iter().map(|(f, _)| f).collect()
     }
 
-    /// Cosine similarity search against stored embeddings.
+    /// Cosine similarity search against stored embeddings. Uses the HNSW
+    /// index once there are [`ANN_THRESHOLD`] embedded facts, an exact scan
+    /// below that (or when the query doesn't match the index's dimensions).
     pub fn vector_search(&self, query_embedding: &[f32], top_k: usize) -> Vec<(&Fact, f32)> {
+        if let Some(index) = &self.index
+            && index.dims() == query_embedding.len()
+            && index.len() >= ANN_THRESHOLD
+        {
+            let ef = (top_k * 4).max(ANN_EF_SEARCH);
+            return index
+                .search(query_embedding, top_k, ef)
+                .into_iter()
+                .filter_map(|(id, similarity)| {
+                    let (scope, category, key) = parse_index_id(id)?;
+                    self.get(scope, category, key).map(|f| (f, similarity))
+                })
+                .collect();
+        }
+        self.exact_search(query_embedding, top_k)
+    }
+
+    /// Linear-scan cosine similarity search — exact, O(n).
+    pub fn exact_search(&self, query_embedding: &[f32], top_k: usize) -> Vec<(&Fact, f32)> {
         let mut results: Vec<(&Fact, f32)> = self
             .facts
             .values()
@@ -190,6 +334,118 @@ impl SemanticMemory {
     pub fn count(&self) -> usize {
         self.facts.values().map(|v| v.len()).sum()
     }
+
+    // ── Vector index ───────────────────────────────────────────
+
+    /// Whether the HNSW index is active.
+    pub fn has_index(&self) -> bool {
+        self.index.is_some()
+    }
+
+    /// Install a previously persisted index. Facts upserted afterwards with
+    /// an unchanged embedding are not re-inserted; call [`prune_index`]
+    /// once loading is done to drop entries for facts that no longer exist.
+    ///
+    /// [`prune_index`]: Self::prune_index
+    pub fn restore_index(&mut self, index: HnswIndex) {
+        self.index = Some(index);
+        self.index_dirty = false;
+    }
+
+    /// Drop index entries whose fact is gone or no longer embedded.
+    pub fn prune_index(&mut self) {
+        let Some(index) = &mut self.index else {
+            return;
+        };
+        let live: HashSet<String> = self
+            .facts
+            .iter()
+            .flat_map(|(category, facts)| {
+                facts
+                    .iter()
+                    .filter(|f| f.embedding.is_some())
+                    .map(move |f| index_id(&f.scope, category, &f.key))
+            })
+            .collect();
+        let stale: Vec<String> = index
+            .ids()
+            .filter(|id| !live.contains(*id))
+            .map(str::to_string)
+            .collect();
+        self.index_dirty |= !stale.is_empty();
+        for id in stale {
+            index.remove(&id);
+        }
+    }
+
+    /// Serialized index if it changed since the last call (for persistence).
+    pub fn take_index_changes(&mut self) -> Option<Vec<u8>> {
+        if !std::mem::take(&mut self.index_dirty) {
+            return None;
+        }
+        self.index.as_ref().map(HnswIndex::to_bytes)
+    }
+
+    fn index_embedding(&mut self, scope: &str, category: &str, key: &str, embedding: &[f32]) {
+        let id = index_id(scope, category, key);
+        match &mut self.index {
+            Some(index) if index.contains(&id, embedding) => return,
+            Some(index) if index.dims() == embedding.len() => {
+                index.insert(&id, embedding);
+            }
+            Some(index) => {
+                // Embedding space changed — start over; the rest of the facts
+                // join as they are re-embedded.
+                *index = HnswIndex::new(embedding.len());
+                index.insert(&id, embedding);
+            }
+            None if self.embedded >= ANN_THRESHOLD => self.build_index(embedding.len()),
+            None => return,
+        }
+        self.index_dirty = true;
+    }
+
+    fn unindex(&mut self, scope: &str, category: &str, key: &str) {
+        if let Some(index) = &mut self.index {
+            self.index_dirty |= index.remove(&index_id(scope, category, key));
+        }
+    }
+
+    /// Build the index from every fact embedded with `dims` dimensions.
+    fn build_index(&mut self, dims: usize) {
+        let mut index = HnswIndex::new(dims);
+        for (category, facts) in &self.facts {
+            for fact in facts {
+                if let Some(embedding) = &fact.embedding {
+                    index.insert(&index_id(&fact.scope, category, &fact.key), embedding);
+                }
+            }
+        }
+        tracing::info!(
+            vectors = index.len(),
+            dims,
+            "built HNSW index for semantic memory"
+        );
+        self.index = Some(index);
+    }
+}
+
+/// Global facts keep the `category␟key` ids they had before scopes, so
+/// persisted indexes stay valid.
+fn index_id(scope: &str, category: &str, key: &str) -> String {
+    if scope == crate::scope::GLOBAL {
+        format!("{category}{ID_SEPARATOR}{key}")
+    } else {
+        format!("{scope}{ID_SEPARATOR}{category}{ID_SEPARATOR}{key}")
+    }
+}
+
+fn parse_index_id(id: &str) -> Option<(&str, &str, &str)> {
+    let mut parts = id.splitn(3, ID_SEPARATOR);
+    match (parts.next()?, parts.next()?, parts.next()) {
+        (category, key, None) => Some((crate::scope::GLOBAL, category, key)),
+        (scope, category, Some(key)) => Some((scope, category, key)),
+    }
 }
 
 /// Compute cosine similarity between two vectors.
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use parking_lot::Mutex;
 use rusqlite::Connection;
 use std::path::Path;
@@ -5,8 +6,16 @@ use std::sync::Arc;
 use tracing::info;
 use uuid::Uuid;
 
+use crate::audit::{AuditKey, AuditReport, SegmentHeader};
+use crate::backup::{ImportReport, Manifest, MergeStrategy};
+use crate::decay::{Consolidation, DecayPolicy};
 use crate::episodic::EpisodicMemory;
-use crate::semantic::SemanticMemory;
+use crate::fts::{EpisodeHit, FactHit, MessageHit};
+use crate::graph::KnowledgeGraph;
+use crate::history::{FactConflict, FactVersion};
+use crate::hnsw::HnswIndex;
+use crate::knowledge::KnowledgeBase;
+use crate::semantic::{Fact, SemanticMemory};
 use crate::working::WorkingMemory;
 
 /// Unified memory store combining all three memory tiers.
@@ -14,6 +23,10 @@ pub struct MemoryStore {
     pub working: WorkingMemory,
     pub episodic: EpisodicMemory,
     pub semantic: SemanticMemory,
+    pub knowledge: KnowledgeBase,
+    pub graph: KnowledgeGraph,
+    decay: DecayPolicy,
+    audit_key: AuditKey,
     db: Arc<Mutex<Connection>>,
 }
 
@@ -22,124 +35,44 @@ impl MemoryStore {
     pub fn open(path: &Path) -> claw_core::Result<Self> {
         info!(?path, "opening memory store");
 
-        let conn =
+        let mut conn =
             Connection::open(path).map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
 
-        // Enable WAL mode for concurrent reads
-        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;")
-            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
-
-        // Create tables
+        // Enable WAL mode for concurrent reads. `INSERT OR REPLACE` only fires
+        // delete triggers (which keep the FTS indexes in sync) with recursive
+        // triggers on.
         conn.execute_batch(
-            "
-            CREATE TABLE IF NOT EXISTS episodes (
-                id TEXT PRIMARY KEY,
-                session_id TEXT NOT NULL,
-                summary TEXT NOT NULL,
-                outcome TEXT,
-                tags TEXT DEFAULT '[]',
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            );
-
-            CREATE TABLE IF NOT EXISTS episode_messages (
-                id TEXT PRIMARY KEY,
-                episode_id TEXT NOT NULL REFERENCES episodes(id),
-                role TEXT NOT NULL,
-                content TEXT NOT NULL,
-                timestamp TEXT NOT NULL
-            );
-
-            CREATE TABLE IF NOT EXISTS facts (
-                id TEXT PRIMARY KEY,
-                category TEXT NOT NULL,
-                key TEXT NOT NULL,
-                value TEXT NOT NULL,
-                confidence REAL DEFAULT 1.0,
-                source TEXT,
-                embedding BLOB,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                UNIQUE(category, key)
-            );
-
-            CREATE TABLE IF NOT EXISTS goals (
-                id TEXT PRIMARY KEY,
-                description TEXT NOT NULL,
-                status TEXT NOT NULL DEFAULT 'active',
-                priority INTEGER DEFAULT 5,
-                progress REAL DEFAULT 0.0,
-                parent_id TEXT REFERENCES goals(id),
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            );
-
-            CREATE TABLE IF NOT EXISTS goal_steps (
-                id TEXT PRIMARY KEY,
-                goal_id TEXT NOT NULL REFERENCES goals(id),
-                description TEXT NOT NULL,
-                status TEXT NOT NULL DEFAULT 'pending',
-                result TEXT,
-                created_at TEXT NOT NULL
-            );
-
-            CREATE TABLE IF NOT EXISTS audit_log (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                timestamp TEXT NOT NULL,
-                event_type TEXT NOT NULL,
-                action TEXT NOT NULL,
-                details TEXT,
-                checksum TEXT NOT NULL
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_episodes_session ON episodes(session_id);
-            CREATE INDEX IF NOT EXISTS idx_facts_category ON facts(category);
-            CREATE INDEX IF NOT EXISTS idx_goals_status ON goals(status);
-            CREATE INDEX IF NOT EXISTS idx_audit_timestamp ON audit_log(timestamp);
-
-            CREATE TABLE IF NOT EXISTS sessions (
-                id TEXT PRIMARY KEY,
-                name TEXT,
-                channel TEXT,
-                target TEXT,
-                active INTEGER DEFAULT 1,
-                message_count INTEGER DEFAULT 0,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            );
-            CREATE INDEX IF NOT EXISTS idx_sessions_active ON sessions(active);
-
-            CREATE TABLE IF NOT EXISTS session_messages (
-                session_id TEXT PRIMARY KEY,
-                messages_json TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            );
-
-            CREATE TABLE IF NOT EXISTS scheduled_tasks (
-                id TEXT PRIMARY KEY,
-                label TEXT,
-                description TEXT NOT NULL,
-                kind_json TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                session_id TEXT,
-                active INTEGER DEFAULT 1,
-                fire_count INTEGER DEFAULT 0,
-                last_fired TEXT
-            );
-            CREATE INDEX IF NOT EXISTS idx_scheduled_tasks_active ON scheduled_tasks(active);
-            ",
+            "PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL; PRAGMA recursive_triggers=ON;",
         )
         .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
 
+        // Bring the schema up to date, backing the file up first
+        let file = (path != Path::new(":memory:")).then_some(path);
+        crate::migrations::migrate(&mut conn, file)?;
+
+        // The audit log is sealed with a key kept next to the database
+        let audit_key = match file {
+            Some(path) => AuditKey::load_or_create(&crate::audit::key_path(path))?,
+            None => AuditKey::ephemeral()?,
+        };
+        crate::audit::seal_legacy(&conn, &audit_key)?;
+
         let db = Arc::new(Mutex::new(conn));
 
         let mut episodic = EpisodicMemory::new();
         episodic.set_db(Arc::clone(&db));
 
+        let knowledge = KnowledgeBase::open(Arc::clone(&db))?;
+        let graph = KnowledgeGraph::open(Arc::clone(&db));
+
         let mut store = Self {
             working: WorkingMemory::new(),
             episodic,
             semantic: SemanticMemory::new(),
+            knowledge,
+            graph,
+            decay: DecayPolicy::default(),
+            audit_key,
             db,
         };
 
@@ -170,17 +103,32 @@ impl MemoryStore {
         Self::open(Path::new(":memory:"))
     }
 
+    /// Schema version of the open database (see [`crate::migrations`]).
+    pub fn schema_version(&self) -> u32 {
+        crate::migrations::schema_version(&self.db.lock()).unwrap_or(0)
+    }
+
+    /// How facts decay, are reinforced and archived.
+    pub fn decay_policy(&self) -> &DecayPolicy {
+        &self.decay
+    }
+
+    /// Replace the fact decay policy.
+    pub fn set_decay_policy(&mut self, policy: DecayPolicy) {
+        self.decay = policy;
+    }
+
     /// Get a reference to the raw database connection (for advanced queries).
     pub fn db(&self) -> parking_lot::MutexGuard<'_, Connection> {
         self.db.lock()
     }
 
-    /// Persist a fact to SQLite (upsert by category+key), optionally with an embedding.
+    /// Persist a global fact to SQLite (upsert by category+key), optionally with an embedding.
     pub fn persist_fact(&self, category: &str, key: &str, value: &str) -> claw_core::Result<()> {
         self.persist_fact_with_embedding(category, key, value, None)
     }
 
-    /// Persist a fact with an optional embedding vector.
+    /// Persist a global fact with an optional embedding vector.
     pub fn persist_fact_with_embedding(
         &self,
         category: &str,
@@ -196,91 +144,582 @@ impl MemoryStore {
         db.execute(
             "INSERT INTO facts (id, category, key, value, confidence, source, embedding, created_at, updated_at)
              VALUES (?1, ?2, ?3, ?4, 1.0, 'agent', ?5, ?6, ?6)
-             ON CONFLICT(category, key) DO UPDATE SET value = excluded.value, embedding = COALESCE(excluded.embedding, facts.embedding), updated_at = excluded.updated_at",
+             ON CONFLICT(scope, category, key) DO UPDATE SET value = excluded.value, confidence = excluded.confidence, embedding = COALESCE(excluded.embedding, facts.embedding), updated_at = excluded.updated_at",
             rusqlite::params![id, category, key, value, embedding_blob, now],
         )
         .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
         Ok(())
     }
 
-    /// Delete a fact from SQLite by category and key.
-    pub fn delete_fact(&self, category: &str, key: &str) -> claw_core::Result<bool> {
+    /// Store a fact in semantic memory and SQLite. Its value, confidence and
+    /// source become a new version in the fact's history if the value
+    /// changed. Returns the fact it replaced, if any.
+    pub fn remember(&mut self, fact: Fact) -> claw_core::Result<Option<Fact>> {
+        let previous = self
+            .semantic
+            .get(&fact.scope, &fact.category, &fact.key)
+            .cloned();
+        let embedding_blob: Option<Vec<u8>> = fact
+            .embedding
+            .as_ref()
+            .map(|emb| emb.iter().flat_map(|f| f.to_le_bytes()).collect());
+        self.db
+            .lock()
+            .execute(
+                "INSERT INTO facts (id, scope, category, key, value, confidence, source, embedding, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                 ON CONFLICT(scope, category, key) DO UPDATE SET value = excluded.value,
+                    confidence = excluded.confidence, source = COALESCE(excluded.source, facts.source),
+                    embedding = COALESCE(excluded.embedding, facts.embedding),
+                    updated_at = excluded.updated_at",
+                rusqlite::params![
+                    fact.id.to_string(),
+                    fact.scope,
+                    fact.category,
+                    fact.key,
+                    fact.value,
+                    fact.confidence,
+                    fact.source,
+                    embedding_blob,
+                    fact.created_at.to_rfc3339(),
+                    fact.updated_at.to_rfc3339(),
+                ],
+            )
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+        self.semantic.upsert(fact);
+        Ok(previous)
+    }
+
+    /// Replace the stored embedding of a fact (SQLite and semantic memory).
+    pub fn update_fact_embedding(
+        &mut self,
+        scope: &str,
+        category: &str,
+        key: &str,
+        embedding: &[f32],
+    ) -> claw_core::Result<()> {
+        let blob: Vec<u8> = embedding.iter().flat_map(|f| f.to_le_bytes()).collect();
+        self.db
+            .lock()
+            .execute(
+                "UPDATE facts SET embedding = ?1 WHERE scope = ?2 AND category = ?3 AND key = ?4",
+                rusqlite::params![blob, scope, category, key],
+            )
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+        self.semantic
+            .set_embedding(scope, category, key, Some(embedding.to_vec()));
+        Ok(())
+    }
+
+    /// Read a value from the `memory_meta` key/value table.
+    pub fn meta(&self, key: &str) -> Option<String> {
+        self.db
+            .lock()
+            .query_row(
+                "SELECT value FROM memory_meta WHERE key = ?1",
+                rusqlite::params![key],
+                |row| row.get(0),
+            )
+            .ok()
+    }
+
+    /// Write a value to the `memory_meta` key/value table.
+    pub fn set_meta(&self, key: &str, value: &str) -> claw_core::Result<()> {
+        self.db
+            .lock()
+            .execute(
+                "INSERT INTO memory_meta (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                rusqlite::params![key, value],
+            )
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Delete a fact from SQLite by scope, category and key.
+    pub fn delete_fact(&self, scope: &str, category: &str, key: &str) -> claw_core::Result<bool> {
         let db = self.db.lock();
         let rows = db
             .execute(
-                "DELETE FROM facts WHERE category = ?1 AND key = ?2",
-                rusqlite::params![category, key],
+                "DELETE FROM facts WHERE scope = ?1 AND category = ?2 AND key = ?3",
+                rusqlite::params![scope, category, key],
             )
             .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
         Ok(rows > 0)
     }
 
-    /// Delete all facts in a category from SQLite. Returns number of rows deleted.
-    pub fn delete_facts_by_category(&self, category: &str) -> claw_core::Result<usize> {
+    /// Delete a scope's facts in a category from SQLite. Returns number of rows deleted.
+    pub fn delete_facts_by_category(
+        &self,
+        scope: &str,
+        category: &str,
+    ) -> claw_core::Result<usize> {
         let db = self.db.lock();
         let rows = db
             .execute(
-                "DELETE FROM facts WHERE category = ?1",
-                rusqlite::params![category],
+                "DELETE FROM facts WHERE scope = ?1 AND category = ?2",
+                rusqlite::params![scope, category],
             )
             .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
         Ok(rows)
     }
 
     /// Load all facts from SQLite into semantic memory. Returns number of facts loaded.
-    #[allow(clippy::type_complexity)]
+    ///
+    /// The persisted HNSW index is restored first, so only facts added or
+    /// re-embedded since it was saved are inserted into it.
     pub fn load_facts(&mut self) -> claw_core::Result<usize> {
-        let rows: Vec<(String, String, String, f64, Option<Vec<u8>>)> = {
+        let restored = !self.semantic.has_index()
+            && match self.load_vector_index() {
+                Some(index) => {
+                    self.semantic.restore_index(index);
+                    true
+                }
+                None => false,
+            };
+        let facts: Vec<Fact> = {
             let db = self.db.lock();
             let mut stmt = db
-                .prepare_cached("SELECT category, key, value, confidence, embedding FROM facts")
+                .prepare_cached(
+                    "SELECT category, key, value, confidence, source, embedding, created_at,
+                            updated_at, access_count, last_accessed, scope
+                     FROM facts",
+                )
                 .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
 
             stmt.query_map([], |row| {
-                Ok((
-                    row.get::<_, String>(0)?,
-                    row.get::<_, String>(1)?,
-                    row.get::<_, String>(2)?,
-                    row.get::<_, f64>(3)?,
-                    row.get::<_, Option<Vec<u8>>>(4)?,
-                ))
+                Ok(Fact {
+                    id: Uuid::new_v4(),
+                    scope: row.get(10)?,
+                    category: row.get(0)?,
+                    key: row.get(1)?,
+                    value: row.get(2)?,
+                    confidence: row.get::<_, Option<f64>>(3)?.unwrap_or(1.0),
+                    source: row.get(4)?,
+                    embedding: row.get::<_, Option<Vec<u8>>>(5)?.and_then(decode_embedding),
+                    created_at: parse_time(row.get(6)?),
+                    updated_at: parse_time(row.get(7)?),
+                    access_count: row.get::<_, i64>(8)?.clamp(0, u32::MAX as i64) as u32,
+                    last_accessed: row.get::<_, Option<String>>(9)?.map(parse_time),
+                })
             })
             .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?
             .filter_map(|r| r.ok())
             .collect::<Vec<_>>()
         };
 
-        let count = rows.len();
-        for (category, key, value, confidence, embedding_blob) in rows {
-            // Deserialize embedding from LE f32 bytes
-            let embedding = embedding_blob.and_then(|blob| {
-                if blob.len() % 4 != 0 {
-                    return None;
+        let count = facts.len();
+        for fact in facts {
+            self.semantic.upsert(fact);
+        }
+        if restored {
+            self.semantic.prune_index();
+        }
+        self.persist_vector_index()?;
+        Ok(count)
+    }
+
+    /// Read the persisted fact index; `None` if absent or unreadable.
+    fn load_vector_index(&self) -> Option<HnswIndex> {
+        let data: Vec<u8> = self
+            .db
+            .lock()
+            .query_row(
+                "SELECT data FROM vector_index WHERE name = 'facts'",
+                [],
+                |row| row.get(0),
+            )
+            .ok()?;
+        let index = HnswIndex::from_bytes(&data);
+        if index.is_none() {
+            tracing::warn!("persisted vector index is corrupt, rebuilding");
+        }
+        index
+    }
+
+    /// Save the semantic memory's HNSW index so the next start doesn't
+    /// rebuild it. No-op if the index is unchanged or not built yet.
+    pub fn persist_vector_index(&mut self) -> claw_core::Result<()> {
+        let Some(data) = self.semantic.take_index_changes() else {
+            return Ok(());
+        };
+        self.db
+            .lock()
+            .execute(
+                "INSERT INTO vector_index (name, data, updated_at) VALUES ('facts', ?1, ?2)
+                 ON CONFLICT(name) DO UPDATE SET data = excluded.data, updated_at = excluded.updated_at",
+                rusqlite::params![data, chrono::Utc::now().to_rfc3339()],
+            )
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+        Ok(())
+    }
+
+    // ── Export / import ────────────────────────────────────────────
+
+    /// Write the whole memory database (facts with embeddings, episodes,
+    /// goals, sessions and their messages, scheduled tasks, audit log) to
+    /// `out` as a JSONL archive. See [`crate::backup`] for the format.
+    pub fn export(&self, out: &mut dyn std::io::Write) -> claw_core::Result<Manifest> {
+        crate::backup::export(&self.db.lock(), out)
+    }
+
+    /// Merge an archive written by [`MemoryStore::export`] into this store,
+    /// then reload facts and episodes. The import is all-or-nothing. Imported
+    /// scheduled tasks and sessions are picked up on the next start.
+    pub fn import(
+        &mut self,
+        input: &mut dyn std::io::BufRead,
+        strategy: MergeStrategy,
+    ) -> claw_core::Result<ImportReport> {
+        let report = {
+            let db = self.db.lock();
+            let (report, sessions) = crate::backup::import(&db, input, strategy, &self.audit_key)?;
+            for json in sessions {
+                let messages: Vec<claw_core::Message> =
+                    serde_json::from_str(&json).unwrap_or_default();
+                crate::fts::index_messages(&db, &messages)
+                    .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+            }
+            report
+        };
+
+        self.load_facts()?;
+        let mut episodic = EpisodicMemory::new();
+        episodic.set_db(Arc::clone(&self.db));
+        episodic.load_from_db()?;
+        self.episodic = episodic;
+        Ok(report)
+    }
+
+    // ── Full-text search ───────────────────────────────────────────
+
+    /// BM25-ranked full-text search over facts in every scope.
+    pub fn search_facts(&self, query: &str, limit: usize) -> claw_core::Result<Vec<FactHit>> {
+        crate::fts::search_facts(&self.db.lock(), query, None, limit)
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))
+    }
+
+    /// BM25-ranked full-text search over all stored episodes, or only those
+    /// in `scopes`.
+    pub fn search_episodes(
+        &self,
+        query: &str,
+        scopes: Option<&[String]>,
+        limit: usize,
+    ) -> claw_core::Result<Vec<EpisodeHit>> {
+        crate::fts::search_episodes(&self.db.lock(), query, scopes, limit)
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))
+    }
+
+    /// BM25-ranked full-text search over persisted session messages,
+    /// optionally within one session.
+    pub fn search_messages(
+        &self,
+        query: &str,
+        session_id: Option<&Uuid>,
+        limit: usize,
+    ) -> claw_core::Result<Vec<MessageHit>> {
+        let sessions: Option<Vec<String>> = session_id.map(|id| vec![id.to_string()]);
+        crate::fts::search_messages(&self.db.lock(), query, sessions.as_deref(), limit)
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))
+    }
+
+    /// [`search_messages`](Self::search_messages) within these sessions
+    /// only — the conversations a caller may see.
+    pub fn search_messages_in(
+        &self,
+        query: &str,
+        sessions: &[Uuid],
+        limit: usize,
+    ) -> claw_core::Result<Vec<MessageHit>> {
+        let sessions: Vec<String> = sessions.iter().map(|id| id.to_string()).collect();
+        crate::fts::search_messages(&self.db.lock(), query, Some(&sessions), limit)
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))
+    }
+
+    /// Hybrid fact retrieval: BM25 keyword ranking and (when an embedding is
+    /// given) vector similarity ranking, merged with reciprocal rank fusion,
+    /// then weighted by each fact's decayed confidence and recall count (see
+    /// [`DecayPolicy::recall_weight`]). `scopes` limits retrieval to facts
+    /// in those scopes. Falls back to in-memory word matching if the FTS
+    /// query fails.
+    pub fn hybrid_fact_search(
+        &self,
+        query: &str,
+        query_embedding: Option<&[f32]>,
+        scopes: Option<&[String]>,
+        limit: usize,
+    ) -> Vec<(&Fact, f64)> {
+        let depth = limit * 2;
+        let in_scope = |f: &Fact| scopes.is_none_or(|s| s.contains(&f.scope));
+        let keyword: Vec<(String, String, String)> =
+            match crate::fts::search_facts(&self.db.lock(), query, scopes, depth) {
+                Ok(hits) => hits
+                    .into_iter()
+                    .map(|h| (h.scope, h.category, h.key))
+                    .collect(),
+                Err(e) => {
+                    tracing::warn!(error = %e, "fact full-text search failed, using word matching");
+                    self.semantic
+                        .search(query)
+                        .into_iter()
+                        .filter(|f| in_scope(f))
+                        .take(depth)
+                        .map(fact_key)
+                        .collect()
                 }
-                Some(
-                    blob.chunks_exact(4)
-                        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                        .collect::<Vec<f32>>(),
-                )
-            });
+            };
+        // Facts outside the scopes take up vector results, so look further
+        let vector: Vec<(String, String, String)> = query_embedding
+            .map(|emb| {
+                let wide = if scopes.is_some() { depth * 4 } else { depth };
+                self.semantic
+                    .vector_search(emb, wide)
+                    .into_iter()
+                    .filter(|(f, _)| in_scope(f))
+                    .take(depth)
+                    .map(|(f, _)| fact_key(f))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let now = Utc::now();
+        let mut ranked: Vec<(&Fact, f64)> = crate::fts::reciprocal_rank_fusion(&[vector, keyword])
+            .into_iter()
+            .filter_map(|((scope, category, key), score)| {
+                self.semantic
+                    .get(&scope, &category, &key)
+                    .map(|f| (f, score * self.decay.recall_weight(f, now)))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked.truncate(limit);
+        ranked
+    }
+
+    // ── History and conflicts ──────────────────────────────────────
+
+    /// Every value a fact has held, oldest first.
+    pub fn fact_history(
+        &self,
+        scope: &str,
+        category: &str,
+        key: &str,
+    ) -> claw_core::Result<Vec<FactVersion>> {
+        crate::history::history(&self.db.lock(), scope, category, key)
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))
+    }
+
+    /// Record that `current` replaced a contradicting `previous` value of
+    /// the same fact. Updates the fact's open conflict if it has one.
+    pub fn record_conflict(
+        &self,
+        previous: &Fact,
+        current: &Fact,
+        reason: Option<&str>,
+    ) -> claw_core::Result<FactConflict> {
+        crate::history::open_conflict(
+            &self.db.lock(),
+            &current.scope,
+            &current.category,
+            &current.key,
+            &previous.value,
+            previous.source.as_deref(),
+            &current.value,
+            current.source.as_deref(),
+            reason,
+        )
+        .map_err(|e| claw_core::ClawError::Memory(e.to_string()))
+    }
 
-            let fact = crate::semantic::Fact {
+    /// Fact conflicts, newest first. Resolved ones only if `include_resolved`.
+    pub fn fact_conflicts(&self, include_resolved: bool) -> claw_core::Result<Vec<FactConflict>> {
+        crate::history::conflicts(&self.db.lock(), include_resolved)
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))
+    }
+
+    /// Look up a conflict by id (or an unambiguous prefix of it).
+    pub fn fact_conflict(&self, id: &str) -> claw_core::Result<Option<FactConflict>> {
+        crate::history::conflict(&self.db.lock(), id)
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))
+    }
+
+    /// Resolve an open conflict by keeping `value` — by default the value
+    /// currently stored. Storing a different value records it as a new
+    /// version sourced from the resolution.
+    pub fn resolve_conflict(
+        &mut self,
+        id: &str,
+        value: Option<&str>,
+    ) -> claw_core::Result<FactConflict> {
+        let conflict = self
+            .fact_conflict(id)?
+            .ok_or_else(|| claw_core::ClawError::Memory(format!("no fact conflict '{id}'")))?;
+        if !conflict.is_open() {
+            return Err(claw_core::ClawError::Memory(format!(
+                "conflict {} is already resolved",
+                conflict.id
+            )));
+        }
+        let keep = value.unwrap_or(&conflict.value);
+        let current = self
+            .semantic
+            .get(&conflict.scope, &conflict.category, &conflict.key);
+        if current.is_none_or(|f| f.value != keep) {
+            let now = Utc::now();
+            self.remember(Fact {
                 id: Uuid::new_v4(),
-                category,
-                key,
-                value,
-                confidence,
-                source: Some("sqlite".to_string()),
-                embedding,
-                created_at: chrono::Utc::now(),
-                updated_at: chrono::Utc::now(),
+                scope: conflict.scope.clone(),
+                category: conflict.category.clone(),
+                key: conflict.key.clone(),
+                value: keep.to_string(),
+                confidence: 1.0,
+                source: Some(format!("conflict:{}", conflict.id)),
+                embedding: None,
+                created_at: now,
+                updated_at: now,
+                access_count: 0,
+                last_accessed: None,
+            })?;
+        }
+        let db = self.db.lock();
+        crate::history::mark_resolved(&db, &conflict.id, keep)
+            .and_then(|()| crate::history::conflict(&db, &conflict.id))
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?
+            .ok_or_else(|| claw_core::ClawError::Memory(format!("no fact conflict '{id}'")))
+    }
+
+    // ── Decay and consolidation ────────────────────────────────────
+
+    /// Record that facts were recalled: bump their access counts, stamp the
+    /// recall time and reinforce their confidence. Facts are given as
+    /// (scope, category, key); unknown ones are skipped. Returns the number
+    /// of facts updated.
+    pub fn reinforce_facts(
+        &mut self,
+        facts: &[(String, String, String)],
+    ) -> claw_core::Result<usize> {
+        let now = Utc::now();
+        let stamp = now.to_rfc3339();
+        let mut db = self.db.lock();
+        let tx = db
+            .transaction()
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+        let mut updated = 0;
+        for (scope, category, key) in facts {
+            let Some(confidence) = self
+                .semantic
+                .get(scope, category, key)
+                .map(|f| self.decay.reinforced(f, now))
+            else {
+                continue;
             };
-            self.semantic.upsert(fact);
+            let Some(fact) = self
+                .semantic
+                .record_access(scope, category, key, confidence, now)
+            else {
+                continue;
+            };
+            tx.execute(
+                "UPDATE facts SET access_count = ?1, last_accessed = ?2, confidence = ?3
+                 WHERE scope = ?4 AND category = ?5 AND key = ?6",
+                rusqlite::params![
+                    fact.access_count as i64,
+                    stamp,
+                    confidence,
+                    scope,
+                    category,
+                    key
+                ],
+            )
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+            updated += 1;
         }
-        Ok(count)
+        tx.commit()
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+        Ok(updated)
     }
 
-    /// Write an audit log entry with a tamper-evident checksum.
+    /// Move facts whose decayed confidence has fallen below the policy's
+    /// threshold to the `facts_archive` table, and delete archived facts
+    /// older than the retention period. All-or-nothing.
+    pub fn consolidate_facts(&mut self) -> claw_core::Result<Consolidation> {
+        let now = Utc::now();
+        let stale: Vec<((String, String, String), f64)> = self
+            .semantic
+            .all_facts()
+            .into_iter()
+            .filter(|f| self.decay.is_stale(f, now))
+            .map(|f| (fact_key(f), self.decay.effective_confidence(f, now)))
+            .collect();
+
+        let mut report = Consolidation::default();
+        {
+            let mut db = self.db.lock();
+            let tx = db
+                .transaction()
+                .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+            let stamp = now.to_rfc3339();
+            for ((scope, category, key), confidence) in &stale {
+                tx.execute(
+                    "INSERT OR REPLACE INTO facts_archive
+                        (id, scope, category, key, value, confidence, source, embedding,
+                         created_at, updated_at, access_count, last_accessed, archived_at)
+                     SELECT id, scope, category, key, value, ?4, source, embedding, created_at,
+                            updated_at, access_count, last_accessed, ?5
+                     FROM facts WHERE scope = ?1 AND category = ?2 AND key = ?3",
+                    rusqlite::params![scope, category, key, confidence, stamp],
+                )
+                .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+                report.archived += tx
+                    .execute(
+                        "DELETE FROM facts WHERE scope = ?1 AND category = ?2 AND key = ?3",
+                        rusqlite::params![scope, category, key],
+                    )
+                    .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+            }
+            if self.decay.archive_retention_days > 0 {
+                let cutoff = now
+                    - chrono::Duration::days(
+                        self.decay.archive_retention_days.min(i32::MAX as u64) as i64,
+                    );
+                report.purged = tx
+                    .execute(
+                        "DELETE FROM facts_archive WHERE archived_at < ?1",
+                        rusqlite::params![cutoff.to_rfc3339()],
+                    )
+                    .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+            }
+            tx.commit()
+                .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+        }
+
+        for ((scope, category, key), _) in &stale {
+            self.semantic.remove(scope, category, key);
+        }
+        if report.archived + report.purged > 0 {
+            info!(
+                archived = report.archived,
+                purged = report.purged,
+                "consolidated semantic memory"
+            );
+        }
+        self.persist_vector_index()?;
+        Ok(report)
+    }
+
+    /// Number of archived facts.
+    pub fn archived_fact_count(&self) -> claw_core::Result<usize> {
+        self.db
+            .lock()
+            .query_row("SELECT count(*) FROM facts_archive", [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .map(|n| n as usize)
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))
+    }
+
+    /// Write an audit log entry, sealed into the log's hash chain.
     pub fn audit(
         &self,
         event_type: &str,
@@ -288,24 +727,37 @@ impl MemoryStore {
         details: Option<&str>,
     ) -> claw_core::Result<()> {
         let timestamp = chrono::Utc::now().to_rfc3339();
-        let checksum_input = format!(
-            "{}:{}:{}:{}",
-            timestamp,
+        crate::audit::append(
+            &self.db.lock(),
+            &self.audit_key,
+            &timestamp,
             event_type,
             action,
-            details.unwrap_or("")
-        );
-        // Simple checksum — production would use HMAC with a device-local key
-        let checksum = format!("{:x}", md5_hash(checksum_input.as_bytes()));
+            details,
+        )?;
+        Ok(())
+    }
 
-        let db = self.db.lock();
-        db.execute(
-            "INSERT INTO audit_log (timestamp, event_type, action, details, checksum) VALUES (?1, ?2, ?3, ?4, ?5)",
-            rusqlite::params![timestamp, event_type, action, details, checksum],
-        )
-        .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+    /// Check the audit log's hash chain for edited, deleted or reordered
+    /// entries.
+    pub fn verify_audit(&self) -> claw_core::Result<AuditReport> {
+        crate::audit::verify(&self.db.lock(), &self.audit_key)
+    }
 
-        Ok(())
+    /// Export chained audit entries with ids in `from..=to` as a signed
+    /// JSONL segment (see [`crate::audit`]).
+    pub fn export_audit(
+        &self,
+        from: Option<i64>,
+        to: Option<i64>,
+        out: &mut dyn std::io::Write,
+    ) -> claw_core::Result<SegmentHeader> {
+        crate::audit::export_segment(&self.db.lock(), &self.audit_key, from, to, out)
+    }
+
+    /// The key audit entries are sealed with.
+    pub fn audit_key(&self) -> &AuditKey {
+        &self.audit_key
     }
 
     /// Read recent audit log entries.
@@ -577,21 +1029,50 @@ impl MemoryStore {
         Ok(rows)
     }
 
+    /// Every persisted session opened on a channel: (id, channel, target).
+    pub fn session_targets(&self) -> claw_core::Result<Vec<(Uuid, String, String)>> {
+        let db = self.db.lock();
+        let mut stmt = db
+            .prepare_cached(
+                "SELECT id, channel, target FROM sessions
+                 WHERE channel IS NOT NULL AND target IS NOT NULL",
+            )
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .filter_map(|(id, channel, target)| Some((id.parse().ok()?, channel, target)))
+            .collect();
+        Ok(rows)
+    }
+
     /// Delete empty sessions (0 messages, no name) from SQLite to prevent clutter.
     pub fn cleanup_empty_sessions(&self) -> claw_core::Result<usize> {
         let db = self.db.lock();
         let deleted = db
             .execute("DELETE FROM sessions WHERE message_count = 0", [])
             .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
-        // Also clean up orphaned session_messages
+        // Also clean up orphaned session_messages and their search index
         let _ = db.execute(
             "DELETE FROM session_messages WHERE session_id NOT IN (SELECT id FROM sessions)",
             [],
         );
+        let _ = db.execute(
+            "DELETE FROM message_text WHERE session_id NOT IN (SELECT id FROM sessions)",
+            [],
+        );
         Ok(deleted)
     }
 
-    /// Persist session messages (working memory) to SQLite as a JSON blob.
+    /// Persist session messages (working memory) to SQLite as a JSON blob,
+    /// and add their text to the full-text index.
     pub fn persist_session_messages(
         &self,
         session_id: &Uuid,
@@ -610,6 +1091,8 @@ impl MemoryStore {
             rusqlite::params![session_id.to_string(), json, now],
         )
         .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
+        crate::fts::index_messages(&db, messages)
+            .map_err(|e| claw_core::ClawError::Memory(e.to_string()))?;
         Ok(())
     }
 
@@ -731,10 +1214,26 @@ pub struct ScheduledTaskRow {
     pub last_fired: Option<String>,
 }
 
-/// Simple hash for audit checksums (would use blake3 or HMAC in production).
-fn md5_hash(data: &[u8]) -> u64 {
-    use std::hash::{Hash, Hasher};
-    let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    data.hash(&mut hasher);
-    hasher.finish()
+/// Deserialize an embedding stored as LE f32 bytes.
+/// A fact's identity: (scope, category, key).
+fn fact_key(fact: &Fact) -> (String, String, String) {
+    (fact.scope.clone(), fact.category.clone(), fact.key.clone())
+}
+
+fn decode_embedding(blob: Vec<u8>) -> Option<Vec<f32>> {
+    if !blob.len().is_multiple_of(4) {
+        return None;
+    }
+    Some(
+        blob.chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect(),
+    )
+}
+
+/// Parse a stored RFC 3339 timestamp, treating unreadable ones as now.
+fn parse_time(value: String) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(&value)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
 }
@@ -1,6 +1,15 @@
+use std::sync::Arc;
+
 use claw_core::Message;
 use uuid::Uuid;
 
+/// Counts the prompt tokens a message occupies (e.g. a BPE tokenizer for the
+/// active model). Without one, `Message::estimate_tokens` is used.
+pub type TokenCounter = Arc<dyn Fn(&Message) -> usize + Send + Sync>;
+
+/// Bounds for the provider-reported / locally-counted correction factor.
+const CALIBRATION_RANGE: (f64, f64) = (0.5, 2.0);
+
 /// Working memory — the current conversation context held in RAM.
 ///
 /// This is what gets sent to the LLM as context. It manages window sizing,
@@ -8,6 +17,8 @@ use uuid::Uuid;
 pub struct WorkingMemory {
     /// Active session messages.
     sessions: std::collections::HashMap<Uuid, SessionContext>,
+    /// Token counter for new and recounted messages.
+    counter: Option<TokenCounter>,
 }
 
 /// Per-session working memory.
@@ -15,8 +26,14 @@ pub struct SessionContext {
     pub session_id: Uuid,
     pub messages: Vec<Message>,
     pub system_prompt: Option<String>,
-    /// Total estimated token count of this context.
+    /// Total estimated token count of this context (`counted_tokens` scaled
+    /// by `calibration`).
     pub estimated_tokens: usize,
+    /// Raw token count from the token counter.
+    pub counted_tokens: usize,
+    /// Ratio of provider-reported to locally-counted tokens, learned from
+    /// the usage of previous calls. 1.0 until a call has been recorded.
+    pub calibration: f64,
     /// Maximum tokens before we need to compact (model context window).
     pub max_tokens: usize,
     /// Compact at this fraction of max_tokens (e.g. 0.75).
@@ -35,9 +52,24 @@ impl WorkingMemory {
     pub fn new() -> Self {
         Self {
             sessions: std::collections::HashMap::new(),
+            counter: None,
+        }
+    }
+
+    /// Count tokens with `counter` from now on, and recount existing sessions.
+    pub fn set_token_counter(&mut self, counter: TokenCounter) {
+        self.counter = Some(counter);
+        let ids: Vec<Uuid> = self.sessions.keys().copied().collect();
+        for id in ids {
+            self.recount_tokens(id);
         }
     }
 
+    /// Token count of a single message with the configured counter.
+    pub fn count_tokens(&self, message: &Message) -> usize {
+        count_message(&self.counter, message)
+    }
+
     /// Get or create a session context.
     pub fn session(&mut self, session_id: Uuid) -> &mut SessionContext {
         self.sessions
@@ -47,6 +79,8 @@ impl WorkingMemory {
                 messages: Vec::new(),
                 system_prompt: None,
                 estimated_tokens: 0,
+                counted_tokens: 0,
+                calibration: 1.0,
                 max_tokens: 128_000,
                 compaction_threshold: 0.75,
                 compaction_count: 0,
@@ -63,9 +97,10 @@ impl WorkingMemory {
     /// Add a message to a session.
     pub fn push(&mut self, message: Message) {
         let session_id = message.session_id;
+        let token_count = self.count_tokens(&message);
         let ctx = self.session(session_id);
-        let token_estimate = message.estimate_tokens();
-        ctx.estimated_tokens += token_estimate;
+        ctx.counted_tokens += token_count;
+        ctx.estimated_tokens = ctx.calibrated(ctx.counted_tokens);
         ctx.messages.push(message);
 
         // Auto-compact if over threshold
@@ -166,8 +201,7 @@ impl WorkingMemory {
             .push(Message::text(session_id, claw_core::Role::System, &summary));
         ctx.messages.extend(recent);
 
-        // Recount tokens
-        ctx.estimated_tokens = ctx.messages.iter().map(|m| m.estimate_tokens()).sum();
+        ctx.recount(&self.counter);
         ctx.compaction_count += 1;
 
         Some(summary)
@@ -274,8 +308,7 @@ impl WorkingMemory {
             ctx.messages.push(summary_msg);
             ctx.messages.extend(recent);
 
-            // Recount tokens accurately
-            ctx.estimated_tokens = ctx.messages.iter().map(|m| m.estimate_tokens()).sum();
+            ctx.recount(&self.counter);
             ctx.compaction_count += 1;
         }
     }
@@ -283,8 +316,30 @@ impl WorkingMemory {
     /// Force recount tokens (useful after external modifications).
     pub fn recount_tokens(&mut self, session_id: Uuid) {
         if let Some(ctx) = self.sessions.get_mut(&session_id) {
-            ctx.estimated_tokens = ctx.messages.iter().map(|m| m.estimate_tokens()).sum();
+            ctx.recount(&self.counter);
+        }
+    }
+
+    /// Correct the session's estimate with the prompt size a provider reported
+    /// for a call that sent the session's current messages. `overhead_tokens`
+    /// is the locally-counted size of everything else in the prompt (system
+    /// prompt, tool schemas).
+    pub fn record_prompt_tokens(
+        &mut self,
+        session_id: Uuid,
+        reported_tokens: usize,
+        overhead_tokens: usize,
+    ) {
+        let Some(ctx) = self.sessions.get_mut(&session_id) else {
+            return;
+        };
+        let reported_messages = reported_tokens.saturating_sub(overhead_tokens);
+        if ctx.counted_tokens == 0 || reported_messages == 0 {
+            return;
         }
+        let (min, max) = CALIBRATION_RANGE;
+        ctx.calibration = (reported_messages as f64 / ctx.counted_tokens as f64).clamp(min, max);
+        ctx.estimated_tokens = ctx.calibrated(ctx.counted_tokens);
     }
 
     /// Clear a session's working memory.
@@ -297,3 +352,25 @@ impl WorkingMemory {
         self.sessions.keys().copied().collect()
     }
 }
+
+impl SessionContext {
+    fn calibrated(&self, tokens: usize) -> usize {
+        (tokens as f64 * self.calibration).round() as usize
+    }
+
+    fn recount(&mut self, counter: &Option<TokenCounter>) {
+        self.counted_tokens = self
+            .messages
+            .iter()
+            .map(|m| count_message(counter, m))
+            .sum();
+        self.estimated_tokens = self.calibrated(self.counted_tokens);
+    }
+}
+
+fn count_message(counter: &Option<TokenCounter>, message: &Message) -> usize {
+    match counter {
+        Some(count) => count(message),
+        None => message.estimate_tokens(),
+    }
+}
@@ -13,6 +13,7 @@ mod tests {
             Episode {
                 id: Uuid::new_v4(),
                 session_id: Uuid::new_v4(),
+                scope: "global".into(),
                 summary: summary.to_string(),
                 outcome: None,
                 tags: tags.into_iter().map(String::from).collect(),
@@ -97,6 +98,7 @@ mod tests {
         fn make_fact(cat: &str, key: &str, val: &str) -> Fact {
             Fact {
                 id: Uuid::new_v4(),
+                scope: "global".into(),
                 category: cat.to_string(),
                 key: key.to_string(),
                 value: val.to_string(),
@@ -105,6 +107,8 @@ mod tests {
                 embedding: None,
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
+                access_count: 0,
+                last_accessed: None,
             }
         }
 
@@ -113,7 +117,7 @@ mod tests {
             let mut mem = SemanticMemory::new();
             mem.upsert(make_fact("user", "name", "Alice"));
             assert_eq!(mem.count(), 1);
-            let fact = mem.get("user", "name").unwrap();
+            let fact = mem.get("global", "user", "name").unwrap();
             assert_eq!(fact.value, "Alice");
         }
 
@@ -123,7 +127,7 @@ mod tests {
             mem.upsert(make_fact("user", "name", "Alice"));
             mem.upsert(make_fact("user", "name", "Bob"));
             assert_eq!(mem.count(), 1);
-            let fact = mem.get("user", "name").unwrap();
+            let fact = mem.get("global", "user", "name").unwrap();
             assert_eq!(fact.value, "Bob");
         }
 
@@ -181,6 +185,172 @@ mod tests {
             assert_eq!(results[1].0.key, "c");
         }
 
+        /// Deterministic pseudo-random unit-ish vectors for index tests.
+        pub(crate) fn random_vectors(n: usize, dims: usize, seed: u64) -> Vec<Vec<f32>> {
+            let mut state = seed;
+            (0..n)
+                .map(|_| {
+                    (0..dims)
+                        .map(|_| {
+                            state = state
+                                .wrapping_mul(6364136223846793005)
+                                .wrapping_add(1442695040888963407);
+                            ((state >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+
+        #[test]
+        fn test_hnsw_recall_matches_exact_search() {
+            let mut mem = SemanticMemory::new();
+            let vectors = random_vectors(1500, 16, 7);
+            for (i, v) in vectors.iter().enumerate() {
+                let mut f = make_fact("embed", &format!("k{i}"), "v");
+                f.embedding = Some(v.clone());
+                mem.upsert(f);
+            }
+            assert!(mem.has_index());
+
+            let mut hits = 0;
+            let queries = random_vectors(20, 16, 99);
+            for q in &queries {
+                let exact: Vec<String> = mem
+                    .exact_search(q, 10)
+                    .iter()
+                    .map(|(f, _)| f.key.clone())
+                    .collect();
+                let approx = mem.vector_search(q, 10);
+                assert_eq!(approx.len(), 10);
+                hits += approx
+                    .iter()
+                    .filter(|(f, _)| exact.contains(&f.key))
+                    .count();
+            }
+            let recall = hits as f64 / (queries.len() * 10) as f64;
+            assert!(recall >= 0.9, "recall@10 = {recall}");
+
+            // Removals are reflected immediately
+            let top = mem.vector_search(&vectors[42], 1);
+            assert_eq!(top[0].0.key, "k42");
+            assert!(mem.remove("global", "embed", "k42"));
+            assert!(
+                mem.vector_search(&vectors[42], 5)
+                    .iter()
+                    .all(|(f, _)| f.key != "k42")
+            );
+            assert_eq!(mem.remove_category("global", "embed"), 1499);
+            assert!(mem.vector_search(&vectors[0], 5).is_empty());
+        }
+
+        #[test]
+        fn test_small_store_uses_exact_search() {
+            let mut mem = SemanticMemory::new();
+            for (i, v) in random_vectors(50, 8, 3).into_iter().enumerate() {
+                let mut f = make_fact("embed", &format!("k{i}"), "v");
+                f.embedding = Some(v);
+                mem.upsert(f);
+            }
+            assert!(!mem.has_index());
+        }
+
+        #[test]
+        fn test_hnsw_serialization_round_trip() {
+            use claw_memory::HnswIndex;
+
+            let mut index = HnswIndex::new(8);
+            let vectors = random_vectors(200, 8, 11);
+            for (i, v) in vectors.iter().enumerate() {
+                index.insert(&format!("id{i}"), v);
+            }
+            index.remove("id3");
+            assert!(!index.insert("bad", &[1.0, 2.0]));
+
+            let bytes = index.to_bytes();
+            let restored = HnswIndex::from_bytes(&bytes).unwrap();
+            assert_eq!(restored.len(), 199);
+            assert_eq!(
+                restored.search(&vectors[10], 3, 32),
+                index.search(&vectors[10], 3, 32)
+            );
+            assert!(HnswIndex::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+            assert!(HnswIndex::from_bytes(b"nope").is_none());
+        }
+
+        #[test]
+        fn test_hnsw_rejects_corrupt_index() {
+            use claw_memory::HnswIndex;
+
+            let mut index = HnswIndex::new(4);
+            let vectors = random_vectors(40, 4, 11);
+            for (i, v) in vectors.iter().enumerate() {
+                index.insert(&format!("id{i}"), v);
+            }
+            let bytes = index.to_bytes();
+            let header = &bytes[..5];
+
+            // A hand-written index: (id, links per layer) with 4-d vectors
+            let blob = |entry: u32, max_level: u32, nodes: &[(&str, Vec<Vec<u32>>)]| {
+                let mut out = header.to_vec();
+                for v in [4, entry, max_level] {
+                    out.extend_from_slice(&v.to_le_bytes());
+                }
+                out.extend_from_slice(&0u64.to_le_bytes());
+                out.extend_from_slice(&(nodes.len() as u32).to_le_bytes());
+                for (id, links) in nodes {
+                    out.push(0);
+                    out.extend_from_slice(&(id.len() as u32).to_le_bytes());
+                    out.extend_from_slice(id.as_bytes());
+                    out.extend_from_slice(&[0u8; 16]);
+                    out.extend_from_slice(&(links.len() as u32).to_le_bytes());
+                    for layer in links {
+                        out.extend_from_slice(&(layer.len() as u32).to_le_bytes());
+                        for n in layer {
+                            out.extend_from_slice(&n.to_le_bytes());
+                        }
+                    }
+                }
+                out
+            };
+            let valid = blob(0, 1, &[("a", vec![vec![1], vec![]]), ("b", vec![vec![0]])]);
+            assert!(HnswIndex::from_bytes(&valid).is_some());
+            // "b" doesn't live on layer 1
+            let bad_layer = blob(0, 1, &[("a", vec![vec![1], vec![1]]), ("b", vec![vec![0]])]);
+            assert!(HnswIndex::from_bytes(&bad_layer).is_none());
+            // Entry point below max_level
+            let short_entry = blob(1, 1, &[("a", vec![vec![1], vec![]]), ("b", vec![vec![0]])]);
+            assert!(HnswIndex::from_bytes(&short_entry).is_none());
+            // No layers at all
+            let no_layers = blob(0, 0, &[("a", vec![vec![]]), ("b", vec![])]);
+            assert!(HnswIndex::from_bytes(&no_layers).is_none());
+            // Duplicate live id
+            let duplicate = blob(0, 0, &[("a", vec![vec![1]]), ("a", vec![vec![0]])]);
+            assert!(HnswIndex::from_bytes(&duplicate).is_none());
+
+            // Huge counts fail on the bytes left instead of allocating
+            let mut huge_levels = valid.clone();
+            let at = huge_levels.len() - 8;
+            huge_levels[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(HnswIndex::from_bytes(&huge_levels).is_none());
+            for offset in [5, 25] {
+                // dims, node count
+                let mut huge = bytes.clone();
+                huge[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+                assert!(HnswIndex::from_bytes(&huge).is_none(), "offset {offset}");
+            }
+
+            // No single damaged byte makes loading or searching panic
+            for i in 0..bytes.len() {
+                let mut damaged = bytes.clone();
+                damaged[i] ^= 0xFF;
+                if let Some(index) = HnswIndex::from_bytes(&damaged) {
+                    index.search(&vectors[0], 3, 16);
+                }
+                assert!(HnswIndex::from_bytes(&bytes[..i]).is_none());
+            }
+        }
+
         #[test]
         fn test_all_facts() {
             let mut mem = SemanticMemory::new();
@@ -287,6 +457,27 @@ mod tests {
             let sessions = wm.active_sessions();
             assert_eq!(sessions.len(), 2);
         }
+
+        #[test]
+        fn test_token_counter_and_calibration() {
+            let mut wm = WorkingMemory::new();
+            let sid = Uuid::new_v4();
+            wm.push(Message::text(sid, Role::User, "hello"));
+            wm.set_token_counter(std::sync::Arc::new(|_: &Message| 100));
+            wm.push(Message::text(sid, Role::Assistant, "hi"));
+            assert_eq!(wm.session(sid).estimated_tokens, 200);
+
+            // Provider saw 50 overhead + 300 message tokens → factor 1.5
+            wm.record_prompt_tokens(sid, 350, 50);
+            assert_eq!(wm.session(sid).estimated_tokens, 300);
+            wm.push(Message::text(sid, Role::User, "more"));
+            assert_eq!(wm.session(sid).counted_tokens, 300);
+            assert_eq!(wm.session(sid).estimated_tokens, 450);
+
+            // Wild reports are clamped
+            wm.record_prompt_tokens(sid, 100_000, 0);
+            assert_eq!(wm.session(sid).calibration, 2.0);
+        }
     }
 
     // ── Memory Store (SQLite roundtrip) ─────────────────────────
@@ -326,7 +517,7 @@ mod tests {
             let count = store.load_facts().unwrap();
             // load_facts is also called in open(), but we added 2 more
             assert!(count >= 2);
-            let fact = store.semantic.get("user", "name").unwrap();
+            let fact = store.semantic.get("global", "user", "name").unwrap();
             assert_eq!(fact.value, "Alice");
         }
 
@@ -366,5 +557,1743 @@ mod tests {
                 .unwrap();
             assert_eq!(val, "Bob");
         }
+
+        #[test]
+        fn test_fts_facts_ranked_and_kept_in_sync() {
+            let store = MemoryStore::open_in_memory().unwrap();
+            store
+                .persist_fact("infra", "deploy_target", "Production runs on Kubernetes")
+                .unwrap();
+            store
+                .persist_fact(
+                    "notes",
+                    "misc",
+                    "kubernetes mentioned in passing, also docker and nginx",
+                )
+                .unwrap();
+            store.persist_fact("user", "name", "Alice").unwrap();
+
+            // Stemming: "deployments" matches "deploy_target"'s key tokens
+            let hits = store.search_facts("kubernetes deployments", 10).unwrap();
+            assert_eq!(hits.len(), 2);
+            assert_eq!(hits[0].key, "deploy_target");
+            assert!(hits[0].score > hits[1].score);
+
+            store
+                .persist_fact("infra", "deploy_target", "Production runs on Nomad")
+                .unwrap();
+            let hits = store.search_facts("kubernetes", 10).unwrap();
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].key, "misc");
+
+            store.delete_fact("global", "notes", "misc").unwrap();
+            assert!(store.search_facts("kubernetes", 10).unwrap().is_empty());
+            // Operators and punctuation in user input are treated as text
+            assert!(store.search_facts("\"NOT (AND* :", 10).unwrap().is_empty());
+        }
+
+        #[test]
+        fn test_fts_episodes_across_full_history() {
+            use chrono::Utc;
+            use claw_memory::Episode;
+            use uuid::Uuid;
+
+            let mut store = MemoryStore::open_in_memory().unwrap();
+            let mut episode = Episode {
+                id: Uuid::new_v4(),
+                session_id: Uuid::new_v4(),
+                scope: "global".into(),
+                summary: "Migrated the billing database to Postgres".into(),
+                outcome: Some("success".into()),
+                tags: vec!["billing".into()],
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            };
+            store.episodic.record(episode.clone());
+            // Re-recording replaces the row; the index must follow
+            episode.summary = "Migrated the billing database to MySQL".into();
+            store.episodic.record(episode.clone());
+
+            let hits = store.search_episodes("postgres", None, 5).unwrap();
+            assert!(hits.is_empty());
+            let hits = store.search_episodes("billing mysql", None, 5).unwrap();
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].episode.id, episode.id);
+            assert_eq!(hits[0].episode.tags, vec!["billing".to_string()]);
+        }
+
+        #[test]
+        fn test_fts_session_messages_indexed_and_backfilled() {
+            use claw_core::{Message, Role};
+            use uuid::Uuid;
+
+            let dir = tempfile::tempdir().unwrap();
+            let db_path = dir.path().join("test.db");
+            let s1 = Uuid::new_v4();
+            let s2 = Uuid::new_v4();
+            {
+                let store = MemoryStore::open(&db_path).unwrap();
+                let mut messages = vec![
+                    Message::text(s1, Role::User, "How do I rotate the TLS certificate?"),
+                    Message::text(s1, Role::Assistant, "Run certbot renew on the proxy."),
+                ];
+                store.persist_session_messages(&s1, &messages).unwrap();
+                // Re-persisting the grown conversation doesn't duplicate rows
+                messages.push(Message::text(s1, Role::User, "thanks"));
+                store.persist_session_messages(&s1, &messages).unwrap();
+                store
+                    .persist_session_messages(
+                        &s2,
+                        &[Message::text(
+                            s2,
+                            Role::User,
+                            "certificate for the mail server",
+                        )],
+                    )
+                    .unwrap();
+
+                let hits = store.search_messages("certificate", None, 10).unwrap();
+                assert_eq!(hits.len(), 2);
+                let hits = store.search_messages("certificate", Some(&s1), 10).unwrap();
+                assert_eq!(hits.len(), 1);
+                assert!(
+                    hits[0].snippet.contains("[certificate]"),
+                    "{}",
+                    hits[0].snippet
+                );
+                assert_eq!(hits[0].role, "user");
+
+                // Simulate a database from before the message index existed
+                store
+                    .db()
+                    .execute_batch(
+                        "DROP TABLE messages_fts; DROP TABLE message_text; PRAGMA user_version = 1;",
+                    )
+                    .unwrap();
+            }
+
+            let store = MemoryStore::open(&db_path).unwrap();
+            let hits = store.search_messages("certbot", None, 10).unwrap();
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].session_id, s1.to_string());
+            assert_eq!(
+                store
+                    .search_messages("certificate", None, 10)
+                    .unwrap()
+                    .len(),
+                2
+            );
+        }
+
+        #[test]
+        fn test_hybrid_fact_search_fuses_keyword_and_vector_rankings() {
+            let mut store = MemoryStore::open_in_memory().unwrap();
+            let facts: [(&str, &str, [f32; 3]); 3] = [
+                (
+                    "server_ip",
+                    "The staging server is at 10.0.0.5",
+                    [1.0, 0.0, 0.0],
+                ),
+                (
+                    "server_os",
+                    "The staging server runs Debian",
+                    [0.0, 1.0, 0.0],
+                ),
+                ("favourite_color", "blue", [0.9, 0.1, 0.0]),
+            ];
+            for (key, value, emb) in &facts {
+                store
+                    .persist_fact_with_embedding("infra", key, value, Some(emb))
+                    .unwrap();
+            }
+            store.load_facts().unwrap();
+
+            // Keyword only
+            let hits = store.hybrid_fact_search("debian", None, None, 5);
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].0.key, "server_os");
+
+            // Both rankers agree on server_ip → it wins; the vector-only and
+            // keyword-only matches follow
+            let hits = store.hybrid_fact_search("staging server", Some(&[1.0, 0.0, 0.0]), None, 5);
+            assert_eq!(hits[0].0.key, "server_ip");
+            let keys: Vec<&str> = hits.iter().map(|(f, _)| f.key.as_str()).collect();
+            assert_eq!(keys.len(), 3);
+            assert!(keys.contains(&"favourite_color"));
+        }
+
+        #[test]
+        fn test_match_query_and_rank_fusion() {
+            use claw_memory::fts::{match_query, reciprocal_rank_fusion};
+
+            assert_eq!(
+                match_query("Deploy the app-server, deploy!").as_deref(),
+                Some("\"deploy\" OR \"the\" OR \"app\" OR \"server\"")
+            );
+            assert_eq!(match_query("a ? !"), None);
+
+            let fused = reciprocal_rank_fusion(&[vec!["a", "b", "c"], vec!["b", "c"]]);
+            let order: Vec<&str> = fused.iter().map(|(k, _)| *k).collect();
+            assert_eq!(order, vec!["b", "c", "a"]);
+        }
+
+        #[test]
+        fn test_vector_index_persisted_and_reconciled() {
+            let dir = tempfile::tempdir().unwrap();
+            let db_path = dir.path().join("test.db");
+            let vectors = super::semantic::random_vectors(1100, 8, 5);
+            {
+                let store = MemoryStore::open(&db_path).unwrap();
+                for (i, v) in vectors.iter().enumerate().take(1050) {
+                    store
+                        .persist_fact_with_embedding("embed", &format!("k{i}"), "v", Some(v))
+                        .unwrap();
+                }
+            }
+            let index_rows = |store: &MemoryStore| -> i64 {
+                store
+                    .db()
+                    .query_row("SELECT count(*) FROM vector_index", [], |r| r.get(0))
+                    .unwrap()
+            };
+
+            // First open builds and saves the index
+            let store = MemoryStore::open(&db_path).unwrap();
+            assert!(store.semantic.has_index());
+            assert_eq!(index_rows(&store), 1);
+            store.delete_fact("global", "embed", "k0").unwrap();
+            store
+                .persist_fact_with_embedding("embed", "k1060", "v", Some(&vectors[1060]))
+                .unwrap();
+            drop(store);
+
+            // Reopen restores it and catches up with rows changed since
+            let store = MemoryStore::open(&db_path).unwrap();
+            assert!(store.semantic.has_index());
+            let top = store.semantic.vector_search(&vectors[1060], 1);
+            assert_eq!(top[0].0.key, "k1060");
+            assert!(
+                store
+                    .semantic
+                    .vector_search(&vectors[0], 5)
+                    .iter()
+                    .all(|(f, _)| f.key != "k0")
+            );
+        }
+    }
+
+    mod knowledge {
+        use claw_memory::MemoryStore;
+        use claw_memory::knowledge::{
+            ChunkOptions, DocumentKind, KbSource, chunk_text, collect_files, content_hash,
+            extract_text, html_to_text,
+        };
+
+        fn source(path: &str, text: &str, chunks: usize) -> KbSource {
+            KbSource {
+                path: path.into(),
+                root: "/docs".into(),
+                hash: content_hash(text.as_bytes()),
+                size: text.len() as u64,
+                modified: 0,
+                chunk_count: chunks,
+                ingested_at: String::new(),
+            }
+        }
+
+        #[test]
+        fn test_chunk_offsets_lines_headings_and_overlap() {
+            let mut text = String::from("# Guide\n\nIntro paragraph.\n\n## Install\n\n");
+            for i in 0..12 {
+                text.push_str(&format!("Step {i}: {}\n\n", "run the installer ".repeat(3)));
+            }
+            let options = ChunkOptions {
+                max_chars: 300,
+                overlap: 80,
+            };
+            let chunks = chunk_text(&text, DocumentKind::Markdown, options);
+            assert!(chunks.len() > 2);
+            for (i, c) in chunks.iter().enumerate() {
+                assert_eq!(c.index, i);
+                assert!(c.end - c.start <= 300);
+                assert_eq!(c.text, text[c.start..c.end].trim());
+                let first_line = text.lines().nth(c.start_line - 1).unwrap();
+                assert!(c.text.starts_with(first_line));
+            }
+            assert_eq!(chunks[0].heading.as_deref(), Some("Guide"));
+            assert_eq!(chunks.last().unwrap().heading.as_deref(), Some("Install"));
+            // Consecutive chunks share trailing context
+            assert!(chunks[1].start < chunks[0].end);
+            assert!(chunks.last().unwrap().text.ends_with("installer"));
+        }
+
+        #[test]
+        fn test_chunk_splits_oversized_blocks_and_pages() {
+            let long = "x".repeat(450);
+            let chunks = chunk_text(
+                &long,
+                DocumentKind::Code,
+                ChunkOptions {
+                    max_chars: 200,
+                    overlap: 0,
+                },
+            );
+            assert_eq!(chunks.len(), 3);
+            assert_eq!(chunks.iter().map(|c| c.text.len()).sum::<usize>(), 450);
+
+            let pdf = "First page text.\n\x0cSecond page text.\n";
+            let chunks = chunk_text(
+                pdf,
+                DocumentKind::Pdf,
+                ChunkOptions {
+                    max_chars: 100,
+                    overlap: 0,
+                },
+            );
+            assert_eq!(chunks.len(), 2);
+            assert_eq!(chunks[0].location(), "p. 1");
+            assert_eq!(chunks[1].location(), "p. 2");
+            assert_eq!(chunks[1].text, "Second page text.");
+        }
+
+        #[test]
+        fn test_html_and_pdf_extraction() {
+            let html = "<html><head><style>p { color: red }</style></head><body>\
+                <h1>Title</h1><p>Fish &amp; chips<br>for &#163;5</p>\
+                <script>alert('x')</script><!-- note --><p>Done</p></body></html>";
+            let text = html_to_text(html);
+            assert_eq!(text, "# Title\n\nFish & chips\nfor £5\n\nDone\n");
+
+            let dir = tempfile::tempdir().unwrap();
+            let pdf = dir.path().join("doc.pdf");
+            std::fs::write(
+                &pdf,
+                "%PDF-1.4\n1 0 obj << /Length 44 >>\nstream\nBT /F1 12 Tf (Hello PDF) Tj ET\nendstream\nendobj\n\
+                 2 0 obj << /Length 44 >>\nstream\nBT (Page two \\(GET\\)) Tj ET\nendstream\nendobj\n%%EOF\n",
+            )
+            .unwrap();
+            let text = extract_text(&pdf, DocumentKind::Pdf).unwrap();
+            assert!(text.contains("Hello PDF"));
+            assert!(text.contains("Page two (GET)"));
+            assert_eq!(text.matches('\x0c').count(), 1);
+        }
+
+        #[test]
+        fn test_collect_files_skips_hidden_build_and_unsupported() {
+            let dir = tempfile::tempdir().unwrap();
+            let root = dir.path();
+            for path in [
+                "README.md",
+                "src/main.rs",
+                "notes/a.txt",
+                "image.png",
+                ".git/config.toml",
+                "target/debug/out.rs",
+                "node_modules/pkg/index.js",
+            ] {
+                let full = root.join(path);
+                std::fs::create_dir_all(full.parent().unwrap()).unwrap();
+                std::fs::write(full, "x").unwrap();
+            }
+            let files: Vec<String> = collect_files(root)
+                .unwrap()
+                .iter()
+                .map(|p| {
+                    p.strip_prefix(root)
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                })
+                .collect();
+            assert_eq!(files, vec!["README.md", "notes/a.txt", "src/main.rs"]);
+            assert!(collect_files(&root.join("image.png")).unwrap().is_empty());
+        }
+
+        #[test]
+        fn test_knowledge_base_replace_search_and_remove() {
+            let dir = tempfile::tempdir().unwrap();
+            let db_path = dir.path().join("test.db");
+            let deploy = "# Deploy\n\nRun the migration script before restarting workers.\n";
+            let style = "# Style\n\nUse four spaces for indentation.\n";
+            {
+                let mut store = MemoryStore::open(&db_path).unwrap();
+                let options = ChunkOptions::default();
+                let chunks = chunk_text(deploy, DocumentKind::Markdown, options);
+                let embeddings = vec![vec![1.0, 0.0]; chunks.len()];
+                store
+                    .knowledge
+                    .replace_source(
+                        &source("/docs/deploy.md", deploy, chunks.len()),
+                        &chunks,
+                        Some(&embeddings),
+                    )
+                    .unwrap();
+                let chunks = chunk_text(style, DocumentKind::Markdown, options);
+                let embeddings = vec![vec![0.0, 1.0]; chunks.len()];
+                store
+                    .knowledge
+                    .replace_source(
+                        &source("/docs/style.md", style, chunks.len()),
+                        &chunks,
+                        Some(&embeddings),
+                    )
+                    .unwrap();
+                store.knowledge.add_root("/docs").unwrap();
+            }
+
+            // Reopened: keyword and vector search both find their passage
+            let mut store = MemoryStore::open(&db_path).unwrap();
+            assert_eq!(store.knowledge.roots().unwrap(), vec!["/docs"]);
+            assert_eq!(store.knowledge.sources().unwrap().len(), 2);
+            let hits = store.knowledge.search("migration", None, 5).unwrap();
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].citation(), "/docs/deploy.md (L1-3, § Deploy)");
+            let hits = store
+                .knowledge
+                .search("tabs or spaces?", Some(&[0.0, 1.0]), 1)
+                .unwrap();
+            assert_eq!(hits[0].source, "/docs/style.md");
+
+            // Re-ingesting replaces the old passages
+            let updated = "# Deploy\n\nDrain the queue, then restart workers.\n";
+            let chunks = chunk_text(updated, DocumentKind::Markdown, ChunkOptions::default());
+            store
+                .knowledge
+                .replace_source(&source("/docs/deploy.md", updated, 1), &chunks, None)
+                .unwrap();
+            assert!(
+                store
+                    .knowledge
+                    .search("migration", None, 5)
+                    .unwrap()
+                    .is_empty()
+            );
+            assert_eq!(store.knowledge.search("drain", None, 5).unwrap().len(), 1);
+            assert_eq!(
+                store.knowledge.source("/docs/deploy.md").unwrap().hash,
+                content_hash(updated.as_bytes())
+            );
+
+            assert!(store.knowledge.remove_source("/docs/style.md").unwrap());
+            assert!(!store.knowledge.remove_source("/docs/style.md").unwrap());
+            assert!(
+                store
+                    .knowledge
+                    .search("indentation", Some(&[0.0, 1.0]), 5)
+                    .unwrap()
+                    .iter()
+                    .all(|h| h.source != "/docs/style.md")
+            );
+            assert_eq!(store.knowledge.chunk_count(), 1);
+        }
+    }
+
+    mod backup {
+        use claw_core::{Message, Role};
+        use claw_memory::{Episode, MemoryStore, MergeStrategy};
+        use uuid::Uuid;
+
+        fn populated_store() -> (MemoryStore, Uuid) {
+            let mut store = MemoryStore::open_in_memory().unwrap();
+            let session = Uuid::new_v4();
+            store
+                .persist_fact_with_embedding("prefs", "theme", "dark", Some(&[0.25, -1.5, 3.0]))
+                .unwrap();
+            store.persist_fact("project", "lang", "rust").unwrap();
+            store.episodic.record(Episode {
+                id: Uuid::new_v4(),
+                session_id: session,
+                scope: "global".into(),
+                summary: "Deployed the billing service".into(),
+                outcome: Some("success".into()),
+                tags: vec!["deploy".into()],
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            });
+            let goal = Uuid::new_v4();
+            store
+                .persist_goal(&goal, "Ship v2", "active", 3, 0.5, None)
+                .unwrap();
+            store
+                .persist_goal_step(&Uuid::new_v4(), &goal, "Write changelog", "pending", None)
+                .unwrap();
+            store
+                .persist_session(&session, Some("ops"), None, None, true, 2)
+                .unwrap();
+            store
+                .persist_session_messages(
+                    &session,
+                    &[
+                        Message::text(session, Role::User, "rotate the staging keys"),
+                        Message::text(session, Role::Assistant, "Keys rotated."),
+                    ],
+                )
+                .unwrap();
+            store
+                .persist_scheduled_task(
+                    "task-1",
+                    Some("nightly"),
+                    "Back up memory",
+                    r#"{"Cron":"0 3 * * *"}"#,
+                    &chrono::Utc::now().to_rfc3339(),
+                    None,
+                    true,
+                    4,
+                    None,
+                )
+                .unwrap();
+            store.audit("test", "created", Some("fixture")).unwrap();
+            (store, session)
+        }
+
+        fn export(store: &MemoryStore) -> Vec<u8> {
+            let mut out = Vec::new();
+            store.export(&mut out).unwrap();
+            out
+        }
+
+        fn count(store: &MemoryStore, table: &str) -> i64 {
+            store
+                .db()
+                .query_row(&format!("SELECT count(*) FROM {table}"), [], |r| r.get(0))
+                .unwrap()
+        }
+
+        #[test]
+        fn test_export_import_round_trip() {
+            let (source, session) = populated_store();
+            let archive = export(&source);
+
+            let manifest: serde_json::Value =
+                serde_json::from_slice(archive.split(|b| *b == b'\n').next().unwrap()).unwrap();
+            assert_eq!(manifest["format"], "claw-memory-export");
+            assert_eq!(manifest["version"], 1);
+            let facts = manifest["tables"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|t| t["name"] == "facts")
+                .unwrap();
+            assert_eq!(facts["rows"], 2);
+
+            let mut target = MemoryStore::open_in_memory().unwrap();
+            let report = target
+                .import(&mut archive.as_slice(), MergeStrategy::Skip)
+                .unwrap();
+            assert_eq!(report.total().inserted, 11);
+            assert_eq!(report.tables["facts"].inserted, 2);
+            for table in [
+                "fact_history",
+                "facts",
+                "episodes",
+                "goals",
+                "goal_steps",
+                "sessions",
+                "session_messages",
+                "scheduled_tasks",
+                "audit_log",
+            ] {
+                assert_eq!(count(&source, table), count(&target, table), "{table}");
+            }
+
+            // In-memory tiers and indexes are refreshed from the imported rows
+            let theme = target.semantic.get("global", "prefs", "theme").unwrap();
+            assert_eq!(theme.embedding.as_deref(), Some(&[0.25, -1.5, 3.0][..]));
+            assert_eq!(target.episodic.recent(5).len(), 1);
+            assert_eq!(target.search_facts("rust", 5).unwrap().len(), 1);
+            let hits = target.search_messages("staging keys", None, 5).unwrap();
+            assert_eq!(hits[0].session_id, session.to_string());
+            assert_eq!(target.load_scheduled_tasks().unwrap()[0].fire_count, 4);
+
+            // Importing again changes nothing, not even the append-only audit log
+            let report = target
+                .import(&mut archive.as_slice(), MergeStrategy::Overwrite)
+                .unwrap();
+            assert_eq!(report.total().inserted, 0);
+            assert_eq!(report.tables["audit_log"].skipped, 1);
+            assert_eq!(count(&target, "audit_log"), 1);
+        }
+
+        #[test]
+        fn test_import_merge_strategies() {
+            let (source, _) = populated_store();
+            source
+                .db()
+                .execute(
+                    "UPDATE facts SET updated_at = '2030-01-01T00:00:00+00:00' WHERE key = 'theme'",
+                    [],
+                )
+                .unwrap();
+            source
+                .db()
+                .execute(
+                    "UPDATE facts SET updated_at = '2000-01-01T00:00:00+00:00' WHERE key = 'lang'",
+                    [],
+                )
+                .unwrap();
+            let archive = export(&source);
+
+            let value = |store: &MemoryStore, category: &str, key: &str| {
+                store
+                    .semantic
+                    .get("global", category, key)
+                    .unwrap()
+                    .value
+                    .clone()
+            };
+            let local = || {
+                let mut store = MemoryStore::open_in_memory().unwrap();
+                store.persist_fact("prefs", "theme", "light").unwrap();
+                store.persist_fact("project", "lang", "go").unwrap();
+                store.load_facts().unwrap();
+                store
+            };
+
+            let mut store = local();
+            let report = store
+                .import(&mut archive.as_slice(), MergeStrategy::Skip)
+                .unwrap();
+            assert_eq!(report.tables["facts"].skipped, 2);
+            assert_eq!(value(&store, "prefs", "theme"), "light");
+
+            let mut store = local();
+            store
+                .import(&mut archive.as_slice(), MergeStrategy::Overwrite)
+                .unwrap();
+            assert_eq!(value(&store, "prefs", "theme"), "dark");
+            assert_eq!(value(&store, "project", "lang"), "rust");
+
+            // Only the archived fact that is newer than the local copy wins
+            let mut store = local();
+            let report = store
+                .import(&mut archive.as_slice(), MergeStrategy::NewestWins)
+                .unwrap();
+            assert_eq!(report.tables["facts"].updated, 1);
+            assert_eq!(value(&store, "prefs", "theme"), "dark");
+            assert_eq!(value(&store, "project", "lang"), "go");
+        }
+
+        #[test]
+        fn test_import_rejects_bad_archives_atomically() {
+            let mut store = MemoryStore::open_in_memory().unwrap();
+            let newer = br#"{"format":"claw-memory-export","version":99,"exported_at":"","claw_version":"","tables":[]}"#;
+            assert!(
+                store
+                    .import(&mut newer.as_slice(), MergeStrategy::Skip)
+                    .is_err()
+            );
+            assert!(
+                store
+                    .import(
+                        &mut b"{\"format\":\"other\"}".as_slice(),
+                        MergeStrategy::Skip
+                    )
+                    .is_err()
+            );
+
+            // A corrupt line rolls back the rows before it
+            let (source, _) = populated_store();
+            let mut archive = export(&source);
+            archive.extend_from_slice(b"{not json\n");
+            assert!(
+                store
+                    .import(&mut archive.as_slice(), MergeStrategy::Skip)
+                    .is_err()
+            );
+            assert_eq!(count(&store, "facts"), 0);
+            assert_eq!(
+                "newest-wins".parse::<MergeStrategy>().unwrap(),
+                MergeStrategy::NewestWins
+            );
+            assert!("merge".parse::<MergeStrategy>().is_err());
+        }
+    }
+
+    mod migrations {
+        use claw_memory::MemoryStore;
+        use claw_memory::migrations::{self, MIGRATIONS};
+        use rusqlite::Connection;
+
+        fn backups(dir: &std::path::Path) -> Vec<String> {
+            let mut names: Vec<String> = std::fs::read_dir(dir)
+                .unwrap()
+                .flatten()
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .filter(|n| n.ends_with(".bak"))
+                .collect();
+            names.sort();
+            names
+        }
+
+        #[test]
+        fn test_migrations_ordered_and_fresh_database_current() {
+            for (i, m) in MIGRATIONS.iter().enumerate() {
+                assert_eq!(m.version as usize, i + 1, "{}", m.description);
+            }
+            let dir = tempfile::tempdir().unwrap();
+            let db_path = dir.path().join("memory.db");
+            assert_eq!(migrations::inspect(&db_path).unwrap(), None);
+
+            let store = MemoryStore::open(&db_path).unwrap();
+            assert_eq!(store.schema_version(), migrations::latest_version());
+            drop(store);
+            // Nothing to back up for a new database, nor when reopening it
+            let store = MemoryStore::open(&db_path).unwrap();
+            drop(store);
+            assert!(backups(dir.path()).is_empty());
+            assert_eq!(
+                migrations::inspect(&db_path).unwrap(),
+                Some(migrations::latest_version())
+            );
+        }
+
+        #[test]
+        fn test_unversioned_database_backed_up_and_migrated() {
+            let dir = tempfile::tempdir().unwrap();
+            let db_path = dir.path().join("memory.db");
+            {
+                // Schema as written before versioning: no FTS, user_version 0
+                let conn = Connection::open(&db_path).unwrap();
+                conn.execute_batch(
+                    "CREATE TABLE facts (
+                        id TEXT PRIMARY KEY, category TEXT NOT NULL, key TEXT NOT NULL,
+                        value TEXT NOT NULL, confidence REAL DEFAULT 1.0, source TEXT,
+                        embedding BLOB, created_at TEXT NOT NULL, updated_at TEXT NOT NULL,
+                        UNIQUE(category, key)
+                    );
+                    INSERT INTO facts (id, category, key, value, created_at, updated_at)
+                    VALUES ('f1', 'infra', 'dns', 'cloudflare', '2024-01-01', '2024-01-01');",
+                )
+                .unwrap();
+            }
+            // Stale backups beyond the retention limit are pruned
+            for i in 0..4 {
+                std::fs::write(dir.path().join(format!("memory.db.v0-old{i}.bak")), "").unwrap();
+            }
+
+            let store = MemoryStore::open(&db_path).unwrap();
+            assert_eq!(store.schema_version(), migrations::latest_version());
+            assert_eq!(
+                store.semantic.get("global", "infra", "dns").unwrap().value,
+                "cloudflare"
+            );
+            assert_eq!(store.search_facts("cloudflare", 5).unwrap().len(), 1);
+            drop(store);
+
+            let names = backups(dir.path());
+            assert_eq!(names.len(), 3, "{names:?}");
+            let fresh: Vec<&String> = names.iter().filter(|n| !n.contains("old")).collect();
+            assert_eq!(fresh.len(), 1);
+            let backup = Connection::open(dir.path().join(fresh[0])).unwrap();
+            let version: u32 = backup
+                .query_row("PRAGMA user_version", [], |r| r.get(0))
+                .unwrap();
+            assert_eq!(version, 0);
+            let value: String = backup
+                .query_row("SELECT value FROM facts WHERE key = 'dns'", [], |r| {
+                    r.get(0)
+                })
+                .unwrap();
+            assert_eq!(value, "cloudflare");
+        }
+
+        #[test]
+        fn test_newer_schema_refused() {
+            let dir = tempfile::tempdir().unwrap();
+            let db_path = dir.path().join("memory.db");
+            {
+                let conn = Connection::open(&db_path).unwrap();
+                conn.execute_batch("CREATE TABLE t (x); PRAGMA user_version = 99;")
+                    .unwrap();
+            }
+            assert_eq!(migrations::inspect(&db_path).unwrap(), Some(99));
+            let err = MemoryStore::open(&db_path).err().unwrap().to_string();
+            assert!(err.contains("v99"), "{err}");
+            assert!(backups(dir.path()).is_empty());
+        }
+    }
+
+    // ── Audit ──────────────────────────────────────────────────
+
+    mod audit {
+        use claw_memory::{AuditKey, AuditLog, AuditReport, MemoryStore, audit};
+
+        /// A file-backed store with five audit entries.
+        fn audited() -> (tempfile::TempDir, MemoryStore) {
+            let dir = tempfile::tempdir().unwrap();
+            let store = MemoryStore::open(&dir.path().join("memory.db")).unwrap();
+            for i in 1..=5 {
+                store
+                    .audit("tool_execution", "shell_exec", Some(&format!("ls {i}")))
+                    .unwrap();
+            }
+            (dir, store)
+        }
+
+        fn problems(report: &AuditReport) -> Vec<String> {
+            report.issues.iter().map(|i| i.to_string()).collect()
+        }
+
+        fn tampered(sql: &str) -> Vec<String> {
+            let (_dir, store) = audited();
+            store.db().execute_batch(sql).unwrap();
+            problems(&store.verify_audit().unwrap())
+        }
+
+        #[test]
+        fn test_chain_detects_edits_deletions_and_reordering() {
+            let (dir, store) = audited();
+            let report = store.verify_audit().unwrap();
+            assert!(report.is_intact(), "{:?}", report.issues);
+            assert_eq!((report.entries, report.last_id), (5, Some(5)));
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let key = audit::key_path(&dir.path().join("memory.db"));
+                let mode = std::fs::metadata(key).unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o600);
+            }
+
+            let edited = tampered("UPDATE audit_log SET details = 'rm -rf /' WHERE id = 2");
+            assert_eq!(edited.len(), 1, "{edited:?}");
+            assert!(edited[0].starts_with("#2: contents don't match"));
+
+            let deleted = tampered("DELETE FROM audit_log WHERE id = 3");
+            assert_eq!(deleted, vec!["#4: 1 entries deleted after #2"]);
+
+            let truncated = tampered("DELETE FROM audit_log WHERE id >= 4");
+            assert_eq!(
+                truncated,
+                vec!["entries after #3 were deleted (the chain ended at #5)"]
+            );
+
+            // Swapping two rows' contents invalidates both
+            let swapped = tampered(
+                "UPDATE audit_log SET id = -1 WHERE id = 2;
+                 UPDATE audit_log SET id = 2 WHERE id = 3;
+                 UPDATE audit_log SET id = 3 WHERE id = -1;",
+            );
+            assert!(
+                swapped.iter().any(|p| p.starts_with("#2: not linked")),
+                "{swapped:?}"
+            );
+            assert!(
+                swapped.iter().any(|p| p.starts_with("#3: contents")),
+                "{swapped:?}"
+            );
+
+            let inserted = tampered(
+                "INSERT INTO audit_log (timestamp, event_type, action, checksum)
+                 VALUES ('2024-01-01T00:00:00Z', 'approval', 'approved', 'x')",
+            );
+            assert_eq!(
+                inserted,
+                vec!["#6: not sealed into the chain — inserted directly"]
+            );
+
+            // Entries written with a different key don't verify
+            drop(store);
+            std::fs::write(
+                audit::key_path(&dir.path().join("memory.db")),
+                format!("{}\n", "ab".repeat(32)),
+            )
+            .unwrap();
+            let store = MemoryStore::open(&dir.path().join("memory.db")).unwrap();
+            let report = store.verify_audit().unwrap();
+            assert!(problems(&report)[0].contains("the audit key was replaced"));
+            assert_eq!(report.issues.len(), 6);
+        }
+
+        #[test]
+        fn test_truncation_with_rewound_head_detected() {
+            let head_of = |store: &MemoryStore, id: i64| -> (String, String) {
+                let db = store.db();
+                let head: String = db
+                    .query_row(
+                        "SELECT value FROM memory_meta WHERE key = 'audit_head'",
+                        [],
+                        |row| row.get(0),
+                    )
+                    .unwrap();
+                let mac: String = db
+                    .query_row(
+                        "SELECT checksum FROM audit_log WHERE id = ?1",
+                        [id],
+                        |row| row.get(0),
+                    )
+                    .unwrap();
+                (head, mac)
+            };
+
+            // Point the head at the new last entry, as the rows show it
+            let (_dir, store) = audited();
+            let (head, mac3) = head_of(&store, 3);
+            let signature = head.rsplit(' ').next().unwrap().to_string();
+            for forged in [
+                format!("3 {mac3}"),
+                format!("3 {mac3} 3 {signature}"),
+                format!("3 {mac3} 3 {}", "00".repeat(32)),
+            ] {
+                store
+                    .db()
+                    .execute_batch(&format!(
+                        "DELETE FROM audit_log WHERE id >= 4;
+                         UPDATE memory_meta SET value = '{forged}' WHERE key = 'audit_head';"
+                    ))
+                    .unwrap();
+                assert_eq!(
+                    problems(&store.verify_audit().unwrap()),
+                    vec!["the chain head record doesn't match its signature — it was edited"],
+                    "{forged}"
+                );
+            }
+
+            // New entries link to the recorded head, so appending after a
+            // truncation doesn't paper over it
+            for forge_head in [false, true] {
+                let (_dir, store) = audited();
+                let (_, mac3) = head_of(&store, 3);
+                store
+                    .db()
+                    .execute_batch("DELETE FROM audit_log WHERE id >= 4")
+                    .unwrap();
+                if forge_head {
+                    store
+                        .db()
+                        .execute_batch(&format!(
+                            "UPDATE memory_meta SET value = '3 {mac3}' WHERE key = 'audit_head'"
+                        ))
+                        .unwrap();
+                }
+                store
+                    .audit("tool_execution", "shell_exec", Some("ls"))
+                    .unwrap();
+                let report = store.verify_audit().unwrap();
+                assert!(!report.is_intact(), "forged head: {forge_head}");
+            }
+
+            // Deleting the head record doesn't help either
+            let (_dir, store) = audited();
+            store
+                .db()
+                .execute_batch(
+                    "DELETE FROM audit_log WHERE id >= 4;
+                     DELETE FROM memory_meta WHERE key = 'audit_head';",
+                )
+                .unwrap();
+            assert_eq!(
+                problems(&store.verify_audit().unwrap()),
+                vec!["the chain head record is missing — entries after #3 may have been deleted"]
+            );
+            store
+                .audit("tool_execution", "shell_exec", Some("ls"))
+                .unwrap();
+            assert!(!store.verify_audit().unwrap().is_intact());
+        }
+
+        #[test]
+        fn test_legacy_entries_pinned_when_chain_starts() {
+            let dir = tempfile::tempdir().unwrap();
+            let db_path = dir.path().join("memory.db");
+            drop(MemoryStore::open(&db_path).unwrap());
+            // Entries as written before the chain, with plain checksums
+            let conn = rusqlite::Connection::open(&db_path).unwrap();
+            conn.execute_batch(
+                "DELETE FROM memory_meta WHERE key LIKE 'audit_%';
+                 INSERT INTO audit_log (timestamp, event_type, action, details, checksum) VALUES
+                   ('2024-01-01T00:00:00Z', 'message', 'processed', 'hi', 'a1'),
+                   ('2024-01-02T00:00:00Z', 'approval', 'approved', NULL, 'b2');
+                 PRAGMA user_version = 7;",
+            )
+            .unwrap();
+            drop(conn);
+
+            let store = MemoryStore::open(&db_path).unwrap();
+            store.audit("message", "processed", Some("hello")).unwrap();
+            let report = store.verify_audit().unwrap();
+            assert!(report.is_intact(), "{:?}", report.issues);
+            assert_eq!((report.entries, report.legacy), (4, 2));
+            assert_eq!(store.audit_log(10)[1].2, "chain_started");
+
+            store
+                .db()
+                .execute_batch("UPDATE audit_log SET details = 'bye' WHERE id = 1")
+                .unwrap();
+            let report = store.verify_audit().unwrap();
+            assert_eq!(
+                problems(&report),
+                vec!["#3: entries from before the chain were changed since it started"]
+            );
+        }
+
+        #[test]
+        fn test_signed_segments_verified_offline() {
+            let (dir, store) = audited();
+            let db_path = dir.path().join("memory.db");
+
+            // The CLI reads the log while the agent has it open
+            let log = AuditLog::open(&db_path).unwrap();
+            assert!(log.verify().unwrap().is_intact());
+            let mut segment = Vec::new();
+            let header = log.export_segment(Some(2), Some(4), &mut segment).unwrap();
+            assert_eq!((header.first_id, header.last_id, header.count), (2, 4, 3));
+
+            let report = audit::verify_segment(&mut segment.as_slice(), store.audit_key()).unwrap();
+            assert!(report.is_intact(), "{:?}", report.issues);
+            assert_eq!(report.entries, 3);
+
+            let text = String::from_utf8(segment.clone()).unwrap();
+            let edited = text.replace("ls 3", "ls 9");
+            let report = audit::verify_segment(&mut edited.as_bytes(), store.audit_key()).unwrap();
+            assert_eq!(problems(&report).len(), 1);
+            assert!(problems(&report)[0].starts_with("#3: contents"));
+
+            let lines: Vec<&str> = text.lines().collect();
+            let dropped = [lines[0], lines[1], lines[3]].join("\n");
+            let report = audit::verify_segment(&mut dropped.as_bytes(), store.audit_key()).unwrap();
+            assert!(!report.is_intact());
+
+            let other = AuditKey::ephemeral().unwrap();
+            let report = audit::verify_segment(&mut segment.as_slice(), &other).unwrap();
+            assert!(problems(&report)[0].contains("signed with key"));
+
+            // Archives are re-sealed into the importing store's chain
+            let mut archive = Vec::new();
+            store.export(&mut archive).unwrap();
+            let mut target = MemoryStore::open_in_memory().unwrap();
+            target.audit("startup", "started", None).unwrap();
+            target
+                .import(&mut archive.as_slice(), claw_memory::MergeStrategy::Skip)
+                .unwrap();
+            let report = target.verify_audit().unwrap();
+            assert!(report.is_intact(), "{:?}", report.issues);
+            assert_eq!(report.entries, 6);
+        }
+    }
+
+    // ── Decay ──────────────────────────────────────────────────
+
+    mod decay {
+        use super::*;
+        use claw_memory::{DecayPolicy, Fact, MemoryStore};
+
+        fn fact_aged(category: &str, days: i64, access_count: u32) -> Fact {
+            let then = Utc::now() - chrono::Duration::days(days);
+            Fact {
+                id: Uuid::new_v4(),
+                scope: "global".into(),
+                category: category.to_string(),
+                key: "k".to_string(),
+                value: "v".to_string(),
+                confidence: 1.0,
+                source: None,
+                embedding: None,
+                created_at: then,
+                updated_at: then,
+                access_count,
+                last_accessed: None,
+            }
+        }
+
+        /// Backdate a fact in SQLite and reload it.
+        fn backdate(store: &mut MemoryStore, category: &str, key: &str, days: i64) {
+            let then = (Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+            store
+                .db()
+                .execute(
+                    "UPDATE facts SET updated_at = ?1, last_accessed = NULL
+                     WHERE category = ?2 AND key = ?3",
+                    rusqlite::params![then, category, key],
+                )
+                .unwrap();
+            store.load_facts().unwrap();
+        }
+
+        #[test]
+        fn test_forgetting_curve() {
+            let policy = DecayPolicy {
+                half_life_days: 30.0,
+                ..Default::default()
+            };
+            let now = Utc::now();
+
+            let fresh = fact_aged("user", 0, 0);
+            assert!((policy.effective_confidence(&fresh, now) - 1.0).abs() < 1e-3);
+            let month = fact_aged("user", 30, 0);
+            assert!((policy.effective_confidence(&month, now) - 0.5).abs() < 1e-3);
+
+            // Recalled facts fade more slowly and rank higher
+            let used = fact_aged("user", 30, 5);
+            assert!(policy.effective_confidence(&used, now) > 0.6);
+            assert!(policy.recall_weight(&used, now) > policy.recall_weight(&month, now));
+
+            // Reinforcement restores part of what was lost
+            let reinforced = policy.reinforced(&month, now);
+            assert!((reinforced - 0.6).abs() < 1e-3, "{reinforced}");
+
+            // Exempt categories and a zero half-life don't decay
+            let lesson = fact_aged("learned_lessons", 3650, 0);
+            assert_eq!(policy.effective_confidence(&lesson, now), 1.0);
+            assert!(!policy.is_stale(&lesson, now));
+            let off = DecayPolicy {
+                half_life_days: 0.0,
+                ..Default::default()
+            };
+            let ancient = fact_aged("user", 3650, 0);
+            assert_eq!(off.effective_confidence(&ancient, now), 1.0);
+            assert!(policy.is_stale(&ancient, now));
+        }
+
+        #[test]
+        fn test_reinforcement_persisted_and_ranked() {
+            let dir = tempfile::tempdir().unwrap();
+            let db_path = dir.path().join("memory.db");
+            let mut store = MemoryStore::open(&db_path).unwrap();
+            for key in ["editor_home", "editor_work"] {
+                store.persist_fact("prefs", key, "editor vim").unwrap();
+            }
+            store.load_facts().unwrap();
+            for key in ["editor_home", "editor_work"] {
+                backdate(&mut store, "prefs", key, 60);
+            }
+
+            let keys = vec![(
+                "global".to_string(),
+                "prefs".to_string(),
+                "editor_work".to_string(),
+            )];
+            for _ in 0..3 {
+                assert_eq!(store.reinforce_facts(&keys).unwrap(), 1);
+            }
+            let missing = vec![(
+                "global".to_string(),
+                "prefs".to_string(),
+                "nope".to_string(),
+            )];
+            assert_eq!(store.reinforce_facts(&missing).unwrap(), 0);
+
+            let work = store
+                .semantic
+                .get("global", "prefs", "editor_work")
+                .unwrap();
+            assert_eq!(work.access_count, 3);
+            assert!(work.last_accessed.is_some());
+            let home = store
+                .semantic
+                .get("global", "prefs", "editor_home")
+                .unwrap();
+            let now = Utc::now();
+            assert!(
+                store.decay_policy().effective_confidence(work, now)
+                    > store.decay_policy().effective_confidence(home, now)
+            );
+
+            // Frequently recalled facts win ties in relevance
+            let results = store.hybrid_fact_search("editor vim", None, None, 2);
+            assert_eq!(results.len(), 2);
+            assert_eq!(results[0].0.key, "editor_work");
+            drop(store);
+
+            // Access history survives a restart
+            let store = MemoryStore::open(&db_path).unwrap();
+            let work = store
+                .semantic
+                .get("global", "prefs", "editor_work")
+                .unwrap();
+            assert_eq!(work.access_count, 3);
+            assert!(work.last_accessed.is_some());
+            assert!(work.confidence < 1.0);
+        }
+
+        #[test]
+        fn test_consolidation_archives_and_purges() {
+            let mut store = MemoryStore::open_in_memory().unwrap();
+            store.set_decay_policy(DecayPolicy {
+                half_life_days: 30.0,
+                archive_below: 0.1,
+                archive_retention_days: 30,
+                ..Default::default()
+            });
+            store.persist_fact("infra", "old_dns", "bind9").unwrap();
+            store.persist_fact("infra", "dns", "cloudflare").unwrap();
+            store
+                .persist_fact("learned_lessons", "retry", "back off")
+                .unwrap();
+            store.load_facts().unwrap();
+            backdate(&mut store, "infra", "old_dns", 365);
+            backdate(&mut store, "learned_lessons", "retry", 365);
+
+            let report = store.consolidate_facts().unwrap();
+            assert_eq!(report.archived, 1);
+            assert_eq!(report.purged, 0);
+            assert!(store.semantic.get("global", "infra", "old_dns").is_none());
+            assert!(store.semantic.get("global", "infra", "dns").is_some());
+            assert!(
+                store
+                    .semantic
+                    .get("global", "learned_lessons", "retry")
+                    .is_some()
+            );
+            assert!(store.search_facts("bind9", 5).unwrap().is_empty());
+            assert_eq!(store.archived_fact_count().unwrap(), 1);
+            let (value, confidence): (String, f64) = store
+                .db()
+                .query_row(
+                    "SELECT value, confidence FROM facts_archive WHERE key = 'old_dns'",
+                    [],
+                    |r| Ok((r.get(0)?, r.get(1)?)),
+                )
+                .unwrap();
+            assert_eq!(value, "bind9");
+            assert!(confidence < 0.1);
+
+            // Nothing new to archive; old archive entries are purged
+            let stale = (Utc::now() - chrono::Duration::days(31)).to_rfc3339();
+            store
+                .db()
+                .execute("UPDATE facts_archive SET archived_at = ?1", [stale])
+                .unwrap();
+            let report = store.consolidate_facts().unwrap();
+            assert_eq!(report.archived, 0);
+            assert_eq!(report.purged, 1);
+            assert_eq!(store.archived_fact_count().unwrap(), 0);
+        }
+    }
+
+    // ── History and conflicts ──────────────────────────────────
+
+    mod history {
+        use super::*;
+        use claw_memory::history::{ValueChange, classify, detect_conflict};
+        use claw_memory::{Fact, MemoryStore, MergeStrategy};
+
+        fn fact(key: &str, value: &str, source: &str) -> Fact {
+            Fact {
+                id: Uuid::new_v4(),
+                scope: "global".into(),
+                category: "infra".to_string(),
+                key: key.to_string(),
+                value: value.to_string(),
+                confidence: 1.0,
+                source: Some(source.to_string()),
+                embedding: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                access_count: 0,
+                last_accessed: None,
+            }
+        }
+
+        #[test]
+        fn test_classify_and_detect() {
+            assert_eq!(classify("Cloudflare.", "  cloudflare"), ValueChange::Same);
+            assert_eq!(classify("Alice", "Alice Smith"), ValueChange::Refined);
+            assert_eq!(classify("blue", "green"), ValueChange::Changed);
+
+            let mut store = MemoryStore::open_in_memory().unwrap();
+            store.remember(fact("dns", "bind9", "session:a")).unwrap();
+            let history = store.fact_history("global", "infra", "dns").unwrap();
+
+            // Same source updating its own fact is an update, not a conflict
+            let previous = store
+                .semantic
+                .get("global", "infra", "dns")
+                .unwrap()
+                .clone();
+            let update = fact("dns", "cloudflare", "session:a");
+            assert_eq!(detect_conflict(&previous, &update, &history), None);
+            // A different source disagreeing is
+            let other = fact("dns", "route53", "mesh:peer1");
+            let reason = detect_conflict(&previous, &other, &history).unwrap();
+            assert!(reason.contains("mesh:peer1"), "{reason}");
+            // More detail is never a conflict
+            let refined = fact("dns", "bind9 on ns1", "mesh:peer1");
+            assert_eq!(detect_conflict(&previous, &refined, &history), None);
+
+            // Flipping back to an earlier value is, whatever the source
+            store.remember(update).unwrap();
+            let history = store.fact_history("global", "infra", "dns").unwrap();
+            let previous = store
+                .semantic
+                .get("global", "infra", "dns")
+                .unwrap()
+                .clone();
+            let flip = fact("dns", "bind9", "session:a");
+            assert!(detect_conflict(&previous, &flip, &history).is_some());
+        }
+
+        #[test]
+        fn test_history_versions() {
+            let mut store = MemoryStore::open_in_memory().unwrap();
+            assert!(
+                store
+                    .remember(fact("dns", "bind9", "session:a"))
+                    .unwrap()
+                    .is_none()
+            );
+            let replaced = store
+                .remember(fact("dns", "cloudflare", "tool:memory_store"))
+                .unwrap()
+                .unwrap();
+            assert_eq!(replaced.value, "bind9");
+            // Rewriting the same value doesn't add a version
+            store
+                .remember(fact("dns", "cloudflare", "tool:memory_store"))
+                .unwrap();
+            store.persist_fact("infra", "dns", "route53").unwrap();
+
+            let history = store.fact_history("global", "infra", "dns").unwrap();
+            let values: Vec<(u32, &str)> = history
+                .iter()
+                .map(|v| (v.version, v.value.as_str()))
+                .collect();
+            assert_eq!(
+                values,
+                vec![(1, "bind9"), (2, "cloudflare"), (3, "route53")]
+            );
+            assert_eq!(history[0].source.as_deref(), Some("session:a"));
+            assert_eq!(history[1].source.as_deref(), Some("tool:memory_store"));
+            assert_eq!(
+                store
+                    .semantic
+                    .get("global", "infra", "dns")
+                    .unwrap()
+                    .source
+                    .as_deref(),
+                Some("tool:memory_store")
+            );
+
+            // Import into a fresh store carries history without duplicating it
+            let mut archive = Vec::new();
+            store.export(&mut archive).unwrap();
+            let mut copy = MemoryStore::open_in_memory().unwrap();
+            copy.import(&mut archive.as_slice(), MergeStrategy::Skip)
+                .unwrap();
+            assert_eq!(
+                copy.fact_history("global", "infra", "dns").unwrap().len(),
+                3
+            );
+        }
+
+        #[test]
+        fn test_conflicts_open_update_and_resolve() {
+            let mut store = MemoryStore::open_in_memory().unwrap();
+            let a = fact("dns", "bind9", "session:a");
+            let b = fact("dns", "cloudflare", "mesh:peer1");
+            store.remember(a.clone()).unwrap();
+            store.remember(b.clone()).unwrap();
+
+            let conflict = store.record_conflict(&a, &b, Some("disagree")).unwrap();
+            assert!(conflict.is_open());
+            assert_eq!(conflict.previous_value, "bind9");
+            assert_eq!(conflict.value, "cloudflare");
+            // Flipping again updates the open conflict
+            store.remember(a.clone()).unwrap();
+            let again = store.record_conflict(&b, &a, None).unwrap();
+            assert_eq!(again.id, conflict.id);
+            assert_eq!(again.occurrences, 2);
+            assert_eq!(again.value, "bind9");
+            assert_eq!(again.reason.as_deref(), Some("disagree"));
+            assert_eq!(store.fact_conflicts(false).unwrap().len(), 1);
+
+            // Resolve by short id, keeping the other value
+            let resolved = store
+                .resolve_conflict(&conflict.id[..8], Some("cloudflare"))
+                .unwrap();
+            assert!(!resolved.is_open());
+            assert_eq!(resolved.resolution.as_deref(), Some("cloudflare"));
+            assert_eq!(
+                store.semantic.get("global", "infra", "dns").unwrap().value,
+                "cloudflare"
+            );
+            let latest = store
+                .fact_history("global", "infra", "dns")
+                .unwrap()
+                .pop()
+                .unwrap();
+            assert_eq!(latest.value, "cloudflare");
+            assert!(latest.source.unwrap().starts_with("conflict:"));
+
+            assert!(store.fact_conflicts(false).unwrap().is_empty());
+            assert_eq!(store.fact_conflicts(true).unwrap().len(), 1);
+            assert!(store.resolve_conflict(&conflict.id, None).is_err());
+            assert!(store.resolve_conflict("nope", None).is_err());
+        }
+    }
+
+    // ── Knowledge graph ────────────────────────────────────────
+
+    mod graph {
+        use claw_memory::graph::{Direction, RelationPattern, entity_id};
+        use claw_memory::{MemoryStore, MergeStrategy};
+        use std::collections::BTreeMap;
+
+        fn attrs(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        }
+
+        /// web-01 runs nginx and postgres; nginx is owned by Alice, who is
+        /// on the ops team; postgres is owned by Bob.
+        fn fleet() -> MemoryStore {
+            let store = MemoryStore::open_in_memory().unwrap();
+            let g = &store.graph;
+            let none = BTreeMap::new();
+            let web = g
+                .upsert_entity("Server", "Web-01", &attrs(&[("ip", "10.0.0.5")]), None)
+                .unwrap();
+            for (kind, name) in [
+                ("service", "nginx"),
+                ("service", "postgres"),
+                ("person", "Alice"),
+                ("person", "Bob"),
+                ("team", "ops"),
+            ] {
+                g.upsert_entity(kind, name, &none, Some("session:1"))
+                    .unwrap();
+            }
+            for (subject, predicate, object) in [
+                ("service:nginx", "runs on", &web.id),
+                ("service:postgres", "runs_on", &web.id),
+                ("service:nginx", "owned_by", &"person:alice".to_string()),
+                ("service:postgres", "owned_by", &"person:bob".to_string()),
+                ("person:alice", "member_of", &"team:ops".to_string()),
+            ] {
+                g.relate(subject, predicate, object, 0.8, Some("session:1"))
+                    .unwrap();
+            }
+            store
+        }
+
+        #[test]
+        fn test_entities_normalised_and_merged() {
+            let store = fleet();
+            let g = &store.graph;
+            assert_eq!(entity_id("Server", "  Web-01 "), "server:web-01");
+
+            let web = g.entity("server:web-01").unwrap().unwrap();
+            assert_eq!(web.name, "Web-01");
+            assert_eq!(web.attributes["ip"], "10.0.0.5");
+
+            // Same entity written again: attributes merged, one node
+            let web = g
+                .upsert_entity(
+                    "server",
+                    "web-01",
+                    &attrs(&[("OS", "debian")]),
+                    Some("mesh:peer1"),
+                )
+                .unwrap();
+            assert_eq!(web.attributes.len(), 2);
+            assert_eq!(web.attributes["os"], "debian");
+            assert_eq!(web.source.as_deref(), Some("mesh:peer1"));
+            assert_eq!(g.counts().unwrap(), (6, 5));
+
+            // Resolve by id or name; names match every kind
+            assert_eq!(g.resolve("Web-01").unwrap().len(), 1);
+            g.upsert_entity("project", "nginx", &BTreeMap::new(), None)
+                .unwrap();
+            assert_eq!(g.resolve("nginx").unwrap().len(), 2);
+            assert_eq!(g.resolve("service:nginx").unwrap().len(), 1);
+            assert_eq!(
+                g.find_entities("ng", Some("service"), 10).unwrap()[0].id,
+                "service:nginx"
+            );
+
+            let mentioned = g
+                .mentioned_in("is postgres on web-01 healthy?", 10)
+                .unwrap();
+            let ids: Vec<&str> = mentioned.iter().map(|e| e.id.as_str()).collect();
+            assert_eq!(ids, vec!["service:postgres", "server:web-01"]);
+
+            assert!(g.upsert_entity("", "x", &BTreeMap::new(), None).is_err());
+        }
+
+        #[test]
+        fn test_relations_pattern_and_dedup() {
+            let store = fleet();
+            let g = &store.graph;
+
+            // Restating a relation refreshes it
+            let r = g
+                .relate(
+                    "service:nginx",
+                    "Runs-On",
+                    "server:web-01",
+                    1.0,
+                    Some("session:2"),
+                )
+                .unwrap();
+            assert_eq!(r.predicate, "runs_on");
+            assert_eq!(r.confidence, 1.0);
+            assert_eq!(r.source.as_deref(), Some("session:2"));
+            assert_eq!(g.counts().unwrap().1, 5);
+            assert!(
+                g.relate("service:nginx", "runs_on", "server:nope", 1.0, None)
+                    .is_err()
+            );
+
+            // Which services run on web-01 (by name)?
+            let pattern = RelationPattern {
+                predicate: Some("runs_on".into()),
+                object: Some("web-01".into()),
+                ..Default::default()
+            };
+            let mut services: Vec<String> = g
+                .relations(&pattern, 10)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.subject)
+                .collect();
+            services.sort();
+            assert_eq!(services, vec!["service:nginx", "service:postgres"]);
+
+            let pattern = RelationPattern {
+                object_kind: Some("person".into()),
+                ..Default::default()
+            };
+            assert_eq!(g.relations(&pattern, 10).unwrap().len(), 2);
+        }
+
+        #[test]
+        fn test_neighbors_traversal() {
+            let store = fleet();
+            let g = &store.graph;
+
+            // One hop: the services on the host
+            let n = g
+                .neighbors("server:web-01", 1, Direction::Both, None, 50)
+                .unwrap()
+                .unwrap();
+            assert_eq!(n.relations.len(), 2);
+            assert!(n.relations.iter().all(|(hop, _)| *hop == 1));
+
+            // Two hops: and who owns them
+            let n = g
+                .neighbors("server:web-01", 2, Direction::Both, None, 50)
+                .unwrap()
+                .unwrap();
+            let owners: Vec<&str> = n
+                .relations
+                .iter()
+                .filter(|(hop, r)| *hop == 2 && r.predicate == "owned_by")
+                .map(|(_, r)| r.object.as_str())
+                .collect();
+            assert_eq!(owners, vec!["person:alice", "person:bob"]);
+            assert!(!n.relations.iter().any(|(_, r)| r.predicate == "member_of"));
+            assert_eq!(n.entities.len(), 4);
+
+            // Direction and predicate filters
+            let n = g
+                .neighbors(
+                    "service:nginx",
+                    3,
+                    Direction::Outgoing,
+                    Some("owned_by"),
+                    50,
+                )
+                .unwrap()
+                .unwrap();
+            assert_eq!(n.relations.len(), 1);
+            let n = g
+                .neighbors("person:alice", 1, Direction::Incoming, None, 50)
+                .unwrap()
+                .unwrap();
+            assert_eq!(n.relations[0].1.subject, "service:nginx");
+            // Depth is capped, and the limit stops the walk
+            let n = g
+                .neighbors("team:ops", 10, Direction::Both, None, 3)
+                .unwrap()
+                .unwrap();
+            assert_eq!(n.relations.len(), 3);
+            assert!(
+                g.neighbors("server:nope", 1, Direction::Both, None, 50)
+                    .unwrap()
+                    .is_none()
+            );
+
+            assert!(g.delete_entity("service:nginx").unwrap());
+            assert_eq!(g.counts().unwrap(), (5, 3));
+        }
+
+        #[test]
+        fn test_graph_export_import() {
+            let source = fleet();
+            let mut archive = Vec::new();
+            source.export(&mut archive).unwrap();
+
+            let mut target = MemoryStore::open_in_memory().unwrap();
+            let report = target
+                .import(&mut archive.as_slice(), MergeStrategy::Skip)
+                .unwrap();
+            assert_eq!(report.tables["graph_entities"].inserted, 6);
+            assert_eq!(report.tables["graph_relations"].inserted, 5);
+            let web = target.graph.entity("server:web-01").unwrap().unwrap();
+            assert_eq!(web.attributes["ip"], "10.0.0.5");
+        }
+    }
+
+    // ── Scopes ─────────────────────────────────────────────────
+
+    mod scope {
+        use super::*;
+        use claw_memory::{Episode, Fact, MemoryScope, MemoryStore, ScopeKind};
+
+        fn fact(scope: &str, value: &str) -> Fact {
+            Fact {
+                id: Uuid::new_v4(),
+                scope: scope.into(),
+                category: "prefs".to_string(),
+                key: "editor".to_string(),
+                value: value.to_string(),
+                confidence: 1.0,
+                source: None,
+                embedding: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                access_count: 0,
+                last_accessed: None,
+            }
+        }
+
+        fn alice() -> MemoryScope {
+            MemoryScope {
+                channel: Some("telegram".into()),
+                user: Some("alice".into()),
+                session: None,
+                read: ScopeKind::ALL.to_vec(),
+                write: vec![ScopeKind::Session, ScopeKind::User, ScopeKind::Global],
+                default_write: ScopeKind::Session,
+            }
+        }
+
+        #[test]
+        fn test_scope_rules() {
+            assert_eq!(ScopeKind::of("user:telegram:42"), Some(ScopeKind::User));
+            assert_eq!(ScopeKind::of("global"), Some(ScopeKind::Global));
+            assert_eq!(ScopeKind::of("team:x"), None);
+
+            let alice = alice();
+            assert_eq!(
+                alice.readable(),
+                vec!["user:alice", "channel:telegram", "global"]
+            );
+            assert!(alice.can_read("channel:telegram"));
+            assert!(!alice.can_write("channel:telegram"));
+            assert!(!alice.can_read("user:bob"));
+            // No session to write to: the next broader writable scope
+            assert_eq!(alice.write_scope(None).unwrap(), "user:alice");
+            assert_eq!(
+                alice.write_scope(Some(ScopeKind::Global)).unwrap(),
+                "global"
+            );
+            assert!(alice.write_scope(Some(ScopeKind::Channel)).is_err());
+            assert!(alice.write_scope(Some(ScopeKind::Session)).is_err());
+            assert_eq!(MemoryScope::global().write_scope(None).unwrap(), "global");
+        }
+
+        #[test]
+        fn test_scoped_facts_coexist_and_filter() {
+            let dir = tempfile::tempdir().unwrap();
+            let db_path = dir.path().join("memory.db");
+            let mut store = MemoryStore::open(&db_path).unwrap();
+            store.remember(fact("global", "vim")).unwrap();
+            store.remember(fact("user:alice", "helix")).unwrap();
+            store.remember(fact("user:bob", "emacs")).unwrap();
+            store.remember(fact("user:alice", "zed")).unwrap();
+            assert_eq!(store.semantic.count(), 3);
+            assert_eq!(
+                store
+                    .semantic
+                    .get("user:alice", "prefs", "editor")
+                    .unwrap()
+                    .value,
+                "zed"
+            );
+            assert_eq!(
+                store
+                    .fact_history("user:alice", "prefs", "editor")
+                    .unwrap()
+                    .len(),
+                2
+            );
+            assert_eq!(
+                store
+                    .fact_history("user:bob", "prefs", "editor")
+                    .unwrap()
+                    .len(),
+                1
+            );
+
+            let readable = alice().readable();
+            let values: Vec<String> = store
+                .hybrid_fact_search("editor", None, Some(&readable), 10)
+                .into_iter()
+                .map(|(f, _)| f.value.clone())
+                .collect();
+            assert_eq!(values.len(), 2, "{values:?}");
+            assert!(values.contains(&"zed".to_string()));
+            assert!(values.contains(&"vim".to_string()));
+            assert_eq!(store.search_facts("editor", 10).unwrap().len(), 3);
+
+            let mut episode = Episode {
+                id: Uuid::new_v4(),
+                session_id: Uuid::new_v4(),
+                scope: "user:bob".into(),
+                summary: "Set up emacs for bob".into(),
+                outcome: None,
+                tags: vec![],
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            };
+            store.episodic.record(episode.clone());
+            assert!(
+                store
+                    .search_episodes("emacs", Some(&readable), 5)
+                    .unwrap()
+                    .is_empty()
+            );
+            episode.scope = "global".into();
+            store.episodic.record(episode);
+            assert_eq!(
+                store
+                    .search_episodes("emacs", Some(&readable), 5)
+                    .unwrap()
+                    .len(),
+                1
+            );
+
+            assert!(store.delete_fact("user:bob", "prefs", "editor").unwrap());
+            store.semantic.remove("user:bob", "prefs", "editor");
+            drop(store);
+
+            // Re-running the scope migration on a scoped database is harmless
+            rusqlite::Connection::open(&db_path)
+                .unwrap()
+                .execute_batch("PRAGMA user_version = 6;")
+                .unwrap();
+            let mut store = MemoryStore::open(&db_path).unwrap();
+            assert_eq!(store.load_facts().unwrap(), 2);
+            assert_eq!(
+                store
+                    .semantic
+                    .get("global", "prefs", "editor")
+                    .unwrap()
+                    .value,
+                "vim"
+            );
+            assert_eq!(
+                store
+                    .semantic
+                    .get("user:alice", "prefs", "editor")
+                    .unwrap()
+                    .value,
+                "zed"
+            );
+            assert!(store.semantic.get("user:bob", "prefs", "editor").is_none());
+            assert_eq!(store.search_facts("zed", 5).unwrap()[0].scope, "user:alice");
+        }
     }
 }
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Arc, LazyLock};
 use tokio::sync::{Mutex as TokioMutex, RwLock as TokioRwLock, mpsc, oneshot};
 use tracing::{debug, error, info, warn};
@@ -20,16 +21,18 @@ pub async fn set_runtime_handle(handle: RuntimeHandle) {
 
 use claw_autonomy::{
     ApprovalGate, ApprovalResponse, AutonomyLevel, BudgetTracker, GoalPlanner, GuardrailEngine,
+    PathScope,
 };
 use claw_channels::adapter::{Channel, ChannelEvent, OutgoingMessage};
 use claw_config::ClawConfig;
 use claw_core::{Event, EventBus};
 use claw_llm::{LlmProvider, ModelRouter};
-use claw_memory::MemoryStore;
+use claw_memory::{MemoryScope, MemoryStore, ScopeKind};
 use claw_mesh::{MeshMessage, MeshNode};
 use claw_plugin::PluginHost;
 use claw_skills::SkillRegistry;
 
+use crate::sandbox::Sandbox;
 use crate::scheduler::SchedulerHandle;
 use crate::session::SessionManager;
 use crate::tools::BuiltinTools;
@@ -150,6 +153,9 @@ pub enum StreamEvent {
         input_tokens: u32,
         output_tokens: u32,
         cost_usd: f64,
+        /// Model that answered, as chosen by the router.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        model: Option<String>,
     },
     #[serde(rename = "done")]
     Done,
@@ -246,6 +252,8 @@ pub struct SharedAgentState {
     pub sessions: SessionManager,
     pub budget: BudgetTracker,
     pub guardrails: Arc<GuardrailEngine>,
+    /// Where path-taking tools may read and write.
+    pub path_scope: Arc<PathScope>,
     pub approval: Arc<ApprovalGate>,
     pub plugins: Arc<PluginHost>,
     pub skills: Arc<TokioMutex<SkillRegistry>>,
@@ -254,6 +262,8 @@ pub struct SharedAgentState {
     pub planner: Arc<TokioMutex<GoalPlanner>>,
     pub channels: Arc<TokioMutex<Vec<Box<dyn Channel>>>>,
     pub embedder: Option<Arc<dyn claw_llm::EmbeddingProvider>>,
+    /// Tokenizer for the primary model — sizes prompts and tool results.
+    pub tokenizer: Arc<dyn claw_llm::Tokenizer>,
     pub mesh: Arc<TokioMutex<MeshNode>>,
     pub pending_mesh_tasks: PendingMeshTasks,
     pub pending_sub_tasks: PendingSubTasks,
@@ -268,6 +278,9 @@ pub struct SharedAgentState {
     /// Active stream sender for forwarding sub-agent events to the parent stream.
     /// Set at the start of process_message_streaming_shared or process_channel_message.
     pub stream_tx: Arc<TokioMutex<Option<mpsc::Sender<StreamEvent>>>>,
+    /// Memory scope a sub-agent inherits from the conversation that spawned
+    /// it. `None` = each message's scope comes from its channel and sender.
+    pub memory_scope: Option<MemoryScope>,
 }
 
 /// The response sent back to the API caller.
@@ -372,6 +385,62 @@ impl RuntimeHandle {
         crate::query::handle_query(&self.state, kind, self.started_at).await
     }
 
+    /// Ingest a file or directory into the knowledge base.
+    pub async fn kb_ingest(&self, path: &str) -> Result<serde_json::Value, String> {
+        crate::knowledge::ingest_path(&self.state, path)
+            .await
+            .map(|report| report.to_json())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Resolve a fact conflict, keeping `value` (default: the current value).
+    pub async fn resolve_fact_conflict(
+        &self,
+        id: &str,
+        value: Option<&str>,
+    ) -> Result<serde_json::Value, String> {
+        let conflict = crate::facts::resolve_conflict(&self.state, id, value)
+            .await
+            .map_err(|e| e.to_string())?;
+        serde_json::to_value(conflict).map_err(|e| e.to_string())
+    }
+
+    /// Export the memory database as a JSONL archive.
+    pub async fn memory_export(&self) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        self.state
+            .memory
+            .read()
+            .await
+            .export(&mut out)
+            .map_err(|e| e.to_string())?;
+        Ok(out)
+    }
+
+    /// Merge a JSONL archive into the memory database. `strategy` is `skip`,
+    /// `overwrite` or `newest-wins`.
+    pub async fn memory_import(
+        &self,
+        archive: &[u8],
+        strategy: &str,
+    ) -> Result<serde_json::Value, String> {
+        let strategy: claw_memory::MergeStrategy = strategy.parse().map_err(|e| format!("{e}"))?;
+        let mut mem = self.state.memory.write().await;
+        let report = mem
+            .import(&mut std::io::Cursor::new(archive), strategy)
+            .map_err(|e| e.to_string())?;
+        let total = report.total();
+        let _ = mem.audit(
+            "memory",
+            "import",
+            Some(&format!(
+                "strategy={strategy:?} inserted={} updated={} skipped={}",
+                total.inserted, total.updated, total.skipped
+            )),
+        );
+        serde_json::to_value(&report).map_err(|e| e.to_string())
+    }
+
     /// Subscribe to server-push notifications (cron results, etc.).
     pub fn subscribe_notifications(&self) -> tokio::sync::broadcast::Receiver<Notification> {
         self.notification_tx.subscribe()
@@ -405,17 +474,16 @@ impl AgentRuntime {
         info!("initializing agent runtime");
 
         // Resolve memory db_path relative to ~/.claw/ if it's not absolute
-        let db_path = if config.memory.db_path.is_absolute() {
-            config.memory.db_path.clone()
-        } else {
-            let config_base = dirs::home_dir()
-                .unwrap_or_else(|| std::path::PathBuf::from("."))
-                .join(".claw");
-            config_base.join(&config.memory.db_path)
-        };
+        let db_path = config.memory.resolved_db_path();
 
         // Initialize memory store
         let mut memory = MemoryStore::open(&db_path)?;
+        memory
+            .working
+            .set_token_counter(token_counter(claw_llm::tokenizer::for_model(
+                &config.agent.model,
+            )));
+        memory.set_decay_policy(decay_policy(&config.memory));
 
         // Load persisted facts into semantic memory
         match memory.load_facts() {
@@ -425,9 +493,7 @@ impl AgentRuntime {
         }
 
         // Initialize autonomy subsystems
-        let mut guardrails = GuardrailEngine::new();
-        guardrails.set_allowlist(config.autonomy.tool_allowlist.clone());
-        guardrails.set_denylist(config.autonomy.tool_denylist.clone());
+        let guardrails = guardrails(&config.autonomy)?;
 
         let budget = BudgetTracker::new(
             config.autonomy.daily_budget_usd,
@@ -478,7 +544,7 @@ impl AgentRuntime {
 
         Ok(Self {
             config: config.clone(),
-            llm: ModelRouter::new(),
+            llm: ModelRouter::from_config(&config),
             memory,
             sessions,
             guardrails,
@@ -486,7 +552,7 @@ impl AgentRuntime {
             planner,
             approval: ApprovalGate::new(),
             plugins,
-            tools: BuiltinTools::new(),
+            tools: BuiltinTools::with_sandbox(Sandbox::from_config(&config.runtime)?),
             channels: Vec::new(),
             event_bus: EventBus::default(),
         })
@@ -525,6 +591,7 @@ impl AgentRuntime {
             autonomy = %AutonomyLevel::from_u8(self.config.autonomy.level),
             "starting agent runtime"
         );
+        self.tools.sandbox().protect_host_process();
 
         // Clean up stale empty sessions from previous runs
         match self.memory.cleanup_empty_sessions() {
@@ -549,8 +616,7 @@ impl AgentRuntime {
                                     let msg_count = messages.len();
                                     let ctx = self.memory.working.session(id);
                                     ctx.messages = messages;
-                                    ctx.estimated_tokens =
-                                        ctx.messages.iter().map(|m| m.estimate_tokens()).sum();
+                                    self.memory.working.recount_tokens(id);
                                     tracing::debug!(session = %id, messages = msg_count, "restored session messages");
                                 }
                                 _ => {}
@@ -703,6 +769,7 @@ impl AgentRuntime {
             sessions: self.sessions,
             budget: self.budget,
             guardrails: Arc::new(self.guardrails),
+            path_scope: Arc::new(path_scope(&self.config)),
             approval: Arc::new(self.approval),
             plugins: Arc::new(self.plugins),
             skills: Arc::new(TokioMutex::new(skills)),
@@ -710,7 +777,8 @@ impl AgentRuntime {
             memory: Arc::new(TokioRwLock::new(self.memory)),
             planner: Arc::new(TokioMutex::new(self.planner)),
             channels: Arc::new(TokioMutex::new(self.channels)),
-            embedder: None,
+            embedder: claw_llm::embedding::embedder_from_config(&self.config),
+            tokenizer: claw_llm::tokenizer::for_model(&self.config.agent.model),
             mesh: Arc::new(TokioMutex::new(mesh_node)),
             pending_mesh_tasks: Arc::new(TokioMutex::new(HashMap::new())),
             pending_sub_tasks: Arc::new(TokioMutex::new(HashMap::new())),
@@ -719,8 +787,22 @@ impl AgentRuntime {
             http_client: reqwest::Client::new(),
             reply_context: Arc::new(TokioMutex::new(None)),
             stream_tx: Arc::new(TokioMutex::new(None)),
+            memory_scope: None,
         };
 
+        // ── Sync fact embeddings with the configured embedder ───────
+        if let Some(ref embedder) = state.embedder {
+            info!(embedder = %embedder.space(), "vector search enabled");
+            let s = state.clone();
+            tokio::spawn(async move {
+                match crate::learning::sync_fact_embeddings(&s).await {
+                    Ok(0) => {}
+                    Ok(n) => info!(count = n, "embedded stored facts"),
+                    Err(e) => warn!(error = %e, "failed to embed stored facts"),
+                }
+            });
+        }
+
         // ── Start mesh networking (if enabled) ─────────────────────
         let mut mesh_rx: Option<mpsc::Receiver<MeshMessage>> = None;
         if self.config.mesh.enabled {
@@ -832,6 +914,37 @@ impl AgentRuntime {
 
         info!("agent runtime started, waiting for messages");
 
+        // Re-scan knowledge base paths so edited documents stay current
+        if self.config.memory.kb_refresh_secs > 0 {
+            let state_for_kb = state.clone();
+            let period = std::time::Duration::from_secs(self.config.memory.kb_refresh_secs);
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(period);
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = crate::knowledge::refresh_all(&state_for_kb).await {
+                        warn!(error = %e, "knowledge base refresh failed");
+                    }
+                }
+            });
+        }
+
+        // Archive facts that have faded from disuse
+        if self.config.memory.fact_consolidation_secs > 0 {
+            let state_for_decay = state.clone();
+            let period = std::time::Duration::from_secs(self.config.memory.fact_consolidation_secs);
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(period);
+                loop {
+                    interval.tick().await;
+                    let mut mem = state_for_decay.memory.write().await;
+                    if let Err(e) = mem.consolidate_facts() {
+                        warn!(error = %e, "fact consolidation failed");
+                    }
+                }
+            });
+        }
+
         // Spawn a background task to persist sessions + messages periodically
         {
             let state_for_persist = state.clone();
@@ -1201,10 +1314,13 @@ impl AgentRuntime {
 
         info!("agent runtime shutting down — flushing sessions");
 
-        // Graceful shutdown: persist all sessions and working memory
+        // Graceful shutdown: persist all sessions, working memory and the fact index
         {
             let sessions = state.sessions.snapshot().await;
-            let mem = state.memory.read().await;
+            let mut mem = state.memory.write().await;
+            if let Err(e) = mem.persist_vector_index() {
+                warn!(error = %e, "failed to persist vector index");
+            }
             for session in &sessions {
                 if session.message_count == 0 {
                     continue;
@@ -1232,6 +1348,106 @@ impl AgentRuntime {
     }
 }
 
+/// Adapt a tokenizer into working memory's per-message token counter.
+fn token_counter(tokenizer: Arc<dyn claw_llm::Tokenizer>) -> claw_memory::TokenCounter {
+    Arc::new(move |message| tokenizer.count_message(message))
+}
+
+/// The fact decay policy configured in `[memory]`.
+fn decay_policy(config: &claw_config::schema::MemoryConfig) -> claw_memory::DecayPolicy {
+    claw_memory::DecayPolicy {
+        half_life_days: config.fact_half_life_days,
+        reinforcement: config.fact_reinforcement,
+        archive_below: config.fact_archive_below,
+        archive_retention_days: config.fact_archive_retention_days,
+        exempt_categories: config.fact_decay_exempt.clone(),
+    }
+}
+
+/// The guardrails configured in `[autonomy]`: allow/deny lists and the
+/// policy file, if any. A policy file that doesn't load is an error — the
+/// agent doesn't start without the rules it was given.
+pub fn guardrails(
+    config: &claw_config::schema::AutonomyConfig,
+) -> claw_core::Result<GuardrailEngine> {
+    let mut guardrails = GuardrailEngine::new();
+    guardrails.set_allowlist(config.tool_allowlist.clone());
+    guardrails.set_denylist(config.tool_denylist.clone());
+    if let Some(path) = config.resolved_policy_file() {
+        guardrails.set_policy(claw_autonomy::PolicyGuardrail::load(&path)?);
+    }
+    Ok(guardrails)
+}
+
+/// The filesystem scope configured in `[autonomy]`, rooted at the directory
+/// Claw runs in. Claw's own files are forbidden wherever they live: the
+/// config it loaded (API keys), the memory database with its journals and
+/// backups (every user's facts and sessions), the audit key and the
+/// guardrail policy file — an agent that could rewrite its policy could
+/// approve anything.
+pub fn path_scope(config: &ClawConfig) -> PathScope {
+    let workspace = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let db = config.memory.resolved_db_path();
+    PathScope::from_config(&config.autonomy, workspace)
+        .forbid([claw_memory::audit::key_path(&db)])
+        .forbid(config.autonomy.resolved_policy_file())
+        .forbid(config.source.as_ref())
+        .forbid_with_siblings([db])
+}
+
+/// The memory scope of a conversation: who is talking, on which channel and
+/// session, and what `[memory.scopes]` lets that channel read and write.
+/// Unknown scope names are skipped (config validation reports them).
+pub(crate) fn memory_scope(
+    config: &claw_config::schema::MemoryConfig,
+    channel: &str,
+    sender: &str,
+    session: Uuid,
+) -> MemoryScope {
+    let scopes = &config.scopes;
+    let (read, write, default_write) = scopes.rules_for(channel);
+    let kinds = |names: &[String]| -> Vec<ScopeKind> {
+        names.iter().filter_map(|n| n.parse().ok()).collect()
+    };
+    MemoryScope {
+        channel: Some(channel.to_string()),
+        user: Some(scopes.identity(channel, sender)),
+        session: Some(session),
+        read: kinds(read),
+        write: kinds(write),
+        default_write: default_write.parse().unwrap_or(ScopeKind::Global),
+    }
+}
+
+/// Sessions whose conversations a caller may search: its own and, if it may
+/// read its user scope, that person's other direct conversations (a DM
+/// session's target is the sender). Other people's conversations, and
+/// group chats the caller isn't in, stay out of reach.
+pub(crate) fn readable_sessions(
+    config: &claw_config::schema::MemoryConfig,
+    memory: &MemoryStore,
+    scope: &MemoryScope,
+) -> Vec<Uuid> {
+    let mut sessions: Vec<Uuid> = scope.session.into_iter().collect();
+    if let Some(user) = &scope.user
+        && scope.read.contains(&ScopeKind::User)
+    {
+        match memory.session_targets() {
+            Ok(targets) => sessions.extend(
+                targets
+                    .into_iter()
+                    .filter(|(id, channel, target)| {
+                        Some(id) != scope.session.as_ref()
+                            && &config.scopes.identity(channel, target) == user
+                    })
+                    .map(|(id, _, _)| id),
+            ),
+            Err(e) => warn!(error = %e, "failed to list sessions for conversation search"),
+        }
+    }
+    sessions
+}
+
 // ── Test helpers ──────────────────────────────────────────────
 
 /// Build a `SharedAgentState` suitable for testing (with in-memory DB, no channels).
@@ -1246,10 +1462,13 @@ pub fn build_test_state_with_router(
     config: ClawConfig,
     llm: ModelRouter,
 ) -> claw_core::Result<SharedAgentState> {
-    let memory = MemoryStore::open_in_memory()?;
-    let mut guardrails = GuardrailEngine::new();
-    guardrails.set_allowlist(config.autonomy.tool_allowlist.clone());
-    guardrails.set_denylist(config.autonomy.tool_denylist.clone());
+    let tokenizer = claw_llm::tokenizer::for_model(&config.agent.model);
+    let mut memory = MemoryStore::open_in_memory()?;
+    memory
+        .working
+        .set_token_counter(token_counter(tokenizer.clone()));
+    memory.set_decay_policy(decay_policy(&config.memory));
+    let guardrails = guardrails(&config.autonomy)?;
 
     let budget = BudgetTracker::new(
         config.autonomy.daily_budget_usd,
@@ -1262,10 +1481,11 @@ pub fn build_test_state_with_router(
     Ok(SharedAgentState {
         config: config.clone(),
         llm: Arc::new(llm),
-        tools: BuiltinTools::new(),
+        tools: BuiltinTools::with_sandbox(Sandbox::from_config(&config.runtime)?),
         sessions: SessionManager::new(),
         budget,
         guardrails: Arc::new(guardrails),
+        path_scope: Arc::new(path_scope(&config)),
         approval: Arc::new(approval),
         plugins: Arc::new(plugins),
         skills: Arc::new(TokioMutex::new(SkillRegistry::new_empty())),
@@ -1274,6 +1494,7 @@ pub fn build_test_state_with_router(
         planner: Arc::new(TokioMutex::new(planner)),
         channels: Arc::new(TokioMutex::new(Vec::new())),
         embedder: None,
+        tokenizer,
         mesh: Arc::new(TokioMutex::new(MeshNode::new().unwrap())),
         pending_mesh_tasks: Arc::new(TokioMutex::new(HashMap::new())),
         pending_sub_tasks: Arc::new(TokioMutex::new(HashMap::new())),
@@ -1282,6 +1503,7 @@ pub fn build_test_state_with_router(
         http_client: reqwest::Client::new(),
         reply_context: Arc::new(TokioMutex::new(None)),
         stream_tx: Arc::new(TokioMutex::new(None)),
+        memory_scope: None,
     })
 }
 
@@ -1289,7 +1511,7 @@ pub fn build_test_state_with_router(
 mod tests {
     use super::*;
     use crate::tool_dispatch::execute_tool_shared;
-    use claw_core::ToolCall;
+    use claw_core::{Message, Role, ToolCall};
     use claw_llm::mock::MockProvider;
 
     fn test_config() -> ClawConfig {
@@ -1297,6 +1519,8 @@ mod tests {
         config.agent.model = "mock/test-model".to_string();
         config.agent.max_iterations = 5;
         config.autonomy.level = 3; // autonomous — no approvals for low-risk
+        // Extraction runs in the background and would take queued responses
+        config.memory.graph_extraction = false;
         config
     }
 
@@ -1489,6 +1713,75 @@ mod tests {
         assert!(got_done, "expected done event");
     }
 
+    #[tokio::test]
+    async fn test_escalated_parallel_call_waits_for_approval() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = dir.path().join("policy.toml");
+        std::fs::write(
+            &policy,
+            r#"
+[[rule]]
+name = "reads-need-approval"
+tool = ["file_read"]
+action = "escalate"
+reason = "file reads need approval"
+"#,
+        )
+        .unwrap();
+        let secret = dir.path().join("secret.txt");
+        std::fs::write(&secret, "top secret").unwrap();
+
+        // Two parallel-safe calls in one turn — the batch would run concurrently
+        let read = |id: &str| ToolCall {
+            id: id.into(),
+            tool_name: "file_read".into(),
+            arguments: serde_json::json!({"path": secret.to_string_lossy()}),
+        };
+        let mock = MockProvider::new("mock")
+            .with_mock_response(claw_llm::mock::MockResponse {
+                tool_calls: vec![read("call_a"), read("call_b")],
+                stop_reason: claw_llm::StopReason::ToolUse,
+                ..Default::default()
+            })
+            .with_response("done");
+        let mut config = test_config();
+        config.agent.parallel_tool_calls = true;
+        config.autonomy.policy_file = Some(policy);
+        let mut router = ModelRouter::new();
+        router.add_provider(Arc::new(mock));
+        let state = build_test_state_with_router(config, router).unwrap();
+
+        let (chunk_tx, mut chunk_rx) = mpsc::channel::<StreamEvent>(256);
+        let task = tokio::spawn(process_stream_message(
+            state,
+            "Read the secret twice".into(),
+            None,
+            chunk_tx,
+        ));
+
+        let mut approval = None;
+        while let Some(event) = chunk_rx.recv().await {
+            match event {
+                StreamEvent::ApprovalRequired {
+                    tool_name, reason, ..
+                } => {
+                    approval = Some((tool_name, reason));
+                    break;
+                }
+                StreamEvent::ToolResult { content, .. } => {
+                    panic!("escalated call ran without approval: {content}")
+                }
+                StreamEvent::Done => break,
+                _ => {}
+            }
+        }
+        task.abort();
+
+        let (tool_name, reason) = approval.expect("expected an approval request");
+        assert_eq!(tool_name, "file_read");
+        assert!(reason.contains("file reads need approval"), "{reason}");
+    }
+
     #[tokio::test]
     async fn test_skill_system_prompt_injection() {
         let mock = MockProvider::new("mock").with_response("Mock LLM response");
@@ -1544,11 +1837,223 @@ mod tests {
             tool_name: "skill-my-skill".into(),
             arguments: serde_json::json!({}),
         };
-        let result = execute_tool_shared(&state, &call).await;
+        let result = execute_tool_shared(&state, &call, &MemoryScope::global()).await;
         assert!(result.is_error);
         assert!(result.content.contains("Tool not found"));
     }
 
+    #[tokio::test]
+    async fn test_file_tools_stay_in_path_scope() {
+        let mock = MockProvider::new("mock").with_response("unused");
+        let mut state = test_state_with_mock(mock);
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("ws");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(dir.path().join("outside.txt"), "outside").unwrap();
+        std::fs::write(workspace.join(".env"), "API_KEY=sk-123").unwrap();
+        state.path_scope = Arc::new(
+            PathScope::new(&workspace)
+                .workspace_only(true)
+                .forbid([workspace.join(".env")]),
+        );
+        let call = |tool: &str, args: serde_json::Value| ToolCall {
+            id: "test-call".into(),
+            tool_name: tool.into(),
+            arguments: args,
+        };
+
+        // Relative paths land in the workspace
+        let write = call(
+            "file_write",
+            serde_json::json!({ "path": "notes/todo.md", "content": "ship it" }),
+        );
+        let result = execute_tool_shared(&state, &write, &MemoryScope::global()).await;
+        assert!(!result.is_error, "{}", result.content);
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("notes/todo.md")).unwrap(),
+            "ship it"
+        );
+
+        for (tool, args) in [
+            ("file_read", serde_json::json!({ "path": ".env" })),
+            ("file_read", serde_json::json!({ "path": "../outside.txt" })),
+            ("file_list", serde_json::json!({ "path": dir.path() })),
+            (
+                "apply_patch",
+                serde_json::json!({ "edits": [
+                    { "path": "notes/todo.md", "old_string": "ship", "new_string": "test" },
+                    { "path": "notes/../../outside.txt", "old_string": "out", "new_string": "in" },
+                ] }),
+            ),
+        ] {
+            let result =
+                execute_tool_shared(&state, &call(tool, args), &MemoryScope::global()).await;
+            assert!(result.is_error, "{tool}: {}", result.content);
+            assert!(
+                result.content.starts_with("Access denied"),
+                "{tool}: {}",
+                result.content
+            );
+        }
+        // A denied patch applies none of its edits
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("notes/todo.md")).unwrap(),
+            "ship it"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("outside.txt")).unwrap(),
+            "outside"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_policy_file_is_out_of_reach() {
+        let mock = MockProvider::new("mock").with_response("unused");
+        let mut state = test_state_with_mock(mock);
+        let dir = tempfile::tempdir().unwrap();
+        let policy = dir.path().join("policy.toml");
+        std::fs::write(&policy, "").unwrap();
+        let mut config = test_config();
+        config.autonomy.policy_file = Some(policy.clone());
+        state.path_scope = Arc::new(path_scope(&config));
+
+        let path = policy.to_string_lossy();
+        for (tool, args) in [
+            (
+                "file_write",
+                serde_json::json!({
+                    "path": path,
+                    "content": "[[rule]]\nname = \"yes\"\naction = \"approve\"\n",
+                }),
+            ),
+            (
+                "apply_patch",
+                serde_json::json!({ "edits": [
+                    { "path": path, "old_string": "", "new_string": "[[rule]]" },
+                ] }),
+            ),
+        ] {
+            let call = ToolCall {
+                id: "test-call".into(),
+                tool_name: tool.into(),
+                arguments: args,
+            };
+            let result = execute_tool_shared(&state, &call, &MemoryScope::global()).await;
+            assert!(
+                result.content.starts_with("Access denied"),
+                "{tool}: {}",
+                result.content
+            );
+        }
+        assert_eq!(std::fs::read_to_string(&policy).unwrap(), "");
+    }
+
+    #[tokio::test]
+    async fn test_claw_files_are_out_of_reach() {
+        let mock = MockProvider::new("mock").with_response("unused");
+        let mut state = test_state_with_mock(mock);
+        let dir = tempfile::tempdir().unwrap();
+        let custom = dir.path().join("custom.toml");
+        let db = dir.path().join("data/memory.db");
+        std::fs::create_dir(dir.path().join("data")).unwrap();
+        for file in [
+            custom.clone(),
+            db.clone(),
+            dir.path().join("data/memory.db-wal"),
+            dir.path().join("data/memory.db.v3-20260101000000000.bak"),
+        ] {
+            std::fs::write(&file, "secret").unwrap();
+        }
+        std::fs::write(dir.path().join("data/notes.md"), "fine").unwrap();
+        // Loaded through --config or CLAW_CONFIG, not ~/.claw/claw.toml
+        let mut config = test_config();
+        config.source = Some(custom.clone());
+        config.memory.db_path = db.clone();
+        state.path_scope = Arc::new(path_scope(&config));
+
+        let read = |path: PathBuf| ToolCall {
+            id: "test-call".into(),
+            tool_name: "file_read".into(),
+            arguments: serde_json::json!({ "path": path }),
+        };
+        for name in [
+            "custom.toml",
+            "data/memory.db",
+            "data/memory.db-wal",
+            "data/memory.db.v3-20260101000000000.bak",
+        ] {
+            let result =
+                execute_tool_shared(&state, &read(dir.path().join(name)), &MemoryScope::global())
+                    .await;
+            assert!(
+                result.content.starts_with("Access denied"),
+                "{name}: {}",
+                result.content
+            );
+        }
+        let result = execute_tool_shared(
+            &state,
+            &read(dir.path().join("data/notes.md")),
+            &MemoryScope::global(),
+        )
+        .await;
+        assert!(!result.is_error, "{}", result.content);
+    }
+
+    #[tokio::test]
+    async fn test_kb_ingest_stays_in_path_scope() {
+        let mock = MockProvider::new("mock").with_response("unused");
+        let mut state = test_state_with_mock(mock);
+        state.embedder = Some(Arc::new(claw_llm::embedding::HashEmbedding::new(16)));
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("ws");
+        let secrets = workspace.join("docs/secrets");
+        std::fs::create_dir_all(&secrets).unwrap();
+        std::fs::create_dir(dir.path().join("outside")).unwrap();
+        std::fs::write(dir.path().join("outside/notes.md"), "outside notes").unwrap();
+        std::fs::write(workspace.join("docs/deploy.md"), "Run migrations first.").unwrap();
+        std::fs::write(secrets.join("keys.md"), "API_KEY=sk-123").unwrap();
+        let docs = workspace.join("docs").to_string_lossy().to_string();
+
+        // Ingested before the secrets were forbidden
+        state.path_scope = Arc::new(PathScope::new(&workspace).workspace_only(true));
+        let report = crate::knowledge::ingest_path(&state, &docs).await.unwrap();
+        assert_eq!(report.ingested, 2);
+
+        state.path_scope = Arc::new(
+            PathScope::new(&workspace)
+                .workspace_only(true)
+                .forbid([&secrets]),
+        );
+        for path in ["../outside", "docs"] {
+            let call = ToolCall {
+                id: "kb1".into(),
+                tool_name: "kb_ingest".into(),
+                arguments: serde_json::json!({ "path": path }),
+            };
+            let result = execute_tool_shared(&state, &call, &MemoryScope::global()).await;
+            assert!(
+                result.content.starts_with("Access denied"),
+                "{path}: {}",
+                result.content
+            );
+        }
+
+        // Every file the walk visits is checked, and a forbidden one that
+        // was stored earlier is dropped
+        let report = crate::knowledge::ingest_path(&state, &docs).await.unwrap();
+        assert_eq!((report.unchanged, report.removed), (1, 1));
+        assert!(
+            report.errors.iter().any(|e| e.contains("forbidden path")),
+            "{:?}",
+            report.errors
+        );
+        let hits = crate::knowledge::search(&state, "API_KEY", 5)
+            .await
+            .unwrap();
+        assert!(hits.iter().all(|h| !h.chunk.text.contains("sk-123")));
+    }
+
     #[tokio::test]
     async fn test_plugin_tool_dispatch_format() {
         let mock = MockProvider::new("mock").with_response("unused");
@@ -1560,7 +2065,545 @@ mod tests {
             tool_name: "nonexistent_plugin_some_tool".into(),
             arguments: serde_json::json!({}),
         };
-        let result = execute_tool_shared(&state, &call).await;
+        let result = execute_tool_shared(&state, &call, &MemoryScope::global()).await;
         assert!(result.is_error);
     }
+
+    #[tokio::test]
+    async fn test_sync_fact_embeddings_reembeds_on_provider_change() {
+        let mock = MockProvider::new("mock").with_response("unused");
+        let mut state = test_state_with_mock(mock);
+        state.embedder = Some(Arc::new(claw_llm::embedding::HashEmbedding::new(16)));
+        state
+            .memory
+            .write()
+            .await
+            .persist_fact("prefs", "theme", "dark")
+            .unwrap();
+        state.memory.write().await.load_facts().unwrap();
+
+        // First run embeds everything and records the vector space
+        assert_eq!(
+            crate::learning::sync_fact_embeddings(&state).await.unwrap(),
+            1
+        );
+        {
+            let mem = state.memory.read().await;
+            let fact = mem.semantic.get("global", "prefs", "theme").unwrap();
+            assert_eq!(fact.embedding.as_ref().map(|e| e.len()), Some(16));
+            assert_eq!(mem.meta("embedding_space").as_deref(), Some("builtin:16"));
+        }
+        // Nothing to do while the embedder is unchanged
+        assert_eq!(
+            crate::learning::sync_fact_embeddings(&state).await.unwrap(),
+            0
+        );
+
+        // A different embedder re-embeds stored facts
+        state.embedder = Some(Arc::new(claw_llm::embedding::HashEmbedding::new(32)));
+        assert_eq!(
+            crate::learning::sync_fact_embeddings(&state).await.unwrap(),
+            1
+        );
+        let mem = state.memory.read().await;
+        let fact = mem.semantic.get("global", "prefs", "theme").unwrap();
+        assert_eq!(fact.embedding.as_ref().map(|e| e.len()), Some(32));
+        let hits = mem
+            .semantic
+            .vector_search(fact.embedding.as_ref().unwrap(), 1);
+        assert_eq!(hits[0].0.key, "theme");
+    }
+
+    #[tokio::test]
+    async fn test_kb_ingest_only_reprocesses_changed_files() {
+        let mock = MockProvider::new("mock").with_response("unused");
+        let mut state = test_state_with_mock(mock);
+        state.embedder = Some(Arc::new(claw_llm::embedding::HashEmbedding::new(16)));
+        let dir = tempfile::tempdir().unwrap();
+        let docs = dir.path();
+        std::fs::write(
+            docs.join("deploy.md"),
+            "# Deploy\n\nRun migrations first.\n",
+        )
+        .unwrap();
+        std::fs::write(docs.join("style.txt"), "Indent with four spaces.\n").unwrap();
+        std::fs::write(docs.join("logo.png"), "not text").unwrap();
+        let path = docs.to_string_lossy().to_string();
+
+        let report = crate::knowledge::ingest_path(&state, &path).await.unwrap();
+        assert_eq!((report.files, report.ingested, report.unchanged), (2, 2, 0));
+
+        // Unchanged files are skipped; edits and deletions are picked up
+        std::fs::write(
+            docs.join("deploy.md"),
+            "# Deploy\n\nDrain the queue, then restart workers.\n",
+        )
+        .unwrap();
+        std::fs::remove_file(docs.join("style.txt")).unwrap();
+        let report = crate::knowledge::ingest_path(&state, &path).await.unwrap();
+        assert_eq!(
+            (report.ingested, report.unchanged, report.removed),
+            (1, 0, 1)
+        );
+        let report = crate::knowledge::ingest_path(&state, &path).await.unwrap();
+        assert_eq!((report.ingested, report.unchanged), (0, 1));
+
+        let call = ToolCall {
+            id: "kb1".into(),
+            tool_name: "kb_search".into(),
+            arguments: serde_json::json!({ "query": "restart workers" }),
+        };
+        let result = execute_tool_shared(&state, &call, &MemoryScope::global()).await;
+        assert!(!result.is_error);
+        assert!(result.content.contains("deploy.md (L1-3, § Deploy)"));
+        assert!(result.content.contains("Drain the queue"));
+        assert!(!result.content.contains("migrations"));
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_flags_flip_back_as_conflict() {
+        let mock = MockProvider::new("mock").with_response("unused");
+        let state = test_state_with_mock(mock);
+        let call = |id: &str, tool: &str, arguments: serde_json::Value| ToolCall {
+            id: id.into(),
+            tool_name: tool.into(),
+            arguments,
+        };
+        let store = |value: &str| {
+            call(
+                "store",
+                "memory_store",
+                serde_json::json!({ "category": "infra", "key": "dns", "value": value }),
+            )
+        };
+
+        // Changing our own fact is an update; flipping back is a contradiction
+        for value in ["bind9", "cloudflare"] {
+            let result = execute_tool_shared(&state, &store(value), &MemoryScope::global()).await;
+            assert!(
+                !result.content.contains("contradicts"),
+                "{}",
+                result.content
+            );
+        }
+        let result = execute_tool_shared(&state, &store("bind9"), &MemoryScope::global()).await;
+        assert!(
+            result
+                .content
+                .contains("contradicts the previous value \"cloudflare\"")
+        );
+
+        let list = call("list", "memory_conflicts", serde_json::json!({}));
+        let result = execute_tool_shared(&state, &list, &MemoryScope::global()).await;
+        assert!(
+            result.content.contains("1 conflict(s)"),
+            "{}",
+            result.content
+        );
+        assert!(result.content.contains("infra/dns"));
+
+        let id = state.memory.read().await.fact_conflicts(false).unwrap()[0]
+            .id
+            .clone();
+        let resolve = call(
+            "resolve",
+            "memory_conflicts",
+            serde_json::json!({ "action": "resolve", "id": &id[..8], "value": "cloudflare" }),
+        );
+        let result = execute_tool_shared(&state, &resolve, &MemoryScope::global()).await;
+        assert!(!result.is_error, "{}", result.content);
+        let mem = state.memory.read().await;
+        assert_eq!(
+            mem.semantic.get("global", "infra", "dns").unwrap().value,
+            "cloudflare"
+        );
+        assert!(mem.fact_conflicts(false).unwrap().is_empty());
+        assert_eq!(mem.fact_history("global", "infra", "dns").unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_graph_extraction_and_tools() {
+        let extraction = serde_json::json!({
+            "entities": [
+                { "kind": "server", "name": "web-01", "attributes": [{ "key": "ip", "value": "10.0.0.5" }] },
+                { "kind": "person", "name": "Alice", "attributes": [] }
+            ],
+            "relations": [
+                { "subject_kind": "service", "subject": "nginx", "predicate": "runs on",
+                  "object_kind": "server", "object": "web-01" },
+                { "subject_kind": "service", "subject": "nginx", "predicate": "owned_by",
+                  "object_kind": "person", "object": "Alice" },
+                { "subject_kind": "service", "subject": "nginx", "predicate": "self",
+                  "object_kind": "service", "object": "nginx" }
+            ]
+        });
+        let mock = MockProvider::new("mock").with_response(&extraction.to_string());
+        let mut state = test_state_with_mock(mock);
+        state
+            .config
+            .routing
+            .tasks
+            .insert("graph_extraction".into(), "mock/test-model".into());
+
+        // Only turns with something to say are sent for extraction
+        let sid = Uuid::new_v4();
+        let short = vec![Message::text(sid, Role::User, "thanks")];
+        assert!(crate::graph::turn_excerpt(&short).is_none());
+        let turn = vec![
+            Message::text(sid, Role::User, "old question"),
+            Message::text(sid, Role::Assistant, "old answer"),
+            Message::text(
+                sid,
+                Role::User,
+                "Alice just told me she owns the nginx service that runs on web-01 (10.0.0.5).",
+            ),
+            Message::text(sid, Role::Assistant, "Noted."),
+        ];
+        let excerpt = crate::graph::turn_excerpt(&turn).unwrap();
+        assert!(!excerpt.contains("old question"));
+
+        crate::graph::extract_and_store(&state, &excerpt, "session:test").await;
+        {
+            let mem = state.memory.read().await;
+            assert_eq!(mem.graph.counts().unwrap(), (3, 2));
+            let nginx = mem.graph.entity("service:nginx").unwrap().unwrap();
+            assert_eq!(nginx.source.as_deref(), Some("session:test"));
+        }
+
+        let call = ToolCall {
+            id: "g1".into(),
+            tool_name: "graph_neighbors".into(),
+            arguments: serde_json::json!({ "entity": "web-01", "depth": 2 }),
+        };
+        let result = execute_tool_shared(&state, &call, &MemoryScope::global()).await;
+        assert!(!result.is_error, "{}", result.content);
+        assert!(result.content.contains("server:web-01 — ip: 10.0.0.5"));
+        assert!(
+            result
+                .content
+                .contains("service:nginx —runs_on→ server:web-01")
+        );
+        assert!(
+            result
+                .content
+                .contains("service:nginx —owned_by→ person:alice")
+        );
+
+        let call = ToolCall {
+            id: "g2".into(),
+            tool_name: "graph_query".into(),
+            arguments: serde_json::json!({ "predicate": "owned_by", "object_kind": "person" }),
+        };
+        let result = execute_tool_shared(&state, &call, &MemoryScope::global()).await;
+        assert!(
+            result.content.contains("1 relation(s)"),
+            "{}",
+            result.content
+        );
+
+        let call = ToolCall {
+            id: "g3".into(),
+            tool_name: "graph_query".into(),
+            arguments: serde_json::json!({}),
+        };
+        assert!(
+            execute_tool_shared(&state, &call, &MemoryScope::global())
+                .await
+                .is_error
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remote_image_attachments_are_inlined_or_noted() {
+        use claw_channels::adapter::Attachment;
+        use claw_core::MessageContent;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        /// Answer one request with `content_type` and `body`.
+        async fn serve(content_type: &'static str, body: &'static [u8]) -> String {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = socket.write_all(head.as_bytes()).await;
+                let _ = socket.write_all(body).await;
+            });
+            format!("http://{addr}")
+        }
+
+        let state = test_state_with_mock(MockProvider::new("mock"));
+        // Slack's url_private without the bot token: its sign-in page
+        let slack = serve("text/html; charset=utf-8", b"<html>Sign in</html>").await;
+        let cdn = serve("image/png", b"\x89PNG\r\n\x1a\nfake").await;
+        let attachments = [
+            Attachment {
+                filename: "diagram.png".into(),
+                media_type: "image/png".into(),
+                data: format!("{slack}/files-pri/T0123-F0456/diagram.png"),
+            },
+            Attachment {
+                filename: "cat.png".into(),
+                media_type: "image/png".into(),
+                data: format!("{cdn}/attachments/1/2/cat.png"),
+            },
+        ];
+
+        let msg =
+            crate::agent_loop::user_message(&state, Uuid::new_v4(), "look", &attachments).await;
+        assert_eq!(msg.content.len(), 3);
+        let MessageContent::Text { text } = &msg.content[1] else {
+            panic!("expected a note, got {:?}", msg.content[1]);
+        };
+        assert!(text.contains("diagram.png") && text.contains("could not be downloaded"));
+        let MessageContent::Image { data, .. } = &msg.content[2] else {
+            panic!("expected an image, got {:?}", msg.content[2]);
+        };
+        assert!(data.starts_with("data:image/png;base64,"), "{data}");
+        let (images, notes) = claw_llm::media::message_media(&msg);
+        assert_eq!(images.len(), 1);
+        assert!(notes.is_empty());
+        assert!(!matches!(images[0], claw_llm::media::ImageSource::Url(_)));
+    }
+
+    #[tokio::test]
+    async fn test_graph_is_fed_by_global_conversations_only() {
+        let extraction = serde_json::json!({
+            "entities": [{ "kind": "server", "name": "web-01", "attributes": [] }],
+            "relations": []
+        });
+        let text = "My private box web-01 is where I keep the diaries, please remember that.";
+        let mock = MockProvider::new("mock")
+            .with_response("Noted.")
+            .with_response("Noted.")
+            .with_response(&extraction.to_string());
+        let requests = mock.recorded_requests();
+        let mut state = test_state_with_mock(mock);
+        state.config.memory.graph_extraction = true;
+        state
+            .config
+            .routing
+            .tasks
+            .insert("graph_extraction".into(), "mock/test-model".into());
+
+        // API conversations write user memory by default
+        let resp = process_api_message(state.clone(), text.into(), None).await;
+        assert!(resp.error.is_none(), "{:?}", resp.error);
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        assert_eq!(state.memory.read().await.graph.counts().unwrap(), (0, 0));
+
+        state.config.memory.scopes.channels.insert(
+            "api".into(),
+            claw_config::schema::ScopeRules {
+                default_write: Some("global".into()),
+                ..Default::default()
+            },
+        );
+        let resp = process_api_message(state.clone(), text.into(), None).await;
+        assert!(resp.error.is_none(), "{:?}", resp.error);
+        for _ in 0..50 {
+            if state.memory.read().await.graph.counts().unwrap().0 > 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(state.memory.read().await.graph.counts().unwrap(), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_lessons_stay_in_the_conversation_scope() {
+        let lessons = serde_json::json!({
+            "lessons": [{ "key": "diary_host", "lesson": "Alice's diaries live on web-01" }]
+        });
+        let mock = MockProvider::new("mock").with_response(&lessons.to_string());
+        let mut state = test_state_with_mock(mock);
+        state
+            .config
+            .routing
+            .tasks
+            .insert("lesson_extraction".into(), "mock/test-model".into());
+
+        let sid = Uuid::new_v4();
+        {
+            let mut mem = state.memory.write().await;
+            mem.working
+                .push(Message::text(sid, Role::User, "Where are my diaries?"));
+            mem.working
+                .push(Message::text(sid, Role::Assistant, "I cannot find them."));
+            mem.working.push(Message::text(
+                sid,
+                Role::User,
+                "They are on web-01, look there.",
+            ));
+        }
+        let alice = memory_scope(&state.config.memory, "telegram", "111", sid);
+        crate::learning::maybe_extract_lessons(&state, sid, &alice).await;
+
+        let mem = state.memory.read().await;
+        let stored = mem.semantic.category("learned_lessons");
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].scope, "user:telegram:111");
+        assert!(
+            mem.semantic
+                .get("global", "learned_lessons", "diary_host")
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_tools_keep_users_apart() {
+        let mock = MockProvider::new("mock").with_response("unused");
+        let mut state = test_state_with_mock(mock);
+        let scopes = &mut state.config.memory.scopes;
+        scopes
+            .identities
+            .insert("telegram:111".into(), "alice".into());
+        scopes
+            .identities
+            .insert("discord:alice#1".into(), "alice".into());
+        let scope_of = |channel: &str, sender: &str| {
+            memory_scope(&state.config.memory, channel, sender, Uuid::new_v4())
+        };
+        let alice = scope_of("telegram", "111");
+        let bob = scope_of("telegram", "222");
+        let call = |tool: &str, arguments: serde_json::Value| ToolCall {
+            id: "c".into(),
+            tool_name: tool.into(),
+            arguments,
+        };
+        let search = call(
+            "memory_search",
+            serde_json::json!({ "query": "favourite editor" }),
+        );
+
+        // Facts go to the speaker's own scope by default
+        let store = call(
+            "memory_store",
+            serde_json::json!({ "category": "prefs", "key": "favourite_editor", "value": "helix" }),
+        );
+        let result = execute_tool_shared(&state, &store, &alice).await;
+        assert!(
+            result.content.contains("[user:alice]"),
+            "{}",
+            result.content
+        );
+        assert!(
+            state
+                .memory
+                .read()
+                .await
+                .semantic
+                .get("user:alice", "prefs", "favourite_editor")
+                .is_some()
+        );
+
+        let result = execute_tool_shared(&state, &search, &bob).await;
+        assert!(!result.content.contains("helix"), "{}", result.content);
+        let list = call("memory_list", serde_json::json!({}));
+        let result = execute_tool_shared(&state, &list, &bob).await;
+        assert!(
+            result.content.contains("Memory is empty"),
+            "{}",
+            result.content
+        );
+
+        // The same person on another channel shares the scope
+        let result = execute_tool_shared(&state, &search, &scope_of("discord", "alice#1")).await;
+        assert!(result.content.contains("helix"), "{}", result.content);
+
+        // Bob can't delete Alice's fact
+        let delete = call(
+            "memory_delete",
+            serde_json::json!({ "category": "prefs", "key": "favourite_editor" }),
+        );
+        let result = execute_tool_shared(&state, &delete, &bob).await;
+        assert!(
+            result.content.contains("Fact not found"),
+            "{}",
+            result.content
+        );
+
+        // Global facts are shared with everyone
+        let store = call(
+            "memory_store",
+            serde_json::json!({ "category": "team", "key": "favourite_editor", "value": "zed", "scope": "global" }),
+        );
+        execute_tool_shared(&state, &store, &alice).await;
+        let result = execute_tool_shared(&state, &search, &bob).await;
+        assert!(result.content.contains("zed"), "{}", result.content);
+        assert!(!result.content.contains("helix"), "{}", result.content);
+
+        // Channels can be limited to their own scopes
+        state.config.memory.scopes.channels.insert(
+            "webchat".into(),
+            claw_config::schema::ScopeRules {
+                write: Some(vec!["session".into()]),
+                default_write: Some("session".into()),
+                ..Default::default()
+            },
+        );
+        let guest = memory_scope(&state.config.memory, "webchat", "guest", Uuid::new_v4());
+        let result = execute_tool_shared(&state, &store, &guest).await;
+        assert!(result.is_error, "{}", result.content);
+        assert!(result.content.contains("may not write global memory"));
+    }
+
+    #[tokio::test]
+    async fn test_memory_search_keeps_conversations_apart() {
+        let mock = MockProvider::new("mock").with_response("unused");
+        let mut state = test_state_with_mock(mock);
+        state
+            .config
+            .memory
+            .scopes
+            .identities
+            .insert("discord:alice#1".into(), "telegram:111".into());
+
+        // Alice told the agent something in her Telegram DM
+        let alice_dm = Uuid::new_v4();
+        {
+            let mem = state.memory.read().await;
+            mem.persist_session(&alice_dm, None, Some("telegram"), Some("111"), true, 1)
+                .unwrap();
+            let message = claw_core::Message::text(
+                alice_dm,
+                claw_core::Role::User,
+                "my locker combination is 4-8-15",
+            );
+            mem.persist_session_messages(&alice_dm, &[message]).unwrap();
+        }
+
+        let search = ToolCall {
+            id: "c".into(),
+            tool_name: "memory_search".into(),
+            arguments: serde_json::json!({ "query": "locker combination", "type": "conversations" }),
+        };
+        let scope_of = |channel: &str, sender: &str| {
+            memory_scope(&state.config.memory, channel, sender, Uuid::new_v4())
+        };
+
+        let result = execute_tool_shared(&state, &search, &scope_of("telegram", "222")).await;
+        assert!(!result.content.contains("4-8-15"), "{}", result.content);
+
+        // Alice finds it from a new session, and from her linked Discord account
+        for (channel, sender) in [("telegram", "111"), ("discord", "alice#1")] {
+            let result = execute_tool_shared(&state, &search, &scope_of(channel, sender)).await;
+            assert!(result.content.contains("4-8-15"), "{}", result.content);
+        }
+
+        // Without her user scope, only the current conversation is searchable
+        let mut session_only = scope_of("telegram", "111");
+        session_only.read = vec![ScopeKind::Session, ScopeKind::Global];
+        let result = execute_tool_shared(&state, &search, &session_only).await;
+        assert!(!result.content.contains("4-8-15"), "{}", result.content);
+        session_only.session = Some(alice_dm);
+        let result = execute_tool_shared(&state, &search, &session_only).await;
+        assert!(result.content.contains("4-8-15"), "{}", result.content);
+    }
 }
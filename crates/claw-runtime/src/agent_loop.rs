@@ -4,11 +4,14 @@ use tokio::sync::mpsc;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use claw_autonomy::{ApprovalResponse, AutonomyLevel, guardrail::GuardrailVerdict};
-use claw_channels::adapter::IncomingMessage;
-use claw_core::{Message, Role, Tool, ToolResult};
+use claw_autonomy::{
+    ApprovalResponse, AutonomyLevel, GuardrailContext, guardrail::GuardrailVerdict,
+};
+use claw_channels::adapter::{Attachment, IncomingMessage};
+use claw_config::TaskClass;
+use claw_core::{Message, MessageContent, Role, Tool, ToolCall, ToolResult};
 use claw_device::DeviceTools;
-use claw_llm::{LlmRequest, StopReason};
+use claw_llm::{LlmRequest, ResponseFormat, StopReason};
 use claw_mesh::MeshMessage;
 
 use crate::agent::{
@@ -145,22 +148,24 @@ pub(crate) async fn process_mesh_message(state: SharedAgentState, message: MeshM
                             .get("confidence")
                             .and_then(|v| v.as_f64())
                             .unwrap_or(0.8);
-                        let source = format!("mesh:{peer_id}");
-                        let mut mem = state.memory.write().await;
-                        // Upsert into in-memory semantic store
-                        mem.semantic.upsert(claw_memory::semantic::Fact {
+                        let fact = claw_memory::semantic::Fact {
                             id: uuid::Uuid::new_v4(),
+                            scope: claw_memory::scope::GLOBAL.to_string(),
                             category: category.to_string(),
                             key: key.to_string(),
                             value: value.to_string(),
                             confidence,
-                            source: Some(source.clone()),
+                            source: Some(format!("mesh:{peer_id}")),
                             embedding: None,
                             created_at: chrono::Utc::now(),
                             updated_at: chrono::Utc::now(),
-                        });
-                        // Persist to SQLite
-                        let _ = mem.persist_fact(category, key, value);
+                            access_count: 0,
+                            last_accessed: None,
+                        };
+                        // Store in semantic memory and SQLite, flagging conflicts
+                        if let Err(e) = crate::facts::store_fact(&state, fact).await {
+                            warn!(error = %e, "failed to store synced fact");
+                        }
                         info!(
                             category = category,
                             key = key,
@@ -191,6 +196,7 @@ pub(crate) async fn process_mesh_message(state: SharedAgentState, message: MeshM
                         let episode = claw_memory::episodic::Episode {
                             id: uuid::Uuid::new_v4(),
                             session_id: uuid::Uuid::new_v4(),
+                            scope: claw_memory::scope::GLOBAL.to_string(),
                             summary: summary.to_string(),
                             outcome,
                             tags,
@@ -205,6 +211,26 @@ pub(crate) async fn process_mesh_message(state: SharedAgentState, message: MeshM
                         );
                     }
                 }
+                "graph" => {
+                    // Merge a peer's extracted entities and relations
+                    match serde_json::from_value::<crate::graph::Extraction>(data) {
+                        Ok(extraction) => {
+                            let source = format!("mesh:{peer_id}");
+                            match crate::graph::apply(&state, &extraction, &source).await {
+                                Ok((entities, relations)) => info!(
+                                    entities,
+                                    relations,
+                                    from = %peer_id,
+                                    "synced knowledge graph from mesh peer"
+                                ),
+                                Err(e) => warn!(error = %e, "failed to store synced graph"),
+                            }
+                        }
+                        Err(e) => {
+                            warn!(from = %peer_id, error = %e, "received malformed graph sync delta");
+                        }
+                    }
+                }
                 other => {
                     debug!(delta_type = other, "unknown sync delta type — ignoring");
                 }
@@ -607,31 +633,200 @@ fn is_lazy_stop(text: &str, iteration: usize) -> bool {
     deferral_count >= threshold
 }
 
+/// Build the user's message, turning channel attachments into image / file blocks
+/// so vision-capable providers see photos users send. Images a channel hands
+/// over as URLs are downloaded and inlined: the URL may need the channel's
+/// credentials or expire, and an image the provider can't fetch would fail
+/// every later turn too. One that can't be downloaded becomes a text note.
+pub(crate) async fn user_message(
+    state: &SharedAgentState,
+    session_id: Uuid,
+    text: &str,
+    attachments: &[Attachment],
+) -> Message {
+    let mut msg = Message::text(session_id, Role::User, text);
+    for att in attachments {
+        if att.media_type.starts_with("image/") {
+            let data = if att.data.starts_with("http://") || att.data.starts_with("https://") {
+                match fetch_image(&state.http_client, &att.data).await {
+                    Some(data) => data,
+                    None => {
+                        msg.content.push(MessageContent::Text {
+                            text: format!(
+                                "[Image {} ({}) could not be downloaded: {}]",
+                                att.filename, att.media_type, att.data
+                            ),
+                        });
+                        continue;
+                    }
+                }
+            } else {
+                att.data.clone()
+            };
+            msg.content.push(MessageContent::Image {
+                data,
+                media_type: att.media_type.clone(),
+            });
+        } else {
+            msg.content.push(MessageContent::File {
+                path: att.data.clone(),
+                media_type: Some(att.media_type.clone()),
+            });
+        }
+    }
+    msg
+}
+
+/// Download an image as a `data:` URL. `None` unless the server answers
+/// with an image small enough to inline — a sign-in page isn't one.
+async fn fetch_image(client: &reqwest::Client, url: &str) -> Option<String> {
+    let resp = client
+        .get(url)
+        .timeout(std::time::Duration::from_secs(15))
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?;
+    let media_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)?
+        .to_str()
+        .ok()?
+        .split(';')
+        .next()?
+        .trim()
+        .to_string();
+    if !media_type.starts_with("image/")
+        || resp
+            .content_length()
+            .is_some_and(|len| len > claw_llm::media::MAX_IMAGE_BYTES)
+    {
+        debug!(url, media_type, "not inlining attachment");
+        return None;
+    }
+    let bytes = resp.bytes().await.ok()?;
+    if bytes.len() as u64 > claw_llm::media::MAX_IMAGE_BYTES {
+        return None;
+    }
+    Some(claw_llm::media::data_url(&media_type, &bytes))
+}
+
+/// Metadata key marking a tool result whose screenshot was auto-attached.
+const AUTO_ATTACHED_KEY: &str = "auto_attached_image";
+
+/// Attach the screenshot a device tool just took to its result message, so the
+/// model sees it on the next turn. Only the most recent auto-attached screenshot
+/// is kept as an image — older ones are replaced by a text note to bound cost.
+fn attach_screenshot(
+    working: &mut claw_memory::WorkingMemory,
+    result_msg: &mut Message,
+    tool_result: &ToolResult,
+) {
+    let Some(path) = tool_result
+        .data
+        .as_ref()
+        .and_then(|d| d["screenshot_path"].as_str())
+    else {
+        return;
+    };
+
+    let ctx = working.session(result_msg.session_id);
+    let mut stripped = false;
+    for old in ctx
+        .messages
+        .iter_mut()
+        .filter(|m| m.metadata.contains_key(AUTO_ATTACHED_KEY))
+    {
+        old.metadata.remove(AUTO_ATTACHED_KEY);
+        for block in old.content.iter_mut() {
+            if matches!(block, MessageContent::Image { .. }) {
+                *block = MessageContent::Text {
+                    text: "[earlier screenshot omitted]".into(),
+                };
+                stripped = true;
+            }
+        }
+    }
+    if stripped {
+        working.recount_tokens(result_msg.session_id);
+    }
+
+    result_msg.content.push(MessageContent::Image {
+        data: path.to_string(),
+        media_type: "image/png".into(),
+    });
+    result_msg
+        .metadata
+        .insert(AUTO_ATTACHED_KEY.into(), serde_json::Value::Bool(true));
+}
+
+/// The definition of a tool the model called. Unknown tools are treated as
+/// mutating and risky so the guardrails stay strict about them.
+fn tool_definition(tools: &[Tool], name: &str) -> Tool {
+    tools
+        .iter()
+        .find(|t| t.name == name)
+        .cloned()
+        .unwrap_or_else(|| Tool {
+            name: name.to_string(),
+            description: String::new(),
+            parameters: serde_json::Value::Null,
+            capabilities: vec![],
+            is_mutating: true,
+            risk_level: 5,
+            provider: None,
+        })
+}
+
+/// The call the guardrails judge. Terminal input is judged as the command
+/// lines it submits, pending input included, so a command typed in pieces
+/// is seen whole.
+async fn guarded_call(call: &ToolCall) -> ToolCall {
+    let (key, suffix) = match call.tool_name.as_str() {
+        "terminal_input" => ("text", ""),
+        "terminal_run" => ("command", "\n"),
+        _ => return call.clone(),
+    };
+    let (Some(id), Some(text)) = (
+        call.arguments["terminal_id"].as_u64(),
+        call.arguments[key].as_str(),
+    ) else {
+        return call.clone();
+    };
+    let submitted = crate::terminal::submitted_input(id as u32, &format!("{text}{suffix}")).await;
+    let mut guarded = call.clone();
+    guarded.arguments[key] = serde_json::Value::String(submitted);
+    guarded
+}
+
 /// Truncate a tool result to fit within the token budget.
 /// Preserves the beginning and end of the content, replacing the middle with a note.
-fn truncate_tool_result(content: &str, max_tokens: usize) -> String {
+fn truncate_tool_result(
+    content: &str,
+    max_tokens: usize,
+    tokenizer: &dyn claw_llm::Tokenizer,
+) -> String {
     if max_tokens == 0 {
         return content.to_string(); // 0 = no limit
     }
-    let max_chars = max_tokens * 4; // ~4 chars per token
-    if content.len() <= max_chars {
+    let total_tokens = tokenizer.count(content);
+    if total_tokens <= max_tokens {
         return content.to_string();
     }
 
+    // Convert the token budget to chars using this content's own density
+    let total_chars = content.chars().count();
+    let max_chars = max_tokens * total_chars / total_tokens;
+
     // Keep first 60% and last 20% of allowed chars, replace middle with truncation note
     let head_chars = (max_chars * 6) / 10;
     let tail_chars = (max_chars * 2) / 10;
     let head: String = content.chars().take(head_chars).collect();
-    let tail: String = content
-        .chars()
-        .rev()
-        .take(tail_chars)
-        .collect::<Vec<_>>()
-        .into_iter()
-        .rev()
-        .collect();
-    let omitted_chars = content.len() - head_chars - tail_chars;
-    let omitted_tokens = omitted_chars / 4;
+    let tail: String = content.chars().skip(total_chars - tail_chars).collect();
+    let omitted_chars = total_chars - head_chars - tail_chars;
+    let omitted_tokens =
+        total_tokens.saturating_sub(tokenizer.count(&head) + tokenizer.count(&tail));
 
     format!(
         "{head}\n\n[... truncated {omitted_tokens} tokens ({omitted_chars} chars) to fit context window ...]\n\n{tail}"
@@ -639,7 +834,7 @@ fn truncate_tool_result(content: &str, max_tokens: usize) -> String {
 }
 
 /// Perform LLM-powered compaction if the context is getting large.
-/// Uses the fast_model if available, otherwise the primary model.
+/// Uses the `compaction` task model (fast_model by default).
 async fn maybe_compact_context(
     state: &SharedAgentState,
     session_id: Uuid,
@@ -665,13 +860,7 @@ async fn maybe_compact_context(
 
     info!(session = %session_id, messages = messages_to_remove, "performing LLM-powered context compaction");
 
-    // Use fast model for compaction if available, otherwise primary
-    let compaction_model = state
-        .config
-        .agent
-        .fast_model
-        .as_deref()
-        .unwrap_or(&state.config.agent.model);
+    let compaction_model = state.config.model_for_task(TaskClass::Compaction);
 
     let compaction_prompt = format!(
         "Summarize this conversation history concisely. Preserve:\n\
@@ -680,12 +869,19 @@ async fn maybe_compact_context(
          - File paths, commands, and technical details that were discussed\n\
          - Any errors encountered and how they were resolved\n\
          - Current state of progress (what's done, what remains)\n\n\
-         Keep the summary under 500 words. Be factual and specific.\n\n\
+         Keep the summary under 500 words. Be factual and specific. \
+         Return it in the \"summary\" field.\n\n\
          Conversation to summarize:\n{text_to_summarize}"
     );
+    let summary_schema = serde_json::json!({
+        "type": "object",
+        "properties": { "summary": { "type": "string", "minLength": 1 } },
+        "required": ["summary"],
+        "additionalProperties": false
+    });
 
     let request = LlmRequest {
-        model: compaction_model.to_string(),
+        model: compaction_model,
         messages: vec![Message::text(Uuid::nil(), Role::User, &compaction_prompt)],
         tools: Arc::new(vec![]),
         system: Some(
@@ -695,12 +891,24 @@ async fn maybe_compact_context(
         max_tokens: 2048,
         temperature: 0.3,
         thinking_level: Some("off".to_string()),
+        response_format: Some(ResponseFormat::new("compaction_summary", summary_schema)),
         stream: false,
     };
 
-    match state.llm.complete(&request, None).await {
-        Ok(response) => {
-            let summary = response.message.text_content();
+    // The router has already validated the reply against the schema
+    let summary = state
+        .llm
+        .complete(&request, None)
+        .await
+        .and_then(|response| {
+            serde_json::from_str::<serde_json::Value>(&response.message.text_content())?["summary"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| claw_core::ClawError::LlmProvider("summary missing".into()))
+        });
+
+    match summary {
+        Ok(summary) => {
             let mut mem = state.memory.write().await;
             mem.working
                 .apply_llm_compaction(session_id, &summary, messages_to_remove);
@@ -723,6 +931,42 @@ async fn maybe_compact_context(
     }
 }
 
+/// Generate a short session title with the `title` task model. Only runs when
+/// `[routing.tasks] title` is configured; otherwise sessions are labelled with
+/// the start of the first message.
+async fn generate_session_title(state: &SharedAgentState, user_text: &str) -> Option<String> {
+    if !state.config.routing.tasks.contains_key("title") {
+        return None;
+    }
+    let excerpt: String = user_text.chars().take(1000).collect();
+    let request = LlmRequest {
+        model: state.config.model_for_task(TaskClass::Title),
+        messages: vec![Message::text(Uuid::nil(), Role::User, &excerpt)],
+        tools: Arc::new(vec![]),
+        system: Some(
+            "Write a title of at most six words for a conversation that starts with the \
+             user's message. Output only the title, without quotes."
+                .to_string(),
+        ),
+        max_tokens: 32,
+        temperature: 0.3,
+        thinking_level: Some("off".to_string()),
+        response_format: None,
+        stream: false,
+    };
+    match state.llm.complete(&request, None).await {
+        Ok(response) => {
+            let title = response.message.text_content();
+            let title = title.trim().trim_matches('"').trim();
+            (!title.is_empty()).then(|| title.chars().take(60).collect())
+        }
+        Err(e) => {
+            debug!(error = %e, "session title generation failed");
+            None
+        }
+    }
+}
+
 /// Core non-streaming message processing using shared state with fine-grained locks.
 pub(crate) async fn process_message_shared(
     state: &SharedAgentState,
@@ -764,6 +1008,17 @@ pub(crate) async fn process_message_streaming_shared(
         None => state.sessions.find_or_create(channel_id, target).await,
     };
 
+    // Whose memory this conversation recalls and writes
+    let scope = state.memory_scope.clone().unwrap_or_else(|| {
+        crate::agent::memory_scope(
+            &state.config.memory,
+            channel_id,
+            &incoming.sender,
+            session_id,
+        )
+    });
+    let readable = scope.readable();
+
     // Store reply context so channel_send_file tool can route to the right channel
     {
         let mut ctx = state.reply_context.lock().await;
@@ -776,7 +1031,9 @@ pub(crate) async fn process_message_streaming_shared(
         *stx = Some(tx.clone());
     }
 
+    let sender = incoming.sender;
     let user_text = incoming.text.unwrap_or_default();
+    let attachments = incoming.attachments;
 
     // 1. RECEIVE + RECALL — embed query (before lock) then search memory
     let query_embedding = if let Some(ref embedder) = state.embedder {
@@ -788,61 +1045,47 @@ pub(crate) async fn process_message_streaming_shared(
         None
     };
 
+    let user_msg = user_message(state, session_id, &user_text, &attachments).await;
     let (context_parts, active_goals) = {
         let mut mem = state.memory.write().await;
-        let user_msg = Message::text(session_id, Role::User, &user_text);
         mem.working.push(user_msg);
         drop(mem);
         state.sessions.record_message(session_id).await;
         let mem = state.memory.read().await;
 
-        let relevant_episodes = mem.episodic.search(&user_text);
-
-        // Build a combined keyword query from user text for broader matching
+        // Stop words dropped so BM25 ranks on the words that carry meaning
         let search_terms = extract_search_keywords(&user_text);
 
-        // Collect facts from multiple search strategies, dedup by category+key
-        let mut seen_fact_keys = std::collections::HashSet::new();
-        let mut relevant_facts: Vec<String> = Vec::new();
-
-        // Strategy 1: Vector search
-        if let Some(ref qemb) = query_embedding {
-            for (fact, _score) in mem.semantic.vector_search(qemb, 10) {
-                let fk = format!("{}:{}", fact.category, fact.key);
-                if seen_fact_keys.insert(fk) {
-                    relevant_facts.push(format!(
-                        "- [{}] {}: {}",
-                        fact.category, fact.key, fact.value
-                    ));
-                }
-            }
-        }
-
-        // Strategy 2: Word-level keyword search on user text
-        for fact in mem.semantic.search(&user_text).iter().take(10) {
-            let fk = format!("{}:{}", fact.category, fact.key);
-            if seen_fact_keys.insert(fk) {
-                relevant_facts.push(format!(
-                    "- [{}] {}: {}",
-                    fact.category, fact.key, fact.value
-                ));
-            }
-        }
-
-        // Strategy 3: Search with extracted keywords
-        if search_terms != user_text.to_lowercase() {
-            for fact in mem.semantic.search(&search_terms).iter().take(5) {
-                let fk = format!("{}:{}", fact.category, fact.key);
-                if seen_fact_keys.insert(fk) {
-                    relevant_facts.push(format!(
-                        "- [{}] {}: {}",
-                        fact.category, fact.key, fact.value
-                    ));
+        let relevant_episodes: Vec<claw_memory::Episode> =
+            match mem.search_episodes(&search_terms, Some(&readable), 5) {
+                Ok(hits) => hits.into_iter().map(|h| h.episode).collect(),
+                Err(e) => {
+                    warn!(error = %e, "episode full-text search failed");
+                    mem.episodic
+                        .search(&user_text)
+                        .into_iter()
+                        .filter(|e| scope.can_read(&e.scope))
+                        .cloned()
+                        .collect()
                 }
-            }
-        }
+            };
 
-        relevant_facts.truncate(15);
+        // Hybrid retrieval: BM25 keyword ranking fused with vector similarity,
+        // weighted towards confident, frequently recalled facts
+        let recalled = mem.hybrid_fact_search(
+            &search_terms,
+            query_embedding.as_deref(),
+            Some(&readable),
+            15,
+        );
+        let relevant_facts: Vec<String> = recalled
+            .iter()
+            .map(|(fact, _score)| format!("- [{}] {}: {}", fact.category, fact.key, fact.value))
+            .collect();
+        let recalled_keys: Vec<(String, String, String)> = recalled
+            .iter()
+            .map(|(fact, _score)| (fact.scope.clone(), fact.category.clone(), fact.key.clone()))
+            .collect();
 
         let mut parts = Vec::new();
         if !relevant_episodes.is_empty() {
@@ -863,11 +1106,36 @@ pub(crate) async fn process_message_streaming_shared(
             ));
         }
 
+        // Entities the user mentioned, with what they're connected to
+        let mentioned = mem.graph.mentioned_in(&user_text, 5).unwrap_or_default();
+        let mut graph_lines = Vec::new();
+        for entity in &mentioned {
+            graph_lines.push(format!("- {}", crate::graph::format_entity_line(entity)));
+            if let Ok(Some(neighborhood)) =
+                mem.graph
+                    .neighbors(&entity.id, 1, claw_memory::Direction::Both, None, 10)
+            {
+                graph_lines.extend(
+                    neighborhood
+                        .relations
+                        .iter()
+                        .map(|(_, r)| format!("  - {}", crate::graph::format_relation(r))),
+                );
+            }
+        }
+        if !graph_lines.is_empty() {
+            parts.push(format!(
+                "Known entities (graph_neighbors for more):\n{}",
+                graph_lines.join("\n")
+            ));
+        }
+
         // Always load learned lessons — these are high-value self-corrections
         let lessons: Vec<String> = mem
             .semantic
             .category("learned_lessons")
             .iter()
+            .filter(|f| scope.can_read(&f.scope))
             .map(|f| format!("- **{}**: {}", f.key, f.value))
             .collect();
         if !lessons.is_empty() {
@@ -879,6 +1147,13 @@ pub(crate) async fn process_message_streaming_shared(
 
         drop(mem);
 
+        // Recalled facts are reinforced so they fade more slowly
+        if !recalled_keys.is_empty()
+            && let Err(e) = state.memory.write().await.reinforce_facts(&recalled_keys)
+        {
+            warn!(error = %e, "failed to reinforce recalled facts");
+        }
+
         let planner = state.planner.lock().await;
         let goals: Vec<_> = planner.active_goals().into_iter().cloned().collect();
         drop(planner);
@@ -997,6 +1272,17 @@ pub(crate) async fn process_message_streaming_shared(
     let all_tools = Arc::new(all_tools_vec);
     state.budget.reset_loop();
 
+    // Prompt tokens outside working memory, for calibrating its estimate
+    let prompt_overhead = state.tokenizer.count(&system_prompt)
+        + all_tools
+            .iter()
+            .map(|t| {
+                state.tokenizer.count(&t.name)
+                    + state.tokenizer.count(&t.description)
+                    + state.tokenizer.count(&t.parameters.to_string())
+            })
+            .sum::<usize>();
+
     let autonomy_level = AutonomyLevel::from_u8(state.config.autonomy.level);
     let mut iteration = 0;
     let max_iterations = state.config.agent.max_iterations;
@@ -1021,10 +1307,7 @@ pub(crate) async fn process_message_streaming_shared(
     let mut last_turn_tool_names: Vec<String> = Vec::new();
 
     // Configure context window for this session
-    let context_window = claw_config::resolve_context_window(
-        state.config.agent.context_window,
-        &state.config.agent.model,
-    );
+    let context_window = state.config.context_window_for(&state.config.agent.model);
     {
         let mut mem = state.memory.write().await;
         mem.working.set_context_window(
@@ -1067,24 +1350,26 @@ pub(crate) async fn process_message_streaming_shared(
             mem.working.messages(session_id).to_vec()
         };
 
+        let model = if consecutive_llm_failures >= 3 {
+            state
+                .config
+                .agent
+                .fallback_model
+                .as_deref()
+                .unwrap_or(&state.config.agent.model)
+                .to_string()
+        } else {
+            state.config.agent.model.clone()
+        };
         let request = LlmRequest {
-            model: if consecutive_llm_failures >= 3 {
-                state
-                    .config
-                    .agent
-                    .fallback_model
-                    .as_deref()
-                    .unwrap_or(&state.config.agent.model)
-                    .to_string()
-            } else {
-                state.config.agent.model.clone()
-            },
+            max_tokens: state.config.max_tokens_for(&model),
+            model,
             messages,
             tools: all_tools.clone(),
             system: Some(system_prompt.clone()),
-            max_tokens: state.config.agent.max_tokens,
             temperature: state.config.agent.temperature,
             thinking_level: Some(state.config.agent.thinking_level.clone()),
+            response_format: None,
             stream: true,
         };
 
@@ -1162,6 +1447,7 @@ pub(crate) async fn process_message_streaming_shared(
                             input_tokens: usage.input_tokens,
                             output_tokens: usage.output_tokens,
                             cost_usd: usage.estimated_cost_usd,
+                            model: usage.model.clone(),
                         })
                         .await;
                 }
@@ -1173,6 +1459,12 @@ pub(crate) async fn process_message_streaming_shared(
                     let _ = tx.send(StreamEvent::Error { message: e }).await;
                     return Ok(());
                 }
+                claw_llm::StreamChunk::RateLimited { retry_after_secs } => {
+                    let message =
+                        claw_core::ClawError::RateLimited { retry_after_secs }.to_string();
+                    let _ = tx.send(StreamEvent::Error { message }).await;
+                    return Ok(());
+                }
             }
         }
 
@@ -1181,6 +1473,23 @@ pub(crate) async fn process_message_streaming_shared(
         // Store assistant message — brief lock
         {
             let mut mem = state.memory.write().await;
+            // Correct the token estimate with the provider's real prompt size,
+            // unless a different model (and tokenizer) answered
+            let reported_prompt = total_usage.input_tokens
+                + total_usage.cache_read_tokens
+                + total_usage.cache_write_tokens;
+            if reported_prompt > 0
+                && total_usage
+                    .model
+                    .as_deref()
+                    .is_none_or(|m| m == state.config.agent.model)
+            {
+                mem.working.record_prompt_tokens(
+                    session_id,
+                    reported_prompt as usize,
+                    prompt_overhead,
+                );
+            }
             let mut assistant_msg = Message::text(session_id, Role::Assistant, &full_text);
             assistant_msg.tool_calls = tool_calls.clone();
             mem.working.push(assistant_msg);
@@ -1251,40 +1560,48 @@ pub(crate) async fn process_message_streaming_shared(
         let parallel_enabled = state.config.agent.parallel_tool_calls;
         let can_parallelize = parallel_enabled && tool_calls.len() > 1;
 
-        if can_parallelize && tool_calls.iter().all(|tc| is_parallel_safe(&tc.tool_name)) {
-            // All tool calls are parallel-safe — run them all concurrently
+        // Decide the whole batch up front: a call that needs approval pulls
+        // the batch onto the sequential path, where the approval gate runs.
+        let parallel_verdicts =
+            if can_parallelize && tool_calls.iter().all(|tc| is_parallel_safe(&tc.tool_name)) {
+                let guardrail_ctx =
+                    GuardrailContext::new(autonomy_level).from_sender(channel_id, &sender);
+                let mut verdicts: Vec<GuardrailVerdict> = Vec::with_capacity(tool_calls.len());
+                for tc in &tool_calls {
+                    verdicts.push(state.guardrails.evaluate_in(
+                        &tool_definition(&all_tools, &tc.tool_name),
+                        &guarded_call(tc).await,
+                        &guardrail_ctx,
+                    ));
+                }
+                let needs_approval = verdicts
+                    .iter()
+                    .any(|v| matches!(v, GuardrailVerdict::Escalate(_)));
+                (!needs_approval).then_some(verdicts)
+            } else {
+                None
+            };
+
+        if let Some(verdicts) = parallel_verdicts {
+            // All tool calls are parallel-safe and decided — run them all concurrently
             let mut join_set = tokio::task::JoinSet::new();
-            for tool_call in tool_calls.clone() {
+            for (tool_call, verdict) in tool_calls.clone().into_iter().zip(verdicts) {
                 state.budget.record_tool_call()?;
-                let tool_def = all_tools
-                    .iter()
-                    .find(|t| t.name == tool_call.tool_name)
-                    .cloned()
-                    .unwrap_or_else(|| Tool {
-                        name: tool_call.tool_name.clone(),
-                        description: String::new(),
-                        parameters: serde_json::Value::Null,
-                        capabilities: vec![],
-                        is_mutating: true,
-                        risk_level: 5,
-                        provider: None,
-                    });
-                let verdict = state
-                    .guardrails
-                    .evaluate(&tool_def, &tool_call, autonomy_level);
                 let s = state.clone();
+                let sc = scope.clone();
                 let tc = tool_call.clone();
                 let tc_id = tool_call.id.clone();
                 join_set.spawn(async move {
                     let result = match verdict {
-                        GuardrailVerdict::Approve => execute_tool_shared(&s, &tc).await,
-                        GuardrailVerdict::Deny(reason) => ToolResult {
-                            tool_call_id: tc_id.clone(),
-                            content: format!("DENIED: {reason}"),
-                            is_error: true,
-                            data: None,
-                        },
-                        _ => execute_tool_shared(&s, &tc).await,
+                        GuardrailVerdict::Approve => execute_tool_shared(&s, &tc, &sc).await,
+                        GuardrailVerdict::Deny(reason) | GuardrailVerdict::Escalate(reason) => {
+                            ToolResult {
+                                tool_call_id: tc_id.clone(),
+                                content: format!("DENIED: {reason}"),
+                                is_error: true,
+                                data: None,
+                            }
+                        }
                     };
                     (tc_id, result)
                 });
@@ -1301,11 +1618,14 @@ pub(crate) async fn process_message_streaming_shared(
                             data: tool_result.data.clone(),
                         })
                         .await;
-                    let truncated_content =
-                        truncate_tool_result(&tool_result.content, tool_result_max_tokens);
+                    let truncated_content = truncate_tool_result(
+                        &tool_result.content,
+                        tool_result_max_tokens,
+                        state.tokenizer.as_ref(),
+                    );
                     {
                         let mut mem = state.memory.write().await;
-                        let result_msg = Message {
+                        let mut result_msg = Message {
                             id: Uuid::new_v4(),
                             session_id,
                             role: Role::Tool,
@@ -1318,6 +1638,9 @@ pub(crate) async fn process_message_streaming_shared(
                             tool_calls: vec![],
                             metadata: Default::default(),
                         };
+                        if state.config.agent.attach_screenshots {
+                            attach_screenshot(&mut mem.working, &mut result_msg, &tool_result);
+                        }
                         mem.working.push(result_msg);
                     }
                 }
@@ -1327,25 +1650,19 @@ pub(crate) async fn process_message_streaming_shared(
             for tool_call in &tool_calls {
                 state.budget.record_tool_call()?;
 
-                let tool_def = all_tools
-                    .iter()
-                    .find(|t| t.name == tool_call.tool_name)
-                    .cloned()
-                    .unwrap_or_else(|| Tool {
-                        name: tool_call.tool_name.clone(),
-                        description: String::new(),
-                        parameters: serde_json::Value::Null,
-                        capabilities: vec![],
-                        is_mutating: true,
-                        risk_level: 5,
-                        provider: None,
-                    });
+                let tool_def = tool_definition(&all_tools, &tool_call.tool_name);
 
-                let verdict = state
-                    .guardrails
-                    .evaluate(&tool_def, tool_call, autonomy_level);
+                let guardrail_ctx =
+                    GuardrailContext::new(autonomy_level).from_sender(channel_id, &sender);
+                let verdict = state.guardrails.evaluate_in(
+                    &tool_def,
+                    &guarded_call(tool_call).await,
+                    &guardrail_ctx,
+                );
                 let tool_result = match verdict {
-                    GuardrailVerdict::Approve => execute_tool_shared(state, tool_call).await,
+                    GuardrailVerdict::Approve => {
+                        execute_tool_shared(state, tool_call, &scope).await
+                    }
                     GuardrailVerdict::Deny(reason) => ToolResult {
                         tool_call_id: tool_call.id.clone(),
                         content: format!("DENIED: {reason}"),
@@ -1380,7 +1697,7 @@ pub(crate) async fn process_message_streaming_shared(
                             .await;
                         match response {
                             ApprovalResponse::Approved => {
-                                execute_tool_shared(state, tool_call).await
+                                execute_tool_shared(state, tool_call, &scope).await
                             }
                             ApprovalResponse::Denied => ToolResult {
                                 tool_call_id: tool_call.id.clone(),
@@ -1408,13 +1725,16 @@ pub(crate) async fn process_message_streaming_shared(
                     .await;
 
                 // Truncate tool result to fit context window
-                let truncated_content =
-                    truncate_tool_result(&tool_result.content, tool_result_max_tokens);
+                let truncated_content = truncate_tool_result(
+                    &tool_result.content,
+                    tool_result_max_tokens,
+                    state.tokenizer.as_ref(),
+                );
 
                 // Store tool result — brief lock
                 {
                     let mut mem = state.memory.write().await;
-                    let result_msg = Message {
+                    let mut result_msg = Message {
                         id: Uuid::new_v4(),
                         session_id,
                         role: Role::Tool,
@@ -1427,6 +1747,9 @@ pub(crate) async fn process_message_streaming_shared(
                         tool_calls: vec![],
                         metadata: Default::default(),
                     };
+                    if state.config.agent.attach_screenshots {
+                        attach_screenshot(&mut mem.working, &mut result_msg, &tool_result);
+                    }
                     mem.working.push(result_msg);
                 }
             }
@@ -1484,7 +1807,9 @@ pub(crate) async fn process_message_streaming_shared(
         // Build a brief summary for episodic memory
         let messages = mem.working.messages(session_id);
         let msg_count = messages.len();
-        if msg_count >= 2 {
+        if msg_count >= 2
+            && let Ok(episode_scope) = scope.write_scope(None)
+        {
             // Get last assistant text for summary
             let last_assistant = messages
                 .iter()
@@ -1496,6 +1821,7 @@ pub(crate) async fn process_message_streaming_shared(
             let episode = claw_memory::episodic::Episode {
                 id: uuid::Uuid::new_v4(),
                 session_id,
+                scope: episode_scope,
                 summary,
                 outcome: Some("completed".to_string()),
                 tags: extract_episode_tags(&user_text),
@@ -1507,20 +1833,47 @@ pub(crate) async fn process_message_streaming_shared(
     }
 
     // 6. LEARN — extract lessons from error→correction→success patterns
-    maybe_extract_lessons(state, session_id).await;
+    maybe_extract_lessons(state, session_id, &scope).await;
+
+    // 7. MAP — record the turn's entities and relations in the knowledge
+    // graph, off the response path. The graph is shared by every
+    // conversation and synced to mesh peers, so only conversations that
+    // write global memory feed it.
+    if state.config.memory.graph_extraction
+        && scope
+            .write_scope(None)
+            .is_ok_and(|s| s == claw_memory::scope::GLOBAL)
+    {
+        let excerpt = {
+            let mem = state.memory.read().await;
+            crate::graph::turn_excerpt(mem.working.messages(session_id))
+        };
+        if let Some(excerpt) = excerpt {
+            let state = state.clone();
+            tokio::spawn(async move {
+                let source = format!("session:{session_id}");
+                crate::graph::extract_and_store(&state, &excerpt, &source).await;
+            });
+        }
+    }
 
     // Auto-set session label from first user message if not yet set
     if let Some(session) = state.sessions.get(session_id).await
         && session.name.is_none()
         && !user_text.is_empty()
     {
-        let label: String = user_text.chars().take(60).collect();
-        let label = label
-            .split('\n')
-            .next()
-            .unwrap_or(&label)
-            .trim()
-            .to_string();
+        let label = match generate_session_title(state, &user_text).await {
+            Some(title) => title,
+            None => {
+                let label: String = user_text.chars().take(60).collect();
+                label
+                    .split('\n')
+                    .next()
+                    .unwrap_or(&label)
+                    .trim()
+                    .to_string()
+            }
+        };
         state.sessions.set_name(session_id, &label).await;
     }
 
@@ -60,7 +60,9 @@ pub(crate) fn tool_progress_emoji(name: &str) -> &'static str {
         "file_read" | "directory_list" | "file_list" | "file_find" => "📖",
         "process_start" | "terminal_run" => "🚀",
         "web_search" | "brave_search" => "🔍",
-        "memory_store" | "memory_search" | "memory_forget" => "🧠",
+        "memory_store" | "memory_search" | "memory_forget" | "memory_conflicts" => "🧠",
+        "graph_query" | "graph_neighbors" => "🕸️",
+        "kb_ingest" | "kb_search" | "kb_list_sources" => "📚",
         "goal_create" | "goal_update" => "🎯",
         "mesh_delegate" => "🌐",
         "channel_send_file" => "📎",
@@ -154,6 +156,8 @@ pub(crate) fn describe_tool_call(name: &str, args: &serde_json::Value) -> String
         }
         "memory_store" => "Storing memory".to_string(),
         "memory_search" => "Searching memory".to_string(),
+        "kb_ingest" => format!("Ingesting {}", args["path"].as_str().unwrap_or("…")),
+        "kb_search" => "Searching knowledge base".to_string(),
         "goal_create" => {
             let desc = args["description"].as_str().unwrap_or("…");
             let short: String = desc.chars().take(40).collect();
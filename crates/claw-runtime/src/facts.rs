@@ -0,0 +1,165 @@
+//! Storing facts — every write goes through [`store_fact`], which keeps the
+//! fact's history and flags values that contradict the one they replace.
+
+use std::sync::Arc;
+
+use claw_config::TaskClass;
+use claw_core::{Message, Result, Role};
+use claw_llm::{LlmRequest, ResponseFormat};
+use claw_memory::history::{self, ValueChange};
+use claw_memory::{Fact, FactConflict};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::agent::SharedAgentState;
+
+/// Store a fact. If it replaces a value it contradicts, a conflict is opened
+/// (or the fact's open conflict updated) and returned.
+pub(crate) async fn store_fact(
+    state: &SharedAgentState,
+    fact: Fact,
+) -> Result<Option<FactConflict>> {
+    let (previous, versions) = {
+        let mem = state.memory.read().await;
+        match mem
+            .semantic
+            .get(&fact.scope, &fact.category, &fact.key)
+            .cloned()
+        {
+            Some(previous) => {
+                let versions = mem.fact_history(&fact.scope, &fact.category, &fact.key)?;
+                (Some(previous), versions)
+            }
+            None => (None, Vec::new()),
+        }
+    };
+
+    let reason = match previous {
+        Some(ref previous)
+            if history::classify(&previous.value, &fact.value) == ValueChange::Changed =>
+        {
+            if state.config.memory.judge_fact_conflicts {
+                match judge_conflict(state, previous, &fact).await {
+                    Some(verdict) => verdict,
+                    None => history::detect_conflict(previous, &fact, &versions),
+                }
+            } else {
+                history::detect_conflict(previous, &fact, &versions)
+            }
+        }
+        _ => None,
+    };
+
+    let mut mem = state.memory.write().await;
+    let current = fact.clone();
+    let previous = mem.remember(fact)?.or(previous);
+    let (Some(reason), Some(previous)) = (reason, previous) else {
+        return Ok(None);
+    };
+    let conflict = mem.record_conflict(&previous, &current, Some(&reason))?;
+    info!(
+        category = %conflict.category,
+        key = %conflict.key,
+        conflict = %conflict.id,
+        reason = %reason,
+        "fact value conflicts with the one it replaced"
+    );
+    Ok(Some(conflict))
+}
+
+/// Resolve a conflict by keeping `value` (default: the current value),
+/// re-embedding the fact if its value changed.
+pub(crate) async fn resolve_conflict(
+    state: &SharedAgentState,
+    id: &str,
+    value: Option<&str>,
+) -> Result<FactConflict> {
+    let conflict = state.memory.write().await.resolve_conflict(id, value)?;
+    let kept = conflict.resolution.as_deref().unwrap_or(&conflict.value);
+    if kept != conflict.value
+        && let Some(ref embedder) = state.embedder
+    {
+        let text = format!("{} {} {kept}", conflict.category, conflict.key);
+        match embedder.embed(&[&text]).await {
+            Ok(vecs) if !vecs.is_empty() => {
+                state.memory.write().await.update_fact_embedding(
+                    &conflict.scope,
+                    &conflict.category,
+                    &conflict.key,
+                    &vecs[0],
+                )?;
+            }
+            Ok(_) => {}
+            Err(e) => warn!(error = %e, "failed to re-embed resolved fact"),
+        }
+    }
+    Ok(conflict)
+}
+
+/// Ask the `fact_conflict` task model whether `new` contradicts `previous`.
+/// `Some(Some(reason))` = contradiction, `Some(None)` = a legitimate update,
+/// `None` = the model couldn't be asked.
+async fn judge_conflict(
+    state: &SharedAgentState,
+    previous: &Fact,
+    new: &Fact,
+) -> Option<Option<String>> {
+    let prompt = format!(
+        "A stored fact is being given a new value.\n\n\
+         Fact: {}/{}\n\
+         Current value (source: {}): {}\n\
+         New value (source: {}): {}\n\n\
+         Decide whether the new value contradicts the current one — both claim to \
+         describe the same thing and can't both be right — or is a legitimate update \
+         (the situation changed, or the new value corrects or completes the old one).",
+        previous.category,
+        previous.key,
+        previous.source.as_deref().unwrap_or("unknown"),
+        previous.value,
+        new.source.as_deref().unwrap_or("unknown"),
+        new.value,
+    );
+    let request = LlmRequest {
+        model: state.config.model_for_task(TaskClass::FactConflict),
+        messages: vec![Message::text(Uuid::nil(), Role::User, &prompt)],
+        tools: Arc::new(vec![]),
+        system: Some(
+            "You judge whether facts contradict each other. Output only valid JSON.".to_string(),
+        ),
+        max_tokens: 256,
+        temperature: 0.0,
+        thinking_level: Some("off".to_string()),
+        response_format: Some(ResponseFormat::new("fact_conflict", verdict_schema())),
+        stream: false,
+    };
+    match state.llm.complete(&request, None).await {
+        Ok(response) => {
+            let value: serde_json::Value =
+                serde_json::from_str(&response.message.text_content()).ok()?;
+            let contradiction = value["contradiction"].as_bool()?;
+            let reason = value["reason"]
+                .as_str()
+                .filter(|r| !r.trim().is_empty())
+                .unwrap_or("judged contradictory")
+                .to_string();
+            Some(contradiction.then_some(reason))
+        }
+        Err(e) => {
+            debug!(error = %e, "fact conflict judgement failed");
+            None
+        }
+    }
+}
+
+/// JSON schema for `judge_conflict` replies.
+fn verdict_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "contradiction": { "type": "boolean" },
+            "reason": { "type": "string" }
+        },
+        "required": ["contradiction", "reason"],
+        "additionalProperties": false
+    })
+}
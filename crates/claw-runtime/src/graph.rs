@@ -0,0 +1,372 @@
+//! Knowledge graph extraction — after each turn the `graph_extraction` task
+//! model reads what was said and done, and the entities and relations it
+//! finds are merged into the graph and shared with mesh peers.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use claw_config::TaskClass;
+use claw_core::{Message, MessageContent, Result, Role};
+use claw_llm::{LlmRequest, ResponseFormat};
+use claw_memory::graph::{Neighborhood, Relation};
+use claw_mesh::MeshMessage;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::agent::SharedAgentState;
+
+/// Turns with less text than this aren't worth an extraction call.
+const MIN_EXCERPT_CHARS: usize = 80;
+
+/// Cap on the excerpt sent to the model.
+const MAX_EXCERPT_CHARS: usize = 6000;
+
+/// Confidence given to relations the model extracted.
+const EXTRACTED_CONFIDENCE: f64 = 0.8;
+
+/// Entities and relations found in a turn, or received from a mesh peer.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Extraction {
+    #[serde(default)]
+    pub entities: Vec<ExtractedEntity>,
+    #[serde(default)]
+    pub relations: Vec<ExtractedRelation>,
+}
+
+impl Extraction {
+    fn is_empty(&self) -> bool {
+        self.entities.is_empty() && self.relations.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ExtractedEntity {
+    pub kind: String,
+    pub name: String,
+    #[serde(default)]
+    pub attributes: Vec<Attribute>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Attribute {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ExtractedRelation {
+    pub subject_kind: String,
+    pub subject: String,
+    pub predicate: String,
+    pub object_kind: String,
+    pub object: String,
+    #[serde(default = "default_confidence")]
+    pub confidence: f64,
+}
+
+fn default_confidence() -> f64 {
+    EXTRACTED_CONFIDENCE
+}
+
+/// The latest turn of a conversation — from the last user message on — as
+/// text for the extractor. `None` if there's too little to bother.
+pub(crate) fn turn_excerpt(messages: &[Message]) -> Option<String> {
+    let start = messages.iter().rposition(|m| m.role == Role::User)?;
+    let mut excerpt = String::new();
+    for msg in &messages[start..] {
+        let role = match msg.role {
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+            Role::Tool => "Tool",
+            Role::System => continue,
+        };
+        let text = msg.text_content();
+        if !text.trim().is_empty() {
+            let text: String = text.chars().take(1500).collect();
+            excerpt.push_str(&format!("[{role}]: {text}\n"));
+        }
+        for tc in &msg.tool_calls {
+            let args: String = tc.arguments.to_string().chars().take(300).collect();
+            excerpt.push_str(&format!("[Tool Call]: {}({args})\n", tc.tool_name));
+        }
+        for content in &msg.content {
+            if let MessageContent::ToolResult {
+                content, is_error, ..
+            } = content
+                && !is_error
+            {
+                let result: String = content.chars().take(800).collect();
+                excerpt.push_str(&format!("[Tool Result]: {result}\n"));
+            }
+        }
+    }
+    let excerpt: String = excerpt.chars().take(MAX_EXCERPT_CHARS).collect();
+    (excerpt.trim().len() >= MIN_EXCERPT_CHARS).then_some(excerpt)
+}
+
+/// Extract entities and relations from a turn excerpt, store them with
+/// `source` as provenance and broadcast them to mesh peers.
+pub(crate) async fn extract_and_store(state: &SharedAgentState, excerpt: &str, source: &str) {
+    let known: Vec<String> = match state.memory.read().await.graph.mentioned_in(excerpt, 30) {
+        Ok(entities) => entities.into_iter().map(|e| e.id).collect(),
+        Err(e) => {
+            warn!(error = %e, "failed to look up known graph entities");
+            vec![]
+        }
+    };
+    let Some(extraction) = extract_via_llm(state, excerpt, &known).await else {
+        return;
+    };
+    if extraction.is_empty() {
+        return;
+    }
+
+    match apply(state, &extraction, source).await {
+        Ok((entities, relations)) => {
+            info!(entities, relations, source, "updated knowledge graph");
+        }
+        Err(e) => {
+            warn!(error = %e, "failed to store extracted graph");
+            return;
+        }
+    }
+
+    let mesh = state.mesh.lock().await;
+    if mesh.is_running()
+        && mesh.peer_count() > 0
+        && let Ok(data) = serde_json::to_value(&extraction)
+    {
+        let sync_msg = MeshMessage::SyncDelta {
+            peer_id: mesh.peer_id().to_string(),
+            delta_type: "graph".to_string(),
+            data,
+        };
+        let _ = mesh.broadcast(&sync_msg).await;
+    }
+}
+
+/// Merge an extraction into the graph. Relations create the entities they
+/// name. Malformed items are skipped. Returns the entities and relations
+/// written.
+pub(crate) async fn apply(
+    state: &SharedAgentState,
+    extraction: &Extraction,
+    source: &str,
+) -> Result<(usize, usize)> {
+    let mem = state.memory.read().await;
+    let graph = &mem.graph;
+    let mut entities = 0;
+    for entity in &extraction.entities {
+        let attributes: BTreeMap<String, String> = entity
+            .attributes
+            .iter()
+            .filter(|a| !a.value.trim().is_empty())
+            .map(|a| (a.key.clone(), a.value.clone()))
+            .collect();
+        match graph.upsert_entity(&entity.kind, &entity.name, &attributes, Some(source)) {
+            Ok(_) => entities += 1,
+            Err(e) => debug!(error = %e, name = %entity.name, "skipping extracted entity"),
+        }
+    }
+    let mut relations = 0;
+    let no_attributes = BTreeMap::new();
+    for relation in &extraction.relations {
+        let endpoints = graph
+            .upsert_entity(
+                &relation.subject_kind,
+                &relation.subject,
+                &no_attributes,
+                Some(source),
+            )
+            .and_then(|subject| {
+                graph
+                    .upsert_entity(
+                        &relation.object_kind,
+                        &relation.object,
+                        &no_attributes,
+                        Some(source),
+                    )
+                    .map(|object| (subject, object))
+            });
+        let result = endpoints.and_then(|(subject, object)| {
+            if subject.id == object.id {
+                return Err(claw_core::ClawError::Memory(
+                    "relation from an entity to itself".into(),
+                ));
+            }
+            graph.relate(
+                &subject.id,
+                &relation.predicate,
+                &object.id,
+                relation.confidence,
+                Some(source),
+            )
+        });
+        match result {
+            Ok(_) => relations += 1,
+            Err(e) => debug!(error = %e, "skipping extracted relation"),
+        }
+    }
+    Ok((entities, relations))
+}
+
+/// Ask the `graph_extraction` task model for the entities and relations in
+/// an excerpt. `None` if the call failed.
+async fn extract_via_llm(
+    state: &SharedAgentState,
+    excerpt: &str,
+    known: &[String],
+) -> Option<Extraction> {
+    let known = if known.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "Entities already in the graph (reuse these kinds and names):\n{}\n\n",
+            known.join("\n")
+        )
+    };
+    let prompt = format!(
+        "Extract a knowledge graph from this conversation turn.\n\n\
+         Entities are concrete, named things worth remembering: people, servers, \
+         services, websites, domains, projects, repositories, organizations, \
+         databases. Give each a lowercase \"kind\" (person, server, service, \
+         website, domain, project, repo, organization, database, ...) and its \
+         proper \"name\". Add attributes that describe the entity itself \
+         (ip, os, email, role, url, version, ...).\n\n\
+         Relations link two entities with a short snake_case predicate, e.g. \
+         runs_on, hosted_on, owned_by, maintained_by, depends_on, member_of, \
+         deployed_to, part_of. Phrase them subject → object (\"nginx runs_on web-01\").\n\n\
+         Only include what the turn states or a tool result shows — no guesses, \
+         no generic concepts, no files or commands. If there's nothing, return \
+         empty arrays.\n\n\
+         {known}Turn:\n{excerpt}\n"
+    );
+    let request = LlmRequest {
+        model: state.config.model_for_task(TaskClass::GraphExtraction),
+        messages: vec![Message::text(Uuid::nil(), Role::User, &prompt)],
+        tools: Arc::new(vec![]),
+        system: Some(
+            "You are a precise knowledge graph extractor. Output only valid JSON.".to_string(),
+        ),
+        max_tokens: 1024,
+        temperature: 0.0,
+        thinking_level: Some("off".to_string()),
+        response_format: Some(ResponseFormat::new("graph", extraction_schema())),
+        stream: false,
+    };
+    match state.llm.complete(&request, None).await {
+        Ok(response) => serde_json::from_str(&response.message.text_content())
+            .map_err(|e| debug!(error = %e, "unparseable graph extraction"))
+            .ok(),
+        Err(e) => {
+            debug!(error = %e, "graph extraction LLM call failed");
+            None
+        }
+    }
+}
+
+/// JSON schema for `extract_via_llm` replies.
+fn extraction_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "entities": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "kind": { "type": "string" },
+                        "name": { "type": "string" },
+                        "attributes": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "key": { "type": "string" },
+                                    "value": { "type": "string" }
+                                },
+                                "required": ["key", "value"],
+                                "additionalProperties": false
+                            }
+                        }
+                    },
+                    "required": ["kind", "name", "attributes"],
+                    "additionalProperties": false
+                }
+            },
+            "relations": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "subject_kind": { "type": "string" },
+                        "subject": { "type": "string" },
+                        "predicate": { "type": "string" },
+                        "object_kind": { "type": "string" },
+                        "object": { "type": "string" }
+                    },
+                    "required": ["subject_kind", "subject", "predicate", "object_kind", "object"],
+                    "additionalProperties": false
+                }
+            }
+        },
+        "required": ["entities", "relations"],
+        "additionalProperties": false
+    })
+}
+
+/// One relation as a line: `service:nginx —runs_on→ server:web-01`.
+pub(crate) fn format_relation(relation: &Relation) -> String {
+    let mut line = relation.to_string();
+    if relation.confidence < 1.0 {
+        line.push_str(&format!(" ({:.0}%)", relation.confidence * 100.0));
+    }
+    line
+}
+
+/// A neighbourhood as text: the entity and its attributes, then the edges
+/// found at each hop.
+pub(crate) fn format_neighborhood(n: &Neighborhood) -> String {
+    let mut lines = vec![format_entity_line(&n.root)];
+    if n.relations.is_empty() {
+        lines.push("  (no relations)".to_string());
+    }
+    let mut hop = 0;
+    for (depth, relation) in &n.relations {
+        if *depth != hop {
+            hop = *depth;
+            lines.push(format!("\nHop {hop}:"));
+        }
+        lines.push(format!("  {}", format_relation(relation)));
+    }
+    let described: Vec<String> = n
+        .entities
+        .iter()
+        .filter(|e| !e.attributes.is_empty())
+        .map(format_entity_line)
+        .collect();
+    if !described.is_empty() {
+        lines.push("\nEntities:".to_string());
+        lines.extend(described.into_iter().map(|l| format!("  {l}")));
+    }
+    lines.join("\n")
+}
+
+/// `server:web-01 (Web-01) — ip: 10.0.0.5, os: debian`
+pub(crate) fn format_entity_line(entity: &claw_memory::Entity) -> String {
+    let mut line = entity.id.clone();
+    // Ids are lowercased; show the name as written when that differs
+    if entity.id.get(entity.kind.len() + 1..) != Some(entity.name.as_str()) {
+        line.push_str(&format!(" ({})", entity.name));
+    }
+    if !entity.attributes.is_empty() {
+        let attrs: Vec<String> = entity
+            .attributes
+            .iter()
+            .map(|(k, v)| format!("{k}: {v}"))
+            .collect();
+        line.push_str(&format!(" — {}", attrs.join(", ")));
+    }
+    line
+}
@@ -0,0 +1,277 @@
+//! Knowledge base ingestion — reads files from disk, chunks and embeds them,
+//! and keeps the stored passages in step with the files on later runs.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use claw_core::{ClawError, Result};
+use claw_memory::knowledge::{self, ChunkOptions, DocumentKind, KbHit, KbSource};
+use tracing::{debug, info, warn};
+
+use crate::agent::SharedAgentState;
+
+/// Passages embedded per embedder call.
+const EMBED_BATCH: usize = 64;
+
+/// Outcome of one ingest run.
+#[derive(Debug, Default)]
+pub(crate) struct IngestReport {
+    pub root: String,
+    pub files: usize,
+    pub ingested: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    pub chunks: usize,
+    pub errors: Vec<String>,
+}
+
+impl IngestReport {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "root": self.root,
+            "files": self.files,
+            "ingested": self.ingested,
+            "unchanged": self.unchanged,
+            "removed": self.removed,
+            "chunks": self.chunks,
+            "errors": self.errors,
+        })
+    }
+
+    pub fn summary(&self) -> String {
+        let mut out = format!(
+            "Ingested {}: {} file(s) scanned, {} (re-)ingested into {} chunk(s), {} unchanged, {} removed.",
+            self.root, self.files, self.ingested, self.chunks, self.unchanged, self.removed
+        );
+        for e in &self.errors {
+            out.push_str(&format!("\n  ⚠️ {e}"));
+        }
+        out
+    }
+}
+
+fn modified_secs(meta: &std::fs::Metadata) -> i64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Ingest a file or directory into the knowledge base. Files whose size,
+/// modification time or content hash are unchanged since the last run are
+/// skipped, as are files under a forbidden path; stored files under `path`
+/// that no longer exist are removed.
+pub(crate) async fn ingest_path(state: &SharedAgentState, path: &str) -> Result<IngestReport> {
+    let root = std::fs::canonicalize(path)
+        .map_err(|e| ClawError::Agent(format!("cannot ingest {path}: {e}")))?;
+    let root_str = root.to_string_lossy().to_string();
+    let files = {
+        let root = root.clone();
+        tokio::task::spawn_blocking(move || knowledge::collect_files(&root))
+            .await
+            .map_err(|e| ClawError::Agent(e.to_string()))??
+    };
+    state.memory.read().await.knowledge.add_root(&root_str)?;
+
+    let options = ChunkOptions {
+        max_chars: state.config.memory.kb_chunk_chars,
+        overlap: state.config.memory.kb_chunk_overlap,
+    };
+    let mut report = IngestReport {
+        root: root_str.clone(),
+        files: files.len(),
+        ..Default::default()
+    };
+
+    // Forbidden paths stay off limits wherever the walk finds them; files
+    // already stored from one are removed below
+    let files: Vec<PathBuf> = files
+        .into_iter()
+        .filter(|file| match state.path_scope.forbidden_by(file) {
+            Some(forbidden) => {
+                warn!(file = %file.display(), "knowledge base ingest skipped a forbidden path");
+                report.errors.push(format!(
+                    "{}: skipped, inside forbidden path {} (autonomy.forbidden_paths)",
+                    file.display(),
+                    forbidden.display()
+                ));
+                false
+            }
+            None => true,
+        })
+        .collect();
+    for file in &files {
+        match ingest_file(state, &root_str, file, options).await {
+            Ok(Some(chunks)) => {
+                report.ingested += 1;
+                report.chunks += chunks;
+            }
+            Ok(None) => report.unchanged += 1,
+            Err(e) => {
+                warn!(file = %file.display(), error = %e, "knowledge base ingest failed");
+                report.errors.push(format!("{}: {e}", file.display()));
+            }
+        }
+    }
+
+    let present: HashSet<String> = files
+        .iter()
+        .map(|f| f.to_string_lossy().to_string())
+        .collect();
+    report.removed = remove_missing(state, &root_str, &present).await?;
+
+    if report.ingested + report.removed > 0 {
+        info!(
+            root = %root_str,
+            ingested = report.ingested,
+            unchanged = report.unchanged,
+            removed = report.removed,
+            "knowledge base updated"
+        );
+    }
+    Ok(report)
+}
+
+/// Ingest one file. Returns the new chunk count, or `None` if unchanged.
+async fn ingest_file(
+    state: &SharedAgentState,
+    root: &str,
+    file: &Path,
+    options: ChunkOptions,
+) -> Result<Option<usize>> {
+    let meta = std::fs::metadata(file)?;
+    if meta.len() > knowledge::MAX_FILE_BYTES {
+        return Err(ClawError::Agent(format!(
+            "skipped, larger than {} MB",
+            knowledge::MAX_FILE_BYTES / (1024 * 1024)
+        )));
+    }
+    let path = file.to_string_lossy().to_string();
+    let size = meta.len();
+    let modified = modified_secs(&meta);
+    let existing = state.memory.read().await.knowledge.source(&path);
+    if existing
+        .as_ref()
+        .is_some_and(|s| s.size == size && s.modified == modified)
+    {
+        return Ok(None);
+    }
+
+    let kind = DocumentKind::from_path(file)
+        .ok_or_else(|| ClawError::Agent("unsupported file type".into()))?;
+    let owned = file.to_path_buf();
+    let (hash, chunks) = tokio::task::spawn_blocking(move || -> Result<_> {
+        let hash = knowledge::content_hash(&std::fs::read(&owned)?);
+        let text = knowledge::extract_text(&owned, kind)?;
+        Ok((hash, knowledge::chunk_text(&text, kind, options)))
+    })
+    .await
+    .map_err(|e| ClawError::Agent(e.to_string()))??;
+
+    if existing.as_ref().is_some_and(|s| s.hash == hash) {
+        state
+            .memory
+            .read()
+            .await
+            .knowledge
+            .touch_source(&path, size, modified)?;
+        return Ok(None);
+    }
+
+    let embeddings = match state.embedder {
+        Some(ref embedder) => {
+            let mut all = Vec::with_capacity(chunks.len());
+            for batch in chunks.chunks(EMBED_BATCH) {
+                let texts: Vec<&str> = batch.iter().map(|c| c.text.as_str()).collect();
+                match embedder.embed(&texts).await {
+                    Ok(vectors) => all.extend(vectors),
+                    Err(e) => {
+                        warn!(file = %path, error = %e, "failed to embed passages, keyword search only");
+                        break;
+                    }
+                }
+            }
+            (all.len() == chunks.len()).then_some(all)
+        }
+        None => None,
+    };
+
+    let source = KbSource {
+        path: path.clone(),
+        root: root.to_string(),
+        hash,
+        size,
+        modified,
+        chunk_count: chunks.len(),
+        ingested_at: String::new(),
+    };
+    state
+        .memory
+        .write()
+        .await
+        .knowledge
+        .replace_source(&source, &chunks, embeddings.as_deref())?;
+    debug!(file = %path, chunks = chunks.len(), "ingested into knowledge base");
+    Ok(Some(chunks.len()))
+}
+
+/// Drop stored sources under `root` that aren't in `present`.
+async fn remove_missing(
+    state: &SharedAgentState,
+    root: &str,
+    present: &HashSet<String>,
+) -> Result<usize> {
+    let mut mem = state.memory.write().await;
+    let stale: Vec<String> = mem
+        .knowledge
+        .sources()?
+        .into_iter()
+        .filter(|s| s.root == root && !present.contains(&s.path))
+        .map(|s| s.path)
+        .collect();
+    for path in &stale {
+        mem.knowledge.remove_source(path)?;
+    }
+    Ok(stale.len())
+}
+
+/// Re-scan every ingested path, picking up new, changed and deleted files.
+/// A path that no longer exists has its files removed.
+pub(crate) async fn refresh_all(state: &SharedAgentState) -> Result<()> {
+    let roots = state.memory.read().await.knowledge.roots()?;
+    for root in roots {
+        if !PathBuf::from(&root).exists() {
+            let removed = remove_missing(state, &root, &HashSet::new()).await?;
+            if removed > 0 {
+                info!(root = %root, removed, "knowledge base path is gone, removed its files");
+            }
+            continue;
+        }
+        ingest_path(state, &root).await?;
+    }
+    Ok(())
+}
+
+/// Search the knowledge base, embedding the query when an embedder is set.
+pub(crate) async fn search(
+    state: &SharedAgentState,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<KbHit>> {
+    let query_embedding = match state.embedder {
+        Some(ref embedder) => match embedder.embed(&[query]).await {
+            Ok(vecs) => vecs.into_iter().next(),
+            Err(e) => {
+                warn!(error = %e, "failed to embed knowledge base query");
+                None
+            }
+        },
+        None => None,
+    };
+    state
+        .memory
+        .read()
+        .await
+        .knowledge
+        .search(query, query_embedding.as_deref(), limit)
+}
@@ -1,8 +1,10 @@
 use std::sync::Arc;
 
 use crate::agent::SharedAgentState;
+use claw_config::TaskClass;
 use claw_core::{Message, Role};
-use claw_llm::LlmRequest;
+use claw_llm::{LlmRequest, ResponseFormat};
+use claw_memory::scope::MemoryScope;
 use claw_mesh::MeshMessage;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
@@ -204,10 +206,8 @@ fn detect_lesson_patterns(messages: &[Message]) -> bool {
                     saw_success_after = true;
                 }
             }
-            Role::User => {
-                if saw_error_or_refusal {
-                    saw_user_correction_after = true;
-                }
+            Role::User if saw_error_or_refusal => {
+                saw_user_correction_after = true;
             }
             _ => {}
         }
@@ -285,7 +285,7 @@ async fn extract_lessons_via_llm(
     let prompt = format!(
         "Analyze this conversation excerpt where mistakes were made and then corrected. \
          Extract specific, actionable lessons learned.\n\n\
-         For each lesson, output a JSON array of objects with:\n\
+         For each lesson, add an object to \"lessons\" with:\n\
          - \"key\": a short snake_case identifier (e.g. \"plesk_login_needs_cookie_accept\")\n\
          - \"lesson\": a concise description of what was learned, including the correct approach\n\n\
          Focus on:\n\
@@ -294,54 +294,44 @@ async fn extract_lessons_via_llm(
          - Steps that must be done in a specific order\n\
          - Common errors and their solutions\n\
          - Anything the user had to correct or point out\n\n\
-         Output ONLY the JSON array, nothing else. If no clear lessons, output [].\n\n\
+         If there are no clear lessons, return an empty \"lessons\" array.\n\n\
          Conversation excerpt:\n{excerpt}\n"
     );
 
-    // Use fast model if available to keep costs low
-    let model = state
-        .config
-        .agent
-        .fast_model
-        .as_deref()
-        .unwrap_or(&state.config.agent.model);
+    // Routed to the fast model by default to keep costs low
+    let model = state.config.model_for_task(TaskClass::LessonExtraction);
 
     let request = LlmRequest {
-        model: model.to_string(),
+        model,
         messages: vec![Message::text(Uuid::nil(), Role::User, &prompt)],
         tools: Arc::new(vec![]),
         system: Some("You are a precise lesson extractor. Output only valid JSON.".to_string()),
         max_tokens: 1024,
         temperature: 0.2,
         thinking_level: Some("off".to_string()),
+        response_format: Some(ResponseFormat::new("lessons", lessons_schema())),
         stream: false,
     };
 
+    // The router validates the reply against the schema (with one repair
+    // attempt), so the message text is the canonical JSON object.
     match state.llm.complete(&request, None).await {
         Ok(response) => {
-            let text = response.message.text_content();
-            // Parse JSON from the response (handle markdown code fences)
-            let json_text = text
-                .trim()
-                .trim_start_matches("```json")
-                .trim_start_matches("```")
-                .trim_end_matches("```")
-                .trim();
-
-            match serde_json::from_str::<Vec<serde_json::Value>>(json_text) {
-                Ok(items) => items
-                    .iter()
-                    .filter_map(|item| {
-                        let key = item["key"].as_str()?.to_string();
-                        let lesson = item["lesson"].as_str()?.to_string();
-                        Some((key, lesson))
-                    })
-                    .collect(),
-                Err(e) => {
-                    debug!(error = %e, "failed to parse lesson extraction JSON");
-                    vec![]
-                }
-            }
+            let value: serde_json::Value =
+                serde_json::from_str(&response.message.text_content()).unwrap_or_default();
+            value["lessons"]
+                .as_array()
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|item| {
+                            let key = item["key"].as_str()?.to_string();
+                            let lesson = item["lesson"].as_str()?.to_string();
+                            Some((key, lesson))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
         }
         Err(e) => {
             debug!(error = %e, "lesson extraction LLM call failed");
@@ -350,8 +340,42 @@ async fn extract_lessons_via_llm(
     }
 }
 
+/// JSON schema for `extract_lessons_via_llm` replies.
+fn lessons_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "lessons": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "key": { "type": "string" },
+                        "lesson": { "type": "string" }
+                    },
+                    "required": ["key", "lesson"],
+                    "additionalProperties": false
+                }
+            }
+        },
+        "required": ["lessons"],
+        "additionalProperties": false
+    })
+}
+
 /// Run the full self-learning pipeline: detect patterns, extract lessons, persist them.
-pub(crate) async fn maybe_extract_lessons(state: &SharedAgentState, session_id: Uuid) {
+/// Lessons land in the conversation's default write scope, so what one user's
+/// conversation teaches stays with that user; only global lessons reach mesh
+/// peers.
+pub(crate) async fn maybe_extract_lessons(
+    state: &SharedAgentState,
+    session_id: Uuid,
+    scope: &MemoryScope,
+) {
+    let Ok(lesson_scope) = scope.write_scope(None) else {
+        return;
+    };
+
     // Read messages — brief lock
     let messages = {
         let mem = state.memory.read().await;
@@ -373,10 +397,10 @@ pub(crate) async fn maybe_extract_lessons(state: &SharedAgentState, session_id:
     info!(session = %session_id, count = lessons.len(), "extracted lessons from conversation");
 
     // Persist each lesson as a semantic fact
-    let mut mem = state.memory.write().await;
     for (key, lesson) in &lessons {
         let fact = claw_memory::semantic::Fact {
             id: Uuid::new_v4(),
+            scope: lesson_scope.clone(),
             category: "learned_lessons".to_string(),
             key: key.clone(),
             value: lesson.clone(),
@@ -385,13 +409,13 @@ pub(crate) async fn maybe_extract_lessons(state: &SharedAgentState, session_id:
             embedding: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
+            access_count: 0,
+            last_accessed: None,
         };
-        mem.semantic.upsert(fact);
-        if let Err(e) = mem.persist_fact("learned_lessons", key, lesson) {
+        if let Err(e) = crate::facts::store_fact(state, fact).await {
             warn!(error = %e, key = key, "failed to persist lesson to SQLite");
         }
     }
-    drop(mem);
 
     // Generate embeddings for the lessons if embedder is available
     if let Some(ref embedder) = state.embedder {
@@ -401,25 +425,15 @@ pub(crate) async fn maybe_extract_lessons(state: &SharedAgentState, session_id:
             .collect();
         let text_refs: Vec<&str> = texts.iter().map(|s| s.as_str()).collect();
         if let Ok(embeddings) = embedder.embed(&text_refs).await {
-            let mem = state.memory.read().await;
-            for (i, (key, _lesson)) in lessons.iter().enumerate() {
-                if let Some(emb) = embeddings.get(i) {
-                    // Re-persist with embedding
-                    if let Some(fact) = mem.semantic.get("learned_lessons", key) {
-                        let _ = mem.persist_fact_with_embedding(
-                            "learned_lessons",
-                            key,
-                            &fact.value,
-                            Some(emb),
-                        );
-                    }
-                }
+            let mut mem = state.memory.write().await;
+            for ((key, _lesson), emb) in lessons.iter().zip(&embeddings) {
+                let _ = mem.update_fact_embedding(&lesson_scope, "learned_lessons", key, emb);
             }
         }
     }
 
-    // Broadcast lessons to mesh peers
-    {
+    // Broadcast global lessons to mesh peers
+    if lesson_scope == claw_memory::scope::GLOBAL {
         let mesh = state.mesh.lock().await;
         if mesh.is_running() && mesh.peer_count() > 0 {
             for (key, lesson) in &lessons {
@@ -438,3 +452,54 @@ pub(crate) async fn maybe_extract_lessons(state: &SharedAgentState, session_id:
         }
     }
 }
+
+/// `memory_meta` key recording the vector space stored fact embeddings are in.
+const EMBEDDING_SPACE_KEY: &str = "embedding_space";
+
+/// Facts embedded per batch when re-embedding.
+const REEMBED_BATCH: usize = 64;
+
+/// Bring stored fact embeddings in line with the configured embedder: when the
+/// embedder (provider, model or dimensions) changed since the last run, every
+/// fact is re-embedded; otherwise only facts missing a vector are filled in.
+/// Returns the number of facts embedded.
+pub(crate) async fn sync_fact_embeddings(state: &SharedAgentState) -> claw_core::Result<usize> {
+    let Some(ref embedder) = state.embedder else {
+        return Ok(0);
+    };
+    let space = embedder.space();
+    let dims = embedder.dimensions();
+
+    let (changed, pending) = {
+        let mem = state.memory.read().await;
+        let changed = mem.meta(EMBEDDING_SPACE_KEY).as_deref() != Some(space.as_str());
+        let pending: Vec<(String, String, String, String)> = mem
+            .semantic
+            .all_facts()
+            .into_iter()
+            .filter(|f| changed || f.embedding.as_ref().is_none_or(|e| e.len() != dims))
+            .map(|f| {
+                let text = format!("{} {} {}", f.category, f.key, f.value);
+                (f.scope.clone(), f.category.clone(), f.key.clone(), text)
+            })
+            .collect();
+        (changed, pending)
+    };
+    if changed {
+        info!(space = %space, facts = pending.len(), "embedding provider changed, re-embedding facts");
+    }
+
+    for batch in pending.chunks(REEMBED_BATCH) {
+        let texts: Vec<&str> = batch.iter().map(|(_, _, _, text)| text.as_str()).collect();
+        let embeddings = embedder.embed(&texts).await?;
+        let mut mem = state.memory.write().await;
+        for ((scope, category, key, _), emb) in batch.iter().zip(&embeddings) {
+            mem.update_fact_embedding(scope, category, key, emb)?;
+        }
+    }
+
+    let mut mem = state.memory.write().await;
+    mem.persist_vector_index()?;
+    mem.set_meta(EMBEDDING_SPACE_KEY, &space)?;
+    Ok(pending.len())
+}
@@ -35,8 +35,12 @@
 pub mod agent;
 pub(crate) mod agent_loop;
 pub(crate) mod channel_helpers;
+pub(crate) mod facts;
+pub(crate) mod graph;
+pub(crate) mod knowledge;
 pub(crate) mod learning;
 pub(crate) mod query;
+pub mod sandbox;
 pub mod scheduler;
 pub mod session;
 pub(crate) mod sub_agent;
@@ -10,7 +10,28 @@ pub enum QueryKind {
     Goals,
     Tools,
     Facts,
-    MemorySearch(String),
+    /// Facts, episodes and messages; `session` limits messages to one
+    /// conversation.
+    MemorySearch {
+        query: String,
+        session: Option<Uuid>,
+    },
+    FactConflicts {
+        include_resolved: bool,
+    },
+    FactHistory {
+        scope: String,
+        category: String,
+        key: String,
+    },
+    /// Knowledge graph: an entity's neighbourhood, or matching entities.
+    Graph {
+        entity: Option<String>,
+        search: Option<String>,
+        depth: usize,
+    },
+    KbSources,
+    KbSearch(String),
     Config,
     AuditLog(usize),
     MeshPeers,
@@ -181,6 +202,7 @@ pub(crate) async fn handle_query(
         }
         QueryKind::Facts => {
             let mem = state.memory.read().await;
+            let now = chrono::Utc::now();
             let facts: Vec<serde_json::Value> = mem
                 .semantic
                 .all_facts()
@@ -191,17 +213,23 @@ pub(crate) async fn handle_query(
                         "category": f.category,
                         "key": f.key,
                         "value": f.value,
-                        "confidence": f.confidence,
+                        "confidence": mem.decay_policy().effective_confidence(f, now),
                         "source": f.source,
                         "created_at": f.created_at.to_rfc3339(),
                         "updated_at": f.updated_at.to_rfc3339(),
+                        "access_count": f.access_count,
+                        "last_accessed": f.last_accessed.map(|t| t.to_rfc3339()),
                     })
                 })
                 .collect();
             let count = facts.len();
-            serde_json::json!({ "facts": facts, "count": count })
+            let archived = mem.archived_fact_count().unwrap_or(0);
+            serde_json::json!({ "facts": facts, "count": count, "archived": archived })
         }
-        QueryKind::MemorySearch(ref query_text) => {
+        QueryKind::MemorySearch {
+            query: ref query_text,
+            session,
+        } => {
             // Embed query for vector search if embedder is available
             let query_embedding = if let Some(ref embedder) = state.embedder {
                 match embedder.embed(&[query_text.as_str()]).await {
@@ -213,73 +241,178 @@ pub(crate) async fn handle_query(
             };
 
             let mem = state.memory.read().await;
-            let episodes = mem.episodic.search(query_text);
-
-            // Use vector search for facts when embedding is available
-            let fact_results: Vec<serde_json::Value> = if let Some(ref qemb) = query_embedding {
-                let vector_hits = mem.semantic.vector_search(qemb, 20);
-                if !vector_hits.is_empty() {
-                    vector_hits
-                        .iter()
-                        .map(|(f, score)| {
-                            serde_json::json!({
-                                "type": "fact",
-                                "category": f.category,
-                                "key": f.key,
-                                "value": f.value,
-                                "confidence": f.confidence,
-                                "relevance": score,
-                            })
-                        })
-                        .collect()
-                } else {
-                    mem.semantic
+            let episodes: Vec<(claw_memory::Episode, Option<f64>)> =
+                match mem.search_episodes(query_text, None, 10) {
+                    Ok(hits) => hits
+                        .into_iter()
+                        .map(|h| (h.episode, Some(h.score)))
+                        .collect(),
+                    Err(_) => mem
+                        .episodic
                         .search(query_text)
-                        .iter()
-                        .take(20)
-                        .map(|f| {
-                            serde_json::json!({
-                                "type": "fact",
-                                "category": f.category,
-                                "key": f.key,
-                                "value": f.value,
-                                "confidence": f.confidence,
-                            })
-                        })
-                        .collect()
-                }
-            } else {
-                mem.semantic
-                    .search(query_text)
-                    .iter()
-                    .take(20)
-                    .map(|f| {
-                        serde_json::json!({
-                            "type": "fact",
-                            "category": f.category,
-                            "key": f.key,
-                            "value": f.value,
-                            "confidence": f.confidence,
-                        })
+                        .into_iter()
+                        .map(|e| (e.clone(), None))
+                        .collect(),
+                };
+
+            // Hybrid BM25 + vector ranking; `relevance` is the fused score
+            let fact_results: Vec<serde_json::Value> = mem
+                .hybrid_fact_search(query_text, query_embedding.as_deref(), None, 20)
+                .into_iter()
+                .map(|(f, score)| {
+                    serde_json::json!({
+                        "type": "fact",
+                        "scope": f.scope,
+                        "category": f.category,
+                        "key": f.key,
+                        "value": f.value,
+                        "confidence": mem.decay_policy().effective_confidence(f, chrono::Utc::now()),
+                        "relevance": score,
                     })
-                    .collect()
-            };
+                })
+                .collect();
 
             let ep_results: Vec<serde_json::Value> = episodes
                 .iter()
-                .take(10)
-                .map(|e| {
+                .map(|(e, score)| {
                     serde_json::json!({
                         "type": "episode",
+                        "scope": e.scope,
                         "summary": e.summary,
                         "outcome": e.outcome,
                         "tags": e.tags,
                         "created_at": e.created_at.to_rfc3339(),
+                        "relevance": score,
+                    })
+                })
+                .collect();
+
+            let message_results: Vec<serde_json::Value> = mem
+                .search_messages(query_text, session.as_ref(), 20)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|m| {
+                    serde_json::json!({
+                        "type": "message",
+                        "session_id": m.session_id,
+                        "message_id": m.message_id,
+                        "role": m.role,
+                        "snippet": m.snippet,
+                        "timestamp": m.timestamp,
+                        "relevance": m.score,
                     })
                 })
                 .collect();
             let mut results = ep_results;
             results.extend(fact_results);
+            results.extend(message_results);
+            serde_json::json!({ "results": results, "query": query_text })
+        }
+        QueryKind::FactConflicts { include_resolved } => {
+            let conflicts = state
+                .memory
+                .read()
+                .await
+                .fact_conflicts(include_resolved)
+                .map_err(|e| e.to_string())?;
+            let count = conflicts.len();
+            serde_json::json!({ "conflicts": conflicts, "count": count })
+        }
+        QueryKind::FactHistory {
+            ref scope,
+            ref category,
+            ref key,
+        } => {
+            let mem = state.memory.read().await;
+            let versions = mem
+                .fact_history(scope, category, key)
+                .map_err(|e| e.to_string())?;
+            let current = mem
+                .semantic
+                .get(scope, category, key)
+                .map(|f| f.value.clone());
+            serde_json::json!({
+                "scope": scope,
+                "category": category,
+                "key": key,
+                "current": current,
+                "versions": versions,
+            })
+        }
+        QueryKind::Graph {
+            entity,
+            search,
+            depth,
+        } => {
+            let mem = state.memory.read().await;
+            let graph = &mem.graph;
+            match entity {
+                Some(reference) => {
+                    let mut neighborhoods = Vec::new();
+                    for e in graph.resolve(&reference).map_err(|e| e.to_string())? {
+                        if let Some(n) = graph
+                            .neighbors(&e.id, depth, claw_memory::Direction::Both, None, 500)
+                            .map_err(|e| e.to_string())?
+                        {
+                            neighborhoods.push(n);
+                        }
+                    }
+                    serde_json::json!({ "entity": reference, "neighborhoods": neighborhoods })
+                }
+                None => {
+                    let (entity_count, relation_count) =
+                        graph.counts().map_err(|e| e.to_string())?;
+                    let entities = graph
+                        .find_entities(search.as_deref().unwrap_or(""), None, 200)
+                        .map_err(|e| e.to_string())?;
+                    serde_json::json!({
+                        "entity_count": entity_count,
+                        "relation_count": relation_count,
+                        "entities": entities,
+                    })
+                }
+            }
+        }
+        QueryKind::KbSources => {
+            let mem = state.memory.read().await;
+            let sources: Vec<serde_json::Value> = mem
+                .knowledge
+                .sources()
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|s| {
+                    serde_json::json!({
+                        "path": s.path,
+                        "root": s.root,
+                        "size": s.size,
+                        "chunks": s.chunk_count,
+                        "ingested_at": s.ingested_at,
+                    })
+                })
+                .collect();
+            let roots = mem.knowledge.roots().map_err(|e| e.to_string())?;
+            let count = sources.len();
+            serde_json::json!({ "sources": sources, "roots": roots, "count": count })
+        }
+        QueryKind::KbSearch(ref query_text) => {
+            let hits = crate::knowledge::search(state, query_text, 10)
+                .await
+                .map_err(|e| e.to_string())?;
+            let results: Vec<serde_json::Value> = hits
+                .iter()
+                .map(|h| {
+                    serde_json::json!({
+                        "source": h.source,
+                        "citation": h.citation(),
+                        "location": h.chunk.location(),
+                        "heading": h.chunk.heading,
+                        "start": h.chunk.start,
+                        "end": h.chunk.end,
+                        "text": h.chunk.text,
+                        "relevance": h.score,
+                    })
+                })
+                .collect();
             serde_json::json!({ "results": results, "query": query_text })
         }
         QueryKind::Config => {
@@ -294,6 +427,11 @@ pub(crate) async fn handle_query(
                     "max_iterations": state.config.agent.max_iterations,
                     "thinking_level": &state.config.agent.thinking_level,
                 },
+                "routing": {
+                    "policy": &state.config.routing.policy,
+                    "max_cost_per_request_usd": state.config.routing.max_cost_per_request_usd,
+                    "tasks": &state.config.routing.tasks,
+                },
                 "autonomy": {
                     "level": state.config.autonomy.level,
                     "daily_budget_usd": state.config.autonomy.daily_budget_usd,
@@ -302,12 +440,27 @@ pub(crate) async fn handle_query(
                     "proactive": state.config.autonomy.proactive,
                     "tool_allowlist": &state.config.autonomy.tool_allowlist,
                     "tool_denylist": &state.config.autonomy.tool_denylist,
+                    "policy_file": state.config.autonomy.policy_file.as_ref().map(|p| p.display().to_string()),
+                    "workspace_only": state.config.autonomy.workspace_only,
+                    "workspace": state.path_scope.workspace().display().to_string(),
+                    "allowed_paths": &state.config.autonomy.allowed_paths,
+                    "forbidden_paths": &state.config.autonomy.forbidden_paths,
+                },
+                "runtime": {
+                    "sandbox": state.tools.sandbox().mode(),
+                    "sandbox_dir": state.config.runtime.resolved_sandbox_dir().display().to_string(),
+                    "sandbox_network": state.config.runtime.sandbox_network,
+                    "sandbox_cpu_secs": state.config.runtime.sandbox_cpu_secs,
+                    "sandbox_memory_mb": state.config.runtime.sandbox_memory_mb,
+                    "sandbox_max_command_secs": state.config.runtime.sandbox_max_command_secs,
                 },
                 "memory": {
                     "db_path": state.config.memory.db_path.display().to_string(),
                     "max_episodes": state.config.memory.max_episodes,
                     "vector_search": state.config.memory.vector_search,
                     "embedding_dims": state.config.memory.embedding_dims,
+                    "embedding_provider": &state.config.memory.embedding_provider,
+                    "embedder": state.embedder.as_ref().map(|e| e.space()),
                 },
                 "server": {
                     "listen": &state.config.server.listen,
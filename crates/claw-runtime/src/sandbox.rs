@@ -0,0 +1,1222 @@
+//! # Process Sandbox
+//!
+//! Confines the commands `shell_exec`, `process_start` and PTY terminals run,
+//! without Docker or any helper binary — just the kernel:
+//!
+//! - **rlimits** cap CPU time, address space, file size and process count
+//! - **namespaces** (`runtime.sandbox = "namespaces"`) put the command in its
+//!   own user, mount, IPC and UTS namespaces — and a network namespace when
+//!   `sandbox_network = false` — with system directories bind-mounted
+//!   read-only and the session's `.tmp` mounted over `/tmp`
+//! - **Landlock** limits the filesystem to reading system directories (and
+//!   `sandbox_read_paths`) and writing the session workspace
+//! - **seccomp** refuses syscalls a contained command has no use for —
+//!   mounting, module loading, `ptrace`, new namespaces, `bpf`, `io_uring`,
+//!   ... — and, with the network off, IP and Unix sockets
+//!
+//! Each session gets its own writable workspace under `sandbox_dir`, which
+//! is also the command's `HOME` and default working directory. The
+//! environment is scrubbed down to locale and terminal variables, so API
+//! keys in Claw's environment don't leak into commands.
+//!
+//! Everything that allocates happens in the parent before `fork`; the child
+//! only makes syscalls on what was prepared for it.
+//!
+//! ## Known limits
+//!
+//! - Landlock doesn't govern `connect()` on Unix sockets, so with the
+//!   network on a command can reach any socket Claw's user can by path —
+//!   `/var/run/docker.sock`, say. Turn `sandbox_network` off, or run Claw
+//!   as a user without access to such sockets.
+//! - A command that starts its own session (`setsid`) leaves the process
+//!   group `shell_exec` kills at its timeout; rlimits still bound its CPU
+//!   time.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::sync::Arc;
+
+use claw_config::schema::{RuntimeConfig, SandboxMode};
+use uuid::Uuid;
+
+/// Directories sandboxed commands can always read and execute from. Of
+/// `/run` only the resolver config `/etc/resolv.conf` may link to — the
+/// rest holds daemon sockets and secrets.
+const SYSTEM_DIRS: &[&str] = &[
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/libx32",
+    "/etc",
+    "/opt",
+    "/nix",
+    "/snap",
+    "/proc",
+    "/sys",
+    "/dev",
+    "/run/systemd/resolve",
+    "/run/resolvconf",
+];
+
+/// Device files sandboxed commands can write.
+const WRITABLE_DEVICES: &[&str] = &[
+    "/dev/null",
+    "/dev/zero",
+    "/dev/full",
+    "/dev/random",
+    "/dev/urandom",
+    "/dev/tty",
+    "/dev/ptmx",
+    "/dev/pts",
+    "/dev/shm",
+];
+
+/// Environment variables passed through to sandboxed commands.
+const KEPT_ENV: &[&str] = &[
+    "PATH",
+    "LANG",
+    "LANGUAGE",
+    "LC_ALL",
+    "LC_CTYPE",
+    "LC_MESSAGES",
+    "TERM",
+    "COLORTERM",
+    "TZ",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+];
+
+/// The sandbox the shell and process tools run commands in. Cheap to clone.
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    mode: SandboxMode,
+    root: PathBuf,
+    read_paths: Vec<PathBuf>,
+    network: bool,
+    cpu_secs: u64,
+    memory_mb: u64,
+    file_size_mb: u64,
+    max_processes: u64,
+    max_command_secs: u64,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl Sandbox {
+    /// No sandbox — commands run as Claw does.
+    pub fn none() -> Self {
+        Self::new(&RuntimeConfig::default())
+    }
+
+    fn new(config: &RuntimeConfig) -> Self {
+        Self {
+            mode: config.sandbox,
+            root: config.resolved_sandbox_dir(),
+            read_paths: config.sandbox_read_paths.clone(),
+            network: config.sandbox_network,
+            cpu_secs: config.sandbox_cpu_secs,
+            memory_mb: config.sandbox_memory_mb,
+            file_size_mb: config.sandbox_file_size_mb,
+            max_processes: config.sandbox_max_processes,
+            max_command_secs: config.sandbox_max_command_secs,
+        }
+    }
+
+    /// The sandbox `[runtime]` configures. It's tried on a trivial command
+    /// first: a sandbox the kernel can't provide is an error, not a silent
+    /// fallback to running unconfined.
+    pub fn from_config(config: &RuntimeConfig) -> claw_core::Result<Self> {
+        let sandbox = Self::new(config);
+        if sandbox.mode == SandboxMode::None {
+            return Ok(sandbox);
+        }
+        sandbox.probe().map_err(|e| {
+            claw_core::ClawError::Config(format!(
+                "runtime.sandbox = \"{}\" isn't available here: {e}",
+                sandbox.mode
+            ))
+        })?;
+        Ok(sandbox)
+    }
+
+    /// Sandboxed commands run as our user; keep them out of
+    /// `/proc/<claw>/environ` and `/proc/<claw>/mem`. This marks the whole
+    /// process, so the daemon does it once at startup rather than every
+    /// caller that builds a sandbox.
+    pub fn protect_host_process(&self) {
+        if !self.is_enabled() {
+            return;
+        }
+        #[cfg(target_os = "linux")]
+        unsafe {
+            libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0);
+        }
+    }
+
+    fn probe(&self) -> io::Result<()> {
+        let confinement = self.confine(None)?.expect("sandbox is enabled");
+        let mut cmd = std::process::Command::new("/bin/sh");
+        cmd.arg("-c").arg("exit 0");
+        cmd.stdin(std::process::Stdio::null());
+        confinement.apply(&mut cmd);
+        let status = cmd.status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "a confined `sh` exited with {status}"
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn mode(&self) -> SandboxMode {
+        self.mode
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.mode != SandboxMode::None
+    }
+
+    /// The writable workspace of a session.
+    pub fn workspace(&self, session: Option<Uuid>) -> PathBuf {
+        match session {
+            Some(id) => self.root.join(id.to_string()),
+            None => self.root.join("shared"),
+        }
+    }
+
+    /// The longest a sandboxed `shell_exec` may run.
+    pub fn clamp_timeout(&self, secs: u64) -> u64 {
+        if self.is_enabled() && self.max_command_secs > 0 {
+            secs.min(self.max_command_secs)
+        } else {
+            secs
+        }
+    }
+
+    /// Prepare the confinement for one command of a session, creating the
+    /// session's workspace. `None` when the sandbox is off.
+    pub fn confine(&self, session: Option<Uuid>) -> io::Result<Option<Confinement>> {
+        if !self.is_enabled() {
+            return Ok(None);
+        }
+        let workspace = self.workspace(session);
+        let tmp = workspace.join(".tmp");
+        std::fs::create_dir_all(&tmp)?;
+        #[cfg(target_os = "linux")]
+        {
+            let prepared = linux::prepare(self, &workspace, &tmp)?;
+            // Under namespaces the session's `.tmp` is the sandbox's `/tmp`
+            let tmp = match self.mode == SandboxMode::Namespaces && binds_tmp(&tmp) {
+                true => PathBuf::from("/tmp"),
+                false => tmp,
+            };
+            Ok(Some(Confinement {
+                mode: self.mode,
+                workspace,
+                tmp,
+                prepared: Arc::new(prepared),
+            }))
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = tmp;
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the process sandbox needs Linux",
+            ))
+        }
+    }
+
+    /// How the sandbox may have stopped a command that failed, from its
+    /// exit status (if it has exited) and output. Only what the sandbox
+    /// alone causes is reported as a block — the seccomp kill signal, the
+    /// rlimit signals and `Permission denied` on paths Landlock leaves out
+    /// entirely. Output that only hints at the sandbox is reported as a
+    /// possible cause. `None` when nothing points at the sandbox.
+    pub fn explain(&self, status: Option<&ExitStatus>, output: &str) -> Option<String> {
+        if !self.is_enabled() || status.is_some_and(|s| s.success()) {
+            return None;
+        }
+        let signal = status.and_then(|s| {
+            use std::os::unix::process::ExitStatusExt;
+            // `sh -c` reports a signalled child as 128 + signal
+            s.signal()
+                .or(s.code().filter(|c| *c > 128).map(|c| c - 128))
+        });
+        let mut blocked = vec![];
+        let mut possible = vec![];
+        if signal == Some(libc::SIGSYS) {
+            blocked.push("killed by the seccomp filter for a syscall it refuses".to_string());
+        }
+        if signal == Some(libc::SIGXCPU) {
+            blocked.push(format!(
+                "killed at the CPU time limit ({}s, runtime.sandbox_cpu_secs)",
+                self.cpu_secs
+            ));
+        }
+        if signal == Some(libc::SIGXFSZ) {
+            blocked.push(format!(
+                "stopped at the file size limit ({} MB, runtime.sandbox_file_size_mb)",
+                self.file_size_mb
+            ));
+        }
+
+        let lower = output.to_lowercase();
+        let says = |needles: &[&str]| needles.iter().any(|n| lower.contains(n));
+        let excluded = self.excluded_paths(output);
+        if !excluded.is_empty() {
+            blocked.push(format!(
+                "Landlock doesn't let commands touch {} — only {}",
+                excluded.join(", "),
+                self.reachable()
+            ));
+        }
+        if !self.network
+            && says(&[
+                "network is unreachable",
+                "could not resolve host",
+                "temporary failure in name resolution",
+                "name or service not known",
+                "failed to connect",
+                "couldn't connect",
+                "socket: permission denied",
+                "socket: operation not permitted",
+            ])
+        {
+            possible.push("network access is off (runtime.sandbox_network = false)".into());
+        } else if excluded.is_empty()
+            && says(&[
+                "read-only file system",
+                "permission denied",
+                "operation not permitted",
+            ])
+        {
+            possible.push(format!("only {}", self.reachable()));
+        }
+        if self.memory_mb > 0
+            && says(&[
+                "cannot allocate memory",
+                "out of memory",
+                "bad_alloc",
+                "memory allocation of",
+            ])
+        {
+            possible.push(format!(
+                "the memory limit is {} MB (runtime.sandbox_memory_mb)",
+                self.memory_mb
+            ));
+        }
+
+        match (blocked.is_empty(), possible.is_empty()) {
+            (true, true) => None,
+            (true, false) => Some(format!(
+                "🔒 Possibly blocked by the sandbox ({}): {}",
+                self.mode,
+                possible.join("; ")
+            )),
+            (false, true) => Some(format!(
+                "🔒 Blocked by the sandbox ({}): {}",
+                self.mode,
+                blocked.join("; ")
+            )),
+            (false, false) => Some(format!(
+                "🔒 Blocked by the sandbox ({}): {} (possibly also: {})",
+                self.mode,
+                blocked.join("; "),
+                possible.join("; ")
+            )),
+        }
+    }
+
+    /// What sandboxed commands can write and read, for explanations.
+    fn reachable(&self) -> String {
+        let mut writable = vec![self.root.join("<session>").display().to_string()];
+        if self.mode == SandboxMode::Namespaces {
+            writable.push("/tmp".into());
+        }
+        format!(
+            "{} is writable and only system directories{} are readable",
+            writable.join(" and "),
+            if self.read_paths.is_empty() {
+                ""
+            } else {
+                " and runtime.sandbox_read_paths"
+            }
+        )
+    }
+
+    /// Absolute paths `Permission denied` was reported for that Landlock
+    /// grants no access to at all — outside the system directories, the
+    /// read paths, the writable devices and the sandbox's workspaces.
+    fn excluded_paths(&self, output: &str) -> Vec<String> {
+        let granted = |path: &Path| {
+            SYSTEM_DIRS
+                .iter()
+                .chain(WRITABLE_DEVICES)
+                .map(Path::new)
+                .chain(self.read_paths.iter().map(PathBuf::as_path))
+                .chain([self.root.as_path()])
+                .chain((self.mode == SandboxMode::Namespaces).then_some(Path::new("/tmp")))
+                .any(|p| path.starts_with(p))
+        };
+        let mut excluded: Vec<String> = output
+            .lines()
+            .filter(|line| line.to_lowercase().contains("permission denied"))
+            .flat_map(str::split_whitespace)
+            .map(|word| word.trim_matches(|c: char| "'\"`‘’:,;()".contains(c)))
+            .filter(|word| word.starts_with('/') && !granted(Path::new(word)))
+            .map(String::from)
+            .collect();
+        excluded.dedup();
+        excluded
+    }
+}
+
+/// Whether the namespaces mode can mount `tmp` over `/tmp` — not when it
+/// lives under `/tmp` itself, where the mount would hide the workspace.
+fn binds_tmp(tmp: &Path) -> bool {
+    Path::new("/tmp").is_dir() && !tmp.starts_with("/tmp")
+}
+
+/// Everything one sandboxed command needs, prepared ahead of `fork`.
+#[derive(Clone)]
+pub struct Confinement {
+    mode: SandboxMode,
+    workspace: PathBuf,
+    /// `TMPDIR` for the command.
+    tmp: PathBuf,
+    #[cfg(target_os = "linux")]
+    prepared: Arc<linux::Prepared>,
+}
+
+impl Confinement {
+    pub fn mode(&self) -> SandboxMode {
+        self.mode
+    }
+
+    /// The session workspace the command can write.
+    pub fn workspace(&self) -> &Path {
+        &self.workspace
+    }
+
+    /// Confine a command: scrub its environment (keeping whatever was set
+    /// on it explicitly), start it in the workspace unless it has a working
+    /// directory, and enter the sandbox between `fork` and `exec`.
+    pub fn apply(&self, cmd: &mut std::process::Command) {
+        let explicit: Vec<_> = cmd
+            .get_envs()
+            .map(|(k, v)| (k.to_os_string(), v.map(|v| v.to_os_string())))
+            .collect();
+        cmd.env_clear();
+        for key in KEPT_ENV {
+            if let Some(value) = std::env::var_os(key) {
+                cmd.env(key, value);
+            }
+        }
+        cmd.env("HOME", &self.workspace);
+        cmd.env("TMPDIR", &self.tmp);
+        cmd.env("CLAW_SANDBOX", self.mode.to_string());
+        for (key, value) in explicit {
+            if let Some(value) = value {
+                cmd.env(key, value);
+            }
+        }
+        if cmd.get_current_dir().is_none() {
+            cmd.current_dir(&self.workspace);
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::process::CommandExt;
+            let prepared = Arc::clone(&self.prepared);
+            // SAFETY: `enter` only makes syscalls on memory prepared before
+            // the fork — no allocation, no locks.
+            unsafe {
+                cmd.pre_exec(move || linux::enter(&prepared));
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::*;
+    use std::ffi::CString;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+
+    // ── Landlock ───────────────────────────────────────────────
+
+    const SYS_LANDLOCK_CREATE_RULESET: libc::c_long = 444;
+    const SYS_LANDLOCK_ADD_RULE: libc::c_long = 445;
+    const SYS_LANDLOCK_RESTRICT_SELF: libc::c_long = 446;
+    const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1;
+    const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+
+    const ACCESS_EXECUTE: u64 = 1 << 0;
+    const ACCESS_WRITE_FILE: u64 = 1 << 1;
+    const ACCESS_READ_FILE: u64 = 1 << 2;
+    const ACCESS_READ_DIR: u64 = 1 << 3;
+    /// Everything Landlock ABI 1 handles: the above plus removing and
+    /// making files, directories, links, devices, sockets and FIFOs.
+    const ACCESS_ABI_1: u64 = (1 << 13) - 1;
+    const ACCESS_REFER: u64 = 1 << 13;
+    const ACCESS_TRUNCATE: u64 = 1 << 14;
+    /// Rights that make sense on a file rather than a directory.
+    const FILE_ACCESS: u64 =
+        ACCESS_EXECUTE | ACCESS_WRITE_FILE | ACCESS_READ_FILE | ACCESS_TRUNCATE;
+    const READ_ACCESS: u64 = ACCESS_EXECUTE | ACCESS_READ_FILE | ACCESS_READ_DIR;
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    fn landlock_ruleset(read: &[PathBuf], write: &[PathBuf]) -> io::Result<OwnedFd> {
+        let abi = unsafe {
+            libc::syscall(
+                SYS_LANDLOCK_CREATE_RULESET,
+                std::ptr::null::<RulesetAttr>(),
+                0usize,
+                LANDLOCK_CREATE_RULESET_VERSION,
+            )
+        };
+        if abi < 1 {
+            return Err(io::Error::other(format!(
+                "Landlock is unavailable ({}) — it needs Linux 5.13+ with landlock in the lsm= list",
+                io::Error::last_os_error()
+            )));
+        }
+        let mut handled = ACCESS_ABI_1;
+        if abi >= 2 {
+            handled |= ACCESS_REFER;
+        }
+        if abi >= 3 {
+            handled |= ACCESS_TRUNCATE;
+        }
+        let attr = RulesetAttr {
+            handled_access_fs: handled,
+        };
+        let fd = unsafe {
+            libc::syscall(
+                SYS_LANDLOCK_CREATE_RULESET,
+                &attr as *const RulesetAttr,
+                std::mem::size_of::<RulesetAttr>(),
+                0u32,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let ruleset = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+
+        let rules = read
+            .iter()
+            .map(|p| (p, READ_ACCESS))
+            .chain(write.iter().map(|p| (p, handled)));
+        for (path, access) in rules {
+            let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+                continue;
+            };
+            let raw = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+            if raw < 0 {
+                // Not on this system
+                continue;
+            }
+            let dir = unsafe { OwnedFd::from_raw_fd(raw) };
+            let mut allowed = access & handled;
+            if !path.is_dir() {
+                allowed &= FILE_ACCESS;
+            }
+            let rule = PathBeneathAttr {
+                allowed_access: allowed,
+                parent_fd: dir.as_raw_fd(),
+            };
+            let ret = unsafe {
+                libc::syscall(
+                    SYS_LANDLOCK_ADD_RULE,
+                    ruleset.as_raw_fd(),
+                    LANDLOCK_RULE_PATH_BENEATH,
+                    &rule as *const PathBeneathAttr,
+                    0u32,
+                )
+            };
+            if ret != 0 {
+                let e = io::Error::last_os_error();
+                return Err(io::Error::other(format!(
+                    "Landlock rule for {}: {e}",
+                    path.display()
+                )));
+            }
+        }
+        Ok(ruleset)
+    }
+
+    // ── seccomp ────────────────────────────────────────────────
+
+    const BPF_LD_W_ABS: u16 = 0x20;
+    const BPF_JEQ_K: u16 = 0x15;
+    const BPF_JGE_K: u16 = 0x35;
+    const BPF_JSET_K: u16 = 0x45;
+    const BPF_RET_K: u16 = 0x06;
+    const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+    const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+    const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+    const SECCOMP_SET_MODE_FILTER: libc::c_ulong = 1;
+    /// `seccomp_data` offsets: syscall number, arch, first argument.
+    const OFFSET_NR: u32 = 0;
+    const OFFSET_ARCH: u32 = 4;
+    const OFFSET_ARG0: u32 = 16;
+
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: Option<u32> = Some(0xC000_003E);
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: Option<u32> = Some(0xC000_00B7);
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    const AUDIT_ARCH: Option<u32> = None;
+
+    /// Syscalls a sandboxed command gets EPERM for.
+    const DENIED: &[libc::c_long] = &[
+        libc::SYS_mount,
+        libc::SYS_umount2,
+        libc::SYS_pivot_root,
+        libc::SYS_chroot,
+        libc::SYS_fsopen,
+        libc::SYS_fsconfig,
+        libc::SYS_fsmount,
+        libc::SYS_move_mount,
+        libc::SYS_open_tree,
+        libc::SYS_mount_setattr,
+        libc::SYS_unshare,
+        libc::SYS_setns,
+        libc::SYS_ptrace,
+        libc::SYS_process_vm_readv,
+        libc::SYS_process_vm_writev,
+        libc::SYS_kexec_load,
+        libc::SYS_kexec_file_load,
+        libc::SYS_init_module,
+        libc::SYS_finit_module,
+        libc::SYS_delete_module,
+        libc::SYS_reboot,
+        libc::SYS_swapon,
+        libc::SYS_swapoff,
+        libc::SYS_bpf,
+        libc::SYS_perf_event_open,
+        libc::SYS_userfaultfd,
+        libc::SYS_keyctl,
+        libc::SYS_add_key,
+        libc::SYS_request_key,
+        libc::SYS_open_by_handle_at,
+        libc::SYS_name_to_handle_at,
+        libc::SYS_acct,
+        libc::SYS_quotactl,
+        libc::SYS_settimeofday,
+        libc::SYS_clock_settime,
+        libc::SYS_clock_adjtime,
+        libc::SYS_adjtimex,
+        libc::SYS_sethostname,
+        libc::SYS_setdomainname,
+        // io_uring opens files and sockets without the syscalls filtered here
+        libc::SYS_io_uring_setup,
+        libc::SYS_io_uring_enter,
+        libc::SYS_io_uring_register,
+    ];
+
+    /// `clone` flags that create namespaces.
+    const NAMESPACE_FLAGS: u32 = (libc::CLONE_NEWNS
+        | libc::CLONE_NEWUTS
+        | libc::CLONE_NEWIPC
+        | libc::CLONE_NEWUSER
+        | libc::CLONE_NEWPID
+        | libc::CLONE_NEWNET
+        | libc::CLONE_NEWCGROUP) as u32;
+
+    /// Socket families a sandbox without network can't open. Unix sockets
+    /// reach host daemons; `socketpair` still works.
+    const NETWORK_FAMILIES: &[u32] = &[
+        libc::AF_UNIX as u32,
+        libc::AF_INET as u32,
+        libc::AF_INET6 as u32,
+        libc::AF_PACKET as u32,
+    ];
+
+    fn stmt(code: u16, k: u32) -> libc::sock_filter {
+        libc::sock_filter {
+            code,
+            jt: 0,
+            jf: 0,
+            k,
+        }
+    }
+
+    fn jump(code: u16, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+        libc::sock_filter { code, jt, jf, k }
+    }
+
+    fn seccomp_filter(network: bool) -> io::Result<Vec<libc::sock_filter>> {
+        let arch = AUDIT_ARCH.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the seccomp filter supports x86_64 and aarch64",
+            )
+        })?;
+        let errno = |e: i32| stmt(BPF_RET_K, SECCOMP_RET_ERRNO | e as u32);
+        let mut prog = vec![
+            stmt(BPF_LD_W_ABS, OFFSET_ARCH),
+            jump(BPF_JEQ_K, arch, 1, 0),
+            stmt(BPF_RET_K, SECCOMP_RET_KILL_PROCESS),
+            stmt(BPF_LD_W_ABS, OFFSET_NR),
+        ];
+        if cfg!(target_arch = "x86_64") {
+            // x32 ABI syscalls
+            prog.push(jump(BPF_JGE_K, 0x4000_0000, 0, 1));
+            prog.push(errno(libc::EPERM));
+        }
+        for nr in DENIED {
+            prog.push(jump(BPF_JEQ_K, *nr as u32, 0, 1));
+            prog.push(errno(libc::EPERM));
+        }
+        // clone3 hides its flags in memory; libc falls back to clone
+        prog.push(jump(BPF_JEQ_K, libc::SYS_clone3 as u32, 0, 1));
+        prog.push(errno(libc::ENOSYS));
+        prog.push(jump(BPF_JEQ_K, libc::SYS_clone as u32, 0, 4));
+        prog.push(stmt(BPF_LD_W_ABS, OFFSET_ARG0));
+        prog.push(jump(BPF_JSET_K, NAMESPACE_FLAGS, 0, 1));
+        prog.push(errno(libc::EPERM));
+        prog.push(stmt(BPF_RET_K, SECCOMP_RET_ALLOW));
+        if !network {
+            let n = NETWORK_FAMILIES.len() as u8;
+            prog.push(jump(BPF_JEQ_K, libc::SYS_socket as u32, 0, n + 3));
+            prog.push(stmt(BPF_LD_W_ABS, OFFSET_ARG0));
+            for (i, family) in NETWORK_FAMILIES.iter().enumerate() {
+                prog.push(jump(BPF_JEQ_K, *family, n - i as u8, 0));
+            }
+            prog.push(stmt(BPF_RET_K, SECCOMP_RET_ALLOW));
+            prog.push(errno(libc::EACCES));
+        }
+        prog.push(stmt(BPF_RET_K, SECCOMP_RET_ALLOW));
+        Ok(prog)
+    }
+
+    // ── Namespaces ─────────────────────────────────────────────
+
+    struct Namespaces {
+        flags: libc::c_int,
+        uid_map: Vec<u8>,
+        gid_map: Vec<u8>,
+        /// System directories to remount read-only, with the mount flags
+        /// they already have (a user namespace can't drop them).
+        read_only: Vec<(CString, libc::c_ulong)>,
+        /// The session's `.tmp`, mounted over `/tmp`.
+        tmp: Option<CString>,
+    }
+
+    fn namespaces(network: bool, tmp: &Path) -> io::Result<Namespaces> {
+        let mut flags =
+            libc::CLONE_NEWUSER | libc::CLONE_NEWNS | libc::CLONE_NEWIPC | libc::CLONE_NEWUTS;
+        if !network {
+            flags |= libc::CLONE_NEWNET;
+        }
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let mut read_only = vec![];
+        for dir in SYSTEM_DIRS {
+            // Symlinked dirs (merged /usr) are covered by their targets
+            let is_dir = std::fs::symlink_metadata(dir).is_ok_and(|m| m.is_dir());
+            if !is_dir || ["/proc", "/sys", "/dev"].contains(dir) || dir.starts_with("/run/") {
+                continue;
+            }
+            let path = CString::new(*dir)?;
+            let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+            if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+                continue;
+            }
+            let mut locked = 0;
+            for (st, ms) in [
+                (libc::ST_NOSUID, libc::MS_NOSUID),
+                (libc::ST_NODEV, libc::MS_NODEV),
+                (libc::ST_NOEXEC, libc::MS_NOEXEC),
+                (libc::ST_NOATIME, libc::MS_NOATIME),
+                (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+                (libc::ST_RELATIME, libc::MS_RELATIME),
+            ] {
+                if stat.f_flag & st != 0 {
+                    locked |= ms;
+                }
+            }
+            read_only.push((path, locked));
+        }
+        let tmp = match binds_tmp(tmp) {
+            true => Some(CString::new(tmp.as_os_str().as_bytes())?),
+            false => None,
+        };
+        Ok(Namespaces {
+            flags,
+            uid_map: format!("{uid} {uid} 1").into_bytes(),
+            gid_map: format!("{gid} {gid} 1").into_bytes(),
+            read_only,
+            tmp,
+        })
+    }
+
+    // ── Preparing and entering ─────────────────────────────────
+
+    #[cfg(target_env = "gnu")]
+    type Resource = libc::__rlimit_resource_t;
+    #[cfg(not(target_env = "gnu"))]
+    type Resource = libc::c_int;
+
+    pub(super) struct Prepared {
+        rlimits: Vec<(Resource, libc::rlimit)>,
+        namespaces: Option<Namespaces>,
+        ruleset: OwnedFd,
+        filter: Vec<libc::sock_filter>,
+    }
+
+    pub(super) fn prepare(sandbox: &Sandbox, workspace: &Path, tmp: &Path) -> io::Result<Prepared> {
+        let mb = |n: u64| n.saturating_mul(1024 * 1024);
+        let mut rlimits = vec![(
+            libc::RLIMIT_CORE,
+            libc::rlimit {
+                rlim_cur: 0,
+                rlim_max: 0,
+            },
+        )];
+        for (resource, soft, hard) in [
+            // SIGXCPU at the limit, SIGKILL a little after
+            (
+                libc::RLIMIT_CPU,
+                sandbox.cpu_secs,
+                sandbox.cpu_secs.saturating_add(5),
+            ),
+            (
+                libc::RLIMIT_AS,
+                mb(sandbox.memory_mb),
+                mb(sandbox.memory_mb),
+            ),
+            (
+                libc::RLIMIT_FSIZE,
+                mb(sandbox.file_size_mb),
+                mb(sandbox.file_size_mb),
+            ),
+            (
+                libc::RLIMIT_NPROC,
+                sandbox.max_processes,
+                sandbox.max_processes,
+            ),
+        ] {
+            if soft > 0 {
+                rlimits.push((
+                    resource,
+                    libc::rlimit {
+                        rlim_cur: soft,
+                        rlim_max: hard,
+                    },
+                ));
+            }
+        }
+
+        let namespaces = match sandbox.mode {
+            SandboxMode::Namespaces => Some(namespaces(sandbox.network, tmp)?),
+            _ => None,
+        };
+
+        let mut read: Vec<PathBuf> = SYSTEM_DIRS.iter().map(PathBuf::from).collect();
+        read.extend(sandbox.read_paths.iter().cloned());
+        let mut write = vec![workspace.to_path_buf(), tmp.to_path_buf()];
+        write.extend(WRITABLE_DEVICES.iter().map(PathBuf::from));
+        let ruleset = landlock_ruleset(&read, &write)?;
+
+        Ok(Prepared {
+            rlimits,
+            namespaces,
+            ruleset,
+            filter: seccomp_filter(sandbox.network)?,
+        })
+    }
+
+    fn check(ret: libc::c_int) -> io::Result<()> {
+        if ret < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Runs in the child between `fork` and `exec`.
+    pub(super) fn enter(p: &Prepared) -> io::Result<()> {
+        unsafe {
+            for (resource, limit) in &p.rlimits {
+                check(libc::setrlimit(*resource, limit))?;
+            }
+
+            if let Some(ns) = &p.namespaces {
+                check(libc::unshare(ns.flags))?;
+                write_file(c"/proc/self/setgroups", b"deny")?;
+                write_file(c"/proc/self/uid_map", &ns.uid_map)?;
+                write_file(c"/proc/self/gid_map", &ns.gid_map)?;
+                let none = std::ptr::null();
+                check(libc::mount(
+                    none,
+                    c"/".as_ptr(),
+                    none,
+                    libc::MS_REC | libc::MS_PRIVATE,
+                    std::ptr::null(),
+                ))?;
+                for (dir, locked) in &ns.read_only {
+                    check(libc::mount(
+                        dir.as_ptr(),
+                        dir.as_ptr(),
+                        none,
+                        libc::MS_BIND | libc::MS_REC,
+                        std::ptr::null(),
+                    ))?;
+                    check(libc::mount(
+                        none,
+                        dir.as_ptr(),
+                        none,
+                        libc::MS_BIND | libc::MS_REMOUNT | libc::MS_RDONLY | locked,
+                        std::ptr::null(),
+                    ))?;
+                }
+                if let Some(tmp) = &ns.tmp {
+                    check(libc::mount(
+                        tmp.as_ptr(),
+                        c"/tmp".as_ptr(),
+                        none,
+                        libc::MS_BIND,
+                        std::ptr::null(),
+                    ))?;
+                }
+                let host = b"claw-sandbox";
+                check(libc::sethostname(host.as_ptr().cast(), host.len()))?;
+            }
+
+            check(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0))?;
+            check(
+                libc::syscall(SYS_LANDLOCK_RESTRICT_SELF, p.ruleset.as_raw_fd(), 0u32)
+                    as libc::c_int,
+            )?;
+
+            let prog = libc::sock_fprog {
+                len: p.filter.len() as u16,
+                filter: p.filter.as_ptr() as *mut libc::sock_filter,
+            };
+            check(libc::syscall(
+                libc::SYS_seccomp,
+                SECCOMP_SET_MODE_FILTER,
+                0 as libc::c_ulong,
+                &prog as *const libc::sock_fprog,
+            ) as libc::c_int)?;
+        }
+        Ok(())
+    }
+
+    unsafe fn write_file(path: &std::ffi::CStr, data: &[u8]) -> io::Result<()> {
+        unsafe {
+            let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+            check(fd)?;
+            let written = libc::write(fd, data.as_ptr().cast(), data.len());
+            libc::close(fd);
+            if written != data.len() as isize {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::tools::BuiltinTools;
+    use claw_core::ToolCall;
+
+    /// A sandbox rooted in `dir`, or `None` where the kernel can't provide it.
+    fn sandbox(
+        dir: &Path,
+        mode: SandboxMode,
+        tweak: impl FnOnce(&mut RuntimeConfig),
+    ) -> Option<Sandbox> {
+        let mut config = RuntimeConfig {
+            sandbox: mode,
+            sandbox_dir: dir.join("sandbox"),
+            ..RuntimeConfig::default()
+        };
+        tweak(&mut config);
+        match Sandbox::from_config(&config) {
+            Ok(sandbox) => Some(sandbox),
+            Err(e) => {
+                eprintln!("skipping: {e}");
+                None
+            }
+        }
+    }
+
+    /// Run `sh -c script` in the sandbox: (exit status, stdout + stderr).
+    fn run(sandbox: &Sandbox, session: Uuid, script: &str) -> (ExitStatus, String) {
+        let mut cmd = std::process::Command::new("/bin/sh");
+        cmd.arg("-c").arg(script);
+        sandbox
+            .confine(Some(session))
+            .unwrap()
+            .unwrap()
+            .apply(&mut cmd);
+        let out = cmd.output().unwrap();
+        let text = format!(
+            "{}{}",
+            String::from_utf8_lossy(&out.stdout),
+            String::from_utf8_lossy(&out.stderr)
+        );
+        (out.status, text)
+    }
+
+    #[test]
+    fn test_landlock_confines_writes_reads_and_environment() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some(sandbox) = sandbox(dir.path(), SandboxMode::Landlock, |_| {}) else {
+            return;
+        };
+        let session = Uuid::new_v4();
+        let workspace = sandbox.workspace(Some(session));
+        let secret = dir.path().join("secret.txt");
+        std::fs::write(&secret, "hunter2").unwrap();
+
+        // The workspace is HOME, the working directory and writable
+        let (status, out) = run(
+            &sandbox,
+            session,
+            "echo hi > notes.txt && pwd && echo $HOME $CLAW_SANDBOX",
+        );
+        assert!(status.success(), "{out}");
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("notes.txt")).unwrap(),
+            "hi\n"
+        );
+        assert!(
+            out.contains(&format!("{0}\n{0} landlock", workspace.display())),
+            "{out}"
+        );
+        // System dirs are readable and executable
+        let (status, out) = run(
+            &sandbox,
+            session,
+            "ls /usr/bin > /dev/null && cat /etc/hostname",
+        );
+        assert!(status.success(), "{out}");
+
+        // Nothing else is
+        for script in [
+            format!("cat {}", secret.display()),
+            format!("echo pwned > {}", dir.path().join("new.txt").display()),
+            format!("rm {}", secret.display()),
+        ] {
+            let (status, out) = run(&sandbox, session, &script);
+            assert!(!status.success(), "{script}: {out}");
+            let note = sandbox.explain(Some(&status), &out).unwrap();
+            assert!(
+                note.starts_with("🔒 Blocked by the sandbox (landlock)"),
+                "{note}"
+            );
+        }
+        assert_eq!(std::fs::read_to_string(&secret).unwrap(), "hunter2");
+        assert!(!dir.path().join("new.txt").exists());
+
+        // A denial on a path Landlock grants some access to, or on no path
+        // at all, might have another cause
+        let (status, out) = run(&sandbox, session, "touch /usr/claw-escape");
+        assert!(!status.success(), "{out}");
+        let note = sandbox.explain(Some(&status), &out).unwrap();
+        assert!(
+            note.starts_with("🔒 Possibly blocked by the sandbox (landlock)"),
+            "{note}"
+        );
+        let note = sandbox
+            .explain(Some(&status), "error: Permission denied (publickey)")
+            .unwrap();
+        assert!(note.starts_with("🔒 Possibly blocked"), "{note}");
+        assert_eq!(
+            sandbox.explain(Some(&status), "make: *** [all] Error 2"),
+            None
+        );
+
+        // Other sessions have their own workspace
+        let (status, _) = run(
+            &sandbox,
+            Uuid::new_v4(),
+            &format!("cat {}", workspace.join("notes.txt").display()),
+        );
+        assert!(!status.success());
+    }
+
+    #[test]
+    fn test_limits_network_and_syscalls() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some(sandbox) = sandbox(dir.path(), SandboxMode::Landlock, |c| {
+            c.sandbox_cpu_secs = 1;
+            c.sandbox_network = false;
+        }) else {
+            return;
+        };
+        let session = Uuid::new_v4();
+
+        let (status, out) = run(&sandbox, session, "while :; do :; done");
+        let note = sandbox.explain(Some(&status), &out).unwrap();
+        assert!(note.contains("CPU time limit (1s"), "{note}");
+
+        if Path::new("/bin/bash").exists() {
+            let (status, out) = run(
+                &sandbox,
+                session,
+                "exec /bin/bash -c 'echo > /dev/tcp/127.0.0.1/9'",
+            );
+            assert!(!status.success());
+            assert!(!out.contains("refused"), "{out}");
+        }
+        if Path::new("/usr/bin/unshare").exists() {
+            let (status, out) = run(&sandbox, session, "/usr/bin/unshare -U true");
+            assert!(!status.success(), "{out}");
+        }
+        if Path::new("/usr/bin/python3").exists() {
+            // Unix sockets reach host daemons; io_uring would open them unfiltered
+            let (_, out) = run(
+                &sandbox,
+                session,
+                "/usr/bin/python3 -c 'import socket; socket.socket(socket.AF_UNIX)'",
+            );
+            assert!(out.contains("PermissionError"), "{out}");
+            let (_, out) = run(
+                &sandbox,
+                session,
+                "/usr/bin/python3 -c 'import ctypes; libc = ctypes.CDLL(None, use_errno=True); \
+                 libc.syscall(425, 4, 0); print(ctypes.get_errno())'",
+            );
+            assert_eq!(out.trim(), libc::EPERM.to_string());
+        }
+        if Path::new("/run").is_dir() {
+            let (status, out) = run(&sandbox, session, "ls /run");
+            assert!(!status.success(), "{out}");
+        }
+    }
+
+    #[test]
+    fn test_namespaces_mount_system_dirs_read_only() {
+        // Outside /tmp, so the session's .tmp can be mounted over it
+        let target = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../target");
+        std::fs::create_dir_all(&target).unwrap();
+        let dir = tempfile::tempdir_in(target).unwrap();
+        let Some(sandbox) = sandbox(dir.path(), SandboxMode::Namespaces, |_| {}) else {
+            return;
+        };
+        let session = Uuid::new_v4();
+        let (status, out) = run(
+            &sandbox,
+            session,
+            "hostname; echo scratch > /tmp/scratch && echo $TMPDIR",
+        );
+        assert!(status.success(), "{out}");
+        assert_eq!(out, "claw-sandbox\n/tmp\n");
+        // /tmp is the session's own
+        let tmp = sandbox.workspace(Some(session)).join(".tmp/scratch");
+        assert_eq!(std::fs::read_to_string(tmp).unwrap(), "scratch\n");
+
+        let (status, out) = run(&sandbox, session, "touch /usr/claw-escape");
+        assert!(!status.success(), "{out}");
+        assert!(!Path::new("/usr/claw-escape").exists());
+    }
+
+    #[tokio::test]
+    async fn test_shell_exec_timeout_kills_the_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some(sandbox) = sandbox(dir.path(), SandboxMode::Landlock, |c| {
+            c.sandbox_max_command_secs = 1;
+        }) else {
+            return;
+        };
+        let session = Uuid::new_v4();
+        let workspace = sandbox.workspace(Some(session));
+        let tools = BuiltinTools::with_sandbox(sandbox);
+        let call = ToolCall {
+            id: "test-call".into(),
+            tool_name: "shell_exec".into(),
+            arguments: serde_json::json!({
+                "command": "sleep 30 & echo $! > sleeper.pid; sleep 30",
+                "timeout_secs": 60,
+            }),
+        };
+
+        let started = std::time::Instant::now();
+        let err = tools.execute_for(&call, Some(session)).await.unwrap_err();
+        assert!(err.to_string().contains("timed out after 1s"), "{err}");
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+        // The background sleep went with it
+        let pid = std::fs::read_to_string(workspace.join("sleeper.pid")).unwrap();
+        let stat = format!("/proc/{}/stat", pid.trim());
+        let alive = || {
+            std::fs::read_to_string(&stat)
+                .is_ok_and(|s| !s.rsplit(')').next().unwrap_or("").trim().starts_with('Z'))
+        };
+        for _ in 0..50 {
+            if !alive() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        assert!(!alive(), "sleep {} outlived the timeout", pid.trim());
+    }
+
+    #[tokio::test]
+    async fn test_shell_exec_reports_sandbox_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some(sandbox) = sandbox(dir.path(), SandboxMode::Landlock, |_| {}) else {
+            return;
+        };
+        let tools = BuiltinTools::with_sandbox(sandbox);
+        let call = |command: String| ToolCall {
+            id: "test-call".into(),
+            tool_name: "shell_exec".into(),
+            arguments: serde_json::json!({ "command": command }),
+        };
+
+        let outside = dir.path().join("outside.txt");
+        let result = tools
+            .execute_for(
+                &call(format!("echo x > {}", outside.display())),
+                Some(Uuid::new_v4()),
+            )
+            .await
+            .unwrap();
+        assert!(result.is_error);
+        assert!(
+            result.content.contains("🔒 Blocked by the sandbox"),
+            "{}",
+            result.content
+        );
+        assert!(result.data.unwrap()["sandbox_blocked"].is_string());
+
+        let result = tools
+            .execute_for(
+                &call("echo fine > ok.txt && cat ok.txt".into()),
+                Some(Uuid::new_v4()),
+            )
+            .await
+            .unwrap();
+        assert!(!result.is_error, "{}", result.content);
+        assert_eq!(result.content, "fine");
+    }
+}
@@ -6,7 +6,9 @@ use tracing::{info, warn};
 use uuid::Uuid;
 
 use claw_channels::adapter::IncomingMessage;
+use claw_config::TaskClass;
 use claw_core::{ToolCall, ToolResult};
+use claw_memory::MemoryScope;
 
 use crate::agent::{SharedAgentState, StreamEvent, SubTaskState, SubTaskStatus};
 use crate::agent_loop::{process_api_message, process_message_streaming_shared};
@@ -45,7 +47,11 @@ fn sub_agent_system_prompt(role: &str) -> String {
 }
 
 /// Spawn a sub-agent to work on a task concurrently.
-pub(crate) async fn exec_sub_agent_spawn(state: &SharedAgentState, call: &ToolCall) -> ToolResult {
+pub(crate) async fn exec_sub_agent_spawn(
+    state: &SharedAgentState,
+    call: &ToolCall,
+    scope: &MemoryScope,
+) -> ToolResult {
     let role = match call.arguments.get("role").and_then(|v| v.as_str()) {
         Some(r) => r.to_string(),
         None => {
@@ -154,6 +160,7 @@ pub(crate) async fn exec_sub_agent_spawn(state: &SharedAgentState, call: &ToolCa
         task.clone(),
         context_summary,
         depends_on.clone(),
+        scope.clone(),
     ));
 
     info!(
@@ -211,6 +218,7 @@ fn run_sub_agent_task(
     task_description: String,
     context_summary: Option<String>,
     depends_on: Vec<Uuid>,
+    scope: MemoryScope,
 ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
     Box::pin(async move {
         // Wait for dependencies if needed
@@ -301,10 +309,13 @@ fn run_sub_agent_task(
         let mut sub_state = state.clone();
         let mut sub_config = sub_state.config.clone();
         sub_config.agent.system_prompt = Some(sub_agent_system_prompt(&role));
+        sub_config.agent.model = sub_config.model_for_task(TaskClass::SubAgent(&role));
         if sub_config.agent.max_iterations < 100 {
             sub_config.agent.max_iterations = 100;
         }
         sub_state.config = sub_config;
+        // Remembers on behalf of whoever the parent was talking to
+        sub_state.memory_scope = Some(scope);
 
         // Check if we have a parent stream tx to forward events to
         let parent_tx = {
@@ -26,6 +26,8 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::Mutex;
 use tracing::{debug, info};
 
+use crate::sandbox::Confinement;
+
 /// Maximum output buffer size per terminal (256 KB).
 const MAX_OUTPUT_BYTES: usize = 256 * 1024;
 
@@ -126,6 +128,8 @@ pub struct TerminalSession {
     exited: Arc<AtomicBool>,
     _reader_handle: tokio::task::JoinHandle<()>,
     started_at: std::time::Instant,
+    /// Input typed on the current line but not yet submitted with Enter.
+    pending_line: String,
 }
 
 impl TerminalSession {
@@ -170,7 +174,12 @@ static TERMINAL_REGISTRY: std::sync::LazyLock<Mutex<TerminalManager>> =
 /// Spawns a login shell (`/bin/zsh` on macOS, `/bin/bash` fallback) connected
 /// to a real PTY. Returns `(terminal_id, initial_output)`.
 /// If `working_dir` is provided, the shell will `cd` into that directory after starting.
-pub async fn terminal_open(label: &str, working_dir: Option<&str>) -> io::Result<(u32, String)> {
+/// With a `confinement`, the shell runs in the sandbox.
+pub async fn terminal_open(
+    label: &str,
+    working_dir: Option<&str>,
+    confinement: Option<Confinement>,
+) -> io::Result<(u32, String)> {
     // --- Create PTY pair ---
     let mut master: libc::c_int = 0;
     let mut slave: libc::c_int = 0;
@@ -236,6 +245,10 @@ pub async fn terminal_open(label: &str, working_dir: Option<&str>) -> io::Result
             Ok(())
         });
     }
+    // After the PTY hook above, so the shell enters the sandbox last
+    if let Some(confinement) = &confinement {
+        confinement.apply(&mut cmd);
+    }
 
     let child = cmd.spawn().map_err(|e| {
         // Clean up fds on spawn failure
@@ -306,6 +319,7 @@ pub async fn terminal_open(label: &str, working_dir: Option<&str>) -> io::Result
             exited,
             _reader_handle: reader_handle,
             started_at: std::time::Instant::now(),
+            pending_line: String::new(),
         },
     );
 
@@ -355,6 +369,45 @@ pub async fn terminal_input(id: u32, text: &str, timeout_ms: u64) -> io::Result<
     wait_for_output(id, timeout_ms).await
 }
 
+/// The command lines that sending `text` to a terminal submits: its pending,
+/// un-submitted input followed by `text`, up to the last line break. A
+/// command typed in pieces — `rm -rf ` then `~\n` — comes back whole, and
+/// text without a line break submits nothing. Only plain typing, backspace,
+/// Ctrl-C and Ctrl-U are followed; history recall and other line editing
+/// aren't.
+pub async fn submitted_input(id: u32, text: &str) -> String {
+    let mgr = TERMINAL_REGISTRY.lock().await;
+    let mut line = mgr
+        .sessions
+        .get(&id)
+        .map(|s| s.pending_line.clone())
+        .unwrap_or_default();
+    drop(mgr);
+    feed_line(&mut line, text)
+}
+
+/// Type `text` onto `line`, returning the lines it submits (each ending in
+/// `\n`) and leaving the rest in `line`.
+fn feed_line(line: &mut String, text: &str) -> String {
+    let mut submitted = String::new();
+    for c in text.chars() {
+        match c {
+            '\n' | '\r' => {
+                submitted.push_str(line);
+                submitted.push('\n');
+                line.clear();
+            }
+            // Ctrl-C and Ctrl-U discard the line
+            '\x03' | '\x15' => line.clear(),
+            '\x7f' | '\x08' => {
+                line.pop();
+            }
+            c => line.push(c),
+        }
+    }
+    submitted
+}
+
 /// Read the last N lines of terminal output (without advancing the cursor).
 /// Detects repeated views with no change to prevent polling loops.
 pub async fn terminal_view(id: u32, lines: usize) -> io::Result<String> {
@@ -442,8 +495,8 @@ pub struct TerminalInfo {
 
 /// Write raw bytes to a terminal's master fd.
 async fn terminal_write_raw(id: u32, text: &str) -> io::Result<()> {
-    let mgr = TERMINAL_REGISTRY.lock().await;
-    let session = mgr.sessions.get(&id).ok_or_else(|| {
+    let mut mgr = TERMINAL_REGISTRY.lock().await;
+    let session = mgr.sessions.get_mut(&id).ok_or_else(|| {
         io::Error::new(io::ErrorKind::NotFound, format!("terminal {id} not found"))
     })?;
 
@@ -454,6 +507,7 @@ async fn terminal_write_raw(id: u32, text: &str) -> io::Result<()> {
         ));
     }
 
+    feed_line(&mut session.pending_line, text);
     let fd = session.master_fd;
     // Clear the "new output" buffer before writing so we only capture output
     // from this command, not leftover output from previous commands.
@@ -775,10 +829,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_feed_line_submits_whole_lines() {
+        let mut line = String::new();
+        assert_eq!(feed_line(&mut line, "rm -rf "), "");
+        assert_eq!(line, "rm -rf ");
+        assert_eq!(feed_line(&mut line, "~\r"), "rm -rf ~\n");
+        assert_eq!(line, "");
+
+        assert_eq!(feed_line(&mut line, "ls\ncd /x"), "ls\n");
+        assert_eq!(line, "cd /x");
+        assert_eq!(feed_line(&mut line, "\x7f\x7fy\n"), "cd y\n");
+
+        feed_line(&mut line, "rm -rf /");
+        assert_eq!(feed_line(&mut line, "\x03echo hi\n"), "echo hi\n");
+    }
+
     #[tokio::test]
     async fn test_terminal_open_close() {
         // Open a terminal
-        let (id, _initial) = terminal_open("test-terminal", None)
+        let (id, _initial) = terminal_open("test-terminal", None, None)
             .await
             .expect("open failed");
         assert!(id > 0);
@@ -798,7 +868,9 @@ mod tests {
 
     #[tokio::test]
     async fn test_terminal_run_command() {
-        let (id, _) = terminal_open("test-run", None).await.expect("open failed");
+        let (id, _) = terminal_open("test-run", None, None)
+            .await
+            .expect("open failed");
 
         // Run a simple command
         let output = terminal_run(id, "echo hello_from_pty", 5000)
@@ -813,9 +885,35 @@ mod tests {
         terminal_close(id).await.ok();
     }
 
+    #[tokio::test]
+    async fn test_submitted_input_includes_pending_line() {
+        let (id, _) = terminal_open("test-pending", None, None)
+            .await
+            .expect("open failed");
+
+        terminal_input(id, "echo piece", 1000)
+            .await
+            .expect("input failed");
+        assert_eq!(submitted_input(id, "").await, "");
+        assert_eq!(
+            submitted_input(id, "s_joined\n").await,
+            "echo pieces_joined\n"
+        );
+
+        let output = terminal_run(id, "s_joined", 5000)
+            .await
+            .expect("run failed");
+        assert!(output.contains("pieces_joined"), "output: {output}");
+        assert_eq!(submitted_input(id, "\r").await, "\n");
+
+        terminal_close(id).await.ok();
+    }
+
     #[tokio::test]
     async fn test_terminal_view() {
-        let (id, _) = terminal_open("test-view", None).await.expect("open failed");
+        let (id, _) = terminal_open("test-view", None, None)
+            .await
+            .expect("open failed");
 
         // Run a command
         terminal_run(id, "echo view_test_line", 5000).await.ok();
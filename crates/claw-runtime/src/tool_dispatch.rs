@@ -6,7 +6,8 @@ use uuid::Uuid;
 
 use claw_core::{ToolCall, ToolResult};
 use claw_device::DeviceTools;
-use claw_llm::LlmRequest;
+use claw_llm::{LlmRequest, ResponseFormat};
+use claw_memory::{MemoryScope, ScopeKind};
 use claw_mesh::MeshMessage;
 
 use crate::agent::{MeshTaskResult, SharedAgentState};
@@ -16,15 +17,52 @@ use crate::sub_agent::{
     exec_sub_agent_status, exec_sub_agent_wait,
 };
 
-pub(crate) async fn execute_tool_shared(state: &SharedAgentState, call: &ToolCall) -> ToolResult {
+/// Run a tool call. `scope` is whose memory the memory tools read and write.
+pub(crate) async fn execute_tool_shared(
+    state: &SharedAgentState,
+    call: &ToolCall,
+    scope: &MemoryScope,
+) -> ToolResult {
     debug!(tool = %call.tool_name, "executing tool");
 
+    // Path-taking tools get their paths resolved and checked against the
+    // filesystem scope — no allowlist or policy rule gets around it
+    let scoped;
+    let call = match state
+        .path_scope
+        .check_call(&call.tool_name, &call.arguments)
+    {
+        Ok(None) => call,
+        Ok(Some(arguments)) => {
+            scoped = ToolCall {
+                arguments,
+                ..call.clone()
+            };
+            &scoped
+        }
+        Err(reason) => {
+            warn!(tool = %call.tool_name, %reason, "path outside filesystem scope");
+            return ToolResult {
+                tool_call_id: call.id.clone(),
+                content: format!("Access denied: {reason}"),
+                is_error: true,
+                data: None,
+            };
+        }
+    };
+
     // Memory and goal tools need locks on shared state
     match call.tool_name.as_str() {
-        "memory_search" => return exec_memory_search_shared(state, call).await,
-        "memory_store" => return exec_memory_store_shared(state, call).await,
-        "memory_delete" => return exec_memory_delete_shared(state, call).await,
-        "memory_list" => return exec_memory_list_shared(state, call).await,
+        "memory_search" => return exec_memory_search_shared(state, call, scope).await,
+        "memory_store" => return exec_memory_store_shared(state, call, scope).await,
+        "memory_delete" => return exec_memory_delete_shared(state, call, scope).await,
+        "memory_list" => return exec_memory_list_shared(state, call, scope).await,
+        "memory_conflicts" => return exec_memory_conflicts(state, call, scope).await,
+        "graph_query" => return exec_graph_query(state, call).await,
+        "graph_neighbors" => return exec_graph_neighbors(state, call).await,
+        "kb_ingest" => return exec_kb_ingest(state, call).await,
+        "kb_search" => return exec_kb_search(state, call).await,
+        "kb_list_sources" => return exec_kb_list_sources(state, call).await,
         "goal_create" => return exec_goal_create_shared(state, call).await,
         "goal_list" => return exec_goal_list_shared(state, call).await,
         "goal_complete_step" => return exec_goal_complete_step_shared(state, call).await,
@@ -35,7 +73,7 @@ pub(crate) async fn execute_tool_shared(state: &SharedAgentState, call: &ToolCal
         "mesh_delegate" => return exec_mesh_delegate_shared(state, call).await,
         "mesh_status" => return exec_mesh_status_shared(state, call).await,
         "channel_send_file" => return exec_channel_send_file(state, call).await,
-        "sub_agent_spawn" => return exec_sub_agent_spawn(state, call).await,
+        "sub_agent_spawn" => return exec_sub_agent_spawn(state, call, scope).await,
         "sub_agent_wait" => return exec_sub_agent_wait(state, call).await,
         "sub_agent_status" => return exec_sub_agent_status(state, call).await,
         "cron_schedule" => return exec_cron_schedule(state, call).await,
@@ -46,7 +84,7 @@ pub(crate) async fn execute_tool_shared(state: &SharedAgentState, call: &ToolCal
 
     // Builtin tools (shell, file ops) — no lock needed
     if state.tools.has_tool(&call.tool_name) {
-        match state.tools.execute(call).await {
+        match state.tools.execute_for(call, scope.session).await {
             Ok(result) => return result,
             Err(e) => {
                 return ToolResult {
@@ -111,6 +149,9 @@ async fn exec_llm_generate_shared(state: &SharedAgentState, call: &ToolCall) ->
     };
 
     let max_tokens = call.arguments["max_tokens"].as_u64().unwrap_or(2048) as u32;
+    let response_format = call.arguments["schema"]
+        .is_object()
+        .then(|| ResponseFormat::new("llm_generate_output", call.arguments["schema"].clone()));
 
     let request = LlmRequest {
         model: state.config.agent.model.clone(),
@@ -130,6 +171,7 @@ async fn exec_llm_generate_shared(state: &SharedAgentState, call: &ToolCall) ->
         max_tokens,
         temperature: state.config.agent.temperature,
         thinking_level: None,
+        response_format,
         stream: false,
     };
 
@@ -596,7 +638,33 @@ async fn exec_mesh_status_shared(state: &SharedAgentState, call: &ToolCall) -> T
     }
 }
 
-async fn exec_memory_search_shared(state: &SharedAgentState, call: &ToolCall) -> ToolResult {
+/// The scope a memory tool call writes to: its `scope` argument, else the
+/// conversation's default.
+fn target_scope(call: &ToolCall, scope: &MemoryScope) -> claw_core::Result<String> {
+    let kind = match call.arguments["scope"].as_str() {
+        Some(kind) => Some(
+            kind.parse::<ScopeKind>()
+                .map_err(claw_core::ClawError::Memory)?,
+        ),
+        None => None,
+    };
+    scope.write_scope(kind)
+}
+
+/// ` [user:telegram:42]` after a fact that isn't global; nothing for global ones.
+fn scope_suffix(scope: &str) -> String {
+    if scope == claw_memory::scope::GLOBAL {
+        String::new()
+    } else {
+        format!(" [{scope}]")
+    }
+}
+
+async fn exec_memory_search_shared(
+    state: &SharedAgentState,
+    call: &ToolCall,
+    scope: &MemoryScope,
+) -> ToolResult {
     let query = call.arguments["query"].as_str().unwrap_or("");
     let mem_type = call.arguments["type"].as_str().unwrap_or("all");
 
@@ -612,9 +680,29 @@ async fn exec_memory_search_shared(state: &SharedAgentState, call: &ToolCall) ->
 
     let mem = state.memory.read().await;
     let mut results = Vec::new();
+    let readable = scope.readable();
+
+    // Stop words dropped so BM25 ranks on the words that carry meaning —
+    // unless that leaves nothing to search for
+    let keywords = match extract_search_keywords(query) {
+        k if k.is_empty() => query.to_string(),
+        k => k,
+    };
 
     if mem_type == "episodic" || mem_type == "all" {
-        let episodes = mem.episodic.search(query);
+        let episodes: Vec<claw_memory::Episode> =
+            match mem.search_episodes(&keywords, Some(&readable), 10) {
+                Ok(hits) => hits.into_iter().map(|h| h.episode).collect(),
+                Err(e) => {
+                    warn!(error = %e, "episode full-text search failed");
+                    mem.episodic
+                        .search(query)
+                        .into_iter()
+                        .filter(|e| scope.can_read(&e.scope))
+                        .cloned()
+                        .collect()
+                }
+            };
         for ep in episodes.iter().take(10) {
             results.push(format!(
                 "[Episode {}] {}{}",
@@ -628,55 +716,50 @@ async fn exec_memory_search_shared(state: &SharedAgentState, call: &ToolCall) ->
         }
     }
 
+    let mut recalled = Vec::new();
     if mem_type == "semantic" || mem_type == "all" {
-        // Combine vector + keyword search, dedup by category:key
-        let mut seen = std::collections::HashSet::new();
-
-        // Vector search first (highest quality)
-        if let Some(ref qemb) = query_embedding {
-            for (fact, score) in mem.semantic.vector_search(qemb, 15) {
-                let fk = format!("{}:{}", fact.category, fact.key);
-                if seen.insert(fk) {
-                    results.push(format!(
-                        "[Fact: {}/{}] {} (relevance: {:.0}%)",
-                        fact.category,
-                        fact.key,
-                        fact.value,
-                        score * 100.0
-                    ));
-                }
-            }
-        }
-
-        // Word-level keyword search (catches things without embeddings)
-        for fact in mem.semantic.search(query).iter().take(15) {
-            let fk = format!("{}:{}", fact.category, fact.key);
-            if seen.insert(fk) {
-                results.push(format!(
-                    "[Fact: {}/{}] {} (confidence: {:.0}%)",
-                    fact.category,
-                    fact.key,
-                    fact.value,
-                    fact.confidence * 100.0
-                ));
-            }
+        let now = chrono::Utc::now();
+        for (fact, _score) in
+            mem.hybrid_fact_search(&keywords, query_embedding.as_deref(), Some(&readable), 15)
+        {
+            results.push(format!(
+                "[Fact: {}/{}{}] {} (confidence: {:.0}%)",
+                fact.category,
+                fact.key,
+                scope_suffix(&fact.scope),
+                fact.value,
+                mem.decay_policy().effective_confidence(fact, now) * 100.0
+            ));
+            recalled.push((fact.scope.clone(), fact.category.clone(), fact.key.clone()));
         }
+    }
 
-        // Also search with extracted keywords for broader matching
-        let keywords = extract_search_keywords(query);
-        if keywords != query.to_lowercase() {
-            for fact in mem.semantic.search(&keywords).iter().take(5) {
-                let fk = format!("{}:{}", fact.category, fact.key);
-                if seen.insert(fk) {
+    if mem_type == "conversations" || mem_type == "all" {
+        let limit = if mem_type == "all" { 5 } else { 15 };
+        let sessions = crate::agent::readable_sessions(&state.config.memory, &mem, scope);
+        match mem.search_messages_in(&keywords, &sessions, limit) {
+            Ok(hits) => {
+                for hit in hits {
                     results.push(format!(
-                        "[Fact: {}/{}] {}",
-                        fact.category, fact.key, fact.value
+                        "[Message {} {}, session {}] {}",
+                        hit.timestamp.get(..10).unwrap_or(&hit.timestamp),
+                        hit.role,
+                        hit.session_id,
+                        hit.snippet
                     ));
                 }
             }
+            Err(e) => warn!(error = %e, "message full-text search failed"),
         }
     }
 
+    drop(mem);
+    if !recalled.is_empty()
+        && let Err(e) = state.memory.write().await.reinforce_facts(&recalled)
+    {
+        warn!(error = %e, "failed to reinforce recalled facts");
+    }
+
     let content = if results.is_empty() {
         format!(
             "No relevant memories found for query: \"{query}\". Try memory_list to see all stored facts."
@@ -693,10 +776,25 @@ async fn exec_memory_search_shared(state: &SharedAgentState, call: &ToolCall) ->
     }
 }
 
-async fn exec_memory_store_shared(state: &SharedAgentState, call: &ToolCall) -> ToolResult {
+async fn exec_memory_store_shared(
+    state: &SharedAgentState,
+    call: &ToolCall,
+    scope: &MemoryScope,
+) -> ToolResult {
     let category = call.arguments["category"].as_str().unwrap_or("general");
     let key = call.arguments["key"].as_str().unwrap_or("unknown");
     let value = call.arguments["value"].as_str().unwrap_or("");
+    let fact_scope = match target_scope(call, scope) {
+        Ok(fact_scope) => fact_scope,
+        Err(e) => {
+            return ToolResult {
+                tool_call_id: call.id.clone(),
+                content: format!("Error: {e}"),
+                is_error: true,
+                data: None,
+            };
+        }
+    };
 
     // Generate embedding if an embedder is configured
     let embedding = if let Some(ref embedder) = state.embedder {
@@ -713,29 +811,31 @@ async fn exec_memory_store_shared(state: &SharedAgentState, call: &ToolCall) ->
         None
     };
 
-    let mut mem = state.memory.write().await;
-
     let fact = claw_memory::semantic::Fact {
         id: Uuid::new_v4(),
+        scope: fact_scope.clone(),
         category: category.to_string(),
         key: key.to_string(),
         value: value.to_string(),
         confidence: 1.0,
-        source: Some("agent".to_string()),
-        embedding: embedding.clone(),
+        source: Some("tool:memory_store".to_string()),
+        embedding,
         created_at: chrono::Utc::now(),
         updated_at: chrono::Utc::now(),
+        access_count: 0,
+        last_accessed: None,
     };
 
-    mem.semantic.upsert(fact);
-
-    if let Err(e) = mem.persist_fact_with_embedding(category, key, value, embedding.as_deref()) {
-        warn!(error = %e, "failed to persist fact to SQLite");
-    }
-    drop(mem); // Release memory lock before mesh operations
+    let conflict = match crate::facts::store_fact(state, fact).await {
+        Ok(conflict) => conflict,
+        Err(e) => {
+            warn!(error = %e, "failed to persist fact to SQLite");
+            None
+        }
+    };
 
-    // Broadcast fact to mesh peers for sync
-    {
+    // Broadcast fact to mesh peers for sync — only global facts are shared
+    if fact_scope == claw_memory::scope::GLOBAL {
         let mesh = state.mesh.lock().await;
         if mesh.is_running() && mesh.peer_count() > 0 {
             let sync_msg = MeshMessage::SyncDelta {
@@ -760,15 +860,33 @@ async fn exec_memory_store_shared(state: &SharedAgentState, call: &ToolCall) ->
         }
     }
 
+    let mut content = format!(
+        "Stored fact: {category}/{key}{} = {value}",
+        scope_suffix(&fact_scope)
+    );
+    if let Some(conflict) = conflict {
+        content.push_str(&format!(
+            "\n⚠️ This contradicts the previous value \"{}\" (from {}): {}. Recorded as conflict {} — \
+             check which value is right and settle it with memory_conflicts (action \"resolve\").",
+            conflict.previous_value,
+            conflict.previous_source.as_deref().unwrap_or("unknown source"),
+            conflict.reason.as_deref().unwrap_or("values differ"),
+            conflict.id.get(..8).unwrap_or(&conflict.id),
+        ));
+    }
     ToolResult {
         tool_call_id: call.id.clone(),
-        content: format!("Stored fact: {category}/{key} = {value}"),
+        content,
         is_error: false,
         data: None,
     }
 }
 
-async fn exec_memory_delete_shared(state: &SharedAgentState, call: &ToolCall) -> ToolResult {
+async fn exec_memory_delete_shared(
+    state: &SharedAgentState,
+    call: &ToolCall,
+    scope: &MemoryScope,
+) -> ToolResult {
     let category = call.arguments["category"].as_str().unwrap_or("");
     let key = call.arguments.get("key").and_then(|v| v.as_str());
 
@@ -780,22 +898,41 @@ async fn exec_memory_delete_shared(state: &SharedAgentState, call: &ToolCall) ->
             data: None,
         };
     }
+    let fact_scope = match target_scope(call, scope) {
+        Ok(fact_scope) => fact_scope,
+        Err(e) => {
+            return ToolResult {
+                tool_call_id: call.id.clone(),
+                content: format!("Error: {e}"),
+                is_error: true,
+                data: None,
+            };
+        }
+    };
 
     let mut mem = state.memory.write().await;
 
     let result_msg = if let Some(key) = key {
         // Delete a specific fact
-        let removed_mem = mem.semantic.remove(category, key);
-        let removed_db = mem.delete_fact(category, key).unwrap_or(false);
+        let removed_mem = mem.semantic.remove(&fact_scope, category, key);
+        let removed_db = mem.delete_fact(&fact_scope, category, key).unwrap_or(false);
         if removed_mem || removed_db {
-            format!("Deleted fact: {category}/{key}")
+            format!(
+                "Deleted fact: {category}/{key}{}",
+                scope_suffix(&fact_scope)
+            )
         } else {
-            format!("Fact not found: {category}/{key}")
+            format!(
+                "Fact not found: {category}/{key}{}",
+                scope_suffix(&fact_scope)
+            )
         }
     } else {
         // Delete entire category
-        let count_mem = mem.semantic.remove_category(category);
-        let count_db = mem.delete_facts_by_category(category).unwrap_or(0);
+        let count_mem = mem.semantic.remove_category(&fact_scope, category);
+        let count_db = mem
+            .delete_facts_by_category(&fact_scope, category)
+            .unwrap_or(0);
         let count = count_mem.max(count_db);
         if count > 0 {
             format!("Deleted {count} fact(s) from category '{category}'")
@@ -814,49 +951,69 @@ async fn exec_memory_delete_shared(state: &SharedAgentState, call: &ToolCall) ->
     }
 }
 
-async fn exec_memory_list_shared(state: &SharedAgentState, call: &ToolCall) -> ToolResult {
+async fn exec_memory_list_shared(
+    state: &SharedAgentState,
+    call: &ToolCall,
+    scope: &MemoryScope,
+) -> ToolResult {
     let filter_category = call.arguments.get("category").and_then(|v| v.as_str());
 
     let mem = state.memory.read().await;
     let mut lines = Vec::new();
+    let visible = |cat: &str| -> Vec<&claw_memory::Fact> {
+        mem.semantic
+            .category(cat)
+            .iter()
+            .filter(|f| scope.can_read(&f.scope))
+            .collect()
+    };
 
     if let Some(cat) = filter_category {
         // List facts in a specific category
-        let facts = mem.semantic.category(cat);
+        let facts = visible(cat);
         if facts.is_empty() {
             lines.push(format!("Category '{cat}': (empty)"));
         } else {
             lines.push(format!("Category '{}' ({} facts):", cat, facts.len()));
             for fact in facts {
                 lines.push(format!(
-                    "  - {}: {} (confidence: {:.0}%, updated: {})",
+                    "  - {}{}: {} (confidence: {:.0}%, updated: {})",
                     fact.key,
+                    scope_suffix(&fact.scope),
                     fact.value,
-                    fact.confidence * 100.0,
+                    mem.decay_policy()
+                        .effective_confidence(fact, chrono::Utc::now())
+                        * 100.0,
                     fact.updated_at.format("%Y-%m-%d %H:%M")
                 ));
             }
         }
     } else {
         // List all categories with their facts
-        let mut categories: Vec<&str> = mem.semantic.categories();
-        categories.sort();
+        let mut categories: Vec<(&str, Vec<&claw_memory::Fact>)> = mem
+            .semantic
+            .categories()
+            .into_iter()
+            .map(|cat| (cat, visible(cat)))
+            .filter(|(_, facts)| !facts.is_empty())
+            .collect();
+        categories.sort_by_key(|(cat, _)| *cat);
         if categories.is_empty() {
             lines.push("Memory is empty — no facts stored.".to_string());
         } else {
-            let total = mem.semantic.count();
+            let total: usize = categories.iter().map(|(_, facts)| facts.len()).sum();
             lines.push(format!(
                 "Total: {} facts across {} categories\n",
                 total,
                 categories.len()
             ));
-            for cat in categories {
-                let facts = mem.semantic.category(cat);
+            for (cat, facts) in categories {
                 lines.push(format!("📁 {} ({}):", cat, facts.len()));
                 for fact in facts.iter().take(20) {
                     lines.push(format!(
-                        "  - {}: {}",
+                        "  - {}{}: {}",
                         fact.key,
+                        scope_suffix(&fact.scope),
                         if fact.value.len() > 120 {
                             format!("{}…", &fact.value[..120])
                         } else {
@@ -881,6 +1038,361 @@ async fn exec_memory_list_shared(state: &SharedAgentState, call: &ToolCall) -> T
     }
 }
 
+async fn exec_memory_conflicts(
+    state: &SharedAgentState,
+    call: &ToolCall,
+    scope: &MemoryScope,
+) -> ToolResult {
+    let error = |message: String| ToolResult {
+        tool_call_id: call.id.clone(),
+        content: format!("Error: {message}"),
+        is_error: true,
+        data: None,
+    };
+
+    if call.arguments["action"].as_str() == Some("resolve") {
+        let Some(id) = call.arguments["id"].as_str().filter(|id| !id.is_empty()) else {
+            return error("'id' is required to resolve a conflict".into());
+        };
+        let value = call.arguments["value"].as_str();
+        if let Ok(Some(conflict)) = state.memory.read().await.fact_conflict(id)
+            && !scope.can_write(&conflict.scope)
+        {
+            return error(format!(
+                "conflict '{id}' is in {} memory, which this conversation can't change",
+                conflict.scope
+            ));
+        }
+        return match crate::facts::resolve_conflict(state, id, value).await {
+            Ok(conflict) => ToolResult {
+                tool_call_id: call.id.clone(),
+                content: format!(
+                    "Resolved conflict {}: {}/{} = {}",
+                    conflict.id.get(..8).unwrap_or(&conflict.id),
+                    conflict.category,
+                    conflict.key,
+                    conflict.resolution.as_deref().unwrap_or(&conflict.value)
+                ),
+                is_error: false,
+                data: serde_json::to_value(&conflict).ok(),
+            },
+            Err(e) => error(e.to_string()),
+        };
+    }
+
+    let include_resolved = call.arguments["include_resolved"]
+        .as_bool()
+        .unwrap_or(false);
+    let mem = state.memory.read().await;
+    let conflicts: Vec<claw_memory::FactConflict> = match mem.fact_conflicts(include_resolved) {
+        Ok(conflicts) => conflicts
+            .into_iter()
+            .filter(|c| scope.can_read(&c.scope))
+            .collect(),
+        Err(e) => return error(e.to_string()),
+    };
+    if conflicts.is_empty() {
+        return ToolResult {
+            tool_call_id: call.id.clone(),
+            content: "No open memory conflicts.".to_string(),
+            is_error: false,
+            data: None,
+        };
+    }
+
+    let mut lines = vec![format!("{} conflict(s):", conflicts.len())];
+    for c in &conflicts {
+        lines.push(format!(
+            "\n[{}] {}/{}{} — {}{}",
+            c.id.get(..8).unwrap_or(&c.id),
+            c.category,
+            c.key,
+            scope_suffix(&c.scope),
+            c.reason.as_deref().unwrap_or("values differ"),
+            if c.is_open() {
+                String::new()
+            } else {
+                format!(" (resolved: {})", c.resolution.as_deref().unwrap_or(""))
+            }
+        ));
+        lines.push(format!(
+            "  previous: {} (from {})",
+            c.previous_value,
+            c.previous_source.as_deref().unwrap_or("unknown")
+        ));
+        lines.push(format!(
+            "  current:  {} (from {})",
+            c.value,
+            c.source.as_deref().unwrap_or("unknown")
+        ));
+        if c.occurrences > 1 {
+            lines.push(format!("  flipped {} times", c.occurrences));
+        }
+        if let Ok(history) = mem.fact_history(&c.scope, &c.category, &c.key)
+            && history.len() > 2
+        {
+            lines.push("  history:".to_string());
+            for v in &history {
+                lines.push(format!(
+                    "    v{} {} — {} ({})",
+                    v.version,
+                    v.recorded_at.get(..16).unwrap_or(&v.recorded_at),
+                    v.value,
+                    v.source.as_deref().unwrap_or("unknown")
+                ));
+            }
+        }
+    }
+
+    ToolResult {
+        tool_call_id: call.id.clone(),
+        content: lines.join("\n"),
+        is_error: false,
+        data: None,
+    }
+}
+
+async fn exec_graph_query(state: &SharedAgentState, call: &ToolCall) -> ToolResult {
+    let result = |content: String, is_error: bool| ToolResult {
+        tool_call_id: call.id.clone(),
+        content,
+        is_error,
+        data: None,
+    };
+    let arg = |name: &str| {
+        call.arguments[name]
+            .as_str()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+    };
+    let limit = call.arguments["limit"].as_u64().unwrap_or(50).clamp(1, 200) as usize;
+    let pattern = claw_memory::RelationPattern {
+        subject: arg("subject"),
+        predicate: arg("predicate"),
+        object: arg("object"),
+        subject_kind: arg("subject_kind"),
+        object_kind: arg("object_kind"),
+    };
+    let mem = state.memory.read().await;
+
+    if pattern.subject.is_none() && pattern.predicate.is_none() && pattern.object.is_none() {
+        let Some(name) = arg("name").or_else(|| arg("kind").map(|_| String::new())) else {
+            return result(
+                "Error: give a subject, predicate or object to match relations, or a name to find entities".into(),
+                true,
+            );
+        };
+        return match mem
+            .graph
+            .find_entities(&name, arg("kind").as_deref(), limit)
+        {
+            Ok(entities) if entities.is_empty() => {
+                result(format!("No entities matching '{name}'."), false)
+            }
+            Ok(entities) => {
+                let mut lines = vec![format!("{} entit(ies):", entities.len())];
+                lines.extend(
+                    entities
+                        .iter()
+                        .map(|e| format!("- {}", crate::graph::format_entity_line(e))),
+                );
+                result(lines.join("\n"), false)
+            }
+            Err(e) => result(format!("Error: {e}"), true),
+        };
+    }
+
+    match mem.graph.relations(&pattern, limit) {
+        Ok(relations) if relations.is_empty() => result("No relations match.".to_string(), false),
+        Ok(relations) => {
+            let mut lines = vec![format!("{} relation(s):", relations.len())];
+            lines.extend(
+                relations
+                    .iter()
+                    .map(|r| format!("- {}", crate::graph::format_relation(r))),
+            );
+            result(lines.join("\n"), false)
+        }
+        Err(e) => result(format!("Error: {e}"), true),
+    }
+}
+
+async fn exec_graph_neighbors(state: &SharedAgentState, call: &ToolCall) -> ToolResult {
+    let result = |content: String, is_error: bool| ToolResult {
+        tool_call_id: call.id.clone(),
+        content,
+        is_error,
+        data: None,
+    };
+    let Some(reference) = call.arguments["entity"]
+        .as_str()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    else {
+        return result("Error: 'entity' is required".into(), true);
+    };
+    let direction = match call.arguments["direction"]
+        .as_str()
+        .unwrap_or("both")
+        .parse::<claw_memory::Direction>()
+    {
+        Ok(direction) => direction,
+        Err(e) => return result(format!("Error: {e}"), true),
+    };
+    let depth = call.arguments["depth"].as_u64().unwrap_or(1) as usize;
+    let limit = call.arguments["limit"].as_u64().unwrap_or(50).clamp(1, 500) as usize;
+    let predicate = call.arguments["predicate"]
+        .as_str()
+        .filter(|s| !s.trim().is_empty());
+
+    let mem = state.memory.read().await;
+    let entities = match mem.graph.resolve(reference) {
+        Ok(entities) => entities,
+        Err(e) => return result(format!("Error: {e}"), true),
+    };
+    if entities.is_empty() {
+        let similar: Vec<String> = mem
+            .graph
+            .find_entities(reference, None, 10)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+        return result(
+            if similar.is_empty() {
+                format!("No entity '{reference}' in the knowledge graph.")
+            } else {
+                format!(
+                    "No entity '{reference}' in the knowledge graph. Similar: {}",
+                    similar.join(", ")
+                )
+            },
+            false,
+        );
+    }
+
+    let mut sections = Vec::new();
+    for entity in entities.iter().take(3) {
+        match mem
+            .graph
+            .neighbors(&entity.id, depth, direction, predicate, limit)
+        {
+            Ok(Some(neighborhood)) => {
+                sections.push(crate::graph::format_neighborhood(&neighborhood));
+            }
+            Ok(None) => {}
+            Err(e) => return result(format!("Error: {e}"), true),
+        }
+    }
+    result(sections.join("\n\n"), false)
+}
+
+async fn exec_kb_ingest(state: &SharedAgentState, call: &ToolCall) -> ToolResult {
+    let path = call.arguments["path"].as_str().unwrap_or("");
+    if path.is_empty() {
+        return ToolResult {
+            tool_call_id: call.id.clone(),
+            content: "Error: 'path' is required".to_string(),
+            is_error: true,
+            data: None,
+        };
+    }
+    match crate::knowledge::ingest_path(state, path).await {
+        Ok(report) => ToolResult {
+            tool_call_id: call.id.clone(),
+            content: report.summary(),
+            is_error: false,
+            data: Some(report.to_json()),
+        },
+        Err(e) => ToolResult {
+            tool_call_id: call.id.clone(),
+            content: format!("Error: {e}"),
+            is_error: true,
+            data: None,
+        },
+    }
+}
+
+async fn exec_kb_search(state: &SharedAgentState, call: &ToolCall) -> ToolResult {
+    let query = call.arguments["query"].as_str().unwrap_or("");
+    let limit = call.arguments["limit"].as_u64().unwrap_or(5).clamp(1, 20) as usize;
+
+    let hits = match crate::knowledge::search(state, query, limit).await {
+        Ok(hits) => hits,
+        Err(e) => {
+            return ToolResult {
+                tool_call_id: call.id.clone(),
+                content: format!("Error: {e}"),
+                is_error: true,
+                data: None,
+            };
+        }
+    };
+
+    let content = if hits.is_empty() {
+        format!(
+            "No passages in the knowledge base match \"{query}\". Use kb_list_sources to see what has been ingested."
+        )
+    } else {
+        let mut lines = Vec::new();
+        for (i, hit) in hits.iter().enumerate() {
+            lines.push(format!(
+                "[{}] {}\n{}\n",
+                i + 1,
+                hit.citation(),
+                hit.chunk.text
+            ));
+        }
+        lines.push("Cite the sources ([n] and path) of passages you use.".to_string());
+        lines.join("\n")
+    };
+
+    ToolResult {
+        tool_call_id: call.id.clone(),
+        content,
+        is_error: false,
+        data: None,
+    }
+}
+
+async fn exec_kb_list_sources(state: &SharedAgentState, call: &ToolCall) -> ToolResult {
+    let sources = match state.memory.read().await.knowledge.sources() {
+        Ok(sources) => sources,
+        Err(e) => {
+            return ToolResult {
+                tool_call_id: call.id.clone(),
+                content: format!("Error: {e}"),
+                is_error: true,
+                data: None,
+            };
+        }
+    };
+
+    let content = if sources.is_empty() {
+        "The knowledge base is empty — add documents with kb_ingest.".to_string()
+    } else {
+        let total: usize = sources.iter().map(|s| s.chunk_count).sum();
+        let mut lines = vec![format!("{} file(s), {} passage(s):", sources.len(), total)];
+        for s in &sources {
+            lines.push(format!(
+                "  - {} ({} passages, ingested {})",
+                s.path,
+                s.chunk_count,
+                s.ingested_at.get(..16).unwrap_or(&s.ingested_at)
+            ));
+        }
+        lines.join("\n")
+    };
+
+    ToolResult {
+        tool_call_id: call.id.clone(),
+        content,
+        is_error: false,
+        data: None,
+    }
+}
+
 async fn exec_goal_create_shared(state: &SharedAgentState, call: &ToolCall) -> ToolResult {
     let description = call.arguments["description"]
         .as_str()
@@ -1331,6 +1843,10 @@ pub(crate) fn is_parallel_safe(tool_name: &str) -> bool {
             | "file_grep"
             | "memory_search"
             | "memory_list"
+            | "graph_query"
+            | "graph_neighbors"
+            | "kb_search"
+            | "kb_list_sources"
             | "mesh_peers"
             | "mesh_delegate"
             | "mesh_status"
@@ -1,9 +1,12 @@
 use claw_core::{Result, Tool, ToolCall, ToolResult};
 use serde_json::json;
 use std::collections::HashMap;
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock};
 use tokio::sync::Mutex;
 use tracing::info;
+use uuid::Uuid;
+
+use crate::sandbox::{Confinement, Sandbox};
 
 /// Info about a background process started by the agent.
 #[derive(Debug, Clone)]
@@ -26,18 +29,26 @@ static FILE_EDIT_COUNTS: LazyLock<Mutex<HashMap<String, u32>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
 
 /// Built-in tools that ship with the Claw runtime.
-#[derive(Clone, Copy)]
-pub struct BuiltinTools;
-
-impl Default for BuiltinTools {
-    fn default() -> Self {
-        Self::new()
-    }
+#[derive(Clone, Default)]
+pub struct BuiltinTools {
+    /// Where `shell_exec`, `process_start` and terminals run commands.
+    sandbox: Arc<Sandbox>,
 }
 
 impl BuiltinTools {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Built-in tools that run commands in `sandbox`.
+    pub fn with_sandbox(sandbox: Sandbox) -> Self {
+        Self {
+            sandbox: Arc::new(sandbox),
+        }
+    }
+
+    pub fn sandbox(&self) -> &Sandbox {
+        &self.sandbox
     }
 
     pub fn has_tool(&self, name: &str) -> bool {
@@ -56,6 +67,12 @@ impl BuiltinTools {
                 | "memory_store"
                 | "memory_delete"
                 | "memory_list"
+                | "memory_conflicts"
+                | "graph_query"
+                | "graph_neighbors"
+                | "kb_ingest"
+                | "kb_search"
+                | "kb_list_sources"
                 | "goal_create"
                 | "goal_list"
                 | "goal_complete_step"
@@ -346,6 +363,10 @@ impl BuiltinTools {
                         "max_tokens": {
                             "type": "integer",
                             "description": "Maximum tokens to generate (default: 2048)"
+                        },
+                        "schema": {
+                            "type": "object",
+                            "description": "Optional JSON Schema the output must match. When set, the result is JSON validated against it."
                         }
                     },
                     "required": ["prompt"]
@@ -357,7 +378,7 @@ impl BuiltinTools {
             },
             Tool {
                 name: "memory_search".into(),
-                description: "Search the agent's long-term memory for relevant facts, past conversations, and learned lessons. Searches across category names, keys, and values using word-level matching. Use short keywords rather than full sentences for best results. Use memory_list if you want to browse everything stored.".into(),
+                description: "Search the agent's long-term memory for relevant facts, past conversations, and learned lessons. Facts are ranked by combined full-text (BM25) and semantic similarity; episodes and old conversation messages by full-text relevance. Use short keywords rather than full sentences for best results. Use memory_list if you want to browse everything stored.".into(),
                 parameters: json!({
                     "type": "object",
                     "properties": {
@@ -367,8 +388,8 @@ impl BuiltinTools {
                         },
                         "type": {
                             "type": "string",
-                            "enum": ["episodic", "semantic", "all"],
-                            "description": "Memory type to search. Default: all"
+                            "enum": ["episodic", "semantic", "conversations", "all"],
+                            "description": "Memory type to search. 'conversations' searches messages of past sessions. Default: all"
                         }
                     },
                     "required": ["query"]
@@ -395,6 +416,11 @@ impl BuiltinTools {
                         "value": {
                             "type": "string",
                             "description": "The fact/knowledge to store"
+                        },
+                        "scope": {
+                            "type": "string",
+                            "enum": ["global", "channel", "user", "session"],
+                            "description": "Who the fact is remembered for: 'user' (the person you're talking to), 'session' (this conversation only), 'channel' (everyone on this channel) or 'global' (everyone). Defaults to the configured scope — usually 'user'."
                         }
                     },
                     "required": ["category", "key", "value"]
@@ -417,6 +443,11 @@ impl BuiltinTools {
                         "key": {
                             "type": "string",
                             "description": "Key of the specific fact to delete. Omit to delete ALL facts in the category."
+                        },
+                        "scope": {
+                            "type": "string",
+                            "enum": ["global", "channel", "user", "session"],
+                            "description": "Scope the fact was stored in (memory_list and memory_search show non-global scopes in brackets). Defaults to the configured scope — usually 'user'."
                         }
                     },
                     "required": ["category"]
@@ -443,6 +474,168 @@ impl BuiltinTools {
                 risk_level: 0,
                 provider: None,
             },
+            Tool {
+                name: "memory_conflicts".into(),
+                description: "Review and resolve contradictions in long-term memory. A conflict is recorded when a fact is given a value that contradicts the one it replaced (e.g. two sources disagree, or the value flips back and forth). action 'list' shows open conflicts with both values, their sources and each fact's value history; action 'resolve' settles one by keeping the right value. Resolve a conflict instead of re-storing the fact back and forth.".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "action": {
+                            "type": "string",
+                            "enum": ["list", "resolve"],
+                            "description": "'list' (default) or 'resolve'"
+                        },
+                        "id": {
+                            "type": "string",
+                            "description": "Conflict id (or its first 8 characters), for 'resolve'"
+                        },
+                        "value": {
+                            "type": "string",
+                            "description": "For 'resolve': the correct value to keep — the previous value, the current one, or a corrected one. Default: the current value."
+                        },
+                        "include_resolved": {
+                            "type": "boolean",
+                            "description": "For 'list': also show resolved conflicts"
+                        }
+                    }
+                }),
+                capabilities: vec![],
+                is_mutating: true,
+                risk_level: 0,
+                provider: None,
+            },
+            Tool {
+                name: "graph_query".into(),
+                description: "Query the knowledge graph of entities (people, servers, services, projects, repos, ...) and the relations between them, which is built automatically from conversations. Matches relations 'subject —predicate→ object'; every field is optional but give at least one. Entities are given by id ('server:web-01') or name ('web-01'). Example: which services run on a host → predicate 'runs_on', object 'web-01'. With only 'name', lists matching entities instead.".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "subject": {
+                            "type": "string",
+                            "description": "Subject entity id or name"
+                        },
+                        "predicate": {
+                            "type": "string",
+                            "description": "Relation type, e.g. runs_on, owned_by, depends_on, member_of"
+                        },
+                        "object": {
+                            "type": "string",
+                            "description": "Object entity id or name"
+                        },
+                        "subject_kind": {
+                            "type": "string",
+                            "description": "Only subjects of this kind (person, server, service, ...)"
+                        },
+                        "object_kind": {
+                            "type": "string",
+                            "description": "Only objects of this kind"
+                        },
+                        "name": {
+                            "type": "string",
+                            "description": "Find entities whose name or id contains this text"
+                        },
+                        "kind": {
+                            "type": "string",
+                            "description": "With 'name': only entities of this kind"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum results (default: 50)"
+                        }
+                    }
+                }),
+                capabilities: vec![],
+                is_mutating: false,
+                risk_level: 0,
+                provider: None,
+            },
+            Tool {
+                name: "graph_neighbors".into(),
+                description: "Show an entity from the knowledge graph with its attributes and everything connected to it, up to 3 hops away. Use it to answer questions like 'what runs on web-01 and who owns it' (depth 2).".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "entity": {
+                            "type": "string",
+                            "description": "Entity id ('server:web-01') or name ('web-01')"
+                        },
+                        "depth": {
+                            "type": "integer",
+                            "description": "Hops to follow, 1-3 (default: 1)"
+                        },
+                        "direction": {
+                            "type": "string",
+                            "enum": ["out", "in", "both"],
+                            "description": "Follow outgoing, incoming or all relations (default: both)"
+                        },
+                        "predicate": {
+                            "type": "string",
+                            "description": "Only follow relations of this type"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum relations to return (default: 50)"
+                        }
+                    },
+                    "required": ["entity"]
+                }),
+                capabilities: vec![],
+                is_mutating: false,
+                risk_level: 0,
+                provider: None,
+            },
+            Tool {
+                name: "kb_ingest".into(),
+                description: "Add a file or directory to the knowledge base (Markdown, text, source code, HTML, PDF). Documents are split into passages and indexed for kb_search. Re-ingesting a path only re-processes files that changed and drops files that were deleted. Ingested paths are also re-scanned periodically.".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "File or directory to ingest"
+                        }
+                    },
+                    "required": ["path"]
+                }),
+                capabilities: vec!["fs.read".into()],
+                is_mutating: true,
+                risk_level: 1,
+                provider: None,
+            },
+            Tool {
+                name: "kb_search".into(),
+                description: "Search the knowledge base of ingested documents. Returns the most relevant passages with their source file and location (lines or page). Cite the sources of passages you use in your answer.".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "What to look for — keywords or a question"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum passages to return (default: 5, max: 20)"
+                        }
+                    },
+                    "required": ["query"]
+                }),
+                capabilities: vec![],
+                is_mutating: false,
+                risk_level: 0,
+                provider: None,
+            },
+            Tool {
+                name: "kb_list_sources".into(),
+                description: "List the files in the knowledge base with their passage counts and when they were ingested.".into(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+                capabilities: vec![],
+                is_mutating: false,
+                risk_level: 0,
+                provider: None,
+            },
             Tool {
                 name: "goal_create".into(),
                 description: "Create a new goal for the agent to pursue. IMPORTANT: Always run goal_list first to check for existing goals before creating a new one — do NOT create duplicate goals. If a matching goal already exists, use its ID instead of creating a new one.".into(),
@@ -966,8 +1159,14 @@ impl BuiltinTools {
     }
 
     pub async fn execute(&self, call: &ToolCall) -> Result<ToolResult> {
+        self.execute_for(call, None).await
+    }
+
+    /// Run a tool for a session — sandboxed commands get that session's
+    /// workspace.
+    pub async fn execute_for(&self, call: &ToolCall, session: Option<Uuid>) -> Result<ToolResult> {
         match call.tool_name.as_str() {
-            "shell_exec" => self.exec_shell(call).await,
+            "shell_exec" => self.exec_shell(call, session).await,
             "file_read" => self.exec_file_read(call).await,
             "file_write" => self.exec_file_write(call).await,
             "file_edit" => self.exec_file_edit(call).await,
@@ -975,12 +1174,12 @@ impl BuiltinTools {
             "file_find" => self.exec_file_find(call).await,
             "file_grep" => self.exec_file_grep(call).await,
             "http_fetch" => self.exec_http_fetch(call).await,
-            "process_start" => self.exec_process_start(call).await,
+            "process_start" => self.exec_process_start(call, session).await,
             "process_list" => self.exec_process_list(call).await,
             "process_kill" => self.exec_process_kill(call).await,
             "process_output" => self.exec_process_output(call).await,
             "apply_patch" => self.exec_apply_patch(call).await,
-            "terminal_open" => self.exec_terminal_open(call).await,
+            "terminal_open" => self.exec_terminal_open(call, session).await,
             "terminal_run" => self.exec_terminal_run(call).await,
             "terminal_view" => self.exec_terminal_view(call).await,
             "terminal_input" => self.exec_terminal_input(call).await,
@@ -989,7 +1188,17 @@ impl BuiltinTools {
         }
     }
 
-    async fn exec_shell(&self, call: &ToolCall) -> Result<ToolResult> {
+    /// The sandbox confinement for one of a session's commands.
+    fn confine(&self, tool: &str, session: Option<Uuid>) -> Result<Option<Confinement>> {
+        self.sandbox
+            .confine(session)
+            .map_err(|e| claw_core::ClawError::ToolExecution {
+                tool: tool.into(),
+                reason: format!("can't set up the sandbox: {e}"),
+            })
+    }
+
+    async fn exec_shell(&self, call: &ToolCall, session: Option<Uuid>) -> Result<ToolResult> {
         let command = call.arguments["command"].as_str().ok_or_else(|| {
             claw_core::ClawError::ToolExecution {
                 tool: "shell_exec".into(),
@@ -997,7 +1206,9 @@ impl BuiltinTools {
             }
         })?;
 
-        let timeout_secs = call.arguments["timeout_secs"].as_u64().unwrap_or(120);
+        let timeout_secs = self
+            .sandbox
+            .clamp_timeout(call.arguments["timeout_secs"].as_u64().unwrap_or(120));
         let working_dir = call.arguments["working_dir"].as_str();
 
         info!(
@@ -1014,18 +1225,45 @@ impl BuiltinTools {
         if let Some(dir) = working_dir {
             cmd.current_dir(dir);
         }
+        // Its own process group, so a timeout kills everything it started
+        cmd.process_group(0);
+        cmd.kill_on_drop(true);
+        if let Some(confinement) = self.confine("shell_exec", session)? {
+            confinement.apply(cmd.as_std_mut());
+        }
 
-        let output =
-            tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), cmd.output())
-                .await
-                .map_err(|_| claw_core::ClawError::ToolExecution {
-                    tool: "shell_exec".into(),
-                    reason: format!("command timed out after {timeout_secs}s"),
-                })?
-                .map_err(|e| claw_core::ClawError::ToolExecution {
+        let child = cmd
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| claw_core::ClawError::ToolExecution {
+                tool: "shell_exec".into(),
+                reason: e.to_string(),
+            })?;
+        let pgid = child.id();
+        let output = match tokio::time::timeout(
+            std::time::Duration::from_secs(timeout_secs),
+            child.wait_with_output(),
+        )
+        .await
+        {
+            Ok(output) => output.map_err(|e| claw_core::ClawError::ToolExecution {
+                tool: "shell_exec".into(),
+                reason: e.to_string(),
+            })?,
+            Err(_) => {
+                if let Some(pgid) = pgid {
+                    // SAFETY: signals only the group the command leads
+                    unsafe {
+                        libc::killpg(pgid as libc::pid_t, libc::SIGKILL);
+                    }
+                }
+                return Err(claw_core::ClawError::ToolExecution {
                     tool: "shell_exec".into(),
-                    reason: e.to_string(),
-                })?;
+                    reason: format!("command timed out after {timeout_secs}s and was killed"),
+                });
+            }
+        };
 
         let stdout = String::from_utf8_lossy(&output.stdout);
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -1069,15 +1307,27 @@ impl BuiltinTools {
             "Command completed successfully (no output).".to_string()
         };
 
+        let mut data = json!({
+            "exit_code": exit_code,
+            "stdout": stdout_trimmed.chars().take(10_000).collect::<String>(),
+            "stderr": stderr_trimmed.chars().take(5_000).collect::<String>(),
+        });
+        let blocked = self
+            .sandbox
+            .explain(Some(&output.status), &format!("{stdout}\n{stderr}"));
+        let content = match blocked {
+            Some(note) => {
+                data["sandbox_blocked"] = json!(note);
+                format!("{content}\n\n{note}")
+            }
+            None => content,
+        };
+
         Ok(ToolResult {
             tool_call_id: call.id.clone(),
             content,
             is_error: !output.status.success(),
-            data: Some(json!({
-                "exit_code": exit_code,
-                "stdout": stdout_trimmed.chars().take(10_000).collect::<String>(),
-                "stderr": stderr_trimmed.chars().take(5_000).collect::<String>(),
-            })),
+            data: Some(data),
         })
     }
 
@@ -1486,7 +1736,11 @@ impl BuiltinTools {
 
     // ── process_start: launch background process with output capture ──
 
-    async fn exec_process_start(&self, call: &ToolCall) -> Result<ToolResult> {
+    async fn exec_process_start(
+        &self,
+        call: &ToolCall,
+        session: Option<Uuid>,
+    ) -> Result<ToolResult> {
         let command = call.arguments["command"].as_str().ok_or_else(|| {
             claw_core::ClawError::ToolExecution {
                 tool: "process_start".into(),
@@ -1502,21 +1756,45 @@ impl BuiltinTools {
             "starting background process"
         );
 
-        // Create a temp log file for capturing output
+        // Create a temp log file for capturing output. It's opened here
+        // rather than redirected to in the shell, so a sandboxed command
+        // writes it without being able to open anything else in /tmp.
         let log_file = format!("/tmp/claw-proc-{}.log", uuid::Uuid::new_v4().as_simple());
-
-        // Redirect stdout+stderr to log file, pipe stdin from /dev/null
-        let wrapped_command = format!("{} > {} 2>&1 < /dev/null", command, shell_escape(&log_file));
+        let log = std::fs::File::create(&log_file).and_then(|f| Ok((f.try_clone()?, f)));
+        let (stdout, stderr) = match log {
+            Ok(files) => files,
+            Err(e) => {
+                return Ok(ToolResult {
+                    tool_call_id: call.id.clone(),
+                    content: format!("Failed to create log file {log_file}: {e}"),
+                    is_error: true,
+                    data: None,
+                });
+            }
+        };
 
         let mut cmd = tokio::process::Command::new("sh");
-        cmd.arg("-c").arg(&wrapped_command);
+        cmd.arg("-c").arg(command);
         if let Some(dir) = working_dir {
             cmd.current_dir(dir);
         }
-        // Fully detach — stdin/stdout/stderr all null since we redirect in shell
+        // Fully detach — stdout+stderr go to the log file, stdin from /dev/null
         cmd.stdin(std::process::Stdio::null());
-        cmd.stdout(std::process::Stdio::null());
-        cmd.stderr(std::process::Stdio::null());
+        cmd.stdout(stdout);
+        cmd.stderr(stderr);
+        let confinement = self.confine("process_start", session)?;
+        if let Some(confinement) = &confinement {
+            confinement.apply(cmd.as_std_mut());
+        }
+        let sandboxed = confinement
+            .map(|c| {
+                format!(
+                    "\nSandbox: {} (workspace {})",
+                    c.mode(),
+                    c.workspace().display()
+                )
+            })
+            .unwrap_or_default();
 
         match cmd.spawn() {
             Ok(child) => {
@@ -1541,7 +1819,7 @@ impl BuiltinTools {
                     content: format!(
                         "Started background process '{label}' (PID: {pid})\n\
                          Command: {command}\n\
-                         Log file: {log_file}\n\
+                         Log file: {log_file}{sandboxed}\n\
                          Use `process_output` with PID {pid} to check output."
                     ),
                     is_error: false,
@@ -1841,13 +2119,28 @@ impl BuiltinTools {
 
     // ── Terminal (PTY) Tool Handlers ──────────────────────────────────
 
-    async fn exec_terminal_open(&self, call: &ToolCall) -> Result<ToolResult> {
+    async fn exec_terminal_open(
+        &self,
+        call: &ToolCall,
+        session: Option<Uuid>,
+    ) -> Result<ToolResult> {
         let label = call.arguments["label"].as_str().unwrap_or("default");
         let working_dir = call.arguments["working_dir"].as_str();
+        let confinement = self.confine("terminal_open", session)?;
+        let sandboxed = confinement
+            .as_ref()
+            .map(|c| {
+                format!(
+                    "Sandbox: {} (workspace {})\n",
+                    c.mode(),
+                    c.workspace().display()
+                )
+            })
+            .unwrap_or_default();
 
-        match crate::terminal::terminal_open(label, working_dir).await {
+        match crate::terminal::terminal_open(label, working_dir, confinement).await {
             Ok((id, initial_output)) => {
-                let mut content = format!("Terminal {id} opened (label: '{label}')\n");
+                let mut content = format!("Terminal {id} opened (label: '{label}')\n{sandboxed}");
                 if !initial_output.trim().is_empty() {
                     content.push_str(&format!("\nInitial output:\n{initial_output}"));
                 }
@@ -1888,11 +2181,19 @@ impl BuiltinTools {
         info!(terminal_id = terminal_id, command = command, "terminal_run");
 
         match crate::terminal::terminal_run(terminal_id, command, timeout_ms).await {
-            Ok(output) => Ok(ToolResult {
-                tool_call_id: call.id.clone(),
-                content: output,
-                is_error: false,
-                data: None,
+            Ok(output) => Ok(match self.sandbox.explain(None, &output) {
+                Some(note) => ToolResult {
+                    tool_call_id: call.id.clone(),
+                    content: format!("{output}\n\n{note}"),
+                    is_error: false,
+                    data: Some(json!({ "sandbox_blocked": note })),
+                },
+                None => ToolResult {
+                    tool_call_id: call.id.clone(),
+                    content: output,
+                    is_error: false,
+                    data: None,
+                },
             }),
             Err(e) => Ok(ToolResult {
                 tool_call_id: call.id.clone(),
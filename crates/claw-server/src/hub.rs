@@ -469,7 +469,7 @@ async fn local_hub_stats(State(state): State<Arc<crate::AppState>>) -> Json<serd
         }
     }
     let mut top_tags: Vec<(String, usize)> = tag_counts.into_iter().collect();
-    top_tags.sort_by(|a, b| b.1.cmp(&a.1));
+    top_tags.sort_by_key(|t| std::cmp::Reverse(t.1));
     top_tags.truncate(20);
 
     Json(serde_json::json!({
@@ -936,7 +936,7 @@ async fn hub_stats(
     }
 
     let mut top_tags: Vec<(String, usize)> = tag_counts.into_iter().collect();
-    top_tags.sort_by(|a, b| b.1.cmp(&a.1));
+    top_tags.sort_by_key(|t| std::cmp::Reverse(t.1));
     top_tags.truncate(20);
 
     Ok(Json(serde_json::json!({
@@ -80,6 +80,67 @@ struct MemorySearchParams {
     q: String,
 }
 
+/// Query params for `/memory/search`: `session` limits message hits to one
+/// conversation.
+#[derive(Deserialize)]
+struct MemoryQueryParams {
+    q: String,
+    session: Option<uuid::Uuid>,
+}
+
+/// Query params for listing fact conflicts.
+#[derive(Deserialize)]
+struct FactConflictParams {
+    #[serde(default)]
+    all: bool,
+}
+
+/// Request body for resolving a fact conflict.
+#[derive(Deserialize)]
+struct ResolveConflictRequest {
+    value: Option<String>,
+}
+
+/// Query params for a fact's value history.
+#[derive(Deserialize)]
+struct FactHistoryParams {
+    #[serde(default = "default_fact_scope")]
+    scope: String,
+    category: String,
+    key: String,
+}
+
+/// Query params for the knowledge graph.
+#[derive(Deserialize)]
+struct GraphParams {
+    entity: Option<String>,
+    q: Option<String>,
+    #[serde(default = "default_graph_depth")]
+    depth: usize,
+}
+
+fn default_graph_depth() -> usize {
+    1
+}
+
+fn default_fact_scope() -> String {
+    "global".to_string()
+}
+
+/// Largest memory archive accepted by the import endpoint.
+const MAX_IMPORT_BYTES: usize = 512 * 1024 * 1024;
+
+/// Query params for memory import.
+#[derive(Deserialize)]
+struct MemoryImportParams {
+    #[serde(default = "default_strategy")]
+    strategy: String,
+}
+
+fn default_strategy() -> String {
+    "skip".to_string()
+}
+
 /// Query params for audit log.
 #[derive(Deserialize)]
 struct AuditLogParams {
@@ -129,6 +190,22 @@ pub fn build_router(
         .route("/api/v1/tools", get(tools_handler))
         .route("/api/v1/memory/facts", get(facts_handler))
         .route("/api/v1/memory/search", get(memory_search_handler))
+        .route("/api/v1/memory/history", get(fact_history_handler))
+        .route("/api/v1/memory/conflicts", get(fact_conflicts_handler))
+        .route(
+            "/api/v1/memory/conflicts/{id}/resolve",
+            post(resolve_conflict_handler),
+        )
+        .route("/api/v1/memory/graph", get(graph_handler))
+        .route("/api/v1/memory/export", get(memory_export_handler))
+        .route(
+            "/api/v1/memory/import",
+            post(memory_import_handler)
+                .layer(axum::extract::DefaultBodyLimit::max(MAX_IMPORT_BYTES)),
+        )
+        .route("/api/v1/kb/ingest", post(kb_ingest_handler))
+        .route("/api/v1/kb/sources", get(kb_sources_handler))
+        .route("/api/v1/kb/search", get(kb_search_handler))
         .route("/api/v1/config", get(config_handler))
         .route("/api/v1/audit", get(audit_handler))
         .route(
@@ -324,7 +401,12 @@ async fn metrics_handler(
     [(axum::http::header::HeaderName, &'static str); 1],
     String,
 ) {
-    let body = state.metrics.render_prometheus();
+    let mut body = state.metrics.render_prometheus();
+    if let Some(ref handle) = *state.handle.read().await {
+        body.push_str(&metrics::render_llm_queue_depths(
+            &handle.state().llm.queue_depths(),
+        ));
+    }
     (
         StatusCode::OK,
         [(
@@ -493,13 +575,188 @@ async fn facts_handler(
 }
 
 async fn memory_search_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<MemoryQueryParams>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let handle = get_handle(&state)
+        .await
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    let query = QueryKind::MemorySearch {
+        query: params.q,
+        session: params.session,
+    };
+    match handle.query(query).await {
+        Ok(data) => Ok(Json(data)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+async fn fact_history_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<FactHistoryParams>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let handle = get_handle(&state)
+        .await
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    match handle
+        .query(QueryKind::FactHistory {
+            scope: params.scope,
+            category: params.category,
+            key: params.key,
+        })
+        .await
+    {
+        Ok(data) => Ok(Json(data)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+async fn fact_conflicts_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<FactConflictParams>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let handle = get_handle(&state)
+        .await
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    match handle
+        .query(QueryKind::FactConflicts {
+            include_resolved: params.all,
+        })
+        .await
+    {
+        Ok(data) => Ok(Json(data)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+async fn resolve_conflict_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    body: Option<Json<ResolveConflictRequest>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let handle = get_handle(&state).await.ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(serde_json::json!({ "error": "runtime is not running" })),
+    ))?;
+    let value = body.and_then(|Json(b)| b.value);
+    handle
+        .resolve_fact_conflict(&id, value.as_deref())
+        .await
+        .map(Json)
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e })),
+            )
+        })
+}
+
+async fn graph_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<GraphParams>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let handle = get_handle(&state)
+        .await
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    match handle
+        .query(QueryKind::Graph {
+            entity: params.entity,
+            search: params.q,
+            depth: params.depth,
+        })
+        .await
+    {
+        Ok(data) => Ok(Json(data)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+async fn memory_export_handler(State(state): State<Arc<AppState>>) -> Result<Response, StatusCode> {
+    let handle = get_handle(&state)
+        .await
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    let archive = handle
+        .memory_export()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let filename = format!(
+        "claw-memory-{}.jsonl",
+        chrono::Utc::now().format("%Y%m%d-%H%M%S")
+    );
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(axum::http::header::CONTENT_TYPE, "application/x-ndjson")
+        .header(
+            axum::http::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{filename}\""),
+        )
+        .body(axum::body::Body::from(archive))
+        .unwrap())
+}
+
+async fn memory_import_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<MemoryImportParams>,
+    body: axum::body::Bytes,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let handle = get_handle(&state).await.ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(serde_json::json!({ "error": "runtime is not running" })),
+    ))?;
+    handle
+        .memory_import(&body, &params.strategy)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e })),
+            )
+        })
+}
+
+/// Request body for knowledge base ingestion.
+#[derive(Deserialize)]
+struct KbIngestRequest {
+    path: String,
+}
+
+async fn kb_ingest_handler(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<KbIngestRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let handle = get_handle(&state).await.ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(serde_json::json!({ "error": "runtime is not running" })),
+    ))?;
+    handle.kb_ingest(&body.path).await.map(Json).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": e })),
+        )
+    })
+}
+
+async fn kb_sources_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let handle = get_handle(&state)
+        .await
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    match handle.query(QueryKind::KbSources).await {
+        Ok(data) => Ok(Json(data)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+async fn kb_search_handler(
     State(state): State<Arc<AppState>>,
     Query(params): Query<MemorySearchParams>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     let handle = get_handle(&state)
         .await
         .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
-    match handle.query(QueryKind::MemorySearch(params.q)).await {
+    match handle.query(QueryKind::KbSearch(params.q)).await {
         Ok(data) => Ok(Json(data)),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
@@ -243,6 +243,20 @@ claw_rate_limit_rejections_total {}
     }
 }
 
+/// Render the LLM rate-limit queue depth per provider as a Prometheus gauge.
+pub fn render_llm_queue_depths(depths: &[(String, usize)]) -> String {
+    let mut out = String::from(
+        "\n# HELP claw_llm_queue_depth LLM calls waiting for provider rate-limit budget.\n\
+         # TYPE claw_llm_queue_depth gauge\n",
+    );
+    for (provider, depth) in depths {
+        out.push_str(&format!(
+            "claw_llm_queue_depth{{provider=\"{provider}\"}} {depth}\n"
+        ));
+    }
+    out
+}
+
 impl Default for Metrics {
     fn default() -> Self {
         Self::new()
@@ -283,6 +297,14 @@ mod tests {
         assert!(output.contains("claw_cost_usd_total 0.008"));
     }
 
+    #[test]
+    fn test_llm_queue_depths() {
+        let output = render_llm_queue_depths(&[("anthropic".into(), 3), ("openai".into(), 0)]);
+        assert!(output.contains("# TYPE claw_llm_queue_depth gauge"));
+        assert!(output.contains("claw_llm_queue_depth{provider=\"anthropic\"} 3"));
+        assert!(output.contains("claw_llm_queue_depth{provider=\"openai\"} 0"));
+    }
+
     #[test]
     fn test_metrics_prometheus_format() {
         let m = Metrics::new();
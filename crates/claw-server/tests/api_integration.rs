@@ -25,6 +25,7 @@ async fn setup(responses: Vec<&str>) -> axum::Router {
         c.agent.model = "mock/test-model".to_string();
         c.agent.max_iterations = 5;
         c.autonomy.level = 3;
+        c.memory.graph_extraction = false;
         c
     };
 